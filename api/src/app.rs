@@ -1,6 +1,65 @@
-use axum::middleware::from_extractor_with_state;
+use std::{
+    io::ErrorKind,
+    net::SocketAddr,
+    sync::{Arc, OnceLock},
+    time::Duration,
+};
+
+use axum::{
+    Extension, Json,
+    middleware::{from_extractor_with_state, from_fn_with_state},
+    routing::get,
+};
 use beep_auth::KeycloakAuthRepository;
-use communities_core::create_repositories;
+use communities_core::{
+    application::{CommunitiesArchive, CommunitiesRepositories, CommunitiesService},
+    create_repositories,
+    domain::{
+        lease::ports::LeaseLock,
+        lifecycle::entities::LifecycleStage,
+        membership::ports::MemberDirectory,
+        message::{
+            entities::{AbuseThreshold, AttachmentLimits, LinkPreviewPolicy},
+            events::MessageEventBus,
+            ports::{AttachmentScanner, AttachmentStorage, LinkUnfurler, ThumbnailGenerator},
+        },
+        search::ports::SearchIndex,
+    },
+    infrastructure::{
+        attachment::AttachmentScanWorker,
+        lease::SingletonJob,
+        link_preview::LinkPreviewWorker,
+        message::purge::{DeletedMessagePurge, DeletedMessagePurgeConfig},
+        message::retention::{MessageRetention, MessageRetentionConfig},
+        message::thread_archive::{ThreadArchive, ThreadArchiveConfig},
+        outbox::{KeyManagementService, LocalKeyManagementService, OutboxEncryption},
+        search::{SearchIndexer, SearchIndexerConfig},
+    },
+};
+#[cfg(any(feature = "elasticsearch", feature = "meilisearch", feature = "tantivy"))]
+use communities_core::domain::search::ports::MessageSearchRepository;
+#[cfg(feature = "elasticsearch")]
+use communities_core::infrastructure::search::ElasticsearchMessageSearchRepository;
+#[cfg(feature = "meilisearch")]
+use communities_core::infrastructure::search::MeilisearchMessageSearchRepository;
+#[cfg(feature = "tantivy")]
+use communities_core::infrastructure::search::TantivyMessageSearchRepository;
+#[cfg(feature = "s3-archive")]
+use communities_core::infrastructure::archive::S3ObjectStorage;
+#[cfg(feature = "s3-attachments")]
+use communities_core::infrastructure::attachment::S3AttachmentStorage;
+#[cfg(feature = "clamav")]
+use communities_core::infrastructure::attachment::ClamAvScanner;
+#[cfg(feature = "http-thumbnails")]
+use communities_core::infrastructure::attachment::HttpThumbnailGenerator;
+#[cfg(feature = "http-members")]
+use communities_core::infrastructure::membership::HttpMemberDirectory;
+#[cfg(feature = "vault-kms")]
+use communities_core::infrastructure::outbox::VaultTransitKms;
+#[cfg(feature = "link-previews")]
+use communities_core::infrastructure::link_preview::OpenGraphUnfurler;
+use futures::FutureExt;
+use tokio::net::TcpListener;
 use utoipa::OpenApi;
 use utoipa_axum::router::OpenApiRouter;
 use utoipa_scalar::{Scalar, Servable};
@@ -9,105 +68,115 @@ use utoipa_scalar::{Scalar, Servable};
 
 use crate::{
     Config,
+    config::{
+        ArchiveConfig, AttachmentConfig, DocsExposure, Environment, LinkPreviewConfig, MembersConfig, OutboxConfig, RealtimeSource, SearchBackend, SearchConfig, SingletonJobsMode,
+    },
+    consumer::{
+        AmqpConsumer, ChannelDeletedHandler, ChannelMembersHandler, ConsumerStatus, QueueBinding,
+    },
     http::{
         health::routes::health_routes,
         server::{
-            ApiError, AppState, middleware::auth::AuthMiddleware,
-            middleware::auth::entities::AuthValidator,
-            authorization::SpiceDbAuthz,
+            ApiError, AppState, cache::MessageListCache, export_links::ExportLinks,
+            middleware::auth::AuthMiddleware,
+            middleware::body_logging::{BodyLogging, log_bodies},
+            middleware::impersonation::{Impersonation, impersonate},
+            middleware::read_only::reject_writes,
+            public_id::{EncryptedIds, IdObfuscation, public_ids},
+            throttle::StreamThrottle,
+            authorization::{DummyAuthz, DynAuthz, SpiceDbAuthz},
             authorization::SpiceDbConfig as LocalSpiceConfig,
+            response::{CursorMeta, EmptyMeta, PageMeta, ResponseMeta, negotiate_format},
         },
+        ws::{fanout::FanoutConfig, replay::ReplayConfig},
     },
-    message_routes,
+    lifecycle::{Lifecycle, config_hash, shutdown_signal},
+    admin_routes, attachment_routes, channel_lock_routes, channel_settings_routes,
+    export_download_routes, graphql_routes, legal_hold_routes, message_routes, read_marker_routes, storage_routes,
+    ws_routes,
 };
 
 #[derive(OpenApi)]
-#[openapi(info(
-    title = "Beep communities openapi",
-    contact(name = "communities-core@beep.ovh"),
-    description = "API documentation for the Communities service",
-    version = "0.0.1"
-))]
+#[openapi(
+    info(
+        title = "Beep communities openapi",
+        contact(name = "communities-core@beep.ovh"),
+        description = "API documentation for the Communities service",
+        version = "0.0.1"
+    ),
+    // meta of the `Api-Version: 2` envelopes
+    components(schemas(ResponseMeta, PageMeta, CursorMeta, EmptyMeta))
+)]
 struct ApiDoc;
 pub struct App {
     config: Config,
     pub state: AppState,
     app_router: axum::Router,
     health_router: axum::Router,
+    bound_addresses: OnceLock<BoundAddresses>,
+    lifecycle: Lifecycle,
+}
+
+/// Socket addresses the API and health listeners are actually bound to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BoundAddresses {
+    pub api: SocketAddr,
+    pub health: SocketAddr,
 }
 
 impl App {
     #[tracing::instrument(skip(config))]
     pub async fn new(config: Config) -> Result<Self, ApiError> {
-        tracing::debug!("Creating repositories...");
-        let state: AppState =
-            {
-                let repos = create_repositories(&config.database.mongo_uri, &config.database.mongo_db_name)
-                    .await
-                    .map_err(|e| ApiError::StartupError {
-                        msg: format!("Failed to create repositories: {}", e),
-                    })?;
-
-                // Build service from repositories
-                let service: communities_core::application::CommunitiesService = repos.clone().into();
-
-                // Initialize authorization client. If the spicedb feature is enabled
-                // we'll attempt to initialize the SpiceDB-backed client; otherwise use
-                // a permissive dummy implementation.
-                use std::sync::Arc;
-                let authz: Arc<dyn crate::http::server::authorization::Authorization> = {
-                    let cfg = LocalSpiceConfig {
-                        endpoint: config.spicedb.endpoint.clone(),
-                        token: if config.spicedb.token.is_empty() { None } else { Some(config.spicedb.token.clone()) },
-                    };
-                    let client = SpiceDbAuthz::new(cfg).await.map_err(|e| ApiError::StartupError { msg: format!("Failed to init spice db authz: {:?}", e) })?;
-                    Arc::new(client)
-                };
-
-                AppState::new(service, authz)
-            };
-        let keycloak_repository = KeycloakAuthRepository::new(
-            format!(
-                "{}/realms/{}",
-                config.keycloak.internal_url, config.keycloak.realm
-            ),
-            None,
-        );
-        let (app_router, mut api) = OpenApiRouter::<AppState>::new()
-            .merge(message_routes())
-            // Add application routes here
-            .route_layer(from_extractor_with_state::<
-                AuthMiddleware,
-                KeycloakAuthRepository,
-            >(keycloak_repository))
-            .split_for_parts();
+        let instance = instance_id(&config);
 
-        // Override API documentation info
-        let custom_info = ApiDoc::openapi();
-        api.info = custom_info.info;
+        tracing::debug!("Creating repositories...");
+        let repos = init_repositories(&config).await?;
 
-        let openapi_json = api.to_pretty_json().map_err(|e| ApiError::StartupError {
-            msg: format!("Failed to generate OpenAPI spec: {}", e),
-        })?;
+        // Read-only replicas run no background job, as every job writes
+        let writable = !config.read_only;
+        if config.read_only {
+            tracing::warn!("read-only mode: mutations are refused and no job runs");
+        }
+        let lifecycle_publisher = writable
+            .then(|| repos.lifecycle_publisher(config.routing.service_lifecycle.clone()));
+        let lease_lock = (config.jobs.singleton_jobs == SingletonJobsMode::Lease)
+            .then(|| repos.lease_lock(instance.clone()));
 
-        let app_router = app_router
-            .with_state(state.clone())
-            .merge(Scalar::with_url("/scalar", api));
-        // Write OpenAPI spec to file in development environment
-        if matches!(config.environment, crate::config::Environment::Development) {
-            std::fs::write("openapi.json", &openapi_json).map_err(|e| ApiError::StartupError {
-                msg: format!("Failed to write OpenAPI spec to file: {}", e),
-            })?;
+        if writable {
+            spawn_outbox_retention(&config, &repos, lease_lock.as_ref());
         }
+        let (service, features) = init_service(&config, &repos, writable)?;
+        if writable {
+            spawn_maintenance_jobs(&config, &service, lease_lock.as_ref());
+        }
+
+        let search_index = init_search_index(&config.search)?;
+        let state = init_state(
+            &config,
+            &repos,
+            service,
+            &features,
+            search_index.clone(),
+            &instance,
+            writable,
+        )
+        .await?;
+        prime_list_cache(&config, &state);
+        spawn_workers(&config, &state, &features, search_index);
+        spawn_consumers(&config, &repos, &state, features);
 
+        let app_router = init_app_router(&config, &state)?;
         let health_router = axum::Router::new()
             .merge(health_routes())
             .with_state(state.clone());
+        let lifecycle = Lifecycle::new(instance, config_hash(&config), lifecycle_publisher);
         Ok(Self {
             config,
             state,
             app_router,
             health_router,
+            bound_addresses: OnceLock::new(),
+            lifecycle,
         })
     }
 
@@ -115,38 +184,1038 @@ impl App {
         self.app_router.clone()
     }
 
+    /// Addresses the listeners are bound to, once [`App::bind`] has run
+    pub fn bound_addresses(&self) -> Option<BoundAddresses> {
+        self.bound_addresses.get().copied()
+    }
+
+    /// Bind the API and health listeners without serving them yet.
+    ///
+    /// Ports may be set to 0 to let the OS pick a free port; the actually bound
+    /// addresses are available on the returned [`BoundListeners`] and through
+    /// [`App::bound_addresses`].
     #[tracing::instrument(skip(self))]
-    pub async fn start(&self) -> Result<(), ApiError> {
-        let health_addr = format!("0.0.0.0:{}", self.config.clone().message.health_port);
-        let api_addr = format!("0.0.0.0:{}", self.config.clone().message.api_port);
-        // Create TCP listeners for both messages
-        let health_listener = tokio::net::TcpListener::bind(&health_addr)
-            .await
-            .map_err(|_| ApiError::StartupError {
-                msg: format!("Failed to bind health message: {}", health_addr),
-            })?;
-        let api_listener = tokio::net::TcpListener::bind(&api_addr)
-            .await
-            .map_err(|_| ApiError::StartupError {
-                msg: format!("Failed to bind API message: {}", api_addr),
-            })?;
-
-    tracing::info!(api_addr = %api_addr, health_addr = %health_addr, "Starting HTTP listeners");
-    // Run both listeners concurrently
-        tokio::try_join!(
-            axum::serve(health_listener, self.health_router.clone()),
-            axum::serve(api_listener, self.app_router.clone())
-        )
-        .expect("Failed to start messages");
+    pub async fn bind(&self) -> Result<BoundListeners, ApiError> {
+        let api_port = self.config.message.api_port;
+        let health_port = self.config.message.health_port;
+        let health_addr = format!("0.0.0.0:{}", health_port);
+        let api_addr = format!("0.0.0.0:{}", api_port);
+
+        // Health and API share a single listener when configured on the same
+        // (non-ephemeral) port; requests are then dispatched by path.
+        let listeners = if api_port == health_port && api_port != 0 {
+            let api = bind_listener(&api_addr).await?;
+            let addr = local_addr(&api)?;
+            BoundListeners {
+                api,
+                health: None,
+                addresses: BoundAddresses {
+                    api: addr,
+                    health: addr,
+                },
+            }
+        } else {
+            let health = bind_listener(&health_addr).await?;
+            let api = bind_listener(&api_addr).await?;
+            let addresses = BoundAddresses {
+                api: local_addr(&api)?,
+                health: local_addr(&health)?,
+            };
+            BoundListeners {
+                api,
+                health: Some(health),
+                addresses,
+            }
+        };
+
+        let _ = self.bound_addresses.set(listeners.addresses);
+        Ok(listeners)
+    }
+
+    /// Serve requests on listeners previously obtained from [`App::bind`]
+    #[tracing::instrument(skip(self, listeners))]
+    pub async fn serve(&self, listeners: BoundListeners) -> Result<(), ApiError> {
+        let addresses = listeners.addresses;
+        let server_error = |e: std::io::Error| ApiError::StartupError {
+            msg: format!("HTTP server failed: {}", e),
+        };
+        // Shared by both listeners, so the shutdown is announced once
+        let lifecycle = self.lifecycle.clone();
+        let shutdown = async move {
+            shutdown_signal().await;
+            lifecycle.announce(LifecycleStage::ShutdownInitiated).await;
+        }
+        .boxed()
+        .shared();
+
+        self.lifecycle.announce(LifecycleStage::Started).await;
+        match listeners.health {
+            Some(health_listener) => {
+                tracing::info!(api_addr = %addresses.api, health_addr = %addresses.health, "Starting HTTP listeners");
+                // Run both listeners concurrently
+                tokio::try_join!(
+                    axum::serve(health_listener, self.health_router.clone())
+                        .with_graceful_shutdown(shutdown.clone()),
+                    axum::serve(listeners.api, self.app_router.clone())
+                        .with_graceful_shutdown(shutdown)
+                )
+                .map_err(server_error)?;
+            }
+            None => {
+                tracing::info!(addr = %addresses.api, "Starting shared HTTP listener");
+                let router = self.app_router.clone().merge(self.health_router.clone());
+                axum::serve(listeners.api, router)
+                    .with_graceful_shutdown(shutdown)
+                    .await
+                    .map_err(server_error)?;
+            }
+        }
+        self.lifecycle.announce(LifecycleStage::ShutdownCompleted).await;
         Ok(())
     }
 
+    pub async fn start(&self) -> Result<(), ApiError> {
+        let listeners = self.bind().await?;
+        self.serve(listeners).await
+    }
+
     #[tracing::instrument(skip(self))]
     pub async fn shutdown(&self) {
         self.state.shutdown().await;
     }
 }
 
+/// TCP listeners bound by [`App::bind`], ready to be served
+pub struct BoundListeners {
+    api: TcpListener,
+    health: Option<TcpListener>,
+    addresses: BoundAddresses,
+}
+
+impl BoundListeners {
+    pub fn addresses(&self) -> BoundAddresses {
+        self.addresses
+    }
+}
+
+/// Bind a TCP listener, telling apart the most common bind failures
+async fn bind_listener(addr: &str) -> Result<TcpListener, ApiError> {
+    TcpListener::bind(addr).await.map_err(|e| match e.kind() {
+        ErrorKind::AddrInUse => ApiError::AddressInUse {
+            addr: addr.to_string(),
+        },
+        ErrorKind::PermissionDenied => ApiError::BindPermissionDenied {
+            addr: addr.to_string(),
+        },
+        _ => ApiError::StartupError {
+            msg: format!("Failed to bind {}: {}", addr, e),
+        },
+    })
+}
+
+fn local_addr(listener: &TcpListener) -> Result<SocketAddr, ApiError> {
+    listener.local_addr().map_err(|e| ApiError::StartupError {
+        msg: format!("Failed to read bound address: {}", e),
+    })
+}
+
+/// Optional features the service was built with, that need a worker or a
+/// consumer once the state exists
+struct ServiceFeatures {
+    attachment_scanner: bool,
+    link_unfurler: bool,
+    message_archive: Option<Arc<CommunitiesArchive>>,
+    consumer_status: Option<ConsumerStatus>,
+    members_status: Option<ConsumerStatus>,
+}
+
+/// Connect the MongoDB repositories, encrypting the outbox when a key is configured
+async fn init_repositories(config: &Config) -> Result<CommunitiesRepositories, ApiError> {
+    let mut repos = create_repositories(
+        &config.database.mongo_uri,
+        &config.database.mongo_db_name,
+        config.routing.clone(),
+    )
+    .await
+    .map_err(|e| ApiError::StartupError {
+        msg: format!("Failed to create repositories: {}", e),
+    })?;
+
+    if let Some(encryption) = init_outbox_encryption(config)? {
+        repos.message_repository = repos.message_repository.with_outbox_encryption(encryption);
+    }
+    Ok(repos)
+}
+
+/// Delete the published outbox records past `OUTBOX_SENT_RETENTION_SECS`
+fn spawn_outbox_retention(
+    config: &Config,
+    repos: &CommunitiesRepositories,
+    lease_lock: Option<&Arc<dyn LeaseLock>>,
+) {
+    // 0 keeps published records forever
+    if config.outbox.sent_retention_secs == 0 {
+        return;
+    }
+    let lease = lease_lock.map(|lock| {
+        SingletonJob::new(
+            lock.clone(),
+            "outbox-retention",
+            Duration::from_secs(config.jobs.lease_ttl_secs),
+        )
+    });
+    repos.enforce_outbox_retention(Duration::from_secs(config.outbox.sent_retention_secs), lease);
+}
+
+/// Build the service from the repositories and the configured limits and adapters
+fn init_service(
+    config: &Config,
+    repos: &CommunitiesRepositories,
+    writable: bool,
+) -> Result<(CommunitiesService, ServiceFeatures), ApiError> {
+    let mut service: CommunitiesService = repos.clone().into();
+    // 0 leaves channels without a custom quota unlimited
+    let storage_quota = config.storage.channel_quota_bytes;
+    service = service.with_default_storage_quota((storage_quota > 0).then_some(storage_quota));
+    // 0 lets channels pin any number of messages
+    let pin_limit = config.pins.channel_pin_limit;
+    service = service.with_pin_limit((pin_limit > 0).then_some(pin_limit));
+    // 0 reports no deletion burst
+    let threshold = config.trust_safety.mass_deletion_threshold;
+    let window = config.trust_safety.mass_deletion_window_secs as i64;
+    service = service.with_mass_deletion_threshold((threshold > 0).then(|| AbuseThreshold {
+        count: threshold,
+        window: chrono::Duration::seconds(window),
+    }));
+    service = service
+        .with_moderation_reason_templates(config.trust_safety.moderation_reason_templates.clone());
+
+    if let Some(storage) = init_attachment_storage(&config.attachments)? {
+        service = service.with_attachment_storage(storage);
+    }
+    if let Some(generator) = init_thumbnail_generator(&config.attachments)? {
+        service = service.with_thumbnail_generator(generator);
+    }
+    let link_unfurler = init_link_unfurler(&config.link_previews)?;
+    if let Some(unfurler) = &link_unfurler {
+        service = service.with_link_unfurler(
+            unfurler.clone(),
+            LinkPreviewPolicy {
+                allowed_domains: config.link_previews.allowed_domains.clone(),
+                denied_domains: config.link_previews.denied_domains.clone(),
+            },
+        );
+    }
+    let attachment_scanner = init_attachment_scanner(&config.attachments)?;
+    if let Some(scanner) = &attachment_scanner {
+        service = service.with_attachment_scanner(scanner.clone());
+    }
+    // 0 leaves the count or size unlimited
+    let attachments = &config.attachments;
+    service = service.with_attachment_limits(AttachmentLimits {
+        max_count: (attachments.max_per_message > 0).then_some(attachments.max_per_message),
+        max_size_bytes: (attachments.max_size_bytes > 0).then_some(attachments.max_size_bytes),
+        allowed_content_types: attachments.allowed_content_types.clone(),
+    });
+
+    let message_archive = init_message_archive(&config.archive, repos)?;
+    if let Some(archive) = &message_archive {
+        service = service.with_message_archiver(archive.clone());
+    }
+
+    // The API keeps serving while the broker is down, only reported as degraded
+    let consumer_status = (writable && !config.consumer.rabbitmq_url.is_empty())
+        .then(|| ConsumerStatus::new("rabbitmq"));
+    if let Some(status) = &consumer_status {
+        service = service.with_health_probe(Arc::new(status.clone()));
+    }
+    // Only fed while events are consumed; otherwise mentions of
+    // `@everyone` and roles notify nobody
+    let members_status = consumer_status
+        .as_ref()
+        .map(|_| ConsumerStatus::new("rabbitmq-members"));
+    if let Some(status) = &members_status {
+        service = service
+            .with_channel_membership(repos.channel_membership())
+            .with_health_probe(Arc::new(status.clone()));
+    }
+    if let Some(directory) = init_member_directory(&config.members)? {
+        service = service.with_member_directory(directory);
+    }
+    // 0 lists the recipients of every mention
+    let fanout_limit = config.members.mention_fanout_limit;
+    service = service.with_mention_fanout_limit((fanout_limit > 0).then_some(fanout_limit));
+
+    Ok((
+        service,
+        ServiceFeatures {
+            attachment_scanner: attachment_scanner.is_some(),
+            link_unfurler: link_unfurler.is_some(),
+            message_archive,
+            consumer_status,
+            members_status,
+        },
+    ))
+}
+
+/// Spawn the purge of deleted messages, the thread archive and the message
+/// retention, each led by the lease holder when singleton jobs are leased
+fn spawn_maintenance_jobs(
+    config: &Config,
+    service: &CommunitiesService,
+    lease_lock: Option<&Arc<dyn LeaseLock>>,
+) {
+    // 0 keeps soft deleted messages forever, expired ones are purged regardless
+    let retention = config.purge.deleted_message_retention_secs;
+    let mut purge = DeletedMessagePurge::new(
+        service.clone(),
+        DeletedMessagePurgeConfig {
+            retention: (retention > 0).then(|| Duration::from_secs(retention)),
+            interval: Duration::from_secs(config.purge.purge_interval_secs.max(1)),
+            ..Default::default()
+        },
+    );
+    if let Some(lock) = lease_lock {
+        purge = purge.with_leader(lock.clone());
+    }
+    purge.spawn();
+
+    // 0 never archives threads
+    let idle = config.threads.archive_after_secs;
+    if idle > 0 {
+        let mut archive = ThreadArchive::new(
+            service.clone(),
+            ThreadArchiveConfig {
+                idle: Duration::from_secs(idle),
+                interval: Duration::from_secs(config.threads.archive_interval_secs.max(1)),
+                ..Default::default()
+            },
+        );
+        if let Some(lock) = lease_lock {
+            archive = archive.with_leader(lock.clone());
+        }
+        archive.spawn();
+    }
+
+    // 0 never deletes messages, even in channels with a custom retention
+    let interval = config.retention.interval_secs;
+    if interval > 0 {
+        let default_retention = config.retention.message_retention_secs;
+        let mut retention = MessageRetention::new(
+            service.clone(),
+            MessageRetentionConfig {
+                default_retention: (default_retention > 0)
+                    .then(|| Duration::from_secs(default_retention)),
+                interval: Duration::from_secs(interval),
+                ..Default::default()
+            },
+        );
+        if let Some(lock) = lease_lock {
+            retention = retention.with_leader(lock.clone());
+        }
+        retention.spawn();
+    }
+}
+
+/// Assemble the state shared by the handlers
+async fn init_state(
+    config: &Config,
+    repos: &CommunitiesRepositories,
+    service: CommunitiesService,
+    features: &ServiceFeatures,
+    search_index: Option<Arc<dyn SearchIndex>>,
+    instance: &str,
+    writable: bool,
+) -> Result<AppState, ApiError> {
+    let list_cache = MessageListCache::new(
+        Duration::from_secs(config.cache.list_ttl_secs),
+        config.cache.list_max_capacity,
+    );
+
+    let authz = init_authz(config).await?;
+
+    let mut search = match search_index {
+        Some(index) => repos.message_search_over(index),
+        None => repos.message_search(),
+    };
+    if let Some(archive) = &features.message_archive {
+        search = search.with_archive(archive.clone());
+    }
+
+    let mut builder = AppState::builder(service)
+        .list_cache(list_cache)
+        .authz(authz)
+        .fanout(FanoutConfig {
+            send_buffer: config.realtime.send_buffer,
+            policy: config.realtime.slow_consumer_policy,
+        })
+        .replay(ReplayConfig {
+            capacity: config.realtime.replay_events,
+            ttl: Duration::from_secs(config.realtime.replay_ttl_secs),
+        })
+        .search(search)
+        .stream_throttle(StreamThrottle::new(config.streaming.max_bytes_per_sec))
+        .read_only(config.read_only);
+    if let Some(links) = init_export_links(config)? {
+        builder = builder.export_links(links);
+    }
+    if let Some(id_obfuscation) = init_public_ids(config)? {
+        builder = builder.id_obfuscation(id_obfuscation);
+    }
+    if config.realtime.source == RealtimeSource::ChangeStream {
+        builder = builder.events(spawn_change_stream(repos, instance, writable));
+    }
+
+    builder.build()
+}
+
+/// Watch the MongoDB change stream into the events the connections fan out
+fn spawn_change_stream(
+    repos: &CommunitiesRepositories,
+    instance: &str,
+    writable: bool,
+) -> MessageEventBus {
+    let events = MessageEventBus::new();
+    let mut watcher = repos
+        .message_repository
+        .change_stream_watcher(events.clone())
+        .for_instance(instance);
+    if !writable {
+        watcher = watcher.without_token_persistence();
+    }
+    watcher.spawn();
+    events
+}
+
+/// Warm the listings of the busiest channels up in the background,
+/// so a deploy does not send their first requests all to MongoDB
+fn prime_list_cache(config: &Config, state: &AppState) {
+    if config.cache.prime_channels == 0 {
+        return;
+    }
+    let cache = state.list_cache.clone();
+    let service = state.service.clone();
+    let channels = config.cache.prime_channels;
+    let window = chrono::Duration::seconds(config.cache.prime_window_secs as i64);
+    tokio::spawn(async move {
+        match cache.prime(&service, chrono::Utc::now() - window, channels).await {
+            Ok(primed) => tracing::info!(primed, "primed the listing cache"),
+            Err(e) => {
+                tracing::warn!(error = %e, "failed to prime the listing cache")
+            }
+        }
+    });
+}
+
+/// Follow the message events with the attachment scans, link previews and
+/// search indexing the service was configured with
+fn spawn_workers(
+    config: &Config,
+    state: &AppState,
+    features: &ServiceFeatures,
+    search_index: Option<Arc<dyn SearchIndex>>,
+) {
+    // Scanned and unfurled by the instance that created the message, off the request
+    if features.attachment_scanner {
+        AttachmentScanWorker::new(state.service.clone(), config.attachments.scan_concurrency)
+            .follow(state.service.events());
+    }
+    if features.link_unfurler {
+        LinkPreviewWorker::new(state.service.clone(), config.link_previews.concurrency)
+            .follow(state.service.events());
+    }
+
+    if let Some(index) = search_index {
+        let (indexer, _consumer) = SearchIndexer::spawn(index, SearchIndexerConfig::default());
+        indexer.follow(&state.events);
+    }
+}
+
+/// Consume the channel deletions and membership changes published on RabbitMQ
+fn spawn_consumers(
+    config: &Config,
+    repos: &CommunitiesRepositories,
+    state: &AppState,
+    features: ServiceFeatures,
+) {
+    if let Some(status) = features.consumer_status {
+        AmqpConsumer::new(
+            config.consumer.rabbitmq_url.clone(),
+            QueueBinding {
+                exchange: config.consumer.channels_exchange.clone(),
+                routing_key: config.consumer.channel_deleted_routing_key.clone(),
+                queue: config.consumer.channel_deleted_queue.clone(),
+            },
+            ChannelDeletedHandler::new(state.clone()),
+            status,
+        )
+        .spawn();
+    }
+    if let Some(status) = features.members_status {
+        AmqpConsumer::new(
+            config.consumer.rabbitmq_url.clone(),
+            QueueBinding {
+                exchange: config.consumer.communities_exchange.clone(),
+                routing_key: config.consumer.channel_members_routing_key.clone(),
+                queue: config.consumer.channel_members_queue.clone(),
+            },
+            ChannelMembersHandler::new(repos.channel_membership()),
+            status,
+        )
+        .spawn();
+    }
+}
+
+/// Router of the API, behind authentication and the configured middlewares,
+/// with the documentation as exposed by `DOCS_EXPOSURE`
+fn init_app_router(config: &Config, state: &AppState) -> Result<axum::Router, ApiError> {
+    let keycloak_repository = KeycloakAuthRepository::new(
+        format!(
+            "{}/realms/{}",
+            config.keycloak.internal_url, config.keycloak.realm
+        ),
+        None,
+    );
+    let mut router = api_router();
+    if config.read_only {
+        router = router.route_layer(axum::middleware::from_fn(reject_writes));
+    }
+    // inside authentication, to recognize the followed users
+    if let Some(logging) = init_body_logging(config)? {
+        router = router.route_layer(from_fn_with_state(Arc::new(logging), log_bodies));
+    }
+    // right inside authentication, so the rest sees the impersonated user
+    router = router.route_layer(from_fn_with_state(
+        Arc::new(Impersonation::new(
+            config.impersonation_enabled,
+            state.authz.clone(),
+        )),
+        impersonate,
+    ));
+    if config.impersonation_enabled {
+        tracing::warn!("admins may impersonate users");
+    }
+    let (app_router, api) = router
+        .route_layer(from_extractor_with_state::<
+            AuthMiddleware,
+            KeycloakAuthRepository,
+        >(keycloak_repository.clone()))
+        // signed links are their own authorization
+        .merge(public_router())
+        .split_for_parts();
+
+    // also exposed as an extension, for the route permission layers
+    let app_router = app_router
+        .with_state(state.clone())
+        .layer(from_fn_with_state(state.clone(), public_ids))
+        .layer(Extension(state.clone()));
+    let app_router = match config.docs.exposure {
+        DocsExposure::Disabled => app_router,
+        DocsExposure::Public => app_router.merge(docs_router(with_doc_info(api))),
+        DocsExposure::Authenticated => app_router.merge(
+            docs_router(with_doc_info(api)).route_layer(from_extractor_with_state::<
+                AuthMiddleware,
+                KeycloakAuthRepository,
+            >(keycloak_repository)),
+        ),
+    };
+    tracing::info!(exposure = ?config.docs.exposure, "API documentation exposure");
+    Ok(app_router.layer(axum::middleware::from_fn(negotiate_format)))
+}
+
+/// Build the authorization client selected by the configuration.
+///
+/// SpiceDB is used whenever both an endpoint and a token are configured. The
+/// allow-all client is only accepted as a fallback in Development and Test.
+async fn init_authz(config: &Config) -> Result<DynAuthz, ApiError> {
+    let spicedb = &config.spicedb;
+    if !spicedb.endpoint.is_empty() && !spicedb.token.is_empty() {
+        let cfg = LocalSpiceConfig {
+            endpoint: spicedb.endpoint.clone(),
+            token: Some(spicedb.token.clone()),
+        };
+        let client = SpiceDbAuthz::new(cfg).await.map_err(|e| ApiError::StartupError {
+            msg: format!("Failed to init spice db authz: {:?}", e),
+        })?;
+        tracing::info!(endpoint = %spicedb.endpoint, "authorization backend: spicedb");
+        return Ok(Arc::new(client));
+    }
+
+    match config.environment {
+        Environment::Development | Environment::Test => {
+            tracing::warn!(
+                environment = ?config.environment,
+                "authorization backend: allow-all (spicedb endpoint/token not configured)"
+            );
+            Ok(Arc::new(DummyAuthz::new()))
+        }
+        Environment::Production => Err(ApiError::StartupError {
+            msg: "SpiceDB endpoint and token must be configured in production".to_string(),
+        }),
+    }
+}
+
+/// Log sampled request and response bodies when `DEBUG_LOG_*` selects any
+/// request; never in production, where bodies hold user data
+fn init_body_logging(config: &Config) -> Result<Option<BodyLogging>, ApiError> {
+    let debug_log = &config.debug_log;
+    let Some(logging) = BodyLogging::new(
+        debug_log.sample_percent,
+        debug_log.user_ids.iter().copied(),
+        debug_log.redact_fields.iter().cloned(),
+        debug_log.max_body_bytes,
+    ) else {
+        return Ok(None);
+    };
+
+    if matches!(config.environment, Environment::Production) {
+        return Err(ApiError::StartupError {
+            msg: "Body logging (DEBUG_LOG_*) cannot be enabled in production".to_string(),
+        });
+    }
+    tracing::warn!(
+        sample_percent = debug_log.sample_percent,
+        users = debug_log.user_ids.len(),
+        "logging sampled request and response bodies"
+    );
+    Ok(Some(logging))
+}
+
+/// Lease holder id of this replica
+fn instance_id(config: &Config) -> String {
+    if config.jobs.instance_id.is_empty() {
+        uuid::Uuid::new_v4().to_string()
+    } else {
+        config.jobs.instance_id.clone()
+    }
+}
+
+/// Build the outbox payload encryption from the configured master key or
+/// Vault transit key, if any
+fn init_outbox_encryption(config: &Config) -> Result<Option<OutboxEncryption>, ApiError> {
+    let outbox = &config.outbox;
+    if !outbox.vault_addr.is_empty() {
+        if !outbox.encryption_key.is_empty() {
+            return Err(ApiError::StartupError {
+                msg: "set either OUTBOX_ENCRYPTION_KEY or OUTBOX_VAULT_ADDR, not both".to_string(),
+            });
+        }
+        return Ok(Some(OutboxEncryption::new(init_vault_kms(outbox)?)));
+    }
+    if outbox.encryption_key.is_empty() {
+        return Ok(None);
+    }
+
+    let invalid_key = || ApiError::StartupError {
+        msg: "OUTBOX_ENCRYPTION_KEY must be 64 hexadecimal characters (32 bytes)".to_string(),
+    };
+    let master_key: [u8; 32] = hex_key(&outbox.encryption_key).ok_or_else(invalid_key)?;
+
+    tracing::info!(key_id = %outbox.encryption_key_id, "outbox payload encryption enabled");
+    let kms = LocalKeyManagementService::new(outbox.encryption_key_id.clone(), &master_key);
+    Ok(Some(OutboxEncryption::new(Arc::new(kms))))
+}
+
+/// Data keys of the outbox payloads encrypted by the transit engine of `OUTBOX_VAULT_ADDR`
+fn init_vault_kms(outbox: &OutboxConfig) -> Result<Arc<dyn KeyManagementService>, ApiError> {
+    #[cfg(feature = "vault-kms")]
+    {
+        if outbox.vault_token.is_empty() {
+            return Err(ApiError::StartupError {
+                msg: "OUTBOX_VAULT_ADDR requires OUTBOX_VAULT_TOKEN".to_string(),
+            });
+        }
+        let kms = VaultTransitKms::new(
+            outbox.vault_addr.clone(),
+            outbox.vault_token.clone(),
+            outbox.vault_transit_mount.clone(),
+            outbox.vault_key.clone(),
+            Duration::from_secs(outbox.vault_timeout_secs.max(1)),
+        )
+        .map_err(|e| ApiError::StartupError {
+            msg: format!("Failed to configure the Vault transit engine: {e}"),
+        })?;
+
+        tracing::info!(
+            address = %outbox.vault_addr,
+            key = %outbox.vault_key,
+            "outbox payload encryption enabled with Vault transit"
+        );
+        Ok(Arc::new(kms))
+    }
+    #[cfg(not(feature = "vault-kms"))]
+    {
+        let _ = outbox;
+        Err(ApiError::StartupError {
+            msg: "OUTBOX_VAULT_ADDR requires building with the `vault-kms` feature".to_string(),
+        })
+    }
+}
+
+/// Sign export download links when `EXPORT_LINK_KEY` is set
+fn init_export_links(config: &Config) -> Result<Option<ExportLinks>, ApiError> {
+    let exports = &config.exports;
+    if exports.link_key.is_empty() {
+        return Ok(None);
+    }
+
+    let key: [u8; 32] = hex_key(&exports.link_key).ok_or_else(|| ApiError::StartupError {
+        msg: "EXPORT_LINK_KEY must be 64 hexadecimal characters (32 bytes)".to_string(),
+    })?;
+    let ttl = Duration::from_secs(exports.link_ttl_secs.max(1));
+    tracing::info!(ttl_secs = ttl.as_secs(), "export download links enabled");
+    Ok(Some(ExportLinks::new(&key, ttl)))
+}
+
+/// Key of `N` bytes written as `2 * N` hexadecimal characters
+fn hex_key<const N: usize>(hex: &str) -> Option<[u8; N]> {
+    if !hex.is_ascii() || hex.len() != 2 * N {
+        return None;
+    }
+    let mut key = [0u8; N];
+    for (i, byte) in key.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).ok()?;
+    }
+    Some(key)
+}
+
+/// Encryption of the message and channel IDs of the API, when `PUBLIC_ID_KEY` is set
+fn init_public_ids(config: &Config) -> Result<Option<Arc<dyn IdObfuscation>>, ApiError> {
+    let key = &config.public_ids.key;
+    if key.is_empty() {
+        return Ok(None);
+    }
+
+    let invalid_key = || ApiError::StartupError {
+        msg: "PUBLIC_ID_KEY must be 32 hexadecimal characters (16 bytes)".to_string(),
+    };
+    let id_key: [u8; 16] = hex_key(key).ok_or_else(invalid_key)?;
+
+    tracing::info!("public ID encryption enabled");
+    Ok(Some(Arc::new(EncryptedIds::new(&id_key))))
+}
+
+/// Search backend storing its own copy of the messages, as configured by
+/// `SEARCH_BACKEND`; `None` when searches use the MongoDB text index
+pub fn init_search_index(config: &SearchConfig) -> Result<Option<Arc<dyn SearchIndex>>, ApiError> {
+    match config.backend {
+        SearchBackend::MongoText => Ok(None),
+        #[cfg(feature = "elasticsearch")]
+        SearchBackend::Elasticsearch => {
+            let mut repository = ElasticsearchMessageSearchRepository::new(
+                config.elasticsearch_url.clone(),
+                config.elasticsearch_index.clone(),
+            );
+            if !config.elasticsearch_username.is_empty() {
+                repository = repository.with_basic_auth(
+                    config.elasticsearch_username.clone(),
+                    config.elasticsearch_password.clone(),
+                );
+            }
+
+            tracing::info!(index = %config.elasticsearch_index, "searching messages with elasticsearch");
+            Ok(Some(ensure_search_index(repository)))
+        }
+        #[cfg(not(feature = "elasticsearch"))]
+        SearchBackend::Elasticsearch => Err(ApiError::StartupError {
+            msg: "SEARCH_BACKEND=elasticsearch requires building with the `elasticsearch` feature"
+                .to_string(),
+        }),
+        #[cfg(feature = "meilisearch")]
+        SearchBackend::Meilisearch => {
+            let mut repository = MeilisearchMessageSearchRepository::new(
+                config.meilisearch_url.clone(),
+                config.meilisearch_index.clone(),
+            );
+            if !config.meilisearch_api_key.is_empty() {
+                repository = repository.with_api_key(config.meilisearch_api_key.clone());
+            }
+
+            tracing::info!(index = %config.meilisearch_index, "searching messages with meilisearch");
+            Ok(Some(ensure_search_index(repository)))
+        }
+        #[cfg(not(feature = "meilisearch"))]
+        SearchBackend::Meilisearch => Err(ApiError::StartupError {
+            msg: "SEARCH_BACKEND=meilisearch requires building with the `meilisearch` feature"
+                .to_string(),
+        }),
+        #[cfg(feature = "tantivy")]
+        SearchBackend::Tantivy => {
+            let repository = TantivyMessageSearchRepository::open(&config.tantivy_index_path)
+                .map_err(|e| ApiError::StartupError {
+                    msg: format!("Failed to open the search index: {e}"),
+                })?;
+
+            tracing::info!(path = %config.tantivy_index_path.display(), "searching messages with tantivy");
+            Ok(Some(ensure_search_index(repository)))
+        }
+        #[cfg(not(feature = "tantivy"))]
+        SearchBackend::Tantivy => Err(ApiError::StartupError {
+            msg: "SEARCH_BACKEND=tantivy requires building with the `tantivy` feature".to_string(),
+        }),
+    }
+}
+
+/// Archive of the messages past their retention, as configured by
+/// `ARCHIVE_S3_BUCKET`; `None` when they are deleted without archiving
+pub fn init_message_archive(
+    config: &ArchiveConfig,
+    repos: &CommunitiesRepositories,
+) -> Result<Option<Arc<CommunitiesArchive>>, ApiError> {
+    if config.s3_bucket.is_empty() {
+        return Ok(None);
+    }
+
+    #[cfg(feature = "s3-archive")]
+    {
+        let storage = S3ObjectStorage::new(
+            &config.s3_bucket,
+            &config.s3_region,
+            &config.s3_endpoint,
+            &config.s3_access_key_id,
+            &config.s3_secret_access_key,
+        )
+        .map_err(|e| ApiError::StartupError {
+            msg: format!("Failed to configure the message archive: {e}"),
+        })?;
+
+        tracing::info!(bucket = %config.s3_bucket, "archiving expired messages to s3");
+        Ok(Some(Arc::new(
+            repos.message_archive(Arc::new(storage), config.prefix.clone()),
+        )))
+    }
+    #[cfg(not(feature = "s3-archive"))]
+    {
+        let _ = repos;
+        Err(ApiError::StartupError {
+            msg: "ARCHIVE_S3_BUCKET requires building with the `s3-archive` feature".to_string(),
+        })
+    }
+}
+
+/// Storage the attachments are uploaded to, as configured by
+/// `ATTACHMENT_S3_BUCKET`; `None` when uploads are disabled
+pub fn init_attachment_storage(
+    config: &AttachmentConfig,
+) -> Result<Option<Arc<dyn AttachmentStorage>>, ApiError> {
+    if config.s3_bucket.is_empty() {
+        return Ok(None);
+    }
+
+    #[cfg(feature = "s3-attachments")]
+    {
+        let storage = S3AttachmentStorage::new(
+            &config.s3_bucket,
+            &config.s3_region,
+            &config.s3_endpoint,
+            &config.s3_access_key_id,
+            &config.s3_secret_access_key,
+            &config.public_url,
+        )
+        .map_err(|e| ApiError::StartupError {
+            msg: format!("Failed to configure the attachment storage: {e}"),
+        })?;
+
+        tracing::info!(bucket = %config.s3_bucket, "attachment uploads enabled");
+        Ok(Some(Arc::new(storage)))
+    }
+    #[cfg(not(feature = "s3-attachments"))]
+    {
+        Err(ApiError::StartupError {
+            msg: "ATTACHMENT_S3_BUCKET requires building with the `s3-attachments` feature"
+                .to_string(),
+        })
+    }
+}
+
+/// Malware scanner of posted attachments, as configured by
+/// `ATTACHMENT_CLAMAV_ADDRESS`; `None` leaves them unscanned
+pub fn init_attachment_scanner(
+    config: &AttachmentConfig,
+) -> Result<Option<Arc<dyn AttachmentScanner>>, ApiError> {
+    if config.clamav_address.is_empty() {
+        return Ok(None);
+    }
+
+    #[cfg(feature = "clamav")]
+    {
+        tracing::info!(address = %config.clamav_address, "attachment scanning enabled");
+        Ok(Some(Arc::new(ClamAvScanner::new(
+            config.clamav_address.clone(),
+            Duration::from_secs(config.scan_timeout_secs.max(1)),
+        ))))
+    }
+    #[cfg(not(feature = "clamav"))]
+    {
+        Err(ApiError::StartupError {
+            msg: "ATTACHMENT_CLAMAV_ADDRESS requires building with the `clamav` feature"
+                .to_string(),
+        })
+    }
+}
+
+/// Previews of image attachments, as configured by `THUMBNAIL_SERVICE_URL`;
+/// `None` posts images without one
+pub fn init_thumbnail_generator(
+    config: &AttachmentConfig,
+) -> Result<Option<Arc<dyn ThumbnailGenerator>>, ApiError> {
+    if config.thumbnail_service_url.is_empty() {
+        return Ok(None);
+    }
+
+    #[cfg(feature = "http-thumbnails")]
+    {
+        let generator = HttpThumbnailGenerator::new(
+            config.thumbnail_service_url.clone(),
+            Duration::from_secs(config.thumbnail_timeout_secs.max(1)),
+        )
+        .map_err(|e| ApiError::StartupError {
+            msg: format!("Failed to configure the thumbnail service: {e}"),
+        })?;
+
+        tracing::info!(url = %config.thumbnail_service_url, "image previews enabled");
+        Ok(Some(Arc::new(generator)))
+    }
+    #[cfg(not(feature = "http-thumbnails"))]
+    {
+        Err(ApiError::StartupError {
+            msg: "THUMBNAIL_SERVICE_URL requires building with the `http-thumbnails` feature"
+                .to_string(),
+        })
+    }
+}
+
+/// Holders of the roles mentioned by new messages, as configured by
+/// `MEMBERS_SERVICE_URL`; `None` resolves them from the snapshot of channel
+/// members
+pub fn init_member_directory(
+    config: &MembersConfig,
+) -> Result<Option<Arc<dyn MemberDirectory>>, ApiError> {
+    if config.members_service_url.is_empty() {
+        return Ok(None);
+    }
+
+    #[cfg(feature = "http-members")]
+    {
+        let directory = HttpMemberDirectory::new(
+            config.members_service_url.clone(),
+            Duration::from_secs(config.members_timeout_secs.max(1)),
+        )
+        .map_err(|e| ApiError::StartupError {
+            msg: format!("Failed to configure the members service: {e}"),
+        })?;
+
+        tracing::info!(url = %config.members_service_url, "role mentions resolved by the members service");
+        Ok(Some(Arc::new(directory)))
+    }
+    #[cfg(not(feature = "http-members"))]
+    {
+        Err(ApiError::StartupError {
+            msg: "MEMBERS_SERVICE_URL requires building with the `http-members` feature"
+                .to_string(),
+        })
+    }
+}
+
+/// Previews of the links of new messages, as configured by
+/// `LINK_PREVIEWS_ENABLED`; `None` leaves messages without embeds
+pub fn init_link_unfurler(
+    config: &LinkPreviewConfig,
+) -> Result<Option<Arc<dyn LinkUnfurler>>, ApiError> {
+    if !config.enabled {
+        return Ok(None);
+    }
+
+    #[cfg(feature = "link-previews")]
+    {
+        let unfurler = OpenGraphUnfurler::new(
+            Duration::from_secs(config.timeout_secs.max(1)),
+            config.max_page_bytes,
+        )
+        .map_err(|e| ApiError::StartupError {
+            msg: format!("Failed to configure link previews: {e}"),
+        })?;
+
+        tracing::info!(
+            allowed_domains = ?config.allowed_domains,
+            denied_domains = ?config.denied_domains,
+            "link previews enabled"
+        );
+        Ok(Some(Arc::new(unfurler)))
+    }
+    #[cfg(not(feature = "link-previews"))]
+    {
+        Err(ApiError::StartupError {
+            msg: "LINK_PREVIEWS_ENABLED requires building with the `link-previews` feature"
+                .to_string(),
+        })
+    }
+}
+
+/// Create the index of `repository` in the background like the MongoDB
+/// indexes; indexing calls are retried until the backend is reachable
+#[cfg(any(feature = "elasticsearch", feature = "meilisearch", feature = "tantivy"))]
+fn ensure_search_index<R>(repository: R) -> Arc<dyn SearchIndex>
+where
+    R: MessageSearchRepository + Clone + 'static,
+{
+    let indexed = repository.clone();
+    tokio::spawn(async move {
+        if let Err(e) = indexed.ensure_index().await {
+            tracing::warn!(error = %e, "failed to ensure search index");
+        }
+    });
+    Arc::new(repository)
+}
+
+/// Routes of the API, before authentication is layered on
+fn api_router() -> OpenApiRouter<AppState> {
+    OpenApiRouter::<AppState>::new()
+        .merge(message_routes())
+        .merge(ws_routes())
+        .merge(graphql_routes())
+        .merge(storage_routes())
+        .merge(attachment_routes())
+        .merge(legal_hold_routes())
+        .merge(channel_lock_routes())
+        .merge(channel_settings_routes())
+        .merge(read_marker_routes())
+        .merge(admin_routes())
+    // Add application routes here
+}
+
+/// Routes served without authentication
+fn public_router() -> OpenApiRouter<AppState> {
+    export_download_routes()
+}
+
+fn with_doc_info(mut api: utoipa::openapi::OpenApi) -> utoipa::openapi::OpenApi {
+    let doc = ApiDoc::openapi();
+    api.info = doc.info.clone();
+    api.merge(doc);
+    api
+}
+
+/// OpenAPI specification of the API, generated without starting the service
+pub fn openapi() -> utoipa::openapi::OpenApi {
+    let (_, api) = api_router().merge(public_router()).split_for_parts();
+    with_doc_info(api)
+}
+
+/// Scalar UI at `/scalar` and the raw specification at `/openapi.json`
+fn docs_router(api: utoipa::openapi::OpenApi) -> axum::Router {
+    let spec = Arc::new(api.clone());
+    axum::Router::new()
+        .merge(Scalar::with_url("/scalar", api))
+        .route(
+            "/openapi.json",
+            get(move || {
+                let spec = spec.clone();
+                async move { Json(spec.as_ref().clone()) }
+            }),
+        )
+}
+
 pub trait AppBuilder {
     fn build(config: Config) -> impl Future<Output = Result<App, ApiError>>;
     fn with_state(self, state: AppState) -> impl Future<Output = Result<App, ApiError>>;
@@ -0,0 +1,64 @@
+use std::path::PathBuf;
+
+use api::app::openapi;
+use api::http::server::ApiError;
+use api::openapi_diff::breaking_changes;
+use clap::{Parser, Subcommand};
+
+#[derive(Parser, Debug)]
+#[command(name = "openapi")]
+#[command(about = "OpenAPI specification of the API", long_about = None)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Print the specification on stdout (the default)
+    Print,
+    /// Compare the specification with a baseline and fail on changes that
+    /// break its clients (removed fields, changed types, ...)
+    Diff {
+        #[arg(long = "baseline", default_value = "openapi.json")]
+        baseline: PathBuf,
+    },
+}
+
+fn main() -> Result<(), ApiError> {
+    let cli = Cli::parse();
+
+    match cli.command.unwrap_or(Command::Print) {
+        Command::Print => {
+            let spec = openapi()
+                .to_pretty_json()
+                .map_err(|e| ApiError::StartupError {
+                    msg: format!("Failed to generate OpenAPI spec: {}", e),
+                })?;
+            println!("{spec}");
+        }
+        Command::Diff { baseline } => {
+            let baseline = std::fs::read_to_string(&baseline)
+                .map_err(|e| e.to_string())
+                .and_then(|spec| serde_json::from_str(&spec).map_err(|e| e.to_string()))
+                .map_err(|e| ApiError::StartupError {
+                    msg: format!("Failed to read the baseline {}: {e}", baseline.display()),
+                })?;
+            let current = serde_json::to_value(openapi()).map_err(|e| ApiError::StartupError {
+                msg: format!("Failed to generate OpenAPI spec: {}", e),
+            })?;
+
+            let changes = breaking_changes(&baseline, &current);
+            if !changes.is_empty() {
+                for change in &changes {
+                    eprintln!("breaking: {change}");
+                }
+                return Err(ApiError::StartupError {
+                    msg: format!("{} breaking changes to the API", changes.len()),
+                });
+            }
+            eprintln!("no breaking change");
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,97 @@
+use std::sync::Arc;
+
+use api::app::init_search_index;
+use api::config::{DatabaseConfig, SearchConfig};
+use api::http::server::ApiError;
+use api::logging::init_tracing;
+use clap::{Parser, Subcommand};
+use communities_core::{
+    application::MessageRoutingInfos,
+    create_repositories,
+    domain::{message::entities::ChannelId, search::ports::NoopSearchIndex},
+    infrastructure::search::{SearchIndexer, SearchIndexerConfig, reindex_channel},
+};
+use dotenv::dotenv;
+use tracing::{info, level_filters::LevelFilter};
+use uuid::Uuid;
+
+#[derive(Parser, Debug)]
+#[command(name = "search")]
+#[command(about = "Maintenance commands for the message search index", long_about = None)]
+struct Cli {
+    #[command(flatten)]
+    database: DatabaseConfig,
+
+    #[command(flatten)]
+    search: SearchConfig,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Rebuild the search index of a channel from MongoDB
+    Reindex {
+        #[arg(long = "channel")]
+        channel: Uuid,
+
+        /// Maximum number of messages sent to the search backend per call
+        #[arg(long = "batch-size", default_value = "100")]
+        batch_size: usize,
+
+        /// Maximum number of calls to the search backend per second
+        #[arg(long = "max-batches-per-second", default_value = "10")]
+        max_batches_per_second: u32,
+    },
+}
+
+#[tokio::main]
+async fn main() -> Result<(), ApiError> {
+    init_tracing(LevelFilter::INFO);
+
+    dotenv().ok();
+    let cli = Cli::parse();
+
+    match cli.command {
+        Command::Reindex {
+            channel,
+            batch_size,
+            max_batches_per_second,
+        } => {
+            let repositories = create_repositories(
+                &cli.database.mongo_uri,
+                &cli.database.mongo_db_name,
+                MessageRoutingInfos::default(),
+            )
+            .await?;
+
+            // the MongoDB text index needs no rebuild
+            let index =
+                init_search_index(&cli.search)?.unwrap_or_else(|| Arc::new(NoopSearchIndex));
+            let (indexer, consumer) = SearchIndexer::spawn(
+                index,
+                SearchIndexerConfig {
+                    batch_size,
+                    max_batches_per_second,
+                    ..Default::default()
+                },
+            );
+
+            let channel_id = ChannelId(channel);
+            let queued =
+                reindex_channel(&repositories.message_repository, &indexer, &channel_id).await?;
+
+            // dropping the last handle lets the consumer drain the queue and stop
+            drop(indexer);
+            consumer
+                .await
+                .map_err(|e| ApiError::StartupError { msg: e.to_string() })?;
+
+            info!(channel = %channel, queued, "channel reindexed");
+            repositories.shutdown().await;
+        }
+    }
+
+    Ok(())
+}
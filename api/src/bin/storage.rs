@@ -0,0 +1,54 @@
+use api::config::DatabaseConfig;
+use api::http::server::ApiError;
+use api::logging::init_tracing;
+use clap::{Parser, Subcommand};
+use communities_core::{application::MessageRoutingInfos, create_repositories};
+use dotenv::dotenv;
+use tracing::{info, level_filters::LevelFilter};
+
+#[derive(Parser, Debug)]
+#[command(name = "storage")]
+#[command(about = "Maintenance commands for the MongoDB storage", long_about = None)]
+struct Cli {
+    #[command(flatten)]
+    database: DatabaseConfig,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Convert the stored reference UUIDs to the standard BSON encoding.
+    /// Safe to run while the service is up, and to run again
+    BackfillUuids {
+        /// Number of documents fetched from MongoDB per round trip
+        #[arg(long = "batch-size", default_value = "500")]
+        batch_size: u32,
+    },
+}
+
+#[tokio::main]
+async fn main() -> Result<(), ApiError> {
+    init_tracing(LevelFilter::INFO);
+
+    dotenv().ok();
+    let cli = Cli::parse();
+
+    let repositories = create_repositories(
+        &cli.database.mongo_uri,
+        &cli.database.mongo_db_name,
+        MessageRoutingInfos::default(),
+    )
+    .await?;
+
+    match cli.command {
+        Command::BackfillUuids { batch_size } => {
+            let converted = repositories.backfill_standard_uuids(batch_size).await?;
+            info!(converted, "uuid backfill complete");
+        }
+    }
+
+    repositories.shutdown().await;
+    Ok(())
+}
@@ -1,7 +1,10 @@
 use clap::Parser;
 use clap::ValueEnum;
 use communities_core::application::MessageRoutingInfos;
+use communities_core::domain::message::entities::ModerationReasonTemplate;
+use crate::http::ws::fanout::SlowConsumerPolicy;
 use std::path::PathBuf;
+use uuid::Uuid;
 
 #[derive(Clone, Parser, Debug, Default)]
 #[command(name = "communities-api")]
@@ -22,6 +25,69 @@ pub struct Config {
     #[command(flatten)]
     pub spicedb: SpiceDbConfig,
 
+    #[command(flatten)]
+    pub cache: CacheConfig,
+
+    #[command(flatten)]
+    pub realtime: RealtimeConfig,
+
+    #[command(flatten)]
+    pub outbox: OutboxConfig,
+
+    #[command(flatten)]
+    pub docs: DocsConfig,
+
+    #[command(flatten)]
+    pub consumer: ConsumerConfig,
+
+    #[command(flatten)]
+    pub jobs: JobsConfig,
+
+    #[command(flatten)]
+    pub storage: StorageConfig,
+
+    #[command(flatten)]
+    pub pins: PinsConfig,
+
+    #[command(flatten)]
+    pub purge: PurgeConfig,
+
+    #[command(flatten)]
+    pub threads: ThreadsConfig,
+
+    #[command(flatten)]
+    pub retention: RetentionConfig,
+
+    #[command(flatten)]
+    pub archive: ArchiveConfig,
+
+    #[command(flatten)]
+    pub attachments: AttachmentConfig,
+
+    #[command(flatten)]
+    pub link_previews: LinkPreviewConfig,
+
+    #[command(flatten)]
+    pub members: MembersConfig,
+
+    #[command(flatten)]
+    pub streaming: StreamingConfig,
+
+    #[command(flatten)]
+    pub exports: ExportConfig,
+
+    #[command(flatten)]
+    pub search: SearchConfig,
+
+    #[command(flatten)]
+    pub trust_safety: TrustSafetyConfig,
+
+    #[command(flatten)]
+    pub public_ids: PublicIdConfig,
+
+    #[command(flatten)]
+    pub debug_log: DebugLogConfig,
+
     #[arg(
         long = "routing-config",
         env = "ROUTING_CONFIG_PATH",
@@ -38,6 +104,17 @@ pub struct Config {
         default_value = "development"
     )]
     pub environment: Environment,
+
+    /// Let admins holding the permission issue requests on behalf of a user
+    /// with the `X-Impersonate-User` header, for support debugging
+    #[arg(long = "impersonation-enabled", env = "IMPERSONATION_ENABLED")]
+    pub impersonation_enabled: bool,
+
+    /// Serve reads only, answering mutations with 503 and running no
+    /// background job, for disaster-recovery replicas pointed at a restored
+    /// or secondary database
+    #[arg(long = "read-only", env = "READ_ONLY")]
+    pub read_only: bool,
 }
 
 #[derive(Clone, Parser, Debug, Default)]
@@ -58,6 +135,750 @@ pub struct SpiceDbConfig {
     pub token: String,
 }
 
+#[derive(Clone, Parser, Debug, Default)]
+pub struct DocsConfig {
+    #[arg(
+        long = "docs-exposure",
+        env = "DOCS_EXPOSURE",
+        default_value = "public"
+    )]
+    pub exposure: DocsExposure,
+}
+
+/// Who can reach the Scalar UI (`/scalar`) and the OpenAPI spec (`/openapi.json`)
+#[derive(Clone, Debug, ValueEnum, Default, PartialEq, Eq)]
+pub enum DocsExposure {
+    /// Not served at all
+    Disabled,
+    #[default]
+    Public,
+    /// Served to requests carrying a valid access token
+    Authenticated,
+}
+
+#[derive(Clone, Parser, Debug, Default)]
+pub struct JobsConfig {
+    #[arg(
+        long = "singleton-jobs",
+        env = "SINGLETON_JOBS",
+        default_value = "every-replica"
+    )]
+    pub singleton_jobs: SingletonJobsMode,
+
+    /// How long a replica keeps a job lease without renewing it
+    #[arg(
+        long = "job-lease-ttl-secs",
+        env = "JOB_LEASE_TTL_SECS",
+        default_value = "30"
+    )]
+    pub lease_ttl_secs: u64,
+
+    /// Identifies this replica as a lease holder; a random id is generated when empty
+    #[arg(long = "instance-id", env = "INSTANCE_ID", default_value = "")]
+    pub instance_id: String,
+}
+
+/// Where singleton background jobs (outbox maintenance...) run
+#[derive(Clone, Debug, ValueEnum, Default, PartialEq, Eq)]
+pub enum SingletonJobsMode {
+    /// On every replica, for single-replica deployments
+    #[default]
+    EveryReplica,
+    /// On the replica holding the job's lease in MongoDB
+    Lease,
+}
+
+#[derive(Clone, Parser, Debug, Default)]
+pub struct ConsumerConfig {
+    /// RabbitMQ connection string; events from the other services are not consumed when empty
+    #[arg(
+        long = "rabbitmq-url",
+        env = "RABBITMQ_URL",
+        default_value = "",
+        hide_env_values = true
+    )]
+    pub rabbitmq_url: String,
+
+    #[arg(
+        long = "channels-exchange",
+        env = "CHANNELS_EXCHANGE",
+        default_value = "beep.channels"
+    )]
+    pub channels_exchange: String,
+
+    #[arg(
+        long = "channel-deleted-routing-key",
+        env = "CHANNEL_DELETED_ROUTING_KEY",
+        default_value = "channel.deleted"
+    )]
+    pub channel_deleted_routing_key: String,
+
+    #[arg(
+        long = "channel-deleted-queue",
+        env = "CHANNEL_DELETED_QUEUE",
+        default_value = "messages.channels.deleted"
+    )]
+    pub channel_deleted_queue: String,
+
+    #[arg(
+        long = "communities-exchange",
+        env = "COMMUNITIES_EXCHANGE",
+        default_value = "beep.communities"
+    )]
+    pub communities_exchange: String,
+
+    /// Binding of the membership events feeding the snapshot of channel members
+    #[arg(
+        long = "channel-members-routing-key",
+        env = "CHANNEL_MEMBERS_ROUTING_KEY",
+        default_value = "member.*"
+    )]
+    pub channel_members_routing_key: String,
+
+    #[arg(
+        long = "channel-members-queue",
+        env = "CHANNEL_MEMBERS_QUEUE",
+        default_value = "messages.communities.members"
+    )]
+    pub channel_members_queue: String,
+}
+
+#[derive(Clone, Parser, Debug, Default)]
+pub struct OutboxConfig {
+    /// How long published outbox records are kept before MongoDB deletes them (0 keeps them)
+    #[arg(
+        long = "outbox-sent-retention-secs",
+        env = "OUTBOX_SENT_RETENTION_SECS",
+        default_value = "604800"
+    )]
+    pub sent_retention_secs: u64,
+
+    /// Hex-encoded 256-bit master key; when set, outbox payloads are encrypted at rest
+    #[arg(
+        long = "outbox-encryption-key",
+        env = "OUTBOX_ENCRYPTION_KEY",
+        default_value = "",
+        hide_default_value = true,
+        hide_env_values = true
+    )]
+    pub encryption_key: String,
+
+    /// Identifier of the master key, stored with each encrypted record to allow rotation
+    #[arg(
+        long = "outbox-encryption-key-id",
+        env = "OUTBOX_ENCRYPTION_KEY_ID",
+        default_value = "local"
+    )]
+    pub encryption_key_id: String,
+
+    /// Address of a HashiCorp Vault whose transit engine holds the master key
+    /// instead of `OUTBOX_ENCRYPTION_KEY` (requires the `vault-kms` feature)
+    #[arg(long = "outbox-vault-addr", env = "OUTBOX_VAULT_ADDR", default_value = "")]
+    pub vault_addr: String,
+
+    /// Vault token allowed to generate data keys and decrypt with the transit key
+    #[arg(
+        long = "outbox-vault-token",
+        env = "OUTBOX_VAULT_TOKEN",
+        default_value = "",
+        hide_default_value = true,
+        hide_env_values = true
+    )]
+    pub vault_token: String,
+
+    /// Mount path of the transit engine
+    #[arg(
+        long = "outbox-vault-transit-mount",
+        env = "OUTBOX_VAULT_TRANSIT_MOUNT",
+        default_value = "transit"
+    )]
+    pub vault_transit_mount: String,
+
+    /// Name of the transit key encrypting the data keys
+    #[arg(
+        long = "outbox-vault-key",
+        env = "OUTBOX_VAULT_KEY",
+        default_value = "outbox"
+    )]
+    pub vault_key: String,
+
+    /// Longest a Vault call may take before the outbox write or publish fails
+    #[arg(
+        long = "outbox-vault-timeout-secs",
+        env = "OUTBOX_VAULT_TIMEOUT_SECS",
+        default_value = "5"
+    )]
+    pub vault_timeout_secs: u64,
+}
+
+#[derive(Clone, Parser, Debug, Default)]
+pub struct RealtimeConfig {
+    #[arg(
+        long = "realtime-source",
+        env = "REALTIME_SOURCE",
+        default_value = "in-process"
+    )]
+    pub source: RealtimeSource,
+
+    /// Frames queued per WebSocket connection before slow consumers are handled
+    #[arg(
+        long = "realtime-send-buffer",
+        env = "REALTIME_SEND_BUFFER",
+        default_value = "256"
+    )]
+    pub send_buffer: usize,
+
+    /// What to do when a connection's send buffer is full: drop the event or disconnect
+    #[arg(
+        long = "realtime-slow-consumer-policy",
+        env = "REALTIME_SLOW_CONSUMER_POLICY",
+        default_value = "drop"
+    )]
+    pub slow_consumer_policy: SlowConsumerPolicy,
+
+    /// Recent events kept per channel for reconnecting clients to resume from (0 to disable)
+    #[arg(
+        long = "realtime-replay-events",
+        env = "REALTIME_REPLAY_EVENTS",
+        default_value = "100"
+    )]
+    pub replay_events: usize,
+
+    /// How long the recent events of a channel are kept after its latest one
+    #[arg(
+        long = "realtime-replay-ttl-secs",
+        env = "REALTIME_REPLAY_TTL_SECS",
+        default_value = "300"
+    )]
+    pub replay_ttl_secs: u64,
+}
+
+/// Where the WebSocket gateway gets its message events from
+#[derive(Clone, Debug, ValueEnum, Default, PartialEq, Eq)]
+pub enum RealtimeSource {
+    /// Writes handled by this instance only
+    #[default]
+    InProcess,
+    /// MongoDB change stream, seeing the writes of every instance (requires a replica set)
+    ChangeStream,
+}
+
+#[derive(Clone, Parser, Debug, Default)]
+pub struct CacheConfig {
+    #[arg(
+        long = "cache-list-ttl-secs",
+        env = "CACHE_LIST_TTL_SECS",
+        default_value = "5"
+    )]
+    pub list_ttl_secs: u64,
+
+    #[arg(
+        long = "cache-list-max-capacity",
+        env = "CACHE_LIST_MAX_CAPACITY",
+        default_value = "10000"
+    )]
+    pub list_max_capacity: u64,
+
+    /// Busiest channels whose first page of messages and pins is cached at startup (0 to disable)
+    #[arg(
+        long = "cache-prime-channels",
+        env = "CACHE_PRIME_CHANNELS",
+        default_value = "20"
+    )]
+    pub prime_channels: usize,
+
+    /// Window over which the activity of channels is measured to pick the busiest ones
+    #[arg(
+        long = "cache-prime-window-secs",
+        env = "CACHE_PRIME_WINDOW_SECS",
+        default_value = "3600"
+    )]
+    pub prime_window_secs: u64,
+}
+
+#[derive(Clone, Parser, Debug, Default)]
+pub struct StorageConfig {
+    /// Attachment bytes a channel may store unless it has a custom quota (0 for unlimited)
+    #[arg(
+        long = "channel-storage-quota-bytes",
+        env = "CHANNEL_STORAGE_QUOTA_BYTES",
+        default_value = "0"
+    )]
+    pub channel_quota_bytes: u64,
+}
+
+#[derive(Clone, Parser, Debug, Default)]
+pub struct PublicIdConfig {
+    /// Hex-encoded 128-bit key; when set, message and channel IDs are encrypted in the REST API
+    #[arg(
+        long = "public-id-key",
+        env = "PUBLIC_ID_KEY",
+        default_value = "",
+        hide_default_value = true,
+        hide_env_values = true
+    )]
+    pub key: String,
+}
+
+#[derive(Clone, Parser, Debug, Default)]
+pub struct DebugLogConfig {
+    /// Percentage of requests logged with their bodies (0 to disable); refused in production
+    #[arg(
+        long = "debug-log-sample-percent",
+        env = "DEBUG_LOG_SAMPLE_PERCENT",
+        default_value = "0"
+    )]
+    pub sample_percent: f64,
+
+    /// Users whose every request is logged with its bodies, comma-separated
+    #[arg(
+        long = "debug-log-user-ids",
+        env = "DEBUG_LOG_USER_IDS",
+        value_delimiter = ','
+    )]
+    pub user_ids: Vec<Uuid>,
+
+    /// JSON keys redacted from logged bodies, on top of the always redacted ones
+    #[arg(
+        long = "debug-log-redact-fields",
+        env = "DEBUG_LOG_REDACT_FIELDS",
+        value_delimiter = ','
+    )]
+    pub redact_fields: Vec<String>,
+
+    /// Bytes logged of each body, longer ones being cut
+    #[arg(
+        long = "debug-log-max-body-bytes",
+        env = "DEBUG_LOG_MAX_BODY_BYTES",
+        default_value = "4096"
+    )]
+    pub max_body_bytes: usize,
+}
+
+#[derive(Clone, Parser, Debug, Default)]
+pub struct PinsConfig {
+    /// Pinned messages a channel may have (0 for unlimited)
+    #[arg(
+        long = "channel-pin-limit",
+        env = "CHANNEL_PIN_LIMIT",
+        default_value = "50"
+    )]
+    pub channel_pin_limit: u64,
+}
+
+#[derive(Clone, Parser, Debug, Default)]
+pub struct PurgeConfig {
+    /// How long soft deleted messages stay readable by moderators before being purged (0 keeps them)
+    #[arg(
+        long = "deleted-message-retention-secs",
+        env = "DELETED_MESSAGE_RETENTION_SECS",
+        default_value = "2592000"
+    )]
+    pub deleted_message_retention_secs: u64,
+
+    /// Wait between two purges of soft deleted and expired messages
+    #[arg(
+        long = "deleted-message-purge-interval-secs",
+        env = "DELETED_MESSAGE_PURGE_INTERVAL_SECS",
+        default_value = "3600"
+    )]
+    pub purge_interval_secs: u64,
+}
+
+#[derive(Clone, Parser, Debug, Default)]
+pub struct ThreadsConfig {
+    /// How long a thread stays without a reply before being archived (0 never archives)
+    #[arg(
+        long = "thread-archive-after-secs",
+        env = "THREAD_ARCHIVE_AFTER_SECS",
+        default_value = "604800"
+    )]
+    pub archive_after_secs: u64,
+
+    /// Wait between two archivings of idle threads
+    #[arg(
+        long = "thread-archive-interval-secs",
+        env = "THREAD_ARCHIVE_INTERVAL_SECS",
+        default_value = "3600"
+    )]
+    pub archive_interval_secs: u64,
+}
+
+#[derive(Clone, Parser, Debug, Default)]
+pub struct RetentionConfig {
+    /// How long messages are kept in channels without a custom retention (0 keeps them)
+    #[arg(
+        long = "message-retention-secs",
+        env = "MESSAGE_RETENTION_SECS",
+        default_value = "0"
+    )]
+    pub message_retention_secs: u64,
+
+    /// Wait between two deletions of expired messages (0 never deletes them)
+    #[arg(
+        long = "message-retention-interval-secs",
+        env = "MESSAGE_RETENTION_INTERVAL_SECS",
+        default_value = "3600"
+    )]
+    pub interval_secs: u64,
+}
+
+#[derive(Clone, Parser, Debug, Default)]
+pub struct ArchiveConfig {
+    /// Bucket receiving the messages past their retention before they are
+    /// deleted (empty deletes them without archiving; requires the `s3-archive` feature)
+    #[arg(long = "archive-s3-bucket", env = "ARCHIVE_S3_BUCKET", default_value = "")]
+    pub s3_bucket: String,
+
+    /// Endpoint of an S3-compatible storage, empty for AWS S3
+    #[arg(long = "archive-s3-endpoint", env = "ARCHIVE_S3_ENDPOINT", default_value = "")]
+    pub s3_endpoint: String,
+
+    #[arg(long = "archive-s3-region", env = "ARCHIVE_S3_REGION", default_value = "us-east-1")]
+    pub s3_region: String,
+
+    #[arg(
+        long = "archive-s3-access-key-id",
+        env = "ARCHIVE_S3_ACCESS_KEY_ID",
+        default_value = ""
+    )]
+    pub s3_access_key_id: String,
+
+    #[arg(
+        long = "archive-s3-secret-access-key",
+        env = "ARCHIVE_S3_SECRET_ACCESS_KEY",
+        default_value = ""
+    )]
+    pub s3_secret_access_key: String,
+
+    /// Prefix of the keys of the archived batches
+    #[arg(long = "archive-prefix", env = "ARCHIVE_PREFIX", default_value = "messages")]
+    pub prefix: String,
+}
+
+#[derive(Clone, Parser, Debug, Default)]
+pub struct AttachmentConfig {
+    /// Bucket the clients upload attachments to through presigned URLs
+    /// (empty disables uploads; requires the `s3-attachments` feature)
+    #[arg(long = "attachment-s3-bucket", env = "ATTACHMENT_S3_BUCKET", default_value = "")]
+    pub s3_bucket: String,
+
+    /// Endpoint of an S3-compatible storage, empty for AWS S3
+    #[arg(
+        long = "attachment-s3-endpoint",
+        env = "ATTACHMENT_S3_ENDPOINT",
+        default_value = ""
+    )]
+    pub s3_endpoint: String,
+
+    #[arg(
+        long = "attachment-s3-region",
+        env = "ATTACHMENT_S3_REGION",
+        default_value = "us-east-1"
+    )]
+    pub s3_region: String,
+
+    #[arg(
+        long = "attachment-s3-access-key-id",
+        env = "ATTACHMENT_S3_ACCESS_KEY_ID",
+        default_value = ""
+    )]
+    pub s3_access_key_id: String,
+
+    #[arg(
+        long = "attachment-s3-secret-access-key",
+        env = "ATTACHMENT_S3_SECRET_ACCESS_KEY",
+        default_value = ""
+    )]
+    pub s3_secret_access_key: String,
+
+    /// Base URL the bucket is served from, followed by the object key in the
+    /// URLs of posted attachments
+    #[arg(long = "attachment-public-url", env = "ATTACHMENT_PUBLIC_URL", default_value = "")]
+    pub public_url: String,
+
+    /// Attachments a message may carry (0 for unlimited)
+    #[arg(
+        long = "attachment-max-per-message",
+        env = "ATTACHMENT_MAX_PER_MESSAGE",
+        default_value = "10"
+    )]
+    pub max_per_message: usize,
+
+    /// Size of each attachment in bytes (0 for unlimited)
+    #[arg(
+        long = "attachment-max-size-bytes",
+        env = "ATTACHMENT_MAX_SIZE_BYTES",
+        default_value = "26214400"
+    )]
+    pub max_size_bytes: u64,
+
+    /// MIME types accepted, comma-separated, `image/*` accepting every image (any type when empty)
+    #[arg(
+        long = "attachment-allowed-content-types",
+        env = "ATTACHMENT_ALLOWED_CONTENT_TYPES",
+        value_delimiter = ','
+    )]
+    pub allowed_content_types: Vec<String>,
+
+    /// `host:port` of the clamd daemon scanning posted attachments (empty
+    /// leaves them unscanned; requires the `clamav` feature)
+    #[arg(
+        long = "attachment-clamav-address",
+        env = "ATTACHMENT_CLAMAV_ADDRESS",
+        default_value = ""
+    )]
+    pub clamav_address: String,
+
+    /// Longest a scan may take before the attachment is left unscanned
+    #[arg(
+        long = "attachment-scan-timeout-secs",
+        env = "ATTACHMENT_SCAN_TIMEOUT_SECS",
+        default_value = "30"
+    )]
+    pub scan_timeout_secs: u64,
+
+    /// Messages whose attachments are scanned at once
+    #[arg(
+        long = "attachment-scan-concurrency",
+        env = "ATTACHMENT_SCAN_CONCURRENCY",
+        default_value = "4"
+    )]
+    pub scan_concurrency: usize,
+
+    /// Service previewing image attachments, sent the URL of each new image
+    /// (empty posts images without preview; requires the `http-thumbnails` feature)
+    #[arg(long = "thumbnail-service-url", env = "THUMBNAIL_SERVICE_URL", default_value = "")]
+    pub thumbnail_service_url: String,
+
+    /// Longest a preview may take before the image is posted without one
+    #[arg(
+        long = "thumbnail-timeout-secs",
+        env = "THUMBNAIL_TIMEOUT_SECS",
+        default_value = "5"
+    )]
+    pub thumbnail_timeout_secs: u64,
+}
+
+#[derive(Clone, Parser, Debug, Default)]
+pub struct LinkPreviewConfig {
+    /// Preview the links of new messages into their embeds (requires the
+    /// `link-previews` feature)
+    #[arg(long = "link-previews-enabled", env = "LINK_PREVIEWS_ENABLED")]
+    pub enabled: bool,
+
+    /// Only preview links to these domains and their subdomains,
+    /// comma-separated (any domain when empty)
+    #[arg(
+        long = "link-preview-allowed-domains",
+        env = "LINK_PREVIEW_ALLOWED_DOMAINS",
+        value_delimiter = ','
+    )]
+    pub allowed_domains: Vec<String>,
+
+    /// Never preview links to these domains and their subdomains, comma-separated
+    #[arg(
+        long = "link-preview-denied-domains",
+        env = "LINK_PREVIEW_DENIED_DOMAINS",
+        value_delimiter = ','
+    )]
+    pub denied_domains: Vec<String>,
+
+    /// Longest a linked page may take to load before it is left without preview
+    #[arg(
+        long = "link-preview-timeout-secs",
+        env = "LINK_PREVIEW_TIMEOUT_SECS",
+        default_value = "5"
+    )]
+    pub timeout_secs: u64,
+
+    /// Bytes read from each linked page, where its metadata is looked for
+    #[arg(
+        long = "link-preview-max-page-bytes",
+        env = "LINK_PREVIEW_MAX_PAGE_BYTES",
+        default_value = "524288"
+    )]
+    pub max_page_bytes: usize,
+
+    /// Messages whose links are previewed at once
+    #[arg(
+        long = "link-preview-concurrency",
+        env = "LINK_PREVIEW_CONCURRENCY",
+        default_value = "4"
+    )]
+    pub concurrency: usize,
+}
+
+#[derive(Clone, Parser, Debug, Default)]
+pub struct MembersConfig {
+    /// Members service asked for the holders of the roles a new message
+    /// mentions (empty resolves them from the snapshot of channel members;
+    /// requires the `http-members` feature)
+    #[arg(long = "members-service-url", env = "MEMBERS_SERVICE_URL", default_value = "")]
+    pub members_service_url: String,
+
+    /// Longest the members service may take before role mentions notify nobody
+    #[arg(
+        long = "members-timeout-secs",
+        env = "MEMBERS_TIMEOUT_SECS",
+        default_value = "2"
+    )]
+    pub members_timeout_secs: u64,
+
+    /// Recipients past which a mention is published as one broadcast naming
+    /// its roles, for the notification service to expand (0 always lists
+    /// the recipients)
+    #[arg(
+        long = "mention-fanout-limit",
+        env = "MENTION_FANOUT_LIMIT",
+        default_value = "5000"
+    )]
+    pub mention_fanout_limit: usize,
+}
+
+#[derive(Clone, Parser, Debug, Default)]
+pub struct StreamingConfig {
+    /// Bandwidth of each streamed response, such as exports (0 for unlimited)
+    #[arg(
+        long = "stream-max-bytes-per-sec",
+        env = "STREAM_MAX_BYTES_PER_SEC",
+        default_value = "0"
+    )]
+    pub max_bytes_per_sec: u64,
+}
+
+#[derive(Clone, Parser, Debug, Default)]
+pub struct ExportConfig {
+    /// Hex-encoded 256-bit key sealing export download links; links are disabled when unset
+    #[arg(
+        long = "export-link-key",
+        env = "EXPORT_LINK_KEY",
+        default_value = "",
+        hide_default_value = true,
+        hide_env_values = true
+    )]
+    pub link_key: String,
+
+    /// How long export download links stay valid
+    #[arg(
+        long = "export-link-ttl-secs",
+        env = "EXPORT_LINK_TTL_SECS",
+        default_value = "3600"
+    )]
+    pub link_ttl_secs: u64,
+}
+
+#[derive(Clone, Parser, Debug, Default)]
+pub struct TrustSafetyConfig {
+    /// Messages a user may delete within the window before being reported to trust & safety (0 to disable)
+    #[arg(
+        long = "mass-deletion-threshold",
+        env = "MASS_DELETION_THRESHOLD",
+        default_value = "0"
+    )]
+    pub mass_deletion_threshold: u64,
+
+    #[arg(
+        long = "mass-deletion-window-secs",
+        env = "MASS_DELETION_WINDOW_SECS",
+        default_value = "600"
+    )]
+    pub mass_deletion_window_secs: u64,
+
+    /// Reasons moderators pick from, as `code=text` separated by `;` (any reason code when empty)
+    #[arg(
+        long = "moderation-reason-templates",
+        env = "MODERATION_REASON_TEMPLATES",
+        value_delimiter = ';'
+    )]
+    pub moderation_reason_templates: Vec<ModerationReasonTemplate>,
+}
+
+#[derive(Clone, Parser, Debug, Default)]
+pub struct SearchConfig {
+    #[arg(
+        long = "search-backend",
+        env = "SEARCH_BACKEND",
+        default_value = "mongo-text"
+    )]
+    pub backend: SearchBackend,
+
+    #[arg(
+        long = "elasticsearch-url",
+        env = "ELASTICSEARCH_URL",
+        default_value = "http://localhost:9200"
+    )]
+    pub elasticsearch_url: String,
+
+    #[arg(
+        long = "elasticsearch-index",
+        env = "ELASTICSEARCH_INDEX",
+        default_value = "messages"
+    )]
+    pub elasticsearch_index: String,
+
+    /// Basic auth credentials, not sent when the username is empty
+    #[arg(
+        long = "elasticsearch-username",
+        env = "ELASTICSEARCH_USERNAME",
+        default_value = ""
+    )]
+    pub elasticsearch_username: String,
+
+    #[arg(
+        long = "elasticsearch-password",
+        env = "ELASTICSEARCH_PASSWORD",
+        default_value = ""
+    )]
+    pub elasticsearch_password: String,
+
+    #[arg(
+        long = "meilisearch-url",
+        env = "MEILISEARCH_URL",
+        default_value = "http://localhost:7700"
+    )]
+    pub meilisearch_url: String,
+
+    #[arg(
+        long = "meilisearch-index",
+        env = "MEILISEARCH_INDEX",
+        default_value = "messages"
+    )]
+    pub meilisearch_index: String,
+
+    /// Not sent when empty
+    #[arg(
+        long = "meilisearch-api-key",
+        env = "MEILISEARCH_API_KEY",
+        default_value = ""
+    )]
+    pub meilisearch_api_key: String,
+
+    /// Directory of the embedded Tantivy index
+    #[arg(
+        long = "tantivy-index-path",
+        env = "TANTIVY_INDEX_PATH",
+        default_value = "data/search"
+    )]
+    pub tantivy_index_path: PathBuf,
+}
+
+/// Backend answering message searches
+#[derive(Clone, Debug, ValueEnum, Default, PartialEq, Eq)]
+pub enum SearchBackend {
+    /// `$text` index of the `messages` collection
+    #[default]
+    MongoText,
+    /// Elasticsearch index fed from message events, with fuzzy matching
+    /// (requires the `elasticsearch` feature)
+    Elasticsearch,
+    /// Meilisearch index fed from message events, with typo tolerance
+    /// (requires the `meilisearch` feature)
+    Meilisearch,
+    /// Tantivy index embedded in the process, fed from message events, for
+    /// single-node deployments (requires the `tantivy` feature)
+    Tantivy,
+}
 
 impl Config {
     /// Load routing configuration from YAML file
@@ -0,0 +1,189 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+use communities_core::domain::health::{entities::IsHealthy, port::HealthProbe};
+
+use futures::StreamExt;
+use lapin::{
+    Connection, ConnectionProperties, ExchangeKind,
+    options::{
+        BasicAckOptions, BasicConsumeOptions, BasicNackOptions, BasicQosOptions,
+        ExchangeDeclareOptions, QueueBindOptions, QueueDeclareOptions,
+    },
+    types::FieldTable,
+};
+use tokio::task::JoinHandle;
+
+use crate::consumer::DeliveryHandler;
+
+/// Delay before reconnecting once the connection to the broker is lost
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Queue consumed by a consumer, and how it is bound to the producer's exchange
+#[derive(Clone, Debug)]
+pub struct QueueBinding {
+    pub exchange: String,
+    pub routing_key: String,
+    pub queue: String,
+}
+
+/// Whether a consumer is connected to the broker, reported by the health check
+#[derive(Clone)]
+pub struct ConsumerStatus {
+    name: String,
+    connected: Arc<AtomicBool>,
+}
+
+impl ConsumerStatus {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            connected: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    fn set_connected(&self, connected: bool) {
+        self.connected.store(connected, Ordering::Relaxed);
+    }
+}
+
+#[async_trait::async_trait]
+impl HealthProbe for ConsumerStatus {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn probe(&self) -> IsHealthy {
+        IsHealthy::new(self.connected.load(Ordering::Relaxed))
+    }
+}
+
+/// Consumes the events of one queue from RabbitMQ, one at a time and in order.
+///
+/// Deliveries are acknowledged once handled. Deliveries that failed on a
+/// transient error are requeued, malformed ones are dropped.
+pub struct AmqpConsumer {
+    url: String,
+    binding: QueueBinding,
+    handler: Arc<dyn DeliveryHandler>,
+    status: ConsumerStatus,
+}
+
+impl AmqpConsumer {
+    pub fn new(
+        url: impl Into<String>,
+        binding: QueueBinding,
+        handler: impl DeliveryHandler,
+        status: ConsumerStatus,
+    ) -> Self {
+        Self {
+            url: url.into(),
+            binding,
+            handler: Arc::new(handler),
+            status,
+        }
+    }
+
+    /// Consume in the background, reconnecting whenever the connection is lost
+    pub fn spawn(self) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                let result = self.consume().await;
+                self.status.set_connected(false);
+                if let Err(e) = result {
+                    tracing::warn!(error = %e, queue = %self.binding.queue, "AMQP consumer stopped, reconnecting");
+                }
+                tokio::time::sleep(RECONNECT_DELAY).await;
+            }
+        })
+    }
+
+    async fn consume(&self) -> Result<(), lapin::Error> {
+        let connection = Connection::connect(&self.url, ConnectionProperties::default()).await?;
+        let channel = connection.create_channel().await?;
+        let binding = &self.binding;
+
+        channel
+            .exchange_declare(
+                &binding.exchange,
+                ExchangeKind::Topic,
+                ExchangeDeclareOptions {
+                    durable: true,
+                    ..Default::default()
+                },
+                FieldTable::default(),
+            )
+            .await?;
+        channel
+            .queue_declare(
+                &binding.queue,
+                QueueDeclareOptions {
+                    durable: true,
+                    ..Default::default()
+                },
+                FieldTable::default(),
+            )
+            .await?;
+        channel
+            .queue_bind(
+                &binding.queue,
+                &binding.exchange,
+                &binding.routing_key,
+                QueueBindOptions::default(),
+                FieldTable::default(),
+            )
+            .await?;
+        // One delivery at a time: a channel deletion may touch many messages,
+        // and membership changes are applied in the order they were published
+        channel.basic_qos(1, BasicQosOptions::default()).await?;
+
+        let mut consumer = channel
+            .basic_consume(
+                &binding.queue,
+                "message-service",
+                BasicConsumeOptions::default(),
+                FieldTable::default(),
+            )
+            .await?;
+        self.status.set_connected(true);
+        tracing::info!(queue = %binding.queue, "AMQP consumer started");
+
+        while let Some(delivery) = consumer.next().await {
+            let delivery = delivery?;
+
+            match self
+                .handler
+                .handle_delivery(delivery.routing_key.as_str(), &delivery.data)
+                .await
+            {
+                Ok(_) => delivery.ack(BasicAckOptions::default()).await?,
+                Err(e) if e.is_retryable() => {
+                    tracing::warn!(error = %e, "failed to handle delivery, requeueing");
+                    tokio::time::sleep(RECONNECT_DELAY).await;
+                    delivery
+                        .nack(BasicNackOptions {
+                            requeue: true,
+                            ..Default::default()
+                        })
+                        .await?
+                }
+                Err(e) => {
+                    tracing::error!(error = %e, "dropping delivery");
+                    delivery
+                        .nack(BasicNackOptions {
+                            requeue: false,
+                            ..Default::default()
+                        })
+                        .await?
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,72 @@
+use communities_core::domain::message::{entities::ChannelId, ports::MessageService};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::{
+    consumer::{ConsumerError, DeliveryHandler},
+    http::server::AppState,
+};
+
+/// `channel.deleted`, published by the channels service on `beep.channels`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ChannelDeletedEvent {
+    pub channel_id: Uuid,
+}
+
+impl ChannelDeletedEvent {
+    pub fn from_payload(payload: &[u8]) -> Result<Self, ConsumerError> {
+        serde_json::from_slice(payload)
+            .map_err(|e| ConsumerError::InvalidPayload { msg: e.to_string() })
+    }
+}
+
+/// Soft deletes the messages of deleted channels, so they don't outlive their
+/// channel, except those under a legal hold
+#[derive(Clone)]
+pub struct ChannelDeletedHandler {
+    state: AppState,
+}
+
+impl ChannelDeletedHandler {
+    pub fn new(state: AppState) -> Self {
+        Self { state }
+    }
+
+    /// Handle one delivery, returning how many messages were deleted.
+    ///
+    /// Redelivering the same event is harmless: already deleted messages are skipped.
+    #[tracing::instrument(skip_all)]
+    pub async fn handle(&self, payload: &[u8]) -> Result<u64, ConsumerError> {
+        let event = ChannelDeletedEvent::from_payload(payload)?;
+        let channel_id = ChannelId::from(event.channel_id);
+
+        let purge = self
+            .state
+            .service
+            .delete_channel_messages(&channel_id)
+            .await?;
+        self.state.list_cache.invalidate_channel(channel_id);
+
+        if !purge.held_by.is_empty() {
+            // kept as the record of the holds that preserved data
+            tracing::warn!(
+                %channel_id,
+                held_by = ?purge.held_by,
+                "kept messages of a deleted channel under legal hold"
+            );
+        }
+        tracing::info!(%channel_id, deleted = purge.deleted, "deleted the messages of a deleted channel");
+        Ok(purge.deleted)
+    }
+}
+
+#[async_trait::async_trait]
+impl DeliveryHandler for ChannelDeletedHandler {
+    async fn handle_delivery(
+        &self,
+        _routing_key: &str,
+        payload: &[u8],
+    ) -> Result<(), ConsumerError> {
+        self.handle(payload).await.map(|_| ())
+    }
+}
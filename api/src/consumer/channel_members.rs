@@ -0,0 +1,92 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use communities_core::domain::{
+    membership::{entities::ChannelMember, ports::ChannelMembership},
+    message::entities::{ChannelId, UserId},
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::consumer::{ConsumerError, DeliveryHandler};
+
+/// `member.joined`, `member.updated` and `member.left`, published by the
+/// communities service on `beep.communities`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ChannelMemberEvent {
+    pub channel_id: Uuid,
+    pub user_id: Uuid,
+    /// Every role of the member after the change; empty when they left
+    #[serde(default)]
+    pub role_ids: Vec<Uuid>,
+    pub occurred_at: DateTime<Utc>,
+}
+
+impl ChannelMemberEvent {
+    pub fn from_payload(payload: &[u8]) -> Result<Self, ConsumerError> {
+        serde_json::from_slice(payload)
+            .map_err(|e| ConsumerError::InvalidPayload { msg: e.to_string() })
+    }
+}
+
+/// Keeps the snapshot of the members of channels up to date, so mentions of
+/// `@everyone` and roles are resolved without calling the communities service
+#[derive(Clone)]
+pub struct ChannelMembersHandler {
+    membership: Arc<dyn ChannelMembership>,
+}
+
+impl ChannelMembersHandler {
+    pub fn new(membership: Arc<dyn ChannelMembership>) -> Self {
+        Self { membership }
+    }
+
+    /// Apply one delivery, told apart by the last segment of its routing key.
+    ///
+    /// Redelivered and out of order events are harmless: changes older than
+    /// the last one applied to the member are skipped.
+    #[tracing::instrument(skip_all, fields(routing_key = %routing_key))]
+    pub async fn handle(&self, routing_key: &str, payload: &[u8]) -> Result<(), ConsumerError> {
+        let event = ChannelMemberEvent::from_payload(payload)?;
+        let channel_id = ChannelId::from(event.channel_id);
+        let user_id = UserId(event.user_id);
+
+        let applied = match routing_key.rsplit('.').next() {
+            Some("joined" | "updated") => {
+                self.membership
+                    .upsert_member(&ChannelMember {
+                        channel_id,
+                        user_id,
+                        role_ids: event.role_ids,
+                        updated_at: event.occurred_at,
+                    })
+                    .await?
+            }
+            Some("left") => {
+                self.membership
+                    .remove_member(&channel_id, &user_id, event.occurred_at)
+                    .await?
+            }
+            _ => {
+                return Err(ConsumerError::InvalidPayload {
+                    msg: format!("unknown membership event {routing_key}"),
+                });
+            }
+        };
+        if !applied {
+            tracing::debug!(%channel_id, %user_id, "skipped an outdated membership change");
+        }
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl DeliveryHandler for ChannelMembersHandler {
+    async fn handle_delivery(
+        &self,
+        routing_key: &str,
+        payload: &[u8],
+    ) -> Result<(), ConsumerError> {
+        self.handle(routing_key, payload).await
+    }
+}
@@ -0,0 +1,41 @@
+//! Events published by the other services and consumed from RabbitMQ.
+//!
+//! Each consumer owns a durable queue bound to the producer's exchange, named
+//! `messages.<domain>.<action>` (see `docs/src/api/rabbitmq.md`).
+
+pub mod amqp;
+pub mod channel_deleted;
+pub mod channel_members;
+
+pub use amqp::{AmqpConsumer, ConsumerStatus, QueueBinding};
+pub use channel_deleted::{ChannelDeletedEvent, ChannelDeletedHandler};
+pub use channel_members::{ChannelMemberEvent, ChannelMembersHandler};
+
+use communities_core::domain::common::CoreError;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ConsumerError {
+    /// The delivery can never be handled, it is dropped
+    #[error("Invalid payload: {msg}")]
+    InvalidPayload { msg: String },
+    /// The delivery may be handled later, it is requeued
+    #[error(transparent)]
+    Core(#[from] CoreError),
+}
+
+impl ConsumerError {
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, ConsumerError::Core(_))
+    }
+}
+
+/// Handles the deliveries of the queue of one consumer
+#[async_trait::async_trait]
+pub trait DeliveryHandler: Send + Sync + 'static {
+    async fn handle_delivery(
+        &self,
+        routing_key: &str,
+        payload: &[u8],
+    ) -> Result<(), ConsumerError>;
+}
@@ -0,0 +1,42 @@
+use communities_core::domain::message::entities::UserErasure;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::http::server::public_id::PublicId;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct UserErasureResponse {
+    pub user_id: Uuid,
+    /// Messages anonymized, including those deleted before
+    pub erased: u64,
+    /// Channels the erased messages were posted in
+    #[schema(value_type = Vec<String>)]
+    pub channels: Vec<PublicId>,
+    /// References of the legal holds that kept messages of the user
+    pub held_by: Vec<String>,
+}
+
+impl UserErasureResponse {
+    pub fn new(user_id: Uuid, erasure: UserErasure) -> Self {
+        Self {
+            user_id,
+            erased: erasure.erased,
+            channels: erasure
+                .channels
+                .into_iter()
+                .map(|channel_id| channel_id.0.into())
+                .collect(),
+            held_by: erasure.held_by,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExportUserParams {
+    /// Also export the messages the user deleted, for requests that legally
+    /// cover every message still stored
+    #[serde(default)]
+    pub include_deleted: bool,
+}
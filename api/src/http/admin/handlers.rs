@@ -0,0 +1,134 @@
+use axum::{
+    BoxError, Extension,
+    body::Body,
+    extract::{Path, Query, State},
+    http::header,
+    response::{IntoResponse, Response as AxumResponse},
+};
+use communities_core::domain::message::{entities::UserId, ports::MessageService};
+use futures::StreamExt;
+use uuid::Uuid;
+
+use crate::http::{
+    admin::dto::{ExportUserParams, UserErasureResponse},
+    messages::dto::MessageResponse,
+    server::{
+        ApiError, AppState, Response,
+        authorization::{Permission, Resource},
+        middleware::auth::entities::UserIdentity,
+        public_id::sync_scope_public_ids,
+        response::NDJSON_CONTENT_TYPE,
+    },
+};
+
+/// Administration of a user is reserved to whoever moderates their messages
+async fn authorize_user(state: &AppState, user_id: Uuid, target: Uuid) -> Result<(), ApiError> {
+    let allowed = state
+        .authz
+        .check(user_id, Permission::ManageMessages, Resource::User(target))
+        .await
+        .map_err(|_| ApiError::InternalServerError)?;
+    if !allowed {
+        return Err(ApiError::Forbidden);
+    }
+    Ok(())
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/users/{user_id}/erase",
+    tag = "admin",
+    params(
+        ("user_id" = String, Path, description = "User whose messages are erased")
+    ),
+    responses(
+        (status = 200, description = "Messages of the user erased, except those under a legal hold", body = UserErasureResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 500, description = "Internal message error")
+    )
+)]
+#[tracing::instrument(skip(state, user_identity))]
+pub async fn erase_user(
+    State(state): State<AppState>,
+    Extension(user_identity): Extension<UserIdentity>,
+    Path(user_id): Path<Uuid>,
+) -> Result<Response<UserErasureResponse>, ApiError> {
+    authorize_user(&state, user_identity.user_id, user_id).await?;
+
+    let erasure = state
+        .service
+        .erase_user(&UserId::from(user_id), &UserId::from(user_identity.user_id))
+        .await?;
+    for channel_id in &erasure.channels {
+        state.list_cache.invalidate_channel(*channel_id);
+    }
+    // The request itself is the audit trail of the erasure
+    tracing::info!(
+        erased = erasure.erased,
+        held_by = ?erasure.held_by,
+        erased_by = %user_identity.user_id,
+        "user erased"
+    );
+
+    Ok(Response::ok(UserErasureResponse::new(user_id, erasure)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/users/{user_id}/export",
+    tag = "admin",
+    params(
+        ("user_id" = String, Path, description = "User whose messages are exported"),
+        ExportUserParams
+    ),
+    responses(
+        (status = 200, description = "Every message of the user across channels oldest first, one JSON object per line", body = MessageResponse, content_type = "application/x-ndjson"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 500, description = "Internal message error")
+    )
+)]
+#[tracing::instrument(skip(state, user_identity, params))]
+pub async fn export_user(
+    State(state): State<AppState>,
+    Extension(user_identity): Extension<UserIdentity>,
+    Path(user_id): Path<Uuid>,
+    Query(params): Query<ExportUserParams>,
+) -> Result<AxumResponse, ApiError> {
+    authorize_user(&state, user_identity.user_id, user_id).await?;
+
+    let messages = state
+        .service
+        .export_user_messages(&UserId::from(user_id), params.include_deleted)
+        .await?;
+    // The request itself is the audit trail of the export
+    tracing::info!(
+        include_deleted = params.include_deleted,
+        exported_by = %user_identity.user_id,
+        "user export started"
+    );
+
+    // Once the first line is sent, a failure can only abort the body: clients
+    // see a truncated export rather than an error status. The lines are
+    // serialized once the request is over, so they get the IDs of the state
+    let id_obfuscation = state.id_obfuscation.clone();
+    let lines = messages.map(move |message| {
+        let message =
+            message.inspect_err(|e| tracing::error!(error = %e, "user export aborted"))?;
+        let mut line = sync_scope_public_ids(&id_obfuscation, || {
+            serde_json::to_vec(&MessageResponse::from(message))
+        })?;
+        line.push(b'\n');
+        Ok::<_, BoxError>(line)
+    });
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, NDJSON_CONTENT_TYPE),
+            (header::CONTENT_DISPOSITION, "attachment"),
+        ],
+        Body::from_stream(state.stream_throttle.throttle(lines)),
+    )
+        .into_response())
+}
@@ -0,0 +1,19 @@
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+use crate::http::{
+    admin::handlers::{__path_erase_user, __path_export_user, erase_user, export_user},
+    server::{AppState, channel_access::route_authorized_by_handler},
+};
+
+/// Administration of users, such as the erasure or export of their messages
+pub fn admin_routes() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new()
+        .routes(route_authorized_by_handler(
+            "ManageMessages on the user",
+            routes!(erase_user),
+        ))
+        .routes(route_authorized_by_handler(
+            "ManageMessages on the user",
+            routes!(export_user),
+        ))
+}
@@ -0,0 +1,40 @@
+use chrono::{DateTime, Utc};
+use communities_core::domain::message::entities::PresignedAttachment;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::http::server::public_id::PublicId;
+
+/// File to be attached to a message of the channel
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct PresignAttachmentRequest {
+    #[schema(value_type = String)]
+    pub channel_id: PublicId,
+    pub name: String,
+    pub content_type: String,
+    /// Size of the file in bytes
+    pub size: u64,
+}
+
+/// Where to upload the file before posting `attachment_id` with a message
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct PresignedAttachmentResponse {
+    pub attachment_id: Uuid,
+    pub upload_url: String,
+    /// HTTP method of the upload, always `PUT`
+    pub method: String,
+    /// The upload URL is refused after this date
+    pub expires_at: DateTime<Utc>,
+}
+
+impl From<PresignedAttachment> for PresignedAttachmentResponse {
+    fn from(presigned: PresignedAttachment) -> Self {
+        Self {
+            attachment_id: presigned.upload.id.0,
+            upload_url: presigned.upload_url,
+            method: "PUT".to_string(),
+            expires_at: presigned.upload.expires_at,
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use axum::{Extension, Json, extract::State};
+use communities_core::domain::message::{
+    entities::{AuthorId, ChannelId, PresignAttachmentInput},
+    ports::MessageService,
+};
+
+use crate::http::{
+    attachments::dto::{PresignAttachmentRequest, PresignedAttachmentResponse},
+    server::{
+        ApiError, AppState, Response, authorization::Permission, channel_access::authorize_channel,
+        middleware::auth::entities::UserIdentity,
+    },
+};
+
+#[utoipa::path(
+    post,
+    path = "/attachments/presign",
+    tag = "attachments",
+    request_body = PresignAttachmentRequest,
+    responses(
+        (status = 201, description = "Upload registered; `PUT` the file to the URL, then post the attachment ID with a message", body = PresignedAttachmentResponse),
+        (status = 400, description = "Bad request - Invalid name, content type or size"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 500, description = "Internal message error"),
+        (status = 503, description = "Attachment uploads are not configured")
+    )
+)]
+#[tracing::instrument(skip(state, user_identity, request))]
+pub async fn presign_attachment(
+    State(state): State<AppState>,
+    Extension(user_identity): Extension<UserIdentity>,
+    Json(request): Json<PresignAttachmentRequest>,
+) -> Result<Response<PresignedAttachmentResponse>, ApiError> {
+    let channel_id = ChannelId::from(request.channel_id.0);
+    authorize_channel(
+        state.authz.as_ref(),
+        user_identity.user_id,
+        Permission::SendMessages,
+        channel_id,
+    )
+    .await?;
+
+    let presigned = state
+        .service
+        .presign_attachment(PresignAttachmentInput {
+            channel_id,
+            uploader_id: AuthorId::from(user_identity.user_id),
+            name: request.name,
+            content_type: request.content_type,
+            size: request.size,
+        })
+        .await?;
+    Ok(Response::created(presigned.into()))
+}
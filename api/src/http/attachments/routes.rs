@@ -0,0 +1,14 @@
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+use crate::http::{
+    attachments::handlers::{__path_presign_attachment, presign_attachment},
+    server::{AppState, channel_access::route_authorized_by_handler},
+};
+
+/// Uploads of the files attached to messages
+pub fn attachment_routes() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new().routes(route_authorized_by_handler(
+        "SendMessages on the channel of the body",
+        routes!(presign_attachment),
+    ))
+}
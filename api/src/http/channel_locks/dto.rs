@@ -0,0 +1,38 @@
+use chrono::{DateTime, Utc};
+use communities_core::domain::message::entities::ChannelWriteLock;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::http::server::public_id::PublicId;
+
+/// Lock to take on the writes of a channel
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct LockChannelWritesRequest {
+    /// What runs on the channel, e.g. the name of the migration or import
+    pub reason: String,
+    /// How long the lock holds unless released earlier, up to a day
+    pub duration_secs: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ChannelWriteLockResponse {
+    #[schema(value_type = String)]
+    pub channel_id: PublicId,
+    pub reason: String,
+    pub locked_by: Uuid,
+    pub locked_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl From<ChannelWriteLock> for ChannelWriteLockResponse {
+    fn from(lock: ChannelWriteLock) -> Self {
+        Self {
+            channel_id: lock.channel_id.0.into(),
+            reason: lock.reason,
+            locked_by: lock.locked_by.0,
+            locked_at: lock.locked_at,
+            expires_at: lock.expires_at,
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use axum::{Extension, Json, extract::State};
+use communities_core::domain::message::{
+    entities::{LockChannelWritesInput, UserId},
+    ports::MessageService,
+};
+
+use crate::http::{
+    channel_locks::dto::{ChannelWriteLockResponse, LockChannelWritesRequest},
+    server::{
+        ApiError, AppState, Response, channel_access::AuthorizedChannel,
+        middleware::auth::entities::UserIdentity,
+    },
+};
+
+#[utoipa::path(
+    get,
+    path = "/channels/{channel_id}/write-lock",
+    tag = "channel-locks",
+    params(
+        ("channel_id" = String, Path, description = "Channel ID")
+    ),
+    responses(
+        (status = 200, description = "Active write lock of the channel", body = ChannelWriteLockResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "The channel is not locked"),
+        (status = 500, description = "Internal message error")
+    )
+)]
+#[tracing::instrument(skip(state), fields(channel_id = %channel_id))]
+pub async fn get_channel_write_lock(
+    State(state): State<AppState>,
+    AuthorizedChannel(channel_id): AuthorizedChannel,
+) -> Result<Response<ChannelWriteLockResponse>, ApiError> {
+    let lock = state
+        .service
+        .channel_write_lock(&channel_id)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    Ok(Response::ok(lock.into()))
+}
+
+#[utoipa::path(
+    put,
+    path = "/channels/{channel_id}/write-lock",
+    tag = "channel-locks",
+    params(
+        ("channel_id" = String, Path, description = "Channel ID")
+    ),
+    request_body = LockChannelWritesRequest,
+    responses(
+        (status = 200, description = "Writes to the channel locked, replacing any previous lock", body = ChannelWriteLockResponse),
+        (status = 400, description = "Bad request - Duration out of bounds"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 500, description = "Internal message error")
+    )
+)]
+#[tracing::instrument(skip(state, user_identity, request), fields(channel_id = %channel_id))]
+pub async fn lock_channel_writes(
+    State(state): State<AppState>,
+    AuthorizedChannel(channel_id): AuthorizedChannel,
+    Extension(user_identity): Extension<UserIdentity>,
+    Json(request): Json<LockChannelWritesRequest>,
+) -> Result<Response<ChannelWriteLockResponse>, ApiError> {
+    let duration = i64::try_from(request.duration_secs)
+        .ok()
+        .and_then(chrono::Duration::try_seconds)
+        .ok_or_else(|| ApiError::BadRequest {
+            msg: "Lock duration is too long".to_string(),
+        })?;
+    let lock = state
+        .service
+        .lock_channel_writes(LockChannelWritesInput {
+            channel_id,
+            reason: request.reason,
+            locked_by: UserId::from(user_identity.user_id),
+            duration,
+        })
+        .await?;
+    tracing::info!(reason = %lock.reason, expires_at = %lock.expires_at, "channel writes locked");
+
+    Ok(Response::ok(lock.into()))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/channels/{channel_id}/write-lock",
+    tag = "channel-locks",
+    params(
+        ("channel_id" = String, Path, description = "Channel ID")
+    ),
+    responses(
+        (status = 200, description = "Writes to the channel unlocked"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 500, description = "Internal message error")
+    )
+)]
+#[tracing::instrument(skip(state), fields(channel_id = %channel_id))]
+pub async fn unlock_channel_writes(
+    State(state): State<AppState>,
+    AuthorizedChannel(channel_id): AuthorizedChannel,
+) -> Result<Response<()>, ApiError> {
+    state.service.unlock_channel_writes(&channel_id).await?;
+    tracing::info!("channel writes unlocked");
+
+    Ok(Response::deleted(()))
+}
@@ -0,0 +1,21 @@
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+use crate::http::{
+    channel_locks::handlers::{
+        __path_get_channel_write_lock, __path_lock_channel_writes, __path_unlock_channel_writes,
+        get_channel_write_lock, lock_channel_writes, unlock_channel_writes,
+    },
+    server::{AppState, authorization::Permission, channel_access::route_with_permission},
+};
+
+/// Locks suspending the writes to a channel while a migration or an import runs on it
+pub fn channel_lock_routes() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new().routes(route_with_permission(
+        Permission::ManageChannels,
+        routes!(
+            get_channel_write_lock,
+            lock_channel_writes,
+            unlock_channel_writes
+        ),
+    ))
+}
@@ -0,0 +1,38 @@
+use communities_core::domain::message::entities::ChannelSettings;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::http::server::public_id::PublicId;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ChannelSettingsResponse {
+    #[schema(value_type = String)]
+    pub channel_id: PublicId,
+    /// Emoji members may react with, absent when any emoji is allowed
+    pub allowed_reactions: Option<Vec<String>>,
+    /// Seconds messages are kept, `0` forever; absent when the default retention applies
+    pub retention_secs: Option<u64>,
+}
+
+/// Emoji allowed as reactions in a channel; `null` allows any emoji again
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct SetAllowedReactionsRequest {
+    pub allowed_reactions: Option<Vec<String>>,
+}
+
+/// How long messages of a channel are kept, `0` forever; `null` reverts to
+/// the default retention
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct SetRetentionRequest {
+    pub retention_secs: Option<u64>,
+}
+
+impl From<ChannelSettings> for ChannelSettingsResponse {
+    fn from(settings: ChannelSettings) -> Self {
+        Self {
+            channel_id: settings.channel_id.0.into(),
+            allowed_reactions: settings.allowed_reactions,
+            retention_secs: settings.retention_secs,
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use axum::{Json, extract::State};
+use communities_core::domain::message::ports::MessageService;
+
+use crate::http::{
+    channel_settings::dto::{
+        ChannelSettingsResponse, SetAllowedReactionsRequest, SetRetentionRequest,
+    },
+    server::{ApiError, AppState, Response, channel_access::AuthorizedChannel},
+};
+
+#[utoipa::path(
+    get,
+    path = "/channels/{channel_id}/settings",
+    tag = "channel_settings",
+    params(
+        ("channel_id" = String, Path, description = "Channel ID")
+    ),
+    responses(
+        (status = 200, description = "Settings of the channel", body = ChannelSettingsResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 500, description = "Internal message error")
+    )
+)]
+#[tracing::instrument(skip(state), fields(channel_id = %channel_id))]
+pub async fn get_channel_settings(
+    State(state): State<AppState>,
+    AuthorizedChannel(channel_id): AuthorizedChannel,
+) -> Result<Response<ChannelSettingsResponse>, ApiError> {
+    let settings = state.service.channel_settings(&channel_id).await?;
+    Ok(Response::ok(settings.into()))
+}
+
+#[utoipa::path(
+    put,
+    path = "/channels/{channel_id}/settings/reactions",
+    tag = "channel_settings",
+    params(
+        ("channel_id" = String, Path, description = "Channel ID")
+    ),
+    request_body = SetAllowedReactionsRequest,
+    responses(
+        (status = 200, description = "Allowed reactions updated", body = ChannelSettingsResponse),
+        (status = 400, description = "Invalid emoji, or too many of them"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 500, description = "Internal message error")
+    )
+)]
+#[tracing::instrument(skip(state, request), fields(channel_id = %channel_id))]
+pub async fn set_allowed_reactions(
+    State(state): State<AppState>,
+    AuthorizedChannel(channel_id): AuthorizedChannel,
+    Json(request): Json<SetAllowedReactionsRequest>,
+) -> Result<Response<ChannelSettingsResponse>, ApiError> {
+    let settings = state
+        .service
+        .set_allowed_reactions(&channel_id, request.allowed_reactions)
+        .await?;
+    Ok(Response::ok(settings.into()))
+}
+
+#[utoipa::path(
+    put,
+    path = "/channels/{channel_id}/settings/retention",
+    tag = "channel_settings",
+    params(
+        ("channel_id" = String, Path, description = "Channel ID")
+    ),
+    request_body = SetRetentionRequest,
+    responses(
+        (status = 200, description = "Retention updated", body = ChannelSettingsResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 500, description = "Internal message error")
+    )
+)]
+#[tracing::instrument(skip(state, request), fields(channel_id = %channel_id))]
+pub async fn set_channel_retention(
+    State(state): State<AppState>,
+    AuthorizedChannel(channel_id): AuthorizedChannel,
+    Json(request): Json<SetRetentionRequest>,
+) -> Result<Response<ChannelSettingsResponse>, ApiError> {
+    let settings = state
+        .service
+        .set_channel_retention(&channel_id, request.retention_secs)
+        .await?;
+    Ok(Response::ok(settings.into()))
+}
@@ -0,0 +1,27 @@
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+use crate::http::{
+    channel_settings::handlers::{
+        __path_get_channel_settings, __path_set_allowed_reactions, __path_set_channel_retention,
+        get_channel_settings, set_allowed_reactions, set_channel_retention,
+    },
+    server::{AppState, authorization::Permission, channel_access::route_with_permission},
+};
+
+/// Settings of channels, such as the emoji allowed as reactions or the
+/// retention of messages
+pub fn channel_settings_routes() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new()
+        .routes(route_with_permission(
+            Permission::ViewChannels,
+            routes!(get_channel_settings),
+        ))
+        .routes(route_with_permission(
+            Permission::ManageChannels,
+            routes!(set_allowed_reactions),
+        ))
+        .routes(route_with_permission(
+            Permission::ManageChannels,
+            routes!(set_channel_retention),
+        ))
+}
@@ -0,0 +1,21 @@
+use async_graphql_axum::{GraphQLRequest, GraphQLResponse};
+use axum::{Extension, extract::State};
+
+use crate::http::{
+    graphql::MessageSchema,
+    server::{AppState, middleware::auth::entities::UserIdentity},
+};
+
+/// Execute a GraphQL request on behalf of the authenticated user
+#[tracing::instrument(skip_all)]
+pub async fn graphql(
+    State(state): State<AppState>,
+    Extension(schema): Extension<MessageSchema>,
+    Extension(user_identity): Extension<UserIdentity>,
+    request: GraphQLRequest,
+) -> GraphQLResponse {
+    schema
+        .execute(request.into_inner().data(state).data(user_identity))
+        .await
+        .into()
+}
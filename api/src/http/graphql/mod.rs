@@ -0,0 +1,11 @@
+//! GraphQL API over the message service, served at `/graphql`.
+//!
+//! Resolvers enforce the same authorization rules as the REST handlers and
+//! report failures with the REST error codes in the `code` extension.
+
+pub mod handlers;
+pub mod routes;
+pub mod schema;
+pub mod types;
+
+pub use schema::{MessageSchema, build_schema};
@@ -0,0 +1,14 @@
+use axum::{Extension, routing::post};
+use utoipa_axum::router::OpenApiRouter;
+
+use crate::http::{
+    graphql::{build_schema, handlers::graphql},
+    server::AppState,
+};
+
+/// `POST /graphql`, left out of the OpenAPI document (the schema describes itself)
+pub fn graphql_routes() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new()
+        .route("/graphql", post(graphql))
+        .layer(Extension(build_schema()))
+}
@@ -0,0 +1,224 @@
+use async_graphql::{Context, EmptySubscription, ErrorExtensions, Object, Schema};
+use communities_core::domain::{
+    common::{GetCursorPaginated, GetMessagesByCursor, MessageBound},
+    message::{
+        entities::{AuthorId, ChannelId, CreateMessageRequest, MessageId, UpdateMessageRequest},
+        ports::MessageService,
+    },
+};
+
+use crate::http::{
+    graphql::types::{EditMessageInput, MessageObject, MessagePageObject, SendMessageInput},
+    server::{
+        ApiError, AppState, authorization::Permission, channel_access::authorize_channel,
+        middleware::auth::entities::UserIdentity, public_id::PublicId,
+    },
+};
+
+pub type MessageSchema = Schema<QueryRoot, MutationRoot, EmptySubscription>;
+
+/// Bounds on the shape of accepted queries
+const MAX_DEPTH: usize = 8;
+const MAX_COMPLEXITY: usize = 256;
+
+pub fn build_schema() -> MessageSchema {
+    Schema::build(QueryRoot, MutationRoot, EmptySubscription)
+        .limit_depth(MAX_DEPTH)
+        .limit_complexity(MAX_COMPLEXITY)
+        .finish()
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// A message, if the user can view its channel
+    async fn message(
+        &self,
+        ctx: &Context<'_>,
+        id: PublicId,
+    ) -> async_graphql::Result<MessageObject> {
+        let (state, user) = request_data(ctx)?;
+
+        let message = state
+            .service
+            .get_message(&MessageId::from(id.0))
+            .await
+            .map_err(ApiError::from)
+            .map_err(graphql_error)?;
+        authorize(state, user, Permission::ViewChannels, message.channel_id)
+            .await
+            .map_err(graphql_error)?;
+
+        Ok(message.into())
+    }
+
+    /// Messages of a channel, newest first, optionally older than `before`
+    /// and newer than `after` (message ids or RFC3339 timestamps)
+    async fn messages(
+        &self,
+        ctx: &Context<'_>,
+        channel_id: PublicId,
+        cursor: Option<String>,
+        before: Option<String>,
+        after: Option<String>,
+        #[graphql(default = 20)] limit: u32,
+    ) -> async_graphql::Result<MessagePageObject> {
+        let (state, user) = request_data(ctx)?;
+        let channel_id = ChannelId::from(channel_id.0);
+
+        authorize(state, user, Permission::ViewChannels, channel_id)
+            .await
+            .map_err(graphql_error)?;
+
+        let query = GetMessagesByCursor {
+            pagination: GetCursorPaginated { cursor, limit },
+            before: parse_bound(before.as_deref()).map_err(graphql_error)?,
+            after: parse_bound(after.as_deref()).map_err(graphql_error)?,
+        };
+        let page = state
+            .service
+            .list_messages_by_cursor(&channel_id, &query)
+            .await
+            .map_err(ApiError::from)
+            .map_err(graphql_error)?;
+
+        Ok(page.into())
+    }
+}
+
+pub struct MutationRoot;
+
+#[Object]
+impl MutationRoot {
+    async fn send_message(
+        &self,
+        ctx: &Context<'_>,
+        input: SendMessageInput,
+    ) -> async_graphql::Result<MessageObject> {
+        let (state, user) = request_data(ctx)?;
+        ensure_writable(state).map_err(graphql_error)?;
+        let channel_id = ChannelId::from(input.channel_id.0);
+
+        authorize(state, user, Permission::SendMessages, channel_id)
+            .await
+            .map_err(graphql_error)?;
+
+        let request = CreateMessageRequest {
+            channel_id,
+            content: input.content,
+            reply_to_message_id: input.reply_to_message_id.map(|id| MessageId::from(id.0)),
+            attachments: vec![],
+            nonce: input.nonce,
+            expires_at: input.expires_at,
+        };
+        let message = state
+            .service
+            .create_message(request.into_input(AuthorId::from(user.user_id)))
+            .await
+            .map_err(ApiError::from)
+            .map_err(graphql_error)?;
+        state.list_cache.invalidate_channel(message.channel_id);
+
+        Ok(message.into())
+    }
+
+    /// Only the author can edit a message
+    async fn edit_message(
+        &self,
+        ctx: &Context<'_>,
+        input: EditMessageInput,
+    ) -> async_graphql::Result<MessageObject> {
+        let (state, user) = request_data(ctx)?;
+        ensure_writable(state).map_err(graphql_error)?;
+        let message_id = MessageId::from(input.id.0);
+
+        ensure_author(state, user, &message_id)
+            .await
+            .map_err(graphql_error)?;
+
+        let request = UpdateMessageRequest {
+            content: input.content,
+            is_pinned: input.is_pinned,
+        };
+        let message = state
+            .service
+            .update_message(request.into_input(message_id))
+            .await
+            .map_err(ApiError::from)
+            .map_err(graphql_error)?;
+        state.list_cache.invalidate_channel(message.channel_id);
+
+        Ok(message.into())
+    }
+
+    /// Only the author can delete a message
+    async fn delete_message(&self, ctx: &Context<'_>, id: PublicId) -> async_graphql::Result<bool> {
+        let (state, user) = request_data(ctx)?;
+        ensure_writable(state).map_err(graphql_error)?;
+        let message_id = MessageId::from(id.0);
+
+        let channel_id = ensure_author(state, user, &message_id)
+            .await
+            .map_err(graphql_error)?;
+        state
+            .service
+            .delete_message(&message_id)
+            .await
+            .map_err(ApiError::from)
+            .map_err(graphql_error)?;
+        state.list_cache.invalidate_channel(channel_id);
+
+        Ok(true)
+    }
+}
+
+/// State and caller identity, attached to each request by the handler
+fn request_data<'a>(ctx: &Context<'a>) -> async_graphql::Result<(&'a AppState, &'a UserIdentity)> {
+    Ok((ctx.data::<AppState>()?, ctx.data::<UserIdentity>()?))
+}
+
+/// Mutations are refused on read-only replicas
+fn ensure_writable(state: &AppState) -> Result<(), ApiError> {
+    if state.read_only {
+        return Err(ApiError::ReadOnly);
+    }
+    Ok(())
+}
+
+async fn authorize(
+    state: &AppState,
+    user: &UserIdentity,
+    permission: Permission,
+    channel_id: ChannelId,
+) -> Result<(), ApiError> {
+    authorize_channel(state.authz.as_ref(), user.user_id, permission, channel_id).await
+}
+
+/// Check the message exists and the user wrote it, returning its channel
+async fn ensure_author(
+    state: &AppState,
+    user: &UserIdentity,
+    message_id: &MessageId,
+) -> Result<ChannelId, ApiError> {
+    let message = state.service.get_message(message_id).await?;
+    if message.author_id.0 != user.user_id {
+        return Err(ApiError::Forbidden);
+    }
+    Ok(message.channel_id)
+}
+
+fn parse_bound(value: Option<&str>) -> Result<Option<MessageBound>, ApiError> {
+    value
+        .map(str::parse::<MessageBound>)
+        .transpose()
+        .map_err(ApiError::from)
+}
+
+/// Same message and `code` as the REST error body
+fn graphql_error(error: ApiError) -> async_graphql::Error {
+    let code = error.error_code().to_string();
+    async_graphql::Error::new(error.to_string()).extend_with(|_, extensions| {
+        extensions.set("code", code);
+    })
+}
@@ -0,0 +1,212 @@
+use async_graphql::{
+    InputObject, InputValueError, InputValueResult, Scalar, ScalarType, SimpleObject, Value,
+};
+use chrono::{DateTime, Utc};
+use communities_core::domain::{
+    common::CursorPage,
+    message::entities::{Attachment, Embed, ImagePreview, Mentions, Message, ReactionCount},
+};
+use uuid::Uuid;
+
+use crate::http::server::public_id::PublicId;
+
+/// ID of a message or channel, encoded like in the REST API
+#[Scalar(name = "PublicId")]
+impl ScalarType for PublicId {
+    fn parse(value: Value) -> InputValueResult<Self> {
+        match &value {
+            Value::String(public) => PublicId::parse(public)
+                .ok_or_else(|| InputValueError::custom(format!("invalid ID: {public}"))),
+            _ => Err(InputValueError::expected_type(value)),
+        }
+    }
+
+    fn to_value(&self) -> Value {
+        Value::String(self.to_string())
+    }
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+#[graphql(name = "Attachment")]
+pub struct AttachmentObject {
+    pub id: Uuid,
+    pub name: String,
+    pub url: String,
+    pub size: u64,
+    pub preview: Option<ImagePreviewObject>,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+#[graphql(name = "ImagePreview")]
+pub struct ImagePreviewObject {
+    pub width: u32,
+    pub height: u32,
+    pub thumbnail_url: Option<String>,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+#[graphql(name = "Embed")]
+pub struct EmbedObject {
+    pub url: String,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub image_url: Option<String>,
+    pub site_name: Option<String>,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+#[graphql(name = "Mentions")]
+pub struct MentionsObject {
+    pub users: Vec<Uuid>,
+    pub roles: Vec<Uuid>,
+    pub everyone: bool,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+#[graphql(name = "ReactionCount")]
+pub struct ReactionCountObject {
+    pub emoji: String,
+    pub count: u64,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+#[graphql(name = "Message")]
+pub struct MessageObject {
+    pub id: PublicId,
+    pub channel_id: PublicId,
+    pub author_id: Uuid,
+    pub content: String,
+    pub reply_to_message_id: Option<PublicId>,
+    pub attachments: Vec<AttachmentObject>,
+    pub is_pinned: bool,
+    pub reactions: Vec<ReactionCountObject>,
+    pub revision: u64,
+    /// Position of the message in its channel
+    pub sequence: u64,
+    pub reply_count: u64,
+    pub last_reply_at: Option<DateTime<Utc>>,
+    /// Set on threads archived after staying idle
+    pub archived_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: Option<DateTime<Utc>>,
+    /// Previews of the links of the content
+    pub embeds: Vec<EmbedObject>,
+    /// Users and roles mentioned in the content
+    pub mentions: MentionsObject,
+    /// Set on ephemeral messages, which disappear at that date
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// A page of messages, newest first
+#[derive(Debug, Clone, SimpleObject)]
+#[graphql(name = "MessagePage")]
+pub struct MessagePageObject {
+    pub items: Vec<MessageObject>,
+    /// Cursor to pass back to fetch the next page, absent on the last page
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, InputObject)]
+pub struct SendMessageInput {
+    pub channel_id: PublicId,
+    pub content: String,
+    pub reply_to_message_id: Option<PublicId>,
+    /// Client-generated identifier making retried sends return the first message
+    pub nonce: Option<String>,
+    /// Makes the message ephemeral, disappearing at that date
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, InputObject)]
+pub struct EditMessageInput {
+    pub id: PublicId,
+    pub content: Option<String>,
+    pub is_pinned: Option<bool>,
+}
+
+impl From<Attachment> for AttachmentObject {
+    fn from(attachment: Attachment) -> Self {
+        Self {
+            id: attachment.id.0,
+            name: attachment.name,
+            url: attachment.url,
+            size: attachment.size,
+            preview: attachment.preview.map(ImagePreviewObject::from),
+        }
+    }
+}
+
+impl From<ImagePreview> for ImagePreviewObject {
+    fn from(preview: ImagePreview) -> Self {
+        Self {
+            width: preview.width,
+            height: preview.height,
+            thumbnail_url: preview.thumbnail_url,
+        }
+    }
+}
+
+impl From<Embed> for EmbedObject {
+    fn from(embed: Embed) -> Self {
+        Self {
+            url: embed.url,
+            title: embed.title,
+            description: embed.description,
+            image_url: embed.image_url,
+            site_name: embed.site_name,
+        }
+    }
+}
+
+impl From<Mentions> for MentionsObject {
+    fn from(mentions: Mentions) -> Self {
+        Self {
+            users: mentions.users.into_iter().map(|user| user.0).collect(),
+            roles: mentions.roles,
+            everyone: mentions.everyone,
+        }
+    }
+}
+
+impl From<ReactionCount> for ReactionCountObject {
+    fn from(count: ReactionCount) -> Self {
+        Self {
+            emoji: count.emoji,
+            count: count.count,
+        }
+    }
+}
+
+impl From<Message> for MessageObject {
+    fn from(message: Message) -> Self {
+        Self {
+            id: message.id.0.into(),
+            channel_id: message.channel_id.0.into(),
+            author_id: message.author_id.0,
+            content: message.content,
+            reply_to_message_id: message.reply_to_message_id.map(|id| id.0.into()),
+            attachments: message.attachments.into_iter().map(Into::into).collect(),
+            is_pinned: message.is_pinned,
+            reactions: message.reactions.into_iter().map(Into::into).collect(),
+            revision: message.revision,
+            sequence: message.sequence,
+            reply_count: message.reply_count,
+            last_reply_at: message.last_reply_at,
+            archived_at: message.archived_at,
+            created_at: message.created_at,
+            updated_at: message.updated_at,
+            embeds: message.embeds.into_iter().map(Into::into).collect(),
+            mentions: message.mentions.into(),
+            expires_at: message.expires_at,
+        }
+    }
+}
+
+impl From<CursorPage<Message>> for MessagePageObject {
+    fn from(page: CursorPage<Message>) -> Self {
+        Self {
+            items: page.items.into_iter().map(Into::into).collect(),
+            next_cursor: page.next_cursor,
+        }
+    }
+}
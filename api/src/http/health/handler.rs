@@ -3,18 +3,31 @@ use chrono::Utc;
 use serde::Serialize;
 use utoipa::ToSchema;
 
-use communities_core::domain::health::port::HealthService;
+use communities_core::{
+    domain::health::port::HealthService, infrastructure::outbox::dropped_outbox_events,
+};
 
 use crate::http::server::{ApiError, AppState, Response};
 
 /// Response structure for the health check
 #[derive(Debug, Clone, Serialize, ToSchema)]
 pub struct HealthResponse {
+    /// `healthy`, or `degraded` when an optional dependency is down
     pub status: String,
     pub database_status: String,
+    /// Optional dependencies, the service keeps serving without them
+    pub components: Vec<ComponentHealthResponse>,
+    /// Outbox events dropped by the `log_and_continue` policy since startup
+    pub dropped_outbox_events: u64,
     pub timestamp: String,
 }
 
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ComponentHealthResponse {
+    pub name: String,
+    pub status: String,
+}
+
 /// Handler for /health endpoint
 /// Checks database connectivity and service health
 #[utoipa::path(
@@ -22,7 +35,7 @@ pub struct HealthResponse {
     path = "/health",
     tag = "health",
     responses(
-        (status = 200, description = "Service is healthy", body = HealthResponse),
+        (status = 200, description = "Service is healthy or degraded", body = HealthResponse),
         (status = 503, description = "Service is unhealthy"),
         (status = 500, description = "Internal message error")
     )
@@ -31,19 +44,21 @@ pub struct HealthResponse {
 pub async fn health_check(
     State(state): State<AppState>,
 ) -> Result<Response<HealthResponse>, ApiError> {
-    let health_check = state.service.check_health().await?;
-
-    let is_healthy = health_check.value();
-    let status = if is_healthy { "healthy" } else { "unhealthy" };
-    let database_status = if is_healthy {
-        "connected"
-    } else {
-        "disconnected"
-    };
+    // An unreachable database fails the check with a 503
+    let report = state.service.check_health().await?;
 
     let response = HealthResponse {
-        status: status.to_string(),
-        database_status: database_status.to_string(),
+        status: report.status.as_str().to_string(),
+        database_status: "connected".to_string(),
+        components: report
+            .components
+            .iter()
+            .map(|component| ComponentHealthResponse {
+                name: component.name.clone(),
+                status: component.status.as_str().to_string(),
+            })
+            .collect(),
+        dropped_outbox_events: dropped_outbox_events(),
         timestamp: Utc::now().to_rfc3339(),
     };
 
@@ -0,0 +1,85 @@
+use chrono::{DateTime, Utc};
+use communities_core::domain::message::entities::{ChannelId, LegalHold, LegalHoldScope, UserId};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::http::server::{ApiError, public_id::PublicId};
+
+/// Scope of a hold: exactly one of `channel_id` and `user_id`
+fn scope_of(
+    channel_id: Option<PublicId>,
+    user_id: Option<Uuid>,
+) -> Result<LegalHoldScope, ApiError> {
+    match (channel_id, user_id) {
+        (Some(channel_id), None) => Ok(LegalHoldScope::Channel(ChannelId::from(channel_id.0))),
+        (None, Some(user_id)) => Ok(LegalHoldScope::User(UserId::from(user_id))),
+        _ => Err(ApiError::BadRequest {
+            msg: "Exactly one of channel_id and user_id is required".to_string(),
+        }),
+    }
+}
+
+/// Hold to place, on a channel or on a user
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct PlaceLegalHoldRequest {
+    #[schema(value_type = Option<String>)]
+    pub channel_id: Option<PublicId>,
+    pub user_id: Option<Uuid>,
+    /// Case or matter the hold is placed for
+    pub reference: String,
+}
+
+impl PlaceLegalHoldRequest {
+    pub fn scope(&self) -> Result<LegalHoldScope, ApiError> {
+        scope_of(self.channel_id, self.user_id)
+    }
+}
+
+/// Holds to list, on a channel or on a user
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListLegalHoldsParams {
+    #[param(value_type = Option<String>)]
+    pub channel_id: Option<PublicId>,
+    pub user_id: Option<Uuid>,
+}
+
+impl ListLegalHoldsParams {
+    pub fn scope(&self) -> Result<LegalHoldScope, ApiError> {
+        scope_of(self.channel_id, self.user_id)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct LegalHoldResponse {
+    pub id: Uuid,
+    /// Set on channel holds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<String>)]
+    pub channel_id: Option<PublicId>,
+    /// Set on user holds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<Uuid>,
+    pub reference: String,
+    pub placed_by: Uuid,
+    pub placed_at: DateTime<Utc>,
+}
+
+impl From<LegalHold> for LegalHoldResponse {
+    fn from(hold: LegalHold) -> Self {
+        let (channel_id, user_id) = match hold.scope {
+            LegalHoldScope::Channel(channel_id) => (Some(channel_id.0.into()), None),
+            LegalHoldScope::User(user_id) => (None, Some(user_id.0)),
+        };
+
+        Self {
+            id: hold.id.0,
+            channel_id,
+            user_id,
+            reference: hold.reference,
+            placed_by: hold.placed_by.0,
+            placed_at: hold.placed_at,
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use axum::{
+    Extension, Json,
+    extract::{Path, Query, State},
+};
+use communities_core::domain::message::{
+    entities::{LegalHoldId, LegalHoldScope, PlaceLegalHoldInput, UserId},
+    ports::MessageService,
+};
+use uuid::Uuid;
+
+use crate::http::{
+    legal_holds::dto::{LegalHoldResponse, ListLegalHoldsParams, PlaceLegalHoldRequest},
+    server::{
+        ApiError, AppState, Response,
+        authorization::{Permission, Resource},
+        channel_access::authorize_channel,
+        middleware::auth::entities::UserIdentity,
+    },
+};
+
+/// Holds on a channel are managed by whoever manages the channel, holds on a
+/// user by whoever moderates their messages
+async fn authorize_scope(
+    state: &AppState,
+    user_id: Uuid,
+    scope: &LegalHoldScope,
+) -> Result<(), ApiError> {
+    match scope {
+        LegalHoldScope::Channel(channel_id) => {
+            authorize_channel(
+                state.authz.as_ref(),
+                user_id,
+                Permission::ManageChannels,
+                *channel_id,
+            )
+            .await
+        }
+        LegalHoldScope::User(held_user) => {
+            let allowed = state
+                .authz
+                .check(
+                    user_id,
+                    Permission::ManageMessages,
+                    Resource::User(held_user.0),
+                )
+                .await
+                .map_err(|_| ApiError::InternalServerError)?;
+            if !allowed {
+                return Err(ApiError::Forbidden);
+            }
+            Ok(())
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/legal-holds",
+    tag = "legal-holds",
+    request_body = PlaceLegalHoldRequest,
+    responses(
+        (status = 201, description = "Legal hold placed", body = LegalHoldResponse),
+        (status = 400, description = "Bad request - Missing scope or blank reference"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 500, description = "Internal message error")
+    )
+)]
+#[tracing::instrument(skip(state, user_identity, request))]
+pub async fn place_legal_hold(
+    State(state): State<AppState>,
+    Extension(user_identity): Extension<UserIdentity>,
+    Json(request): Json<PlaceLegalHoldRequest>,
+) -> Result<Response<LegalHoldResponse>, ApiError> {
+    let scope = request.scope()?;
+    authorize_scope(&state, user_identity.user_id, &scope).await?;
+
+    let hold = state
+        .service
+        .place_legal_hold(PlaceLegalHoldInput {
+            scope,
+            reference: request.reference,
+            placed_by: UserId::from(user_identity.user_id),
+        })
+        .await?;
+    tracing::info!(hold_id = %hold.id, reference = %hold.reference, "legal hold placed");
+
+    Ok(Response::created(hold.into()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/legal-holds",
+    tag = "legal-holds",
+    params(ListLegalHoldsParams),
+    responses(
+        (status = 200, description = "Active legal holds on the channel or user, oldest first", body = Vec<LegalHoldResponse>),
+        (status = 400, description = "Bad request - Missing scope"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 500, description = "Internal message error")
+    )
+)]
+#[tracing::instrument(skip(state, user_identity))]
+pub async fn list_legal_holds(
+    State(state): State<AppState>,
+    Extension(user_identity): Extension<UserIdentity>,
+    Query(params): Query<ListLegalHoldsParams>,
+) -> Result<Response<Vec<LegalHoldResponse>>, ApiError> {
+    let scope = params.scope()?;
+    authorize_scope(&state, user_identity.user_id, &scope).await?;
+
+    let holds = state.service.list_legal_holds(&scope).await?;
+    Ok(Response::ok(
+        holds.into_iter().map(LegalHoldResponse::from).collect(),
+    ))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/legal-holds/{hold_id}",
+    tag = "legal-holds",
+    params(
+        ("hold_id" = String, Path, description = "Legal hold ID")
+    ),
+    responses(
+        (status = 200, description = "Legal hold released"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "Legal hold not found"),
+        (status = 500, description = "Internal message error")
+    )
+)]
+#[tracing::instrument(skip(state, user_identity))]
+pub async fn release_legal_hold(
+    Path(hold_id): Path<Uuid>,
+    State(state): State<AppState>,
+    Extension(user_identity): Extension<UserIdentity>,
+) -> Result<Response<()>, ApiError> {
+    let hold_id = LegalHoldId::from(hold_id);
+
+    let hold = state.service.get_legal_hold(&hold_id).await?;
+    authorize_scope(&state, user_identity.user_id, &hold.scope).await?;
+
+    state.service.release_legal_hold(&hold_id).await?;
+    tracing::info!(%hold_id, reference = %hold.reference, "legal hold released");
+
+    Ok(Response::deleted(()))
+}
@@ -0,0 +1,22 @@
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+use crate::http::{
+    legal_holds::handlers::{
+        __path_list_legal_holds, __path_place_legal_hold, __path_release_legal_hold,
+        list_legal_holds, place_legal_hold, release_legal_hold,
+    },
+    server::{AppState, channel_access::route_authorized_by_handler},
+};
+
+/// Management of the legal holds keeping channels and users out of deletion sweeps
+pub fn legal_hold_routes() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new()
+        .routes(route_authorized_by_handler(
+            "ManageChannels on the channel of the hold, or ManageMessages on its user",
+            routes!(place_legal_hold, list_legal_holds),
+        ))
+        .routes(route_authorized_by_handler(
+            "ManageChannels on the channel of the hold, or ManageMessages on its user",
+            routes!(release_legal_hold),
+        ))
+}
@@ -0,0 +1,546 @@
+//! API response models for messages.
+//!
+//! Handlers never serialize domain entities directly: the JSON shape exposed
+//! to clients is defined here, so storage or domain changes cannot silently
+//! alter API responses.
+
+use chrono::{DateTime, Utc};
+use communities_core::domain::{
+    message::entities::{
+        Attachment, AttachmentId, AuthorId, ChannelDigest, ChannelId, CreateMessageRequest, Embed,
+        ImagePreview, ImportMessageInput, Mentions, Message, MessageId, MessageRevision, ModerationReason,
+        ModerationReasonTemplate, Reaction, ReactionCount, SavedMessage, ThreadFollow, ThreadPreferences,
+    },
+    search::entities::SimilarMessage,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::http::server::{
+    public_id::PublicId,
+    response::{CursorPaginatedResponse, PaginatedResponse},
+};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct AttachmentResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub url: String,
+    pub size: u64,
+    /// Dimensions and thumbnail of images, to lay them out before they are downloaded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preview: Option<ImagePreviewResponse>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ImagePreviewResponse {
+    pub width: u32,
+    pub height: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thumbnail_url: Option<String>,
+}
+
+/// Preview of a link of the content, from the metadata of the linked page
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct EmbedResponse {
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub site_name: Option<String>,
+}
+
+/// Users and roles mentioned in the content as `<@{user_id}>` and
+/// `<@&{role_id}>`, and whether it mentions `@everyone`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct MentionsResponse {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub users: Vec<Uuid>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub roles: Vec<Uuid>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub everyone: bool,
+}
+
+impl MentionsResponse {
+    pub fn is_empty(&self) -> bool {
+        self.users.is_empty() && self.roles.is_empty() && !self.everyone
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ReactionCountResponse {
+    pub emoji: String,
+    pub count: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ReactionResponse {
+    #[schema(value_type = String)]
+    pub message_id: PublicId,
+    pub user_id: Uuid,
+    pub emoji: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A thread the user is notified of the replies of
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ThreadFollowResponse {
+    #[schema(value_type = String)]
+    pub thread_id: PublicId,
+    pub channel_id: Uuid,
+    pub followed_at: DateTime<Utc>,
+}
+
+/// A message the user saved
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct SavedMessageResponse {
+    #[schema(value_type = String)]
+    pub message_id: PublicId,
+    pub channel_id: Uuid,
+    pub saved_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ThreadPreferencesResponse {
+    /// Follow the threads the user replies to
+    pub auto_follow_on_reply: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct UpdateThreadPreferencesRequest {
+    /// Follow the threads the user replies to
+    pub auto_follow_on_reply: bool,
+}
+
+/// Content a message held before one of its edits
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct MessageRevisionResponse {
+    #[schema(value_type = String)]
+    pub message_id: PublicId,
+    /// `revision` of the message while it held this content
+    pub revision: u64,
+    pub content: String,
+    pub written_at: DateTime<Utc>,
+    pub replaced_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct MessageResponse {
+    /// Kept as `_id` for compatibility with the original API
+    #[serde(rename = "_id")]
+    #[schema(value_type = String)]
+    pub id: PublicId,
+    #[schema(value_type = String)]
+    pub channel_id: PublicId,
+    pub author_id: Uuid,
+    pub content: String,
+    #[schema(value_type = Option<String>)]
+    pub reply_to_message_id: Option<PublicId>,
+    pub attachments: Vec<AttachmentResponse>,
+    pub is_pinned: bool,
+    pub reactions: Vec<ReactionCountResponse>,
+    pub revision: u64,
+    /// Position of the message in its channel, strictly increasing in
+    /// storage order; compare these rather than `created_at` to order messages
+    pub sequence: u64,
+    pub reply_count: u64,
+    pub last_reply_at: Option<DateTime<Utc>>,
+    /// Only present on threads archived after staying idle, until their next reply
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archived_at: Option<DateTime<Utc>>,
+
+    pub created_at: DateTime<Utc>,
+    pub updated_at: Option<DateTime<Utc>>,
+    /// Only present on deleted messages, which moderators alone can read back
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<DateTime<Utc>>,
+    /// Nonce the message was created with, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+    /// Previews of the links of the content, added shortly after creation
+    /// with an update of the message
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub embeds: Vec<EmbedResponse>,
+    /// Only present when the content mentions someone
+    #[serde(default, skip_serializing_if = "MentionsResponse::is_empty")]
+    pub mentions: MentionsResponse,
+    /// Only present on ephemeral messages, which disappear at that date
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// Body of a message creation: a [`CreateMessageRequest`] with public IDs
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct CreateMessageBody {
+    #[schema(value_type = String)]
+    pub channel_id: PublicId,
+    pub content: String,
+    #[schema(value_type = Option<String>)]
+    pub reply_to_message_id: Option<PublicId>,
+    /// IDs of attachments presigned with `POST /attachments/presign` and uploaded
+    pub attachments: Vec<Uuid>,
+    /// Identifier generated by the client, at most 64 characters: posting
+    /// again with the same nonce in the channel returns the first message
+    /// instead of creating a copy, so creations can be retried safely
+    #[serde(default)]
+    pub nonce: Option<String>,
+    /// Makes the message ephemeral: it disappears at that date, which must
+    /// be in the future
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl From<CreateMessageBody> for CreateMessageRequest {
+    fn from(body: CreateMessageBody) -> Self {
+        Self {
+            channel_id: ChannelId::from(body.channel_id.0),
+            content: body.content,
+            reply_to_message_id: body.reply_to_message_id.map(|id| MessageId::from(id.0)),
+            attachments: body.attachments.into_iter().map(AttachmentId).collect(),
+            nonce: body.nonce,
+            expires_at: body.expires_at,
+        }
+    }
+}
+
+/// A channel listing: offset pages, or keyset pages when `cursor`, `before` or `after` is given
+#[derive(Serialize, ToSchema)]
+#[serde(untagged)]
+pub enum MessageListResponse {
+    Page(PaginatedResponse<MessageResponse>),
+    Cursor(CursorPaginatedResponse<MessageResponse>),
+}
+
+impl From<Attachment> for AttachmentResponse {
+    fn from(attachment: Attachment) -> Self {
+        Self {
+            id: attachment.id.0,
+            name: attachment.name,
+            url: attachment.url,
+            size: attachment.size,
+            preview: attachment.preview.map(ImagePreviewResponse::from),
+        }
+    }
+}
+
+impl From<ImagePreview> for ImagePreviewResponse {
+    fn from(preview: ImagePreview) -> Self {
+        Self {
+            width: preview.width,
+            height: preview.height,
+            thumbnail_url: preview.thumbnail_url,
+        }
+    }
+}
+
+impl From<Embed> for EmbedResponse {
+    fn from(embed: Embed) -> Self {
+        Self {
+            url: embed.url,
+            title: embed.title,
+            description: embed.description,
+            image_url: embed.image_url,
+            site_name: embed.site_name,
+        }
+    }
+}
+
+impl From<Mentions> for MentionsResponse {
+    fn from(mentions: Mentions) -> Self {
+        Self {
+            users: mentions.users.into_iter().map(|user| user.0).collect(),
+            roles: mentions.roles,
+            everyone: mentions.everyone,
+        }
+    }
+}
+
+impl From<ReactionCount> for ReactionCountResponse {
+    fn from(count: ReactionCount) -> Self {
+        Self {
+            emoji: count.emoji,
+            count: count.count,
+        }
+    }
+}
+
+impl From<Reaction> for ReactionResponse {
+    fn from(reaction: Reaction) -> Self {
+        Self {
+            message_id: reaction.message_id.0.into(),
+            user_id: reaction.user_id.0,
+            emoji: reaction.emoji,
+            created_at: reaction.created_at,
+        }
+    }
+}
+
+impl From<SavedMessage> for SavedMessageResponse {
+    fn from(saved: SavedMessage) -> Self {
+        Self {
+            message_id: saved.message_id.0.into(),
+            channel_id: saved.channel_id.0,
+            saved_at: saved.saved_at,
+        }
+    }
+}
+
+impl From<ThreadFollow> for ThreadFollowResponse {
+    fn from(follow: ThreadFollow) -> Self {
+        Self {
+            thread_id: follow.thread_id.0.into(),
+            channel_id: follow.channel_id.0,
+            followed_at: follow.followed_at,
+        }
+    }
+}
+
+impl From<ThreadPreferences> for ThreadPreferencesResponse {
+    fn from(preferences: ThreadPreferences) -> Self {
+        Self {
+            auto_follow_on_reply: preferences.auto_follow_on_reply,
+        }
+    }
+}
+
+impl From<MessageRevision> for MessageRevisionResponse {
+    fn from(revision: MessageRevision) -> Self {
+        Self {
+            message_id: revision.message_id.0.into(),
+            revision: revision.revision,
+            content: revision.content,
+            written_at: revision.written_at,
+            replaced_at: revision.replaced_at,
+        }
+    }
+}
+
+impl From<Message> for MessageResponse {
+    fn from(message: Message) -> Self {
+        Self {
+            id: message.id.0.into(),
+            channel_id: message.channel_id.0.into(),
+            author_id: message.author_id.0,
+            content: message.content,
+            reply_to_message_id: message.reply_to_message_id.map(|id| id.0.into()),
+            attachments: message.attachments.into_iter().map(Into::into).collect(),
+            is_pinned: message.is_pinned,
+            reactions: message.reactions.into_iter().map(Into::into).collect(),
+            revision: message.revision,
+            sequence: message.sequence,
+            reply_count: message.reply_count,
+            last_reply_at: message.last_reply_at,
+            archived_at: message.archived_at,
+            created_at: message.created_at,
+            updated_at: message.updated_at,
+            deleted_at: message.deleted_at,
+            nonce: message.nonce,
+            embeds: message.embeds.into_iter().map(Into::into).collect(),
+            mentions: message.mentions.into(),
+            expires_at: message.expires_at,
+        }
+    }
+}
+
+/// Suggestions returned for a draft: at most this many by default
+pub const DEFAULT_SIMILAR_LIMIT: u32 = 5;
+/// Suggestions returned for a draft: never more than this many
+pub const MAX_SIMILAR_LIMIT: u32 = 20;
+
+/// Draft to find similar recent messages for
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct SimilarMessagesRequest {
+    pub content: String,
+    /// Maximum number of suggestions, 5 by default and at most 20
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct SimilarMessageResponse {
+    pub message: MessageResponse,
+    /// Share of words the draft and the message have in common, from 0 to 1
+    pub similarity: f32,
+    /// Thread the message belongs to: its parent for a reply, itself otherwise
+    #[schema(value_type = String)]
+    pub thread_id: PublicId,
+}
+
+impl From<SimilarMessage> for SimilarMessageResponse {
+    fn from(similar: SimilarMessage) -> Self {
+        Self {
+            message: similar.message.into(),
+            similarity: similar.similarity,
+            thread_id: similar.thread_id.0.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct GetChannelDigestParams {
+    /// Start of the period to summarize, as an RFC3339 timestamp
+    pub since: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ChannelDigestResponse {
+    #[schema(value_type = String)]
+    pub channel_id: PublicId,
+    pub since: DateTime<Utc>,
+    /// Messages posted since `since`, replies included
+    pub message_count: u64,
+    /// Messages posted since `since` with the most reactions, most reacted first
+    pub top_reacted: Vec<MessageResponse>,
+    /// Messages pinned since `since`, latest pin first
+    pub new_pins: Vec<MessageResponse>,
+    /// Messages replied to since `since`, latest reply first
+    pub active_threads: Vec<MessageResponse>,
+}
+
+impl From<ChannelDigest> for ChannelDigestResponse {
+    fn from(digest: ChannelDigest) -> Self {
+        let responses = |messages: Vec<Message>| {
+            messages
+                .into_iter()
+                .map(MessageResponse::from)
+                .collect::<Vec<_>>()
+        };
+        Self {
+            channel_id: digest.channel_id.0.into(),
+            since: digest.since,
+            message_count: digest.message_count,
+            top_reacted: responses(digest.top_reacted),
+            new_pins: responses(digest.new_pins),
+            active_threads: responses(digest.active_threads),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct GetAuthorMessagesParams {
+    /// Channel to review the messages of the user in
+    #[param(value_type = String)]
+    pub channel_id: PublicId,
+}
+
+/// Messages to fetch at once
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct BatchGetMessagesRequest {
+    #[schema(value_type = Vec<String>)]
+    pub ids: Vec<PublicId>,
+}
+
+/// Channels to preview the newest message of
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct LastMessagesRequest {
+    #[schema(value_type = Vec<String>)]
+    pub channel_ids: Vec<PublicId>,
+}
+
+/// Messages of a channel to delete at once
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct BulkDeleteMessagesRequest {
+    #[schema(value_type = Vec<String>)]
+    pub ids: Vec<PublicId>,
+    /// Why the messages are deleted, told to their authors
+    #[serde(default)]
+    pub reason: Option<ModerationReason>,
+}
+
+/// Moderation reason moderators can pick by its code
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ModerationReasonTemplateResponse {
+    pub code: String,
+    /// Text told to users when the moderator gives none
+    pub text: String,
+}
+
+impl From<ModerationReasonTemplate> for ModerationReasonTemplateResponse {
+    fn from(template: ModerationReasonTemplate) -> Self {
+        Self {
+            code: template.code,
+            text: template.text,
+        }
+    }
+}
+
+/// A line of a channel import: a message as exported by
+/// `GET /channels/{channel_id}/export`, or written by a migration tool
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct ImportMessageLine {
+    #[serde(rename = "_id")]
+    #[schema(value_type = String)]
+    pub id: PublicId,
+    pub author_id: Uuid,
+    pub content: String,
+    #[serde(default)]
+    #[schema(value_type = Option<String>)]
+    pub reply_to_message_id: Option<PublicId>,
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
+    #[serde(default)]
+    pub is_pinned: bool,
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl From<ImportMessageLine> for ImportMessageInput {
+    fn from(line: ImportMessageLine) -> Self {
+        Self {
+            id: MessageId::from(line.id.0),
+            author_id: AuthorId::from(line.author_id),
+            content: line.content,
+            reply_to_message_id: line.reply_to_message_id.map(|id| MessageId::from(id.0)),
+            attachments: line.attachments,
+            is_pinned: line.is_pinned,
+            created_at: line.created_at,
+            updated_at: line.updated_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct IncludeDeletedParams {
+    /// Also return deleted messages, with their content, for moderation review.
+    /// Requires the `MANAGE_MESSAGES` permission on the channel.
+    #[serde(default)]
+    pub include_deleted: bool,
+}
+
+/// Recipient of an encrypted export: exactly one of `passphrase` and `public_key`
+#[derive(Clone, Deserialize, ToSchema)]
+pub struct CreateExportLinkRequest {
+    /// Also export deleted messages, with their content
+    #[serde(default)]
+    pub include_deleted: bool,
+    /// Passphrase of at least 12 characters the export key is derived from
+    pub passphrase: Option<String>,
+    /// PEM-encoded RSA public key (`BEGIN PUBLIC KEY`, 2048 bits or more) the
+    /// export key is encrypted for
+    pub public_key: Option<String>,
+}
+
+/// Link downloading an encrypted export, usable without credentials until it expires
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ExportLinkResponse {
+    /// Path of the download, relative to the API root
+    pub url: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListThreadsParams {
+    /// Also return the threads archived after staying idle
+    #[serde(default)]
+    pub include_archived: bool,
+}
@@ -1,55 +1,274 @@
+use std::collections::{HashMap, HashSet};
+
 use axum::{
-    Extension, Json,
+    BoxError, Extension, Json,
+    body::Body,
     extract::{Path, Query, State},
+    http::{HeaderMap, header},
+    response::{IntoResponse, Response as AxumResponse},
 };
 use communities_core::domain::{
-    common::GetPaginated,
+    common::{GetCursorPaginated, GetMessagesCursorParams, GetPaginated},
     message::{
-        entities::{AuthorId, ChannelId, CreateMessageRequest, Message, MessageId, UpdateMessageRequest},
-        ports::MessageService,
+        entities::{
+            AddReactionInput, AuthorId, ChannelId, CreateMessageRequest, ImportMessageInput,
+            MessageId, ThreadPreferences, UpdateMessageRequest, UserId,
+        },
+        ports::{MessageService, MessageStream},
     },
+    search::entities::SimilarMessagesQuery,
 };
+use communities_core::infrastructure::message::export::{ExportEncryptor, ExportRecipient};
+use futures::{StreamExt, TryStreamExt, future, stream, stream::BoxStream};
 use uuid::Uuid;
 
+use crate::http::messages::dto::{
+    BatchGetMessagesRequest, BulkDeleteMessagesRequest, ChannelDigestResponse,
+    CreateExportLinkRequest, CreateMessageBody, DEFAULT_SIMILAR_LIMIT, ExportLinkResponse,
+    GetAuthorMessagesParams, GetChannelDigestParams, ImportMessageLine,
+    IncludeDeletedParams, LastMessagesRequest, ListThreadsParams, MAX_SIMILAR_LIMIT,
+    MessageListResponse,
+    ModerationReasonTemplateResponse, SavedMessageResponse,
+    MessageResponse, MessageRevisionResponse, ReactionResponse, SimilarMessageResponse,
+    SimilarMessagesRequest, ThreadFollowResponse, ThreadPreferencesResponse,
+    UpdateThreadPreferencesRequest,
+};
 use crate::http::server::{
-    ApiError, AppState, Response, middleware::auth::entities::UserIdentity,
-    response::PaginatedResponse,
+    ApiError, AppState, Response, ValidatedQuery, cache::ListCacheKey,
+    export_links::ExportLinks,
+    middleware::auth::entities::UserIdentity,
+    response::{
+        BatchResult, CursorPaginatedResponse, ETag, NDJSON_CONTENT_TYPE, PaginatedResponse,
+    },
 };
-use crate::http::server::authorization::{Permission, Resource};
+use crate::http::server::authorization::Permission;
+use crate::http::server::channel_access::{AuthorizedChannel, authorize_channel};
+use crate::http::server::public_id::{PublicId, sync_scope_public_ids};
 
 #[utoipa::path(
     post,
     path = "/messages",
     tag = "messages",
-    request_body = CreateMessageRequest,
+    request_body = CreateMessageBody,
     responses(
-        (status = 201, description = "Message created successfully", body = Message),
+        (status = 201, description = "Message created successfully", body = MessageResponse),
         (status = 400, description = "Bad request - Invalid message name"),
         (status = 401, description = "Unauthorized"),
+        (status = 422, description = "An attachment was not presigned by the author in the channel, or not uploaded"),
         (status = 500, description = "Internal message error")
     )
 )]
-#[tracing::instrument(skip(state, user_identity, request))]
+#[tracing::instrument(skip(state, user_identity, body))]
 pub async fn create_message(
     State(state): State<AppState>,
     Extension(user_identity): Extension<UserIdentity>,
-    Json(request): Json<CreateMessageRequest>,
-) -> Result<Response<Message>, ApiError> {
+    Json(body): Json<CreateMessageBody>,
+) -> Result<Response<MessageResponse>, ApiError> {
+    let request = CreateMessageRequest::from(body);
+
     // Authorization: check user can send messages to this channel
     let channel = request.channel_id;
-    let allowed = state
-        .authz
-        .check(user_identity.user_id, Permission::SendMessages, Resource::Channel(channel.0))
-        .await
-        .map_err(|_| ApiError::InternalServerError)?;
-    if !allowed {
-        return Err(ApiError::Forbidden);
-    }
+    authorize_channel(
+        state.authz.as_ref(),
+        user_identity.user_id,
+        Permission::SendMessages,
+        channel,
+    )
+    .await?;
 
     let owner_id = AuthorId::from(user_identity.user_id);
     let input = request.into_input(owner_id);
     let message = state.service.create_message(input).await?;
-    Ok(Response::created(message))
+    state.list_cache.invalidate_channel(message.channel_id);
+    Ok(Response::created(message.into()))
+}
+
+/// Maximum number of messages accepted by a single batch create request
+const MAX_BATCH_SIZE: usize = 50;
+
+#[utoipa::path(
+    post,
+    path = "/messages/batch",
+    tag = "messages",
+    request_body = Vec<CreateMessageBody>,
+    responses(
+        (status = 200, description = "All messages created", body = BatchResult<MessageResponse, usize>),
+        (status = 207, description = "Some messages failed, keyed by their index in the request", body = BatchResult<MessageResponse, usize>),
+        (status = 400, description = "Bad request - Empty or oversized batch"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal message error")
+    )
+)]
+#[tracing::instrument(skip(state, user_identity, requests))]
+pub async fn create_messages_batch(
+    State(state): State<AppState>,
+    Extension(user_identity): Extension<UserIdentity>,
+    Json(requests): Json<Vec<CreateMessageBody>>,
+) -> Result<Response<BatchResult<MessageResponse, usize>>, ApiError> {
+    if requests.is_empty() || requests.len() > MAX_BATCH_SIZE {
+        return Err(ApiError::BadRequest {
+            msg: format!("A batch must contain between 1 and {} messages", MAX_BATCH_SIZE),
+        });
+    }
+
+    let mut result = BatchResult::default();
+    for (index, request) in requests.into_iter().enumerate() {
+        match create_one(&state, &user_identity, request.into()).await {
+            Ok(message) => result.push_success(message),
+            Err(error) => result.push_failure(index, error),
+        }
+    }
+
+    Ok(result.into_batch_response())
+}
+
+async fn create_one(
+    state: &AppState,
+    user_identity: &UserIdentity,
+    request: CreateMessageRequest,
+) -> Result<MessageResponse, ApiError> {
+    let channel = request.channel_id;
+    authorize_channel(
+        state.authz.as_ref(),
+        user_identity.user_id,
+        Permission::SendMessages,
+        channel,
+    )
+    .await?;
+
+    let input = request.into_input(AuthorId::from(user_identity.user_id));
+    let message = state.service.create_message(input).await?;
+    state.list_cache.invalidate_channel(message.channel_id);
+    Ok(message.into())
+}
+
+/// Maximum number of messages fetched by a single batch get request
+const MAX_BATCH_GET_SIZE: usize = 100;
+
+#[utoipa::path(
+    post,
+    path = "/messages/batch-get",
+    tag = "messages",
+    request_body = BatchGetMessagesRequest,
+    responses(
+        (status = 200, description = "All messages retrieved, in the order of the request", body = BatchResult<MessageResponse, String>),
+        (status = 207, description = "Some messages were not found or are in channels the user cannot view, keyed by their ID", body = BatchResult<MessageResponse, String>),
+        (status = 400, description = "Bad request - Empty or oversized batch"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal message error")
+    )
+)]
+#[tracing::instrument(skip(state, user_identity, request))]
+pub async fn get_messages_batch(
+    State(state): State<AppState>,
+    Extension(user_identity): Extension<UserIdentity>,
+    Json(request): Json<BatchGetMessagesRequest>,
+) -> Result<Response<BatchResult<MessageResponse, PublicId>>, ApiError> {
+    if request.ids.is_empty() || request.ids.len() > MAX_BATCH_GET_SIZE {
+        return Err(ApiError::BadRequest {
+            msg: format!("A batch must contain between 1 and {} IDs", MAX_BATCH_GET_SIZE),
+        });
+    }
+
+    let ids: Vec<MessageId> = request.ids.iter().map(|id| MessageId::from(id.0)).collect();
+    let messages = state.service.get_messages(&ids).await?;
+
+    // Each channel is authorized once, however many of its messages were asked for
+    let mut channels: HashMap<ChannelId, Result<(), ApiError>> = HashMap::new();
+    let mut result = BatchResult::default();
+    let mut found = HashSet::new();
+    for message in messages {
+        found.insert(message.id);
+        let access = match channels.get(&message.channel_id) {
+            Some(access) => access.clone(),
+            None => {
+                let access = authorize_channel(
+                    state.authz.as_ref(),
+                    user_identity.user_id,
+                    Permission::ViewChannels,
+                    message.channel_id,
+                )
+                .await;
+                channels.insert(message.channel_id, access.clone());
+                access
+            }
+        };
+        match access {
+            Ok(()) => result.push_success(message.into()),
+            Err(error) => result.push_failure(message.id.0.into(), error),
+        }
+    }
+
+    for id in request.ids {
+        if found.insert(MessageId::from(id.0)) {
+            result.push_failure(id, ApiError::NotFound);
+        }
+    }
+
+    Ok(result.into_batch_response())
+}
+
+/// Maximum number of channels previewed by a single last messages request
+const MAX_LAST_MESSAGES_CHANNELS: usize = 100;
+
+#[utoipa::path(
+    post,
+    path = "/channels/last-messages",
+    tag = "messages",
+    request_body = LastMessagesRequest,
+    responses(
+        (status = 200, description = "Newest message of every channel, in the order of the request", body = BatchResult<MessageResponse, String>),
+        (status = 207, description = "Some channels have no message or cannot be viewed by the user, keyed by their ID", body = BatchResult<MessageResponse, String>),
+        (status = 400, description = "Bad request - Empty or oversized batch"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal message error")
+    )
+)]
+#[tracing::instrument(skip(state, user_identity, request))]
+pub async fn get_last_messages(
+    State(state): State<AppState>,
+    Extension(user_identity): Extension<UserIdentity>,
+    Json(request): Json<LastMessagesRequest>,
+) -> Result<Response<BatchResult<MessageResponse, PublicId>>, ApiError> {
+    if request.channel_ids.is_empty() || request.channel_ids.len() > MAX_LAST_MESSAGES_CHANNELS {
+        return Err(ApiError::BadRequest {
+            msg: format!(
+                "A batch must contain between 1 and {} channel IDs",
+                MAX_LAST_MESSAGES_CHANNELS
+            ),
+        });
+    }
+
+    let mut result = BatchResult::default();
+    let mut viewable = Vec::new();
+    for id in request.channel_ids {
+        let channel_id = ChannelId::from(id.0);
+        if viewable.contains(&channel_id) {
+            continue;
+        }
+        match authorize_channel(
+            state.authz.as_ref(),
+            user_identity.user_id,
+            Permission::ViewChannels,
+            channel_id,
+        )
+        .await
+        {
+            Ok(()) => viewable.push(channel_id),
+            Err(error) => result.push_failure(id, error),
+        }
+    }
+
+    // in the order of `viewable`, skipping the channels without messages
+    let mut messages = state.service.last_messages(&viewable).await?.into_iter().peekable();
+    for channel_id in viewable {
+        match messages.next_if(|message| message.channel_id == channel_id) {
+            Some(message) => result.push_success(message.into()),
+            None => result.push_failure(channel_id.0.into(), ApiError::NotFound),
+        }
+    }
+
+    Ok(result.into_batch_response())
 }
 
 #[utoipa::path(
@@ -57,36 +276,48 @@ pub async fn create_message(
     path = "/messages/{id}",
     tag = "messages",
     params(
-        ("id" = String, Path, description = "Message ID")
+        ("id" = String, Path, description = "Message ID"),
+        IncludeDeletedParams
     ),
     responses(
-        (status = 200, description = "Message retrieved successfully", body = Message),
+        (status = 200, description = "Message retrieved successfully, tagged with its revision", body = MessageResponse),
+        (status = 304, description = "Not modified - If-None-Match holds the current revision"),
         (status = 401, description = "Unauthorized"),
-        (status = 403, description = "Forbidden - Message is private"),
+        (status = 403, description = "Forbidden - Message is private, or deleted messages requested without moderating the channel"),
         (status = 404, description = "Message not found"),
         (status = 500, description = "Internal message error")
     )
 )]
-#[tracing::instrument(skip(state))]
+#[tracing::instrument(skip(state, user_identity))]
 pub async fn get_message(
-    Path(id): Path<Uuid>,
+    Path(id): Path<PublicId>,
     State(state): State<AppState>,
     Extension(user_identity): Extension<UserIdentity>,
-) -> Result<Response<Message>, ApiError> {
-    let message_id = MessageId::from(id);
-    let message = state.service.get_message(&message_id).await?;
-
-    // Authorization: check user can view the channel where this message belongs
-    let allowed = state
-        .authz
-        .check(user_identity.user_id, Permission::ViewChannels, Resource::Channel(message.channel_id.0))
-        .await
-        .map_err(|_| ApiError::InternalServerError)?;
-    if !allowed {
-        return Err(ApiError::Forbidden);
-    }
+    AuthorizedChannel(channel): AuthorizedChannel,
+    Query(params): Query<IncludeDeletedParams>,
+    headers: HeaderMap,
+) -> Result<Response<MessageResponse>, ApiError> {
+    let message_id = MessageId::from(id.0);
+    let message = if params.include_deleted {
+        authorize_channel(
+            state.authz.as_ref(),
+            user_identity.user_id,
+            Permission::ManageMessages,
+            channel,
+        )
+        .await?;
+        state
+            .service
+            .get_message_including_deleted(&message_id)
+            .await?
+    } else {
+        state.service.get_message(&message_id).await?
+    };
 
-    Ok(Response::ok(message))
+    let etag = revision_etag(message.revision);
+    Ok(Response::ok(message.into())
+        .with_etag(&etag)
+        .revalidate(&headers))
 }
 
 #[utoipa::path(
@@ -95,42 +326,381 @@ pub async fn get_message(
     tag = "messages",
     params(
         ("channel_id" = String, Path, description = "Channel ID"),
-        GetPaginated
+        GetPaginated,
+        GetMessagesCursorParams,
+        IncludeDeletedParams
     ),
     responses(
-        (status = 200, description = "List of messages retrieved successfully", body = PaginatedResponse<Message>),
+        (status = 200, description = "List of messages retrieved successfully, newest first", body = MessageListResponse),
+        (status = 400, description = "Bad request - Invalid cursor or bound, page or limit out of bounds, or cursor combined with include_deleted"),
         (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
         (status = 500, description = "Internal message error")
     )
 )]
-#[tracing::instrument(skip(state, user_identity, pagination))]
+#[tracing::instrument(skip(state, channel, user_identity, pagination, cursor_params, params), fields(channel_id = %channel))]
 pub async fn list_messages(
     State(state): State<AppState>,
+    AuthorizedChannel(channel): AuthorizedChannel,
     Extension(user_identity): Extension<UserIdentity>,
-    Path(channel_id): Path<Uuid>,
-    Query(pagination): Query<GetPaginated>,
-) -> Result<Response<PaginatedResponse<Message>>, ApiError> {
-    let channel = ChannelId::from(channel_id);
+    ValidatedQuery(pagination): ValidatedQuery<GetPaginated>,
+    Query(cursor_params): Query<GetMessagesCursorParams>,
+    Query(params): Query<IncludeDeletedParams>,
+) -> Result<Response<Vec<MessageResponse>>, ApiError> {
+    // Moderation listings are offset pages only, and never cached so members
+    // cannot be served deleted content
+    if params.include_deleted {
+        authorize_channel(
+            state.authz.as_ref(),
+            user_identity.user_id,
+            Permission::ManageMessages,
+            channel,
+        )
+        .await?;
+        if cursor_params.into_query(pagination.limit)?.is_some() {
+            return Err(ApiError::BadRequest {
+                msg: "include_deleted cannot be combined with cursor, before or after".to_string(),
+            });
+        }
 
-    // Authorization: ensure user can view the channel before listing
-    let allowed = state
-        .authz
-        .check(user_identity.user_id, Permission::ViewChannels, Resource::Channel(channel.0))
-        .await
-        .map_err(|_| ApiError::InternalServerError)?;
-    if !allowed {
-        return Err(ApiError::Forbidden);
+        let (messages, total) = state
+            .service
+            .list_messages_including_deleted(&channel, &pagination)
+            .await?;
+        return Ok(Response::page(PaginatedResponse::new(
+            messages.into_iter().map(MessageResponse::from).collect(),
+            total,
+            pagination.page,
+            pagination.limit,
+        )));
     }
 
-    let (messages, total) = state.service.list_messages(&channel, &pagination).await?;
+    // Keyset pages are not cached: they are cheap, and the cache is keyed by page
+    if let Some(query) = cursor_params.into_query(pagination.limit)? {
+        let page = state.service.list_messages_by_cursor(&channel, &query).await?;
+        return Ok(Response::cursor_page(CursorPaginatedResponse {
+            data: page.items.into_iter().map(MessageResponse::from).collect(),
+            next_cursor: page.next_cursor,
+        }));
+    }
 
-    let response = PaginatedResponse {
-        data: messages,
-        total,
+    let cache_key = ListCacheKey {
+        channel_id: channel,
         page: pagination.page,
+        limit: pagination.limit,
+    };
+    let (messages, total) = match state.list_cache.get(&cache_key) {
+        Some(cached) => cached,
+        None => {
+            let listing = state.service.list_messages(&channel, &pagination).await?;
+            state.list_cache.insert(cache_key, listing.clone());
+            listing
+        }
+    };
+
+    let response = PaginatedResponse::new(
+        messages.into_iter().map(MessageResponse::from).collect(),
+        total,
+        pagination.page,
+        pagination.limit,
+    );
+
+    Ok(Response::page(response).with_cache_control(state.list_cache.ttl()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/channels/{channel_id}/export",
+    tag = "messages",
+    params(
+        ("channel_id" = String, Path, description = "Channel ID"),
+        IncludeDeletedParams
+    ),
+    responses(
+        (status = 200, description = "Every message of the channel oldest first, one JSON object per line", body = MessageResponse, content_type = "application/x-ndjson"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 500, description = "Internal message error")
+    )
+)]
+#[tracing::instrument(skip(state, params), fields(channel_id = %channel_id))]
+pub async fn export_channel_messages(
+    State(state): State<AppState>,
+    AuthorizedChannel(channel_id): AuthorizedChannel,
+    Query(params): Query<IncludeDeletedParams>,
+) -> Result<AxumResponse, ApiError> {
+    let messages = state
+        .service
+        .export_channel_messages(&channel_id, params.include_deleted)
+        .await?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, NDJSON_CONTENT_TYPE),
+            (header::CONTENT_DISPOSITION, "attachment"),
+        ],
+        Body::from_stream(state.stream_throttle.throttle(export_lines(messages, &state))),
+    )
+        .into_response())
+}
+
+/// NDJSON lines of an export.
+///
+/// Once the first line is sent, a failure can only abort the body: clients
+/// see a truncated export rather than an error status.
+/// The lines are serialized as the body is sent, once the request is over,
+/// so they get the IDs of `state` themselves.
+fn export_lines(
+    messages: MessageStream,
+    state: &AppState,
+) -> BoxStream<'static, Result<Vec<u8>, BoxError>> {
+    let id_obfuscation = state.id_obfuscation.clone();
+    messages
+        .map(move |message| {
+            let message =
+                message.inspect_err(|e| tracing::error!(error = %e, "channel export aborted"))?;
+            let mut line = sync_scope_public_ids(&id_obfuscation, || {
+                serde_json::to_vec(&MessageResponse::from(message))
+            })?;
+            line.push(b'\n');
+            Ok::<_, BoxError>(line)
+        })
+        .boxed()
+}
+
+#[utoipa::path(
+    post,
+    path = "/channels/{channel_id}/export/links",
+    tag = "messages",
+    params(
+        ("channel_id" = String, Path, description = "Channel ID")
+    ),
+    request_body = CreateExportLinkRequest,
+    responses(
+        (status = 201, description = "Link downloading the export encrypted for the recipient", body = ExportLinkResponse),
+        (status = 400, description = "Bad request - No or both recipients, short passphrase or invalid public key"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 503, description = "Export links are not configured"),
+        (status = 500, description = "Internal message error")
+    )
+)]
+#[tracing::instrument(skip(state, request), fields(channel_id = %channel_id))]
+pub async fn create_export_link(
+    State(state): State<AppState>,
+    AuthorizedChannel(channel_id): AuthorizedChannel,
+    Json(request): Json<CreateExportLinkRequest>,
+) -> Result<Response<ExportLinkResponse>, ApiError> {
+    let links = export_links(&state)?;
+
+    let recipient = match (request.passphrase, request.public_key) {
+        // key derivation takes a fraction of a second of CPU on purpose
+        (Some(passphrase), None) => {
+            tokio::task::spawn_blocking(move || ExportRecipient::from_passphrase(&passphrase))
+                .await
+                .map_err(|_| ApiError::InternalServerError)??
+        }
+        (None, Some(public_key)) => ExportRecipient::from_public_key(&public_key)?,
+        _ => {
+            return Err(ApiError::BadRequest {
+                msg: "Exactly one of passphrase and public_key is required".to_string(),
+            });
+        }
     };
 
-    Ok(Response::ok(response))
+    let (token, expires_at) = links.issue(
+        channel_id,
+        request.include_deleted,
+        recipient,
+        chrono::Utc::now(),
+    )?;
+    Ok(Response::created(ExportLinkResponse {
+        url: format!("/exports/{token}"),
+        expires_at,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/exports/{token}",
+    tag = "messages",
+    params(
+        ("token" = String, Path, description = "Token of the link returned when it was created")
+    ),
+    responses(
+        (status = 200, description = "Export of the channel as NDJSON, encrypted for the recipient of the link", content_type = "application/octet-stream"),
+        (status = 404, description = "Unknown, altered or expired link"),
+        (status = 503, description = "Export links are not configured"),
+        (status = 500, description = "Internal message error")
+    )
+)]
+#[tracing::instrument(skip(state, token))]
+pub async fn download_export(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+) -> Result<AxumResponse, ApiError> {
+    let link = export_links(&state)?
+        .open(&token, chrono::Utc::now())
+        .ok_or(ApiError::NotFound)?;
+    let encryptor = ExportEncryptor::new(&link.recipient)?;
+    let messages = state
+        .service
+        .export_channel_messages(&link.channel_id, link.include_deleted)
+        .await?;
+
+    // chunks only come out of the encryptor once filled, and the last one
+    // once every line went in
+    let encrypted = stream::unfold(
+        Some((export_lines(messages, &state), encryptor)),
+        |pending| async move {
+            let (mut lines, mut encryptor) = pending?;
+            match lines.next().await {
+                Some(Ok(line)) => {
+                    let chunk = encryptor.update(&line).map_err(BoxError::from);
+                    Some((chunk, Some((lines, encryptor))))
+                }
+                Some(Err(e)) => Some((Err(e), None)),
+                None => Some((encryptor.finish().map_err(BoxError::from), None)),
+            }
+        },
+    )
+    .try_filter(|chunk| future::ready(!chunk.is_empty()));
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/octet-stream"),
+            (header::CONTENT_DISPOSITION, "attachment"),
+        ],
+        Body::from_stream(state.stream_throttle.throttle(encrypted)),
+    )
+        .into_response())
+}
+
+fn export_links(state: &AppState) -> Result<&ExportLinks, ApiError> {
+    state
+        .export_links
+        .as_ref()
+        .ok_or_else(|| ApiError::ServiceUnavailable {
+            msg: "Export links are not configured".to_string(),
+        })
+}
+
+/// Messages of an import validated and stored together
+const IMPORT_CHUNK_SIZE: usize = 500;
+
+/// Longest line of an import, enough for a message with many attachments
+const MAX_IMPORT_LINE_BYTES: usize = 1024 * 1024;
+
+#[utoipa::path(
+    post,
+    path = "/channels/{channel_id}/import",
+    tag = "messages",
+    params(
+        ("channel_id" = String, Path, description = "Channel ID")
+    ),
+    request_body(content = ImportMessageLine, content_type = "application/x-ndjson", description = "One message per line"),
+    responses(
+        (status = 200, description = "All messages imported, in the order of the request", body = BatchResult<String, usize>),
+        (status = 207, description = "Some messages were rejected, keyed by their line number", body = BatchResult<String, usize>),
+        (status = 400, description = "Bad request - Unreadable body or oversized line"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 500, description = "Internal message error")
+    )
+)]
+#[tracing::instrument(skip(state, body), fields(channel_id = %channel_id))]
+pub async fn import_channel_messages(
+    State(state): State<AppState>,
+    AuthorizedChannel(channel_id): AuthorizedChannel,
+    body: Body,
+) -> Result<Response<BatchResult<PublicId, usize>>, ApiError> {
+    let mut result = BatchResult::default();
+    let mut chunk: Vec<(usize, ImportMessageInput)> = Vec::new();
+
+    // The body is read as it arrives, so imports of any size use little memory
+    let mut data = body.into_data_stream();
+    let mut pending = Vec::new();
+    let mut line_number = 0;
+    let mut ended = false;
+    while !ended {
+        match data.next().await {
+            Some(bytes) => {
+                let bytes = bytes.map_err(|e| ApiError::BadRequest {
+                    msg: format!("Failed to read the import: {e}"),
+                })?;
+                pending.extend_from_slice(&bytes);
+            }
+            None => {
+                ended = true;
+                pending.push(b'\n');
+            }
+        }
+
+        while let Some(end) = pending.iter().position(|&byte| byte == b'\n') {
+            let line: Vec<u8> = pending.drain(..=end).collect();
+            line_number += 1;
+            let line = line.trim_ascii();
+            if line.is_empty() {
+                continue;
+            }
+            match serde_json::from_slice::<ImportMessageLine>(line) {
+                Ok(message) => chunk.push((line_number, message.into())),
+                Err(e) => result.push_failure(
+                    line_number,
+                    ApiError::BadRequest {
+                        msg: format!("Invalid message: {e}"),
+                    },
+                ),
+            }
+            if chunk.len() == IMPORT_CHUNK_SIZE {
+                import_chunk(&state, &channel_id, &mut chunk, &mut result).await?;
+            }
+        }
+        if pending.len() > MAX_IMPORT_LINE_BYTES {
+            return Err(ApiError::BadRequest {
+                msg: format!(
+                    "Line {} is longer than {} bytes",
+                    line_number + 1,
+                    MAX_IMPORT_LINE_BYTES
+                ),
+            });
+        }
+    }
+    import_chunk(&state, &channel_id, &mut chunk, &mut result).await?;
+
+    if !result.succeeded.is_empty() {
+        state.list_cache.invalidate_channel(channel_id);
+    }
+    result.failed.sort_by_key(|failure| failure.key);
+    tracing::info!(
+        imported = result.succeeded.len(),
+        rejected = result.failed.len(),
+        "messages imported"
+    );
+
+    Ok(result.into_batch_response())
+}
+
+/// Import the messages of `chunk`, keyed by line number, into `result`
+async fn import_chunk(
+    state: &AppState,
+    channel_id: &ChannelId,
+    chunk: &mut Vec<(usize, ImportMessageInput)>,
+    result: &mut BatchResult<PublicId, usize>,
+) -> Result<(), ApiError> {
+    if chunk.is_empty() {
+        return Ok(());
+    }
+    let (line_numbers, inputs): (Vec<usize>, Vec<ImportMessageInput>) = chunk.drain(..).unzip();
+
+    let outcome = state.service.import_messages(channel_id, inputs).await?;
+    for id in outcome.imported {
+        result.push_success(PublicId(id.0));
+    }
+    for (position, error) in outcome.rejected {
+        result.push_failure(line_numbers[position], error.into());
+    }
+    Ok(())
 }
 
 #[utoipa::path(
@@ -142,22 +712,25 @@ pub async fn list_messages(
     ),
     request_body = UpdateMessageRequest,
     responses(
-        (status = 200, description = "Message updated successfully", body = Message),
+        (status = 200, description = "Message updated successfully, tagged with its new revision", body = MessageResponse),
         (status = 400, description = "Bad request - Invalid message name"),
         (status = 401, description = "Unauthorized"),
         (status = 403, description = "Forbidden - Not the message owner"),
         (status = 404, description = "Message not found"),
+        (status = 412, description = "Precondition failed - If-Match does not hold the current revision"),
         (status = 500, description = "Internal message error")
     )
 )]
 #[tracing::instrument(skip(state, user_identity, request))]
 pub async fn update_message(
-    Path(id): Path<Uuid>,
+    Path(id): Path<PublicId>,
     State(state): State<AppState>,
     Extension(user_identity): Extension<UserIdentity>,
+    headers: HeaderMap,
     Json(request): Json<UpdateMessageRequest>,
-) -> Result<Response<Message>, ApiError> {
-    let message_id = MessageId::from(id);
+) -> Result<Response<MessageResponse>, ApiError> {
+    let message_id = MessageId::from(id.0);
+    let expected_revision = if_match_revision(&headers)?;
 
     // Check if message exists and user is the owner
     let existing_message = state.service.get_message(&message_id).await?;
@@ -165,9 +738,40 @@ pub async fn update_message(
         return Err(ApiError::Forbidden);
     }
 
-    let input = request.into_input(message_id);
+    let mut input = request.into_input(message_id);
+    input.expected_revision = expected_revision;
     let message = state.service.update_message(input).await?;
-    Ok(Response::ok(message))
+    state.list_cache.invalidate_channel(message.channel_id);
+    let etag = revision_etag(message.revision);
+    Ok(Response::ok(message.into()).with_etag(&etag))
+}
+
+/// ETag of a message representation: its revision, which every update increments
+fn revision_etag(revision: u64) -> ETag {
+    ETag::strong(revision)
+}
+
+/// Revision an `If-Match` header requires the message to be at, `None`
+/// without a header or with `*`
+fn if_match_revision(headers: &HeaderMap) -> Result<Option<u64>, ApiError> {
+    let Some(value) = headers.get(header::IF_MATCH) else {
+        return Ok(None);
+    };
+    let value = value.to_str().unwrap_or_default().trim();
+    if value == "*" {
+        return Ok(None);
+    }
+
+    // If-Match uses the strong comparison: weak tags and lists of tags (one
+    // message has a single current revision) never match
+    value
+        .strip_prefix('"')
+        .and_then(|tag| tag.strip_suffix('"'))
+        .and_then(|revision| revision.parse().ok())
+        .map(Some)
+        .ok_or_else(|| ApiError::PreconditionFailed {
+            error_code: "MESSAGE_REVISION_MISMATCH".to_string(),
+        })
 }
 
 #[utoipa::path(
@@ -187,11 +791,11 @@ pub async fn update_message(
 )]
 #[tracing::instrument(skip(state, user_identity))]
 pub async fn delete_message(
-    Path(id): Path<Uuid>,
+    Path(id): Path<PublicId>,
     State(state): State<AppState>,
     Extension(user_identity): Extension<UserIdentity>,
 ) -> Result<Response<()>, ApiError> {
-    let message_id = MessageId::from(id);
+    let message_id = MessageId::from(id.0);
 
     // Check if message exists and user is the owner
     let existing_message = state.service.get_message(&message_id).await?;
@@ -200,5 +804,881 @@ pub async fn delete_message(
     }
 
     state.service.delete_message(&message_id).await?;
+    state.list_cache.invalidate_channel(existing_message.channel_id);
+    Ok(Response::deleted(()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/moderation/reasons",
+    tag = "messages",
+    responses(
+        (status = 200, description = "Reasons moderators pick from, empty when any reason code is accepted", body = Vec<ModerationReasonTemplateResponse>),
+        (status = 401, description = "Unauthorized")
+    )
+)]
+#[tracing::instrument(skip(state))]
+pub async fn list_moderation_reasons(
+    State(state): State<AppState>,
+) -> Result<Response<Vec<ModerationReasonTemplateResponse>>, ApiError> {
+    Ok(Response::ok(
+        state
+            .service
+            .moderation_reason_templates()
+            .into_iter()
+            .map(ModerationReasonTemplateResponse::from)
+            .collect(),
+    ))
+}
+
+/// Maximum number of messages deleted by a single bulk delete request
+const MAX_BULK_DELETE_SIZE: usize = 100;
+
+#[utoipa::path(
+    post,
+    path = "/channels/{channel_id}/messages/bulk-delete",
+    tag = "messages",
+    params(
+        ("channel_id" = String, Path, description = "Channel ID")
+    ),
+    request_body = BulkDeleteMessagesRequest,
+    responses(
+        (status = 200, description = "All messages deleted, in the order of the request", body = BatchResult<String, String>),
+        (status = 207, description = "Some messages were not found in the channel, keyed by their ID", body = BatchResult<String, String>),
+        (status = 400, description = "Bad request - Empty or oversized batch, or invalid reason"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 423, description = "Writes to the channel are locked"),
+        (status = 500, description = "Internal message error")
+    )
+)]
+#[tracing::instrument(skip(state, user_identity, request), fields(channel_id = %channel_id))]
+pub async fn bulk_delete_messages(
+    State(state): State<AppState>,
+    AuthorizedChannel(channel_id): AuthorizedChannel,
+    Extension(user_identity): Extension<UserIdentity>,
+    Json(request): Json<BulkDeleteMessagesRequest>,
+) -> Result<Response<BatchResult<PublicId, PublicId>>, ApiError> {
+    if request.ids.is_empty() || request.ids.len() > MAX_BULK_DELETE_SIZE {
+        return Err(ApiError::BadRequest {
+            msg: format!("A batch must contain between 1 and {} IDs", MAX_BULK_DELETE_SIZE),
+        });
+    }
+
+    let ids: Vec<MessageId> = request.ids.iter().map(|id| MessageId::from(id.0)).collect();
+    let reason_code = request.reason.as_ref().map(|reason| reason.code.clone());
+    let deleted: HashSet<MessageId> = state
+        .service
+        .bulk_delete_messages(
+            &channel_id,
+            &ids,
+            &UserId::from(user_identity.user_id),
+            request.reason,
+        )
+        .await?
+        .into_iter()
+        .collect();
+    if !deleted.is_empty() {
+        state.list_cache.invalidate_channel(channel_id);
+    }
+    // The request itself is the audit trail of the deletion
+    tracing::info!(
+        count = deleted.len(),
+        deleted_by = %user_identity.user_id,
+        reason = ?reason_code,
+        "messages bulk deleted"
+    );
+
+    let mut result = BatchResult::default();
+    let mut seen = HashSet::new();
+    for id in request.ids {
+        if !seen.insert(id.0) {
+            continue;
+        }
+        if deleted.contains(&MessageId::from(id.0)) {
+            result.push_success(id);
+        } else {
+            result.push_failure(id, ApiError::NotFound);
+        }
+    }
+
+    Ok(result.into_batch_response())
+}
+
+#[utoipa::path(
+    put,
+    path = "/messages/{id}/reactions/{emoji}",
+    tag = "messages",
+    params(
+        ("id" = String, Path, description = "Message ID"),
+        ("emoji" = String, Path, description = "Reaction emoji (percent-encoded)")
+    ),
+    responses(
+        (status = 200, description = "Reaction added successfully", body = ReactionResponse),
+        (status = 400, description = "Bad request - Invalid reaction emoji"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "Message not found"),
+        (status = 422, description = "Emoji not allowed in the channel"),
+        (status = 500, description = "Internal message error")
+    )
+)]
+#[tracing::instrument(skip(state, user_identity))]
+pub async fn add_reaction(
+    Path((id, emoji)): Path<(PublicId, String)>,
+    State(state): State<AppState>,
+    Extension(user_identity): Extension<UserIdentity>,
+    AuthorizedChannel(channel): AuthorizedChannel,
+) -> Result<Response<ReactionResponse>, ApiError> {
+    let message_id = MessageId::from(id.0);
+
+    let input = AddReactionInput {
+        message_id,
+        user_id: UserId::from(user_identity.user_id),
+        emoji,
+    };
+    let reaction = state.service.add_reaction(input).await?;
+    state.list_cache.invalidate_channel(channel);
+    Ok(Response::ok(reaction.into()))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/messages/{id}/reactions/{emoji}",
+    tag = "messages",
+    params(
+        ("id" = String, Path, description = "Message ID"),
+        ("emoji" = String, Path, description = "Reaction emoji (percent-encoded)")
+    ),
+    responses(
+        (status = 200, description = "Reaction removed successfully"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Message or reaction not found"),
+        (status = 500, description = "Internal message error")
+    )
+)]
+#[tracing::instrument(skip(state, user_identity))]
+pub async fn remove_reaction(
+    Path((id, emoji)): Path<(PublicId, String)>,
+    State(state): State<AppState>,
+    Extension(user_identity): Extension<UserIdentity>,
+) -> Result<Response<()>, ApiError> {
+    let message_id = MessageId::from(id.0);
+    let message = state.service.get_message(&message_id).await?;
+
+    // Users can only remove their own reactions
+    let user_id = UserId::from(user_identity.user_id);
+    state.service.remove_reaction(&message_id, &user_id, &emoji).await?;
+    state.list_cache.invalidate_channel(message.channel_id);
     Ok(Response::deleted(()))
 }
+
+#[utoipa::path(
+    get,
+    path = "/messages/{id}/reactions/{emoji}/users",
+    tag = "messages",
+    params(
+        ("id" = String, Path, description = "Message ID"),
+        ("emoji" = String, Path, description = "Reaction emoji (percent-encoded)"),
+        GetCursorPaginated
+    ),
+    responses(
+        (status = 200, description = "Users who reacted with this emoji", body = CursorPaginatedResponse<ReactionResponse>),
+        (status = 400, description = "Bad request - Invalid cursor or limit out of bounds"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "Message not found"),
+        (status = 500, description = "Internal message error")
+    )
+)]
+#[tracing::instrument(skip(state, pagination))]
+pub async fn list_reaction_users(
+    Path((id, emoji)): Path<(PublicId, String)>,
+    State(state): State<AppState>,
+    ValidatedQuery(pagination): ValidatedQuery<GetCursorPaginated>,
+) -> Result<Response<Vec<ReactionResponse>>, ApiError> {
+    let message_id = MessageId::from(id.0);
+
+    let page = state
+        .service
+        .list_reaction_users(&message_id, &emoji, &pagination)
+        .await?;
+
+    Ok(Response::cursor_page(CursorPaginatedResponse {
+        data: page.items.into_iter().map(ReactionResponse::from).collect(),
+        next_cursor: page.next_cursor,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/messages/{id}/replies",
+    tag = "messages",
+    params(
+        ("id" = String, Path, description = "Message ID"),
+        GetCursorPaginated
+    ),
+    responses(
+        (status = 200, description = "Replies to the message, oldest first", body = CursorPaginatedResponse<MessageResponse>),
+        (status = 400, description = "Bad request - Invalid cursor or limit out of bounds"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "Message not found"),
+        (status = 500, description = "Internal message error")
+    )
+)]
+#[tracing::instrument(skip(state, pagination))]
+pub async fn list_replies(
+    Path(id): Path<PublicId>,
+    State(state): State<AppState>,
+    ValidatedQuery(pagination): ValidatedQuery<GetCursorPaginated>,
+) -> Result<Response<Vec<MessageResponse>>, ApiError> {
+    let message_id = MessageId::from(id.0);
+
+    let page = state.service.list_replies(&message_id, &pagination).await?;
+
+    Ok(Response::cursor_page(CursorPaginatedResponse {
+        data: page.items.into_iter().map(MessageResponse::from).collect(),
+        next_cursor: page.next_cursor,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/messages/{id}/history",
+    tag = "messages",
+    params(
+        ("id" = String, Path, description = "Message ID")
+    ),
+    responses(
+        (status = 200, description = "Previous contents of the message, oldest first", body = Vec<MessageRevisionResponse>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Moderating the channel is required"),
+        (status = 404, description = "Message not found"),
+        (status = 500, description = "Internal message error")
+    )
+)]
+#[tracing::instrument(skip(state))]
+pub async fn get_message_history(
+    Path(id): Path<PublicId>,
+    State(state): State<AppState>,
+) -> Result<Response<Vec<MessageRevisionResponse>>, ApiError> {
+    let history = state
+        .service
+        .get_message_history(&MessageId::from(id.0))
+        .await?;
+
+    Ok(Response::ok(
+        history.into_iter().map(MessageRevisionResponse::from).collect(),
+    ))
+}
+
+#[utoipa::path(
+    put,
+    path = "/messages/{id}/reaction-notifications/mute",
+    tag = "messages",
+    params(
+        ("id" = String, Path, description = "Message ID")
+    ),
+    responses(
+        (status = 200, description = "Reaction notifications muted for this message"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Only the author can mute notifications of a message"),
+        (status = 404, description = "Message not found"),
+        (status = 500, description = "Internal message error")
+    )
+)]
+#[tracing::instrument(skip(state, user_identity))]
+pub async fn mute_message_reaction_notifications(
+    Path(id): Path<PublicId>,
+    State(state): State<AppState>,
+    Extension(user_identity): Extension<UserIdentity>,
+) -> Result<Response<()>, ApiError> {
+    let message_id = MessageId::from(id.0);
+
+    // Only the author is notified of reactions, hence the only one who can mute them
+    let message = state.service.get_message(&message_id).await?;
+    if message.author_id.0 != user_identity.user_id {
+        return Err(ApiError::Forbidden);
+    }
+
+    let user_id = UserId::from(user_identity.user_id);
+    state
+        .service
+        .mute_reaction_notifications(&user_id, Some(&message_id))
+        .await?;
+    Ok(Response::ok(()))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/messages/{id}/reaction-notifications/mute",
+    tag = "messages",
+    params(
+        ("id" = String, Path, description = "Message ID")
+    ),
+    responses(
+        (status = 200, description = "Reaction notifications unmuted for this message"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal message error")
+    )
+)]
+#[tracing::instrument(skip(state, user_identity))]
+pub async fn unmute_message_reaction_notifications(
+    Path(id): Path<PublicId>,
+    State(state): State<AppState>,
+    Extension(user_identity): Extension<UserIdentity>,
+) -> Result<Response<()>, ApiError> {
+    let message_id = MessageId::from(id.0);
+    let user_id = UserId::from(user_identity.user_id);
+
+    state
+        .service
+        .unmute_reaction_notifications(&user_id, Some(&message_id))
+        .await?;
+    Ok(Response::deleted(()))
+}
+
+#[utoipa::path(
+    put,
+    path = "/messages/{id}/follow",
+    tag = "messages",
+    params(
+        ("id" = String, Path, description = "Message ID")
+    ),
+    responses(
+        (status = 200, description = "Thread followed, or already followed", body = ThreadFollowResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "Message not found"),
+        (status = 500, description = "Internal message error")
+    )
+)]
+#[tracing::instrument(skip(state, user_identity))]
+pub async fn follow_thread(
+    Path(id): Path<PublicId>,
+    State(state): State<AppState>,
+    Extension(user_identity): Extension<UserIdentity>,
+) -> Result<Response<ThreadFollowResponse>, ApiError> {
+    let user_id = UserId::from(user_identity.user_id);
+
+    let follow = state
+        .service
+        .follow_thread(&user_id, &MessageId::from(id.0))
+        .await?;
+    Ok(Response::ok(follow.into()))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/messages/{id}/follow",
+    tag = "messages",
+    params(
+        ("id" = String, Path, description = "Message ID")
+    ),
+    responses(
+        (status = 200, description = "Thread unfollowed"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal message error")
+    )
+)]
+#[tracing::instrument(skip(state, user_identity))]
+pub async fn unfollow_thread(
+    Path(id): Path<PublicId>,
+    State(state): State<AppState>,
+    Extension(user_identity): Extension<UserIdentity>,
+) -> Result<Response<()>, ApiError> {
+    let user_id = UserId::from(user_identity.user_id);
+
+    state
+        .service
+        .unfollow_thread(&user_id, &MessageId::from(id.0))
+        .await?;
+    Ok(Response::deleted(()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/me/followed-threads",
+    tag = "messages",
+    params(GetPaginated),
+    responses(
+        (status = 200, description = "Threads the user follows, most recently followed first", body = PaginatedResponse<MessageResponse>),
+        (status = 400, description = "Bad request - Page or limit out of bounds"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal message error")
+    )
+)]
+#[tracing::instrument(skip(state, user_identity, pagination))]
+pub async fn list_followed_threads(
+    State(state): State<AppState>,
+    Extension(user_identity): Extension<UserIdentity>,
+    ValidatedQuery(pagination): ValidatedQuery<GetPaginated>,
+) -> Result<Response<Vec<MessageResponse>>, ApiError> {
+    let user_id = UserId::from(user_identity.user_id);
+    let (threads, total) = state
+        .service
+        .list_followed_threads(&user_id, &pagination)
+        .await?;
+
+    // Threads of channels the user can no longer view stay followed, but hidden
+    let mut channels: HashMap<ChannelId, bool> = HashMap::new();
+    let mut visible = Vec::with_capacity(threads.len());
+    for thread in threads {
+        let allowed = match channels.get(&thread.channel_id) {
+            Some(allowed) => *allowed,
+            None => {
+                let allowed = authorize_channel(
+                    state.authz.as_ref(),
+                    user_identity.user_id,
+                    Permission::ViewChannels,
+                    thread.channel_id,
+                )
+                .await
+                .is_ok();
+                channels.insert(thread.channel_id, allowed);
+                allowed
+            }
+        };
+        if allowed {
+            visible.push(MessageResponse::from(thread));
+        }
+    }
+
+    Ok(Response::page(PaginatedResponse::new(
+        visible,
+        total,
+        pagination.page,
+        pagination.limit,
+    )))
+}
+
+#[utoipa::path(
+    put,
+    path = "/messages/{id}/save",
+    tag = "messages",
+    params(
+        ("id" = String, Path, description = "Message ID")
+    ),
+    responses(
+        (status = 200, description = "Message saved, or already saved", body = SavedMessageResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "Message not found"),
+        (status = 500, description = "Internal message error")
+    )
+)]
+#[tracing::instrument(skip(state, user_identity))]
+pub async fn save_message(
+    Path(id): Path<PublicId>,
+    State(state): State<AppState>,
+    Extension(user_identity): Extension<UserIdentity>,
+) -> Result<Response<SavedMessageResponse>, ApiError> {
+    let user_id = UserId::from(user_identity.user_id);
+
+    let saved = state
+        .service
+        .save_message(&user_id, &MessageId::from(id.0))
+        .await?;
+    Ok(Response::ok(saved.into()))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/messages/{id}/save",
+    tag = "messages",
+    params(
+        ("id" = String, Path, description = "Message ID")
+    ),
+    responses(
+        (status = 200, description = "Message removed from the saved messages"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal message error")
+    )
+)]
+#[tracing::instrument(skip(state, user_identity))]
+pub async fn unsave_message(
+    Path(id): Path<PublicId>,
+    State(state): State<AppState>,
+    Extension(user_identity): Extension<UserIdentity>,
+) -> Result<Response<()>, ApiError> {
+    let user_id = UserId::from(user_identity.user_id);
+
+    state
+        .service
+        .unsave_message(&user_id, &MessageId::from(id.0))
+        .await?;
+    Ok(Response::deleted(()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/users/me/saved-messages",
+    tag = "messages",
+    params(GetPaginated),
+    responses(
+        (status = 200, description = "Messages the user saved, most recently saved first", body = PaginatedResponse<MessageResponse>),
+        (status = 400, description = "Bad request - Page or limit out of bounds"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal message error")
+    )
+)]
+#[tracing::instrument(skip(state, user_identity, pagination))]
+pub async fn list_saved_messages(
+    State(state): State<AppState>,
+    Extension(user_identity): Extension<UserIdentity>,
+    ValidatedQuery(pagination): ValidatedQuery<GetPaginated>,
+) -> Result<Response<Vec<MessageResponse>>, ApiError> {
+    let user_id = UserId::from(user_identity.user_id);
+    let (messages, total) = state
+        .service
+        .list_saved_messages(&user_id, &pagination)
+        .await?;
+
+    // Messages of channels the user can no longer view stay saved, but hidden
+    let mut channels: HashMap<ChannelId, bool> = HashMap::new();
+    let mut visible = Vec::with_capacity(messages.len());
+    for message in messages {
+        let allowed = match channels.get(&message.channel_id) {
+            Some(allowed) => *allowed,
+            None => {
+                let allowed = authorize_channel(
+                    state.authz.as_ref(),
+                    user_identity.user_id,
+                    Permission::ViewChannels,
+                    message.channel_id,
+                )
+                .await
+                .is_ok();
+                channels.insert(message.channel_id, allowed);
+                allowed
+            }
+        };
+        if allowed {
+            visible.push(MessageResponse::from(message));
+        }
+    }
+
+    Ok(Response::page(PaginatedResponse::new(
+        visible,
+        total,
+        pagination.page,
+        pagination.limit,
+    )))
+}
+
+#[utoipa::path(
+    get,
+    path = "/me/thread-preferences",
+    tag = "messages",
+    responses(
+        (status = 200, description = "Thread preferences of the user", body = ThreadPreferencesResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal message error")
+    )
+)]
+#[tracing::instrument(skip(state, user_identity))]
+pub async fn get_thread_preferences(
+    State(state): State<AppState>,
+    Extension(user_identity): Extension<UserIdentity>,
+) -> Result<Response<ThreadPreferencesResponse>, ApiError> {
+    let user_id = UserId::from(user_identity.user_id);
+
+    let preferences = state.service.thread_preferences(&user_id).await?;
+    Ok(Response::ok(preferences.into()))
+}
+
+#[utoipa::path(
+    put,
+    path = "/me/thread-preferences",
+    tag = "messages",
+    request_body = UpdateThreadPreferencesRequest,
+    responses(
+        (status = 200, description = "Thread preferences updated", body = ThreadPreferencesResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal message error")
+    )
+)]
+#[tracing::instrument(skip(state, user_identity, request))]
+pub async fn update_thread_preferences(
+    State(state): State<AppState>,
+    Extension(user_identity): Extension<UserIdentity>,
+    Json(request): Json<UpdateThreadPreferencesRequest>,
+) -> Result<Response<ThreadPreferencesResponse>, ApiError> {
+    let preferences = ThreadPreferences {
+        user_id: UserId::from(user_identity.user_id),
+        auto_follow_on_reply: request.auto_follow_on_reply,
+    };
+
+    let preferences = state.service.set_thread_preferences(preferences).await?;
+    Ok(Response::ok(preferences.into()))
+}
+
+#[utoipa::path(
+    put,
+    path = "/reaction-notifications/mute",
+    tag = "messages",
+    responses(
+        (status = 200, description = "Reaction notifications muted for all of the user's messages"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal message error")
+    )
+)]
+#[tracing::instrument(skip(state, user_identity))]
+pub async fn mute_reaction_notifications(
+    State(state): State<AppState>,
+    Extension(user_identity): Extension<UserIdentity>,
+) -> Result<Response<()>, ApiError> {
+    let user_id = UserId::from(user_identity.user_id);
+
+    state
+        .service
+        .mute_reaction_notifications(&user_id, None)
+        .await?;
+    Ok(Response::ok(()))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/reaction-notifications/mute",
+    tag = "messages",
+    responses(
+        (status = 200, description = "Reaction notifications unmuted for all of the user's messages"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal message error")
+    )
+)]
+#[tracing::instrument(skip(state, user_identity))]
+pub async fn unmute_reaction_notifications(
+    State(state): State<AppState>,
+    Extension(user_identity): Extension<UserIdentity>,
+) -> Result<Response<()>, ApiError> {
+    let user_id = UserId::from(user_identity.user_id);
+
+    state
+        .service
+        .unmute_reaction_notifications(&user_id, None)
+        .await?;
+    Ok(Response::deleted(()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/channels/{channel_id}/messages/similar",
+    tag = "messages",
+    params(
+        ("channel_id" = String, Path, description = "Channel ID")
+    ),
+    request_body = SimilarMessagesRequest,
+    responses(
+        (status = 200, description = "Recent messages of the channel similar to the draft, most similar first, one per thread", body = Vec<SimilarMessageResponse>),
+        (status = 400, description = "Bad request - Empty draft"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 500, description = "Internal message error"),
+        (status = 503, description = "Message search is not configured")
+    )
+)]
+#[tracing::instrument(skip(state, request), fields(channel_id = %channel_id))]
+pub async fn list_similar_messages(
+    State(state): State<AppState>,
+    AuthorizedChannel(channel_id): AuthorizedChannel,
+    Json(request): Json<SimilarMessagesRequest>,
+) -> Result<Response<Vec<SimilarMessageResponse>>, ApiError> {
+    let search = state
+        .search
+        .as_ref()
+        .ok_or_else(|| ApiError::ServiceUnavailable {
+            msg: "message search is not configured".to_string(),
+        })?;
+    if request.content.trim().is_empty() {
+        return Err(ApiError::BadRequest {
+            msg: "content must not be empty".to_string(),
+        });
+    }
+
+    let query = SimilarMessagesQuery {
+        channel_id,
+        content: request.content,
+        limit: request
+            .limit
+            .unwrap_or(DEFAULT_SIMILAR_LIMIT)
+            .clamp(1, MAX_SIMILAR_LIMIT),
+    };
+    let similar = search.similar_messages(&query).await?;
+
+    Ok(Response::ok(
+        similar
+            .into_iter()
+            .map(SimilarMessageResponse::from)
+            .collect(),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/channels/{channel_id}/pins",
+    tag = "messages",
+    params(
+        ("channel_id" = String, Path, description = "Channel ID"),
+        GetPaginated
+    ),
+    responses(
+        (status = 200, description = "Pinned messages of the channel, most recently pinned first", body = PaginatedResponse<MessageResponse>),
+        (status = 400, description = "Bad request - Page or limit out of bounds"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 500, description = "Internal message error")
+    )
+)]
+#[tracing::instrument(skip(state, pagination), fields(channel_id = %channel_id))]
+pub async fn list_pinned_messages(
+    State(state): State<AppState>,
+    AuthorizedChannel(channel_id): AuthorizedChannel,
+    ValidatedQuery(pagination): ValidatedQuery<GetPaginated>,
+) -> Result<Response<Vec<MessageResponse>>, ApiError> {
+    let cache_key = ListCacheKey {
+        channel_id,
+        page: pagination.page,
+        limit: pagination.limit,
+    };
+    let (messages, total) = match state.list_cache.get_pinned(&cache_key) {
+        Some(cached) => cached,
+        None => {
+            let listing = state
+                .service
+                .list_pinned_messages(&channel_id, &pagination)
+                .await?;
+            state.list_cache.insert_pinned(cache_key, listing.clone());
+            listing
+        }
+    };
+
+    Ok(Response::page(PaginatedResponse::new(
+        messages.into_iter().map(MessageResponse::from).collect(),
+        total,
+        pagination.page,
+        pagination.limit,
+    ))
+    .with_cache_control(state.list_cache.ttl()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/channels/{channel_id}/threads",
+    tag = "messages",
+    params(
+        ("channel_id" = String, Path, description = "Channel ID"),
+        GetPaginated,
+        ListThreadsParams
+    ),
+    responses(
+        (status = 200, description = "Messages of the channel with replies, most recently active first", body = PaginatedResponse<MessageResponse>),
+        (status = 400, description = "Bad request - Page or limit out of bounds"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 500, description = "Internal message error")
+    )
+)]
+#[tracing::instrument(skip(state, pagination), fields(channel_id = %channel_id))]
+pub async fn list_threads(
+    State(state): State<AppState>,
+    AuthorizedChannel(channel_id): AuthorizedChannel,
+    ValidatedQuery(pagination): ValidatedQuery<GetPaginated>,
+    Query(params): Query<ListThreadsParams>,
+) -> Result<Response<Vec<MessageResponse>>, ApiError> {
+    let (threads, total) = state
+        .service
+        .list_threads(&channel_id, params.include_archived, &pagination)
+        .await?;
+
+    Ok(Response::page(PaginatedResponse::new(
+        threads.into_iter().map(MessageResponse::from).collect(),
+        total,
+        pagination.page,
+        pagination.limit,
+    )))
+}
+
+#[utoipa::path(
+    get,
+    path = "/channels/{channel_id}/digest",
+    tag = "messages",
+    params(
+        ("channel_id" = String, Path, description = "Channel ID"),
+        GetChannelDigestParams
+    ),
+    responses(
+        (status = 200, description = "Summary of the channel activity since the given instant", body = ChannelDigestResponse),
+        (status = 400, description = "Bad request - Missing or invalid since"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 500, description = "Internal message error")
+    )
+)]
+#[tracing::instrument(skip(state), fields(channel_id = %channel_id))]
+pub async fn get_channel_digest(
+    State(state): State<AppState>,
+    AuthorizedChannel(channel_id): AuthorizedChannel,
+    Query(params): Query<GetChannelDigestParams>,
+) -> Result<Response<ChannelDigestResponse>, ApiError> {
+    let digest = state
+        .service
+        .channel_digest(&channel_id, params.since)
+        .await?;
+
+    Ok(Response::ok(digest.into()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/users/{id}/messages",
+    tag = "messages",
+    params(
+        ("id" = String, Path, description = "User ID"),
+        GetAuthorMessagesParams,
+        GetPaginated
+    ),
+    responses(
+        (status = 200, description = "Messages of the user in the channel, newest first", body = PaginatedResponse<MessageResponse>),
+        (status = 400, description = "Bad request - Missing or invalid channel_id, page or limit out of bounds"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 500, description = "Internal message error")
+    )
+)]
+#[tracing::instrument(skip(state, user_identity, pagination))]
+pub async fn list_author_messages(
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+    Extension(user_identity): Extension<UserIdentity>,
+    Query(params): Query<GetAuthorMessagesParams>,
+    ValidatedQuery(pagination): ValidatedQuery<GetPaginated>,
+) -> Result<Response<Vec<MessageResponse>>, ApiError> {
+    let channel_id = ChannelId::from(params.channel_id.0);
+    // users can review their own messages, moderators those of anyone
+    let permission = if id == user_identity.user_id {
+        Permission::ViewChannels
+    } else {
+        Permission::ManageMessages
+    };
+    authorize_channel(
+        state.authz.as_ref(),
+        user_identity.user_id,
+        permission,
+        channel_id,
+    )
+    .await?;
+
+    let (messages, total) = state
+        .service
+        .list_author_messages(&AuthorId::from(id), &channel_id, &pagination)
+        .await?;
+
+    Ok(Response::page(PaginatedResponse::new(
+        messages.into_iter().map(MessageResponse::from).collect(),
+        total,
+        pagination.page,
+        pagination.limit,
+    )))
+}
@@ -2,18 +2,188 @@ use utoipa_axum::{router::OpenApiRouter, routes};
 
 use crate::{
     http::messages::handlers::{
-        __path_create_message, __path_delete_message, __path_get_message, __path_list_messages,
-        __path_update_message, create_message, delete_message, get_message, list_messages,
-        update_message,
+        __path_add_reaction, __path_bulk_delete_messages, __path_create_export_link,
+        __path_create_message, __path_create_messages_batch, __path_delete_message,
+        __path_download_export, __path_export_channel_messages, __path_follow_thread,
+        __path_get_channel_digest, __path_get_last_messages, __path_get_message,
+        __path_get_message_history, __path_get_messages_batch, __path_get_thread_preferences,
+        __path_import_channel_messages, __path_list_author_messages, __path_list_followed_threads,
+        __path_list_messages, __path_list_moderation_reasons, __path_list_pinned_messages,
+        __path_list_reaction_users, __path_list_replies, __path_list_saved_messages,
+        __path_list_similar_messages, __path_list_threads,
+        __path_mute_message_reaction_notifications, __path_mute_reaction_notifications,
+        __path_remove_reaction, __path_save_message, __path_unfollow_thread,
+        __path_unmute_message_reaction_notifications, __path_unmute_reaction_notifications,
+        __path_unsave_message, __path_update_message, __path_update_thread_preferences,
+        add_reaction, bulk_delete_messages, create_export_link, create_message,
+        create_messages_batch, delete_message, download_export, export_channel_messages,
+        follow_thread, get_channel_digest, get_last_messages, get_message, get_message_history,
+        get_messages_batch, get_thread_preferences, import_channel_messages, list_author_messages,
+        list_followed_threads, list_messages, list_moderation_reasons, list_pinned_messages,
+        list_reaction_users, list_replies, list_saved_messages, list_similar_messages,
+        list_threads, mute_message_reaction_notifications, mute_reaction_notifications,
+        remove_reaction, save_message, unfollow_thread, unmute_message_reaction_notifications,
+        unmute_reaction_notifications, unsave_message, update_message, update_thread_preferences,
+    },
+    http::server::{
+        AppState,
+        authorization::Permission,
+        channel_access::{route_authorized_by_handler, route_with_permission},
     },
-    http::server::AppState,
 };
 
+/// Message routes.
+///
+/// Every route declares who may call it: those declared with
+/// [`route_with_permission`] are only reachable with the permission on the
+/// channel of the path, the others with [`route_authorized_by_handler`] name
+/// the check their handler makes. A route declared with neither fails
+/// `route_permission_tests`.
 pub fn message_routes() -> OpenApiRouter<AppState> {
     OpenApiRouter::new()
-        .routes(routes!(create_message))
-        .routes(routes!(get_message))
-        .routes(routes!(list_messages))
-        .routes(routes!(update_message))
-        .routes(routes!(delete_message))
+        .routes(route_authorized_by_handler(
+            "SendMessages on the channel of the body",
+            routes!(create_message),
+        ))
+        .routes(route_authorized_by_handler(
+            "SendMessages on the channel of each message",
+            routes!(create_messages_batch),
+        ))
+        // permissions are checked per message, their channels being unknown up front
+        .routes(route_authorized_by_handler(
+            "ViewChannels on the channel of each message",
+            routes!(get_messages_batch),
+        ))
+        .routes(route_authorized_by_handler(
+            "ViewChannels on each channel",
+            routes!(get_last_messages),
+        ))
+        .routes(route_with_permission(
+            Permission::ViewChannels,
+            routes!(get_message),
+        ))
+        .routes(route_with_permission(
+            Permission::ManageMessages,
+            routes!(get_message_history),
+        ))
+        .routes(route_with_permission(
+            Permission::ViewChannels,
+            routes!(list_messages),
+        ))
+        .routes(route_with_permission(
+            Permission::ManageMessages,
+            routes!(export_channel_messages),
+        ))
+        .routes(route_with_permission(
+            Permission::ManageMessages,
+            routes!(create_export_link),
+        ))
+        .routes(route_with_permission(
+            Permission::ManageChannels,
+            routes!(import_channel_messages),
+        ))
+        .routes(route_with_permission(
+            Permission::ViewChannels,
+            routes!(list_similar_messages),
+        ))
+        .routes(route_with_permission(
+            Permission::ViewChannels,
+            routes!(get_channel_digest),
+        ))
+        .routes(route_with_permission(
+            Permission::ViewChannels,
+            routes!(list_pinned_messages),
+        ))
+        .routes(route_with_permission(
+            Permission::ViewChannels,
+            routes!(list_threads),
+        ))
+        // `{id}` is the author here, not a message
+        .routes(route_authorized_by_handler(
+            "ViewChannels on the channel of the query, ManageMessages for another author",
+            routes!(list_author_messages),
+        ))
+        .routes(route_authorized_by_handler(
+            "the author of the message",
+            routes!(update_message),
+        ))
+        .routes(route_authorized_by_handler(
+            "the author of the message",
+            routes!(delete_message),
+        ))
+        .routes(route_with_permission(
+            Permission::ManageMessages,
+            routes!(bulk_delete_messages),
+        ))
+        .routes(route_authorized_by_handler(
+            "any authenticated user",
+            routes!(list_moderation_reasons),
+        ))
+        .routes(route_with_permission(
+            Permission::SendMessages,
+            routes!(add_reaction),
+        ))
+        // users can always remove their own reactions
+        .routes(route_authorized_by_handler(
+            "the caller's own reaction",
+            routes!(remove_reaction),
+        ))
+        .routes(route_with_permission(
+            Permission::ViewChannels,
+            routes!(list_reaction_users),
+        ))
+        .routes(route_with_permission(
+            Permission::ViewChannels,
+            routes!(list_replies),
+        ))
+        .routes(route_authorized_by_handler(
+            "the author of the message",
+            routes!(
+                mute_message_reaction_notifications,
+                unmute_message_reaction_notifications
+            ),
+        ))
+        .routes(route_authorized_by_handler(
+            "the caller's own preferences",
+            routes!(mute_reaction_notifications, unmute_reaction_notifications),
+        ))
+        .routes(route_with_permission(
+            Permission::ViewChannels,
+            routes!(follow_thread),
+        ))
+        // users can always stop following a thread
+        .routes(route_authorized_by_handler(
+            "the caller's own follows",
+            routes!(unfollow_thread),
+        ))
+        .routes(route_authorized_by_handler(
+            "the caller's own follows, in channels they can view",
+            routes!(list_followed_threads),
+        ))
+        .routes(route_with_permission(
+            Permission::ViewChannels,
+            routes!(save_message),
+        ))
+        // users can always remove a message from their saved messages
+        .routes(route_authorized_by_handler(
+            "the caller's own saved messages",
+            routes!(unsave_message),
+        ))
+        .routes(route_authorized_by_handler(
+            "the caller's own saved messages, in channels they can view",
+            routes!(list_saved_messages),
+        ))
+        .routes(route_authorized_by_handler(
+            "the caller's own preferences",
+            routes!(get_thread_preferences, update_thread_preferences),
+        ))
+}
+
+/// Downloads of encrypted exports, authorized by their signed link alone and
+/// so served outside authentication
+pub fn export_download_routes() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new().routes(route_authorized_by_handler(
+        "anyone holding the signed link",
+        routes!(download_export),
+    ))
 }
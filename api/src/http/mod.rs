@@ -1,3 +1,12 @@
+pub mod admin;
+pub mod attachments;
+pub mod channel_locks;
+pub mod channel_settings;
+pub mod graphql;
 pub mod health;
+pub mod legal_holds;
 pub mod messages;
+pub mod read_markers;
 pub mod server;
+pub mod storage;
+pub mod ws;
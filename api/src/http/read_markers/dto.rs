@@ -0,0 +1,55 @@
+use chrono::{DateTime, Utc};
+use communities_core::domain::message::entities::{ReadMarker, UnreadCount};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::http::server::public_id::PublicId;
+
+/// Last message of a channel the user read; messages with a greater
+/// `sequence` are new to them
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ReadMarkerResponse {
+    #[schema(value_type = String)]
+    pub channel_id: PublicId,
+    #[schema(value_type = String)]
+    pub last_read_message_id: PublicId,
+    pub last_read_sequence: u64,
+    pub read_at: DateTime<Utc>,
+}
+
+/// Messages of a channel posted after the read marker of the user
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct UnreadCountResponse {
+    #[schema(value_type = String)]
+    pub channel_id: PublicId,
+    pub last_read_sequence: u64,
+    pub unread_count: u64,
+}
+
+/// Message of the channel the user read last
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct SetReadMarkerRequest {
+    #[schema(value_type = String)]
+    pub message_id: PublicId,
+}
+
+impl From<ReadMarker> for ReadMarkerResponse {
+    fn from(marker: ReadMarker) -> Self {
+        Self {
+            channel_id: marker.channel_id.0.into(),
+            last_read_message_id: marker.last_read_message_id.0.into(),
+            last_read_sequence: marker.last_read_sequence,
+            read_at: marker.read_at,
+        }
+    }
+}
+
+impl From<UnreadCount> for UnreadCountResponse {
+    fn from(count: UnreadCount) -> Self {
+        Self {
+            channel_id: count.channel_id.0.into(),
+            last_read_sequence: count.last_read_sequence,
+            unread_count: count.unread_count,
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use axum::{Extension, Json, extract::State};
+use communities_core::domain::message::{
+    entities::{MessageId, UnreadCount, UserId},
+    ports::MessageService,
+};
+
+use crate::http::{
+    read_markers::dto::{ReadMarkerResponse, SetReadMarkerRequest, UnreadCountResponse},
+    server::{
+        ApiError, AppState, Response,
+        authorization::Permission,
+        cache::UnreadCacheKey,
+        channel_access::{AuthorizedChannel, authorize_channel},
+        middleware::auth::entities::UserIdentity,
+    },
+};
+
+#[utoipa::path(
+    get,
+    path = "/channels/{channel_id}/read-marker",
+    tag = "read_markers",
+    params(
+        ("channel_id" = String, Path, description = "Channel ID")
+    ),
+    responses(
+        (status = 200, description = "Last message of the channel the user read", body = ReadMarkerResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "The user read no message of the channel yet"),
+        (status = 500, description = "Internal message error")
+    )
+)]
+#[tracing::instrument(skip(state, user_identity), fields(channel_id = %channel_id))]
+pub async fn get_read_marker(
+    State(state): State<AppState>,
+    AuthorizedChannel(channel_id): AuthorizedChannel,
+    Extension(user_identity): Extension<UserIdentity>,
+) -> Result<Response<ReadMarkerResponse>, ApiError> {
+    let user_id = UserId::from(user_identity.user_id);
+
+    let marker = state.service.read_marker(&user_id, &channel_id).await?;
+    Ok(Response::ok(marker.into()))
+}
+
+#[utoipa::path(
+    put,
+    path = "/channels/{channel_id}/read-marker",
+    tag = "read_markers",
+    params(
+        ("channel_id" = String, Path, description = "Channel ID")
+    ),
+    request_body = SetReadMarkerRequest,
+    responses(
+        (status = 200, description = "Read marker of the user, left on a later message they read already", body = ReadMarkerResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "Message not found in the channel"),
+        (status = 500, description = "Internal message error")
+    )
+)]
+#[tracing::instrument(skip(state, user_identity, request), fields(channel_id = %channel_id))]
+pub async fn set_read_marker(
+    State(state): State<AppState>,
+    AuthorizedChannel(channel_id): AuthorizedChannel,
+    Extension(user_identity): Extension<UserIdentity>,
+    Json(request): Json<SetReadMarkerRequest>,
+) -> Result<Response<ReadMarkerResponse>, ApiError> {
+    let user_id = UserId::from(user_identity.user_id);
+
+    let marker = state
+        .service
+        .set_read_marker(
+            &user_id,
+            &channel_id,
+            &MessageId::from(request.message_id.0),
+        )
+        .await?;
+    Ok(Response::ok(marker.into()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/channels/unread-counts",
+    tag = "read_markers",
+    responses(
+        (status = 200, description = "Messages posted after the read marker of the user, for each channel they read and can still view", body = Vec<UnreadCountResponse>),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal message error")
+    )
+)]
+#[tracing::instrument(skip(state, user_identity))]
+pub async fn get_unread_counts(
+    State(state): State<AppState>,
+    Extension(user_identity): Extension<UserIdentity>,
+) -> Result<Response<Vec<UnreadCountResponse>>, ApiError> {
+    let user_id = UserId::from(user_identity.user_id);
+    let markers = state.service.read_markers(&user_id).await?;
+
+    let mut counts = Vec::with_capacity(markers.len());
+    let mut uncached = Vec::new();
+    for marker in markers {
+        // channels the user was removed from since are left out
+        match authorize_channel(
+            state.authz.as_ref(),
+            user_identity.user_id,
+            Permission::ViewChannels,
+            marker.channel_id,
+        )
+        .await
+        {
+            Ok(()) => {}
+            Err(ApiError::Forbidden) => continue,
+            Err(e) => return Err(e),
+        }
+
+        let key = UnreadCacheKey {
+            channel_id: marker.channel_id,
+            last_read_sequence: marker.last_read_sequence,
+        };
+        match state.list_cache.get_unread(&key) {
+            Some(unread_count) => counts.push(UnreadCount {
+                channel_id: marker.channel_id,
+                last_read_sequence: marker.last_read_sequence,
+                unread_count,
+            }),
+            None => uncached.push(marker),
+        }
+    }
+
+    for count in state.service.unread_counts(&uncached).await? {
+        state.list_cache.insert_unread(
+            UnreadCacheKey {
+                channel_id: count.channel_id,
+                last_read_sequence: count.last_read_sequence,
+            },
+            count.unread_count,
+        );
+        counts.push(count);
+    }
+
+    Ok(Response::ok(counts.into_iter().map(Into::into).collect()))
+}
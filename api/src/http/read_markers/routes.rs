@@ -0,0 +1,26 @@
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+use crate::http::{
+    read_markers::handlers::{
+        __path_get_read_marker, __path_get_unread_counts, __path_set_read_marker, get_read_marker,
+        get_unread_counts, set_read_marker,
+    },
+    server::{
+        AppState,
+        authorization::Permission,
+        channel_access::{route_authorized_by_handler, route_with_permission},
+    },
+};
+
+/// Last message of each channel users read, for clients to separate the new ones
+pub fn read_marker_routes() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new()
+        .routes(route_with_permission(
+            Permission::ViewChannels,
+            routes!(get_read_marker, set_read_marker),
+        ))
+        .routes(route_authorized_by_handler(
+            "ViewChannels on each channel",
+            routes!(get_unread_counts),
+        ))
+}
@@ -1,21 +1,30 @@
 use axum::{
     Json,
-    http::StatusCode,
+    http::{HeaderValue, StatusCode, header},
     response::{IntoResponse, Response},
 };
 use communities_core::domain::common::CoreError;
 use serde::Serialize;
 use thiserror::Error;
 
+use crate::http::server::validated_query::FieldError;
+
 /// Unified error type for HTTP API responses
 #[derive(Debug, Error, Clone)]
 pub enum ApiError {
     #[error("Service is unavailable: {msg}")]
     ServiceUnavailable { msg: String },
+    /// This replica serves reads only
+    #[error("This replica is read-only")]
+    ReadOnly,
     #[error("Internal server error")]
     InternalServerError,
     #[error("Startup error: {msg}")]
     StartupError { msg: String },
+    #[error("Address already in use: {addr}")]
+    AddressInUse { addr: String },
+    #[error("Permission denied while binding {addr}")]
+    BindPermissionDenied { addr: String },
     #[error("Unauthorized access")]
     Unauthorized,
     #[error("Forbidden")]
@@ -24,39 +33,118 @@ pub enum ApiError {
     NotFound,
     #[error("Bad request: {msg}")]
     BadRequest { msg: String },
+    /// A bad request clients can tell apart by its code
+    #[error("{msg}")]
+    InvalidInput { error_code: String, msg: String },
+    /// Query parameters missing, malformed or out of bounds, listed by field
+    #[error("Invalid query parameters: {}", describe_fields(.fields))]
+    InvalidQuery { fields: Vec<FieldError> },
     #[error("Conflict")]
     Conflict { error_code: String },
+    #[error("Payload too large")]
+    PayloadTooLarge { error_code: String },
+    #[error("Precondition failed")]
+    PreconditionFailed { error_code: String },
+    #[error("{msg}")]
+    UnprocessableEntity { error_code: String, msg: String },
+    /// Answered with a `Retry-After` header
+    #[error("Locked, retry in {retry_after_secs}s")]
+    Locked {
+        error_code: String,
+        retry_after_secs: u64,
+    },
+}
+
+fn describe_fields(fields: &[FieldError]) -> String {
+    fields
+        .iter()
+        .map(|field| format!("{} {}", field.field, field.message))
+        .collect::<Vec<_>>()
+        .join(", ")
 }
 
 impl ApiError {
     fn status_code(&self) -> StatusCode {
         match self {
             ApiError::StartupError { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+            ApiError::AddressInUse { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+            ApiError::BindPermissionDenied { .. } => StatusCode::INTERNAL_SERVER_ERROR,
             ApiError::ServiceUnavailable { .. } => StatusCode::SERVICE_UNAVAILABLE,
-            ApiError::InternalServerError { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+            ApiError::ReadOnly => StatusCode::SERVICE_UNAVAILABLE,
+            ApiError::InternalServerError => StatusCode::INTERNAL_SERVER_ERROR,
             ApiError::Unauthorized => StatusCode::UNAUTHORIZED,
             ApiError::Forbidden => StatusCode::FORBIDDEN,
-            ApiError::NotFound { .. } => StatusCode::NOT_FOUND,
+            ApiError::NotFound => StatusCode::NOT_FOUND,
             ApiError::BadRequest { .. } => StatusCode::BAD_REQUEST,
+            ApiError::InvalidInput { .. } => StatusCode::BAD_REQUEST,
+            ApiError::InvalidQuery { .. } => StatusCode::BAD_REQUEST,
             ApiError::Conflict { .. } => StatusCode::CONFLICT,
+            ApiError::PayloadTooLarge { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+            ApiError::PreconditionFailed { .. } => StatusCode::PRECONDITION_FAILED,
+            ApiError::UnprocessableEntity { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiError::Locked { .. } => StatusCode::LOCKED,
         }
     }
 }
 
-impl Into<ErrorBody> for ApiError {
-    fn into(self) -> ErrorBody {
-        let status = self.status_code().as_u16();
-        let message = self.to_string();
+impl ApiError {
+    /// Stable machine readable code, used where several errors are reported at once
+    pub fn error_code(&self) -> &str {
         match self {
-            ApiError::Conflict { error_code } => ErrorBody {
-                message: message,
+            ApiError::ServiceUnavailable { .. } => "SERVICE_UNAVAILABLE",
+            ApiError::ReadOnly => "READ_ONLY",
+            ApiError::InternalServerError => "INTERNAL_SERVER_ERROR",
+            ApiError::StartupError { .. }
+            | ApiError::AddressInUse { .. }
+            | ApiError::BindPermissionDenied { .. } => "STARTUP_ERROR",
+            ApiError::Unauthorized => "UNAUTHORIZED",
+            ApiError::Forbidden => "FORBIDDEN",
+            ApiError::NotFound => "NOT_FOUND",
+            ApiError::BadRequest { .. } => "BAD_REQUEST",
+            ApiError::InvalidQuery { .. } => "INVALID_QUERY",
+            ApiError::Conflict { error_code }
+            | ApiError::PayloadTooLarge { error_code }
+            | ApiError::PreconditionFailed { error_code }
+            | ApiError::InvalidInput { error_code, .. }
+            | ApiError::UnprocessableEntity { error_code, .. }
+            | ApiError::Locked { error_code, .. } => error_code,
+        }
+    }
+}
+
+impl From<ApiError> for ErrorBody {
+    fn from(error: ApiError) -> Self {
+        let status = error.status_code().as_u16();
+        let message = error.to_string();
+        match error {
+            ApiError::Conflict { error_code }
+            | ApiError::PayloadTooLarge { error_code }
+            | ApiError::PreconditionFailed { error_code }
+            | ApiError::InvalidInput { error_code, .. }
+            | ApiError::UnprocessableEntity { error_code, .. }
+            | ApiError::Locked { error_code, .. } => ErrorBody {
+                message,
                 error_code: Some(error_code),
-                status: status,
+                status,
+                fields: Vec::new(),
+            },
+            ApiError::ReadOnly => ErrorBody {
+                message,
+                error_code: Some("READ_ONLY".to_string()),
+                status,
+                fields: Vec::new(),
+            },
+            ApiError::InvalidQuery { fields } => ErrorBody {
+                message,
+                error_code: Some("INVALID_QUERY".to_string()),
+                status,
+                fields,
             },
             _ => ErrorBody {
-                message: message,
+                message,
                 error_code: None,
-                status: status,
+                status,
+                fields: Vec::new(),
             },
         }
     }
@@ -64,7 +152,19 @@ impl Into<ErrorBody> for ApiError {
 
 impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
-        (self.status_code(), Json::<ErrorBody>(self.into())).into_response()
+        let retry_after = match &self {
+            ApiError::Locked {
+                retry_after_secs, ..
+            } => Some(*retry_after_secs),
+            _ => None,
+        };
+        let mut response = (self.status_code(), Json::<ErrorBody>(self.into())).into_response();
+        if let Some(secs) = retry_after {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(secs));
+        }
+        response
     }
 }
 
@@ -75,6 +175,102 @@ impl From<CoreError> for ApiError {
                 msg: "Service is unhealthy".to_string(),
             },
             CoreError::MessageNotFound { .. } => ApiError::NotFound,
+            CoreError::ReactionNotFound { .. } => ApiError::NotFound,
+            CoreError::InvalidReplyTarget { .. } => ApiError::BadRequest {
+                msg: "Replies must target a message of the same channel".to_string(),
+            },
+            CoreError::MessageAlreadyExists { .. } => ApiError::Conflict {
+                error_code: "MESSAGE_ALREADY_EXISTS".to_string(),
+            },
+            CoreError::InvalidMessageTimestamps { .. } => ApiError::BadRequest {
+                msg: "Messages cannot be updated before being created, nor created in the future"
+                    .to_string(),
+            },
+            CoreError::InvalidMessageNonce { max } => ApiError::BadRequest {
+                msg: format!("Message nonce must be 1 to {max} characters long"),
+            },
+            CoreError::InvalidMessageExpiry => ApiError::BadRequest {
+                msg: "Messages must expire in the future".to_string(),
+            },
+            CoreError::InvalidCursor => ApiError::BadRequest {
+                msg: "Invalid pagination cursor".to_string(),
+            },
+            CoreError::InvalidReaction { emoji } => ApiError::BadRequest {
+                msg: format!("Invalid reaction emoji: {}", emoji),
+            },
+            CoreError::ReactionNotAllowed { emoji, allowed } => ApiError::UnprocessableEntity {
+                error_code: "REACTION_NOT_ALLOWED".to_string(),
+                msg: format!(
+                    "Reaction {emoji} is not allowed in this channel, allowed reactions: {}",
+                    allowed.join(" ")
+                ),
+            },
+            CoreError::TooManyAllowedReactions { max } => ApiError::BadRequest {
+                msg: format!("Channels may allow at most {max} reactions"),
+            },
+            CoreError::InvalidModerationReason { max } => ApiError::BadRequest {
+                msg: format!("Moderation reasons need a code and at most {max} characters of text"),
+            },
+            CoreError::UnknownModerationReason { code } => ApiError::BadRequest {
+                msg: format!("Unknown moderation reason {code}"),
+            },
+            CoreError::AttachmentNotFound { id } => ApiError::UnprocessableEntity {
+                error_code: "ATTACHMENT_NOT_FOUND".to_string(),
+                msg: format!("Attachment {id} was not presigned for this channel by this user"),
+            },
+            CoreError::AttachmentNotUploaded { id } => ApiError::UnprocessableEntity {
+                error_code: "ATTACHMENT_NOT_UPLOADED".to_string(),
+                msg: format!("Attachment {id} must be uploaded before being posted"),
+            },
+            CoreError::InvalidAttachment { max_name_chars } => ApiError::BadRequest {
+                msg: format!(
+                    "Attachments need a name of 1 to {max_name_chars} characters, a content type and a size"
+                ),
+            },
+            CoreError::TooManyAttachments { max } => ApiError::InvalidInput {
+                error_code: "TOO_MANY_ATTACHMENTS".to_string(),
+                msg: format!("Messages carry at most {max} attachments"),
+            },
+            CoreError::AttachmentTooLarge { max_bytes } => ApiError::InvalidInput {
+                error_code: "ATTACHMENT_TOO_LARGE".to_string(),
+                msg: format!("Attachments are at most {max_bytes} bytes"),
+            },
+            CoreError::AttachmentTypeNotAllowed { content_type } => ApiError::InvalidInput {
+                error_code: "ATTACHMENT_TYPE_NOT_ALLOWED".to_string(),
+                msg: format!("Attachments of type {content_type} are not allowed"),
+            },
+            CoreError::AttachmentUploadsDisabled => ApiError::ServiceUnavailable {
+                msg: "Attachment uploads are not configured".to_string(),
+            },
+            CoreError::ChannelStorageQuotaExceeded { .. } => ApiError::PayloadTooLarge {
+                error_code: "CHANNEL_STORAGE_QUOTA_EXCEEDED".to_string(),
+            },
+            CoreError::PinLimitReached { .. } => ApiError::Conflict {
+                error_code: "PIN_LIMIT_REACHED".to_string(),
+            },
+            CoreError::MessageUnderLegalHold { .. } => ApiError::Conflict {
+                error_code: "MESSAGE_UNDER_LEGAL_HOLD".to_string(),
+            },
+            CoreError::MessageRevisionMismatch { .. } => ApiError::PreconditionFailed {
+                error_code: "MESSAGE_REVISION_MISMATCH".to_string(),
+            },
+            CoreError::ChannelWriteLocked {
+                retry_after_secs, ..
+            } => ApiError::Locked {
+                error_code: "CHANNEL_WRITE_LOCKED".to_string(),
+                retry_after_secs,
+            },
+            CoreError::InvalidChannelWriteLock { max_secs } => ApiError::BadRequest {
+                msg: format!("Channel write locks last between 1 and {max_secs} seconds"),
+            },
+            CoreError::InvalidExportRecipient { msg } => ApiError::BadRequest {
+                msg: format!("Invalid export recipient: {msg}"),
+            },
+            CoreError::LegalHoldNotFound { .. } => ApiError::NotFound,
+            CoreError::ReadMarkerNotFound { .. } => ApiError::NotFound,
+            CoreError::InvalidLegalHoldReference => ApiError::BadRequest {
+                msg: "Legal hold reference cannot be empty".to_string(),
+            },
             CoreError::InvalidMessageName => ApiError::BadRequest {
                 msg: "Server name cannot be empty".to_string(),
             },
@@ -88,4 +284,7 @@ pub struct ErrorBody {
     pub message: String,
     pub error_code: Option<String>,
     pub status: u16,
+    /// Faulty query parameters of `INVALID_QUERY` errors
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldError>,
 }
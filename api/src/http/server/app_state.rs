@@ -1,19 +1,54 @@
-use communities_core::{CommunitiesService, application::CommunitiesRepositories};
+use communities_core::{
+    CommunitiesService, application::CommunitiesSearch, domain::message::events::MessageEventBus,
+};
 use std::sync::Arc;
 
-use crate::http::server::authorization::DynAuthz;
+use crate::http::{
+    server::{
+        ApiError,
+        authorization::{DummyAuthz, DynAuthz},
+        cache::MessageListCache,
+        export_links::ExportLinks,
+        public_id::{IdObfuscation, PlainIds},
+        throttle::StreamThrottle,
+    },
+    ws::{
+        fanout::FanoutConfig,
+        replay::{RealtimeHub, ReplayConfig},
+    },
+};
 
 /// Application state shared across request handlers
 #[derive(Clone)]
 pub struct AppState {
     pub service: CommunitiesService,
     pub authz: DynAuthz,
+    pub list_cache: MessageListCache,
+    /// Events pushed to realtime subscribers
+    pub events: MessageEventBus,
+    /// Send buffering of realtime connections
+    pub fanout: FanoutConfig,
+    /// Numbered realtime events, replayed to reconnecting subscribers
+    pub realtime: RealtimeHub,
+    /// Message search, absent when no search backend is configured
+    pub search: Option<CommunitiesSearch>,
+    /// Bandwidth of streamed responses such as exports
+    pub stream_throttle: StreamThrottle,
+    /// Signed links to encrypted exports, absent when no link key is configured
+    pub export_links: Option<ExportLinks>,
+    /// Whether mutations are refused, on disaster-recovery replicas
+    pub read_only: bool,
+    /// Mapping of message and channel IDs to the IDs clients see
+    pub id_obfuscation: Arc<dyn IdObfuscation>,
 }
 
 impl AppState {
-    /// Create a new AppState with the given service and authorization client
-    pub fn new(service: CommunitiesService, authz: DynAuthz) -> Self {
-        Self { service, authz }
+    /// Start building an AppState around the given service.
+    ///
+    /// An authorization client must be provided explicitly, either through
+    /// [`AppStateBuilder::authz`] or by opting into [`AppStateBuilder::allow_all`].
+    pub fn builder(service: CommunitiesService) -> AppStateBuilder {
+        AppStateBuilder::new(service)
     }
 
     /// Shutdown the underlying database pool
@@ -22,16 +57,130 @@ impl AppState {
     }
 }
 
-impl From<CommunitiesRepositories> for AppState {
-    fn from(repositories: CommunitiesRepositories) -> Self {
-        // Fallback: create a permissive dummy authz client so code using `From`
-        // doesn't break. Most callers should construct AppState::new with a
-        // real authz client.
-        let service = CommunitiesService::new(
-            repositories.message_repository,
-            repositories.health_repository,
+/// Builder for [`AppState`] that refuses to silently fall back to a permissive authz client
+pub struct AppStateBuilder {
+    service: CommunitiesService,
+    authz: Option<DynAuthz>,
+    list_cache: Option<MessageListCache>,
+    events: Option<MessageEventBus>,
+    fanout: FanoutConfig,
+    replay: ReplayConfig,
+    search: Option<CommunitiesSearch>,
+    stream_throttle: StreamThrottle,
+    export_links: Option<ExportLinks>,
+    read_only: bool,
+    id_obfuscation: Arc<dyn IdObfuscation>,
+}
+
+impl AppStateBuilder {
+    pub fn new(service: CommunitiesService) -> Self {
+        Self {
+            service,
+            authz: None,
+            list_cache: None,
+            events: None,
+            fanout: FanoutConfig::default(),
+            replay: ReplayConfig::default(),
+            search: None,
+            stream_throttle: StreamThrottle::unlimited(),
+            export_links: None,
+            read_only: false,
+            id_obfuscation: Arc::new(PlainIds),
+        }
+    }
+
+    /// Use the given authorization client
+    pub fn authz(mut self, authz: DynAuthz) -> Self {
+        self.authz = Some(authz);
+        self
+    }
+
+    /// Explicitly opt into the allow-all [`DummyAuthz`] (local development and tests only)
+    pub fn allow_all(mut self) -> Self {
+        self.authz = Some(Arc::new(DummyAuthz::new()));
+        self
+    }
+
+    /// Replace the default listing cache (e.g. to apply configured TTLs)
+    pub fn list_cache(mut self, list_cache: MessageListCache) -> Self {
+        self.list_cache = Some(list_cache);
+        self
+    }
+
+    /// Feed realtime subscribers from another bus than the service's own
+    /// (e.g. one fed by a MongoDB change stream)
+    pub fn events(mut self, events: MessageEventBus) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Bound the frames queued per realtime connection, and choose what
+    /// happens to clients reading too slowly
+    pub fn fanout(mut self, fanout: FanoutConfig) -> Self {
+        self.fanout = fanout;
+        self
+    }
+
+    /// Size the buffer of recent events replayed to reconnecting realtime clients
+    pub fn replay(mut self, replay: ReplayConfig) -> Self {
+        self.replay = replay;
+        self
+    }
+
+    /// Enable the endpoints backed by message search
+    pub fn search(mut self, search: CommunitiesSearch) -> Self {
+        self.search = Some(search);
+        self
+    }
+
+    /// Limit the bandwidth of each streamed response
+    pub fn stream_throttle(mut self, stream_throttle: StreamThrottle) -> Self {
+        self.stream_throttle = stream_throttle;
+        self
+    }
+
+    /// Enable the signed links to encrypted exports
+    pub fn export_links(mut self, export_links: ExportLinks) -> Self {
+        self.export_links = Some(export_links);
+        self
+    }
+
+    /// Refuse mutations, including GraphQL ones
+    pub fn read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    /// Obfuscate the message and channel IDs of the API instead of exposing the UUIDs
+    pub fn id_obfuscation(mut self, id_obfuscation: Arc<dyn IdObfuscation>) -> Self {
+        self.id_obfuscation = id_obfuscation;
+        self
+    }
+
+    pub fn build(self) -> Result<AppState, ApiError> {
+        let authz = self.authz.ok_or_else(|| ApiError::StartupError {
+            msg: "no authorization client configured for AppState".to_string(),
+        })?;
+
+        let events = self.events.unwrap_or_else(|| self.service.events().clone());
+        let realtime = RealtimeHub::with_id_obfuscation(
+            events.clone(),
+            self.replay,
+            self.id_obfuscation.clone(),
         );
-        let authz = Arc::new(crate::http::server::authorization::DummyAuthz::new());
-        AppState { service, authz }
+
+        Ok(AppState {
+            service: self.service,
+            authz,
+            list_cache: self.list_cache.unwrap_or_default(),
+            events,
+            fanout: self.fanout,
+            realtime,
+            search: self.search,
+            stream_throttle: self.stream_throttle,
+            export_links: self.export_links,
+            read_only: self.read_only,
+            id_obfuscation: self.id_obfuscation,
+        })
     }
 }
@@ -16,6 +16,8 @@ pub enum Permission {
     SendMessages,
     ManageMessages,
     ManageChannels,
+    /// Issue requests on behalf of the user, for support debugging
+    ImpersonateUsers,
 }
 
 /// Simple error type for authz failures.
@@ -36,6 +38,12 @@ impl DummyAuthz {
     }
 }
 
+impl Default for DummyAuthz {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[async_trait::async_trait]
 impl Authorization for DummyAuthz {
     async fn check(&self, _actor: Uuid, _permission: Permission, _resource: Resource) -> Result<bool, AuthzError> {
@@ -63,19 +71,26 @@ mod spicedb_impl {
         }
     }
 
-    fn map_permission(p: Permission) -> ExtPermissions {
+    /// `None` for the permissions the SpiceDB schema does not define, which
+    /// are refused rather than borrowed from a broader one
+    fn map_permission(p: Permission) -> Option<ExtPermissions> {
         match p {
-            Permission::ViewChannels => ExtPermissions::ViewChannels,
-            Permission::SendMessages => ExtPermissions::SendMessages,
-            Permission::ManageMessages => ExtPermissions::ManageMessages,
-            Permission::ManageChannels => ExtPermissions::ManageChannels,
+            Permission::ViewChannels => Some(ExtPermissions::ViewChannels),
+            Permission::SendMessages => Some(ExtPermissions::SendMessages),
+            Permission::ManageMessages => Some(ExtPermissions::ManageMessages),
+            Permission::ManageChannels => Some(ExtPermissions::ManageChannels),
+            // managing the messages of a user must not be enough to act as them
+            Permission::ImpersonateUsers => None,
         }
     }
 
     #[async_trait::async_trait]
     impl Authorization for SpiceDbAuthz {
         async fn check(&self, actor: Uuid, permission: Permission, resource: Resource) -> Result<bool, AuthzError> {
-            let ext_perm = map_permission(permission);
+            let Some(ext_perm) = map_permission(permission) else {
+                tracing::warn!(?permission, "permission not defined in the SpiceDB schema, refused");
+                return Ok(false);
+            };
             let actor_obj = SpiceDbObject::User(actor.to_string());
 
             let resource_obj = match resource {
@@ -87,12 +102,9 @@ mod spicedb_impl {
             Ok(res.has_permissions())
         }
     }
-
-    // re-export for use by the app
-    pub use SpiceDbAuthz as SpiceDbAuthzImpl;
 }
 
 // Re-export the SpiceDbConfig from the external crate directly (public)
 pub use beep_authz::config::SpiceDbConfig;
-pub use spicedb_impl::SpiceDbAuthzImpl as SpiceDbAuthz;
+pub use spicedb_impl::SpiceDbAuthz;
 
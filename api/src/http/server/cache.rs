@@ -0,0 +1,150 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use communities_core::domain::{
+    common::{CoreError, GetPaginated, TotalPaginatedElements},
+    message::{
+        entities::{ChannelId, Message},
+        ports::MessageService,
+    },
+};
+use moka::sync::Cache;
+
+/// Cache key for a single page of a channel listing
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ListCacheKey {
+    pub channel_id: ChannelId,
+    pub page: u32,
+    pub limit: u32,
+}
+
+/// Cache key for the unread messages of a channel after a read sequence
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct UnreadCacheKey {
+    pub channel_id: ChannelId,
+    pub last_read_sequence: u64,
+}
+
+type CachedPage = (Vec<Message>, TotalPaginatedElements);
+
+/// Short-lived in-process cache for channel message and pin listings.
+///
+/// Listings are read far more often than they change, so a small TTL is
+/// enough to absorb read storms. Entries for a channel are invalidated as
+/// soon as a message in that channel is created, deleted, or (un)pinned.
+///
+/// Unread counts are cached by channel and read sequence rather than by user:
+/// the readers of a hot channel mostly caught up to the same few messages, so
+/// they share entries, which the admission policy keeps over the cold ones.
+#[derive(Clone)]
+pub struct MessageListCache {
+    inner: Cache<ListCacheKey, CachedPage>,
+    pinned: Cache<ListCacheKey, CachedPage>,
+    unread: Cache<UnreadCacheKey, u64>,
+    ttl: Duration,
+}
+
+impl MessageListCache {
+    pub fn new(ttl: Duration, max_capacity: u64) -> Self {
+        fn cache<K, V>(ttl: Duration, max_capacity: u64) -> Cache<K, V>
+        where
+            K: std::hash::Hash + Eq + Send + Sync + 'static,
+            V: Clone + Send + Sync + 'static,
+        {
+            Cache::builder()
+                .max_capacity(max_capacity)
+                .time_to_live(ttl)
+                .support_invalidation_closures()
+                .build()
+        }
+
+        Self {
+            inner: cache(ttl, max_capacity),
+            pinned: cache(ttl, max_capacity),
+            unread: cache(ttl, max_capacity),
+            ttl,
+        }
+    }
+
+    /// Time to live of cached entries, also advertised through `Cache-Control`
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    pub fn get(&self, key: &ListCacheKey) -> Option<(Vec<Message>, TotalPaginatedElements)> {
+        self.inner.get(key)
+    }
+
+    pub fn insert(&self, key: ListCacheKey, value: (Vec<Message>, TotalPaginatedElements)) {
+        self.inner.insert(key, value);
+    }
+
+    /// Cached page of the pinned messages of a channel
+    pub fn get_pinned(&self, key: &ListCacheKey) -> Option<CachedPage> {
+        self.pinned.get(key)
+    }
+
+    pub fn insert_pinned(&self, key: ListCacheKey, value: CachedPage) {
+        self.pinned.insert(key, value);
+    }
+
+    /// Cached number of messages of a channel after a read sequence
+    pub fn get_unread(&self, key: &UnreadCacheKey) -> Option<u64> {
+        self.unread.get(key)
+    }
+
+    pub fn insert_unread(&self, key: UnreadCacheKey, count: u64) {
+        self.unread.insert(key, count);
+    }
+
+    /// Drop every cached page and unread count of the given channel
+    pub fn invalidate_channel(&self, channel_id: ChannelId) {
+        for cache in [&self.inner, &self.pinned] {
+            if let Err(e) = cache.invalidate_entries_if(move |key, _| key.channel_id == channel_id)
+            {
+                tracing::warn!(error = %e, channel_id = %channel_id, "failed to invalidate list cache");
+            }
+        }
+        if let Err(e) = self
+            .unread
+            .invalidate_entries_if(move |key, _| key.channel_id == channel_id)
+        {
+            tracing::warn!(error = %e, channel_id = %channel_id, "failed to invalidate unread counts");
+        }
+    }
+
+    /// Load the default first page of the messages and of the pins of the
+    /// `channels` busiest channels since `since`, so the first requests after
+    /// a deploy do not all reach MongoDB at once.
+    ///
+    /// Returns the number of channels primed.
+    pub async fn prime<S: MessageService>(
+        &self,
+        service: &S,
+        since: DateTime<Utc>,
+        channels: usize,
+    ) -> Result<usize, CoreError> {
+        let pagination = GetPaginated::default();
+        let channels = service.most_active_channels(since, channels).await?;
+        for channel_id in &channels {
+            let key = ListCacheKey {
+                channel_id: *channel_id,
+                page: pagination.page,
+                limit: pagination.limit,
+            };
+            let messages = service.list_messages(channel_id, &pagination).await?;
+            self.insert(key.clone(), messages);
+            let pinned = service
+                .list_pinned_messages(channel_id, &pagination)
+                .await?;
+            self.insert_pinned(key, pinned);
+        }
+        Ok(channels.len())
+    }
+}
+
+impl Default for MessageListCache {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(5), 10_000)
+    }
+}
@@ -0,0 +1,182 @@
+//! Authorization of channel-scoped routes.
+//!
+//! Routes declare the permission they require with [`route_with_permission`],
+//! whose layer resolves the channel from the path, checks the permission and
+//! hands the channel to the handler as an [`AuthorizedChannel`]. Handlers
+//! taking an `AuthorizedChannel` thus never repeat the path parsing nor the
+//! permission check, and cannot run on a route that skipped it.
+
+use axum::{
+    extract::{FromRequestParts, RawPathParams, Request},
+    http::request::Parts,
+    middleware::{Next, from_fn},
+    response::Response as AxumResponse,
+};
+use communities_core::domain::message::{
+    entities::{ChannelId, MessageId},
+    ports::MessageService,
+};
+use utoipa::openapi::path::Paths;
+use utoipa_axum::router::UtoipaMethodRouter;
+use uuid::Uuid;
+
+use crate::http::server::{
+    ApiError, AppState,
+    authorization::{Authorization, Permission, Resource},
+    middleware::auth::entities::UserIdentity,
+    public_id::PublicId,
+};
+
+/// Check that `user_id` holds `permission` on `channel`
+pub async fn authorize_channel(
+    authz: &dyn Authorization,
+    user_id: Uuid,
+    permission: Permission,
+    channel: ChannelId,
+) -> Result<(), ApiError> {
+    let allowed = authz
+        .check(user_id, permission, Resource::Channel(channel.0))
+        .await
+        .map_err(|_| ApiError::InternalServerError)?;
+    if !allowed {
+        return Err(ApiError::Forbidden);
+    }
+    Ok(())
+}
+
+/// Channel a [`route_with_permission`] layer checked the permission on,
+/// extracted by the handlers of its route
+#[derive(Clone, Copy, Debug)]
+pub struct AuthorizedChannel(pub ChannelId);
+
+impl<S: Send + Sync> FromRequestParts<S> for AuthorizedChannel {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<AuthorizedChannel>()
+            .copied()
+            .ok_or_else(|| {
+                tracing::error!("handler expects an authorized channel but its route checks none");
+                ApiError::InternalServerError
+            })
+    }
+}
+
+/// OpenAPI extension naming who may call an operation. Every route declares
+/// it, through [`route_with_permission`] or [`route_authorized_by_handler`].
+pub const PERMISSION_EXTENSION: &str = "x-permission";
+
+/// Require `permission` on the channel a route targets before running its handler.
+///
+/// The channel is taken from the `{channel_id}` path parameter, or is the one
+/// of the message in the `{id}` path parameter. Routes with neither are
+/// rejected, so a route cannot be declared with a permission that is never
+/// checked. The router must carry the [`AppState`] as a request extension.
+pub fn route_with_permission(
+    permission: Permission,
+    (schemas, paths, method_router): UtoipaMethodRouter<AppState>,
+) -> UtoipaMethodRouter<AppState> {
+    let method_router = method_router.route_layer(from_fn(move |request: Request, next: Next| {
+        require_permission(permission, request, next)
+    }));
+    let paths = declare_permission(paths, format!("{permission:?} on the channel"));
+    (schemas, paths, method_router)
+}
+
+/// Declare a route whose handler decides who may call it, as no single
+/// channel of its path does: the channel is in the body, each item of a batch
+/// is checked, only the author of the message may act, or the route only
+/// touches the caller's own data. `rule` documents it in the OpenAPI operation.
+pub fn route_authorized_by_handler(
+    rule: &str,
+    (schemas, paths, method_router): UtoipaMethodRouter<AppState>,
+) -> UtoipaMethodRouter<AppState> {
+    (
+        schemas,
+        declare_permission(paths, rule.to_string()),
+        method_router,
+    )
+}
+
+fn declare_permission(mut paths: Paths, rule: String) -> Paths {
+    for item in paths.paths.values_mut() {
+        let operations = [
+            &mut item.get,
+            &mut item.put,
+            &mut item.post,
+            &mut item.delete,
+            &mut item.options,
+            &mut item.head,
+            &mut item.patch,
+            &mut item.trace,
+        ];
+        for operation in operations.into_iter().flatten() {
+            operation
+                .extensions
+                .get_or_insert_with(Default::default)
+                .insert(PERMISSION_EXTENSION.to_string(), rule.clone().into());
+        }
+    }
+    paths
+}
+
+async fn require_permission(
+    permission: Permission,
+    request: Request,
+    next: Next,
+) -> Result<AxumResponse, ApiError> {
+    let (mut parts, body) = request.into_parts();
+
+    let state = parts.extensions.get::<AppState>().cloned().ok_or_else(|| {
+        tracing::error!("AppState is missing from the request extensions");
+        ApiError::InternalServerError
+    })?;
+    let user = parts
+        .extensions
+        .get::<UserIdentity>()
+        .cloned()
+        .ok_or(ApiError::Unauthorized)?;
+
+    let params = RawPathParams::from_request_parts(&mut parts, &state)
+        .await
+        .map_err(|e| ApiError::BadRequest { msg: e.body_text() })?;
+    let channel_id = route_channel(&state, &params).await?;
+
+    authorize_channel(state.authz.as_ref(), user.user_id, permission, channel_id).await?;
+
+    parts.extensions.insert(AuthorizedChannel(channel_id));
+    Ok(next.run(Request::from_parts(parts, body)).await)
+}
+
+/// Channel targeted by a route, from its path parameters
+async fn route_channel(state: &AppState, params: &RawPathParams) -> Result<ChannelId, ApiError> {
+    let param = |name: &str| {
+        params
+            .iter()
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| {
+                PublicId::parse(value).ok_or_else(|| ApiError::BadRequest {
+                    msg: format!("Invalid {name}: {value}"),
+                })
+            })
+            .transpose()
+    };
+
+    if let Some(channel_id) = param("channel_id")? {
+        return Ok(ChannelId::from(channel_id.0));
+    }
+    if let Some(message_id) = param("id")? {
+        // deleted messages still belong to their channel, handlers decide
+        // whether they may be read
+        let message = state
+            .service
+            .get_message_including_deleted(&MessageId::from(message_id.0))
+            .await?;
+        return Ok(message.channel_id);
+    }
+
+    tracing::error!("route declares a permission but has no channel in its path");
+    Err(ApiError::InternalServerError)
+}
@@ -0,0 +1,53 @@
+//! Signed links to encrypted channel exports.
+//!
+//! Creating a link checks the caller's permission and fixes who the export is
+//! encrypted for. The link then works without credentials until it expires,
+//! for recipients without an account: the export it serves is only readable
+//! with their passphrase or private key.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use communities_core::{
+    domain::{common::CoreError, message::entities::ChannelId},
+    infrastructure::message::export::{ExportLink, ExportLinkSealer, ExportRecipient},
+};
+
+/// Issues and checks export links
+#[derive(Clone)]
+pub struct ExportLinks {
+    sealer: ExportLinkSealer,
+    ttl: chrono::Duration,
+}
+
+impl ExportLinks {
+    pub fn new(key: &[u8; 32], ttl: Duration) -> Self {
+        Self {
+            sealer: ExportLinkSealer::new(key),
+            ttl: chrono::Duration::from_std(ttl).unwrap_or(chrono::Duration::MAX),
+        }
+    }
+
+    /// Token of a link to the export of `channel_id` for `recipient`, with its expiry
+    pub fn issue(
+        &self,
+        channel_id: ChannelId,
+        include_deleted: bool,
+        recipient: ExportRecipient,
+        now: DateTime<Utc>,
+    ) -> Result<(String, DateTime<Utc>), CoreError> {
+        let expires_at = now + self.ttl;
+        let token = self.sealer.seal(&ExportLink {
+            channel_id,
+            include_deleted,
+            recipient,
+            expires_at,
+        })?;
+        Ok((token, expires_at))
+    }
+
+    /// The link of `token`, unless it was not issued here, altered or expired
+    pub fn open(&self, token: &str, now: DateTime<Utc>) -> Option<ExportLink> {
+        self.sealer.open(token, now)
+    }
+}
@@ -0,0 +1,197 @@
+//! Debug logging of request and response bodies, to diagnose client
+//! integrations in staging.
+//!
+//! Only a sample of the traffic is logged: a share of all requests, plus
+//! every request of the followed users. JSON bodies are logged with the
+//! values of sensitive keys redacted; other bodies only by size.
+
+use std::{
+    collections::HashSet,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Instant,
+};
+
+use axum::{
+    body::{Body, HttpBody, to_bytes},
+    extract::{Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response as AxumResponse},
+};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::{
+    http::server::{ApiError, middleware::auth::entities::UserIdentity},
+    logging::{REDACTED, is_sensitive_field},
+};
+
+/// Bodies above this size are logged by size only, not to buffer uploads
+const MAX_CAPTURED_BYTES: u64 = 1024 * 1024;
+
+/// Share of requests sampled is expressed out of this many requests
+const SAMPLE_SCALE: u64 = 10_000;
+
+pub struct BodyLogging {
+    /// Requests sampled out of [`SAMPLE_SCALE`]
+    sample_rate: u64,
+    user_ids: HashSet<Uuid>,
+    /// Keys redacted on top of the fields always redacted from logs
+    redacted_fields: HashSet<String>,
+    max_logged_bytes: usize,
+    requests: AtomicU64,
+}
+
+impl BodyLogging {
+    /// Log `sample_percent` of the requests and every request of `user_ids`;
+    /// `None` when neither selects any request
+    pub fn new(
+        sample_percent: f64,
+        user_ids: impl IntoIterator<Item = Uuid>,
+        redacted_fields: impl IntoIterator<Item = String>,
+        max_logged_bytes: usize,
+    ) -> Option<Self> {
+        let sample_rate = (sample_percent.clamp(0.0, 100.0) * (SAMPLE_SCALE / 100) as f64) as u64;
+        let user_ids: HashSet<Uuid> = user_ids.into_iter().collect();
+        if sample_rate == 0 && user_ids.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            sample_rate,
+            user_ids,
+            redacted_fields: redacted_fields
+                .into_iter()
+                .map(|field| field.trim().to_ascii_lowercase())
+                .filter(|field| !field.is_empty())
+                .collect(),
+            max_logged_bytes,
+            requests: AtomicU64::new(0),
+        })
+    }
+
+    /// Whether to log the next request, made by `user_id` when authenticated.
+    ///
+    /// Sampling is spread evenly over the requests rather than random, so a
+    /// low percentage still logs from the first few requests.
+    pub fn samples(&self, user_id: Option<Uuid>) -> bool {
+        if user_id.is_some_and(|id| self.user_ids.contains(&id)) {
+            return true;
+        }
+        let n = self.requests.fetch_add(1, Ordering::Relaxed);
+        (n + 1) * self.sample_rate / SAMPLE_SCALE > n * self.sample_rate / SAMPLE_SCALE
+    }
+
+    /// What is logged of `body`: redacted JSON, cut after the configured size
+    pub fn redact(&self, body: &[u8]) -> String {
+        let mut logged = match serde_json::from_slice::<Value>(body) {
+            Ok(mut value) => {
+                self.redact_value(&mut value);
+                value.to_string()
+            }
+            Err(_) => return format!("[{} bytes, not JSON]", body.len()),
+        };
+
+        if logged.len() > self.max_logged_bytes {
+            let mut end = self.max_logged_bytes;
+            while !logged.is_char_boundary(end) {
+                end -= 1;
+            }
+            logged.truncate(end);
+            logged.push_str("...");
+        }
+        logged
+    }
+
+    fn redact_value(&self, value: &mut Value) {
+        match value {
+            Value::Object(fields) => {
+                for (key, value) in fields.iter_mut() {
+                    if is_sensitive_field(key)
+                        || self.redacted_fields.contains(&key.to_ascii_lowercase())
+                    {
+                        *value = Value::String(REDACTED.to_string());
+                    } else {
+                        self.redact_value(value);
+                    }
+                }
+            }
+            Value::Array(items) => items.iter_mut().for_each(|item| self.redact_value(item)),
+            _ => {}
+        }
+    }
+
+    /// What is logged of `body`, and the body to pass on in its place.
+    ///
+    /// Streamed bodies are left alone, so exports and realtime connections
+    /// are not buffered.
+    async fn capture(&self, body: Body) -> Result<(String, Body), axum::Error> {
+        match body.size_hint().exact() {
+            Some(0) => Ok((String::new(), body)),
+            Some(len) if len <= MAX_CAPTURED_BYTES => {
+                let bytes = to_bytes(body, len as usize).await?;
+                Ok((self.redact(&bytes), Body::from(bytes)))
+            }
+            Some(len) => Ok((format!("[{len} bytes]"), body)),
+            None => Ok(("[streamed]".to_string(), body)),
+        }
+    }
+}
+
+/// Middleware logging the bodies of the requests [`BodyLogging`] samples.
+///
+/// Must run after authentication to recognize the followed users.
+pub async fn log_bodies(
+    State(logging): State<Arc<BodyLogging>>,
+    request: Request,
+    next: Next,
+) -> AxumResponse {
+    let user_id = request
+        .extensions()
+        .get::<UserIdentity>()
+        .map(|identity| identity.user_id);
+    if !logging.samples(user_id) {
+        return next.run(request).await;
+    }
+
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    let (parts, body) = request.into_parts();
+    let (request_body, body) = match logging.capture(body).await {
+        Ok(captured) => captured,
+        Err(e) => {
+            return ApiError::BadRequest {
+                msg: format!("Failed to read the request body: {e}"),
+            }
+            .into_response();
+        }
+    };
+
+    let started = Instant::now();
+    let response = next.run(Request::from_parts(parts, body)).await;
+    let elapsed_ms = started.elapsed().as_millis() as u64;
+
+    let status = response.status().as_u16();
+    let (parts, body) = response.into_parts();
+    let (response_body, body) = match logging.capture(body).await {
+        Ok(captured) => captured,
+        Err(e) => {
+            tracing::error!(error = %e, "failed to read the response body");
+            return ApiError::InternalServerError.into_response();
+        }
+    };
+
+    tracing::info!(
+        %method,
+        %path,
+        user_id = ?user_id,
+        status,
+        elapsed_ms,
+        %request_body,
+        %response_body,
+        "sampled request"
+    );
+    AxumResponse::from_parts(parts, body)
+}
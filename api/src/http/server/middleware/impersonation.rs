@@ -0,0 +1,122 @@
+//! Impersonation of users by support admins, to debug what a user sees.
+//!
+//! A request carrying the [`IMPERSONATE_HEADER`] header is served as the user
+//! it names, provided impersonation is enabled on the deployment and the
+//! authenticated user holds [`Permission::ImpersonateUsers`] over that user.
+//! Every impersonated request, granted or not, is logged on the `audit`
+//! target with both identities.
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Request, State},
+    http::HeaderValue,
+    middleware::Next,
+    response::Response as AxumResponse,
+};
+use uuid::Uuid;
+
+use crate::http::server::{
+    ApiError,
+    authorization::{DynAuthz, Permission, Resource},
+    middleware::auth::entities::UserIdentity,
+};
+
+/// Header naming the user a request is issued on behalf of
+pub const IMPERSONATE_HEADER: &str = "x-impersonate-user";
+
+#[derive(Clone)]
+pub struct Impersonation {
+    enabled: bool,
+    authz: DynAuthz,
+}
+
+/// Request extension of the impersonated requests, whose [`UserIdentity`] is
+/// the impersonated user
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Impersonator {
+    pub admin_id: Uuid,
+}
+
+impl Impersonation {
+    /// Refuse every impersonation unless `enabled`
+    pub fn new(enabled: bool, authz: DynAuthz) -> Self {
+        Self { enabled, authz }
+    }
+
+    /// User `admin_id` may act as, named by the `header` value
+    pub async fn authorize(&self, admin_id: Uuid, header: &HeaderValue) -> Result<Uuid, ApiError> {
+        if !self.enabled {
+            return Err(ApiError::Forbidden);
+        }
+        let user_id = header
+            .to_str()
+            .ok()
+            .and_then(|value| Uuid::try_parse(value.trim()).ok())
+            .ok_or_else(|| ApiError::BadRequest {
+                msg: format!("{IMPERSONATE_HEADER} must be a user ID"),
+            })?;
+        let allowed = self
+            .authz
+            .check(
+                admin_id,
+                Permission::ImpersonateUsers,
+                Resource::User(user_id),
+            )
+            .await
+            .map_err(|_| ApiError::InternalServerError)?;
+        if !allowed {
+            return Err(ApiError::Forbidden);
+        }
+        Ok(user_id)
+    }
+}
+
+/// Middleware swapping the [`UserIdentity`] of impersonated requests; layered
+/// inside authentication, so the admin is known
+pub async fn impersonate(
+    State(impersonation): State<Arc<Impersonation>>,
+    mut request: Request,
+    next: Next,
+) -> Result<AxumResponse, ApiError> {
+    let Some(header) = request.headers().get(IMPERSONATE_HEADER).cloned() else {
+        return Ok(next.run(request).await);
+    };
+    let admin_id = request
+        .extensions()
+        .get::<UserIdentity>()
+        .map(|identity| identity.user_id)
+        .ok_or(ApiError::Unauthorized)?;
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+
+    let user_id = match impersonation.authorize(admin_id, &header).await {
+        Ok(user_id) => user_id,
+        Err(e) => {
+            tracing::warn!(
+                target: "audit",
+                %admin_id,
+                impersonated = ?header,
+                %method,
+                %path,
+                error = %e,
+                "impersonation refused"
+            );
+            return Err(e);
+        }
+    };
+
+    request.extensions_mut().insert(UserIdentity { user_id });
+    request.extensions_mut().insert(Impersonator { admin_id });
+    let response = next.run(request).await;
+    tracing::info!(
+        target: "audit",
+        %admin_id,
+        impersonated_user_id = %user_id,
+        %method,
+        %path,
+        status = response.status().as_u16(),
+        "impersonated request"
+    );
+    Ok(response)
+}
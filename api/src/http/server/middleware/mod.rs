@@ -1 +1,4 @@
 pub mod auth;
+pub mod body_logging;
+pub mod impersonation;
+pub mod read_only;
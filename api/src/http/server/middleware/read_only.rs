@@ -0,0 +1,43 @@
+//! Read-only mode of the disaster-recovery replicas, pointed at a restored
+//! or secondary database that must not diverge from the primary one.
+
+use axum::{
+    extract::{MatchedPath, Request},
+    http::Method,
+    middleware::Next,
+    response::Response as AxumResponse,
+};
+
+use crate::http::server::ApiError;
+
+/// `POST` routes that only read, with a body too large or structured for a query string.
+/// GraphQL mutations are refused by the schema itself.
+const READ_POST_ROUTES: &[&str] = &[
+    "/messages/batch-get",
+    "/channels/last-messages",
+    "/channels/{channel_id}/messages/similar",
+    "/graphql",
+];
+
+/// Whether a request to the route `path` with `method` may change data
+pub fn is_mutation(method: &Method, path: &str) -> bool {
+    match *method {
+        Method::GET | Method::HEAD | Method::OPTIONS => false,
+        Method::POST => !READ_POST_ROUTES.contains(&path),
+        _ => true,
+    }
+}
+
+/// Middleware answering every mutation with [`ApiError::ReadOnly`]; layered
+/// on the routes, so the route pattern is known
+pub async fn reject_writes(request: Request, next: Next) -> Result<AxumResponse, ApiError> {
+    let path = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| request.uri().path().to_string());
+    if is_mutation(request.method(), &path) {
+        return Err(ApiError::ReadOnly);
+    }
+    Ok(next.run(request).await)
+}
@@ -1,9 +1,16 @@
 pub mod api_error;
 pub mod app_state;
+pub mod cache;
+pub mod channel_access;
+pub mod export_links;
 pub mod middleware;
+pub mod public_id;
 pub mod response;
+pub mod throttle;
+pub mod validated_query;
 pub mod authorization;
 
 pub use api_error::ApiError;
 pub use app_state::AppState;
 pub use response::Response;
+pub use validated_query::ValidatedQuery;
@@ -0,0 +1,150 @@
+//! Message and channel IDs as exposed by the REST API.
+//!
+//! Deployments that do not want raw UUIDs exposed configure an
+//! [`IdObfuscation`] in the [`AppState`](crate::http::server::AppState):
+//! [`public_ids`] scopes it to each request, and [`PublicId`]s are then
+//! encoded with it in responses and decoded with it from paths, query strings
+//! and bodies. Outside such a scope, public IDs are the UUIDs themselves.
+
+use std::{fmt, sync::Arc};
+
+use aes::{
+    Aes128, Block,
+    cipher::{BlockDecrypt, BlockEncrypt, Key, KeyInit},
+};
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response as AxumResponse,
+};
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
+use uuid::Uuid;
+
+use crate::http::server::AppState;
+
+/// Reversible mapping between stored UUIDs and the IDs clients see
+pub trait IdObfuscation: Send + Sync {
+    fn encode(&self, id: Uuid) -> String;
+    /// `None` when `public` is not an ID produced by [`IdObfuscation::encode`]
+    fn decode(&self, public: &str) -> Option<Uuid>;
+}
+
+/// UUIDs exposed as they are stored
+pub struct PlainIds;
+
+impl IdObfuscation for PlainIds {
+    fn encode(&self, id: Uuid) -> String {
+        id.to_string()
+    }
+
+    fn decode(&self, public: &str) -> Option<Uuid> {
+        Uuid::try_parse(public).ok()
+    }
+}
+
+/// UUIDs encrypted as a single AES-128 block, written as 32 hex digits.
+///
+/// A UUID always gets the same public ID, so URLs stay stable, but neither
+/// the UUID nor the creation time of v7 UUIDs can be read from it.
+pub struct EncryptedIds {
+    cipher: Aes128,
+}
+
+impl EncryptedIds {
+    pub fn new(key: &[u8; 16]) -> Self {
+        Self {
+            cipher: Aes128::new(Key::<Aes128>::from_slice(key)),
+        }
+    }
+}
+
+impl IdObfuscation for EncryptedIds {
+    fn encode(&self, id: Uuid) -> String {
+        let mut block = Block::clone_from_slice(id.as_bytes());
+        self.cipher.encrypt_block(&mut block);
+        block.iter().map(|byte| format!("{byte:02x}")).collect()
+    }
+
+    fn decode(&self, public: &str) -> Option<Uuid> {
+        if !public.is_ascii() || public.len() != 32 {
+            return None;
+        }
+        let mut block = Block::default();
+        for (i, byte) in block.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&public[2 * i..2 * i + 2], 16).ok()?;
+        }
+        self.cipher.decrypt_block(&mut block);
+        Uuid::from_slice(&block).ok()
+    }
+}
+
+tokio::task_local! {
+    static OBFUSCATION: Arc<dyn IdObfuscation>;
+}
+
+/// Run `future` with its public IDs encoded and decoded by `obfuscation`
+pub async fn scope_public_ids<F: Future>(
+    obfuscation: Arc<dyn IdObfuscation>,
+    future: F,
+) -> F::Output {
+    OBFUSCATION.scope(obfuscation, future).await
+}
+
+/// Run `f` with its public IDs encoded and decoded by `obfuscation`, for the
+/// work done outside of the request, such as streamed bodies
+pub fn sync_scope_public_ids<R>(obfuscation: &Arc<dyn IdObfuscation>, f: impl FnOnce() -> R) -> R {
+    OBFUSCATION.sync_scope(obfuscation.clone(), f)
+}
+
+/// Middleware scoping the [`IdObfuscation`] of the state to the request
+pub async fn public_ids(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> AxumResponse {
+    scope_public_ids(state.id_obfuscation.clone(), next.run(request)).await
+}
+
+fn with_obfuscation<R>(f: impl FnOnce(&dyn IdObfuscation) -> R) -> R {
+    match OBFUSCATION.try_with(Arc::clone) {
+        Ok(obfuscation) => f(obfuscation.as_ref()),
+        Err(_) => f(&PlainIds),
+    }
+}
+
+/// ID of a message or channel, encoded with the scoped [`IdObfuscation`]
+/// when serialized and decoded with it when deserialized
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PublicId(pub Uuid);
+
+impl PublicId {
+    /// Decode an ID received from a client
+    pub fn parse(public: &str) -> Option<Self> {
+        with_obfuscation(|obfuscation| obfuscation.decode(public)).map(Self)
+    }
+}
+
+impl From<Uuid> for PublicId {
+    fn from(id: Uuid) -> Self {
+        Self(id)
+    }
+}
+
+impl fmt::Display for PublicId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&with_obfuscation(|obfuscation| obfuscation.encode(self.0)))
+    }
+}
+
+impl Serialize for PublicId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for PublicId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let public = String::deserialize(deserializer)?;
+        Self::parse(&public).ok_or_else(|| de::Error::custom(format!("invalid ID: {public}")))
+    }
+}
@@ -1,17 +1,181 @@
+use std::{fmt::Display, future::Future, time::Duration};
+
 use axum::{
     Json,
-    http::StatusCode,
+    extract::Request,
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    middleware::Next,
     response::{IntoResponse, Response as AxumResponse},
 };
 use communities_core::domain::common::TotalPaginatedElements;
+
+use crate::http::server::ApiError;
 use serde::Serialize;
 use utoipa::ToSchema;
 
+/// `max-age` of immutable resources: one year, the longest value caches honor
+const IMMUTABLE_MAX_AGE: Duration = Duration::from_secs(365 * 24 * 60 * 60);
+
+/// Media type of MessagePack bodies
+pub const MSGPACK_CONTENT_TYPE: &str = "application/msgpack";
+
+/// Media type of newline-delimited JSON bodies, one value per line
+pub const NDJSON_CONTENT_TYPE: &str = "application/x-ndjson";
+
+/// Media types accepted as asking for MessagePack
+const MSGPACK_MEDIA_TYPES: &[&str] = &[
+    MSGPACK_CONTENT_TYPE,
+    "application/x-msgpack",
+    "application/vnd.msgpack",
+];
+
+/// Request header selecting the API version of the responses
+pub const API_VERSION_HEADER: &str = "api-version";
+
+tokio::task_local! {
+    static RESPONSE_FORMAT: ResponseFormat;
+    static API_VERSION: ApiVersion;
+}
+
+/// Serialization of [`Response`] bodies, negotiated from the request's `Accept` header
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ResponseFormat {
+    #[default]
+    Json,
+    MessagePack,
+}
+
+impl ResponseFormat {
+    /// MessagePack when the client prefers it over JSON, JSON otherwise.
+    ///
+    /// Preference follows the `q` parameters; on a tie the first listed wins,
+    /// and a type listed with `q=0` is never picked.
+    pub fn from_accept(request_headers: &HeaderMap) -> Self {
+        let mut best = (ResponseFormat::Json, 0.0_f32);
+
+        let ranges = request_headers
+            .get_all(header::ACCEPT)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','));
+        for range in ranges {
+            let mut params = range.split(';').map(str::trim);
+            let media_type = params.next().unwrap_or_default().to_ascii_lowercase();
+            let quality = params
+                .filter_map(|param| param.strip_prefix("q="))
+                .find_map(|q| q.parse::<f32>().ok())
+                .unwrap_or(1.0);
+
+            let format = if MSGPACK_MEDIA_TYPES.contains(&media_type.as_str()) {
+                ResponseFormat::MessagePack
+            } else if matches!(
+                media_type.as_str(),
+                "application/json" | "application/*" | "*/*"
+            ) {
+                ResponseFormat::Json
+            } else {
+                continue;
+            };
+            if quality > best.1 {
+                best = (format, quality);
+            }
+        }
+        best.0
+    }
+
+    /// Format negotiated for the request being handled, JSON outside of [`negotiate_format`]
+    pub fn current() -> Self {
+        RESPONSE_FORMAT
+            .try_with(|format| *format)
+            .unwrap_or_default()
+    }
+
+    /// Run `future` with this format as the negotiated one
+    pub async fn scope<F: Future>(self, future: F) -> F::Output {
+        RESPONSE_FORMAT.scope(self, future).await
+    }
+}
+
+/// Shape of [`Response`] bodies, selected with the `Api-Version` request header
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ApiVersion {
+    /// Bare resources, and pages with their pagination fields next to `data`
+    #[default]
+    V1,
+    /// Every body is an [`Envelope`]: `{"data": ..., "meta": {...}}`
+    V2,
+}
+
+impl ApiVersion {
+    /// V2 when the request asks for it, V1 for anything else
+    pub fn from_headers(request_headers: &HeaderMap) -> Self {
+        match request_headers
+            .get(API_VERSION_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(str::trim)
+        {
+            Some("2") => ApiVersion::V2,
+            _ => ApiVersion::V1,
+        }
+    }
+
+    /// Version selected for the request being handled, V1 outside of [`negotiate_format`]
+    pub fn current() -> Self {
+        API_VERSION.try_with(|version| *version).unwrap_or_default()
+    }
+
+    /// Run `future` with this version as the selected one
+    pub async fn scope<F: Future>(self, future: F) -> F::Output {
+        API_VERSION.scope(self, future).await
+    }
+}
+
+/// Middleware negotiating the format and API version of the [`Response`]s built by the handlers
+pub async fn negotiate_format(request: Request, next: Next) -> AxumResponse {
+    let format = ResponseFormat::from_accept(request.headers());
+    let version = ApiVersion::from_headers(request.headers());
+    let mut response = format.scope(version.scope(next.run(request))).await;
+    let headers = response.headers_mut();
+    headers.append(header::VARY, HeaderValue::from_static("accept"));
+    headers.append(header::VARY, HeaderValue::from_static(API_VERSION_HEADER));
+    response
+}
+
+/// Strong entity tag of a resource representation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ETag(String);
+
+impl ETag {
+    /// Build a strong ETag; `value` must change whenever the representation does
+    /// (e.g. a content hash, or an ID and revision of an immutable snapshot)
+    pub fn strong(value: impl Display) -> Self {
+        Self(format!("\"{value}\""))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Whether the request's `If-None-Match` header matches this tag
+    pub fn matches(&self, request_headers: &HeaderMap) -> bool {
+        request_headers
+            .get_all(header::IF_NONE_MATCH)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            // If-None-Match uses the weak comparison
+            .any(|tag| tag == "*" || tag.trim_start_matches("W/") == self.0)
+    }
+}
+
 /// Generic response wrapper for consistent API responses
 #[derive(Debug, Clone)]
 pub struct Response<T> {
     data: T,
+    meta: ResponseMeta,
     status_code: StatusCode,
+    headers: HeaderMap,
 }
 
 impl<T> Response<T>
@@ -22,7 +186,9 @@ where
     pub fn ok(data: T) -> Self {
         Self {
             data,
+            meta: ResponseMeta::None(EmptyMeta {}),
             status_code: StatusCode::OK,
+            headers: HeaderMap::new(),
         }
     }
 
@@ -30,7 +196,9 @@ where
     pub fn created(data: T) -> Self {
         Self {
             data,
+            meta: ResponseMeta::None(EmptyMeta {}),
             status_code: StatusCode::CREATED,
+            headers: HeaderMap::new(),
         }
     }
 
@@ -38,14 +206,95 @@ where
     pub fn deleted(data: T) -> Self {
         Self {
             data,
+            meta: ResponseMeta::None(EmptyMeta {}),
             status_code: StatusCode::OK,
+            headers: HeaderMap::new(),
         }
     }
 
     /// Create a response with a custom status code
     #[allow(dead_code)]
     pub fn with_status(data: T, status_code: StatusCode) -> Self {
-        Self { data, status_code }
+        Self {
+            data,
+            meta: ResponseMeta::None(EmptyMeta {}),
+            status_code,
+            headers: HeaderMap::new(),
+        }
+    }
+
+    /// Allow clients and intermediaries to reuse this response for `max_age`
+    pub fn with_cache_control(mut self, max_age: Duration) -> Self {
+        let value = format!("private, max-age={}", max_age.as_secs());
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            self.headers.insert(header::CACHE_CONTROL, value);
+        }
+        self
+    }
+
+    /// Tag the response so clients can revalidate it with `If-None-Match`
+    pub fn with_etag(mut self, etag: &ETag) -> Self {
+        if let Ok(value) = HeaderValue::from_str(etag.as_str()) {
+            self.headers.insert(header::ETAG, value);
+        }
+        self
+    }
+
+    /// Mark the response as never changing for its URL (attachments, exports,
+    /// specific revisions): caches may reuse it without revalidation.
+    ///
+    /// Responses stay `private` since every resource sits behind authentication.
+    pub fn immutable(self, etag: &ETag) -> Self {
+        let mut response = self.with_etag(etag);
+        let value = format!(
+            "private, max-age={}, immutable",
+            IMMUTABLE_MAX_AGE.as_secs()
+        );
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            response.headers.insert(header::CACHE_CONTROL, value);
+        }
+        response
+    }
+
+    /// Answer `304 Not Modified` (without body) when the request's
+    /// `If-None-Match` matches the ETag previously set on this response
+    pub fn revalidate(mut self, request_headers: &HeaderMap) -> Self {
+        let matched = self
+            .headers
+            .get(header::ETAG)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|etag| ETag(etag.to_string()).matches(request_headers));
+        if matched && self.status_code == StatusCode::OK {
+            self.status_code = StatusCode::NOT_MODIFIED;
+        }
+        self
+    }
+}
+
+impl<T> Response<Vec<T>>
+where
+    T: Serialize,
+{
+    /// 200 OK with an offset page; V1 bodies keep the [`PaginatedResponse`] shape
+    pub fn page(page: PaginatedResponse<T>) -> Self {
+        let mut response = Self::ok(page.data);
+        response.meta = ResponseMeta::Page(PageMeta {
+            total: page.total,
+            page: page.page,
+            limit: page.limit,
+            total_pages: page.total_pages,
+            has_next: page.has_next,
+        });
+        response
+    }
+
+    /// 200 OK with a keyset page; V1 bodies keep the [`CursorPaginatedResponse`] shape
+    pub fn cursor_page(page: CursorPaginatedResponse<T>) -> Self {
+        let mut response = Self::ok(page.data);
+        response.meta = ResponseMeta::Cursor(CursorMeta {
+            next_cursor: page.next_cursor,
+        });
+        response
     }
 }
 
@@ -54,7 +303,92 @@ where
     T: Serialize,
 {
     fn into_response(self) -> AxumResponse {
-        (self.status_code, Json(self.data)).into_response()
+        if self.status_code == StatusCode::NOT_MODIFIED {
+            return (self.status_code, self.headers).into_response();
+        }
+        let Self {
+            data,
+            meta,
+            status_code,
+            headers,
+        } = self;
+        let body = Body::new(data, meta);
+        match ResponseFormat::current() {
+            ResponseFormat::Json => (status_code, headers, Json(body)).into_response(),
+            ResponseFormat::MessagePack => match rmp_serde::to_vec_named(&body) {
+                Ok(body) => (
+                    status_code,
+                    headers,
+                    [(header::CONTENT_TYPE, MSGPACK_CONTENT_TYPE)],
+                    body,
+                )
+                    .into_response(),
+                Err(e) => {
+                    tracing::error!(error = %e, "failed to serialize response to MessagePack");
+                    ApiError::InternalServerError.into_response()
+                }
+            },
+        }
+    }
+}
+
+/// Body of every V2 response
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Envelope<T> {
+    pub data: T,
+    pub meta: ResponseMeta,
+}
+
+/// What a V2 body says about its `data`: pagination of a listing, nothing otherwise
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(untagged)]
+pub enum ResponseMeta {
+    Page(PageMeta),
+    Cursor(CursorMeta),
+    None(EmptyMeta),
+}
+
+/// Pagination of an offset page, see [`PaginatedResponse`]
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct PageMeta {
+    pub total: TotalPaginatedElements,
+    pub page: u32,
+    pub limit: u32,
+    pub total_pages: u64,
+    pub has_next: bool,
+}
+
+/// Pagination of a keyset page, see [`CursorPaginatedResponse`]
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct CursorMeta {
+    pub next_cursor: Option<String>,
+}
+
+/// Meta of a body that is not a listing, serialized as `{}`
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct EmptyMeta {}
+
+/// A body as serialized for the selected [`ApiVersion`]
+#[derive(Serialize)]
+#[serde(untagged)]
+enum Body<T> {
+    Bare(T),
+    Envelope(Envelope<T>),
+    /// V1 pages: the pagination fields next to `data`
+    Flattened {
+        data: T,
+        #[serde(flatten)]
+        meta: ResponseMeta,
+    },
+}
+
+impl<T> Body<T> {
+    fn new(data: T, meta: ResponseMeta) -> Self {
+        match (ApiVersion::current(), meta) {
+            (ApiVersion::V2, meta) => Body::Envelope(Envelope { data, meta }),
+            (ApiVersion::V1, ResponseMeta::None(_)) => Body::Bare(data),
+            (ApiVersion::V1, meta) => Body::Flattened { data, meta },
+        }
     }
 }
 
@@ -63,4 +397,93 @@ pub struct PaginatedResponse<T> {
     pub data: Vec<T>,
     pub total: TotalPaginatedElements,
     pub page: u32,
+    /// Page size the page was computed with
+    pub limit: u32,
+    pub total_pages: u64,
+    /// Whether a page follows this one
+    pub has_next: bool,
+}
+
+impl<T> PaginatedResponse<T> {
+    /// Page `page` of `total` elements, `limit` per page
+    pub fn new(data: Vec<T>, total: TotalPaginatedElements, page: u32, limit: u32) -> Self {
+        let total_pages = if limit == 0 {
+            // no limit: everything fits in a single page
+            u64::from(total > 0)
+        } else {
+            total.div_ceil(u64::from(limit))
+        };
+
+        Self {
+            data,
+            total,
+            page,
+            limit,
+            total_pages,
+            has_next: u64::from(page) < total_pages,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CursorPaginatedResponse<T> {
+    pub data: Vec<T>,
+    /// Cursor to pass back to fetch the next page, absent on the last page
+    pub next_cursor: Option<String>,
+}
+
+/// Outcome of one failed item in a batch operation
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct BatchFailure<K> {
+    /// Identifies the failed item: its position in the request, or its ID
+    pub key: K,
+    /// Machine readable error code, same as the one of a single-item request
+    pub error_code: String,
+    pub message: String,
+}
+
+/// Result of a batch operation where each item may fail independently.
+///
+/// Batch endpoints answer `200 OK` when every item succeeded and
+/// `207 Multi-Status` as soon as one item failed.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct BatchResult<T, K> {
+    pub succeeded: Vec<T>,
+    pub failed: Vec<BatchFailure<K>>,
+}
+
+impl<T, K> Default for BatchResult<T, K> {
+    fn default() -> Self {
+        Self {
+            succeeded: Vec::new(),
+            failed: Vec::new(),
+        }
+    }
+}
+
+impl<T, K> BatchResult<T, K>
+where
+    T: Serialize,
+    K: Serialize,
+{
+    pub fn push_success(&mut self, item: T) {
+        self.succeeded.push(item);
+    }
+
+    pub fn push_failure(&mut self, key: K, error: ApiError) {
+        self.failed.push(BatchFailure {
+            key,
+            error_code: error.error_code().to_string(),
+            message: error.to_string(),
+        });
+    }
+
+    pub fn into_batch_response(self) -> Response<Self> {
+        let status_code = if self.failed.is_empty() {
+            StatusCode::OK
+        } else {
+            StatusCode::MULTI_STATUS
+        };
+        Response::with_status(self, status_code)
+    }
 }
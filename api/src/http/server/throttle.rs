@@ -0,0 +1,96 @@
+//! Soft bandwidth limits on streamed responses, so a large export cannot
+//! saturate the network of the pod at the expense of interactive traffic.
+//!
+//! Each response gets its own token bucket: it may send one second worth of
+//! bytes at once, then waits between chunks to stay under the configured
+//! rate. Chunks are never split, so the limit is only approximate.
+
+use std::{
+    num::NonZeroU64,
+    time::{Duration, Instant},
+};
+
+use futures::{Stream, StreamExt, stream::BoxStream};
+
+/// Bandwidth allowed to each streamed response
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StreamThrottle {
+    bytes_per_sec: Option<NonZeroU64>,
+}
+
+impl StreamThrottle {
+    /// At most `bytes_per_sec` per response, unlimited when 0
+    pub fn new(bytes_per_sec: u64) -> Self {
+        Self {
+            bytes_per_sec: NonZeroU64::new(bytes_per_sec),
+        }
+    }
+
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+
+    pub fn bytes_per_sec(&self) -> Option<u64> {
+        self.bytes_per_sec.map(NonZeroU64::get)
+    }
+
+    /// Delay the chunks of `body` to keep it under the limit; errors go
+    /// through right away
+    pub fn throttle<S, E>(&self, body: S) -> BoxStream<'static, Result<Vec<u8>, E>>
+    where
+        S: Stream<Item = Result<Vec<u8>, E>> + Send + 'static,
+        E: Send + 'static,
+    {
+        let Some(rate) = self.bytes_per_sec else {
+            return body.boxed();
+        };
+
+        let mut bucket = TokenBucket::new(rate.get());
+        body.then(move |chunk| {
+            let wait = match &chunk {
+                Ok(bytes) => bucket.take(bytes.len()),
+                Err(_) => Duration::ZERO,
+            };
+            async move {
+                if !wait.is_zero() {
+                    tokio::time::sleep(wait).await;
+                }
+                chunk
+            }
+        })
+        .boxed()
+    }
+}
+
+struct TokenBucket {
+    /// Bytes added per second, also the size of the bucket
+    rate: f64,
+    /// Negative while waiting for the bytes sent in advance
+    available: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate: u64) -> Self {
+        Self {
+            rate: rate as f64,
+            available: rate as f64,
+            refilled_at: Instant::now(),
+        }
+    }
+
+    /// Take `bytes` from the bucket, returning how long to wait before sending them
+    fn take(&mut self, bytes: usize) -> Duration {
+        let now = Instant::now();
+        let refill = now.duration_since(self.refilled_at).as_secs_f64() * self.rate;
+        self.available = (self.available + refill).min(self.rate);
+        self.refilled_at = now;
+
+        self.available -= bytes as f64;
+        if self.available >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.available / self.rate)
+        }
+    }
+}
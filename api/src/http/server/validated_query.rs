@@ -0,0 +1,111 @@
+//! Query string extractor checking the parameters it deserializes, so
+//! handlers only see values within bounds and clients learn which parameter
+//! was wrong.
+//!
+//! Parameters take their defaults while being deserialized (`#[serde(default)]`),
+//! then [`ValidateQuery::validate`] reports every field out of bounds at once.
+//! Invalid queries are answered `400` with the error code `INVALID_QUERY` and
+//! the list of the faulty `fields`.
+
+use std::ops::RangeInclusive;
+
+use axum::{extract::FromRequestParts, http::request::Parts};
+use communities_core::domain::common::{GetCursorPaginated, GetPaginated, MAX_PAGE_LIMIT};
+use serde::{Serialize, de::DeserializeOwned};
+use utoipa::ToSchema;
+
+use crate::http::server::ApiError;
+
+/// Highest page number, so the offset of any page still fits in a `u32`
+const MAX_PAGE: u32 = u32::MAX / MAX_PAGE_LIMIT;
+
+/// A query parameter that was missing, malformed or out of bounds
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Query parameters a [`ValidatedQuery`] can extract
+pub trait ValidateQuery: DeserializeOwned {
+    /// Every parameter out of bounds, none when the query is valid
+    fn validate(&self) -> Vec<FieldError>;
+}
+
+/// Report `field` unless its `value` lies within `bounds`
+pub fn check_range(
+    errors: &mut Vec<FieldError>,
+    field: &str,
+    value: u32,
+    bounds: RangeInclusive<u32>,
+) {
+    if !bounds.contains(&value) {
+        errors.push(FieldError::new(
+            field,
+            format!("must be between {} and {}", bounds.start(), bounds.end()),
+        ));
+    }
+}
+
+/// Replacement of `Query<T>` answering invalid parameters with field-level
+/// `400`s instead of axum's plain text rejections
+#[derive(Debug, Clone)]
+pub struct ValidatedQuery<T>(pub T);
+
+impl<T, S> FromRequestParts<S> for ValidatedQuery<T>
+where
+    T: ValidateQuery,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let query = parts.uri.query().unwrap_or_default();
+        let deserializer =
+            serde_urlencoded::Deserializer::new(form_urlencoded::parse(query.as_bytes()));
+        let params: T = serde_path_to_error::deserialize(deserializer).map_err(|e| {
+            let field = e.path().to_string();
+            ApiError::InvalidQuery {
+                fields: vec![FieldError::new(field, e.into_inner().to_string())],
+            }
+        })?;
+
+        let fields = params.validate();
+        if !fields.is_empty() {
+            return Err(ApiError::InvalidQuery { fields });
+        }
+        Ok(Self(params))
+    }
+}
+
+impl ValidateQuery for GetPaginated {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        check_range(&mut errors, "page", self.page, 1..=MAX_PAGE);
+        check_range(&mut errors, "limit", self.limit, 1..=MAX_PAGE_LIMIT);
+        errors
+    }
+}
+
+impl ValidateQuery for GetCursorPaginated {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if self.decoded_cursor().is_err() {
+            errors.push(FieldError::new(
+                "cursor",
+                "must be a `next_cursor` returned by the API",
+            ));
+        }
+        check_range(&mut errors, "limit", self.limit, 1..=MAX_PAGE_LIMIT);
+        errors
+    }
+}
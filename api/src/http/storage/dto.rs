@@ -0,0 +1,34 @@
+use communities_core::domain::message::entities::ChannelStorage;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::http::server::public_id::PublicId;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ChannelStorageResponse {
+    #[schema(value_type = String)]
+    pub channel_id: PublicId,
+    /// Cumulative size of the attachments posted in the channel
+    pub used_bytes: u64,
+    /// Bytes the channel may store, absent when unlimited
+    pub quota_bytes: Option<u64>,
+    /// Whether the quota was set for this channel instead of being the default one
+    pub custom_quota: bool,
+}
+
+/// New quota of a channel; `null` reverts it to the default quota
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct SetStorageQuotaRequest {
+    pub quota_bytes: Option<u64>,
+}
+
+impl From<ChannelStorage> for ChannelStorageResponse {
+    fn from(storage: ChannelStorage) -> Self {
+        Self {
+            channel_id: storage.channel_id.0.into(),
+            used_bytes: storage.used_bytes,
+            quota_bytes: storage.quota_bytes,
+            custom_quota: storage.custom_quota,
+        }
+    }
+}
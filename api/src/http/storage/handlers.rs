@@ -0,0 +1,58 @@
+use axum::{Json, extract::State};
+use communities_core::domain::message::ports::MessageService;
+
+use crate::http::{
+    server::{ApiError, AppState, Response, channel_access::AuthorizedChannel},
+    storage::dto::{ChannelStorageResponse, SetStorageQuotaRequest},
+};
+
+#[utoipa::path(
+    get,
+    path = "/channels/{channel_id}/storage",
+    tag = "storage",
+    params(
+        ("channel_id" = String, Path, description = "Channel ID")
+    ),
+    responses(
+        (status = 200, description = "Attachment storage used by the channel and its quota", body = ChannelStorageResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 500, description = "Internal message error")
+    )
+)]
+#[tracing::instrument(skip(state), fields(channel_id = %channel_id))]
+pub async fn get_channel_storage(
+    State(state): State<AppState>,
+    AuthorizedChannel(channel_id): AuthorizedChannel,
+) -> Result<Response<ChannelStorageResponse>, ApiError> {
+    let storage = state.service.channel_storage(&channel_id).await?;
+    Ok(Response::ok(storage.into()))
+}
+
+#[utoipa::path(
+    put,
+    path = "/channels/{channel_id}/storage/quota",
+    tag = "storage",
+    params(
+        ("channel_id" = String, Path, description = "Channel ID")
+    ),
+    request_body = SetStorageQuotaRequest,
+    responses(
+        (status = 200, description = "Quota updated", body = ChannelStorageResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 500, description = "Internal message error")
+    )
+)]
+#[tracing::instrument(skip(state), fields(channel_id = %channel_id))]
+pub async fn set_channel_storage_quota(
+    State(state): State<AppState>,
+    AuthorizedChannel(channel_id): AuthorizedChannel,
+    Json(request): Json<SetStorageQuotaRequest>,
+) -> Result<Response<ChannelStorageResponse>, ApiError> {
+    let storage = state
+        .service
+        .set_channel_storage_quota(&channel_id, request.quota_bytes)
+        .await?;
+    Ok(Response::ok(storage.into()))
+}
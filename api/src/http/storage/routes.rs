@@ -0,0 +1,22 @@
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+use crate::http::{
+    server::{AppState, authorization::Permission, channel_access::route_with_permission},
+    storage::handlers::{
+        __path_get_channel_storage, __path_set_channel_storage_quota, get_channel_storage,
+        set_channel_storage_quota,
+    },
+};
+
+/// Administration of the attachment storage quotas of channels
+pub fn storage_routes() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new()
+        .routes(route_with_permission(
+            Permission::ManageChannels,
+            routes!(get_channel_storage),
+        ))
+        .routes(route_with_permission(
+            Permission::ManageChannels,
+            routes!(set_channel_storage_quota),
+        ))
+}
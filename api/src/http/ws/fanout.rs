@@ -0,0 +1,108 @@
+//! Per-connection send buffers of the WebSocket gateway.
+//!
+//! Frames for a connection are queued in a bounded buffer drained by a
+//! dedicated writer task, so a client reading slowly neither holds back the
+//! events of the others nor grows memory: once its buffer is full, the
+//! [`SlowConsumerPolicy`] drops the frame or disconnects the client.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use axum::extract::ws::Message as WsMessage;
+use clap::ValueEnum;
+use tokio::sync::mpsc::{self, Receiver, Sender, error::TrySendError};
+
+/// Number of realtime events not delivered to a connection since startup
+static DROPPED_REALTIME_EVENTS: AtomicU64 = AtomicU64::new(0);
+
+/// Number of connections closed by the `disconnect` policy since startup
+static SLOW_CONSUMER_DISCONNECTS: AtomicU64 = AtomicU64::new(0);
+
+/// Total number of realtime events dropped since startup, because a
+/// connection's buffer was full or it lagged behind the event bus
+pub fn dropped_realtime_events() -> u64 {
+    DROPPED_REALTIME_EVENTS.load(Ordering::Relaxed)
+}
+
+/// Total number of slow connections disconnected since startup
+pub fn slow_consumer_disconnects() -> u64 {
+    SLOW_CONSUMER_DISCONNECTS.load(Ordering::Relaxed)
+}
+
+/// Count `count` events a connection will never receive
+pub(crate) fn record_dropped(count: u64) {
+    DROPPED_REALTIME_EVENTS.fetch_add(count, Ordering::Relaxed);
+}
+
+/// What to do with a frame for a connection whose send buffer is full
+#[derive(Clone, Copy, Debug, ValueEnum, Default, PartialEq, Eq)]
+pub enum SlowConsumerPolicy {
+    /// Drop the frame and keep the connection; the client misses the event
+    #[default]
+    Drop,
+    /// Close the connection, so the client reconnects and reloads what it missed
+    Disconnect,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct FanoutConfig {
+    /// Frames queued per connection before the policy applies
+    pub send_buffer: usize,
+    pub policy: SlowConsumerPolicy,
+}
+
+impl Default for FanoutConfig {
+    fn default() -> Self {
+        Self {
+            send_buffer: 256,
+            policy: SlowConsumerPolicy::Drop,
+        }
+    }
+}
+
+/// Outcome of [`SendBuffer::push`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Delivery {
+    Queued,
+    /// The buffer was full and the frame was dropped
+    Dropped,
+    /// The buffer was full and the connection must be closed
+    Disconnect,
+    /// The writer stopped, the connection is gone
+    Closed,
+}
+
+/// Bounded queue of the frames waiting to be written to one connection
+pub struct SendBuffer {
+    frames: Sender<WsMessage>,
+    policy: SlowConsumerPolicy,
+}
+
+impl SendBuffer {
+    /// Create a buffer and the receiving end its writer drains
+    pub fn new(config: FanoutConfig) -> (Self, Receiver<WsMessage>) {
+        let (frames, receiver) = mpsc::channel(config.send_buffer.max(1));
+        let buffer = Self {
+            frames,
+            policy: config.policy,
+        };
+        (buffer, receiver)
+    }
+
+    /// Queue `frame` without waiting, applying the policy when the buffer is full
+    pub fn push(&self, frame: WsMessage) -> Delivery {
+        match self.frames.try_send(frame) {
+            Ok(()) => Delivery::Queued,
+            Err(TrySendError::Closed(_)) => Delivery::Closed,
+            Err(TrySendError::Full(_)) => {
+                record_dropped(1);
+                match self.policy {
+                    SlowConsumerPolicy::Drop => Delivery::Dropped,
+                    SlowConsumerPolicy::Disconnect => {
+                        SLOW_CONSUMER_DISCONNECTS.fetch_add(1, Ordering::Relaxed);
+                        Delivery::Disconnect
+                    }
+                }
+            }
+        }
+    }
+}
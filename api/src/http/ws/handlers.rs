@@ -0,0 +1,210 @@
+use axum::{
+    extract::{
+        Query, State,
+        ws::{Message as WsMessage, WebSocket, WebSocketUpgrade},
+    },
+    response::Response as AxumResponse,
+};
+use communities_core::domain::message::{entities::ChannelId, events::MessageEvent};
+use futures::{SinkExt, StreamExt, stream::SplitSink};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{
+    broadcast::{Receiver, error::RecvError},
+    mpsc,
+};
+use utoipa::IntoParams;
+
+use crate::http::{
+    messages::dto::MessageResponse,
+    server::{ApiError, AppState, channel_access::AuthorizedChannel, public_id::PublicId},
+    ws::{
+        fanout::{Delivery, FanoutConfig, SendBuffer, record_dropped},
+        replay::{ChannelFrame, RealtimeHub, Replay},
+    },
+};
+
+/// Frame pushed to realtime clients
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RealtimeEvent {
+    MessageCreated {
+        message: MessageResponse,
+    },
+    MessageUpdated {
+        message: MessageResponse,
+    },
+    MessageDeleted {
+        id: PublicId,
+        channel_id: PublicId,
+    },
+    /// Events of the channel were missed: reload it through the REST API
+    ResyncRequired,
+}
+
+impl From<MessageEvent> for RealtimeEvent {
+    fn from(event: MessageEvent) -> Self {
+        match event {
+            MessageEvent::Created(message) => RealtimeEvent::MessageCreated {
+                message: message.into(),
+            },
+            MessageEvent::Updated(message) => RealtimeEvent::MessageUpdated {
+                message: message.into(),
+            },
+            MessageEvent::Deleted { id, channel_id } => RealtimeEvent::MessageDeleted {
+                id: id.0.into(),
+                channel_id: channel_id.0.into(),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ResumeParams {
+    /// `resume_token` of the last frame received before reconnecting, to
+    /// receive the events missed since
+    pub resume_token: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/channels/{channel_id}/ws",
+    tag = "realtime",
+    params(
+        ("channel_id" = String, Path, description = "Channel ID"),
+        ResumeParams
+    ),
+    responses(
+        (status = 101, description = "Switching to the WebSocket protocol; message events of the channel are pushed as JSON text frames"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 500, description = "Internal message error")
+    )
+)]
+#[tracing::instrument(skip(state, channel, ws, params), fields(channel_id = %channel))]
+pub async fn channel_ws(
+    State(state): State<AppState>,
+    AuthorizedChannel(channel): AuthorizedChannel,
+    Query(params): Query<ResumeParams>,
+    ws: WebSocketUpgrade,
+) -> Result<AxumResponse, ApiError> {
+    // Subscribe before upgrading so no event is missed during the handshake,
+    // and before replaying so none falls between the replay and the live frames
+    let frames = state.realtime.subscribe();
+    let fanout = state.fanout;
+    let hub = state.realtime.clone();
+    Ok(ws.on_upgrade(move |socket| {
+        forward_events(socket, frames, hub, params.resume_token, channel, fanout)
+    }))
+}
+
+async fn forward_events(
+    socket: WebSocket,
+    mut frames: Receiver<ChannelFrame>,
+    hub: RealtimeHub,
+    resume_token: Option<String>,
+    channel: ChannelId,
+    fanout: FanoutConfig,
+) {
+    // Frames go through a bounded buffer drained by their own task, so a
+    // client reading slowly never blocks this loop
+    let (sink, mut incoming) = socket.split();
+    let (buffer, queued) = SendBuffer::new(fanout);
+    let writer = tokio::spawn(write_frames(sink, queued));
+
+    // Live frames up to this one were already replayed
+    let mut replayed_up_to = 0;
+    if let Some(token) = resume_token {
+        let delivery = match hub.replay(channel, &token) {
+            Replay::Frames(missed) => {
+                tracing::debug!(
+                    channel_id = %channel,
+                    count = missed.len(),
+                    "replaying realtime events"
+                );
+                let mut delivery = Delivery::Queued;
+                for frame in missed {
+                    replayed_up_to = frame.seq;
+                    delivery = push_frame(&buffer, &frame.frame, channel);
+                    if delivery != Delivery::Queued {
+                        break;
+                    }
+                }
+                delivery
+            }
+            Replay::ResyncRequired => push_resync(&buffer, channel),
+        };
+        if matches!(delivery, Delivery::Disconnect | Delivery::Closed) {
+            writer.abort();
+            return;
+        }
+    }
+
+    loop {
+        tokio::select! {
+            frame = frames.recv() => match frame {
+                Ok(frame) if frame.channel_id == channel && frame.seq > replayed_up_to => {
+                    match push_frame(&buffer, &frame.frame, channel) {
+                        Delivery::Queued | Delivery::Dropped => {}
+                        Delivery::Disconnect | Delivery::Closed => break,
+                    }
+                }
+                Ok(_) => {}
+                Err(RecvError::Lagged(skipped)) => {
+                    record_dropped(skipped);
+                    tracing::warn!(channel_id = %channel, skipped, "realtime subscriber lagged behind");
+                    // the client cannot tell which events it missed
+                    match push_resync(&buffer, channel) {
+                        Delivery::Queued | Delivery::Dropped => {}
+                        Delivery::Disconnect | Delivery::Closed => break,
+                    }
+                }
+                Err(RecvError::Closed) => break,
+            },
+            message = incoming.next() => match message {
+                // Clients only listen; anything but a close frame is ignored
+                Some(Ok(WsMessage::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+
+    // Frames still buffered are not worth waiting for: the client left or is
+    // too slow, and dropping the sink closes the connection
+    writer.abort();
+}
+
+fn push_frame(buffer: &SendBuffer, frame: &str, channel: ChannelId) -> Delivery {
+    let delivery = buffer.push(WsMessage::Text(frame.into()));
+    match delivery {
+        Delivery::Queued | Delivery::Closed => {}
+        Delivery::Dropped => {
+            tracing::debug!(channel_id = %channel, "realtime send buffer full, event dropped");
+        }
+        Delivery::Disconnect => {
+            tracing::warn!(channel_id = %channel, "disconnecting slow realtime subscriber");
+        }
+    }
+    delivery
+}
+
+fn push_resync(buffer: &SendBuffer, channel: ChannelId) -> Delivery {
+    match serde_json::to_string(&RealtimeEvent::ResyncRequired) {
+        Ok(frame) => push_frame(buffer, &frame, channel),
+        Err(e) => {
+            tracing::error!(error = %e, "failed to serialize realtime event");
+            Delivery::Queued
+        }
+    }
+}
+
+async fn write_frames(
+    mut sink: SplitSink<WebSocket, WsMessage>,
+    mut frames: mpsc::Receiver<WsMessage>,
+) {
+    while let Some(frame) = frames.recv().await {
+        if sink.send(frame).await.is_err() {
+            break;
+        }
+    }
+}
@@ -0,0 +1,4 @@
+pub mod fanout;
+pub mod handlers;
+pub mod replay;
+pub mod routes;
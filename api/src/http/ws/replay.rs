@@ -0,0 +1,246 @@
+//! Replay of recent realtime events to reconnecting subscribers.
+//!
+//! Every event pushed to WebSocket clients is numbered and kept for a while in
+//! a short per-channel log. Frames carry a `resume_token` that clients send
+//! back when reconnecting (`?resume_token=`) to receive the events they missed
+//! instead of reloading the channel through the REST API. When the log no
+//! longer covers a token (too old, or issued by another process), the client
+//! gets a `resync_required` frame and reloads.
+
+use std::{
+    collections::VecDeque,
+    sync::{
+        Arc, Mutex, Once,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use communities_core::domain::message::{
+    entities::ChannelId,
+    events::{MessageEvent, MessageEventBus},
+};
+use moka::sync::Cache;
+use serde::Serialize;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::http::{
+    server::public_id::{IdObfuscation, PlainIds, sync_scope_public_ids},
+    ws::handlers::RealtimeEvent,
+};
+
+/// Numbered frames a subscriber may lag behind before missing some
+const FRAME_BUS_CAPACITY: usize = 1024;
+
+/// Upper bound of the channels whose recent events are kept at once
+const MAX_CHANNEL_LOGS: u64 = 100_000;
+
+#[derive(Clone, Copy, Debug)]
+pub struct ReplayConfig {
+    /// Events kept per channel for reconnecting subscribers
+    pub capacity: usize,
+    /// How long the events of a channel are kept after its latest one
+    pub ttl: Duration,
+}
+
+impl Default for ReplayConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 100,
+            ttl: Duration::from_secs(5 * 60),
+        }
+    }
+}
+
+/// Realtime event of a channel, numbered and serialized once for every subscriber
+#[derive(Clone, Debug)]
+pub struct ChannelFrame {
+    pub channel_id: ChannelId,
+    pub seq: u64,
+    /// JSON text frame, `resume_token` included
+    pub frame: Arc<str>,
+}
+
+/// Outcome of [`RealtimeHub::replay`]
+#[derive(Debug)]
+pub enum Replay {
+    /// Events that followed the token, oldest first
+    Frames(Vec<ChannelFrame>),
+    /// Events may have been missed: the client must reload the channel
+    ResyncRequired,
+}
+
+#[derive(Serialize)]
+struct NumberedEvent {
+    #[serde(flatten)]
+    event: RealtimeEvent,
+    resume_token: String,
+}
+
+/// Recent events of a channel
+struct ChannelLog {
+    /// Every event of the channel numbered after this is in `recent`
+    complete_after: u64,
+    recent: VecDeque<ChannelFrame>,
+}
+
+/// Numbers the message events, keeps the recent ones per channel and fans
+/// them out to the WebSocket connections
+#[derive(Clone)]
+pub struct RealtimeHub {
+    inner: Arc<HubInner>,
+}
+
+struct HubInner {
+    /// Identifies this process in resume tokens, whose numbers are only
+    /// meaningful to the hub that issued them
+    epoch: u32,
+    config: ReplayConfig,
+    events: MessageEventBus,
+    sequence: AtomicU64,
+    logs: Cache<ChannelId, Arc<Mutex<ChannelLog>>>,
+    frames: broadcast::Sender<ChannelFrame>,
+    recorder: Once,
+    /// Frames are serialized once for every connection, outside of their requests
+    id_obfuscation: Arc<dyn IdObfuscation>,
+}
+
+impl RealtimeHub {
+    pub fn new(events: MessageEventBus, config: ReplayConfig) -> Self {
+        Self::with_id_obfuscation(events, config, Arc::new(PlainIds))
+    }
+
+    /// Hub whose frames carry the IDs encoded by `id_obfuscation`
+    pub fn with_id_obfuscation(
+        events: MessageEventBus,
+        config: ReplayConfig,
+        id_obfuscation: Arc<dyn IdObfuscation>,
+    ) -> Self {
+        let (frames, _) = broadcast::channel(FRAME_BUS_CAPACITY);
+        Self {
+            inner: Arc::new(HubInner {
+                epoch: rand_epoch(),
+                config,
+                events,
+                sequence: AtomicU64::new(0),
+                logs: Cache::builder()
+                    .max_capacity(MAX_CHANNEL_LOGS)
+                    .time_to_idle(config.ttl)
+                    .build(),
+                frames,
+                recorder: Once::new(),
+                id_obfuscation,
+            }),
+        }
+    }
+
+    /// Numbered frames of every channel, recorded from the first subscription on
+    pub fn subscribe(&self) -> broadcast::Receiver<ChannelFrame> {
+        let frames = self.inner.frames.subscribe();
+        self.inner.recorder.call_once(|| {
+            let hub = self.clone();
+            let mut events = self.inner.events.subscribe();
+            tokio::spawn(async move {
+                loop {
+                    match events.recv().await {
+                        Ok(event) => {
+                            hub.record(event);
+                        }
+                        Err(RecvError::Lagged(skipped)) => {
+                            // the logs have gaps now, resuming from them would miss events
+                            tracing::warn!(skipped, "realtime replay lagged behind, logs dropped");
+                            hub.inner.logs.invalidate_all();
+                        }
+                        Err(RecvError::Closed) => break,
+                    }
+                }
+            });
+        });
+        frames
+    }
+
+    /// Number `event`, keep it in the log of its channel and send it to the subscribers
+    pub fn record(&self, event: MessageEvent) -> Option<ChannelFrame> {
+        let channel_id = event.channel_id();
+        let seq = self.inner.sequence.fetch_add(1, Ordering::Relaxed) + 1;
+        let numbered = NumberedEvent {
+            event: RealtimeEvent::from(event),
+            resume_token: self.token(seq),
+        };
+        let frame = match sync_scope_public_ids(&self.inner.id_obfuscation, || {
+            serde_json::to_string(&numbered)
+        }) {
+            Ok(frame) => ChannelFrame {
+                channel_id,
+                seq,
+                frame: frame.into(),
+            },
+            Err(e) => {
+                tracing::error!(error = %e, "failed to serialize realtime event");
+                return None;
+            }
+        };
+
+        let log = self.inner.logs.get_with(channel_id, || {
+            Arc::new(Mutex::new(ChannelLog {
+                complete_after: seq - 1,
+                recent: VecDeque::new(),
+            }))
+        });
+        {
+            let mut log = log.lock().unwrap();
+            log.recent.push_back(frame.clone());
+            while log.recent.len() > self.inner.config.capacity {
+                if let Some(evicted) = log.recent.pop_front() {
+                    log.complete_after = evicted.seq;
+                }
+            }
+        }
+
+        // An error only means there is currently no subscriber
+        let _ = self.inner.frames.send(frame.clone());
+        Some(frame)
+    }
+
+    /// Events of `channel_id` that followed the one `token` was issued with
+    pub fn replay(&self, channel_id: ChannelId, token: &str) -> Replay {
+        let Some(after) = self.parse_token(token) else {
+            return Replay::ResyncRequired;
+        };
+        // without a log, the events that followed the token expired
+        let Some(log) = self.inner.logs.get(&channel_id) else {
+            return Replay::ResyncRequired;
+        };
+
+        let log = log.lock().unwrap();
+        if after < log.complete_after {
+            return Replay::ResyncRequired;
+        }
+        Replay::Frames(
+            log.recent
+                .iter()
+                .filter(|frame| frame.seq > after)
+                .cloned()
+                .collect(),
+        )
+    }
+
+    fn token(&self, seq: u64) -> String {
+        format!("{:08x}.{seq}", self.inner.epoch)
+    }
+
+    /// Number of the event a token was issued with, `None` for tokens of
+    /// another hub or from the future
+    fn parse_token(&self, token: &str) -> Option<u64> {
+        let (epoch, seq) = token.split_once('.')?;
+        let epoch = u32::from_str_radix(epoch, 16).ok()?;
+        let seq: u64 = seq.parse().ok()?;
+        (epoch == self.inner.epoch && seq <= self.inner.sequence.load(Ordering::Relaxed))
+            .then_some(seq)
+    }
+}
+
+/// Epoch of a hub, distinct across restarts
+fn rand_epoch() -> u32 {
+    uuid::Uuid::new_v4().as_u128() as u32
+}
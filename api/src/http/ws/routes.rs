@@ -0,0 +1,13 @@
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+use crate::http::{
+    server::{AppState, authorization::Permission, channel_access::route_with_permission},
+    ws::handlers::{__path_channel_ws, channel_ws},
+};
+
+pub fn ws_routes() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new().routes(route_with_permission(
+        Permission::ViewChannels,
+        routes!(channel_ws),
+    ))
+}
@@ -1,9 +1,22 @@
 pub mod app;
 pub mod config;
+pub mod consumer;
 pub mod http;
+pub mod lifecycle;
+pub mod logging;
+pub mod openapi_diff;
 pub use app::App;
 pub use config::Config;
+pub use http::admin::routes::admin_routes;
+pub use http::attachments::routes::attachment_routes;
+pub use http::channel_locks::routes::channel_lock_routes;
+pub use http::channel_settings::routes::channel_settings_routes;
+pub use http::graphql::routes::graphql_routes;
 pub use http::health::routes::health_routes;
-pub use http::messages::routes::message_routes;
+pub use http::legal_holds::routes::legal_hold_routes;
+pub use http::messages::routes::{export_download_routes, message_routes};
+pub use http::read_markers::routes::read_marker_routes;
 pub use http::server::middleware::auth::{AuthMiddleware, entities::AuthValidator};
 pub use http::server::{ApiError, AppState};
+pub use http::storage::routes::storage_routes;
+pub use http::ws::routes::ws_routes;
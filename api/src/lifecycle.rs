@@ -0,0 +1,108 @@
+//! Announcements of the start and shutdown of this replica, on the
+//! `lifecycle` log target and on the broker, so platform dashboards can
+//! correlate deploys with error spikes across the fleet.
+
+use std::{
+    hash::{DefaultHasher, Hash, Hasher},
+    sync::Arc,
+};
+
+use chrono::Utc;
+use communities_core::domain::lifecycle::{
+    entities::{LifecycleStage, ServiceLifecycleEvent},
+    ports::LifecyclePublisher,
+};
+
+use crate::Config;
+
+/// Version of the service announced by every lifecycle event
+pub const SERVICE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[derive(Clone)]
+pub struct Lifecycle {
+    instance_id: String,
+    config_hash: String,
+    publisher: Option<Arc<dyn LifecyclePublisher>>,
+}
+
+impl Lifecycle {
+    /// Announce the lifecycle of `instance_id` in the logs, and on the broker
+    /// through `publisher` when set
+    pub fn new(
+        instance_id: impl Into<String>,
+        config_hash: impl Into<String>,
+        publisher: Option<Arc<dyn LifecyclePublisher>>,
+    ) -> Self {
+        Self {
+            instance_id: instance_id.into(),
+            config_hash: config_hash.into(),
+            publisher,
+        }
+    }
+
+    /// Log and publish that the replica reached `stage`; a broker failure is
+    /// only logged, never holding the start or shutdown back
+    pub async fn announce(&self, stage: LifecycleStage) {
+        let event = ServiceLifecycleEvent {
+            stage,
+            instance_id: self.instance_id.clone(),
+            version: SERVICE_VERSION.to_string(),
+            config_hash: self.config_hash.clone(),
+            occurred_at: Utc::now(),
+        };
+        tracing::info!(
+            target: "lifecycle",
+            ?stage,
+            instance_id = %event.instance_id,
+            version = %event.version,
+            config_hash = %event.config_hash,
+            "service lifecycle"
+        );
+
+        if let Some(publisher) = &self.publisher
+            && let Err(e) = publisher.publish(&event).await
+        {
+            tracing::warn!(error = %e, ?stage, "failed to publish lifecycle event");
+        }
+    }
+}
+
+/// Fingerprint of `config`, the same on replicas configured alike whatever
+/// their instance ID
+pub fn config_hash(config: &Config) -> String {
+    let mut config = config.clone();
+    config.jobs.instance_id.clear();
+
+    let mut hasher = DefaultHasher::new();
+    format!("{config:?}").hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+/// Resolves once the replica is asked to stop, by Ctrl-C or SIGTERM
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::warn!(error = %e, "failed to listen for Ctrl-C");
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                tracing::warn!(error = %e, "failed to listen for SIGTERM");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
@@ -0,0 +1,242 @@
+//! Tracing setup that keeps user data and credentials out of the logs.
+//!
+//! Every event and span field goes through [`RedactingFields`]:
+//! - fields whose name is sensitive (message content, attachment URLs,
+//!   tokens, ...) are replaced by `[REDACTED]`
+//! - other values, including the event message, are scrubbed with
+//!   [`redact_text`], which catches the same data once stringified (e.g. a
+//!   `Debug`-formatted request, or a MongoDB error echoing a filter)
+
+use std::fmt::{self, Debug};
+
+use tracing::{Subscriber, field::Field, level_filters::LevelFilter};
+use tracing_subscriber::{
+    Layer,
+    field::{RecordFields, Visit},
+    fmt::{FormatFields, format::Writer},
+    layer::SubscriberExt,
+    registry::LookupSpan,
+    util::SubscriberInitExt,
+};
+
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Field names whose value is always redacted
+const SENSITIVE_FIELDS: &[&str] = &[
+    "content",
+    "url",
+    "token",
+    "authorization",
+    "password",
+    "passphrase",
+    "secret",
+    "jwt",
+    "encryption_key",
+];
+
+/// Suffixes of field names whose value is always redacted
+const SENSITIVE_SUFFIXES: &[&str] = &["_content", "_url", "_token", "_secret", "_password"];
+
+/// Install the redacting fmt layer as the global subscriber
+pub fn init_tracing(max_level: LevelFilter) {
+    tracing_subscriber::registry()
+        .with(redacting_layer().with_filter(max_level))
+        .init();
+}
+
+/// The fmt layer used by the binaries, with field redaction
+pub fn redacting_layer<S>() -> impl Layer<S>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    tracing_subscriber::fmt::layer()
+        .with_target(false)
+        .fmt_fields(RedactingFields)
+}
+
+pub fn is_sensitive_field(name: &str) -> bool {
+    let name = name.trim_start_matches("r#").to_ascii_lowercase();
+    SENSITIVE_FIELDS.contains(&name.as_str())
+        || SENSITIVE_SUFFIXES
+            .iter()
+            .any(|suffix| name.ends_with(suffix))
+}
+
+/// Scrub sensitive data from free text:
+/// - `Bearer` credentials and JWTs
+/// - quoted values of sensitive `Debug` fields (`content: "..."`)
+/// - the key values MongoDB echoes in duplicate key errors (`dup key: { ... }`)
+pub fn redact_text(text: &str) -> String {
+    let text = redact_debug_fields(text);
+    let text = redact_after(&text, "dup key: ", balanced_braces_len);
+    let text = redact_after(&text, "Bearer ", token_len);
+    redact_jwts(&text)
+}
+
+/// [`FormatFields`] implementation applying the redaction policy
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RedactingFields;
+
+impl<'writer> FormatFields<'writer> for RedactingFields {
+    fn format_fields<R: RecordFields>(&self, writer: Writer<'writer>, fields: R) -> fmt::Result {
+        let mut visitor = RedactingVisitor {
+            writer,
+            result: Ok(()),
+            empty: true,
+        };
+        fields.record(&mut visitor);
+        visitor.result
+    }
+}
+
+struct RedactingVisitor<'writer> {
+    writer: Writer<'writer>,
+    result: fmt::Result,
+    empty: bool,
+}
+
+impl RedactingVisitor<'_> {
+    fn write_field(&mut self, field: &Field, value: &str) {
+        if self.result.is_err() {
+            return;
+        }
+        let separator = if self.empty { "" } else { " " };
+        self.empty = false;
+
+        let name = field.name();
+        self.result = if name == "message" {
+            write!(self.writer, "{separator}{}", redact_text(value))
+        } else if is_sensitive_field(name) {
+            write!(
+                self.writer,
+                "{separator}{}={REDACTED}",
+                name.trim_start_matches("r#")
+            )
+        } else {
+            write!(
+                self.writer,
+                "{separator}{}={}",
+                name.trim_start_matches("r#"),
+                redact_text(value)
+            )
+        };
+    }
+}
+
+impl Visit for RedactingVisitor<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.write_field(field, value);
+        } else {
+            self.write_field(field, &format!("{value:?}"));
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.write_field(field, &format!("{value:?}"));
+    }
+}
+
+/// Replace `name: "value"` (as printed by `#[derive(Debug)]`) for sensitive names
+fn redact_debug_fields(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(pos) = rest.find(": \"") {
+        let (before, after) = rest.split_at(pos);
+        let name_start = before
+            .rfind(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .map_or(0, |i| i + 1);
+        let value = &after[3..];
+
+        if is_sensitive_field(&before[name_start..]) {
+            output.push_str(before);
+            output.push_str(": ");
+            output.push_str(REDACTED);
+            rest = &value[quoted_len(value)..];
+        } else {
+            let len = quoted_len(value);
+            output.push_str(before);
+            output.push_str(": \"");
+            output.push_str(&value[..len]);
+            rest = &value[len..];
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+/// Replace what follows each `marker` (up to `len_of` of the remaining text)
+fn redact_after(text: &str, marker: &str, len_of: impl Fn(&str) -> usize) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(pos) = rest.find(marker) {
+        let value = &rest[pos + marker.len()..];
+        output.push_str(&rest[..pos + marker.len()]);
+        output.push_str(REDACTED);
+        rest = &value[len_of(value)..];
+    }
+    output.push_str(rest);
+    output
+}
+
+fn redact_jwts(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(pos) = rest.find("eyJ") {
+        let candidate = &rest[pos..];
+        let len = token_len(candidate);
+        output.push_str(&rest[..pos]);
+        if candidate[..len].matches('.').count() == 2 {
+            output.push_str(REDACTED);
+        } else {
+            output.push_str(&candidate[..len.max(3)]);
+        }
+        rest = &candidate[len.max(3)..];
+    }
+    output.push_str(rest);
+    output
+}
+
+/// Length of a quoted string's remainder, closing quote included
+fn quoted_len(value: &str) -> usize {
+    let mut escaped = false;
+    for (i, c) in value.char_indices() {
+        match c {
+            '\\' if !escaped => escaped = true,
+            '"' if !escaped => return i + 1,
+            _ => escaped = false,
+        }
+    }
+    value.len()
+}
+
+/// Length of a `{ ... }` block at the start of `value`, nested braces included
+fn balanced_braces_len(value: &str) -> usize {
+    if !value.starts_with('{') {
+        return token_len(value);
+    }
+    let mut depth = 0usize;
+    for (i, c) in value.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return i + 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    value.len()
+}
+
+/// Length of the token (base64url, JWT dots, `+/=`) at the start of `value`
+fn token_len(value: &str) -> usize {
+    value
+        .find(|c: char| !(c.is_ascii_alphanumeric() || "-_.+/=".contains(c)))
+        .unwrap_or(value.len())
+}
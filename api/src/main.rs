@@ -5,17 +5,15 @@ use dotenv::dotenv;
 use api::config::Config;
 use clap::Parser;
 
-use tracing::{info, trace};
+use api::logging::init_tracing;
+use tracing::{info, level_filters::LevelFilter, trace};
 
 #[tokio::main]
 async fn main() -> Result<(), ApiError> {
-    // Initialize tracing subscriber with environment filter and a default level
     // Initialize a basic tracing subscriber. Using a simple default level (INFO).
     // For more advanced filtering (RUST_LOG) we can switch to EnvFilter when desired.
-    tracing_subscriber::fmt()
-        .with_max_level(tracing::Level::INFO)
-        .with_target(false)
-        .init();
+    // Message content, URLs and credentials are redacted from every event.
+    init_tracing(LevelFilter::INFO);
 
     // Load environment variables from .env file
     trace!("loading env vars and config file...");
@@ -0,0 +1,307 @@
+//! Breaking changes between two OpenAPI specifications, so a change to the
+//! API that would break its clients is caught before it ships.
+//!
+//! Reported as breaking:
+//! - removed paths, operations and responses
+//! - new required parameters, and properties newly required by a schema
+//!   (conservatively, whether the schema is read or written by clients)
+//! - removed schemas and schema properties
+//! - parameters and properties whose type changed
+//!
+//! Additions that clients can ignore (paths, optional fields, responses) are
+//! not reported.
+
+use std::fmt;
+
+use serde_json::{Map, Value};
+
+const METHODS: &[&str] = &[
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BreakingChange {
+    RemovedPath {
+        path: String,
+    },
+    RemovedOperation {
+        operation: String,
+    },
+    NewRequiredParameter {
+        operation: String,
+        name: String,
+    },
+    ChangedParameterType {
+        operation: String,
+        name: String,
+        from: String,
+        to: String,
+    },
+    RemovedResponse {
+        operation: String,
+        status: String,
+    },
+    RemovedSchema {
+        schema: String,
+    },
+    RemovedProperty {
+        schema: String,
+        property: String,
+    },
+    NewRequiredProperty {
+        schema: String,
+        property: String,
+    },
+    ChangedPropertyType {
+        schema: String,
+        property: String,
+        from: String,
+        to: String,
+    },
+}
+
+impl fmt::Display for BreakingChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BreakingChange::RemovedPath { path } => write!(f, "path {path} was removed"),
+            BreakingChange::RemovedOperation { operation } => {
+                write!(f, "operation {operation} was removed")
+            }
+            BreakingChange::NewRequiredParameter { operation, name } => {
+                write!(f, "{operation} requires the new parameter `{name}`")
+            }
+            BreakingChange::ChangedParameterType {
+                operation,
+                name,
+                from,
+                to,
+            } => write!(
+                f,
+                "{operation}: parameter `{name}` changed from {from} to {to}"
+            ),
+            BreakingChange::RemovedResponse { operation, status } => {
+                write!(f, "{operation} no longer answers {status}")
+            }
+            BreakingChange::RemovedSchema { schema } => write!(f, "schema {schema} was removed"),
+            BreakingChange::RemovedProperty { schema, property } => {
+                write!(f, "{schema}.{property} was removed")
+            }
+            BreakingChange::NewRequiredProperty { schema, property } => {
+                write!(f, "{schema}.{property} is newly required")
+            }
+            BreakingChange::ChangedPropertyType {
+                schema,
+                property,
+                from,
+                to,
+            } => write!(f, "{schema}.{property} changed from {from} to {to}"),
+        }
+    }
+}
+
+/// Changes of `current` that break the clients of `baseline`
+pub fn breaking_changes(baseline: &Value, current: &Value) -> Vec<BreakingChange> {
+    let mut changes = Vec::new();
+    diff_paths(baseline, current, &mut changes);
+    diff_schemas(baseline, current, &mut changes);
+    changes
+}
+
+fn object<'a>(value: &'a Value, pointer: &str) -> Option<&'a Map<String, Value>> {
+    value.pointer(pointer).and_then(Value::as_object)
+}
+
+fn diff_paths(baseline: &Value, current: &Value, changes: &mut Vec<BreakingChange>) {
+    let Some(baseline_paths) = object(baseline, "/paths") else {
+        return;
+    };
+    let current_paths = object(current, "/paths");
+
+    for (path, baseline_item) in baseline_paths {
+        let Some(current_item) = current_paths.and_then(|paths| paths.get(path)) else {
+            changes.push(BreakingChange::RemovedPath { path: path.clone() });
+            continue;
+        };
+
+        for method in METHODS {
+            let Some(baseline_op) = baseline_item.get(*method) else {
+                continue;
+            };
+            let operation = format!("{} {path}", method.to_uppercase());
+            match current_item.get(*method) {
+                Some(current_op) => diff_operation(&operation, baseline_op, current_op, changes),
+                None => changes.push(BreakingChange::RemovedOperation { operation }),
+            }
+        }
+    }
+}
+
+fn diff_operation(
+    operation: &str,
+    baseline: &Value,
+    current: &Value,
+    changes: &mut Vec<BreakingChange>,
+) {
+    let parameters = |op: &Value| -> Vec<(String, Value)> {
+        op.get("parameters")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|parameter| {
+                let name = parameter.get("name")?.as_str()?;
+                let location = parameter
+                    .get("in")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default();
+                Some((format!("{location}:{name}"), parameter.clone()))
+            })
+            .collect()
+    };
+    let baseline_parameters = parameters(baseline);
+
+    for (key, parameter) in parameters(current) {
+        let name = parameter["name"].as_str().unwrap_or_default().to_string();
+        match baseline_parameters
+            .iter()
+            .find(|(baseline_key, _)| *baseline_key == key)
+        {
+            Some((_, baseline_parameter)) => {
+                let from = type_of(&baseline_parameter["schema"]);
+                let to = type_of(&parameter["schema"]);
+                if from != to {
+                    changes.push(BreakingChange::ChangedParameterType {
+                        operation: operation.to_string(),
+                        name,
+                        from,
+                        to,
+                    });
+                } else if is_required(&parameter) && !is_required(baseline_parameter) {
+                    changes.push(BreakingChange::NewRequiredParameter {
+                        operation: operation.to_string(),
+                        name,
+                    });
+                }
+            }
+            None if is_required(&parameter) => {
+                changes.push(BreakingChange::NewRequiredParameter {
+                    operation: operation.to_string(),
+                    name,
+                });
+            }
+            None => {}
+        }
+    }
+
+    if let Some(responses) = baseline.get("responses").and_then(Value::as_object) {
+        for status in responses.keys() {
+            if current.pointer(&format!("/responses/{status}")).is_none() {
+                changes.push(BreakingChange::RemovedResponse {
+                    operation: operation.to_string(),
+                    status: status.clone(),
+                });
+            }
+        }
+    }
+}
+
+fn is_required(parameter: &Value) -> bool {
+    parameter
+        .get("required")
+        .and_then(Value::as_bool)
+        .unwrap_or(false)
+}
+
+fn diff_schemas(baseline: &Value, current: &Value, changes: &mut Vec<BreakingChange>) {
+    let Some(baseline_schemas) = object(baseline, "/components/schemas") else {
+        return;
+    };
+    let current_schemas = object(current, "/components/schemas");
+
+    for (name, baseline_schema) in baseline_schemas {
+        let Some(current_schema) = current_schemas.and_then(|schemas| schemas.get(name)) else {
+            changes.push(BreakingChange::RemovedSchema {
+                schema: name.clone(),
+            });
+            continue;
+        };
+
+        let current_properties = current_schema.get("properties").and_then(Value::as_object);
+        if let Some(properties) = baseline_schema.get("properties").and_then(Value::as_object) {
+            for (property, baseline_property) in properties {
+                match current_properties.and_then(|properties| properties.get(property)) {
+                    Some(current_property) => {
+                        let from = type_of(baseline_property);
+                        let to = type_of(current_property);
+                        if from != to {
+                            changes.push(BreakingChange::ChangedPropertyType {
+                                schema: name.clone(),
+                                property: property.clone(),
+                                from,
+                                to,
+                            });
+                        }
+                    }
+                    None => changes.push(BreakingChange::RemovedProperty {
+                        schema: name.clone(),
+                        property: property.clone(),
+                    }),
+                }
+            }
+        }
+
+        let required = |schema: &Value| -> Vec<String> {
+            schema
+                .get("required")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(|field| field.as_str().map(str::to_string))
+                .collect()
+        };
+        let baseline_required = required(baseline_schema);
+        for property in required(current_schema) {
+            if !baseline_required.contains(&property) {
+                changes.push(BreakingChange::NewRequiredProperty {
+                    schema: name.clone(),
+                    property,
+                });
+            }
+        }
+    }
+}
+
+/// Type of a schema as compared between the specifications: its reference,
+/// or its type and format, recursively for the items of arrays
+fn type_of(schema: &Value) -> String {
+    if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+        return reference
+            .rsplit('/')
+            .next()
+            .unwrap_or(reference)
+            .to_string();
+    }
+    for composition in ["oneOf", "anyOf", "allOf"] {
+        if let Some(variants) = schema.get(composition).and_then(Value::as_array) {
+            let variants: Vec<String> = variants.iter().map(type_of).collect();
+            return format!("{composition}({})", variants.join(", "));
+        }
+    }
+
+    let kind = match schema.get("type") {
+        Some(Value::String(kind)) => kind.clone(),
+        Some(Value::Array(kinds)) => kinds
+            .iter()
+            .filter_map(Value::as_str)
+            .collect::<Vec<_>>()
+            .join(" | "),
+        _ => "any".to_string(),
+    };
+    let kind = match schema.get("format").and_then(Value::as_str) {
+        Some(format) => format!("{kind} ({format})"),
+        None => kind,
+    };
+    match schema.get("items") {
+        Some(items) => format!("{kind} of {}", type_of(items)),
+        None => kind,
+    }
+}
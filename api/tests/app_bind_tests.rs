@@ -0,0 +1,31 @@
+use api::{App, Config, config::Environment};
+
+fn ephemeral_config() -> Config {
+    let mut config = Config::default();
+    config.database.mongo_uri = "mongodb://localhost:27017".to_string();
+    config.database.mongo_db_name = "message_test_db".to_string();
+    config.environment = Environment::Test;
+    config.message.api_port = 0;
+    config.message.health_port = 0;
+    config
+}
+
+#[tokio::test]
+async fn bind_uses_ephemeral_ports() {
+    let first = App::new(ephemeral_config()).await.expect("build first app");
+    let second = App::new(ephemeral_config()).await.expect("build second app");
+
+    // keep both listeners alive so the OS cannot hand out the same port twice
+    let first_listeners = first.bind().await.expect("bind first app");
+    let second_listeners = second.bind().await.expect("bind second app");
+    let first_addrs = first_listeners.addresses();
+    let second_addrs = second_listeners.addresses();
+
+    assert_ne!(first_addrs.api.port(), 0);
+    assert_ne!(first_addrs.health.port(), 0);
+    // port 0 never collapses into the shared-listener mode
+    assert_ne!(first_addrs.api, first_addrs.health);
+    assert_ne!(first_addrs.api, second_addrs.api);
+
+    assert_eq!(first.bound_addresses(), Some(first_addrs));
+}
@@ -0,0 +1,53 @@
+use api::http::server::middleware::body_logging::BodyLogging;
+use uuid::Uuid;
+
+fn logging(sample_percent: f64, user_ids: Vec<Uuid>) -> BodyLogging {
+    BodyLogging::new(sample_percent, user_ids, vec!["Nickname".to_string()], 4096)
+        .expect("body logging enabled")
+}
+
+#[test]
+fn body_logging_is_disabled_without_sample_or_users() {
+    assert!(BodyLogging::new(0.0, vec![], vec![], 4096).is_none());
+}
+
+#[test]
+fn requests_are_sampled_evenly() {
+    let logging = logging(10.0, vec![]);
+
+    let sampled = (0..1000).filter(|_| logging.samples(None)).count();
+    assert_eq!(sampled, 100);
+}
+
+#[test]
+fn followed_users_are_always_sampled() {
+    let followed = Uuid::new_v4();
+    let logging = logging(0.0, vec![followed]);
+
+    assert!((0..10).all(|_| logging.samples(Some(followed))));
+    assert!(!logging.samples(Some(Uuid::new_v4())));
+    assert!(!logging.samples(None));
+}
+
+#[test]
+fn sensitive_and_configured_keys_are_redacted() {
+    let logging = logging(100.0, vec![]);
+    let body = br#"{"content":"secret","nickname":"bob","attachments":[{"url":"https://cdn/a.png","name":"a.png"}],"channel_id":"42"}"#;
+
+    let logged = logging.redact(body);
+    assert!(!logged.contains("secret"));
+    assert!(!logged.contains("bob"));
+    assert!(!logged.contains("cdn"));
+    assert!(logged.contains("a.png"));
+    assert!(logged.contains("\"channel_id\":\"42\""));
+}
+
+#[test]
+fn non_json_bodies_are_logged_by_size_and_long_ones_cut() {
+    let logging = BodyLogging::new(100.0, vec![], vec![], 16).expect("body logging enabled");
+
+    assert_eq!(logging.redact(b"\x81\xa2id\x01"), "[5 bytes, not JSON]");
+
+    let logged = logging.redact(br#"{"name":"a rather long attachment name"}"#);
+    assert_eq!(logged, r#"{"name":"a rathe..."#);
+}
@@ -0,0 +1,84 @@
+use api::ApiError;
+use api::http::server::authorization::{
+    Authorization, AuthzError, DummyAuthz, Permission, Resource,
+};
+use api::http::server::channel_access::authorize_channel;
+use communities_core::domain::message::entities::ChannelId;
+use uuid::Uuid;
+
+/// Grants `ViewChannels` only
+struct ReadOnlyAuthz;
+
+#[async_trait::async_trait]
+impl Authorization for ReadOnlyAuthz {
+    async fn check(
+        &self,
+        _actor: Uuid,
+        permission: Permission,
+        _resource: Resource,
+    ) -> Result<bool, AuthzError> {
+        Ok(matches!(permission, Permission::ViewChannels))
+    }
+}
+
+struct FailingAuthz;
+
+#[async_trait::async_trait]
+impl Authorization for FailingAuthz {
+    async fn check(
+        &self,
+        _actor: Uuid,
+        _permission: Permission,
+        _resource: Resource,
+    ) -> Result<bool, AuthzError> {
+        Err(AuthzError("unreachable".to_string()))
+    }
+}
+
+fn channel() -> ChannelId {
+    ChannelId::from(Uuid::new_v4())
+}
+
+#[tokio::test]
+async fn granted_permissions_pass() {
+    let user = Uuid::new_v4();
+    assert!(
+        authorize_channel(
+            &DummyAuthz::new(),
+            user,
+            Permission::SendMessages,
+            channel()
+        )
+        .await
+        .is_ok()
+    );
+    assert!(
+        authorize_channel(&ReadOnlyAuthz, user, Permission::ViewChannels, channel())
+            .await
+            .is_ok()
+    );
+}
+
+#[tokio::test]
+async fn missing_permissions_are_forbidden() {
+    let result = authorize_channel(
+        &ReadOnlyAuthz,
+        Uuid::new_v4(),
+        Permission::SendMessages,
+        channel(),
+    )
+    .await;
+    assert!(matches!(result, Err(ApiError::Forbidden)));
+}
+
+#[tokio::test]
+async fn authz_failures_are_internal_errors() {
+    let result = authorize_channel(
+        &FailingAuthz,
+        Uuid::new_v4(),
+        Permission::ViewChannels,
+        channel(),
+    )
+    .await;
+    assert!(matches!(result, Err(ApiError::InternalServerError)));
+}
@@ -0,0 +1,20 @@
+use api::consumer::{ChannelDeletedEvent, ConsumerError};
+use uuid::Uuid;
+
+#[test]
+fn channel_deleted_payload_is_parsed() {
+    let channel_id = Uuid::new_v4();
+    let payload = format!(r#"{{"channel_id":"{channel_id}","deleted_by":"someone"}}"#);
+
+    let event = ChannelDeletedEvent::from_payload(payload.as_bytes()).expect("valid payload");
+    assert_eq!(event.channel_id, channel_id);
+}
+
+#[test]
+fn malformed_payloads_are_not_retried() {
+    for payload in [&b"not json"[..], br#"{}"#, br#"{"channel_id":"nope"}"#] {
+        let err = ChannelDeletedEvent::from_payload(payload).unwrap_err();
+        assert!(matches!(err, ConsumerError::InvalidPayload { .. }));
+        assert!(!err.is_retryable());
+    }
+}
@@ -0,0 +1,82 @@
+use std::sync::Arc;
+
+use api::consumer::{ChannelMemberEvent, ChannelMembersHandler, ConsumerError};
+use communities_core::domain::membership::ports::{ChannelMembership, InMemoryChannelMembership};
+use communities_core::domain::message::entities::{ChannelId, UserId};
+use uuid::Uuid;
+
+fn payload(channel_id: Uuid, user_id: Uuid, role_ids: &[Uuid], occurred_at: &str) -> Vec<u8> {
+    serde_json::json!({
+        "channel_id": channel_id,
+        "user_id": user_id,
+        "role_ids": role_ids,
+        "occurred_at": occurred_at,
+    })
+    .to_string()
+    .into_bytes()
+}
+
+#[test]
+fn member_payload_is_parsed() {
+    let (channel_id, user_id, role_id) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+
+    let event = ChannelMemberEvent::from_payload(&payload(
+        channel_id,
+        user_id,
+        &[role_id],
+        "2026-01-02T03:04:05Z",
+    ))
+    .expect("valid payload");
+    assert_eq!(event.channel_id, channel_id);
+    assert_eq!(event.user_id, user_id);
+    assert_eq!(event.role_ids, vec![role_id]);
+}
+
+#[tokio::test]
+async fn membership_events_are_applied_by_routing_key() {
+    let membership = InMemoryChannelMembership::new();
+    let handler = ChannelMembersHandler::new(Arc::new(membership.clone()));
+    let (channel_id, user_id) = (Uuid::new_v4(), Uuid::new_v4());
+    let channel = ChannelId::from(channel_id);
+
+    let joined = payload(channel_id, user_id, &[], "2026-01-02T03:04:05Z");
+    handler
+        .handle("member.joined", &joined)
+        .await
+        .expect("joined");
+    assert_eq!(
+        membership.list_members(&channel, None).await.unwrap(),
+        vec![UserId(user_id)]
+    );
+
+    let left = payload(channel_id, user_id, &[], "2026-01-02T04:00:00Z");
+    handler.handle("member.left", &left).await.expect("left");
+    // the join redelivered after the departure is outdated
+    handler
+        .handle("member.joined", &joined)
+        .await
+        .expect("redelivered");
+    assert!(
+        membership
+            .list_members(&channel, None)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[tokio::test]
+async fn unknown_and_malformed_events_are_not_retried() {
+    let handler = ChannelMembersHandler::new(Arc::new(InMemoryChannelMembership::new()));
+    let valid = payload(Uuid::new_v4(), Uuid::new_v4(), &[], "2026-01-02T03:04:05Z");
+
+    for (routing_key, payload) in [
+        ("member.banned", valid.as_slice()),
+        ("member.joined", &b"not json"[..]),
+        ("member.joined", br#"{"channel_id":"nope"}"#),
+    ] {
+        let err = handler.handle(routing_key, payload).await.unwrap_err();
+        assert!(matches!(err, ConsumerError::InvalidPayload { .. }));
+        assert!(!err.is_retryable());
+    }
+}
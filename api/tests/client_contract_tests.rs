@@ -0,0 +1,139 @@
+use api::app::openapi;
+use api::http::messages::dto::MessageResponse;
+use api::http::server::ApiError;
+use api::http::server::validated_query::FieldError;
+use chrono::Utc;
+use communities_client::{ErrorCode, OPERATIONS, models};
+use communities_core::domain::common::CoreError;
+use communities_core::domain::message::entities::{
+    AttachmentId, AuthorId, ChannelId, Embed, Mentions, Message, MessageId, UserId,
+};
+use uuid::Uuid;
+
+#[test]
+fn client_operations_exist_in_the_spec() {
+    let spec = openapi();
+
+    for (method, path) in OPERATIONS {
+        let item = spec
+            .paths
+            .paths
+            .get(*path)
+            .unwrap_or_else(|| panic!("{path} is not in the spec"));
+        let operation = match *method {
+            "get" => &item.get,
+            "post" => &item.post,
+            "put" => &item.put,
+            "delete" => &item.delete,
+            method => panic!("unexpected method {method}"),
+        };
+        assert!(operation.is_some(), "{method} {path} is not in the spec");
+    }
+}
+
+#[test]
+fn client_knows_every_error_code() {
+    let message_id = MessageId::from(Uuid::new_v4());
+    let errors = [
+        ApiError::ServiceUnavailable { msg: "down".into() },
+        ApiError::ReadOnly,
+        ApiError::InternalServerError,
+        ApiError::Unauthorized,
+        ApiError::Forbidden,
+        ApiError::NotFound,
+        ApiError::BadRequest { msg: "bad".into() },
+        ApiError::InvalidQuery {
+            fields: vec![FieldError::new("limit", "must be between 1 and 50")],
+        },
+        ApiError::from(CoreError::PinLimitReached {
+            channel_id: ChannelId::from(Uuid::new_v4()),
+            limit: 1,
+        }),
+        ApiError::from(CoreError::MessageUnderLegalHold { id: message_id }),
+        ApiError::from(CoreError::MessageRevisionMismatch {
+            id: message_id,
+            revision: 1,
+        }),
+        ApiError::from(CoreError::ChannelStorageQuotaExceeded {
+            channel_id: ChannelId::from(Uuid::new_v4()),
+        }),
+        ApiError::from(CoreError::ChannelWriteLocked {
+            channel_id: ChannelId::from(Uuid::new_v4()),
+            retry_after_secs: 30,
+        }),
+        ApiError::from(CoreError::MessageAlreadyExists { id: message_id }),
+        ApiError::from(CoreError::ReactionNotAllowed {
+            emoji: "🎉".into(),
+            allowed: vec!["👍".into()],
+        }),
+        ApiError::from(CoreError::AttachmentNotFound {
+            id: AttachmentId::from(Uuid::new_v4()),
+        }),
+        ApiError::from(CoreError::AttachmentNotUploaded {
+            id: AttachmentId::from(Uuid::new_v4()),
+        }),
+        ApiError::from(CoreError::TooManyAttachments { max: 10 }),
+        ApiError::from(CoreError::AttachmentTooLarge { max_bytes: 1024 }),
+        ApiError::from(CoreError::AttachmentTypeNotAllowed {
+            content_type: "application/x-msdownload".into(),
+        }),
+    ];
+
+    for error in errors {
+        let code = ErrorCode::from(error.error_code());
+        assert!(!matches!(code, ErrorCode::Other(_)), "unknown code {code}");
+        assert_eq!(code.as_str(), error.error_code());
+    }
+}
+
+#[test]
+fn client_models_decode_api_responses() {
+    let mentioned = Uuid::new_v4();
+    let message = Message {
+        id: MessageId::from(Uuid::new_v4()),
+        channel_id: ChannelId::from(Uuid::new_v4()),
+        author_id: AuthorId::from(Uuid::new_v4()),
+        content: format!("contract <@{mentioned}>"),
+        reply_to_message_id: None,
+        attachments: vec![],
+        is_pinned: true,
+        reactions: vec![],
+        revision: 2,
+        sequence: 7,
+        reply_count: 0,
+        last_reply_at: None,
+        archived_at: None,
+        created_at: Utc::now(),
+        updated_at: Some(Utc::now()),
+        deleted_at: None,
+        expires_at: None,
+        nonce: None,
+        embeds: vec![Embed {
+            url: "https://example.com/post".into(),
+            title: Some("A post".into()),
+            description: None,
+            image_url: None,
+            site_name: Some("Example".into()),
+        }],
+        mentions: Mentions {
+            users: vec![UserId(mentioned)],
+            roles: vec![],
+            everyone: false,
+        },
+    };
+
+    let body = serde_json::to_value(MessageResponse::from(message.clone())).unwrap();
+    let decoded: models::Message = serde_json::from_value(body).expect("client model");
+
+    assert_eq!(decoded.id, message.id.0.to_string());
+    assert_eq!(decoded.channel_id, message.channel_id.0.to_string());
+    assert_eq!(decoded.revision, 2);
+    assert_eq!(decoded.sequence, 7);
+    assert!(decoded.is_pinned);
+    assert_eq!(decoded.updated_at, message.updated_at);
+    assert_eq!(decoded.embeds.len(), 1);
+    assert_eq!(decoded.embeds[0].title.as_deref(), Some("A post"));
+    assert_eq!(decoded.embeds[0].description, None);
+    assert_eq!(decoded.mentions.users, vec![mentioned]);
+    assert!(decoded.mentions.roles.is_empty());
+}
@@ -1,7 +1,7 @@
 use api::config::Environment;
 use api::http::server::middleware::auth::entities::Claims;
 use api::{
-    App, Config,
+    App, AppState, Config,
     app::AppBuilder,
     config::{DatabaseConfig, JwtConfig},
 };
@@ -82,14 +82,23 @@ impl AsyncTestContext for TestContext {
         };
 
         let repositories =
-            create_repositories(config.clone().database.into(), config.clone().routing)
+            create_repositories(
+                &config.database.mongo_uri,
+                &config.database.mongo_db_name,
+                config.routing.clone(),
+            )
                 .await
                 .expect("Failed to create repositories");
 
         let app = App::build(config)
             .await
             .expect("Failed to build app")
-            .with_state(repositories.clone().into())
+            .with_state(
+                AppState::builder(repositories.clone().into())
+                    .allow_all()
+                    .build()
+                    .expect("Failed to build state"),
+            )
             .await
             .expect("Failed to set state");
 
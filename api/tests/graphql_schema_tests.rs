@@ -0,0 +1,34 @@
+use api::http::graphql::build_schema;
+
+#[test]
+fn schema_exposes_message_queries_and_mutations() {
+    let sdl = build_schema().sdl();
+
+    for field in [
+        "message(",
+        "messages(",
+        "sendMessage(",
+        "editMessage(",
+        "deleteMessage(",
+    ] {
+        assert!(sdl.contains(field), "missing {field} in:\n{sdl}");
+    }
+    // message and channel IDs are encoded like in the REST API
+    assert!(
+        sdl.contains("scalar PublicId"),
+        "missing PublicId in:\n{sdl}"
+    );
+    assert!(
+        sdl.contains("message(id: PublicId!)"),
+        "raw message ID in:\n{sdl}"
+    );
+}
+
+#[tokio::test]
+async fn requests_without_identity_are_rejected() {
+    let response = build_schema()
+        .execute("{ message(id: \"7f9c24e8-3b12-4fef-91e1-0a6c1b5e3d2a\") { id } }")
+        .await;
+
+    assert_eq!(response.errors.len(), 1);
+}
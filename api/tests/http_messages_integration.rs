@@ -1,12 +1,11 @@
-use axum::{body::Body, http::{Request, StatusCode}, routing::{get, post, put, delete}, Router};
+use axum::{body::Body, http::{Request, StatusCode}};
 use tower::util::ServiceExt;
 use tower_http::add_extension::AddExtensionLayer;
-use communities_core::create_repositories;
+use communities_core::{application::MessageRoutingInfos, create_repositories};
 use communities_core::domain::message::ports::MessageRepository;
 use uuid::Uuid;
 use serde_json::json;
 use api as crate_api;
-use crate_api::http::messages::handlers as handlers;
 use crate_api::http::server::app_state::AppState;
 use crate_api::http::server::middleware::auth::entities::UserIdentity;
 
@@ -44,7 +43,7 @@ async fn ensure_mongo_uri() -> Option<(String, Option<String>)> {
     // wait for readiness
     // wait for mongo to accept connections by retrying create_repositories
     for _ in 0..40 {
-        if create_repositories(&uri, &db_name).await.is_ok() {
+        if create_repositories(&uri, &db_name, MessageRoutingInfos::default()).await.is_ok() {
             return Some((uri, Some(container_id)));
         }
         tokio::time::sleep(std::time::Duration::from_millis(250)).await;
@@ -66,20 +65,20 @@ async fn http_handlers_crud_flow() {
     };
 
     // create repositories
-    let repos = create_repositories(&uri, "message_test_db").await.expect("create repos");
-    let state: AppState = repos.clone().into();
+    let repos = create_repositories(&uri, "message_test_db", MessageRoutingInfos::default()).await.expect("create repos");
+    let state = AppState::builder(repos.clone().into())
+        .allow_all()
+        .build()
+        .expect("build app state");
 
     // prepare router with extension providing UserIdentity
     let user_id = Uuid::new_v4();
     let user_identity = UserIdentity { user_id };
 
-    let router = Router::new()
-        .route("/messages", post(handlers::create_message))
-        .route("/messages/{id}", get(handlers::get_message))
-        .route("/channels/{channel_id}/messages", get(handlers::list_messages))
-        .route("/messages/{id}", put(handlers::update_message))
-        .route("/messages/{id}", delete(handlers::delete_message))
+    let (router, _) = crate_api::message_routes().split_for_parts();
+    let router = router
         .with_state(state.clone())
+        .layer(axum::Extension(state.clone()))
         .layer(AddExtensionLayer::new(user_identity.clone()));
 
     // create message
@@ -121,3 +120,277 @@ async fn http_handlers_crud_flow() {
         let _ = std::process::Command::new("docker").args(["rm", "-f", &cid]).output();
     }
 }
+
+/// Denies every permission
+struct DenyAll;
+
+#[async_trait::async_trait]
+impl crate_api::http::server::authorization::Authorization for DenyAll {
+    async fn check(
+        &self,
+        _actor: Uuid,
+        _permission: crate_api::http::server::authorization::Permission,
+        _resource: crate_api::http::server::authorization::Resource,
+    ) -> Result<bool, crate_api::http::server::authorization::AuthzError> {
+        Ok(false)
+    }
+}
+
+#[tokio::test]
+async fn routes_declared_with_a_permission_enforce_it() {
+    use communities_core::domain::message::entities::{AuthorId, ChannelId, CreateMessageRequest};
+    use communities_core::domain::message::ports::MessageService;
+
+    let Some((uri, container_id_opt)) = ensure_mongo_uri().await else {
+        eprintln!("Skipping API integration test: no Mongo available and docker not present");
+        return;
+    };
+
+    let repos = create_repositories(&uri, "message_test_db", MessageRoutingInfos::default())
+        .await
+        .expect("create repos");
+    let state = AppState::builder(repos.clone().into())
+        .authz(std::sync::Arc::new(DenyAll))
+        .build()
+        .expect("build app state");
+
+    let message = state
+        .service
+        .create_message(
+            CreateMessageRequest {
+                channel_id: ChannelId::from(Uuid::new_v4()),
+                content: "guarded".to_string(),
+                reply_to_message_id: None,
+                attachments: vec![],
+                nonce: None,
+                expires_at: None,
+            }
+            .into_input(AuthorId::from(Uuid::new_v4())),
+        )
+        .await
+        .expect("create message");
+
+    let (router, _) = crate_api::message_routes().split_for_parts();
+    let router = router
+        .with_state(state.clone())
+        .layer(axum::Extension(state.clone()))
+        .layer(AddExtensionLayer::new(UserIdentity { user_id: Uuid::new_v4() }));
+
+    let get = |uri: String| Request::builder().method("GET").uri(uri).body(Body::empty()).unwrap();
+
+    let response = router.clone().oneshot(get(format!("/messages/{}", message.id.0))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let response = router.clone().oneshot(get(format!("/messages/{}/replies", message.id.0))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let response = router.clone().oneshot(get(format!("/messages/{}", Uuid::new_v4()))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    if let Some(cid) = container_id_opt {
+        let _ = std::process::Command::new("docker").args(["rm", "-f", &cid]).output();
+    }
+}
+
+#[tokio::test]
+async fn channel_listings_are_cached_until_a_write_to_the_channel() {
+    use crate_api::http::server::cache::{ListCacheKey, MessageListCache};
+    use communities_core::domain::message::entities::{AuthorId, ChannelId, CreateMessageRequest, Message};
+    use communities_core::domain::message::ports::MessageService;
+
+    let Some((uri, container_id_opt)) = ensure_mongo_uri().await else {
+        eprintln!("Skipping API integration test: no Mongo available and docker not present");
+        return;
+    };
+
+    let repos = create_repositories(&uri, "message_test_db", MessageRoutingInfos::default())
+        .await
+        .expect("create repos");
+    let state = AppState::builder(repos.clone().into())
+        .allow_all()
+        .list_cache(MessageListCache::new(std::time::Duration::from_secs(60), 100))
+        .build()
+        .expect("build app state");
+
+    let user_id = Uuid::new_v4();
+    let (router, _) = crate_api::message_routes().split_for_parts();
+    let router = router
+        .with_state(state.clone())
+        .layer(axum::Extension(state.clone()))
+        .layer(AddExtensionLayer::new(UserIdentity { user_id }));
+
+    let channel = ChannelId::from(Uuid::new_v4());
+    let key = ListCacheKey { channel_id: channel, page: 1, limit: 20 };
+    let service = &state.service;
+    let post = move |content: &'static str| async move {
+        service
+            .create_message(
+                CreateMessageRequest {
+                    channel_id: channel,
+                    content: content.to_string(),
+                    reply_to_message_id: None,
+                    attachments: vec![],
+                    nonce: None,
+                    expires_at: None,
+                }
+                .into_input(AuthorId::from(user_id)),
+            )
+            .await
+            .expect("create message")
+    };
+    let request = |method: &str, uri: String, body: Body| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(body)
+            .unwrap()
+    };
+    let list = || request("GET", format!("/channels/{}/messages", channel.0), Body::empty());
+    // a sentinel page, which only an invalidation removes
+    let stale = || state.list_cache.insert(key.clone(), (Vec::<Message>::new(), 999));
+
+    let first = post("first").await;
+    let response = router.clone().oneshot(list()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["cache-control"], "private, max-age=60");
+    assert_eq!(state.list_cache.get(&key).expect("listing cached").1, 1);
+
+    // written without going through the API: the cached page is served
+    post("unseen").await;
+    let response = router.clone().oneshot(list()).await.unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["total"], 1);
+
+    let writes = [
+        request(
+            "POST",
+            "/messages".to_string(),
+            Body::from(json!({ "channel_id": channel.0, "content": "created", "attachments": [] }).to_string()),
+        ),
+        request(
+            "PUT",
+            format!("/messages/{}", first.id.0),
+            Body::from(json!({ "content": "edited" }).to_string()),
+        ),
+        request("PUT", format!("/messages/{}/reactions/%F0%9F%91%8D", first.id.0), Body::empty()),
+        request(
+            "POST",
+            format!("/channels/{}/import", channel.0),
+            Body::from(
+                json!({
+                    "_id": Uuid::new_v4(),
+                    "author_id": user_id,
+                    "content": "imported",
+                    "created_at": chrono::Utc::now(),
+                })
+                .to_string(),
+            ),
+        ),
+        request(
+            "POST",
+            format!("/channels/{}/messages/bulk-delete", channel.0),
+            Body::from(json!({ "ids": [post("bulk deleted").await.id.0] }).to_string()),
+        ),
+        request("DELETE", format!("/messages/{}", first.id.0), Body::empty()),
+    ];
+    for write in writes {
+        let (method, uri) = (write.method().clone(), write.uri().clone());
+        stale();
+        let response = router.clone().oneshot(write).await.unwrap();
+        assert!(response.status().is_success(), "{method} {uri}: {}", response.status());
+        assert!(state.list_cache.get(&key).is_none(), "{method} {uri} kept the cached listing");
+    }
+
+    if let Some(cid) = container_id_opt {
+        let _ = std::process::Command::new("docker").args(["rm", "-f", &cid]).output();
+    }
+}
+
+#[tokio::test]
+async fn export_links_serve_encrypted_exports_without_credentials() {
+    use crate_api::http::server::export_links::ExportLinks;
+    use communities_core::domain::message::entities::{AuthorId, ChannelId, CreateMessageRequest};
+    use communities_core::domain::message::ports::MessageService;
+    use communities_core::infrastructure::message::export::{ExportSecret, decrypt_export};
+
+    let Some((uri, container_id_opt)) = ensure_mongo_uri().await else {
+        eprintln!("Skipping API integration test: no Mongo available and docker not present");
+        return;
+    };
+
+    let repos = create_repositories(&uri, "message_test_db", MessageRoutingInfos::default())
+        .await
+        .expect("create repos");
+    let state = AppState::builder(repos.clone().into())
+        .allow_all()
+        .export_links(ExportLinks::new(&[9; 32], std::time::Duration::from_secs(60)))
+        .build()
+        .expect("build app state");
+
+    let channel = ChannelId::from(Uuid::new_v4());
+    state
+        .service
+        .create_message(
+            CreateMessageRequest {
+                channel_id: channel,
+                content: "exported through a link".to_string(),
+                reply_to_message_id: None,
+                attachments: vec![],
+                nonce: None,
+                expires_at: None,
+            }
+            .into_input(AuthorId::from(Uuid::new_v4())),
+        )
+        .await
+        .expect("create message");
+
+    let (router, _) = crate_api::message_routes().split_for_parts();
+    let router = router
+        .with_state(state.clone())
+        .layer(axum::Extension(state.clone()))
+        .layer(AddExtensionLayer::new(UserIdentity { user_id: Uuid::new_v4() }));
+    // no identity at all: the link is the only credential
+    let (downloads, _) = crate_api::export_download_routes().split_for_parts();
+    let downloads = downloads.with_state(state.clone());
+
+    let create_link = |body: serde_json::Value| {
+        Request::builder()
+            .method("POST")
+            .uri(format!("/channels/{}/export/links", channel.0))
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+    let download = |url: &str| Request::builder().method("GET").uri(url).body(Body::empty()).unwrap();
+
+    let passphrase = "a long enough passphrase";
+    let response = router.clone().oneshot(create_link(json!({ "passphrase": passphrase }))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let link: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let url = link["url"].as_str().expect("link url").to_string();
+
+    let response = downloads.clone().oneshot(download(&url)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let artifact = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert!(!artifact.windows(8).any(|w| w == b"exported"));
+    let export = decrypt_export(&artifact, ExportSecret::Passphrase(passphrase)).expect("decrypt");
+    let line: serde_json::Value = serde_json::from_slice(export.trim_ascii_end()).unwrap();
+    assert_eq!(line["content"], "exported through a link");
+
+    let mut tampered = url.clone();
+    tampered.pop();
+    let response = downloads.clone().oneshot(download(&tampered)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    for body in [json!({}), json!({ "passphrase": passphrase, "public_key": "key" }), json!({ "passphrase": "short" })] {
+        let response = router.clone().oneshot(create_link(body.clone())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{body}");
+    }
+
+    if let Some(cid) = container_id_opt {
+        let _ = std::process::Command::new("docker").args(["rm", "-f", &cid]).output();
+    }
+}
@@ -0,0 +1,114 @@
+use std::sync::Arc;
+
+use api::http::server::authorization::{Authorization, AuthzError, Permission, Resource};
+use api::http::server::middleware::auth::entities::UserIdentity;
+use api::http::server::middleware::impersonation::{
+    IMPERSONATE_HEADER, Impersonation, Impersonator, impersonate,
+};
+use axum::{
+    Extension, Router,
+    body::{Body, to_bytes},
+    http::{Request, StatusCode},
+    middleware::from_fn_with_state,
+    routing::get,
+};
+use tower::ServiceExt;
+use tower_http::add_extension::AddExtensionLayer;
+use uuid::Uuid;
+
+/// Lets `admin` impersonate anyone, and nobody else anything
+struct SupportAdmin {
+    admin: Uuid,
+}
+
+#[async_trait::async_trait]
+impl Authorization for SupportAdmin {
+    async fn check(
+        &self,
+        actor: Uuid,
+        permission: Permission,
+        _resource: Resource,
+    ) -> Result<bool, AuthzError> {
+        Ok(actor == self.admin && matches!(permission, Permission::ImpersonateUsers))
+    }
+}
+
+/// Answers the user the request is served as, and the admin behind it
+fn router(enabled: bool, caller: Uuid, admin: Uuid) -> Router {
+    let impersonation = Impersonation::new(enabled, Arc::new(SupportAdmin { admin }));
+    Router::new()
+        .route(
+            "/me",
+            get(
+                |Extension(identity): Extension<UserIdentity>,
+                 impersonator: Option<Extension<Impersonator>>| async move {
+                    match impersonator {
+                        Some(Extension(impersonator)) => {
+                            format!("{} by {}", identity.user_id, impersonator.admin_id)
+                        }
+                        None => identity.user_id.to_string(),
+                    }
+                },
+            ),
+        )
+        .route_layer(from_fn_with_state(Arc::new(impersonation), impersonate))
+        .layer(AddExtensionLayer::new(UserIdentity { user_id: caller }))
+}
+
+async fn get_me(router: Router, impersonated: Option<&str>) -> (StatusCode, String) {
+    let mut request = Request::builder().uri("/me");
+    if let Some(user) = impersonated {
+        request = request.header(IMPERSONATE_HEADER, user);
+    }
+    let response = router
+        .oneshot(request.body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, String::from_utf8(body.to_vec()).unwrap())
+}
+
+#[tokio::test]
+async fn requests_without_the_header_are_served_as_the_caller() {
+    let caller = Uuid::new_v4();
+    let (status, body) = get_me(router(true, caller, Uuid::new_v4()), None).await;
+    assert_eq!((status, body), (StatusCode::OK, caller.to_string()));
+}
+
+#[tokio::test]
+async fn admins_are_served_as_the_impersonated_user() {
+    let admin = Uuid::new_v4();
+    let user = Uuid::new_v4();
+
+    let (status, body) = get_me(router(true, admin, admin), Some(&user.to_string())).await;
+    assert_eq!(
+        (status, body),
+        (StatusCode::OK, format!("{user} by {admin}"))
+    );
+}
+
+#[tokio::test]
+async fn impersonation_is_refused_without_the_permission() {
+    let user = Uuid::new_v4().to_string();
+
+    let (status, _) = get_me(router(true, Uuid::new_v4(), Uuid::new_v4()), Some(&user)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn impersonation_is_refused_where_disabled() {
+    let admin = Uuid::new_v4();
+    let user = Uuid::new_v4().to_string();
+
+    let (status, _) = get_me(router(false, admin, admin), Some(&user)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn malformed_impersonated_users_are_rejected() {
+    let admin = Uuid::new_v4();
+
+    let (status, _) = get_me(router(true, admin, admin), Some("bob")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,37 @@
+use std::sync::Arc;
+
+use api::Config;
+use api::lifecycle::{Lifecycle, SERVICE_VERSION, config_hash};
+use communities_core::domain::lifecycle::{entities::LifecycleStage, ports::InMemoryLifecyclePublisher};
+
+#[tokio::test]
+async fn stages_are_published_with_the_replica_and_its_version() {
+    let publisher = InMemoryLifecyclePublisher::new();
+    let lifecycle = Lifecycle::new("replica-1", "cafe", Some(Arc::new(publisher.clone())));
+
+    lifecycle.announce(LifecycleStage::Started).await;
+    lifecycle.announce(LifecycleStage::ShutdownInitiated).await;
+    lifecycle.announce(LifecycleStage::ShutdownCompleted).await;
+
+    let published = publisher.published();
+    let stages: Vec<LifecycleStage> = published.iter().map(|event| event.stage).collect();
+    assert_eq!(
+        stages,
+        vec![LifecycleStage::Started, LifecycleStage::ShutdownInitiated, LifecycleStage::ShutdownCompleted]
+    );
+    assert!(published.iter().all(|event| event.instance_id == "replica-1"
+        && event.version == SERVICE_VERSION
+        && event.config_hash == "cafe"));
+}
+
+#[test]
+fn replicas_configured_alike_share_their_config_hash() {
+    let mut first = Config::default();
+    first.jobs.instance_id = "replica-1".to_string();
+    let mut second = first.clone();
+    second.jobs.instance_id = "replica-2".to_string();
+    assert_eq!(config_hash(&first), config_hash(&second));
+
+    second.read_only = true;
+    assert_ne!(config_hash(&first), config_hash(&second));
+}
@@ -0,0 +1,64 @@
+use std::time::Duration;
+
+use api::http::server::cache::{ListCacheKey, MessageListCache};
+use communities_core::domain::common::services::Service;
+use communities_core::domain::health::port::MockHealthRepository;
+use communities_core::domain::message::entities::{
+    AuthorId, ChannelId, InsertMessageInput, MessageId, UpdateMessageInput,
+};
+use communities_core::domain::message::ports::{MessageService, MockMessageRepository};
+use uuid::Uuid;
+
+fn first_page(channel_id: ChannelId) -> ListCacheKey {
+    ListCacheKey {
+        channel_id,
+        page: 1,
+        limit: 20,
+    }
+}
+
+#[tokio::test]
+async fn priming_loads_the_busiest_channels_only() {
+    let service = Service::new(MockMessageRepository::new(), MockHealthRepository::new());
+    let busy = ChannelId::from(Uuid::new_v4());
+    let quiet = ChannelId::from(Uuid::new_v4());
+
+    let post = |channel_id| InsertMessageInput {
+        id: MessageId::from(Uuid::new_v4()),
+        channel_id,
+        author_id: AuthorId::from(Uuid::new_v4()),
+        content: "hello".into(),
+        reply_to_message_id: None,
+        attachments: vec![],
+        nonce: None,
+        expires_at: None,
+    };
+    let pinned = service.create_message(post(busy)).await.unwrap();
+    service.create_message(post(busy)).await.unwrap();
+    service.create_message(post(quiet)).await.unwrap();
+    service
+        .update_message(UpdateMessageInput {
+            id: pinned.id,
+            content: None,
+            is_pinned: Some(true),
+            expected_revision: None,
+        })
+        .await
+        .unwrap();
+
+    let cache = MessageListCache::new(Duration::from_secs(60), 100);
+    let since = chrono::Utc::now() - chrono::Duration::minutes(5);
+    assert_eq!(cache.prime(&service, since, 1).await.unwrap(), 1);
+
+    let (messages, total) = cache.get(&first_page(busy)).expect("busy channel primed");
+    assert_eq!(total, 2);
+    assert_eq!(messages.len(), 2);
+    let (pins, _) = cache.get_pinned(&first_page(busy)).expect("pins primed");
+    assert_eq!(pins[0].id, pinned.id);
+    assert!(cache.get(&first_page(quiet)).is_none());
+
+    // pins are dropped with the rest of the channel
+    cache.invalidate_channel(busy);
+    assert!(cache.get(&first_page(busy)).is_none());
+    assert!(cache.get_pinned(&first_page(busy)).is_none());
+}
@@ -0,0 +1,70 @@
+use api::http::messages::dto::MessageResponse;
+use api::http::ws::handlers::RealtimeEvent;
+use chrono::Utc;
+use communities_core::domain::message::entities::{
+    Attachment, AttachmentId, AuthorId, ChannelId, Mentions, Message, MessageId, ReactionCount,
+};
+use communities_core::domain::message::events::MessageEvent;
+use uuid::Uuid;
+
+fn sample_message() -> Message {
+    Message {
+        id: MessageId::from(Uuid::new_v4()),
+        channel_id: ChannelId::from(Uuid::new_v4()),
+        author_id: AuthorId::from(Uuid::new_v4()),
+        content: "shape".into(),
+        reply_to_message_id: None,
+        attachments: vec![Attachment { id: AttachmentId::from(Uuid::new_v4()), name: "f".into(), url: "u".into(), size: 0, preview: None }],
+        is_pinned: false,
+        reactions: vec![],
+        revision: 0,
+        sequence: 0,
+        reply_count: 0,
+        last_reply_at: None,
+        archived_at: None,
+        created_at: Utc::now(),
+        updated_at: None,
+        deleted_at: None,
+        nonce: None,
+        embeds: Vec::new(),
+        mentions: Mentions::default(),
+        expires_at: None,
+    }
+}
+
+#[test]
+fn message_response_matches_original_json_shape() {
+    let message = sample_message();
+
+    let response = serde_json::to_value(MessageResponse::from(message.clone())).unwrap();
+
+    assert_eq!(response["_id"], serde_json::json!(message.id.0));
+    assert_eq!(response["channel_id"], serde_json::json!(message.channel_id.0));
+    assert_eq!(response["attachments"][0]["id"], serde_json::json!(message.attachments[0].id.0));
+    assert_eq!(response["created_at"], serde_json::json!(message.created_at));
+    assert!(response["updated_at"].is_null());
+}
+
+#[test]
+fn realtime_frames_carry_the_message_as_rest_returns_it() {
+    let mut message = sample_message();
+    message.content = "edited".into();
+    message.is_pinned = true;
+    message.revision = 2;
+    message.reactions = vec![ReactionCount { emoji: "👍".into(), count: 3 }];
+    message.updated_at = Some(Utc::now());
+    let rest = serde_json::to_value(MessageResponse::from(message.clone())).unwrap();
+
+    let frame = serde_json::to_value(RealtimeEvent::from(MessageEvent::Updated(message.clone()))).unwrap();
+    assert_eq!(frame["type"], "message_updated");
+    assert_eq!(frame["message"], rest);
+
+    let frame = serde_json::to_value(RealtimeEvent::from(MessageEvent::Deleted {
+        id: message.id,
+        channel_id: message.channel_id,
+    }))
+    .unwrap();
+    assert_eq!(frame["type"], "message_deleted");
+    assert_eq!(frame["id"], rest["_id"]);
+    assert_eq!(frame["channel_id"], rest["channel_id"]);
+}
@@ -0,0 +1,131 @@
+use api::app::openapi;
+use api::openapi_diff::{BreakingChange, breaking_changes};
+use serde_json::{Value, json};
+
+fn spec() -> Value {
+    json!({
+        "openapi": "3.1.0",
+        "paths": {
+            "/messages/{id}": {
+                "get": {
+                    "parameters": [
+                        { "name": "id", "in": "path", "required": true, "schema": { "type": "string" } },
+                        { "name": "fields", "in": "query", "required": false, "schema": { "type": "string" } }
+                    ],
+                    "responses": { "200": {}, "404": {} }
+                },
+                "delete": { "responses": { "204": {} } }
+            }
+        },
+        "components": {
+            "schemas": {
+                "Message": {
+                    "type": "object",
+                    "required": ["id"],
+                    "properties": {
+                        "id": { "type": "string" },
+                        "sequence": { "type": "integer", "format": "int64" },
+                        "tags": { "type": "array", "items": { "type": "string" } }
+                    }
+                },
+                "Reaction": { "type": "object" }
+            }
+        }
+    })
+}
+
+#[test]
+fn the_generated_spec_does_not_break_itself() {
+    let spec = serde_json::to_value(openapi()).unwrap();
+    assert!(breaking_changes(&spec, &spec).is_empty());
+}
+
+#[test]
+fn additions_are_not_breaking() {
+    let mut current = spec();
+    current["paths"]["/messages"] = json!({ "post": { "responses": { "201": {} } } });
+    current["paths"]["/messages/{id}"]["get"]["parameters"]
+        .as_array_mut()
+        .unwrap()
+        .push(json!({ "name": "expand", "in": "query", "required": false, "schema": { "type": "string" } }));
+    current["paths"]["/messages/{id}"]["get"]["responses"]["304"] = json!({});
+    current["components"]["schemas"]["Message"]["properties"]["pinned"] =
+        json!({ "type": "boolean" });
+    current["components"]["schemas"]["Bookmark"] = json!({ "type": "object" });
+
+    assert_eq!(breaking_changes(&spec(), &current), vec![]);
+}
+
+#[test]
+fn removals_and_type_changes_are_breaking() {
+    let mut current = spec();
+    let item = current["paths"]["/messages/{id}"].as_object_mut().unwrap();
+    item.remove("delete");
+    item["get"]["responses"]
+        .as_object_mut()
+        .unwrap()
+        .remove("404");
+    item["get"]["parameters"][1]["required"] = json!(true);
+    let schemas = current["components"]["schemas"].as_object_mut().unwrap();
+    schemas.remove("Reaction");
+    let message = &mut schemas["Message"];
+    message["properties"]["sequence"] = json!({ "type": "string" });
+    message["properties"]["tags"]["items"] = json!({ "type": "integer" });
+    message["properties"].as_object_mut().unwrap().remove("id");
+    message["required"] = json!(["id", "sequence"]);
+
+    let changes = breaking_changes(&spec(), &current);
+    let expected = [
+        BreakingChange::NewRequiredParameter {
+            operation: "GET /messages/{id}".into(),
+            name: "fields".into(),
+        },
+        BreakingChange::RemovedResponse {
+            operation: "GET /messages/{id}".into(),
+            status: "404".into(),
+        },
+        BreakingChange::RemovedOperation {
+            operation: "DELETE /messages/{id}".into(),
+        },
+        BreakingChange::RemovedProperty {
+            schema: "Message".into(),
+            property: "id".into(),
+        },
+        BreakingChange::ChangedPropertyType {
+            schema: "Message".into(),
+            property: "sequence".into(),
+            from: "integer (int64)".into(),
+            to: "string".into(),
+        },
+        BreakingChange::ChangedPropertyType {
+            schema: "Message".into(),
+            property: "tags".into(),
+            from: "array of string".into(),
+            to: "array of integer".into(),
+        },
+        BreakingChange::NewRequiredProperty {
+            schema: "Message".into(),
+            property: "sequence".into(),
+        },
+        BreakingChange::RemovedSchema {
+            schema: "Reaction".into(),
+        },
+    ];
+    assert_eq!(changes.len(), expected.len(), "{changes:#?}");
+    for change in expected {
+        assert!(changes.contains(&change), "missing {change}");
+    }
+}
+
+#[test]
+fn removed_paths_are_reported_once() {
+    let mut current = spec();
+    current["paths"] = json!({});
+
+    assert_eq!(
+        breaking_changes(&spec(), &current),
+        vec![BreakingChange::RemovedPath {
+            path: "/messages/{id}".into()
+        }]
+    );
+}
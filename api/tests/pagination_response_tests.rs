@@ -0,0 +1,27 @@
+use api::http::server::response::PaginatedResponse;
+
+#[test]
+fn pagination_metadata_is_derived_from_the_total() {
+    let first = PaginatedResponse::new(vec![1, 2], 5, 1, 2);
+    assert_eq!(first.limit, 2);
+    assert_eq!(first.total_pages, 3);
+    assert!(first.has_next);
+
+    let last = PaginatedResponse::new(vec![5], 5, 3, 2);
+    assert_eq!(last.total_pages, 3);
+    assert!(!last.has_next);
+
+    let beyond = PaginatedResponse::<u32>::new(vec![], 5, 4, 2);
+    assert!(!beyond.has_next);
+}
+
+#[test]
+fn empty_and_unlimited_listings_have_consistent_metadata() {
+    let empty = PaginatedResponse::<u32>::new(vec![], 0, 1, 20);
+    assert_eq!(empty.total_pages, 0);
+    assert!(!empty.has_next);
+
+    let unlimited = PaginatedResponse::new(vec![1, 2, 3], 3, 1, 0);
+    assert_eq!(unlimited.total_pages, 1);
+    assert!(!unlimited.has_next);
+}
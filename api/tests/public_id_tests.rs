@@ -0,0 +1,68 @@
+use std::sync::Arc;
+
+use api::http::server::public_id::{
+    EncryptedIds, IdObfuscation, PlainIds, PublicId, scope_public_ids, sync_scope_public_ids,
+};
+use uuid::Uuid;
+
+#[test]
+fn encrypted_ids_round_trip_without_exposing_the_uuid() {
+    let ids = EncryptedIds::new(&[7; 16]);
+    let id = Uuid::new_v4();
+
+    let public = ids.encode(id);
+    assert_eq!(public.len(), 32);
+    assert!(!public.contains(&id.simple().to_string()));
+    // stable, so URLs built from it keep working
+    assert_eq!(ids.encode(id), public);
+    assert_eq!(ids.decode(&public), Some(id));
+
+    // another key cannot read it back
+    assert_ne!(EncryptedIds::new(&[8; 16]).decode(&public), Some(id));
+}
+
+#[test]
+fn encrypted_ids_reject_raw_uuids_and_garbage() {
+    let ids = EncryptedIds::new(&[7; 16]);
+
+    assert_eq!(ids.decode(&Uuid::new_v4().to_string()), None);
+    assert_eq!(ids.decode("not an id"), None);
+    assert_eq!(ids.decode(&"zz".repeat(16)), None);
+    assert_eq!(ids.decode(&"é".repeat(16)), None);
+}
+
+#[test]
+fn public_ids_are_plain_uuids_by_default() {
+    let id = Uuid::new_v4();
+    assert_eq!(PlainIds.decode(&PlainIds.encode(id)), Some(id));
+
+    let json = serde_json::to_value(PublicId(id)).unwrap();
+    assert_eq!(json, serde_json::json!(id));
+    assert_eq!(
+        serde_json::from_value::<PublicId>(json).unwrap(),
+        PublicId(id)
+    );
+    assert!(serde_json::from_value::<PublicId>(serde_json::json!("nope")).is_err());
+}
+
+#[tokio::test]
+async fn public_ids_use_the_obfuscation_of_their_scope() {
+    let ids: Arc<dyn IdObfuscation> = Arc::new(EncryptedIds::new(&[7; 16]));
+    let id = Uuid::new_v4();
+    let public = ids.encode(id);
+
+    let (encoded, decoded) = scope_public_ids(ids.clone(), async {
+        (PublicId(id).to_string(), PublicId::parse(&public))
+    })
+    .await;
+    assert_eq!(encoded, public);
+    assert_eq!(decoded, Some(PublicId(id)));
+    assert_eq!(
+        sync_scope_public_ids(&ids, || serde_json::to_value(PublicId(id)).unwrap()),
+        serde_json::json!(public)
+    );
+
+    // nothing leaks out of the scope
+    assert_eq!(PublicId(id).to_string(), id.to_string());
+    assert_ne!(PublicId::parse(&public), Some(PublicId(id)));
+}
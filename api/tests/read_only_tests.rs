@@ -0,0 +1,73 @@
+use api::http::server::middleware::read_only::{is_mutation, reject_writes};
+use axum::{
+    Router,
+    body::{Body, to_bytes},
+    http::{Method, Request, StatusCode},
+    middleware::from_fn,
+    routing::{get, post},
+};
+use tower::ServiceExt;
+
+#[test]
+fn only_reads_are_allowed() {
+    assert!(!is_mutation(&Method::GET, "/messages/{id}"));
+    assert!(!is_mutation(&Method::HEAD, "/messages/{id}"));
+    assert!(!is_mutation(&Method::POST, "/messages/batch-get"));
+    assert!(!is_mutation(&Method::POST, "/channels/last-messages"));
+    assert!(!is_mutation(
+        &Method::POST,
+        "/channels/{channel_id}/messages/similar"
+    ));
+    assert!(!is_mutation(&Method::POST, "/graphql"));
+
+    assert!(is_mutation(&Method::POST, "/messages"));
+    assert!(is_mutation(&Method::PUT, "/messages/{id}"));
+    assert!(is_mutation(
+        &Method::PATCH,
+        "/channels/{channel_id}/settings"
+    ));
+    assert!(is_mutation(&Method::DELETE, "/messages/{id}"));
+}
+
+#[tokio::test]
+async fn mutations_are_answered_with_a_read_only_error() {
+    let router = Router::new()
+        .route("/messages", post(|| async { "created" }))
+        .route("/messages/{id}", get(|| async { "message" }))
+        .route(
+            "/channels/{channel_id}/messages/similar",
+            post(|| async { "similar" }),
+        )
+        .route_layer(from_fn(reject_writes));
+    let request = |method: &str, uri: &str| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let response = router
+        .clone()
+        .oneshot(request("POST", "/messages"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["error_code"], "READ_ONLY");
+
+    let response = router
+        .clone()
+        .oneshot(request("GET", "/messages/42"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // matched on the route, whatever the channel
+    let response = router
+        .oneshot(request("POST", "/channels/42/messages/similar"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
@@ -0,0 +1,54 @@
+use api::http::server::Response;
+use api::http::server::response::ETag;
+use axum::http::{HeaderMap, HeaderValue, StatusCode, header};
+use axum::response::IntoResponse;
+
+fn if_none_match(value: &'static str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(header::IF_NONE_MATCH, HeaderValue::from_static(value));
+    headers
+}
+
+#[test]
+fn immutable_responses_carry_strong_etag_and_cache_control() {
+    let etag = ETag::strong("attachment-1");
+    let response = Response::ok("payload").immutable(&etag).into_response();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::ETAG], "\"attachment-1\"");
+    assert_eq!(
+        response.headers()[header::CACHE_CONTROL],
+        "private, max-age=31536000, immutable"
+    );
+}
+
+#[test]
+fn matching_if_none_match_yields_not_modified() {
+    let etag = ETag::strong("message-1-r3");
+
+    let response = Response::ok("payload")
+        .immutable(&etag)
+        .revalidate(&if_none_match("\"other\", W/\"message-1-r3\""))
+        .into_response();
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(response.headers()[header::ETAG], "\"message-1-r3\"");
+
+    let response = Response::ok("payload")
+        .with_etag(&etag)
+        .revalidate(&if_none_match("\"message-1-r2\""))
+        .into_response();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // without an ETag there is nothing to revalidate against
+    let response = Response::ok("payload").revalidate(&if_none_match("*")).into_response();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[test]
+fn cached_listings_advertise_their_time_to_live() {
+    let response = Response::ok("page")
+        .with_cache_control(std::time::Duration::from_secs(5))
+        .into_response();
+
+    assert_eq!(response.headers()[header::CACHE_CONTROL], "private, max-age=5");
+}
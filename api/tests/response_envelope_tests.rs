@@ -0,0 +1,94 @@
+use api::http::server::Response;
+use api::http::server::response::{
+    ApiVersion, CursorPaginatedResponse, PaginatedResponse, ResponseFormat,
+};
+use axum::body::to_bytes;
+use axum::http::{HeaderMap, HeaderValue};
+use axum::response::IntoResponse;
+use serde_json::{Value, json};
+
+async fn body<T: serde::Serialize>(response: Response<T>) -> Value {
+    let body = to_bytes(response.into_response().into_body(), usize::MAX)
+        .await
+        .unwrap();
+    serde_json::from_slice(&body).expect("valid json")
+}
+
+#[test]
+fn version_two_is_opt_in() {
+    assert_eq!(ApiVersion::from_headers(&HeaderMap::new()), ApiVersion::V1);
+
+    let mut headers = HeaderMap::new();
+    headers.insert("api-version", HeaderValue::from_static("2"));
+    assert_eq!(ApiVersion::from_headers(&headers), ApiVersion::V2);
+
+    headers.insert("api-version", HeaderValue::from_static("3"));
+    assert_eq!(ApiVersion::from_headers(&headers), ApiVersion::V1);
+}
+
+#[tokio::test]
+async fn version_one_bodies_keep_their_shape() {
+    assert_eq!(body(Response::ok(json!({"id": 1}))).await, json!({"id": 1}));
+
+    let page = body(Response::page(PaginatedResponse::new(vec![1, 2], 5, 1, 2))).await;
+    assert_eq!(
+        page,
+        json!({
+            "data": [1, 2],
+            "total": 5,
+            "page": 1,
+            "limit": 2,
+            "total_pages": 3,
+            "has_next": true
+        })
+    );
+
+    let cursor = body(Response::cursor_page(CursorPaginatedResponse {
+        data: vec![1],
+        next_cursor: None,
+    }))
+    .await;
+    assert_eq!(cursor, json!({"data": [1], "next_cursor": null}));
+}
+
+#[tokio::test]
+async fn version_two_bodies_are_enveloped() {
+    let resource = ApiVersion::V2
+        .scope(body(Response::ok(json!({"id": 1}))))
+        .await;
+    assert_eq!(resource, json!({"data": {"id": 1}, "meta": {}}));
+
+    let page = ApiVersion::V2
+        .scope(body(Response::page(PaginatedResponse::new(
+            vec![1, 2],
+            5,
+            1,
+            2,
+        ))))
+        .await;
+    assert_eq!(
+        page,
+        json!({
+            "data": [1, 2],
+            "meta": {"total": 5, "page": 1, "limit": 2, "total_pages": 3, "has_next": true}
+        })
+    );
+
+    let cursor = ApiVersion::V2
+        .scope(body(Response::cursor_page(CursorPaginatedResponse {
+            data: vec![1],
+            next_cursor: Some("abc".to_string()),
+        })))
+        .await;
+    assert_eq!(cursor, json!({"data": [1], "meta": {"next_cursor": "abc"}}));
+}
+
+#[tokio::test]
+async fn envelopes_apply_to_messagepack_too() {
+    let response = ResponseFormat::MessagePack
+        .scope(ApiVersion::V2.scope(async { Response::ok(7_u32).into_response() }))
+        .await;
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let decoded: Value = rmp_serde::from_slice(&body).expect("valid msgpack");
+    assert_eq!(decoded, json!({"data": 7, "meta": {}}));
+}
@@ -0,0 +1,77 @@
+use api::http::server::Response;
+use api::http::server::response::{MSGPACK_CONTENT_TYPE, ResponseFormat};
+use axum::body::to_bytes;
+use axum::http::{HeaderMap, HeaderValue, header};
+use axum::response::IntoResponse;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Payload {
+    id: u32,
+    content: String,
+}
+
+fn accept(value: &'static str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(header::ACCEPT, HeaderValue::from_static(value));
+    headers
+}
+
+#[test]
+fn json_is_the_default_format() {
+    assert_eq!(
+        ResponseFormat::from_accept(&HeaderMap::new()),
+        ResponseFormat::Json
+    );
+    assert_eq!(
+        ResponseFormat::from_accept(&accept("*/*")),
+        ResponseFormat::Json
+    );
+    assert_eq!(
+        ResponseFormat::from_accept(&accept("text/html")),
+        ResponseFormat::Json
+    );
+    assert_eq!(
+        ResponseFormat::from_accept(&accept("application/msgpack;q=0")),
+        ResponseFormat::Json
+    );
+}
+
+#[test]
+fn msgpack_is_picked_when_preferred() {
+    assert_eq!(
+        ResponseFormat::from_accept(&accept("application/msgpack")),
+        ResponseFormat::MessagePack
+    );
+    assert_eq!(
+        ResponseFormat::from_accept(&accept("application/json;q=0.5, application/x-msgpack")),
+        ResponseFormat::MessagePack
+    );
+    assert_eq!(
+        ResponseFormat::from_accept(&accept("application/json, application/msgpack")),
+        ResponseFormat::Json
+    );
+}
+
+#[tokio::test]
+async fn responses_are_serialized_in_the_negotiated_format() {
+    let payload = || Payload {
+        id: 7,
+        content: "hello".into(),
+    };
+
+    let response = ResponseFormat::MessagePack
+        .scope(async { Response::ok(payload()).into_response() })
+        .await;
+    assert_eq!(
+        response.headers()[header::CONTENT_TYPE],
+        MSGPACK_CONTENT_TYPE
+    );
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let decoded: Payload = rmp_serde::from_slice(&body).expect("valid msgpack");
+    assert_eq!(decoded, payload());
+
+    // outside of the negotiation middleware
+    let response = Response::ok(payload()).into_response();
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+}
@@ -0,0 +1,78 @@
+use api::http::server::channel_access::{AuthorizedChannel, PERMISSION_EXTENSION};
+use axum::{
+    Router,
+    body::Body,
+    http::{Request, StatusCode},
+    routing::get,
+};
+use tower::ServiceExt;
+
+/// Every documented route must declare who may call it, through
+/// `route_with_permission` or `route_authorized_by_handler`
+#[test]
+fn every_route_declares_a_permission() {
+    let api = api::app::openapi();
+
+    let mut undeclared = Vec::new();
+    for (path, item) in &api.paths.paths {
+        let operations = [
+            ("GET", &item.get),
+            ("PUT", &item.put),
+            ("POST", &item.post),
+            ("DELETE", &item.delete),
+            ("OPTIONS", &item.options),
+            ("HEAD", &item.head),
+            ("PATCH", &item.patch),
+            ("TRACE", &item.trace),
+        ];
+        for (method, operation) in operations {
+            let Some(operation) = operation else {
+                continue;
+            };
+            let declared = operation
+                .extensions
+                .as_ref()
+                .is_some_and(|extensions| extensions.contains_key(PERMISSION_EXTENSION));
+            if !declared {
+                undeclared.push(format!("{method} {path}"));
+            }
+        }
+    }
+
+    assert!(
+        undeclared.is_empty(),
+        "routes without a declared permission: {undeclared:?}"
+    );
+}
+
+#[test]
+fn channel_routes_name_their_permission() {
+    let api = api::app::openapi();
+
+    let lock = api.paths.paths["/channels/{channel_id}/write-lock"]
+        .put
+        .as_ref()
+        .expect("lock route");
+    let rule = &lock.extensions.as_ref().expect("extensions")[PERMISSION_EXTENSION];
+    assert_eq!(rule, "ManageChannels on the channel");
+}
+
+/// A handler taking the authorized channel never runs on a route that checked none
+#[tokio::test]
+async fn authorized_channel_requires_a_permission_layer() {
+    let router = Router::new().route(
+        "/channels/{channel_id}/unguarded",
+        get(|AuthorizedChannel(channel_id): AuthorizedChannel| async move { channel_id.to_string() }),
+    );
+
+    let response = router
+        .oneshot(
+            Request::builder()
+                .uri(format!("/channels/{}/unguarded", uuid::Uuid::new_v4()))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+}
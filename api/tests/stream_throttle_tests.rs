@@ -0,0 +1,35 @@
+use std::time::{Duration, Instant};
+
+use api::http::server::throttle::StreamThrottle;
+use futures::{StreamExt, stream};
+
+fn chunks(count: usize, size: usize) -> Vec<Result<Vec<u8>, std::io::Error>> {
+    (0..count).map(|_| Ok(vec![b'x'; size])).collect()
+}
+
+#[tokio::test]
+async fn throttled_streams_stay_under_the_rate_after_a_first_second_burst() {
+    let throttle = StreamThrottle::new(10_000);
+    let started = Instant::now();
+
+    // 20 kB at 10 kB/s: the first 10 kB go at once, the rest takes a second
+    let sent: Vec<_> = throttle.throttle(stream::iter(chunks(4, 5_000))).collect().await;
+
+    assert_eq!(sent.len(), 4);
+    assert!(sent.iter().all(|chunk| chunk.as_ref().is_ok_and(|bytes| bytes.len() == 5_000)));
+    let elapsed = started.elapsed();
+    assert!(elapsed >= Duration::from_millis(900), "sent too fast: {elapsed:?}");
+    assert!(elapsed < Duration::from_secs(3), "sent too slow: {elapsed:?}");
+}
+
+#[tokio::test]
+async fn unlimited_streams_are_not_delayed() {
+    let throttle = StreamThrottle::new(0);
+    assert_eq!(throttle.bytes_per_sec(), None);
+    let started = Instant::now();
+
+    let sent: Vec<_> = throttle.throttle(stream::iter(chunks(100, 100_000))).collect().await;
+
+    assert_eq!(sent.len(), 100);
+    assert!(started.elapsed() < Duration::from_millis(500));
+}
@@ -0,0 +1,80 @@
+use api::http::server::ValidatedQuery;
+use axum::{
+    Router,
+    body::{Body, to_bytes},
+    http::{Request, StatusCode},
+    routing::get,
+};
+use communities_core::domain::common::{GetCursorPaginated, GetPaginated};
+use serde_json::Value;
+use tower::ServiceExt;
+
+fn router() -> Router {
+    Router::new()
+        .route(
+            "/pages",
+            get(|ValidatedQuery(pagination): ValidatedQuery<GetPaginated>| async move {
+                format!("{} {}", pagination.page, pagination.limit)
+            }),
+        )
+        .route(
+            "/cursor",
+            get(|ValidatedQuery(pagination): ValidatedQuery<GetCursorPaginated>| async move {
+                pagination.limit.to_string()
+            }),
+        )
+}
+
+async fn get_uri(uri: &str) -> (StatusCode, Vec<u8>) {
+    let response = router()
+        .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    (status, to_bytes(response.into_body(), usize::MAX).await.unwrap().to_vec())
+}
+
+async fn invalid_fields(uri: &str) -> Vec<(String, String)> {
+    let (status, body) = get_uri(uri).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{uri}");
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["error_code"], "INVALID_QUERY");
+    body["fields"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|field| {
+            (field["field"].as_str().unwrap().to_string(), field["message"].as_str().unwrap().to_string())
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn missing_parameters_take_their_defaults() {
+    assert_eq!(get_uri("/pages").await, (StatusCode::OK, b"1 20".to_vec()));
+    assert_eq!(get_uri("/pages?page=3").await, (StatusCode::OK, b"3 20".to_vec()));
+    assert_eq!(get_uri("/cursor").await, (StatusCode::OK, b"20".to_vec()));
+}
+
+#[tokio::test]
+async fn every_parameter_out_of_bounds_is_reported() {
+    let fields = invalid_fields("/pages?page=0&limit=51").await;
+    assert_eq!(
+        fields,
+        vec![
+            ("page".to_string(), "must be between 1 and 85899345".to_string()),
+            ("limit".to_string(), "must be between 1 and 50".to_string()),
+        ]
+    );
+
+    let fields = invalid_fields("/cursor?cursor=nope&limit=0").await;
+    let names: Vec<&str> = fields.iter().map(|(field, _)| field.as_str()).collect();
+    assert_eq!(names, vec!["cursor", "limit"]);
+}
+
+#[tokio::test]
+async fn malformed_parameters_are_reported_by_field() {
+    let fields = invalid_fields("/pages?limit=ten").await;
+    assert_eq!(fields.len(), 1);
+    assert_eq!(fields[0].0, "limit");
+}
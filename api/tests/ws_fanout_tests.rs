@@ -0,0 +1,44 @@
+use api::http::ws::fanout::{
+    Delivery, FanoutConfig, SendBuffer, SlowConsumerPolicy, dropped_realtime_events,
+    slow_consumer_disconnects,
+};
+use axum::extract::ws::Message as WsMessage;
+
+fn frame() -> WsMessage {
+    WsMessage::Text("{}".into())
+}
+
+#[tokio::test]
+async fn full_buffers_drop_frames() {
+    let (buffer, mut frames) = SendBuffer::new(FanoutConfig {
+        send_buffer: 2,
+        policy: SlowConsumerPolicy::Drop,
+    });
+    let dropped = dropped_realtime_events();
+
+    assert_eq!(buffer.push(frame()), Delivery::Queued);
+    assert_eq!(buffer.push(frame()), Delivery::Queued);
+    assert_eq!(buffer.push(frame()), Delivery::Dropped);
+    assert!(dropped_realtime_events() > dropped);
+
+    // draining the buffer makes room again
+    frames.recv().await.expect("queued frame");
+    assert_eq!(buffer.push(frame()), Delivery::Queued);
+}
+
+#[tokio::test]
+async fn full_buffers_can_disconnect_slow_consumers() {
+    let (buffer, frames) = SendBuffer::new(FanoutConfig {
+        send_buffer: 1,
+        policy: SlowConsumerPolicy::Disconnect,
+    });
+    let disconnects = slow_consumer_disconnects();
+
+    assert_eq!(buffer.push(frame()), Delivery::Queued);
+    assert_eq!(buffer.push(frame()), Delivery::Disconnect);
+    assert!(slow_consumer_disconnects() > disconnects);
+
+    // once the writer is gone, nothing is counted as dropped
+    drop(frames);
+    assert_eq!(buffer.push(frame()), Delivery::Closed);
+}
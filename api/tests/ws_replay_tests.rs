@@ -0,0 +1,94 @@
+use std::time::Duration;
+
+use api::http::ws::replay::{RealtimeHub, Replay, ReplayConfig};
+use communities_core::domain::message::{
+    entities::{ChannelId, MessageId},
+    events::{MessageEvent, MessageEventBus},
+};
+use uuid::Uuid;
+
+fn hub(capacity: usize) -> RealtimeHub {
+    RealtimeHub::new(
+        MessageEventBus::new(),
+        ReplayConfig {
+            capacity,
+            ttl: Duration::from_secs(60),
+        },
+    )
+}
+
+fn deleted(channel_id: ChannelId) -> MessageEvent {
+    MessageEvent::Deleted {
+        id: MessageId(Uuid::new_v4()),
+        channel_id,
+    }
+}
+
+/// `resume_token` carried by a frame
+fn token(frame: &str) -> String {
+    let frame: serde_json::Value = serde_json::from_str(frame).expect("JSON frame");
+    frame["resume_token"]
+        .as_str()
+        .expect("resume token")
+        .to_string()
+}
+
+fn replayed(replay: Replay) -> Vec<u64> {
+    match replay {
+        Replay::Frames(frames) => frames.into_iter().map(|frame| frame.seq).collect(),
+        Replay::ResyncRequired => panic!("expected a replay"),
+    }
+}
+
+#[test]
+fn reconnecting_clients_receive_the_events_they_missed() {
+    let hub = hub(10);
+    let channel = ChannelId(Uuid::new_v4());
+    let other = ChannelId(Uuid::new_v4());
+
+    let seen = hub.record(deleted(channel)).expect("recorded");
+    let missed = hub.record(deleted(channel)).expect("recorded");
+    hub.record(deleted(other)).expect("recorded");
+    let last = hub.record(deleted(channel)).expect("recorded");
+
+    let resumed = replayed(hub.replay(channel, &token(&seen.frame)));
+    assert_eq!(resumed, vec![missed.seq, last.seq]);
+
+    // nothing to replay for a client that saw everything
+    assert!(replayed(hub.replay(channel, &token(&last.frame))).is_empty());
+}
+
+#[test]
+fn tokens_older_than_the_buffer_require_a_resync() {
+    let hub = hub(2);
+    let channel = ChannelId(Uuid::new_v4());
+
+    let seen = hub.record(deleted(channel)).expect("recorded");
+    for _ in 0..3 {
+        hub.record(deleted(channel));
+    }
+
+    assert!(matches!(
+        hub.replay(channel, &token(&seen.frame)),
+        Replay::ResyncRequired
+    ));
+}
+
+#[test]
+fn tokens_of_another_process_require_a_resync() {
+    let channel = ChannelId(Uuid::new_v4());
+    let previous = hub(10);
+    let seen = previous.record(deleted(channel)).expect("recorded");
+
+    let restarted = hub(10);
+    restarted.record(deleted(channel));
+
+    assert!(matches!(
+        restarted.replay(channel, &token(&seen.frame)),
+        Replay::ResyncRequired
+    ));
+    assert!(matches!(
+        restarted.replay(channel, "not a token"),
+        Replay::ResyncRequired
+    ));
+}
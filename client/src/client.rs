@@ -0,0 +1,261 @@
+use reqwest::{Method, RequestBuilder};
+use serde::{
+    Deserialize,
+    de::{DeserializeOwned, IgnoredAny},
+};
+use url::Url;
+
+use crate::{
+    error::{ClientError, ErrorCode},
+    models::{
+        CursorPage, Message, MessageRevision, MessageUpdate, NewAttachment, NewMessage, Page,
+        PresignedAttachment, Reaction,
+    },
+};
+
+/// Client of a deployment of the REST API, acting for the owner of an access token.
+///
+/// Requests ask for the `api-version: 2` envelopes, whose `meta` carries the
+/// pagination of listings.
+#[derive(Clone)]
+pub struct ApiClient {
+    http: reqwest::Client,
+    base_url: Url,
+    access_token: String,
+}
+
+/// Body of `api-version: 2` responses
+#[derive(Deserialize)]
+struct Envelope<T, M> {
+    data: T,
+    meta: M,
+}
+
+#[derive(Deserialize)]
+struct PageMeta {
+    total: u64,
+    page: u32,
+    limit: u32,
+    total_pages: u64,
+    has_next: bool,
+}
+
+#[derive(Deserialize)]
+struct CursorMeta {
+    next_cursor: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ErrorBody {
+    message: String,
+    error_code: Option<String>,
+}
+
+impl ApiClient {
+    /// Call the API at `base_url` (e.g. `http://communities:8080`) with `access_token`
+    pub fn new(base_url: &str, access_token: impl Into<String>) -> Result<Self, ClientError> {
+        let base_url = Url::parse(base_url)?;
+        if base_url.cannot_be_a_base() {
+            return Err(url::ParseError::RelativeUrlWithCannotBeABaseBase.into());
+        }
+
+        Ok(Self {
+            http: reqwest::Client::new(),
+            base_url,
+            access_token: access_token.into(),
+        })
+    }
+
+    /// Send requests through `http`, e.g. to share its connection pool or set timeouts
+    pub fn with_http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    pub async fn create_message(&self, message: &NewMessage) -> Result<Message, ClientError> {
+        let request = self.request(Method::POST, &["messages"]).json(message);
+        Ok(self.send::<_, IgnoredAny>(request).await?.data)
+    }
+
+    /// Register an attachment; its file is uploaded to the returned URL,
+    /// not through the API
+    pub async fn presign_attachment(
+        &self,
+        attachment: &NewAttachment,
+    ) -> Result<PresignedAttachment, ClientError> {
+        let request = self
+            .request(Method::POST, &["attachments", "presign"])
+            .json(attachment);
+        Ok(self.send::<_, IgnoredAny>(request).await?.data)
+    }
+
+    pub async fn get_message(&self, id: &str) -> Result<Message, ClientError> {
+        let request = self.request(Method::GET, &["messages", id]);
+        Ok(self.send::<_, IgnoredAny>(request).await?.data)
+    }
+
+    /// Only the author of a message can update it
+    pub async fn update_message(
+        &self,
+        id: &str,
+        update: &MessageUpdate,
+    ) -> Result<Message, ClientError> {
+        let request = self.request(Method::PUT, &["messages", id]).json(update);
+        Ok(self.send::<_, IgnoredAny>(request).await?.data)
+    }
+
+    /// Update a message only if it is still at `revision`, failing with
+    /// [`ErrorCode::MessageRevisionMismatch`] when someone else edited it since
+    pub async fn update_message_at_revision(
+        &self,
+        id: &str,
+        revision: u64,
+        update: &MessageUpdate,
+    ) -> Result<Message, ClientError> {
+        let request = self
+            .request(Method::PUT, &["messages", id])
+            .header(reqwest::header::IF_MATCH, format!("\"{revision}\""))
+            .json(update);
+        Ok(self.send::<_, IgnoredAny>(request).await?.data)
+    }
+
+    /// Only the author of a message can delete it
+    pub async fn delete_message(&self, id: &str) -> Result<(), ClientError> {
+        let request = self.request(Method::DELETE, &["messages", id]);
+        self.send::<IgnoredAny, IgnoredAny>(request).await?;
+        Ok(())
+    }
+
+    /// Messages of a channel, newest first
+    pub async fn list_messages(
+        &self,
+        channel_id: &str,
+        page: u32,
+        limit: u32,
+    ) -> Result<Page<Message>, ClientError> {
+        let request = self
+            .request(Method::GET, &["channels", channel_id, "messages"])
+            .query(&[("page", page), ("limit", limit)]);
+        self.send_page(request).await
+    }
+
+    /// Pinned messages of a channel, most recently pinned first
+    pub async fn list_pinned_messages(
+        &self,
+        channel_id: &str,
+        page: u32,
+        limit: u32,
+    ) -> Result<Page<Message>, ClientError> {
+        let request = self
+            .request(Method::GET, &["channels", channel_id, "pins"])
+            .query(&[("page", page), ("limit", limit)]);
+        self.send_page(request).await
+    }
+
+    /// Replies to a message, oldest first; pass the `next_cursor` of a page to get the next one
+    pub async fn list_replies(
+        &self,
+        id: &str,
+        cursor: Option<&str>,
+        limit: u32,
+    ) -> Result<CursorPage<Message>, ClientError> {
+        let mut request = self
+            .request(Method::GET, &["messages", id, "replies"])
+            .query(&[("limit", limit)]);
+        if let Some(cursor) = cursor {
+            request = request.query(&[("cursor", cursor)]);
+        }
+
+        let page: Envelope<Vec<Message>, CursorMeta> = self.send(request).await?;
+        Ok(CursorPage {
+            items: page.data,
+            next_cursor: page.meta.next_cursor,
+        })
+    }
+
+    /// Previous contents of a message, oldest first; requires moderating its channel
+    pub async fn get_message_history(&self, id: &str) -> Result<Vec<MessageRevision>, ClientError> {
+        let request = self.request(Method::GET, &["messages", id, "history"]);
+        Ok(self.send::<_, IgnoredAny>(request).await?.data)
+    }
+
+    pub async fn add_reaction(&self, id: &str, emoji: &str) -> Result<Reaction, ClientError> {
+        let request = self.request(Method::PUT, &["messages", id, "reactions", emoji]);
+        Ok(self.send::<_, IgnoredAny>(request).await?.data)
+    }
+
+    /// Remove a reaction of the client's user
+    pub async fn remove_reaction(&self, id: &str, emoji: &str) -> Result<(), ClientError> {
+        let request = self.request(Method::DELETE, &["messages", id, "reactions", emoji]);
+        self.send::<IgnoredAny, IgnoredAny>(request).await?;
+        Ok(())
+    }
+
+    /// Request to the base URL followed by `segments`, each percent-encoded
+    fn request(&self, method: Method, segments: &[&str]) -> RequestBuilder {
+        let mut url = self.base_url.clone();
+        url.path_segments_mut()
+            .expect("checked by ApiClient::new")
+            .pop_if_empty()
+            .extend(segments);
+
+        self.http
+            .request(method, url)
+            .bearer_auth(&self.access_token)
+            .header("api-version", "2")
+    }
+
+    async fn send<T, M>(&self, request: RequestBuilder) -> Result<Envelope<T, M>, ClientError>
+    where
+        T: DeserializeOwned,
+        M: DeserializeOwned,
+    {
+        let response = request.send().await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let status = status.as_u16();
+            return Err(match response.json::<ErrorBody>().await {
+                Ok(body) => ClientError::Api {
+                    status,
+                    code: body
+                        .error_code
+                        .as_deref()
+                        .map(ErrorCode::from)
+                        .unwrap_or_else(|| ErrorCode::from_status(status)),
+                    message: body.message,
+                },
+                Err(_) => ClientError::Api {
+                    status,
+                    code: ErrorCode::from_status(status),
+                    message: String::new(),
+                },
+            });
+        }
+        Ok(response.json().await?)
+    }
+
+    async fn send_page<T: DeserializeOwned>(
+        &self,
+        request: RequestBuilder,
+    ) -> Result<Page<T>, ClientError> {
+        let page: Envelope<Vec<T>, PageMeta> = self.send(request).await?;
+        Ok(Page {
+            items: page.data,
+            total: page.meta.total,
+            page: page.meta.page,
+            limit: page.meta.limit,
+            total_pages: page.meta.total_pages,
+            has_next: page.meta.has_next,
+        })
+    }
+}
+
+impl std::fmt::Debug for ApiClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // the access token is a credential
+        f.debug_struct("ApiClient")
+            .field("base_url", &self.base_url.as_str())
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,142 @@
+use thiserror::Error;
+
+/// Machine readable code of an API error, as in the `error_code` of error
+/// bodies and batch failures
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ErrorCode {
+    BadRequest,
+    Unauthorized,
+    Forbidden,
+    NotFound,
+    PinLimitReached,
+    MessageUnderLegalHold,
+    ChannelStorageQuotaExceeded,
+    /// The message changed since the revision an update was based on
+    MessageRevisionMismatch,
+    /// Writes to the channel are suspended for a while, retry later
+    ChannelWriteLocked,
+    /// An imported message has the ID of a stored one
+    MessageAlreadyExists,
+    /// The channel only allows some emoji as reactions, listed in the message
+    ReactionNotAllowed,
+    /// A posted attachment was not presigned by the author in the channel
+    AttachmentNotFound,
+    /// A posted attachment was presigned but its file never uploaded
+    AttachmentNotUploaded,
+    /// The message carries more attachments than the deployment allows
+    TooManyAttachments,
+    /// An attachment is larger than the deployment allows
+    AttachmentTooLarge,
+    /// The deployment does not accept attachments of this MIME type
+    AttachmentTypeNotAllowed,
+    InternalServerError,
+    ServiceUnavailable,
+    /// The deployment is a read-only replica; writes go to the primary one
+    ReadOnly,
+    /// A query parameter was missing, malformed or out of bounds
+    InvalidQuery,
+    /// A code this version of the client does not know yet
+    Other(String),
+}
+
+impl ErrorCode {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ErrorCode::BadRequest => "BAD_REQUEST",
+            ErrorCode::Unauthorized => "UNAUTHORIZED",
+            ErrorCode::Forbidden => "FORBIDDEN",
+            ErrorCode::NotFound => "NOT_FOUND",
+            ErrorCode::PinLimitReached => "PIN_LIMIT_REACHED",
+            ErrorCode::MessageUnderLegalHold => "MESSAGE_UNDER_LEGAL_HOLD",
+            ErrorCode::ChannelStorageQuotaExceeded => "CHANNEL_STORAGE_QUOTA_EXCEEDED",
+            ErrorCode::MessageRevisionMismatch => "MESSAGE_REVISION_MISMATCH",
+            ErrorCode::ChannelWriteLocked => "CHANNEL_WRITE_LOCKED",
+            ErrorCode::MessageAlreadyExists => "MESSAGE_ALREADY_EXISTS",
+            ErrorCode::ReactionNotAllowed => "REACTION_NOT_ALLOWED",
+            ErrorCode::AttachmentNotFound => "ATTACHMENT_NOT_FOUND",
+            ErrorCode::AttachmentNotUploaded => "ATTACHMENT_NOT_UPLOADED",
+            ErrorCode::TooManyAttachments => "TOO_MANY_ATTACHMENTS",
+            ErrorCode::AttachmentTooLarge => "ATTACHMENT_TOO_LARGE",
+            ErrorCode::AttachmentTypeNotAllowed => "ATTACHMENT_TYPE_NOT_ALLOWED",
+            ErrorCode::InternalServerError => "INTERNAL_SERVER_ERROR",
+            ErrorCode::ServiceUnavailable => "SERVICE_UNAVAILABLE",
+            ErrorCode::ReadOnly => "READ_ONLY",
+            ErrorCode::InvalidQuery => "INVALID_QUERY",
+            ErrorCode::Other(code) => code,
+        }
+    }
+
+    /// Code of an error answered with `status`, for error bodies without an
+    /// explicit `error_code`
+    pub fn from_status(status: u16) -> Self {
+        match status {
+            400 => ErrorCode::BadRequest,
+            401 => ErrorCode::Unauthorized,
+            403 => ErrorCode::Forbidden,
+            404 => ErrorCode::NotFound,
+            500 => ErrorCode::InternalServerError,
+            503 => ErrorCode::ServiceUnavailable,
+            status => ErrorCode::Other(status.to_string()),
+        }
+    }
+}
+
+impl From<&str> for ErrorCode {
+    fn from(code: &str) -> Self {
+        match code {
+            "BAD_REQUEST" => ErrorCode::BadRequest,
+            "UNAUTHORIZED" => ErrorCode::Unauthorized,
+            "FORBIDDEN" => ErrorCode::Forbidden,
+            "NOT_FOUND" => ErrorCode::NotFound,
+            "PIN_LIMIT_REACHED" => ErrorCode::PinLimitReached,
+            "MESSAGE_UNDER_LEGAL_HOLD" => ErrorCode::MessageUnderLegalHold,
+            "CHANNEL_STORAGE_QUOTA_EXCEEDED" => ErrorCode::ChannelStorageQuotaExceeded,
+            "MESSAGE_REVISION_MISMATCH" => ErrorCode::MessageRevisionMismatch,
+            "CHANNEL_WRITE_LOCKED" => ErrorCode::ChannelWriteLocked,
+            "MESSAGE_ALREADY_EXISTS" => ErrorCode::MessageAlreadyExists,
+            "REACTION_NOT_ALLOWED" => ErrorCode::ReactionNotAllowed,
+            "ATTACHMENT_NOT_FOUND" => ErrorCode::AttachmentNotFound,
+            "ATTACHMENT_NOT_UPLOADED" => ErrorCode::AttachmentNotUploaded,
+            "TOO_MANY_ATTACHMENTS" => ErrorCode::TooManyAttachments,
+            "ATTACHMENT_TOO_LARGE" => ErrorCode::AttachmentTooLarge,
+            "ATTACHMENT_TYPE_NOT_ALLOWED" => ErrorCode::AttachmentTypeNotAllowed,
+            "INTERNAL_SERVER_ERROR" => ErrorCode::InternalServerError,
+            "SERVICE_UNAVAILABLE" => ErrorCode::ServiceUnavailable,
+            "READ_ONLY" => ErrorCode::ReadOnly,
+            "INVALID_QUERY" => ErrorCode::InvalidQuery,
+            code => ErrorCode::Other(code.to_string()),
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ClientError {
+    /// The API answered with an error status
+    #[error("{code} ({status}): {message}")]
+    Api {
+        status: u16,
+        code: ErrorCode,
+        message: String,
+    },
+    /// The request could not be sent, or its response not decoded
+    #[error("Request failed: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("Invalid base URL: {0}")]
+    InvalidBaseUrl(#[from] url::ParseError),
+}
+
+impl ClientError {
+    /// Code of the API error, `None` when the API did not answer
+    pub fn code(&self) -> Option<&ErrorCode> {
+        match self {
+            ClientError::Api { code, .. } => Some(code),
+            _ => None,
+        }
+    }
+}
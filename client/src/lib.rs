@@ -0,0 +1,34 @@
+//! Typed client of the Community Service REST API.
+//!
+//! Internal Rust consumers call [`ApiClient`] instead of hand-rolling HTTP
+//! requests. Its models mirror the response schemas of the OpenAPI
+//! specification (`openapi.json`), and failures carry the API's error codes as
+//! an [`ErrorCode`].
+//!
+//! The client is maintained against the specification: every operation it
+//! calls is listed in [`OPERATIONS`], which the API tests check against the
+//! generated specification, so renaming or removing a route breaks the build
+//! of the API rather than its consumers.
+
+mod client;
+pub mod error;
+pub mod models;
+
+pub use client::ApiClient;
+pub use error::{ClientError, ErrorCode};
+
+/// Operations called by [`ApiClient`], as `(method, path)` in the notation of
+/// the OpenAPI specification
+pub const OPERATIONS: &[(&str, &str)] = &[
+    ("post", "/messages"),
+    ("post", "/attachments/presign"),
+    ("get", "/messages/{id}"),
+    ("put", "/messages/{id}"),
+    ("delete", "/messages/{id}"),
+    ("get", "/channels/{channel_id}/messages"),
+    ("get", "/channels/{channel_id}/pins"),
+    ("get", "/messages/{id}/replies"),
+    ("get", "/messages/{id}/history"),
+    ("put", "/messages/{id}/reactions/{emoji}"),
+    ("delete", "/messages/{id}/reactions/{emoji}"),
+];
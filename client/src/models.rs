@@ -0,0 +1,184 @@
+//! Request and response bodies of the REST API, mirroring the schemas of its
+//! OpenAPI specification.
+//!
+//! Message and channel IDs are public IDs: plain UUIDs, or opaque strings on
+//! deployments setting `PUBLIC_ID_KEY`, so they are kept as strings.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Attachment {
+    pub id: Uuid,
+    pub name: String,
+    pub url: String,
+    /// Size of the attached file in bytes, counted against the channel storage quota
+    #[serde(default)]
+    pub size: u64,
+    /// Dimensions and thumbnail of images, to lay them out before they are downloaded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preview: Option<ImagePreview>,
+}
+
+/// `ImagePreviewResponse` schema
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImagePreview {
+    pub width: u32,
+    pub height: u32,
+    #[serde(default)]
+    pub thumbnail_url: Option<String>,
+}
+
+/// `EmbedResponse` schema: preview of a link of a message
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Embed {
+    pub url: String,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub image_url: Option<String>,
+    #[serde(default)]
+    pub site_name: Option<String>,
+}
+
+/// `MentionsResponse` schema: users and roles mentioned in the content
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct Mentions {
+    #[serde(default)]
+    pub users: Vec<Uuid>,
+    #[serde(default)]
+    pub roles: Vec<Uuid>,
+    #[serde(default)]
+    pub everyone: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ReactionCount {
+    pub emoji: String,
+    pub count: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Reaction {
+    pub message_id: String,
+    pub user_id: Uuid,
+    pub emoji: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// `MessageResponse` schema
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Message {
+    #[serde(rename = "_id")]
+    pub id: String,
+    pub channel_id: String,
+    pub author_id: Uuid,
+    pub content: String,
+    pub reply_to_message_id: Option<String>,
+    pub attachments: Vec<Attachment>,
+    pub is_pinned: bool,
+    pub reactions: Vec<ReactionCount>,
+    pub revision: u64,
+    /// Position of the message in its channel, to order messages by
+    pub sequence: u64,
+    pub reply_count: u64,
+    pub last_reply_at: Option<DateTime<Utc>>,
+    /// Only present on archived threads
+    #[serde(default)]
+    pub archived_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: Option<DateTime<Utc>>,
+    /// Only present on deleted messages, which moderators alone can read back
+    #[serde(default)]
+    pub deleted_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub nonce: Option<String>,
+    /// Previews of the links of the content, added shortly after creation
+    #[serde(default)]
+    pub embeds: Vec<Embed>,
+    /// Users and roles mentioned in the content
+    #[serde(default)]
+    pub mentions: Mentions,
+    /// Only present on ephemeral messages, which disappear at that date
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// `MessageRevisionResponse` schema: content a message held before an edit
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct MessageRevision {
+    pub message_id: String,
+    pub revision: u64,
+    pub content: String,
+    pub written_at: DateTime<Utc>,
+    pub replaced_at: DateTime<Utc>,
+}
+
+/// `CreateMessageBody` schema
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NewMessage {
+    pub channel_id: String,
+    pub content: String,
+    pub reply_to_message_id: Option<String>,
+    /// IDs of attachments presigned with [`crate::ApiClient::presign_attachment`]
+    /// and uploaded
+    pub attachments: Vec<Uuid>,
+    /// Set it to retry a creation safely: the API returns the message
+    /// already created with this nonce instead of posting a copy
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+    /// Makes the message ephemeral: it disappears at that date
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// `PresignAttachmentRequest` schema
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NewAttachment {
+    pub channel_id: String,
+    pub name: String,
+    pub content_type: String,
+    pub size: u64,
+}
+
+/// `PresignedAttachmentResponse` schema: `PUT` the file to `upload_url`
+/// before `expires_at`, then post `attachment_id` with a message
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct PresignedAttachment {
+    pub attachment_id: Uuid,
+    pub upload_url: String,
+    pub method: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// `UpdateMessageRequest` schema; fields left to `None` are not changed
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct MessageUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_pinned: Option<bool>,
+}
+
+/// An offset page of a listing
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub total: u64,
+    pub page: u32,
+    pub limit: u32,
+    pub total_pages: u64,
+    /// Whether a page follows this one
+    pub has_next: bool,
+}
+
+/// A keyset page of a listing
+#[derive(Debug, Clone, PartialEq)]
+pub struct CursorPage<T> {
+    pub items: Vec<T>,
+    /// Cursor of the next page, `None` on the last page
+    pub next_cursor: Option<String>,
+}
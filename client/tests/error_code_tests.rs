@@ -0,0 +1,51 @@
+use communities_client::{ApiClient, ClientError, ErrorCode};
+
+#[test]
+fn error_codes_round_trip() {
+    for code in [
+        ErrorCode::BadRequest,
+        ErrorCode::Unauthorized,
+        ErrorCode::Forbidden,
+        ErrorCode::NotFound,
+        ErrorCode::PinLimitReached,
+        ErrorCode::MessageUnderLegalHold,
+        ErrorCode::ChannelStorageQuotaExceeded,
+        ErrorCode::MessageRevisionMismatch,
+        ErrorCode::ChannelWriteLocked,
+        ErrorCode::MessageAlreadyExists,
+        ErrorCode::ReactionNotAllowed,
+        ErrorCode::InternalServerError,
+        ErrorCode::ServiceUnavailable,
+    ] {
+        assert_eq!(ErrorCode::from(code.as_str()), code);
+    }
+
+    // codes added to the API later are kept as they are
+    let code = ErrorCode::from("SLOW_DOWN");
+    assert_eq!(code, ErrorCode::Other("SLOW_DOWN".to_string()));
+    assert_eq!(code.to_string(), "SLOW_DOWN");
+}
+
+#[test]
+fn statuses_map_to_generic_codes() {
+    assert_eq!(ErrorCode::from_status(404), ErrorCode::NotFound);
+    assert_eq!(ErrorCode::from_status(503), ErrorCode::ServiceUnavailable);
+    assert_eq!(
+        ErrorCode::from_status(418),
+        ErrorCode::Other("418".to_string())
+    );
+}
+
+#[test]
+fn base_urls_are_validated() {
+    assert!(ApiClient::new("http://communities:8080", "token").is_ok());
+    assert!(ApiClient::new("http://communities:8080/api/", "token").is_ok());
+    assert!(matches!(
+        ApiClient::new("communities", "token"),
+        Err(ClientError::InvalidBaseUrl(_))
+    ));
+    assert!(matches!(
+        ApiClient::new("mailto:ops@beep.ovh", "token"),
+        Err(ClientError::InvalidBaseUrl(_))
+    ));
+}
@@ -1,27 +1,53 @@
-use mongodb::{Client as MongoClient, options::ClientOptions};
+use std::{sync::Arc, time::Duration};
+
+use mongodb::{Client as MongoClient, Database, options::ClientOptions};
 
 use crate::{
-    domain::common::{CoreError, services::Service},
+    domain::{
+        archive::{ports::ObjectStorage, services::MessageArchive},
+        common::{CoreError, services::Service},
+        lease::ports::LeaseLock,
+        lifecycle::ports::LifecyclePublisher,
+        membership::ports::ChannelMembership,
+        search::{
+            ports::{NoopArchiveStore, SearchIndex},
+            services::MessageSearch,
+        },
+    },
     infrastructure::{
-        MessageRoutingInfo,
-    health::repositories::mongo::MongoHealthRepository,
-        message::repositories::mongo::MongoMessageRepository,
+        archive::MongoArchiveManifestRepository,
+        health::repositories::mongo::MongoHealthRepository,
+        lease::{MongoLeaseLock, SingletonJob},
+        membership::MongoChannelMembership,
+        message::{encoding::backfill_standard_uuids, repositories::mongo::MongoMessageRepository},
+        outbox::{MessageRoutingInfo, OutboxLifecyclePublisher, ensure_outbox_indexes},
+        search::MongoTextSearchIndex,
     },
 };
 
+pub use crate::infrastructure::outbox::MessageRoutingInfos;
+
 /// Concrete service type
 pub type CommunitiesService = Service<MongoMessageRepository, MongoHealthRepository>;
 
+/// Concrete search type
+pub type CommunitiesSearch = MessageSearch<MongoMessageRepository>;
+
+/// Concrete archive type
+pub type CommunitiesArchive = MessageArchive<MongoArchiveManifestRepository>;
+
 #[derive(Clone)]
 pub struct CommunitiesRepositories {
     pub message_repository: MongoMessageRepository,
     pub health_repository: MongoHealthRepository,
+    mongo_db: Database,
 }
 
-#[tracing::instrument(skip(mongo_uri, mongo_db_name))]
+#[tracing::instrument(skip(mongo_uri, mongo_db_name, routing))]
 pub async fn create_repositories(
     mongo_uri: &str,
     mongo_db_name: &str,
+    routing: MessageRoutingInfos,
 ) -> Result<CommunitiesRepositories, CoreError> {
     tracing::info!(db = %mongo_db_name, "creating mongodb client");
     let mongo_options = ClientOptions::parse(mongo_uri)
@@ -33,7 +59,23 @@ pub async fn create_repositories(
 
     let mongo_db = mongo_client.database(mongo_db_name);
 
-    let message_repository = MongoMessageRepository::new(&mongo_db);
+    let message_repository = MongoMessageRepository::new(&mongo_db).with_routing(routing);
+    // Created in the background so startup doesn't wait on server selection;
+    // listings still work without indexes, only slower
+    let indexed_repository = message_repository.clone();
+    let text_search_index = MongoTextSearchIndex::new(&mongo_db);
+    let channel_membership = MongoChannelMembership::new(&mongo_db);
+    tokio::spawn(async move {
+        if let Err(e) = indexed_repository.ensure_indexes().await {
+            tracing::warn!(error = %e, "failed to ensure message indexes");
+        }
+        if let Err(e) = text_search_index.ensure_indexes().await {
+            tracing::warn!(error = %e, "failed to ensure message text index");
+        }
+        if let Err(e) = channel_membership.ensure_indexes().await {
+            tracing::warn!(error = %e, "failed to ensure channel member indexes");
+        }
+    });
 
     let health_repository = MongoHealthRepository::new(&mongo_db);
 
@@ -42,6 +84,7 @@ pub async fn create_repositories(
     Ok(CommunitiesRepositories {
         message_repository,
         health_repository,
+        mongo_db,
     })
 }
 
@@ -52,6 +95,84 @@ impl From<CommunitiesRepositories> for CommunitiesService {
 }
 
 impl CommunitiesRepositories {
+    /// Create the outbox indexes in the background, letting MongoDB delete
+    /// `SENT` outbox records once `sent_retention` elapsed.
+    ///
+    /// With a `lease`, only the replica holding it does so.
+    pub fn enforce_outbox_retention(&self, sent_retention: Duration, lease: Option<SingletonJob>) {
+        let mongo_db = self.mongo_db.clone();
+        tokio::spawn(async move {
+            let ensure = ensure_outbox_indexes(&mongo_db, sent_retention);
+            let result = match lease {
+                Some(lease) => lease
+                    .run_once(ensure)
+                    .await
+                    .and_then(|r| r.unwrap_or(Ok(()))),
+                None => ensure.await,
+            };
+            if let Err(e) = result {
+                tracing::warn!(error = %e, "failed to ensure outbox indexes");
+            }
+        });
+    }
+
+    /// Convert the stored reference UUIDs to the standard encoding, returning
+    /// how many documents were converted
+    pub async fn backfill_standard_uuids(&self, batch_size: u32) -> Result<u64, CoreError> {
+        backfill_standard_uuids(&self.mongo_db, batch_size).await
+    }
+
+    /// Lock shared by the replicas using this database, `holder` identifying this one
+    pub fn lease_lock(&self, holder: impl Into<String>) -> Arc<dyn LeaseLock> {
+        Arc::new(MongoLeaseLock::new(&self.mongo_db, holder))
+    }
+
+    /// Snapshot of the members of channels, shared by the replicas using this database
+    pub fn channel_membership(&self) -> Arc<dyn ChannelMembership> {
+        Arc::new(MongoChannelMembership::new(&self.mongo_db))
+    }
+
+    /// Lifecycle announcements of this replica, written to the outbox on `routing`
+    pub fn lifecycle_publisher(&self, routing: MessageRoutingInfo) -> Arc<dyn LifecyclePublisher> {
+        Arc::new(OutboxLifecyclePublisher::new(&self.mongo_db, routing))
+    }
+
+    /// Search backend using the `$text` index of the `messages` collection
+    pub fn text_search_index(&self) -> MongoTextSearchIndex {
+        MongoTextSearchIndex::new(&self.mongo_db)
+    }
+
+    /// Message search over the `$text` index, without archived messages
+    pub fn message_search(&self) -> CommunitiesSearch {
+        self.message_search_over(Arc::new(self.text_search_index()))
+    }
+
+    /// Message search over another backend, hits being loaded from MongoDB
+    pub fn message_search_over(&self, index: Arc<dyn SearchIndex>) -> CommunitiesSearch {
+        MessageSearch::new(
+            self.message_repository.clone(),
+            index,
+            Arc::new(NoopArchiveStore),
+        )
+    }
+
+    /// Archive writing batches of expired messages to `storage` under
+    /// `prefix`, recorded in the `message_archives` collection
+    pub fn message_archive(
+        &self,
+        storage: Arc<dyn ObjectStorage>,
+        prefix: impl Into<String>,
+    ) -> CommunitiesArchive {
+        let manifest = MongoArchiveManifestRepository::new(&self.mongo_db);
+        let indexed = manifest.clone();
+        tokio::spawn(async move {
+            if let Err(e) = indexed.ensure_indexes().await {
+                tracing::warn!(error = %e, "failed to ensure message archive indexes");
+            }
+        });
+        MessageArchive::new(manifest, storage).with_prefix(prefix)
+    }
+
     pub async fn shutdown(&self) {
         tracing::info!("closing Mongo DB connection");
         // MongoDB driver shuts down automatically
@@ -64,17 +185,3 @@ impl CommunitiesService {
         // MongoDB driver shuts down automatically
     }
 }
-
-/// Configuration for message routing information across different event types.
-///
-/// This struct holds the routing configuration for various outbox events
-/// that need to be published to a message broker. Each field represents
-/// the routing information (exchange name and routing key) for a specific
-/// type of domain event.
-#[derive(Clone, Debug, Default, serde::Deserialize, serde::Serialize)]
-pub struct MessageRoutingInfos {
-    /// Routing information for message creation events
-    pub create_message: MessageRoutingInfo,
-    /// Routing information for message deletion events
-    pub delete_message: MessageRoutingInfo,
-}
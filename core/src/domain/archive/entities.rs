@@ -0,0 +1,42 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::domain::message::entities::{AuthorId, ChannelId, MessageId};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ArchiveBatchId(pub Uuid);
+
+impl std::fmt::Display for ArchiveBatchId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<Uuid> for ArchiveBatchId {
+    fn from(uuid: Uuid) -> Self {
+        ArchiveBatchId(uuid)
+    }
+}
+
+/// Manifest entry of a batch of messages written to object storage, one
+/// gzip-compressed NDJSON object per batch
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArchiveBatch {
+    pub id: ArchiveBatchId,
+    pub channel_id: ChannelId,
+    /// Key of the object holding the batch
+    pub object_key: String,
+    pub message_ids: Vec<MessageId>,
+    /// Authors of the messages of the batch, empty on batches archived
+    /// before they were recorded
+    #[serde(default)]
+    pub author_ids: Vec<AuthorId>,
+    /// Creation date of the oldest message of the batch
+    pub oldest_created_at: DateTime<Utc>,
+    /// Creation date of the newest message of the batch
+    pub newest_created_at: DateTime<Utc>,
+    /// Size of the compressed object
+    pub size_bytes: u64,
+    pub archived_at: DateTime<Utc>,
+}
@@ -0,0 +1,189 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use crate::domain::{
+    archive::entities::ArchiveBatch,
+    common::{CoreError, GetPaginated, TotalPaginatedElements},
+    message::entities::{AuthorId, ChannelId, Message, MessageId},
+};
+
+/// Port to an object storage, such as an S3-compatible bucket
+#[async_trait::async_trait]
+pub trait ObjectStorage: Send + Sync {
+    /// Store `body` under `key`, replacing any object already there
+    async fn put_object(&self, key: &str, body: Vec<u8>) -> Result<(), CoreError>;
+
+    /// Content of the object stored under `key`, `None` when there is none
+    async fn get_object(&self, key: &str) -> Result<Option<Vec<u8>>, CoreError>;
+
+    /// Remove the object stored under `key`, if any
+    async fn delete_object(&self, key: &str) -> Result<(), CoreError>;
+}
+
+/// Port to the manifest recording where archived messages were written
+#[async_trait::async_trait]
+pub trait ArchiveManifestRepository: Send + Sync {
+    async fn record_batch(&self, batch: &ArchiveBatch) -> Result<(), CoreError>;
+
+    /// Batches holding any of `ids`, in no particular order
+    async fn find_by_message_ids(&self, ids: &[MessageId]) -> Result<Vec<ArchiveBatch>, CoreError>;
+
+    /// Batches outside `excluded_channels` that may hold messages of
+    /// `author_id`: those recording them, and those recorded without authors
+    async fn find_by_author(
+        &self,
+        author_id: &AuthorId,
+        excluded_channels: &[ChannelId],
+    ) -> Result<Vec<ArchiveBatch>, CoreError>;
+
+    async fn delete_batch(&self, batch: &ArchiveBatch) -> Result<(), CoreError>;
+
+    /// Batches of a channel, oldest messages first
+    async fn list_batches(
+        &self,
+        channel_id: &ChannelId,
+        pagination: &GetPaginated,
+    ) -> Result<(Vec<ArchiveBatch>, TotalPaginatedElements), CoreError>;
+}
+
+/// Port used by the retention sweep to copy messages to cold storage before
+/// deleting them
+#[async_trait::async_trait]
+pub trait MessageArchiver: Send + Sync {
+    /// Write `messages`, all of `channel_id`, as one batch and record it in
+    /// the manifest; the messages must not be deleted unless this succeeds
+    async fn archive(
+        &self,
+        channel_id: &ChannelId,
+        messages: &[Message],
+    ) -> Result<ArchiveBatch, CoreError>;
+
+    /// Remove the archived messages of `author_id` outside `held_channels`
+    /// from their batches, deleting the batches left empty, and return how
+    /// many were removed
+    async fn erase_author(
+        &self,
+        author_id: &AuthorId,
+        held_channels: &[ChannelId],
+    ) -> Result<u64, CoreError>;
+}
+
+/// Objects held in memory, for tests and local development
+#[derive(Clone, Default)]
+pub struct InMemoryObjectStorage {
+    objects: Arc<Mutex<HashMap<String, Vec<u8>>>>,
+}
+
+impl InMemoryObjectStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keys of the stored objects, sorted
+    pub fn keys(&self) -> Vec<String> {
+        let mut keys: Vec<String> = self.objects.lock().unwrap().keys().cloned().collect();
+        keys.sort();
+        keys
+    }
+}
+
+#[async_trait::async_trait]
+impl ObjectStorage for InMemoryObjectStorage {
+    async fn put_object(&self, key: &str, body: Vec<u8>) -> Result<(), CoreError> {
+        self.objects.lock().unwrap().insert(key.to_string(), body);
+        Ok(())
+    }
+
+    async fn get_object(&self, key: &str) -> Result<Option<Vec<u8>>, CoreError> {
+        Ok(self.objects.lock().unwrap().get(key).cloned())
+    }
+
+    async fn delete_object(&self, key: &str) -> Result<(), CoreError> {
+        self.objects.lock().unwrap().remove(key);
+        Ok(())
+    }
+}
+
+/// Manifest held in memory, for tests and local development
+#[derive(Clone, Default)]
+pub struct InMemoryArchiveManifest {
+    batches: Arc<Mutex<Vec<ArchiveBatch>>>,
+}
+
+impl InMemoryArchiveManifest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait::async_trait]
+impl ArchiveManifestRepository for InMemoryArchiveManifest {
+    async fn record_batch(&self, batch: &ArchiveBatch) -> Result<(), CoreError> {
+        let mut batches = self.batches.lock().unwrap();
+        batches.retain(|recorded| recorded.id != batch.id);
+        batches.push(batch.clone());
+        Ok(())
+    }
+
+    async fn find_by_message_ids(&self, ids: &[MessageId]) -> Result<Vec<ArchiveBatch>, CoreError> {
+        Ok(self
+            .batches
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|batch| batch.message_ids.iter().any(|id| ids.contains(id)))
+            .cloned()
+            .collect())
+    }
+
+    async fn find_by_author(
+        &self,
+        author_id: &AuthorId,
+        excluded_channels: &[ChannelId],
+    ) -> Result<Vec<ArchiveBatch>, CoreError> {
+        Ok(self
+            .batches
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|batch| !excluded_channels.contains(&batch.channel_id))
+            .filter(|batch| batch.author_ids.is_empty() || batch.author_ids.contains(author_id))
+            .cloned()
+            .collect())
+    }
+
+    async fn delete_batch(&self, batch: &ArchiveBatch) -> Result<(), CoreError> {
+        self.batches
+            .lock()
+            .unwrap()
+            .retain(|recorded| recorded.id != batch.id);
+        Ok(())
+    }
+
+    async fn list_batches(
+        &self,
+        channel_id: &ChannelId,
+        pagination: &GetPaginated,
+    ) -> Result<(Vec<ArchiveBatch>, TotalPaginatedElements), CoreError> {
+        let mut batches: Vec<ArchiveBatch> = self
+            .batches
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|batch| batch.channel_id == *channel_id)
+            .cloned()
+            .collect();
+        batches.sort_by_key(|batch| batch.oldest_created_at);
+
+        let total = batches.len() as TotalPaginatedElements;
+        let offset = (pagination.page.saturating_sub(1) * pagination.limit) as usize;
+        let page = batches
+            .into_iter()
+            .skip(offset)
+            .take(pagination.limit as usize)
+            .collect();
+        Ok((page, total))
+    }
+}
@@ -0,0 +1,241 @@
+use std::{
+    collections::HashSet,
+    io::{BufRead, BufReader, Write},
+    sync::Arc,
+};
+
+use chrono::Utc;
+use flate2::{Compression, read::GzDecoder, write::GzEncoder};
+use uuid::Uuid;
+
+use crate::domain::{
+    archive::{
+        entities::{ArchiveBatch, ArchiveBatchId},
+        ports::{ArchiveManifestRepository, MessageArchiver, ObjectStorage},
+    },
+    common::CoreError,
+    message::entities::{AuthorId, ChannelId, Message, MessageId},
+    search::ports::ArchiveStore,
+};
+
+/// Writes archived messages to object storage as gzip-compressed NDJSON, one
+/// object per batch, and reads them back through the manifest
+#[derive(Clone)]
+pub struct MessageArchive<M>
+where
+    M: ArchiveManifestRepository,
+{
+    manifest: M,
+    storage: Arc<dyn ObjectStorage>,
+    /// Prepended to the key of every object, without trailing slash
+    prefix: String,
+}
+
+impl<M> MessageArchive<M>
+where
+    M: ArchiveManifestRepository,
+{
+    pub fn new(manifest: M, storage: Arc<dyn ObjectStorage>) -> Self {
+        Self {
+            manifest,
+            storage,
+            prefix: "messages".to_string(),
+        }
+    }
+
+    /// Write the objects under `prefix` rather than `messages`
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into().trim_matches('/').to_string();
+        self
+    }
+
+    pub fn manifest(&self) -> &M {
+        &self.manifest
+    }
+
+    /// Key of a batch: `<prefix>/<channel>/<yyyy>/<mm>/<dd>/<batch>.ndjson.gz`,
+    /// dated by the archival so listing a prefix finds the batches of a day
+    fn object_key(&self, batch: &ArchiveBatch) -> String {
+        let path = format!(
+            "{}/{}/{}.ndjson.gz",
+            batch.channel_id,
+            batch.archived_at.format("%Y/%m/%d"),
+            batch.id
+        );
+        if self.prefix.is_empty() {
+            path
+        } else {
+            format!("{}/{path}", self.prefix)
+        }
+    }
+}
+
+/// One message per line, compressed as a whole
+fn encode_batch(messages: &[Message]) -> Result<Vec<u8>, CoreError> {
+    let encode_error = |e: std::io::Error| CoreError::ArchiveError { msg: e.to_string() };
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    for message in messages {
+        serde_json::to_writer(&mut encoder, message)
+            .map_err(|e| CoreError::SerializationError { msg: e.to_string() })?;
+        encoder.write_all(b"\n").map_err(encode_error)?;
+    }
+    encoder.finish().map_err(encode_error)
+}
+
+/// Authors of `messages`, each once
+fn authors_of(messages: &[Message]) -> Vec<AuthorId> {
+    let mut authors = Vec::new();
+    for message in messages {
+        if !authors.contains(&message.author_id) {
+            authors.push(message.author_id);
+        }
+    }
+    authors
+}
+
+fn decode_batch(body: &[u8]) -> Result<Vec<Message>, CoreError> {
+    let mut messages = Vec::new();
+    for line in BufReader::new(GzDecoder::new(body)).lines() {
+        let line = line.map_err(|e| CoreError::ArchiveError { msg: e.to_string() })?;
+        if line.is_empty() {
+            continue;
+        }
+        messages.push(
+            serde_json::from_str(&line)
+                .map_err(|e| CoreError::SerializationError { msg: e.to_string() })?,
+        );
+    }
+    Ok(messages)
+}
+
+#[async_trait::async_trait]
+impl<M> MessageArchiver for MessageArchive<M>
+where
+    M: ArchiveManifestRepository,
+{
+    async fn archive(
+        &self,
+        channel_id: &ChannelId,
+        messages: &[Message],
+    ) -> Result<ArchiveBatch, CoreError> {
+        let (Some(oldest), Some(newest)) = (
+            messages.iter().map(|message| message.created_at).min(),
+            messages.iter().map(|message| message.created_at).max(),
+        ) else {
+            return Err(CoreError::ArchiveError {
+                msg: "cannot archive an empty batch".to_string(),
+            });
+        };
+        if messages.iter().any(|message| message.channel_id != *channel_id) {
+            return Err(CoreError::ArchiveError {
+                msg: format!("archive batch mixes channels, expected only {channel_id}"),
+            });
+        }
+
+        let body = encode_batch(messages)?;
+        let mut batch = ArchiveBatch {
+            id: ArchiveBatchId::from(Uuid::new_v4()),
+            channel_id: *channel_id,
+            object_key: String::new(),
+            message_ids: messages.iter().map(|message| message.id).collect(),
+            author_ids: authors_of(messages),
+            oldest_created_at: oldest,
+            newest_created_at: newest,
+            size_bytes: body.len() as u64,
+            archived_at: Utc::now(),
+        };
+        batch.object_key = self.object_key(&batch);
+
+        // an object missing from the manifest is only wasted space, whereas
+        // the other way around would point readers at nothing
+        self.storage.put_object(&batch.object_key, body).await?;
+        self.manifest.record_batch(&batch).await?;
+        Ok(batch)
+    }
+
+    async fn erase_author(
+        &self,
+        author_id: &AuthorId,
+        held_channels: &[ChannelId],
+    ) -> Result<u64, CoreError> {
+        let mut erased = 0;
+        for batch in self.manifest.find_by_author(author_id, held_channels).await? {
+            let Some(body) = self.storage.get_object(&batch.object_key).await? else {
+                // nothing left to erase, e.g. when a previous erasure stopped
+                // between the object and the manifest
+                self.manifest.delete_batch(&batch).await?;
+                continue;
+            };
+            let (removed, kept): (Vec<Message>, Vec<Message>) = decode_batch(&body)?
+                .into_iter()
+                .partition(|message| message.author_id == *author_id);
+
+            let (Some(oldest), Some(newest)) = (
+                kept.iter().map(|message| message.created_at).min(),
+                kept.iter().map(|message| message.created_at).max(),
+            ) else {
+                self.storage.delete_object(&batch.object_key).await?;
+                self.manifest.delete_batch(&batch).await?;
+                erased += removed.len() as u64;
+                continue;
+            };
+            if removed.is_empty() {
+                // batches archived before authors were recorded get theirs,
+                // so later erasures skip them
+                if batch.author_ids.is_empty() {
+                    let author_ids = authors_of(&kept);
+                    self.manifest
+                        .record_batch(&ArchiveBatch { author_ids, ..batch })
+                        .await?;
+                }
+                continue;
+            }
+
+            let body = encode_batch(&kept)?;
+            let rewritten = ArchiveBatch {
+                message_ids: kept.iter().map(|message| message.id).collect(),
+                author_ids: authors_of(&kept),
+                oldest_created_at: oldest,
+                newest_created_at: newest,
+                size_bytes: body.len() as u64,
+                ..batch
+            };
+            self.storage.put_object(&rewritten.object_key, body).await?;
+            self.manifest.record_batch(&rewritten).await?;
+            erased += removed.len() as u64;
+        }
+        Ok(erased)
+    }
+}
+
+#[async_trait::async_trait]
+impl<M> ArchiveStore for MessageArchive<M>
+where
+    M: ArchiveManifestRepository,
+{
+    async fn fetch_archived(&self, ids: &[MessageId]) -> Result<Vec<Message>, CoreError> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let wanted: HashSet<MessageId> = ids.iter().copied().collect();
+        let mut found = Vec::new();
+        for batch in self.manifest.find_by_message_ids(ids).await? {
+            let Some(body) = self.storage.get_object(&batch.object_key).await? else {
+                tracing::warn!(key = %batch.object_key, "archived batch missing from object storage");
+                continue;
+            };
+            found.extend(
+                decode_batch(&body)?
+                    .into_iter()
+                    .filter(|message| wanted.contains(&message.id)),
+            );
+        }
+
+        // a message archived twice, by a sweep retried after a failed
+        // deletion, is returned once
+        let mut seen = HashSet::new();
+        found.retain(|message| seen.insert(message.id));
+        Ok(found)
+    }
+}
@@ -1,8 +1,10 @@
+use chrono::{DateTime, Utc};
 use serde::Deserialize;
 use thiserror::Error;
 use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
 
-use crate::domain::message::entities::MessageId;
+use crate::domain::message::entities::{AttachmentId, ChannelId, LegalHoldId, MessageId};
 
 pub mod services;
 
@@ -20,6 +22,27 @@ pub enum CoreError {
     #[error("Message name cannot be empty")]
     InvalidMessageName,
 
+    #[error("Message {id} cannot be replied to from another channel")]
+    InvalidReplyTarget { id: MessageId },
+
+    #[error("Message {id} already exists")]
+    MessageAlreadyExists { id: MessageId },
+
+    #[error("Message {id} was updated before being created, or created in the future")]
+    InvalidMessageTimestamps { id: MessageId },
+
+    #[error("Invalid reaction emoji: {emoji}")]
+    InvalidReaction { emoji: String },
+
+    #[error("Reaction {emoji} is not allowed in this channel, allowed: {}", allowed.join(" "))]
+    ReactionNotAllowed { emoji: String, allowed: Vec<String> },
+
+    #[error("Channels may allow at most {max} reactions")]
+    TooManyAllowedReactions { max: usize },
+
+    #[error("Reaction {emoji} on message {message_id} not found")]
+    ReactionNotFound { message_id: MessageId, emoji: String },
+
     #[error("Health check failed")]
     Unhealthy,
 
@@ -29,22 +52,286 @@ pub enum CoreError {
     #[error("Database error: {msg}")]
     DatabaseError { msg: String },
 
+    #[error("Message nonce must be 1 to {max} characters long")]
+    InvalidMessageNonce { max: usize },
+
+    #[error("Messages must expire in the future")]
+    InvalidMessageExpiry,
+
+    #[error("Invalid pagination cursor")]
+    InvalidCursor,
+
+    #[error("No message of channel {channel_id} was read yet")]
+    ReadMarkerNotFound { channel_id: ChannelId },
+
+    #[error("Legal hold {id} not found")]
+    LegalHoldNotFound { id: LegalHoldId },
+
+    #[error("Legal hold reference cannot be empty")]
+    InvalidLegalHoldReference,
+
+    #[error("Moderation reasons need a code and at most {max} characters of text")]
+    InvalidModerationReason { max: usize },
+
+    #[error("Unknown moderation reason {code}")]
+    UnknownModerationReason { code: String },
+
+    #[error("Attachment {id} not found")]
+    AttachmentNotFound { id: AttachmentId },
+
+    #[error("Attachment {id} was not uploaded")]
+    AttachmentNotUploaded { id: AttachmentId },
+
+    #[error("Attachments need a name of at most {max_name_chars} characters, a content type and a size")]
+    InvalidAttachment { max_name_chars: usize },
+
+    #[error("Attachment uploads are not configured")]
+    AttachmentUploadsDisabled,
+
+    #[error("Messages carry at most {max} attachments")]
+    TooManyAttachments { max: usize },
+
+    #[error("Attachments are at most {max_bytes} bytes")]
+    AttachmentTooLarge { max_bytes: u64 },
+
+    #[error("Attachments of type {content_type} are not allowed")]
+    AttachmentTypeNotAllowed { content_type: String },
+
+    #[error("Channel {channel_id} exceeded its attachment storage quota")]
+    ChannelStorageQuotaExceeded { channel_id: ChannelId },
+
+    #[error("Message {id} is under a legal hold")]
+    MessageUnderLegalHold { id: MessageId },
+
+    #[error("Message {id} is at revision {revision}, not the expected one")]
+    MessageRevisionMismatch { id: MessageId, revision: u64 },
+
+    #[error("Writes to channel {channel_id} are locked, retry in {retry_after_secs}s")]
+    ChannelWriteLocked { channel_id: ChannelId, retry_after_secs: u64 },
+
+    #[error("Channel write locks last between 1 second and {max_secs} seconds")]
+    InvalidChannelWriteLock { max_secs: i64 },
+
+    #[error("Channel {channel_id} already has {limit} pinned messages")]
+    PinLimitReached { channel_id: ChannelId, limit: u64 },
+
+    #[error("Invalid export recipient: {msg}")]
+    InvalidExportRecipient { msg: String },
+
     /// Serialization error occurred when converting event to JSON
     #[error("Serialization error: {msg}")]
     SerializationError { msg: String },
+
+    /// Payload encryption or decryption failed
+    #[error("Encryption error: {msg}")]
+    EncryptionError { msg: String },
+
+    /// Writing to or reading from the message archive failed
+    #[error("Archive error: {msg}")]
+    ArchiveError { msg: String },
+
+    /// Presigning or inspecting an attachment upload failed
+    #[error("Attachment storage error: {msg}")]
+    AttachmentStorageError { msg: String },
+    /// The malware scanner could not scan an attachment
+    #[error("Attachment scan error: {msg}")]
+    AttachmentScanError { msg: String },
 }
 
 #[derive(Debug, Deserialize, ToSchema, IntoParams)]
 #[into_params(parameter_in = Query)]
 pub struct GetPaginated {
+    #[serde(default = "default_page")]
     pub page: u32,
+    #[serde(default = "default_page_limit")]
     pub limit: u32,
 }
 
+fn default_page() -> u32 {
+    1
+}
+
+fn default_page_limit() -> u32 {
+    20
+}
+
 impl Default for GetPaginated {
     fn default() -> Self {
-        Self { page: 1, limit: 20 }
+        Self {
+            page: default_page(),
+            limit: default_page_limit(),
+        }
     }
 }
 
 pub type TotalPaginatedElements = u64;
+
+/// Upper bound on the number of items returned by a single page
+pub const MAX_PAGE_LIMIT: u32 = 50;
+
+fn default_cursor_limit() -> u32 {
+    20
+}
+
+/// Keyset pagination parameters, for lists that can grow without bound
+#[derive(Debug, Clone, Deserialize, ToSchema, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct GetCursorPaginated {
+    /// Opaque cursor returned as `next_cursor` by the previous page
+    pub cursor: Option<String>,
+    #[serde(default = "default_cursor_limit")]
+    pub limit: u32,
+}
+
+impl Default for GetCursorPaginated {
+    fn default() -> Self {
+        Self {
+            cursor: None,
+            limit: default_cursor_limit(),
+        }
+    }
+}
+
+impl GetCursorPaginated {
+    /// Requested page size, clamped to `1..=MAX_PAGE_LIMIT`
+    pub fn effective_limit(&self) -> usize {
+        self.limit.clamp(1, MAX_PAGE_LIMIT) as usize
+    }
+
+    pub fn decoded_cursor(&self) -> Result<Option<Cursor>, CoreError> {
+        self.cursor.as_deref().map(Cursor::decode).transpose()
+    }
+}
+
+/// Position in a list ordered by `(created_at, id)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cursor {
+    pub created_at: DateTime<Utc>,
+    pub id: Uuid,
+}
+
+impl Cursor {
+    pub fn new(created_at: DateTime<Utc>, id: Uuid) -> Self {
+        Self { created_at, id }
+    }
+
+    /// Hex-encode the position so it is safe to pass around in query strings
+    pub fn encode(&self) -> String {
+        format!("{}|{}", self.created_at.to_rfc3339(), self.id)
+            .bytes()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    pub fn decode(value: &str) -> Result<Self, CoreError> {
+        if !value.is_ascii() || !value.len().is_multiple_of(2) {
+            return Err(CoreError::InvalidCursor);
+        }
+        let bytes = (0..value.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&value[i..i + 2], 16))
+            .collect::<Result<Vec<u8>, _>>()
+            .map_err(|_| CoreError::InvalidCursor)?;
+        let raw = String::from_utf8(bytes).map_err(|_| CoreError::InvalidCursor)?;
+
+        let (created_at, id) = raw.split_once('|').ok_or(CoreError::InvalidCursor)?;
+        let created_at = DateTime::parse_from_rfc3339(created_at)
+            .map_err(|_| CoreError::InvalidCursor)?
+            .with_timezone(&Utc);
+        let id = Uuid::parse_str(id).map_err(|_| CoreError::InvalidCursor)?;
+
+        Ok(Self { created_at, id })
+    }
+}
+
+/// Bounds of a keyset listing, both exclusive
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CursorRange {
+    pub before: Option<Cursor>,
+    pub after: Option<Cursor>,
+}
+
+/// Bound of a message listing: a message of the channel, or an instant
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageBound {
+    Message(MessageId),
+    Timestamp(DateTime<Utc>),
+}
+
+impl std::str::FromStr for MessageBound {
+    type Err = CoreError;
+
+    /// Parse a message id, or else an RFC3339 timestamp
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if let Ok(id) = Uuid::parse_str(value) {
+            return Ok(MessageBound::Message(MessageId(id)));
+        }
+        DateTime::parse_from_rfc3339(value)
+            .map(|date| MessageBound::Timestamp(date.with_timezone(&Utc)))
+            .map_err(|_| CoreError::InvalidCursor)
+    }
+}
+
+/// Keyset listing of the messages of a channel, newest first
+#[derive(Debug, Clone, Default)]
+pub struct GetMessagesByCursor {
+    pub pagination: GetCursorPaginated,
+    /// Only messages older than this bound
+    pub before: Option<MessageBound>,
+    /// Only messages newer than this bound
+    pub after: Option<MessageBound>,
+}
+
+/// Keyset parameters of a channel listing, replacing `page` when any is set
+#[derive(Debug, Clone, Default, Deserialize, ToSchema, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct GetMessagesCursorParams {
+    /// Opaque cursor returned as `next_cursor` by the previous page
+    pub cursor: Option<String>,
+    /// Only messages older than this message id or RFC3339 timestamp
+    pub before: Option<String>,
+    /// Only messages newer than this message id or RFC3339 timestamp
+    pub after: Option<String>,
+}
+
+impl GetMessagesCursorParams {
+    /// The keyset query, `None` when no keyset parameter is set
+    pub fn into_query(self, limit: u32) -> Result<Option<GetMessagesByCursor>, CoreError> {
+        if self.cursor.is_none() && self.before.is_none() && self.after.is_none() {
+            return Ok(None);
+        }
+
+        Ok(Some(GetMessagesByCursor {
+            pagination: GetCursorPaginated {
+                cursor: self.cursor,
+                limit,
+            },
+            before: self.before.as_deref().map(str::parse).transpose()?,
+            after: self.after.as_deref().map(str::parse).transpose()?,
+        }))
+    }
+}
+
+/// A page of a keyset-paginated list
+#[derive(Debug, Clone)]
+pub struct CursorPage<T> {
+    pub items: Vec<T>,
+    /// Cursor of the next page, `None` on the last page
+    pub next_cursor: Option<String>,
+}
+
+impl<T> CursorPage<T> {
+    /// Build a page from up to `limit + 1` items fetched in order; the extra
+    /// item only tells whether a next page exists.
+    pub fn from_overfetched(mut items: Vec<T>, limit: usize, cursor_of: impl Fn(&T) -> Cursor) -> Self {
+        let has_more = items.len() > limit;
+        items.truncate(limit);
+        let next_cursor = if has_more {
+            items.last().map(|last| cursor_of(last).encode())
+        } else {
+            None
+        };
+
+        Self { items, next_cursor }
+    }
+}
@@ -1,24 +1,192 @@
-use crate::domain::{health::port::HealthRepository, message::ports::MessageRepository};
+use std::{collections::HashMap, sync::Arc};
+
+use crate::domain::{
+    archive::ports::MessageArchiver,
+    health::port::{HealthProbe, HealthRepository},
+    membership::ports::{ChannelMembership, MemberDirectory},
+    message::{
+        entities::{AbuseThreshold, AttachmentLimits, LinkPreviewPolicy, ModerationReasonTemplate},
+        events::MessageEventBus,
+        ports::{
+            AttachmentScanner, AttachmentStorage, LinkUnfurler, MessageStore, ThumbnailGenerator,
+        },
+    },
+};
 
 #[derive(Clone)]
 pub struct Service<S, H>
 where
-    S: MessageRepository,
+    S: MessageStore,
     H: HealthRepository,
 {
     pub(crate) message_repository: S,
     pub(crate) health_repository: H,
+    pub(crate) events: MessageEventBus,
+    pub(crate) health_probes: Vec<Arc<dyn HealthProbe>>,
+    /// Attachment storage quota of channels without a custom one, `None` when unlimited
+    pub(crate) default_storage_quota: Option<u64>,
+    /// Deletions by one author reported to trust & safety, `None` to report none
+    pub(crate) mass_deletion_threshold: Option<AbuseThreshold>,
+    /// Pinned messages a channel may have, `None` when unlimited
+    pub(crate) pin_limit: Option<u64>,
+    /// Texts of the moderation reasons by code; any code is accepted when empty
+    pub(crate) moderation_reasons: HashMap<String, String>,
+    /// Cold storage receiving expired messages before they are deleted, `None` to delete them outright
+    pub(crate) archiver: Option<Arc<dyn MessageArchiver>>,
+    /// Storage clients upload attachments to, `None` when attachments cannot be posted
+    pub(crate) attachment_storage: Option<Arc<dyn AttachmentStorage>>,
+    pub(crate) attachment_limits: AttachmentLimits,
+    /// Malware scanner of posted attachments, `None` to leave them unscanned
+    pub(crate) attachment_scanner: Option<Arc<dyn AttachmentScanner>>,
+    /// Previews of image attachments, `None` to post them without one
+    pub(crate) thumbnail_generator: Option<Arc<dyn ThumbnailGenerator>>,
+    /// Previews of the links of posted messages, `None` to leave them without embeds
+    pub(crate) link_unfurler: Option<Arc<dyn LinkUnfurler>>,
+    pub(crate) link_preview_policy: LinkPreviewPolicy,
+    /// Members of channels, `None` to notify only the users mentioned by name
+    pub(crate) channel_membership: Option<Arc<dyn ChannelMembership>>,
+    /// Holders of roles, `None` to resolve role mentions from `channel_membership`
+    pub(crate) member_directory: Option<Arc<dyn MemberDirectory>>,
+    /// Recipients a mention lists at most before being broadcast to its
+    /// groups instead, `None` to always list them
+    pub(crate) mention_fanout_limit: Option<usize>,
 }
 
 impl<S, H> Service<S, H>
 where
-    S: MessageRepository,
+    S: MessageStore,
     H: HealthRepository,
 {
     pub fn new(message_repository: S, health_repository: H) -> Self {
         Self {
             message_repository,
             health_repository,
+            events: MessageEventBus::new(),
+            health_probes: Vec::new(),
+            default_storage_quota: None,
+            mass_deletion_threshold: None,
+            pin_limit: None,
+            moderation_reasons: HashMap::new(),
+            archiver: None,
+            attachment_storage: None,
+            attachment_limits: AttachmentLimits::default(),
+            attachment_scanner: None,
+            thumbnail_generator: None,
+            link_unfurler: None,
+            link_preview_policy: LinkPreviewPolicy::default(),
+            channel_membership: None,
+            member_directory: None,
+            mention_fanout_limit: None,
         }
     }
+
+    /// Reject attachments in channels storing `quota_bytes` or more, unless
+    /// they have a custom quota
+    pub fn with_default_storage_quota(mut self, quota_bytes: Option<u64>) -> Self {
+        self.default_storage_quota = quota_bytes;
+        self
+    }
+
+    /// Refuse to pin messages in channels with `limit` pinned messages already
+    pub fn with_pin_limit(mut self, limit: Option<u64>) -> Self {
+        self.pin_limit = limit;
+        self
+    }
+
+    /// Report authors deleting `threshold.count` of their messages within
+    /// `threshold.window` to trust & safety
+    pub fn with_mass_deletion_threshold(mut self, threshold: Option<AbuseThreshold>) -> Self {
+        self.mass_deletion_threshold = threshold;
+        self
+    }
+
+    /// Only accept moderation reasons with the code of one of `templates`,
+    /// whose text is used when the moderator gives none
+    pub fn with_moderation_reason_templates(
+        mut self,
+        templates: impl IntoIterator<Item = ModerationReasonTemplate>,
+    ) -> Self {
+        self.moderation_reasons = templates
+            .into_iter()
+            .map(|template| (template.code, template.text))
+            .collect();
+        self
+    }
+
+    /// Archive the messages past their retention with `archiver` before
+    /// deleting them; a batch that fails to be archived is kept
+    pub fn with_message_archiver(mut self, archiver: Arc<dyn MessageArchiver>) -> Self {
+        self.archiver = Some(archiver);
+        self
+    }
+
+    /// Let clients upload attachments to `storage` through presigned URLs
+    pub fn with_attachment_storage(mut self, storage: Arc<dyn AttachmentStorage>) -> Self {
+        self.attachment_storage = Some(storage);
+        self
+    }
+
+    /// Refuse attachments beyond `limits`, when presigned and again when posted
+    pub fn with_attachment_limits(mut self, limits: AttachmentLimits) -> Self {
+        self.attachment_limits = limits;
+        self
+    }
+
+    /// Scan the attachments of posted messages with `scanner`, quarantining
+    /// the messages with an infected one
+    pub fn with_attachment_scanner(mut self, scanner: Arc<dyn AttachmentScanner>) -> Self {
+        self.attachment_scanner = Some(scanner);
+        self
+    }
+
+    /// Store a preview from `generator` on the image attachments of new
+    /// messages; images it fails to read are posted without one
+    pub fn with_thumbnail_generator(mut self, generator: Arc<dyn ThumbnailGenerator>) -> Self {
+        self.thumbnail_generator = Some(generator);
+        self
+    }
+
+    /// Store the previews of the links of posted messages from `unfurler`,
+    /// for the links to domains `policy` allows
+    pub fn with_link_unfurler(
+        mut self,
+        unfurler: Arc<dyn LinkUnfurler>,
+        policy: LinkPreviewPolicy,
+    ) -> Self {
+        self.link_unfurler = Some(unfurler);
+        self.link_preview_policy = policy;
+        self
+    }
+
+    /// Resolve `@everyone` and role mentions into the members of the channel
+    /// recorded in `membership`
+    pub fn with_channel_membership(mut self, membership: Arc<dyn ChannelMembership>) -> Self {
+        self.channel_membership = Some(membership);
+        self
+    }
+
+    /// Resolve role mentions into the members `directory` lists, rather than
+    /// from the snapshot of channel members
+    pub fn with_member_directory(mut self, directory: Arc<dyn MemberDirectory>) -> Self {
+        self.member_directory = Some(directory);
+        self
+    }
+
+    /// Broadcast mentions resolving to more than `limit` recipients to the
+    /// groups they mention, rather than listing every recipient
+    pub fn with_mention_fanout_limit(mut self, limit: Option<usize>) -> Self {
+        self.mention_fanout_limit = limit;
+        self
+    }
+
+    /// Report the service as degraded whenever `probe` is not healthy
+    pub fn with_health_probe(mut self, probe: Arc<dyn HealthProbe>) -> Self {
+        self.health_probes.push(probe);
+        self
+    }
+
+    /// Bus on which message create/update/delete events are published
+    pub fn events(&self) -> &MessageEventBus {
+        &self.events
+    }
 }
@@ -1,21 +1,67 @@
 use crate::domain::common::CoreError;
 
-pub struct IsHealthy(bool);
+/// Health of the service or of one of its dependencies
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IsHealthy {
+    Healthy,
+    /// Serving, with some optional dependency down
+    Degraded,
+    Unhealthy,
+}
 
 impl IsHealthy {
     pub fn new(is_healthy: bool) -> Self {
-        Self(is_healthy)
+        if is_healthy {
+            IsHealthy::Healthy
+        } else {
+            IsHealthy::Unhealthy
+        }
     }
 
+    /// Whether requests can still be served
     pub fn value(&self) -> bool {
-        self.0
+        *self != IsHealthy::Unhealthy
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IsHealthy::Healthy => "healthy",
+            IsHealthy::Degraded => "degraded",
+            IsHealthy::Unhealthy => "unhealthy",
+        }
     }
 
     pub fn to_result(&self) -> Result<Self, CoreError> {
         if self.value() {
-            Ok(IsHealthy(self.0))
+            Ok(*self)
         } else {
             Err(CoreError::Unhealthy)
         }
     }
 }
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ComponentHealth {
+    pub name: String,
+    pub status: IsHealthy,
+}
+
+/// Outcome of a health check: the overall status and the optional dependencies behind it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HealthReport {
+    pub status: IsHealthy,
+    pub components: Vec<ComponentHealth>,
+}
+
+impl HealthReport {
+    /// Degraded as soon as one component is not healthy
+    pub fn from_components(components: Vec<ComponentHealth>) -> Self {
+        let status = if components.iter().all(|c| c.status == IsHealthy::Healthy) {
+            IsHealthy::Healthy
+        } else {
+            IsHealthy::Degraded
+        };
+
+        Self { status, components }
+    }
+}
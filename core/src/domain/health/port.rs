@@ -1,13 +1,26 @@
-use crate::domain::{common::CoreError, health::entities::IsHealthy};
+use crate::domain::{
+    common::CoreError,
+    health::entities::{HealthReport, IsHealthy},
+};
 use std::future::Future;
 
 pub trait HealthRepository: Send + Sync {
     fn ping(&self) -> impl Future<Output = IsHealthy> + Send;
 }
 
+/// A dependency the service can run without (search backend, message broker...);
+/// when it is down the service is reported as degraded instead of unhealthy
+#[async_trait::async_trait]
+pub trait HealthProbe: Send + Sync {
+    fn name(&self) -> &str;
+    async fn probe(&self) -> IsHealthy;
+}
+
 pub trait HealthService: Send + Sync {
-    fn check_health(&self) -> impl Future<Output = Result<IsHealthy, CoreError>> + Send;
+    /// Fails with [`CoreError::Unhealthy`] when the database is unreachable
+    fn check_health(&self) -> impl Future<Output = Result<HealthReport, CoreError>> + Send;
 }
+#[derive(Clone)]
 pub struct MockHealthRepository;
 
 impl MockHealthRepository {
@@ -16,6 +29,12 @@ impl MockHealthRepository {
     }
 }
 
+impl Default for MockHealthRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl HealthRepository for MockHealthRepository {
     async fn ping(&self) -> IsHealthy {
         IsHealthy::new(true)
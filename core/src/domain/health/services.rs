@@ -1,18 +1,38 @@
+use std::time::Duration;
+
+use futures::future::join_all;
+
 use crate::domain::{
     common::{CoreError, services::Service},
     health::{
-        entities::IsHealthy,
+        entities::{ComponentHealth, HealthReport, IsHealthy},
         port::{HealthRepository, HealthService},
     },
-    message::ports::MessageRepository,
+    message::ports::MessageStore,
 };
 
+/// A probe answering later than this counts as down, so one stuck dependency
+/// can't time out the whole health check
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
 impl<S, H> HealthService for Service<S, H>
 where
-    S: MessageRepository,
+    S: MessageStore,
     H: HealthRepository,
 {
-    async fn check_health(&self) -> Result<IsHealthy, CoreError> {
-        self.health_repository.ping().await.to_result()
+    async fn check_health(&self) -> Result<HealthReport, CoreError> {
+        self.health_repository.ping().await.to_result()?;
+
+        let components = join_all(self.health_probes.iter().map(|probe| async move {
+            ComponentHealth {
+                name: probe.name().to_string(),
+                status: tokio::time::timeout(PROBE_TIMEOUT, probe.probe())
+                    .await
+                    .unwrap_or(IsHealthy::Unhealthy),
+            }
+        }))
+        .await;
+
+        Ok(HealthReport::from_components(components))
     }
 }
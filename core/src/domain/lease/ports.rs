@@ -0,0 +1,73 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::domain::common::CoreError;
+
+/// Port to a lock shared by every replica, used to run singleton background
+/// jobs on exactly one of them.
+///
+/// A lease expires unless its holder renews it within `ttl`, so a crashed
+/// replica releases its jobs to the others.
+#[async_trait::async_trait]
+pub trait LeaseLock: Send + Sync {
+    /// Acquire or renew the lease `name` for `ttl`; `false` while another
+    /// holder owns an unexpired lease
+    async fn try_acquire(&self, name: &str, ttl: Duration) -> Result<bool, CoreError>;
+
+    /// Give the lease up early; a no-op when held by someone else
+    async fn release(&self, name: &str) -> Result<(), CoreError>;
+}
+
+/// Leases held in memory, shared by the locks created with [`InMemoryLeaseLock::for_holder`]
+#[derive(Clone)]
+pub struct InMemoryLeaseLock {
+    holder: String,
+    leases: Arc<Mutex<HashMap<String, (String, Instant)>>>,
+}
+
+impl InMemoryLeaseLock {
+    pub fn new(holder: impl Into<String>) -> Self {
+        Self {
+            holder: holder.into(),
+            leases: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Lock of another holder competing for the same leases
+    pub fn for_holder(&self, holder: impl Into<String>) -> Self {
+        Self {
+            holder: holder.into(),
+            leases: self.leases.clone(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl LeaseLock for InMemoryLeaseLock {
+    async fn try_acquire(&self, name: &str, ttl: Duration) -> Result<bool, CoreError> {
+        let mut leases = self.leases.lock().unwrap();
+        let now = Instant::now();
+
+        match leases.get(name) {
+            Some((holder, expires_at)) if holder != &self.holder && *expires_at > now => Ok(false),
+            _ => {
+                leases.insert(name.to_string(), (self.holder.clone(), now + ttl));
+                Ok(true)
+            }
+        }
+    }
+
+    async fn release(&self, name: &str) -> Result<(), CoreError> {
+        let mut leases = self.leases.lock().unwrap();
+        if leases
+            .get(name)
+            .is_some_and(|(holder, _)| holder == &self.holder)
+        {
+            leases.remove(name);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,26 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LifecycleStage {
+    /// The replica listens for requests
+    Started,
+    /// The replica stopped accepting connections and drains the open ones
+    ShutdownInitiated,
+    /// Every request was answered; the replica exits
+    ShutdownCompleted,
+}
+
+/// Payload of `service.lifecycle` events, for platform dashboards to
+/// correlate deploys with error spikes across the replicas
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ServiceLifecycleEvent {
+    pub stage: LifecycleStage,
+    /// Replica the event is about, the lease holder ID of its singleton jobs
+    pub instance_id: String,
+    pub version: String,
+    /// Fingerprint of the configuration, changing whenever any setting does
+    pub config_hash: String,
+    pub occurred_at: DateTime<Utc>,
+}
@@ -0,0 +1,34 @@
+use std::sync::{Arc, Mutex};
+
+use crate::domain::{common::CoreError, lifecycle::entities::ServiceLifecycleEvent};
+
+/// Port to the broker the lifecycle of replicas is announced on
+#[async_trait::async_trait]
+pub trait LifecyclePublisher: Send + Sync {
+    async fn publish(&self, event: &ServiceLifecycleEvent) -> Result<(), CoreError>;
+}
+
+/// Lifecycle events kept in memory, for tests and local development
+#[derive(Clone, Default)]
+pub struct InMemoryLifecyclePublisher {
+    events: Arc<Mutex<Vec<ServiceLifecycleEvent>>>,
+}
+
+impl InMemoryLifecyclePublisher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Events published so far, in order
+    pub fn published(&self) -> Vec<ServiceLifecycleEvent> {
+        self.events.lock().unwrap().clone()
+    }
+}
+
+#[async_trait::async_trait]
+impl LifecyclePublisher for InMemoryLifecyclePublisher {
+    async fn publish(&self, event: &ServiceLifecycleEvent) -> Result<(), CoreError> {
+        self.events.lock().unwrap().push(event.clone());
+        Ok(())
+    }
+}
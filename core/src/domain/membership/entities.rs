@@ -0,0 +1,15 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::domain::message::entities::{ChannelId, UserId};
+
+/// A member of a channel, as last reported by the communities service
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChannelMember {
+    pub channel_id: ChannelId,
+    pub user_id: UserId,
+    pub role_ids: Vec<Uuid>,
+    /// When the communities service made the change
+    pub updated_at: DateTime<Utc>,
+}
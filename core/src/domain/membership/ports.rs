@@ -0,0 +1,144 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::domain::{
+    common::CoreError,
+    membership::entities::ChannelMember,
+    message::entities::{ChannelId, UserId},
+};
+
+/// Port to a snapshot of the members of channels, fed by the membership
+/// events of the communities service, so `@everyone` and role mentions are
+/// resolved into recipients without calling it for every message.
+///
+/// Events may arrive out of order: a change older than the last one applied
+/// to a member, removal included, is ignored.
+#[async_trait::async_trait]
+pub trait ChannelMembership: Send + Sync {
+    /// Record `member` with their roles; `false` when a newer change was
+    /// applied already
+    async fn upsert_member(&self, member: &ChannelMember) -> Result<bool, CoreError>;
+
+    /// Record that `user_id` left `channel_id` at `left_at`; `false` when a
+    /// newer change was applied already
+    async fn remove_member(
+        &self,
+        channel_id: &ChannelId,
+        user_id: &UserId,
+        left_at: DateTime<Utc>,
+    ) -> Result<bool, CoreError>;
+
+    /// Members of `channel_id` holding one of `role_ids`, or all of them
+    /// when `None`
+    async fn list_members(
+        &self,
+        channel_id: &ChannelId,
+        role_ids: Option<&[Uuid]>,
+    ) -> Result<Vec<UserId>, CoreError>;
+}
+
+/// Port to the authority on the roles of channel members, such as the
+/// members service, expanding role mentions into the users holding them.
+///
+/// Preferred over the [`ChannelMembership`] snapshot for roles, which lags
+/// behind the membership events it is fed by.
+#[async_trait::async_trait]
+pub trait MemberDirectory: Send + Sync {
+    /// Members of `channel_id` holding one of `role_ids`
+    async fn role_members(
+        &self,
+        channel_id: &ChannelId,
+        role_ids: &[Uuid],
+    ) -> Result<Vec<UserId>, CoreError>;
+}
+
+/// Last change of a member: their roles, or `None` once they left
+type MemberState = (Option<Vec<Uuid>>, DateTime<Utc>);
+
+/// Memberships held in memory, for tests and local development
+#[derive(Clone, Default)]
+pub struct InMemoryChannelMembership {
+    members: Arc<Mutex<HashMap<(ChannelId, UserId), MemberState>>>,
+}
+
+impl InMemoryChannelMembership {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn apply(
+        &self,
+        channel_id: ChannelId,
+        user_id: UserId,
+        role_ids: Option<Vec<Uuid>>,
+        at: DateTime<Utc>,
+    ) -> bool {
+        let mut members = self.members.lock().unwrap();
+        match members.get(&(channel_id, user_id)) {
+            Some((_, applied_at)) if *applied_at > at => false,
+            _ => {
+                members.insert((channel_id, user_id), (role_ids, at));
+                true
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl ChannelMembership for InMemoryChannelMembership {
+    async fn upsert_member(&self, member: &ChannelMember) -> Result<bool, CoreError> {
+        Ok(self.apply(
+            member.channel_id,
+            member.user_id,
+            Some(member.role_ids.clone()),
+            member.updated_at,
+        ))
+    }
+
+    async fn remove_member(
+        &self,
+        channel_id: &ChannelId,
+        user_id: &UserId,
+        left_at: DateTime<Utc>,
+    ) -> Result<bool, CoreError> {
+        Ok(self.apply(*channel_id, *user_id, None, left_at))
+    }
+
+    async fn list_members(
+        &self,
+        channel_id: &ChannelId,
+        role_ids: Option<&[Uuid]>,
+    ) -> Result<Vec<UserId>, CoreError> {
+        let members = self.members.lock().unwrap();
+        let mut listed: Vec<UserId> = members
+            .iter()
+            .filter(|((channel, _), _)| channel == channel_id)
+            .filter_map(|((_, user_id), (roles, _))| {
+                let roles = roles.as_ref()?;
+                let selected = match role_ids {
+                    Some(wanted) => roles.iter().any(|role| wanted.contains(role)),
+                    None => true,
+                };
+                selected.then_some(*user_id)
+            })
+            .collect();
+        listed.sort_by_key(|user_id| user_id.0);
+        Ok(listed)
+    }
+}
+
+#[async_trait::async_trait]
+impl MemberDirectory for InMemoryChannelMembership {
+    async fn role_members(
+        &self,
+        channel_id: &ChannelId,
+        role_ids: &[Uuid],
+    ) -> Result<Vec<UserId>, CoreError> {
+        self.list_members(channel_id, Some(role_ids)).await
+    }
+}
@@ -1,8 +1,12 @@
+use std::collections::HashSet;
+
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 use uuid::Uuid;
 
+use crate::domain::common::CoreError;
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
 pub struct MessageId(pub Uuid);
 
@@ -87,11 +91,297 @@ impl From<AttachmentId> for Uuid {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+pub struct UserId(pub Uuid);
+
+impl std::fmt::Display for UserId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<Uuid> for UserId {
+    fn from(uuid: Uuid) -> Self {
+        UserId(uuid)
+    }
+}
+
+impl From<UserId> for Uuid {
+    fn from(user_id: UserId) -> Self {
+        user_id.0
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct Attachment {
     pub id: AttachmentId,
     pub name: String,
     pub url: String,
+    /// Size of the attached file in bytes, counted against the channel storage quota
+    #[serde(default)]
+    pub size: u64,
+    /// Dimensions and thumbnail of image attachments, when they could be read
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preview: Option<ImagePreview>,
+}
+
+impl Attachment {
+    /// Attachment known by its ID only, completed from its upload when the
+    /// message is created
+    pub fn reference(id: AttachmentId) -> Self {
+        Self {
+            id,
+            name: String::new(),
+            url: String::new(),
+            size: 0,
+            preview: None,
+        }
+    }
+}
+
+/// What clients need to lay out an image attachment before downloading it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct ImagePreview {
+    /// Width of the original image, in pixels
+    pub width: u32,
+    /// Height of the original image, in pixels
+    pub height: u32,
+    /// Downscaled copy of the image, `None` when only its dimensions are known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thumbnail_url: Option<String>,
+}
+
+/// Longest name of an attachment
+pub const MAX_ATTACHMENT_NAME_CHARS: usize = 255;
+
+/// How long a presigned upload URL stays valid
+pub const ATTACHMENT_UPLOAD_TTL_SECS: i64 = 15 * 60;
+
+/// Limits on the attachments of a message, checked before it is stored
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AttachmentLimits {
+    /// Attachments a message may carry, `None` when unlimited
+    pub max_count: Option<usize>,
+    /// Size of each attachment in bytes, `None` when unlimited
+    pub max_size_bytes: Option<u64>,
+    /// MIME types accepted, `type/*` accepting a whole type; any type when empty
+    pub allowed_content_types: Vec<String>,
+}
+
+impl AttachmentLimits {
+    /// Whether files of `content_type` may be attached; its parameters
+    /// (`; charset=...`) are ignored
+    pub fn allows_content_type(&self, content_type: &str) -> bool {
+        if self.allowed_content_types.is_empty() {
+            return true;
+        }
+        let essence = content_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+        let Some((kind, _)) = essence.split_once('/') else {
+            return false;
+        };
+
+        self.allowed_content_types.iter().any(|allowed| {
+            let allowed = allowed.trim().to_ascii_lowercase();
+            match allowed.strip_suffix("/*") {
+                Some(allowed_kind) => allowed_kind == kind,
+                None => allowed == essence,
+            }
+        })
+    }
+}
+
+/// An attachment file presigned for upload, posted with a message once uploaded
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AttachmentUpload {
+    pub id: AttachmentId,
+    pub channel_id: ChannelId,
+    /// Only the uploader may post the attachment
+    pub uploader_id: AuthorId,
+    pub name: String,
+    pub content_type: String,
+    /// Size announced by the uploader; the uploaded object is what counts
+    pub size: u64,
+    /// Key of the file in the attachment storage
+    pub object_key: String,
+    pub created_at: DateTime<Utc>,
+    /// When the upload URL stops being accepted
+    pub expires_at: DateTime<Utc>,
+    /// Message the attachment was posted with, `None` until then
+    pub message_id: Option<MessageId>,
+}
+
+/// Outcome of scanning an attachment file for malware
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScanVerdict {
+    Clean,
+    /// `signature` names the malware the scanner recognized
+    Infected { signature: String },
+}
+
+/// Moderator recorded in the `message.bulk_deleted` events of messages
+/// quarantined for an infected attachment
+pub const ATTACHMENT_SCANNER_ID: UserId = UserId(Uuid::from_u128(1));
+
+/// Moderation reason code of messages quarantined for an infected attachment
+pub const MALWARE_REASON_CODE: &str = "malware";
+
+#[derive(Debug, Clone)]
+pub struct PresignAttachmentInput {
+    pub channel_id: ChannelId,
+    pub uploader_id: AuthorId,
+    pub name: String,
+    pub content_type: String,
+    pub size: u64,
+}
+
+/// A registered upload and the URL its file is `PUT` to
+#[derive(Debug, Clone)]
+pub struct PresignedAttachment {
+    pub upload: AttachmentUpload,
+    pub upload_url: String,
+}
+
+/// Preview of a link found in the content of a message, read from the
+/// OpenGraph metadata of the page
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct Embed {
+    /// The link as written in the message
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub site_name: Option<String>,
+}
+
+/// Users and roles mentioned in the content of a message, written
+/// `<@{user_id}>` and `<@&{role_id}>`, and whether it mentions `@everyone`
+/// in the channel.
+///
+/// Tokens whose ID is not a hyphenated UUID are left as plain text. Each user
+/// and role is listed once, in the order first mentioned.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct Mentions {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub users: Vec<UserId>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub roles: Vec<Uuid>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub everyone: bool,
+}
+
+impl Mentions {
+    pub fn parse(content: &str) -> Self {
+        let mut mentions = Self {
+            everyone: content.split_whitespace().any(|word| {
+                word.trim_end_matches(['.', ',', ';', ':', '!', '?', ')'])
+                    == EVERYONE_MENTION
+            }),
+            ..Self::default()
+        };
+        let mut rest = content;
+        while let Some(start) = rest.find("<@") {
+            rest = &rest[start + "<@".len()..];
+            let Some(end) = rest.find('>') else {
+                break;
+            };
+            let (token, is_role) = match rest[..end].strip_prefix('&') {
+                Some(token) => (token, true),
+                None => (&rest[..end], false),
+            };
+            let Some(id) = parse_hyphenated_uuid(token) else {
+                continue;
+            };
+            if is_role {
+                if !mentions.roles.contains(&id) {
+                    mentions.roles.push(id);
+                }
+            } else if !mentions.users.contains(&UserId(id)) {
+                mentions.users.push(UserId(id));
+            }
+            rest = &rest[end + 1..];
+        }
+        mentions
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.users.is_empty() && self.roles.is_empty() && !self.everyone
+    }
+}
+
+/// Mention of every member of the channel
+pub const EVERYONE_MENTION: &str = "@everyone";
+
+fn parse_hyphenated_uuid(token: &str) -> Option<Uuid> {
+    if token.len() != 36 {
+        return None;
+    }
+    Uuid::try_parse(token).ok()
+}
+
+/// Most links previewed in a single message, the first ones written
+pub const MAX_EMBEDS_PER_MESSAGE: usize = 5;
+
+/// Domains whose links are previewed.
+///
+/// A domain also covers its subdomains. Links to a denied domain are never
+/// previewed; when `allowed_domains` is not empty, only links to one of them
+/// are.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LinkPreviewPolicy {
+    pub allowed_domains: Vec<String>,
+    pub denied_domains: Vec<String>,
+}
+
+impl LinkPreviewPolicy {
+    pub fn allows(&self, url: &url::Url) -> bool {
+        let Some(host) = url.host_str() else {
+            return false;
+        };
+        let host = host.trim_end_matches('.').to_ascii_lowercase();
+        let covers = |domain: &String| {
+            let domain = domain.trim_matches('.').to_ascii_lowercase();
+            host == domain || host.ends_with(&format!(".{domain}"))
+        };
+
+        if self.denied_domains.iter().any(covers) {
+            return false;
+        }
+        self.allowed_domains.is_empty() || self.allowed_domains.iter().any(covers)
+    }
+
+    /// The distinct `http(s)` links of `content` this policy allows, at most
+    /// [`MAX_EMBEDS_PER_MESSAGE`] of them in the order they are written
+    pub fn links_to_preview(&self, content: &str) -> Vec<String> {
+        let mut links: Vec<String> = Vec::new();
+        for word in content.split_whitespace() {
+            let word = word
+                .trim_start_matches(['<', '(', '[', '"', '\''])
+                .trim_end_matches(['>', ')', ']', '"', '\'', '.', ',', ';', ':', '!', '?']);
+            if !(word.starts_with("http://") || word.starts_with("https://")) {
+                continue;
+            }
+            let Ok(url) = url::Url::parse(word) else {
+                continue;
+            };
+            if !self.allows(&url) || links.iter().any(|link| link == word) {
+                continue;
+            }
+            links.push(word.to_string());
+            if links.len() == MAX_EMBEDS_PER_MESSAGE {
+                break;
+            }
+        }
+        links
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
@@ -104,9 +394,284 @@ pub struct Message {
     pub reply_to_message_id: Option<MessageId>,
     pub attachments: Vec<Attachment>,
     pub is_pinned: bool,
+    /// Aggregated reaction counts, computed on read
+    #[serde(default)]
+    pub reactions: Vec<ReactionCount>,
+    /// Incremented on every update, starting at 0 on creation
+    #[serde(default)]
+    pub revision: u64,
+    /// Position of the message in its channel: strictly increasing in the
+    /// order messages were stored, unlike `created_at` which ties and skews
+    /// across instances. Numbers may be skipped; 0 on messages stored before
+    /// sequences were introduced.
+    #[serde(default)]
+    pub sequence: u64,
+    /// Number of replies to this message
+    #[serde(default)]
+    pub reply_count: u64,
+    /// Creation date of the latest reply to this message
+    #[serde(default)]
+    pub last_reply_at: Option<DateTime<Utc>>,
+    /// Set on threads archived after staying idle, cleared by their next reply
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archived_at: Option<DateTime<Utc>>,
 
     pub created_at: DateTime<Utc>,
     pub updated_at: Option<DateTime<Utc>>,
+    /// Set on soft deleted messages, which only moderators can read back
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<DateTime<Utc>>,
+    /// Identifier chosen by the client that created the message, so retried
+    /// creations return it instead of posting a copy
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+    /// Previews of the links of the content, added in the background after
+    /// the message is created
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub embeds: Vec<Embed>,
+    /// Parsed from the content whenever the message is read or written,
+    /// rather than stored
+    #[serde(default, skip_serializing_if = "Mentions::is_empty")]
+    pub mentions: Mentions,
+    /// Set on ephemeral messages: past it, the message is no longer read and
+    /// is soon removed from storage
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// A single user's reaction to a message
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct Reaction {
+    pub message_id: MessageId,
+    pub user_id: UserId,
+    pub emoji: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Number of users who reacted to a message with a given emoji
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, ToSchema)]
+pub struct ReactionCount {
+    pub emoji: String,
+    pub count: u64,
+}
+
+impl ReactionCount {
+    /// Aggregate individual reactions into counts, most used first
+    pub fn aggregate<'a>(reactions: impl IntoIterator<Item = &'a Reaction>) -> Vec<ReactionCount> {
+        let mut counts: Vec<ReactionCount> = Vec::new();
+        for reaction in reactions {
+            match counts.iter_mut().find(|c| c.emoji == reaction.emoji) {
+                Some(count) => count.count += 1,
+                None => counts.push(ReactionCount {
+                    emoji: reaction.emoji.clone(),
+                    count: 1,
+                }),
+            }
+        }
+        counts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.emoji.cmp(&b.emoji)));
+        counts
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct AddReactionInput {
+    pub message_id: MessageId,
+    pub user_id: UserId,
+    pub emoji: String,
+}
+
+/// Attachment storage used by a channel
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, ToSchema)]
+pub struct ChannelStorage {
+    pub channel_id: ChannelId,
+    /// Cumulative size of the attachments posted in the channel
+    pub used_bytes: u64,
+    /// Bytes the channel may store, `None` when unlimited
+    pub quota_bytes: Option<u64>,
+    /// Whether `quota_bytes` was set for this channel instead of being the default quota
+    pub custom_quota: bool,
+}
+
+impl ChannelStorage {
+    /// Storage of a channel without attachments nor custom quota
+    pub fn empty(channel_id: ChannelId) -> Self {
+        Self {
+            channel_id,
+            used_bytes: 0,
+            quota_bytes: None,
+            custom_quota: false,
+        }
+    }
+
+    /// Apply `default_quota` unless the channel has a custom quota
+    pub fn with_default_quota(mut self, default_quota: Option<u64>) -> Self {
+        if !self.custom_quota {
+            self.quota_bytes = default_quota;
+        }
+        self
+    }
+
+    /// Whether the channel already uses all of its quota. The quota is soft:
+    /// the upload crossing it is accepted, the following ones are rejected.
+    pub fn is_exceeded(&self) -> bool {
+        self.quota_bytes
+            .is_some_and(|quota| self.used_bytes >= quota)
+    }
+}
+
+/// Most emoji a channel may allow as reactions
+pub const MAX_ALLOWED_REACTIONS: usize = 100;
+
+/// Settings of a channel, the defaults applying until they are changed
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ChannelSettings {
+    pub channel_id: ChannelId,
+    /// Emoji members may react with, in display order; any emoji when `None`
+    pub allowed_reactions: Option<Vec<String>>,
+    /// Seconds messages are kept before being deleted, `0` keeping them
+    /// forever; the default retention applies when `None`
+    pub retention_secs: Option<u64>,
+}
+
+impl ChannelSettings {
+    /// Settings of a channel never changed
+    pub fn defaults(channel_id: ChannelId) -> Self {
+        Self {
+            channel_id,
+            allowed_reactions: None,
+            retention_secs: None,
+        }
+    }
+
+    /// Whether members may react with `emoji` in the channel
+    pub fn allows_reaction(&self, emoji: &str) -> bool {
+        self.allowed_reactions
+            .as_ref()
+            .is_none_or(|allowed| allowed.iter().any(|allowed| allowed == emoji))
+    }
+}
+
+/// Catch-up summary of the activity of a channel since a given instant
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ChannelDigest {
+    pub channel_id: ChannelId,
+    pub since: DateTime<Utc>,
+    /// Messages posted since `since`, replies included
+    pub message_count: u64,
+    /// Messages posted since `since` with the most reactions, most reacted first
+    pub top_reacted: Vec<Message>,
+    /// Messages pinned since `since`, latest pin first
+    pub new_pins: Vec<Message>,
+    /// Messages replied to since `since`, latest reply first
+    pub active_threads: Vec<Message>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+pub struct LegalHoldId(pub Uuid);
+
+impl std::fmt::Display for LegalHoldId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<Uuid> for LegalHoldId {
+    fn from(uuid: Uuid) -> Self {
+        LegalHoldId(uuid)
+    }
+}
+
+/// Data preserved by a legal hold
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum LegalHoldScope {
+    /// Every message of the channel
+    Channel(ChannelId),
+    /// Every message written by the user, in any channel
+    User(UserId),
+}
+
+/// Preservation order placed for an investigation: held messages are skipped
+/// by deletion sweeps until the hold is released
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct LegalHold {
+    pub id: LegalHoldId,
+    pub scope: LegalHoldScope,
+    /// Case or matter the hold was placed for, recorded when data is kept
+    pub reference: String,
+    pub placed_by: UserId,
+    pub placed_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PlaceLegalHoldInput {
+    pub scope: LegalHoldScope,
+    pub reference: String,
+    pub placed_by: UserId,
+}
+
+/// Writes to a channel suspended while a migration or an import runs on it;
+/// the lock lapses at `expires_at` if it is never released
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ChannelWriteLock {
+    pub channel_id: ChannelId,
+    /// Why writes are suspended, e.g. the name of the migration
+    pub reason: String,
+    pub locked_by: UserId,
+    pub locked_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl ChannelWriteLock {
+    /// Seconds until the lock lapses, at least 1, for clients to retry after
+    pub fn retry_after_secs(&self, now: DateTime<Utc>) -> u64 {
+        (self.expires_at - now).num_seconds().max(1) as u64
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LockChannelWritesInput {
+    pub channel_id: ChannelId,
+    pub reason: String,
+    pub locked_by: UserId,
+    /// How long the lock holds unless released earlier
+    pub duration: chrono::Duration,
+}
+
+/// Longest a channel write lock may be taken for
+pub const MAX_CHANNEL_WRITE_LOCK_SECS: i64 = 24 * 60 * 60;
+
+/// Outcome of the purge of the messages of a deleted channel
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChannelPurge {
+    /// Messages soft deleted
+    pub deleted: u64,
+    /// References of the legal holds that kept messages out of the purge
+    pub held_by: Vec<String>,
+}
+
+/// Outcome of the erasure of the messages of a user
+#[derive(Debug, Clone, PartialEq)]
+pub struct UserErasure {
+    /// Messages anonymized, including those soft deleted before
+    pub erased: u64,
+    /// Channels the erased messages were posted in
+    pub channels: Vec<ChannelId>,
+    /// References of the legal holds that kept messages out of the erasure
+    pub held_by: Vec<String>,
+}
+
+/// Content a message held before an edit replaced it, kept so moderators can
+/// audit edits
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct MessageRevision {
+    pub message_id: MessageId,
+    /// Revision of the message while it held this content
+    pub revision: u64,
+    pub content: String,
+    /// When this content was written, at creation or by an earlier edit
+    pub written_at: DateTime<Utc>,
+    /// When the edit replacing this content was made
+    pub replaced_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
@@ -117,6 +682,99 @@ pub struct InsertMessageInput {
     pub content: String,
     pub reply_to_message_id: Option<MessageId>,
     pub attachments: Vec<Attachment>,
+    /// Deduplicates the creations of an author in a channel
+    pub nonce: Option<String>,
+    /// When the message disappears, for ephemeral messages
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// Longest nonce a client may create a message with
+pub const MAX_NONCE_LEN: usize = 64;
+
+/// A message written on another platform, imported into a channel with its
+/// original ID and timestamps
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ImportMessageInput {
+    pub id: MessageId,
+    pub author_id: AuthorId,
+    pub content: String,
+    pub reply_to_message_id: Option<MessageId>,
+    pub attachments: Vec<Attachment>,
+    pub is_pinned: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// Outcome of an import, each message being stored or rejected on its own
+#[derive(Debug, Clone, Default)]
+pub struct ImportedMessages {
+    /// Messages stored, in the order they were given
+    pub imported: Vec<MessageId>,
+    /// Position in the import and reason of the messages left out
+    pub rejected: Vec<(usize, CoreError)>,
+}
+
+impl Message {
+    /// Total size of the attachments, in bytes
+    pub fn attachments_size(&self) -> u64 {
+        self.attachments
+            .iter()
+            .map(|attachment| attachment.size)
+            .sum()
+    }
+
+    /// Whether the message disappeared at `now`
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+}
+
+impl InsertMessageInput {
+    /// Total size of the attachments, in bytes
+    pub fn attachments_size(&self) -> u64 {
+        self.attachments
+            .iter()
+            .map(|attachment| attachment.size)
+            .sum()
+    }
+}
+
+impl ImportMessageInput {
+    /// Total size of the attachments, in bytes
+    pub fn attachments_size(&self) -> u64 {
+        self.attachments
+            .iter()
+            .map(|attachment| attachment.size)
+            .sum()
+    }
+
+    /// The message as stored in `channel_id` at `sequence`, thread summary
+    /// and reactions left to the messages imported after it
+    pub fn into_message(self, channel_id: ChannelId, sequence: u64) -> Message {
+        let mentions = Mentions::parse(&self.content);
+        Message {
+            id: self.id,
+            channel_id,
+            author_id: self.author_id,
+            content: self.content,
+            reply_to_message_id: self.reply_to_message_id,
+            attachments: self.attachments,
+            is_pinned: self.is_pinned,
+            reactions: Vec::new(),
+            revision: 0,
+            sequence,
+            reply_count: 0,
+            last_reply_at: None,
+            archived_at: None,
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+            deleted_at: None,
+            nonce: None,
+            embeds: Vec::new(),
+            mentions,
+            expires_at: None,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
@@ -124,7 +782,15 @@ pub struct CreateMessageRequest {
     pub channel_id: ChannelId,
     pub content: String,
     pub reply_to_message_id: Option<MessageId>,
-    pub attachments: Vec<Attachment>,
+    /// Attachments uploaded through a presigned URL, by ID
+    pub attachments: Vec<AttachmentId>,
+    /// Client-generated identifier; creating a message again with the same
+    /// nonce in the channel returns the first one
+    #[serde(default)]
+    pub nonce: Option<String>,
+    /// When the message disappears; it is kept until deleted when `None`
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
 }
 
 impl CreateMessageRequest {
@@ -135,7 +801,9 @@ impl CreateMessageRequest {
             author_id,
             content: self.content,
             reply_to_message_id: self.reply_to_message_id,
-            attachments: self.attachments,
+            attachments: self.attachments.into_iter().map(Attachment::reference).collect(),
+            nonce: self.nonce,
+            expires_at: self.expires_at,
         }
     }
 }
@@ -145,6 +813,10 @@ pub struct UpdateMessageInput {
     pub id: MessageId,
     pub content: Option<String>,
     pub is_pinned: Option<bool>,
+    /// Only update the message while it is at this revision, so concurrent
+    /// editors cannot overwrite each other's changes
+    #[serde(default)]
+    pub expected_revision: Option<u64>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
@@ -159,6 +831,33 @@ impl UpdateMessageRequest {
             id,
             content: self.content,
             is_pinned: self.is_pinned,
+            expected_revision: None,
+        }
+    }
+}
+
+/// Payload of `message.created` events
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CreateMessageEvent {
+    pub id: MessageId,
+    pub channel_id: ChannelId,
+    pub author_id: AuthorId,
+    pub content: String,
+    pub reply_to_message_id: Option<MessageId>,
+    pub sequence: u64,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<&Message> for CreateMessageEvent {
+    fn from(message: &Message) -> Self {
+        Self {
+            id: message.id,
+            channel_id: message.channel_id,
+            author_id: message.author_id,
+            content: message.content.clone(),
+            reply_to_message_id: message.reply_to_message_id,
+            sequence: message.sequence,
+            created_at: message.created_at,
         }
     }
 }
@@ -168,9 +867,438 @@ pub struct UpdateMessageEvent {
     pub id: MessageId,
     pub content: String,
     pub is_pinned: bool,
+    /// Revision of the message after the update; consumers can drop any
+    /// event whose revision is not greater than the last one they applied
+    pub revision: u64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub embeds: Vec<Embed>,
+}
+
+impl From<&Message> for UpdateMessageEvent {
+    fn from(message: &Message) -> Self {
+        Self {
+            id: message.id,
+            content: message.content.clone(),
+            is_pinned: message.is_pinned,
+            revision: message.revision,
+            embeds: message.embeds.clone(),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct DeleteMessageEvent {
     pub id: MessageId,
+    pub channel_id: ChannelId,
+}
+
+/// Payload of `message.bulk_deleted` events, one per bulk deletion instead of
+/// one per message
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct MessagesBulkDeletedEvent {
+    pub channel_id: ChannelId,
+    pub ids: Vec<MessageId>,
+    pub deleted_by: UserId,
+    pub deleted_at: DateTime<Utc>,
+    /// Why a moderator deleted the messages, when they gave a reason
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<ModerationReason>,
+}
+
+/// Moderator recorded in the `message.bulk_deleted` events of messages
+/// deleted at the end of their retention period
+pub const RETENTION_SWEEPER_ID: UserId = UserId(Uuid::nil());
+
+/// Longest free text of a moderation reason
+pub const MAX_MODERATION_REASON_TEXT_CHARS: usize = 500;
+
+/// Why a moderator acted, shown to the users affected and kept for audits
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, ToSchema)]
+pub struct ModerationReason {
+    /// Machine readable reason, e.g. `spam`
+    pub code: String,
+    /// Explanation for the user, defaulting to the text of the code's template
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+/// Reason moderators can pick by its code, with the text shown by default
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModerationReasonTemplate {
+    pub code: String,
+    pub text: String,
+}
+
+impl std::str::FromStr for ModerationReasonTemplate {
+    type Err = String;
+
+    /// Parse a `code=text` template
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (code, text) = value
+            .split_once('=')
+            .ok_or_else(|| format!("expected `code=text`, found `{value}`"))?;
+        let code = code.trim();
+        if code.is_empty() {
+            return Err(format!("missing code in `{value}`"));
+        }
+
+        Ok(Self {
+            code: code.to_string(),
+            text: text.trim().to_string(),
+        })
+    }
+}
+
+/// Payload of `user.erased` events, for services holding copies of the
+/// messages of the user (search, notifications) to drop them as well
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct UserErasedEvent {
+    pub user_id: UserId,
+    pub message_ids: Vec<MessageId>,
+    pub erased_by: UserId,
+    pub erased_at: DateTime<Utc>,
+}
+
+/// Payload of `thread.archived` events, sent when a thread stayed idle long enough
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ThreadArchivedEvent {
+    /// Message the thread replies to
+    pub thread_id: MessageId,
+    pub channel_id: ChannelId,
+    pub archived_at: DateTime<Utc>,
+}
+
+/// Payload of `thread.unarchived` events, sent when a reply revives an archived thread
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ThreadUnarchivedEvent {
+    pub thread_id: MessageId,
+    pub channel_id: ChannelId,
+    pub unarchived_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    Reaction,
+    Reply,
+    /// A moderator deleted a message of the recipient
+    Moderation,
+    /// A message mentions the recipient, by name, role or `@everyone`
+    Mention,
+}
+
+/// Payload of `notification.requested` events, consumed by the notification service
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct NotificationRequestedEvent {
+    pub kind: NotificationKind,
+    pub recipient_id: UserId,
+    pub actor_id: UserId,
+    pub channel_id: ChannelId,
+    pub message_id: MessageId,
+    /// Set for reaction notifications
+    pub emoji: Option<String>,
+    /// Set for moderation notifications whose moderator gave a reason
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<ModerationReason>,
+    pub requested_at: DateTime<Utc>,
+}
+
+impl NotificationRequestedEvent {
+    /// Build the notification telling a message's author about a reaction.
+    ///
+    /// Returns `None` when the author reacted to their own message or muted
+    /// reaction notifications (for this message or globally).
+    pub fn for_reaction(message: &Message, reaction: &Reaction, muted: bool) -> Option<Self> {
+        let recipient_id = UserId(message.author_id.0);
+        if muted || recipient_id == reaction.user_id {
+            return None;
+        }
+
+        Some(Self {
+            kind: NotificationKind::Reaction,
+            recipient_id,
+            actor_id: reaction.user_id,
+            channel_id: message.channel_id,
+            message_id: message.id,
+            emoji: Some(reaction.emoji.clone()),
+            reason: None,
+            requested_at: reaction.created_at,
+        })
+    }
+
+    /// Build the notification telling a follower of a thread about a reply.
+    ///
+    /// Returns `None` when the follower wrote the reply.
+    pub fn for_reply(reply: &Message, recipient_id: UserId) -> Option<Self> {
+        let actor_id = UserId(reply.author_id.0);
+        if recipient_id == actor_id {
+            return None;
+        }
+
+        Some(Self {
+            kind: NotificationKind::Reply,
+            recipient_id,
+            actor_id,
+            channel_id: reply.channel_id,
+            message_id: reply.id,
+            emoji: None,
+            reason: None,
+            requested_at: reply.created_at,
+        })
+    }
+
+    /// Build the notification telling an author a moderator deleted their
+    /// message, `None` when they deleted it themselves
+    pub fn for_moderation(
+        deleted: &Message,
+        moderator_id: UserId,
+        reason: Option<ModerationReason>,
+        deleted_at: DateTime<Utc>,
+    ) -> Option<Self> {
+        let recipient_id = UserId(deleted.author_id.0);
+        if recipient_id == moderator_id {
+            return None;
+        }
+
+        Some(Self {
+            kind: NotificationKind::Moderation,
+            recipient_id,
+            actor_id: moderator_id,
+            channel_id: deleted.channel_id,
+            message_id: deleted.id,
+            emoji: None,
+            reason,
+            requested_at: deleted_at,
+        })
+    }
+}
+
+/// Payload of `notification.fanout_requested` events: the same notification
+/// for each of `recipient_ids`, resolved by this service so the notification
+/// service does not expand roles itself
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct NotificationFanoutEvent {
+    pub kind: NotificationKind,
+    pub recipient_ids: Vec<UserId>,
+    pub actor_id: UserId,
+    pub channel_id: ChannelId,
+    pub message_id: MessageId,
+    pub requested_at: DateTime<Utc>,
+}
+
+impl NotificationFanoutEvent {
+    /// Most recipients of one event, keeping `@everyone` in large channels
+    /// well under the frame size of the broker
+    pub const MAX_RECIPIENTS: usize = 1000;
+
+    /// Build the events telling `recipients` that `message` mentions them,
+    /// leaving out its author and duplicates; empty when no one is left
+    pub fn for_mentions(
+        message: &Message,
+        recipients: impl IntoIterator<Item = UserId>,
+    ) -> Vec<Self> {
+        let actor_id = UserId(message.author_id.0);
+        let mut seen = HashSet::new();
+        let recipients: Vec<UserId> = recipients
+            .into_iter()
+            .filter(|recipient| *recipient != actor_id && seen.insert(*recipient))
+            .collect();
+
+        recipients
+            .chunks(Self::MAX_RECIPIENTS)
+            .map(|chunk| Self {
+                kind: NotificationKind::Mention,
+                recipient_ids: chunk.to_vec(),
+                actor_id,
+                channel_id: message.channel_id,
+                message_id: message.id,
+                requested_at: message.created_at,
+            })
+            .collect()
+    }
+}
+
+/// Payload of `notification.broadcast_requested` events, replacing the
+/// fan-out of notifications whose recipients are too many to list: every
+/// member of the channel for `everyone`, or the members holding one of
+/// `role_ids`, plus `user_ids`, left for the notification service to expand
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct NotificationBroadcastEvent {
+    pub kind: NotificationKind,
+    pub channel_id: ChannelId,
+    pub everyone: bool,
+    pub role_ids: Vec<Uuid>,
+    /// Users named individually, but the actor
+    pub user_ids: Vec<UserId>,
+    pub actor_id: UserId,
+    pub message_id: MessageId,
+    pub requested_at: DateTime<Utc>,
+}
+
+impl NotificationBroadcastEvent {
+    /// Build the event telling the groups and users `message` mentions that
+    /// it mentions them; its author is to be left out of the groups
+    pub fn for_mentions(message: &Message) -> Self {
+        let actor_id = UserId(message.author_id.0);
+        let mentions = &message.mentions;
+        Self {
+            kind: NotificationKind::Mention,
+            channel_id: message.channel_id,
+            everyone: mentions.everyone,
+            role_ids: mentions.roles.clone(),
+            user_ids: mentions.users.iter().copied().filter(|user| *user != actor_id).collect(),
+            actor_id,
+            message_id: message.id,
+            requested_at: message.created_at,
+        }
+    }
+}
+
+/// User, role or `@everyone` mentioned by a message
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MentionTarget {
+    User { user_id: UserId },
+    Role { role_id: Uuid },
+    Everyone,
+}
+
+/// Payload of `message.mentioned` events, one for each user, role and
+/// `@everyone` a new message mentions, so mention alerts are pushed without
+/// parsing its content
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct MessageMentionedEvent {
+    pub message_id: MessageId,
+    pub channel_id: ChannelId,
+    pub author_id: AuthorId,
+    pub target: MentionTarget,
+    pub mentioned_at: DateTime<Utc>,
+}
+
+impl MessageMentionedEvent {
+    /// Build the events of the mentions of `message`, in the order of its
+    /// `mentions`, but the author mentioning themselves
+    pub fn for_message(message: &Message) -> Vec<Self> {
+        let mentions = &message.mentions;
+        let users = mentions
+            .users
+            .iter()
+            .filter(|user| user.0 != message.author_id.0)
+            .map(|user_id| MentionTarget::User { user_id: *user_id });
+        let roles = mentions
+            .roles
+            .iter()
+            .map(|role_id| MentionTarget::Role { role_id: *role_id });
+        let everyone = mentions.everyone.then_some(MentionTarget::Everyone);
+
+        users
+            .chain(roles)
+            .chain(everyone)
+            .map(|target| Self {
+                message_id: message.id,
+                channel_id: message.channel_id,
+                author_id: message.author_id,
+                target,
+                mentioned_at: message.created_at,
+            })
+            .collect()
+    }
+}
+
+/// A user following a thread, notified of its replies
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ThreadFollow {
+    pub user_id: UserId,
+    /// Message the followed replies answer
+    pub thread_id: MessageId,
+    pub channel_id: ChannelId,
+    pub followed_at: DateTime<Utc>,
+}
+
+/// A message a user bookmarked, to find it back across channels
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SavedMessage {
+    pub user_id: UserId,
+    pub message_id: MessageId,
+    pub channel_id: ChannelId,
+    pub saved_at: DateTime<Utc>,
+}
+
+/// How threads behave for a user, the defaults applying until they are changed
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ThreadPreferences {
+    pub user_id: UserId,
+    /// Follow the threads the user replies to
+    pub auto_follow_on_reply: bool,
+}
+
+impl ThreadPreferences {
+    /// Preferences of a user who never changed them
+    pub fn defaults(user_id: UserId) -> Self {
+        Self {
+            user_id,
+            auto_follow_on_reply: true,
+        }
+    }
+}
+
+/// Last message of a channel a user read, for clients to separate the
+/// messages that are new to them
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ReadMarker {
+    pub user_id: UserId,
+    pub channel_id: ChannelId,
+    pub last_read_message_id: MessageId,
+    /// Sequence of the last read message; messages with a greater one are new
+    pub last_read_sequence: u64,
+    pub read_at: DateTime<Utc>,
+}
+
+/// Messages of a channel posted after the read marker of a user
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct UnreadCount {
+    pub channel_id: ChannelId,
+    pub last_read_sequence: u64,
+    pub unread_count: u64,
+}
+
+/// Behavior trust & safety tooling is told about once it crosses a threshold
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AbusePattern {
+    /// A user deleted many of their messages in a short time
+    MassDeletion,
+}
+
+/// Number of occurrences within a sliding window that trips an [`AbusePattern`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AbuseThreshold {
+    pub count: u64,
+    pub window: chrono::Duration,
+}
+
+/// Payload of `trust_safety.attachment_quarantined` events, emitted when a
+/// message is quarantined because one of its attachments is infected
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AttachmentQuarantinedEvent {
+    pub message_id: MessageId,
+    pub channel_id: ChannelId,
+    pub author_id: AuthorId,
+    pub attachment_id: AttachmentId,
+    /// Malware the scanner recognized in the attachment
+    pub signature: String,
+    pub quarantined_at: DateTime<Utc>,
+}
+
+/// Payload of `trust_safety.abuse_detected` events, consumed by trust & safety tooling
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AbuseDetectedEvent {
+    pub pattern: AbusePattern,
+    pub user_id: UserId,
+    /// Channel of the action that tripped the threshold
+    pub channel_id: ChannelId,
+    /// Occurrences within the window when the threshold tripped
+    pub count: u64,
+    pub window_seconds: u64,
+    pub detected_at: DateTime<Utc>,
 }
@@ -0,0 +1,55 @@
+use tokio::sync::broadcast;
+
+use crate::domain::message::entities::{ChannelId, Message, MessageId};
+
+/// Number of events a slow subscriber may lag behind before missing some
+const EVENT_BUS_CAPACITY: usize = 1024;
+
+/// Domain event emitted by the service layer after a message write succeeded
+#[derive(Debug, Clone)]
+pub enum MessageEvent {
+    Created(Message),
+    Updated(Message),
+    Deleted { id: MessageId, channel_id: ChannelId },
+}
+
+impl MessageEvent {
+    pub fn channel_id(&self) -> ChannelId {
+        match self {
+            MessageEvent::Created(message) | MessageEvent::Updated(message) => message.channel_id,
+            MessageEvent::Deleted { channel_id, .. } => *channel_id,
+        }
+    }
+}
+
+/// In-process broadcast bus of [`MessageEvent`]s.
+///
+/// Publishing never blocks nor fails: events are simply dropped when nobody
+/// is subscribed, and subscribers lagging more than the bus capacity skip
+/// the oldest events.
+#[derive(Clone)]
+pub struct MessageEventBus {
+    sender: broadcast::Sender<MessageEvent>,
+}
+
+impl MessageEventBus {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_BUS_CAPACITY);
+        Self { sender }
+    }
+
+    pub fn publish(&self, event: MessageEvent) {
+        // An error only means there is currently no subscriber
+        let _ = self.sender.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<MessageEvent> {
+        self.sender.subscribe()
+    }
+}
+
+impl Default for MessageEventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
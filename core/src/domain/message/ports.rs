@@ -1,21 +1,490 @@
-use std::sync::{Arc, Mutex};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use chrono::{DateTime, Utc};
+use futures::stream::{self, BoxStream};
 
 use crate::domain::{
-    common::{CoreError, GetPaginated, TotalPaginatedElements},
-    message::entities::{InsertMessageInput, ChannelId, Message, MessageId, UpdateMessageInput},
+    common::{
+        CoreError, Cursor, CursorPage, CursorRange, GetCursorPaginated, GetMessagesByCursor,
+        GetPaginated, TotalPaginatedElements,
+    },
+    message::entities::{
+        AbuseDetectedEvent, AddReactionInput, AttachmentId, AttachmentQuarantinedEvent,
+        AttachmentUpload, AuthorId, ChannelDigest, ChannelId, ChannelPurge, ChannelSettings,
+        ChannelStorage, ChannelWriteLock, Embed, ImagePreview, ImportMessageInput, ImportedMessages,
+        InsertMessageInput, LegalHold, LegalHoldId, LegalHoldScope, LockChannelWritesInput,
+        Mentions, Message, MessageId, MessageMentionedEvent, MessageRevision,
+        MessagesBulkDeletedEvent, ModerationReason, ModerationReasonTemplate,
+        NotificationBroadcastEvent, NotificationFanoutEvent, NotificationRequestedEvent,
+        PlaceLegalHoldInput, PresignAttachmentInput, PresignedAttachment, Reaction, ReactionCount,
+        ReadMarker, SavedMessage, ScanVerdict,
+        ThreadArchivedEvent, ThreadFollow, ThreadPreferences, ThreadUnarchivedEvent,
+        UnreadCount, UpdateMessageInput, UserErasedEvent, UserErasure, UserId,
+    },
 };
 
+/// Messages read lazily from the storage, one query batch at a time
+pub type MessageStream = BoxStream<'static, Result<Message, CoreError>>;
+
+/// Storage of the messages themselves, with their revisions and thread summaries
 #[async_trait::async_trait]
 pub trait MessageRepository: Send + Sync {
+    /// Store a new message, or return the one its author already created in
+    /// the channel with the same nonce, unless it was deleted or expired since
     async fn insert(&self, input: InsertMessageInput) -> Result<Message, CoreError>;
+    /// Store messages written elsewhere in a channel, with their IDs and
+    /// timestamps, numbered in the given order. Messages whose ID is taken are
+    /// skipped; returns the IDs stored.
+    async fn insert_many(
+        &self,
+        channel_id: &ChannelId,
+        inputs: Vec<ImportMessageInput>,
+    ) -> Result<Vec<MessageId>, CoreError>;
     async fn find_by_id(&self, id: &MessageId) -> Result<Option<Message>, CoreError>;
+    /// Messages among `ids` that exist and were not deleted, in no particular order
+    async fn find_by_ids(&self, ids: &[MessageId]) -> Result<Vec<Message>, CoreError>;
+    /// Like `find_by_id`, also returning a soft deleted message
+    async fn find_by_id_including_deleted(
+        &self,
+        id: &MessageId,
+    ) -> Result<Option<Message>, CoreError>;
     async fn list(
         &self,
         channel_id: &ChannelId,
         pagination: &GetPaginated,
     ) -> Result<(Vec<Message>, TotalPaginatedElements), CoreError>;
+    /// Messages of a channel including the soft deleted ones, newest first
+    async fn list_including_deleted(
+        &self,
+        channel_id: &ChannelId,
+        pagination: &GetPaginated,
+    ) -> Result<(Vec<Message>, TotalPaginatedElements), CoreError>;
+    /// Every message of a channel, oldest first, including the soft deleted
+    /// ones with `include_deleted`
+    async fn stream_channel(
+        &self,
+        channel_id: &ChannelId,
+        include_deleted: bool,
+    ) -> Result<MessageStream, CoreError>;
+    /// Every message of an author across channels, oldest first, including
+    /// the soft deleted ones with `include_deleted`
+    async fn stream_by_author(
+        &self,
+        author_id: &AuthorId,
+        include_deleted: bool,
+    ) -> Result<MessageStream, CoreError>;
+    /// Messages of a channel within `range`, newest first
+    async fn list_by_cursor(
+        &self,
+        channel_id: &ChannelId,
+        range: &CursorRange,
+        limit: usize,
+    ) -> Result<CursorPage<Message>, CoreError>;
+    /// Messages of an author in a channel, newest first
+    async fn list_by_author(
+        &self,
+        author_id: &AuthorId,
+        channel_id: &ChannelId,
+        pagination: &GetPaginated,
+    ) -> Result<(Vec<Message>, TotalPaginatedElements), CoreError>;
+    /// Pinned messages of a channel, most recently pinned first
+    async fn list_pinned(
+        &self,
+        channel_id: &ChannelId,
+        pagination: &GetPaginated,
+    ) -> Result<(Vec<Message>, TotalPaginatedElements), CoreError>;
+    async fn count_pinned(&self, channel_id: &ChannelId) -> Result<u64, CoreError>;
+    /// Messages of a channel with replies, most recent reply first, leaving
+    /// out archived threads unless `include_archived`
+    async fn list_threads(
+        &self,
+        channel_id: &ChannelId,
+        include_archived: bool,
+        pagination: &GetPaginated,
+    ) -> Result<(Vec<Message>, TotalPaginatedElements), CoreError>;
+    /// Archive at most `limit` threads whose latest reply is older than
+    /// `idle_before`, announcing each with a [`ThreadArchivedEvent`]; returns
+    /// the threads archived
+    async fn archive_idle_threads(
+        &self,
+        idle_before: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<Message>, CoreError>;
+    /// Unarchive a thread, announcing it with a [`ThreadUnarchivedEvent`];
+    /// returns whether the thread was archived
+    async fn unarchive_thread(&self, thread_id: &MessageId) -> Result<bool, CoreError>;
+    /// Apply an update, keeping the replaced content as a [`MessageRevision`]
+    /// when the content changes
     async fn update(&self, input: UpdateMessageInput) -> Result<Message, CoreError>;
+    /// Replace the embeds of a message still at `revision`, announcing it like
+    /// an update; returns `None` when the message was edited or deleted since
+    async fn set_embeds(
+        &self,
+        id: &MessageId,
+        revision: u64,
+        embeds: &[Embed],
+    ) -> Result<Option<Message>, CoreError>;
+    /// Previous contents of a message, oldest first, kept after soft deletes
+    async fn list_revisions(
+        &self,
+        message_id: &MessageId,
+    ) -> Result<Vec<MessageRevision>, CoreError>;
+    /// Soft delete a message: it is only readable through the `_including_deleted` methods
     async fn delete(&self, id: &MessageId) -> Result<(), CoreError>;
+    /// Soft delete the messages among `ids` that belong to `channel_id` at
+    /// once, announcing them with a single [`MessagesBulkDeletedEvent`]
+    /// carrying `reason`; returns the messages deleted
+    async fn delete_many(
+        &self,
+        channel_id: &ChannelId,
+        ids: &[MessageId],
+        deleted_by: &UserId,
+        reason: Option<&ModerationReason>,
+    ) -> Result<Vec<Message>, CoreError>;
+    /// Permanently remove a message, soft deleted or not, with its reactions
+    async fn hard_delete(&self, id: &MessageId) -> Result<(), CoreError>;
+    /// At most `limit` messages soft deleted before `before`, oldest deletions
+    /// first, leaving out those of `held_channels` and `held_authors`
+    async fn list_deleted_before(
+        &self,
+        before: DateTime<Utc>,
+        held_channels: &[ChannelId],
+        held_authors: &[AuthorId],
+        limit: usize,
+    ) -> Result<Vec<Message>, CoreError>;
+    /// At most `limit` messages whose expiry passed before `before`, deleted
+    /// or not, soonest expiries first, leaving out those of `held_channels`
+    /// and `held_authors`
+    async fn list_expired_before(
+        &self,
+        before: DateTime<Utc>,
+        held_channels: &[ChannelId],
+        held_authors: &[AuthorId],
+        limit: usize,
+    ) -> Result<Vec<Message>, CoreError>;
+    /// Soft delete every message of a channel except those written by
+    /// `held_authors`, returning how many were deleted
+    async fn delete_by_channel(
+        &self,
+        channel_id: &ChannelId,
+        held_authors: &[AuthorId],
+    ) -> Result<u64, CoreError>;
+    /// Anonymize every message of `author_id` outside `held_channels`, soft
+    /// deleted or not: their content with its mentions, attachments, link
+    /// previews, nonce and revisions are removed and they are soft deleted.
+    /// The reactions and mutes of the user go too.
+    ///
+    /// Newly deleted messages are announced with a [`MessagesBulkDeletedEvent`]
+    /// per channel and the erasure with a [`UserErasedEvent`]; returns the
+    /// messages as they were before the erasure
+    async fn delete_by_author(
+        &self,
+        author_id: &AuthorId,
+        held_channels: &[ChannelId],
+        erased_by: &UserId,
+    ) -> Result<Vec<Message>, CoreError>;
+    async fn list_replies(
+        &self,
+        message_id: &MessageId,
+        pagination: &GetCursorPaginated,
+    ) -> Result<CursorPage<Message>, CoreError>;
+    /// Messages of an author soft deleted since `since`
+    async fn count_deleted_by_author(
+        &self,
+        author_id: &AuthorId,
+        since: DateTime<Utc>,
+    ) -> Result<u64, CoreError>;
+    /// At most `limit` messages that are not deleted and were created before
+    /// `before`, oldest first, in `channel_id` or else in any channel but
+    /// `excluded_channels`, leaving out those of `held_authors`
+    async fn list_created_before(
+        &self,
+        channel_id: Option<&ChannelId>,
+        before: DateTime<Utc>,
+        excluded_channels: &[ChannelId],
+        held_authors: &[AuthorId],
+        limit: usize,
+    ) -> Result<Vec<Message>, CoreError>;
+    /// Activity of a channel since `since`, each list holding at most `limit` messages
+    async fn channel_digest(
+        &self,
+        channel_id: &ChannelId,
+        since: DateTime<Utc>,
+        limit: u32,
+    ) -> Result<ChannelDigest, CoreError>;
+    /// Newest message not deleted of each of `channel_ids` having one, in no
+    /// particular order
+    async fn last_messages(&self, channel_ids: &[ChannelId]) -> Result<Vec<Message>, CoreError>;
+    /// Channels with the most messages posted since `since`, busiest first
+    async fn most_active_channels(
+        &self,
+        since: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<ChannelId>, CoreError>;
+}
+
+/// Reactions to messages, and who muted the notifications of theirs
+#[async_trait::async_trait]
+pub trait ReactionRepository: Send + Sync {
+    async fn add_reaction(&self, input: AddReactionInput) -> Result<Reaction, CoreError>;
+    async fn remove_reaction(
+        &self,
+        message_id: &MessageId,
+        user_id: &UserId,
+        emoji: &str,
+    ) -> Result<(), CoreError>;
+    async fn list_reactions(&self, message_id: &MessageId) -> Result<Vec<Reaction>, CoreError>;
+    async fn list_reaction_users(
+        &self,
+        message_id: &MessageId,
+        emoji: &str,
+        pagination: &GetCursorPaginated,
+    ) -> Result<CursorPage<Reaction>, CoreError>;
+    /// Mute (or unmute) reaction notifications of a user, for one message or globally when `message_id` is `None`
+    async fn set_reaction_notifications_muted(
+        &self,
+        user_id: &UserId,
+        message_id: Option<&MessageId>,
+        muted: bool,
+    ) -> Result<(), CoreError>;
+    /// Whether the user muted reaction notifications for this message or globally
+    async fn reaction_notifications_muted(
+        &self,
+        user_id: &UserId,
+        message_id: &MessageId,
+    ) -> Result<bool, CoreError>;
+}
+
+/// Notifications handed to the notification service through the outbox
+#[async_trait::async_trait]
+pub trait NotificationPublisher: Send + Sync {
+    async fn request_notification(&self, event: &NotificationRequestedEvent) -> Result<(), CoreError>;
+    /// Request the same notification for every recipient of `event` at once
+    async fn request_notification_fanout(
+        &self,
+        event: &NotificationFanoutEvent,
+    ) -> Result<(), CoreError>;
+    /// Request a notification for the groups `event` names, expanded by the
+    /// notification service
+    async fn request_notification_broadcast(
+        &self,
+        event: &NotificationBroadcastEvent,
+    ) -> Result<(), CoreError>;
+    /// Publish a mention made by a new message
+    async fn publish_mention(&self, event: &MessageMentionedEvent) -> Result<(), CoreError>;
+}
+
+/// Reports published to trust & safety through the outbox
+#[async_trait::async_trait]
+pub trait TrustSafetyPublisher: Send + Sync {
+    /// Publish an abuse pattern on the trust & safety routing key
+    async fn report_abuse(&self, event: &AbuseDetectedEvent) -> Result<(), CoreError>;
+    /// Publish a message quarantined for an infected attachment on the trust
+    /// & safety routing key
+    async fn report_quarantine(&self, event: &AttachmentQuarantinedEvent)
+    -> Result<(), CoreError>;
+}
+
+/// Threads followed by users, and how they follow them
+#[async_trait::async_trait]
+pub trait ThreadFollowRepository: Send + Sync {
+    /// Store a follow, keeping the original one when the user already follows the thread
+    async fn follow_thread(&self, follow: &ThreadFollow) -> Result<ThreadFollow, CoreError>;
+    /// Remove a follow, if any
+    async fn unfollow_thread(&self, user_id: &UserId, thread_id: &MessageId)
+    -> Result<(), CoreError>;
+    async fn list_thread_followers(&self, thread_id: &MessageId) -> Result<Vec<UserId>, CoreError>;
+    /// Threads followed by a user, most recently followed first, leaving out
+    /// deleted ones
+    async fn list_followed_threads(
+        &self,
+        user_id: &UserId,
+        pagination: &GetPaginated,
+    ) -> Result<(Vec<Message>, TotalPaginatedElements), CoreError>;
+    /// Thread preferences of a user, the defaults when never changed
+    async fn thread_preferences(&self, user_id: &UserId) -> Result<ThreadPreferences, CoreError>;
+    async fn set_thread_preferences(&self, preferences: &ThreadPreferences) -> Result<(), CoreError>;
+    /// Remove every follow of a user and their thread preferences
+    async fn delete_thread_follows_by_user(&self, user_id: &UserId) -> Result<(), CoreError>;
+}
+
+/// Messages users saved for later
+#[async_trait::async_trait]
+pub trait SavedMessageRepository: Send + Sync {
+    /// Store a saved message, keeping the original one when the user saved it already
+    async fn save_message(&self, saved: &SavedMessage) -> Result<SavedMessage, CoreError>;
+    /// Remove a saved message, if any
+    async fn unsave_message(&self, user_id: &UserId, message_id: &MessageId)
+    -> Result<(), CoreError>;
+    /// Messages saved by a user, most recently saved first, leaving out
+    /// deleted ones
+    async fn list_saved_messages(
+        &self,
+        user_id: &UserId,
+        pagination: &GetPaginated,
+    ) -> Result<(Vec<Message>, TotalPaginatedElements), CoreError>;
+    /// Remove every message saved by a user
+    async fn delete_saved_messages_by_user(&self, user_id: &UserId) -> Result<(), CoreError>;
+}
+
+/// Where users stopped reading each channel
+#[async_trait::async_trait]
+pub trait ReadMarkerRepository: Send + Sync {
+    /// Read marker of a user in a channel, `None` until they read a message
+    async fn read_marker(
+        &self,
+        user_id: &UserId,
+        channel_id: &ChannelId,
+    ) -> Result<Option<ReadMarker>, CoreError>;
+    /// Store `marker` unless the user already read a later message of the
+    /// channel, returning the marker kept
+    async fn set_read_marker(&self, marker: &ReadMarker) -> Result<ReadMarker, CoreError>;
+    /// Read markers of a user in every channel they read
+    async fn read_markers_of_user(&self, user_id: &UserId) -> Result<Vec<ReadMarker>, CoreError>;
+    /// Remove the read markers of a user in every channel
+    async fn delete_read_markers_by_user(&self, user_id: &UserId) -> Result<(), CoreError>;
+    /// Messages not deleted with a greater sequence than each marker in its
+    /// channel; channels without any are left out
+    async fn count_unread(
+        &self,
+        markers: &[ReadMarker],
+    ) -> Result<HashMap<ChannelId, u64>, CoreError>;
+}
+
+/// Attachment storage used by channels, and their quotas
+#[async_trait::async_trait]
+pub trait ChannelStorageRepository: Send + Sync {
+    /// Attachment storage of a channel, with its custom quota if any
+    async fn channel_storage(&self, channel_id: &ChannelId) -> Result<ChannelStorage, CoreError>;
+    async fn add_channel_storage_usage(
+        &self,
+        channel_id: &ChannelId,
+        bytes: u64,
+    ) -> Result<(), CoreError>;
+    /// Give storage back to a channel, never going below zero
+    async fn release_channel_storage_usage(
+        &self,
+        channel_id: &ChannelId,
+        bytes: u64,
+    ) -> Result<(), CoreError>;
+    /// Set the custom quota of a channel, or remove it with `None`
+    async fn set_channel_storage_quota(
+        &self,
+        channel_id: &ChannelId,
+        quota_bytes: Option<u64>,
+    ) -> Result<ChannelStorage, CoreError>;
+}
+
+/// Settings of channels
+#[async_trait::async_trait]
+pub trait ChannelSettingsRepository: Send + Sync {
+    /// Settings of a channel, the defaults when never changed
+    async fn channel_settings(&self, channel_id: &ChannelId) -> Result<ChannelSettings, CoreError>;
+    /// Restrict the reactions of a channel to `allowed`, or allow any with `None`
+    async fn set_allowed_reactions(
+        &self,
+        channel_id: &ChannelId,
+        allowed: Option<Vec<String>>,
+    ) -> Result<ChannelSettings, CoreError>;
+    /// Keep the messages of a channel `retention_secs`, or as long as the
+    /// default retention with `None`
+    async fn set_channel_retention(
+        &self,
+        channel_id: &ChannelId,
+        retention_secs: Option<u64>,
+    ) -> Result<ChannelSettings, CoreError>;
+    /// Settings of the channels with a custom retention
+    async fn list_channel_retentions(&self) -> Result<Vec<ChannelSettings>, CoreError>;
+}
+
+/// Legal holds keeping messages from being deleted
+#[async_trait::async_trait]
+pub trait LegalHoldRepository: Send + Sync {
+    async fn place_legal_hold(&self, input: PlaceLegalHoldInput) -> Result<LegalHold, CoreError>;
+    async fn find_legal_hold(&self, id: &LegalHoldId) -> Result<Option<LegalHold>, CoreError>;
+    /// Active legal holds, oldest first, restricted to `scope` when given
+    async fn list_legal_holds(
+        &self,
+        scope: Option<&LegalHoldScope>,
+    ) -> Result<Vec<LegalHold>, CoreError>;
+    async fn release_legal_hold(&self, id: &LegalHoldId) -> Result<(), CoreError>;
+}
+
+/// Write locks of channels
+#[async_trait::async_trait]
+pub trait ChannelLockRepository: Send + Sync {
+    /// Store the write lock of a channel, replacing any previous one
+    async fn lock_channel_writes(&self, lock: &ChannelWriteLock) -> Result<(), CoreError>;
+    /// Write lock of a channel, expired or not
+    async fn find_channel_write_lock(
+        &self,
+        channel_id: &ChannelId,
+    ) -> Result<Option<ChannelWriteLock>, CoreError>;
+    /// Remove the write lock of a channel, if any
+    async fn unlock_channel_writes(&self, channel_id: &ChannelId) -> Result<(), CoreError>;
+}
+
+/// Attachments presigned for upload, until they are posted
+#[async_trait::async_trait]
+pub trait AttachmentRepository: Send + Sync {
+    /// Register an attachment presigned for upload
+    async fn insert_attachment_upload(&self, upload: &AttachmentUpload) -> Result<(), CoreError>;
+    /// Registered uploads among `ids`, posted or not, in no particular order
+    async fn find_attachment_uploads(
+        &self,
+        ids: &[AttachmentId],
+    ) -> Result<Vec<AttachmentUpload>, CoreError>;
+    /// Record that the uploads `ids` are posted with `message_id`, all or
+    /// none: when one was posted with another message already, none is
+    /// recorded and it fails with [`CoreError::AttachmentNotFound`]
+    async fn attach_uploads(
+        &self,
+        ids: &[AttachmentId],
+        message_id: &MessageId,
+    ) -> Result<(), CoreError>;
+    /// Undo [`AttachmentRepository::attach_uploads`], for a message that was not stored
+    async fn release_uploads(
+        &self,
+        ids: &[AttachmentId],
+        message_id: &MessageId,
+    ) -> Result<(), CoreError>;
+}
+
+/// Every storage port the message service relies on, so it is generic over
+/// one type; consumers needing less depend on the single ports they use
+pub trait MessageStore:
+    MessageRepository
+    + ReactionRepository
+    + NotificationPublisher
+    + TrustSafetyPublisher
+    + ThreadFollowRepository
+    + SavedMessageRepository
+    + ReadMarkerRepository
+    + ChannelStorageRepository
+    + ChannelSettingsRepository
+    + LegalHoldRepository
+    + ChannelLockRepository
+    + AttachmentRepository
+{
+}
+
+impl<T> MessageStore for T where
+    T: MessageRepository
+        + ReactionRepository
+        + NotificationPublisher
+        + TrustSafetyPublisher
+        + ThreadFollowRepository
+        + SavedMessageRepository
+        + ReadMarkerRepository
+        + ChannelStorageRepository
+        + ChannelSettingsRepository
+        + LegalHoldRepository
+        + ChannelLockRepository
+        + AttachmentRepository
+{
 }
 
 /// A service for managing message operations in the application.
@@ -47,10 +516,33 @@ pub trait MessageService: Send + Sync {
     /// # Returns
     ///
     /// Returns a `Future` that resolves to:
-    /// - `Ok(Message)` - The newly created message
+    /// - `Ok(Message)` - The newly created message, or the one the author
+    ///   already created in the channel with the same nonce
     /// - `Err(CoreError)` - If validation fails or repository operation fails
     async fn create_message(&self, input: InsertMessageInput) -> Result<Message, CoreError>;
 
+    /// Imports messages written on another platform into a channel, keeping
+    /// their IDs and timestamps, for migrations.
+    ///
+    /// Each message is validated on its own: its content must not be empty,
+    /// it cannot be created in the future nor updated before its creation, and
+    /// a reply must target a message of the channel, stored already or
+    /// imported earlier in `inputs`. Messages whose ID is taken are rejected,
+    /// so an interrupted import can be sent again. Imports go through channel
+    /// write locks, which are meant to keep other writes out meanwhile, and
+    /// publish no message events: search indexes must be rebuilt afterwards.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Future` that resolves to:
+    /// - `Ok(ImportedMessages)` - The messages stored, and the position in `inputs` and reason of those rejected
+    /// - `Err(CoreError)` - If repository operation fails
+    async fn import_messages(
+        &self,
+        channel_id: &ChannelId,
+        inputs: Vec<ImportMessageInput>,
+    ) -> Result<ImportedMessages, CoreError>;
+
     /// Retrieves a message by its unique identifier.
     ///
     /// This method performs the core business logic for fetching a message, including
@@ -70,6 +562,17 @@ pub trait MessageService: Send + Sync {
     /// - `Err(CoreError)` - Other errors such as database connectivity issues or authorization failures
     async fn get_message(&self, message_id: &MessageId) -> Result<Message, CoreError>;
 
+    /// Retrieves several messages at once, e.g. to resolve a reply chain or a
+    /// list of pins without one request per message.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Future` that resolves to:
+    /// - `Ok(Vec<Message>)` - The messages found, in the order of `message_ids`,
+    ///   once each; missing and deleted messages are left out
+    /// - `Err(CoreError)` - If repository operation fails
+    async fn get_messages(&self, message_ids: &[MessageId]) -> Result<Vec<Message>, CoreError>;
+
     /// Lists messages with pagination support.
     ///
     /// This method retrieves a paginated list of messages. The implementation should
@@ -90,136 +593,2476 @@ pub trait MessageService: Send + Sync {
         pagination: &GetPaginated,
     ) -> Result<(Vec<Message>, TotalPaginatedElements), CoreError>;
 
-    /// Updates an existing message with the provided input.
+    /// Retrieves a message even if it was soft deleted, for moderation review.
     ///
-    /// This method validates that the message exists and that the user has permission
-    /// to update it before applying the changes. Only non-None fields in the input
-    /// will be updated.
+    /// Callers must check the requester may moderate the channel first.
     ///
-    /// # Arguments
+    /// # Returns
     ///
-    /// * `input` - The message update input containing the message ID and fields to update
+    /// Returns a `Future` that resolves to:
+    /// - `Ok(Message)` - The message, with `deleted_at` set if it was deleted
+    /// - `Err(CoreError::MessageNotFound)` - No message was ever stored with the given ID
+    async fn get_message_including_deleted(
+        &self,
+        message_id: &MessageId,
+    ) -> Result<Message, CoreError>;
+
+    /// Lists the messages of a channel newest first, soft deleted ones
+    /// included, for moderation review.
+    ///
+    /// Callers must check the requester may moderate the channel first.
+    async fn list_messages_including_deleted(
+        &self,
+        channel_id: &ChannelId,
+        pagination: &GetPaginated,
+    ) -> Result<(Vec<Message>, TotalPaginatedElements), CoreError>;
+
+    /// Streams every message of a channel oldest first, for backups and
+    /// compliance exports, without holding the channel in memory.
+    ///
+    /// Soft deleted messages are only included with `include_deleted`; callers
+    /// must then check the requester may moderate the channel first.
     ///
     /// # Returns
     ///
     /// Returns a `Future` that resolves to:
-    /// - `Ok(Message)` - The updated message
-    /// - `Err(CoreError::MessageNotFound)` - No message exists with the given ID
-    /// - `Err(CoreError)` - If validation fails or repository operation fails
-    async fn update_message(&self, input: UpdateMessageInput) -> Result<Message, CoreError>;
+    /// - `Ok(MessageStream)` - The messages, read from the storage as the stream is polled
+    /// - `Err(CoreError)` - If the query could not be started
+    async fn export_channel_messages(
+        &self,
+        channel_id: &ChannelId,
+        include_deleted: bool,
+    ) -> Result<MessageStream, CoreError>;
 
-    /// Deletes a message by its unique identifier.
+    /// Streams every message a user wrote, in any channel, oldest first, to
+    /// answer a data access request without holding them in memory.
     ///
-    /// This method validates that the message exists and that the user has permission
-    /// to delete it before removing it from the repository.
+    /// Soft deleted messages are only included with `include_deleted`, for
+    /// requests that legally cover them.
     ///
-    /// # Arguments
+    /// # Returns
     ///
-    /// * `message_id` - A reference to the unique identifier of the message to delete
+    /// Returns a `Future` that resolves to:
+    /// - `Ok(MessageStream)` - The messages, read from the storage as the stream is polled
+    /// - `Err(CoreError)` - If the query could not be started
+    async fn export_user_messages(
+        &self,
+        user_id: &UserId,
+        include_deleted: bool,
+    ) -> Result<MessageStream, CoreError>;
+
+    /// Lists the messages an author posted in a channel, newest first, so
+    /// moderators can review their recent activity.
     ///
     /// # Returns
     ///
     /// Returns a `Future` that resolves to:
-    /// - `Ok(())` - The message was successfully deleted
-    /// - `Err(CoreError::MessageNotFound)` - No message exists with the given ID
+    /// - `Ok((Vec<Message>, TotalPaginatedElements))` - A page of messages and the author's total in the channel
     /// - `Err(CoreError)` - If repository operation fails
-    async fn delete_message(&self, message_id: &MessageId) -> Result<(), CoreError>;
-}
-
-#[derive(Clone)]
-pub struct MockMessageRepository {
-    messages: Arc<Mutex<Vec<Message>>>,
-}
+    async fn list_author_messages(
+        &self,
+        author_id: &AuthorId,
+        channel_id: &ChannelId,
+        pagination: &GetPaginated,
+    ) -> Result<(Vec<Message>, TotalPaginatedElements), CoreError>;
 
-impl MockMessageRepository {
-    pub fn new() -> Self {
-        Self {
-            messages: Arc::new(Mutex::new(Vec::new())),
-        }
-    }
-}
+    /// Lists the pinned messages of a channel, most recently pinned first.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Future` that resolves to:
+    /// - `Ok((Vec<Message>, TotalPaginatedElements))` - A page of pinned messages and how many the channel has
+    /// - `Err(CoreError)` - If repository operation fails
+    async fn list_pinned_messages(
+        &self,
+        channel_id: &ChannelId,
+        pagination: &GetPaginated,
+    ) -> Result<(Vec<Message>, TotalPaginatedElements), CoreError>;
 
-#[async_trait::async_trait]
-impl MessageRepository for MockMessageRepository {
-    async fn find_by_id(&self, id: &MessageId) -> Result<Option<Message>, CoreError> {
-        let messages = self.messages.lock().unwrap();
+    /// Lists the threads of a channel, i.e. its messages with replies, most
+    /// recently active first.
+    ///
+    /// Threads idle long enough are archived and left out unless
+    /// `include_archived`; a new reply unarchives them.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Future` that resolves to:
+    /// - `Ok((Vec<Message>, TotalPaginatedElements))` - A page of threads and how many are listed in total
+    /// - `Err(CoreError)` - If repository operation fails
+    async fn list_threads(
+        &self,
+        channel_id: &ChannelId,
+        include_archived: bool,
+        pagination: &GetPaginated,
+    ) -> Result<(Vec<Message>, TotalPaginatedElements), CoreError>;
 
-        let message = messages.iter().find(|s| &s.id == id).cloned();
+    /// Makes a user follow a thread, to be notified of its replies.
+    ///
+    /// Following a thread twice is idempotent.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Future` that resolves to:
+    /// - `Ok(ThreadFollow)` - The (possibly pre-existing) follow
+    /// - `Err(CoreError::MessageNotFound)` - No message exists with the given ID
+    /// - `Err(CoreError)` - If repository operation fails
+    async fn follow_thread(
+        &self,
+        user_id: &UserId,
+        thread_id: &MessageId,
+    ) -> Result<ThreadFollow, CoreError>;
 
-        Ok(message)
-    }
+    /// Stops notifying a user of the replies to a thread; a no-op when the
+    /// user did not follow it.
+    async fn unfollow_thread(&self, user_id: &UserId, thread_id: &MessageId)
+    -> Result<(), CoreError>;
 
-    async fn list(
+    /// Lists the threads a user follows, most recently followed first.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Future` that resolves to:
+    /// - `Ok((Vec<Message>, TotalPaginatedElements))` - A page of threads and how many the user follows
+    /// - `Err(CoreError)` - If repository operation fails
+    async fn list_followed_threads(
         &self,
-        channel_id: &ChannelId,
+        user_id: &UserId,
         pagination: &GetPaginated,
-    ) -> Result<(Vec<Message>, TotalPaginatedElements), CoreError> {
-        let messages = self.messages.lock().unwrap();
+    ) -> Result<(Vec<Message>, TotalPaginatedElements), CoreError>;
 
-        // Filter messages by channel
-        let filtered: Vec<Message> = messages.iter().filter(|m| &m.channel_id == channel_id).cloned().collect();
-        let total = filtered.len() as u64;
+    /// Saves a message for a user, to find it back from any channel.
+    ///
+    /// Saving a message twice is idempotent.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Future` that resolves to:
+    /// - `Ok(SavedMessage)` - The (possibly pre-existing) saved message
+    /// - `Err(CoreError::MessageNotFound)` - No message exists with the given ID
+    /// - `Err(CoreError)` - If repository operation fails
+    async fn save_message(
+        &self,
+        user_id: &UserId,
+        message_id: &MessageId,
+    ) -> Result<SavedMessage, CoreError>;
 
-        let offset = ((pagination.page - 1) * pagination.limit) as usize;
-        let limit = pagination.limit as usize;
+    /// Removes a message from the saved messages of a user; a no-op when the
+    /// user did not save it.
+    async fn unsave_message(&self, user_id: &UserId, message_id: &MessageId)
+    -> Result<(), CoreError>;
 
-        let paginated_messages: Vec<Message> = filtered.into_iter().skip(offset).take(limit).collect();
+    /// Lists the messages a user saved, most recently saved first.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Future` that resolves to:
+    /// - `Ok((Vec<Message>, TotalPaginatedElements))` - A page of messages and how many the user saved
+    /// - `Err(CoreError)` - If repository operation fails
+    async fn list_saved_messages(
+        &self,
+        user_id: &UserId,
+        pagination: &GetPaginated,
+    ) -> Result<(Vec<Message>, TotalPaginatedElements), CoreError>;
 
-        Ok((paginated_messages, total))
-    }
+    /// Returns the thread preferences of a user.
+    async fn thread_preferences(&self, user_id: &UserId) -> Result<ThreadPreferences, CoreError>;
 
-    async fn insert(&self, input: InsertMessageInput) -> Result<Message, CoreError> {
-        let mut messages = self.messages.lock().unwrap();
+    /// Replaces the thread preferences of a user, e.g. to stop following the
+    /// threads they reply to.
+    async fn set_thread_preferences(
+        &self,
+        preferences: ThreadPreferences,
+    ) -> Result<ThreadPreferences, CoreError>;
 
-        let new_message = Message {
-            id: input.id,
-            channel_id: input.channel_id,
-            author_id: input.author_id,
-            content: input.content,
-            reply_to_message_id: input.reply_to_message_id,
-            attachments: input.attachments,
-            is_pinned: false,
+    /// Returns the last message of a channel a user read, or
+    /// `CoreError::ReadMarkerNotFound` when they read none yet.
+    async fn read_marker(
+        &self,
+        user_id: &UserId,
+        channel_id: &ChannelId,
+    ) -> Result<ReadMarker, CoreError>;
+
+    /// Marks a message of a channel as the last one a user read.
+    ///
+    /// Markers only move forward: marking an earlier message leaves the
+    /// marker unchanged, so devices catching up late do not bring back
+    /// messages read elsewhere.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Future` that resolves to:
+    /// - `Ok(ReadMarker)` - The marker of the user in the channel
+    /// - `Err(CoreError::MessageNotFound)` - If the message is not a message of the channel
+    /// - `Err(CoreError)` - If repository operation fails
+    async fn set_read_marker(
+        &self,
+        user_id: &UserId,
+        channel_id: &ChannelId,
+        message_id: &MessageId,
+    ) -> Result<ReadMarker, CoreError>;
+
+    /// Returns the read markers of a user in every channel they read.
+    async fn read_markers(&self, user_id: &UserId) -> Result<Vec<ReadMarker>, CoreError>;
+
+    /// Counts the messages posted after each read marker in its channel, in a
+    /// single aggregation.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Future` that resolves to:
+    /// - `Ok(Vec<UnreadCount>)` - One count per marker, in the same order
+    /// - `Err(CoreError)` - If repository operation fails
+    async fn unread_counts(&self, markers: &[ReadMarker]) -> Result<Vec<UnreadCount>, CoreError>;
+
+    /// Archives up to `limit` threads without a reply since `idle_before`,
+    /// publishing each as an update of the thread.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Future` that resolves to:
+    /// - `Ok(u64)` - The number of threads archived, below `limit` once none is left
+    /// - `Err(CoreError)` - If repository operation fails
+    async fn archive_idle_threads(
+        &self,
+        idle_before: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<u64, CoreError>;
+
+    /// Updates an existing message with the provided input.
+    ///
+    /// This method validates that the message exists and that the user has permission
+    /// to update it before applying the changes. Only non-None fields in the input
+    /// will be updated.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The message update input containing the message ID and fields to update
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Future` that resolves to:
+    /// - `Ok(Message)` - The updated message
+    /// - `Err(CoreError::MessageNotFound)` - No message exists with the given ID
+    /// - `Err(CoreError::MessageRevisionMismatch)` - `expected_revision` is set and stale
+    /// - `Err(CoreError)` - If validation fails or repository operation fails
+    async fn update_message(&self, input: UpdateMessageInput) -> Result<Message, CoreError>;
+
+    /// Lists the previous contents of a message, so moderators can audit its
+    /// edits. Soft deleted messages keep their history until purged.
+    ///
+    /// Callers must check the requester may moderate the channel first.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Future` that resolves to:
+    /// - `Ok(Vec<MessageRevision>)` - Replaced contents, oldest first; empty if never edited
+    /// - `Err(CoreError::MessageNotFound)` - No message was ever stored with the given ID
+    /// - `Err(CoreError)` - If repository operation fails
+    async fn get_message_history(
+        &self,
+        message_id: &MessageId,
+    ) -> Result<Vec<MessageRevision>, CoreError>;
+
+    /// Deletes a message by its unique identifier.
+    ///
+    /// This method validates that the message exists and that the user has permission
+    /// to delete it before removing it from the repository.
+    ///
+    /// # Arguments
+    ///
+    /// * `message_id` - A reference to the unique identifier of the message to delete
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Future` that resolves to:
+    /// - `Ok(())` - The message was successfully deleted
+    /// - `Err(CoreError::MessageNotFound)` - No message exists with the given ID
+    /// - `Err(CoreError)` - If repository operation fails
+    async fn delete_message(&self, message_id: &MessageId) -> Result<(), CoreError>;
+
+    /// Deletes several messages of a channel at once, for moderators.
+    ///
+    /// IDs of messages that do not exist, were deleted already or belong to
+    /// another channel are skipped. The authors of the deleted messages are
+    /// notified, with the `reason` when the moderator gave one.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Future` that resolves to:
+    /// - `Ok(Vec<MessageId>)` - The messages deleted
+    /// - `Err(CoreError::InvalidModerationReason)` - The reason has no code or too long a text
+    /// - `Err(CoreError::UnknownModerationReason)` - The reason code has no template
+    /// - `Err(CoreError::ChannelWriteLocked)` - Writes to the channel are locked
+    /// - `Err(CoreError)` - If repository operation fails
+    async fn bulk_delete_messages(
+        &self,
+        channel_id: &ChannelId,
+        message_ids: &[MessageId],
+        deleted_by: &UserId,
+        reason: Option<ModerationReason>,
+    ) -> Result<Vec<MessageId>, CoreError>;
+
+    /// Returns the moderation reasons moderators pick from, empty when any
+    /// reason code is accepted.
+    fn moderation_reason_templates(&self) -> Vec<ModerationReasonTemplate>;
+
+    /// Permanently removes a message, soft deleted or not, for administrators.
+    ///
+    /// Unlike [`MessageService::delete_message`] nothing is left for
+    /// moderators to review, so messages under a legal hold are refused.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Future` that resolves to:
+    /// - `Ok(())` - The message was permanently removed
+    /// - `Err(CoreError::MessageNotFound)` - No message exists with the given ID, deleted or not
+    /// - `Err(CoreError::MessageUnderLegalHold)` - A hold covers the message's channel or author
+    /// - `Err(CoreError)` - If repository operation fails
+    async fn hard_delete_message(&self, message_id: &MessageId) -> Result<(), CoreError>;
+
+    /// Permanently removes up to `limit` messages soft deleted before
+    /// `deleted_before`, giving their attachment storage back to their channel.
+    ///
+    /// Messages under a legal hold are kept, however long ago they were deleted.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Future` that resolves to:
+    /// - `Ok(u64)` - The number of messages removed, below `limit` once none is left
+    /// - `Err(CoreError)` - If repository operation fails
+    async fn purge_deleted_messages(
+        &self,
+        deleted_before: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<u64, CoreError>;
+
+    /// Permanently removes up to `limit` messages whose expiry passed before
+    /// `expired_before`, like [`hard_delete_message`](Self::hard_delete_message).
+    ///
+    /// Messages under a legal hold are kept, however long ago they expired.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Future` that resolves to:
+    /// - `Ok(u64)` - The number of messages removed, below `limit` once none is left
+    /// - `Err(CoreError)` - If repository operation fails
+    async fn purge_expired_messages(
+        &self,
+        expired_before: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<u64, CoreError>;
+
+    /// Deletes every message of a channel, once the channel itself was deleted.
+    ///
+    /// Messages under a legal hold are kept: nothing is deleted while the
+    /// channel is held, and messages of held users are skipped. Releasing a
+    /// hold afterwards does not delete them retroactively.
+    /// Deleting the messages of a channel twice is a no-op.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Future` that resolves to:
+    /// - `Ok(ChannelPurge)` - The number of messages deleted and the references of the holds that kept some
+    /// - `Err(CoreError)` - If repository operation fails
+    async fn delete_channel_messages(
+        &self,
+        channel_id: &ChannelId,
+    ) -> Result<ChannelPurge, CoreError>;
+
+    /// Erases every message written by a user, to honor a right to be
+    /// forgotten request: messages are anonymized and soft deleted, their
+    /// attachments removed, and the reactions, thread follows, thread
+    /// preferences, saved messages and read markers of the user deleted.
+    /// Their archived messages are removed from the archive.
+    ///
+    /// Messages under a legal hold are kept: nothing is erased while the
+    /// user is held, and messages of held channels are skipped. Channel write
+    /// locks do not delay an erasure. Erasing a user twice erases nothing more.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Future` that resolves to:
+    /// - `Ok(UserErasure)` - The number of messages erased and the references of the holds that kept some
+    /// - `Err(CoreError)` - If repository operation fails
+    async fn erase_user(
+        &self,
+        user_id: &UserId,
+        erased_by: &UserId,
+    ) -> Result<UserErasure, CoreError>;
+
+    /// Adds a reaction from a user to a message.
+    ///
+    /// Reacting twice with the same emoji is idempotent.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Future` that resolves to:
+    /// - `Ok(Reaction)` - The (possibly pre-existing) reaction
+    /// - `Err(CoreError::InvalidReaction)` - The emoji is empty or too long
+    /// - `Err(CoreError::MessageNotFound)` - No message exists with the given ID
+    async fn add_reaction(&self, input: AddReactionInput) -> Result<Reaction, CoreError>;
+
+    /// Removes a user's reaction from a message.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Future` that resolves to:
+    /// - `Ok(())` - The reaction was removed
+    /// - `Err(CoreError::ReactionNotFound)` - The user had not reacted with this emoji
+    async fn remove_reaction(
+        &self,
+        message_id: &MessageId,
+        user_id: &UserId,
+        emoji: &str,
+    ) -> Result<(), CoreError>;
+
+    /// Lists every individual reaction on a message.
+    async fn list_reactions(&self, message_id: &MessageId) -> Result<Vec<Reaction>, CoreError>;
+
+    /// Lists the users who reacted to a message with a given emoji, oldest first.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Future` that resolves to:
+    /// - `Ok(CursorPage<Reaction>)` - A page of reactions and the cursor of the next page
+    /// - `Err(CoreError::MessageNotFound)` - No message exists with the given ID
+    /// - `Err(CoreError::InvalidCursor)` - The cursor could not be decoded
+    async fn list_reaction_users(
+        &self,
+        message_id: &MessageId,
+        emoji: &str,
+        pagination: &GetCursorPaginated,
+    ) -> Result<CursorPage<Reaction>, CoreError>;
+
+    /// Lists the messages of a channel, newest first.
+    ///
+    /// Unlike [`MessageService::list_messages`], pages don't shift when new
+    /// messages are posted while paginating, and deep pages stay cheap.
+    /// The listing can be bounded by messages or instants with `before` and
+    /// `after`; the next page cursor stays within these bounds.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Future` that resolves to:
+    /// - `Ok(CursorPage<Message>)` - A page of messages and the cursor of the next page
+    /// - `Err(CoreError::InvalidCursor)` - The cursor could not be decoded, or a
+    ///   bound is not a message of the channel
+    async fn list_messages_by_cursor(
+        &self,
+        channel_id: &ChannelId,
+        query: &GetMessagesByCursor,
+    ) -> Result<CursorPage<Message>, CoreError>;
+
+    /// Lists the replies to a message, oldest first.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Future` that resolves to:
+    /// - `Ok(CursorPage<Message>)` - A page of replies and the cursor of the next page
+    /// - `Err(CoreError::MessageNotFound)` - No message exists with the given ID
+    /// - `Err(CoreError::InvalidCursor)` - The cursor could not be decoded
+    async fn list_replies(
+        &self,
+        message_id: &MessageId,
+        pagination: &GetCursorPaginated,
+    ) -> Result<CursorPage<Message>, CoreError>;
+
+    /// Mutes reaction notifications of a user, for one message or globally when `message_id` is `None`.
+    ///
+    /// Muting twice is idempotent.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Future` that resolves to:
+    /// - `Ok(())` - Reactions no longer trigger notifications for this user
+    /// - `Err(CoreError::MessageNotFound)` - No message exists with the given ID
+    async fn mute_reaction_notifications(
+        &self,
+        user_id: &UserId,
+        message_id: Option<&MessageId>,
+    ) -> Result<(), CoreError>;
+
+    /// Reverts [`MessageService::mute_reaction_notifications`] for the same scope.
+    ///
+    /// Unmuting a message does not lift a global mute, and conversely.
+    async fn unmute_reaction_notifications(
+        &self,
+        user_id: &UserId,
+        message_id: Option<&MessageId>,
+    ) -> Result<(), CoreError>;
+
+    /// Returns the attachment storage used by a channel and its quota.
+    ///
+    /// Channels without a custom quota get the service's default quota.
+    async fn channel_storage(&self, channel_id: &ChannelId) -> Result<ChannelStorage, CoreError>;
+
+    /// Sets a custom attachment storage quota for a channel, or reverts it to
+    /// the default quota with `None`.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Future` that resolves to:
+    /// - `Ok(ChannelStorage)` - The storage of the channel under its new quota
+    /// - `Err(CoreError)` - If repository operation fails
+    async fn set_channel_storage_quota(
+        &self,
+        channel_id: &ChannelId,
+        quota_bytes: Option<u64>,
+    ) -> Result<ChannelStorage, CoreError>;
+
+    /// Returns the settings of a channel.
+    async fn channel_settings(&self, channel_id: &ChannelId) -> Result<ChannelSettings, CoreError>;
+
+    /// Restricts the emoji members may react with in a channel, e.g. on
+    /// workplace deployments, or lifts the restriction with `None`.
+    ///
+    /// Emoji are trimmed and deduplicated, keeping their order. Reactions
+    /// added before the restriction are kept.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Future` that resolves to:
+    /// - `Ok(ChannelSettings)` - The settings of the channel with the new restriction
+    /// - `Err(CoreError::InvalidReaction)` - An emoji is empty or too long
+    /// - `Err(CoreError::TooManyAllowedReactions)` - More than `MAX_ALLOWED_REACTIONS` emoji
+    /// - `Err(CoreError)` - If repository operation fails
+    async fn set_allowed_reactions(
+        &self,
+        channel_id: &ChannelId,
+        allowed: Option<Vec<String>>,
+    ) -> Result<ChannelSettings, CoreError>;
+
+    /// Sets how long the messages of a channel are kept before being
+    /// deleted, `0` keeping them forever, or reverts to the default retention
+    /// with `None`.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Future` that resolves to:
+    /// - `Ok(ChannelSettings)` - The settings of the channel with the new retention
+    /// - `Err(CoreError)` - If repository operation fails
+    async fn set_channel_retention(
+        &self,
+        channel_id: &ChannelId,
+        retention_secs: Option<u64>,
+    ) -> Result<ChannelSettings, CoreError>;
+
+    /// Deletes the messages kept longer than the retention of their channel,
+    /// `default_retention` applying to channels without a custom one (none
+    /// when `None`). Messages under a legal hold are kept.
+    ///
+    /// Messages are soft deleted `batch_size` at a time, so they are purged
+    /// later like any deleted message. With an archiver, each batch is
+    /// archived first and kept when its archival fails.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Future` that resolves to:
+    /// - `Ok(u64)` - How many messages were deleted
+    /// - `Err(CoreError)` - If repository operation or archival fails
+    async fn delete_expired_messages(
+        &self,
+        default_retention: Option<chrono::Duration>,
+        batch_size: usize,
+    ) -> Result<u64, CoreError>;
+
+    /// Returns a catch-up summary of a channel since `since`: the number of
+    /// messages posted, the most reacted ones, the new pins and the threads
+    /// replied to. Shared by the catch-up UI and the email digests.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Future` that resolves to:
+    /// - `Ok(ChannelDigest)` - The digest, each list holding a few messages
+    /// - `Err(CoreError)` - If repository operation fails
+    async fn channel_digest(
+        &self,
+        channel_id: &ChannelId,
+        since: DateTime<Utc>,
+    ) -> Result<ChannelDigest, CoreError>;
+
+    /// Returns the newest message of each channel, for sidebar previews.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Future` that resolves to:
+    /// - `Ok(Vec<Message>)` - The newest message not deleted of each channel
+    ///   having one, in the order of `channel_ids`
+    /// - `Err(CoreError)` - If repository operation fails
+    async fn last_messages(&self, channel_ids: &[ChannelId]) -> Result<Vec<Message>, CoreError>;
+
+    /// Returns the channels with the most messages posted since `since`,
+    /// busiest first, e.g. to warm caches up before traffic reaches them.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Future` that resolves to:
+    /// - `Ok(Vec<ChannelId>)` - At most `limit` channels
+    /// - `Err(CoreError)` - If repository operation fails
+    async fn most_active_channels(
+        &self,
+        since: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<ChannelId>, CoreError>;
+
+    /// Places a legal hold on a channel or a user, keeping their messages out
+    /// of deletion sweeps until it is released.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Future` that resolves to:
+    /// - `Ok(LegalHold)` - The hold placed
+    /// - `Err(CoreError::InvalidLegalHoldReference)` - The reference is blank
+    /// - `Err(CoreError)` - If repository operation fails
+    async fn place_legal_hold(&self, input: PlaceLegalHoldInput) -> Result<LegalHold, CoreError>;
+
+    /// Returns a legal hold, or `CoreError::LegalHoldNotFound` once released.
+    async fn get_legal_hold(&self, id: &LegalHoldId) -> Result<LegalHold, CoreError>;
+
+    /// Lists the active legal holds on a channel or a user, oldest first.
+    async fn list_legal_holds(&self, scope: &LegalHoldScope) -> Result<Vec<LegalHold>, CoreError>;
+
+    /// Releases a legal hold; the data it covered can be deleted again.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Future` that resolves to:
+    /// - `Ok(())` - The hold was released
+    /// - `Err(CoreError::LegalHoldNotFound)` - No active hold exists with the given ID
+    /// - `Err(CoreError)` - If repository operation fails
+    async fn release_legal_hold(&self, id: &LegalHoldId) -> Result<(), CoreError>;
+
+    /// Suspends the writes to a channel (messages, edits, deletions and
+    /// reactions) while a migration or an import runs on it. Writes fail with
+    /// `CoreError::ChannelWriteLocked` until the lock is released or expires.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Future` that resolves to:
+    /// - `Ok(ChannelWriteLock)` - The lock taken, replacing any previous one
+    /// - `Err(CoreError::InvalidChannelWriteLock)` - The duration is out of bounds
+    /// - `Err(CoreError)` - If repository operation fails
+    async fn lock_channel_writes(
+        &self,
+        input: LockChannelWritesInput,
+    ) -> Result<ChannelWriteLock, CoreError>;
+
+    /// Returns the write lock of a channel, `None` when it is not locked.
+    async fn channel_write_lock(
+        &self,
+        channel_id: &ChannelId,
+    ) -> Result<Option<ChannelWriteLock>, CoreError>;
+
+    /// Releases the write lock of a channel; releasing an unlocked channel does nothing.
+    async fn unlock_channel_writes(&self, channel_id: &ChannelId) -> Result<(), CoreError>;
+
+    /// Registers an attachment and returns the URL its file is uploaded to.
+    ///
+    /// The attachment is then posted by referencing its ID in a message of
+    /// the same channel, by the same author, once the file was uploaded.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Future` that resolves to:
+    /// - `Ok(PresignedAttachment)` - The registered upload and its presigned URL
+    /// - `Err(CoreError)` - If the attachment is invalid, uploads are not
+    ///   configured or repository operation fails
+    async fn presign_attachment(
+        &self,
+        input: PresignAttachmentInput,
+    ) -> Result<PresignedAttachment, CoreError>;
+
+    /// Scan the attachments of a posted message for malware.
+    ///
+    /// A message with an infected attachment is quarantined: it is soft
+    /// deleted by [`ATTACHMENT_SCANNER_ID`] with the `malware` reason, so
+    /// moderators can still review it, and an [`AttachmentQuarantinedEvent`]
+    /// is published for trust & safety.
+    ///
+    /// [`ATTACHMENT_SCANNER_ID`]: crate::domain::message::entities::ATTACHMENT_SCANNER_ID
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Future` that resolves to:
+    /// - `Ok(ScanVerdict)` - The verdict of the first infected attachment, or
+    ///   `Clean` when none is (or no scanner is configured)
+    /// - `Err(CoreError)` - If an attachment could not be fetched or scanned,
+    ///   or repository operation fails
+    async fn scan_attachments(&self, message: &Message) -> Result<ScanVerdict, CoreError>;
+
+    /// Preview the links of a posted message into its embeds.
+    ///
+    /// Links the link preview policy allows are unfurled one by one; a link
+    /// that fails to be unfurled is left without a preview. The embeds are
+    /// only stored while the message is still at the revision it was read at,
+    /// and are published as an update of the message.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Future` that resolves to:
+    /// - `Ok(Some(Message))` - The message with its embeds
+    /// - `Ok(None)` - If no link was previewed (or no unfurler is configured),
+    ///   or the message was edited or deleted meanwhile
+    /// - `Err(CoreError)` - If repository operation fails
+    async fn unfurl_links(&self, message: &Message) -> Result<Option<Message>, CoreError>;
+}
+
+/// Port to the object storage holding attachment files, which clients
+/// upload to directly
+#[async_trait::async_trait]
+pub trait AttachmentStorage: Send + Sync {
+    /// URL accepting a `PUT` of the object `key` until `expires_in` elapsed
+    async fn presign_upload(
+        &self,
+        key: &str,
+        expires_in: std::time::Duration,
+    ) -> Result<String, CoreError>;
+
+    /// Size of the object `key`, `None` while it was not uploaded
+    async fn object_size(&self, key: &str) -> Result<Option<u64>, CoreError>;
+
+    /// Content of the object `key`, `None` while it was not uploaded
+    async fn fetch(&self, key: &str) -> Result<Option<Vec<u8>>, CoreError>;
+
+    /// URL the object `key` is downloaded from
+    fn download_url(&self, key: &str) -> String;
+}
+
+/// Port to the service reading the dimensions of image attachments and
+/// generating their thumbnails
+#[async_trait::async_trait]
+pub trait ThumbnailGenerator: Send + Sync {
+    /// Preview of the image of `content_type` downloaded from `url`, `None`
+    /// when it is not an image the service can read
+    async fn preview(&self, url: &str, content_type: &str)
+    -> Result<Option<ImagePreview>, CoreError>;
+}
+
+/// Port to the fetcher of the pages linked from messages
+#[async_trait::async_trait]
+pub trait LinkUnfurler: Send + Sync {
+    /// Preview of the page at `url`, `None` when it has nothing to show
+    async fn unfurl(&self, url: &str) -> Result<Option<Embed>, CoreError>;
+}
+
+/// Port to the malware scanner checking the attachment files once posted
+#[async_trait::async_trait]
+pub trait AttachmentScanner: Send + Sync {
+    async fn scan(&self, content: &[u8]) -> Result<ScanVerdict, CoreError>;
+}
+
+/// Attachment files held in memory, for tests and local development
+#[derive(Clone, Default)]
+pub struct InMemoryAttachmentStorage {
+    /// Size of each uploaded object
+    objects: Arc<Mutex<HashMap<String, u64>>>,
+    /// Content of the objects uploaded with one
+    contents: Arc<Mutex<HashMap<String, Vec<u8>>>>,
+}
+
+impl InMemoryAttachmentStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Upload `size` bytes to `key`, as a client would with the presigned URL
+    pub fn upload(&self, key: &str, size: u64) {
+        self.objects.lock().unwrap().insert(key.to_string(), size);
+    }
+
+    /// Upload `content` to `key`, for the scanner to read it back
+    pub fn upload_content(&self, key: &str, content: &[u8]) {
+        self.upload(key, content.len() as u64);
+        self.contents
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), content.to_vec());
+    }
+}
+
+#[async_trait::async_trait]
+impl AttachmentStorage for InMemoryAttachmentStorage {
+    async fn presign_upload(
+        &self,
+        key: &str,
+        _expires_in: std::time::Duration,
+    ) -> Result<String, CoreError> {
+        Ok(format!("memory://{key}?upload"))
+    }
+
+    async fn object_size(&self, key: &str) -> Result<Option<u64>, CoreError> {
+        Ok(self.objects.lock().unwrap().get(key).copied())
+    }
+
+    async fn fetch(&self, key: &str) -> Result<Option<Vec<u8>>, CoreError> {
+        if !self.objects.lock().unwrap().contains_key(key) {
+            return Ok(None);
+        }
+        Ok(Some(
+            self.contents
+                .lock()
+                .unwrap()
+                .get(key)
+                .cloned()
+                .unwrap_or_default(),
+        ))
+    }
+
+    fn download_url(&self, key: &str) -> String {
+        format!("memory://{key}")
+    }
+}
+
+/// Reaction notification mutes of the mock: a user, and the message muted
+/// (`None` for every message)
+type ReactionMute = (UserId, Option<MessageId>);
+
+#[derive(Clone)]
+pub struct MockMessageRepository {
+    messages: Arc<Mutex<Vec<Message>>>,
+    reactions: Arc<Mutex<Vec<Reaction>>>,
+    /// `(user, None)` is a global mute
+    reaction_mutes: Arc<Mutex<Vec<ReactionMute>>>,
+    notifications: Arc<Mutex<Vec<NotificationRequestedEvent>>>,
+    notification_fanouts: Arc<Mutex<Vec<NotificationFanoutEvent>>>,
+    notification_broadcasts: Arc<Mutex<Vec<NotificationBroadcastEvent>>>,
+    mentions: Arc<Mutex<Vec<MessageMentionedEvent>>>,
+    abuse_reports: Arc<Mutex<Vec<AbuseDetectedEvent>>>,
+    quarantines: Arc<Mutex<Vec<AttachmentQuarantinedEvent>>>,
+    bulk_deletions: Arc<Mutex<Vec<MessagesBulkDeletedEvent>>>,
+    user_erasures: Arc<Mutex<Vec<UserErasedEvent>>>,
+    thread_archivals: Arc<Mutex<Vec<ThreadArchivedEvent>>>,
+    thread_unarchivals: Arc<Mutex<Vec<ThreadUnarchivedEvent>>>,
+    thread_follows: Arc<Mutex<Vec<ThreadFollow>>>,
+    saved_messages: Arc<Mutex<Vec<SavedMessage>>>,
+    thread_preferences: Arc<Mutex<HashMap<UserId, ThreadPreferences>>>,
+    read_markers: Arc<Mutex<HashMap<(UserId, ChannelId), ReadMarker>>>,
+    channel_storage: Arc<Mutex<HashMap<ChannelId, ChannelStorage>>>,
+    channel_settings: Arc<Mutex<HashMap<ChannelId, ChannelSettings>>>,
+    /// When each pinned message was pinned
+    pinned_at: Arc<Mutex<HashMap<MessageId, DateTime<Utc>>>>,
+    legal_holds: Arc<Mutex<Vec<LegalHold>>>,
+    channel_write_locks: Arc<Mutex<HashMap<ChannelId, ChannelWriteLock>>>,
+    /// Soft deleted messages, with their `deleted_at` set
+    deleted: Arc<Mutex<Vec<Message>>>,
+    revisions: Arc<Mutex<Vec<MessageRevision>>>,
+    /// Latest sequence handed out per channel
+    sequences: Arc<Mutex<HashMap<ChannelId, u64>>>,
+    attachment_uploads: Arc<Mutex<Vec<AttachmentUpload>>>,
+}
+
+impl MockMessageRepository {
+    pub fn new() -> Self {
+        Self {
+            messages: Arc::new(Mutex::new(Vec::new())),
+            reactions: Arc::new(Mutex::new(Vec::new())),
+            reaction_mutes: Arc::new(Mutex::new(Vec::new())),
+            notifications: Arc::new(Mutex::new(Vec::new())),
+            notification_fanouts: Arc::new(Mutex::new(Vec::new())),
+            notification_broadcasts: Arc::new(Mutex::new(Vec::new())),
+            mentions: Arc::new(Mutex::new(Vec::new())),
+            abuse_reports: Arc::new(Mutex::new(Vec::new())),
+            quarantines: Arc::new(Mutex::new(Vec::new())),
+            bulk_deletions: Arc::new(Mutex::new(Vec::new())),
+            user_erasures: Arc::new(Mutex::new(Vec::new())),
+            thread_archivals: Arc::new(Mutex::new(Vec::new())),
+            thread_unarchivals: Arc::new(Mutex::new(Vec::new())),
+            thread_follows: Arc::new(Mutex::new(Vec::new())),
+            saved_messages: Arc::new(Mutex::new(Vec::new())),
+            thread_preferences: Arc::new(Mutex::new(HashMap::new())),
+            read_markers: Arc::new(Mutex::new(HashMap::new())),
+            channel_storage: Arc::new(Mutex::new(HashMap::new())),
+            channel_settings: Arc::new(Mutex::new(HashMap::new())),
+            pinned_at: Arc::new(Mutex::new(HashMap::new())),
+            legal_holds: Arc::new(Mutex::new(Vec::new())),
+            channel_write_locks: Arc::new(Mutex::new(HashMap::new())),
+            deleted: Arc::new(Mutex::new(Vec::new())),
+            revisions: Arc::new(Mutex::new(Vec::new())),
+            sequences: Arc::new(Mutex::new(HashMap::new())),
+            attachment_uploads: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Notifications requested so far, in order
+    pub fn requested_notifications(&self) -> Vec<NotificationRequestedEvent> {
+        self.notifications.lock().unwrap().clone()
+    }
+
+    /// Notification fan-outs requested so far, in order
+    pub fn requested_notification_fanouts(&self) -> Vec<NotificationFanoutEvent> {
+        self.notification_fanouts.lock().unwrap().clone()
+    }
+
+    /// Notification broadcasts requested so far, in order
+    pub fn requested_notification_broadcasts(&self) -> Vec<NotificationBroadcastEvent> {
+        self.notification_broadcasts.lock().unwrap().clone()
+    }
+
+    /// Mentions published so far, in order
+    pub fn published_mentions(&self) -> Vec<MessageMentionedEvent> {
+        self.mentions.lock().unwrap().clone()
+    }
+
+    /// Abuse patterns reported so far, in order
+    pub fn reported_abuse(&self) -> Vec<AbuseDetectedEvent> {
+        self.abuse_reports.lock().unwrap().clone()
+    }
+
+    /// Messages quarantined for an infected attachment so far, in order
+    pub fn reported_quarantines(&self) -> Vec<AttachmentQuarantinedEvent> {
+        self.quarantines.lock().unwrap().clone()
+    }
+
+    /// Bulk deletions announced so far, in order
+    pub fn bulk_deletions(&self) -> Vec<MessagesBulkDeletedEvent> {
+        self.bulk_deletions.lock().unwrap().clone()
+    }
+
+    /// User erasures announced so far, in order
+    pub fn user_erasures(&self) -> Vec<UserErasedEvent> {
+        self.user_erasures.lock().unwrap().clone()
+    }
+
+    /// Thread archivals announced so far, in order
+    pub fn thread_archivals(&self) -> Vec<ThreadArchivedEvent> {
+        self.thread_archivals.lock().unwrap().clone()
+    }
+
+    /// Thread unarchivals announced so far, in order
+    pub fn thread_unarchivals(&self) -> Vec<ThreadUnarchivedEvent> {
+        self.thread_unarchivals.lock().unwrap().clone()
+    }
+
+    fn with_reaction_counts(&self, mut message: Message) -> Message {
+        let reactions = self.reactions.lock().unwrap();
+        message.reactions =
+            ReactionCount::aggregate(reactions.iter().filter(|r| r.message_id == message.id));
+        message
+    }
+}
+
+impl Default for MockMessageRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait::async_trait]
+impl MessageRepository for MockMessageRepository {
+    async fn find_by_id(&self, id: &MessageId) -> Result<Option<Message>, CoreError> {
+        let messages = self.messages.lock().unwrap();
+
+        let message = messages.iter().find(|s| &s.id == id && !s.is_expired(Utc::now())).cloned();
+
+        Ok(message.map(|m| self.with_reaction_counts(m)))
+    }
+
+    async fn find_by_ids(&self, ids: &[MessageId]) -> Result<Vec<Message>, CoreError> {
+        let messages = self.messages.lock().unwrap();
+
+        Ok(messages
+            .iter()
+            .filter(|m| ids.contains(&m.id) && !m.is_expired(Utc::now()))
+            .cloned()
+            .map(|m| self.with_reaction_counts(m))
+            .collect())
+    }
+
+    async fn find_by_id_including_deleted(
+        &self,
+        id: &MessageId,
+    ) -> Result<Option<Message>, CoreError> {
+        if let Some(message) = self.find_by_id(id).await? {
+            return Ok(Some(message));
+        }
+
+        let deleted = self.deleted.lock().unwrap();
+        Ok(deleted.iter().find(|m| &m.id == id).cloned())
+    }
+
+    async fn list_including_deleted(
+        &self,
+        channel_id: &ChannelId,
+        pagination: &GetPaginated,
+    ) -> Result<(Vec<Message>, TotalPaginatedElements), CoreError> {
+        let mut all: Vec<Message> = self
+            .messages
+            .lock()
+            .unwrap()
+            .iter()
+            .chain(self.deleted.lock().unwrap().iter())
+            .filter(|m| &m.channel_id == channel_id)
+            .cloned()
+            .collect();
+        all.sort_by_key(|m| std::cmp::Reverse(m.created_at));
+        let total = all.len() as u64;
+
+        let offset = ((pagination.page - 1) * pagination.limit) as usize;
+        let page = all
+            .into_iter()
+            .skip(offset)
+            .take(pagination.limit as usize)
+            .map(|m| self.with_reaction_counts(m))
+            .collect();
+
+        Ok((page, total))
+    }
+
+    async fn stream_channel(
+        &self,
+        channel_id: &ChannelId,
+        include_deleted: bool,
+    ) -> Result<MessageStream, CoreError> {
+        let deleted = self.deleted.lock().unwrap().clone();
+        let mut all: Vec<Message> = self
+            .messages
+            .lock()
+            .unwrap()
+            .iter()
+            .cloned()
+            .chain(deleted.into_iter().filter(|_| include_deleted))
+            .filter(|m| &m.channel_id == channel_id)
+            .map(|m| self.with_reaction_counts(m))
+            .collect();
+        all.sort_by_key(|m| m.created_at);
+
+        Ok(Box::pin(stream::iter(all.into_iter().map(Ok))))
+    }
+
+    async fn stream_by_author(
+        &self,
+        author_id: &AuthorId,
+        include_deleted: bool,
+    ) -> Result<MessageStream, CoreError> {
+        let deleted = self.deleted.lock().unwrap().clone();
+        let mut all: Vec<Message> = self
+            .messages
+            .lock()
+            .unwrap()
+            .iter()
+            .cloned()
+            .chain(deleted.into_iter().filter(|_| include_deleted))
+            .filter(|m| &m.author_id == author_id)
+            .map(|m| self.with_reaction_counts(m))
+            .collect();
+        all.sort_by_key(|m| m.created_at);
+
+        Ok(Box::pin(stream::iter(all.into_iter().map(Ok))))
+    }
+
+    async fn list(
+        &self,
+        channel_id: &ChannelId,
+        pagination: &GetPaginated,
+    ) -> Result<(Vec<Message>, TotalPaginatedElements), CoreError> {
+        let messages = self.messages.lock().unwrap();
+
+        // Filter messages by channel
+        let filtered: Vec<Message> = messages
+            .iter()
+            .filter(|m| &m.channel_id == channel_id && !m.is_expired(Utc::now()))
+            .cloned()
+            .collect();
+        let total = filtered.len() as u64;
+
+        let offset = ((pagination.page - 1) * pagination.limit) as usize;
+        let limit = pagination.limit as usize;
+
+        let paginated_messages: Vec<Message> = filtered
+            .into_iter()
+            .skip(offset)
+            .take(limit)
+            .map(|m| self.with_reaction_counts(m))
+            .collect();
+
+        Ok((paginated_messages, total))
+    }
+
+    async fn list_by_author(
+        &self,
+        author_id: &AuthorId,
+        channel_id: &ChannelId,
+        pagination: &GetPaginated,
+    ) -> Result<(Vec<Message>, TotalPaginatedElements), CoreError> {
+        let mut authored: Vec<Message> = self
+            .messages
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|m| &m.author_id == author_id && &m.channel_id == channel_id)
+            .cloned()
+            .collect();
+        authored.sort_by_key(|m| std::cmp::Reverse(m.created_at));
+        let total = authored.len() as u64;
+
+        let offset = ((pagination.page - 1) * pagination.limit) as usize;
+        let page = authored
+            .into_iter()
+            .skip(offset)
+            .take(pagination.limit as usize)
+            .map(|m| self.with_reaction_counts(m))
+            .collect();
+
+        Ok((page, total))
+    }
+
+    async fn list_pinned(
+        &self,
+        channel_id: &ChannelId,
+        pagination: &GetPaginated,
+    ) -> Result<(Vec<Message>, TotalPaginatedElements), CoreError> {
+        let pinned_at = self.pinned_at.lock().unwrap().clone();
+        let mut pinned: Vec<Message> = self
+            .messages
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|m| &m.channel_id == channel_id && m.is_pinned)
+            .cloned()
+            .collect();
+        pinned.sort_by(|a, b| {
+            pinned_at
+                .get(&b.id)
+                .cmp(&pinned_at.get(&a.id))
+                .then(b.created_at.cmp(&a.created_at))
+        });
+        let total = pinned.len() as u64;
+
+        let offset = ((pagination.page - 1) * pagination.limit) as usize;
+        let page = pinned
+            .into_iter()
+            .skip(offset)
+            .take(pagination.limit as usize)
+            .map(|m| self.with_reaction_counts(m))
+            .collect();
+
+        Ok((page, total))
+    }
+
+    async fn count_pinned(&self, channel_id: &ChannelId) -> Result<u64, CoreError> {
+        let messages = self.messages.lock().unwrap();
+        Ok(messages
+            .iter()
+            .filter(|m| &m.channel_id == channel_id && m.is_pinned)
+            .count() as u64)
+    }
+
+    async fn list_threads(
+        &self,
+        channel_id: &ChannelId,
+        include_archived: bool,
+        pagination: &GetPaginated,
+    ) -> Result<(Vec<Message>, TotalPaginatedElements), CoreError> {
+        let mut threads: Vec<Message> = self
+            .messages
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|m| &m.channel_id == channel_id && m.reply_count > 0)
+            .filter(|m| include_archived || m.archived_at.is_none())
+            .cloned()
+            .collect();
+        threads.sort_by_key(|m| std::cmp::Reverse(m.last_reply_at));
+        let total = threads.len() as u64;
+
+        let offset = ((pagination.page - 1) * pagination.limit) as usize;
+        let page = threads
+            .into_iter()
+            .skip(offset)
+            .take(pagination.limit as usize)
+            .map(|m| self.with_reaction_counts(m))
+            .collect();
+
+        Ok((page, total))
+    }
+
+    async fn archive_idle_threads(
+        &self,
+        idle_before: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<Message>, CoreError> {
+        let mut messages = self.messages.lock().unwrap();
+        let now = chrono::Utc::now();
+
+        let mut archived = Vec::new();
+        for message in messages.iter_mut().filter(|m| {
+            m.archived_at.is_none() && m.last_reply_at.is_some_and(|at| at < idle_before)
+        }) {
+            if archived.len() == limit {
+                break;
+            }
+            message.archived_at = Some(now);
+            archived.push(message.clone());
+        }
+
+        self.thread_archivals
+            .lock()
+            .unwrap()
+            .extend(archived.iter().map(|thread| ThreadArchivedEvent {
+                thread_id: thread.id,
+                channel_id: thread.channel_id,
+                archived_at: now,
+            }));
+        Ok(archived)
+    }
+
+    async fn unarchive_thread(&self, thread_id: &MessageId) -> Result<bool, CoreError> {
+        let mut messages = self.messages.lock().unwrap();
+
+        let Some(thread) = messages
+            .iter_mut()
+            .find(|m| &m.id == thread_id && m.archived_at.is_some())
+        else {
+            return Ok(false);
+        };
+        thread.archived_at = None;
+        self.thread_unarchivals
+            .lock()
+            .unwrap()
+            .push(ThreadUnarchivedEvent {
+                thread_id: thread.id,
+                channel_id: thread.channel_id,
+                unarchived_at: chrono::Utc::now(),
+            });
+        Ok(true)
+    }
+
+    async fn list_by_cursor(
+        &self,
+        channel_id: &ChannelId,
+        range: &CursorRange,
+        limit: usize,
+    ) -> Result<CursorPage<Message>, CoreError> {
+        let messages = self.messages.lock().unwrap();
+
+        let mut listed: Vec<Message> = messages
+            .iter()
+            .filter(|m| &m.channel_id == channel_id)
+            .cloned()
+            .collect();
+        listed.sort_by_key(|m| std::cmp::Reverse((m.created_at, m.id.0)));
+        let page: Vec<Message> = listed
+            .into_iter()
+            .filter(|m| {
+                let key = (m.created_at, m.id.0);
+                range.before.is_none_or(|c| key < (c.created_at, c.id))
+                    && range.after.is_none_or(|c| key > (c.created_at, c.id))
+            })
+            .take(limit + 1)
+            .map(|m| self.with_reaction_counts(m))
+            .collect();
+
+        Ok(CursorPage::from_overfetched(page, limit, |m| {
+            Cursor::new(m.created_at, m.id.0)
+        }))
+    }
+
+    async fn insert(&self, input: InsertMessageInput) -> Result<Message, CoreError> {
+        let mut messages = self.messages.lock().unwrap();
+
+        if let Some(nonce) = &input.nonce {
+            let existing = messages.iter().find(|m| {
+                m.channel_id == input.channel_id
+                    && m.author_id == input.author_id
+                    && m.nonce.as_ref() == Some(nonce)
+                    && !m.is_expired(Utc::now())
+            });
+            if let Some(existing) = existing {
+                return Ok(existing.clone());
+            }
+        }
+
+        let sequence = {
+            let mut sequences = self.sequences.lock().unwrap();
+            let last = sequences.entry(input.channel_id).or_default();
+            *last += 1;
+            *last
+        };
+
+        let mentions = Mentions::parse(&input.content);
+        let new_message = Message {
+            id: input.id,
+            channel_id: input.channel_id,
+            author_id: input.author_id,
+            content: input.content,
+            reply_to_message_id: input.reply_to_message_id,
+            attachments: input.attachments,
+            is_pinned: false,
+            reactions: Vec::new(),
+            revision: 0,
+            sequence,
+            reply_count: 0,
+            last_reply_at: None,
+            archived_at: None,
 
             created_at: chrono::Utc::now(),
             updated_at: None,
+            deleted_at: None,
+            nonce: input.nonce,
+            embeds: Vec::new(),
+            mentions,
+            expires_at: input.expires_at,
+        };
+
+        if let Some(parent_id) = &new_message.reply_to_message_id
+            && let Some(parent) = messages.iter_mut().find(|m| &m.id == parent_id)
+        {
+            parent.reply_count += 1;
+            parent.last_reply_at = Some(new_message.created_at);
+        }
+
+        messages.push(new_message.clone());
+
+        Ok(new_message)
+    }
+
+    async fn insert_many(
+        &self,
+        channel_id: &ChannelId,
+        inputs: Vec<ImportMessageInput>,
+    ) -> Result<Vec<MessageId>, CoreError> {
+        let mut messages = self.messages.lock().unwrap();
+        let deleted = self.deleted.lock().unwrap();
+        let mut sequences = self.sequences.lock().unwrap();
+        let last = sequences.entry(*channel_id).or_default();
+
+        let mut imported = Vec::new();
+        for input in inputs {
+            *last += 1;
+            if messages.iter().chain(deleted.iter()).any(|m| m.id == input.id) {
+                continue;
+            }
+
+            let message = input.into_message(*channel_id, *last);
+            if let Some(parent_id) = &message.reply_to_message_id
+                && let Some(parent) = messages.iter_mut().find(|m| &m.id == parent_id)
+            {
+                parent.reply_count += 1;
+                parent.last_reply_at = parent.last_reply_at.max(Some(message.created_at));
+            }
+            imported.push(message.id);
+            messages.push(message);
+        }
+
+        Ok(imported)
+    }
+
+    async fn update(&self, input: UpdateMessageInput) -> Result<Message, CoreError> {
+        let mut messages = self.messages.lock().unwrap();
+
+        let message = messages
+            .iter_mut()
+            .find(|s| s.id == input.id)
+            .ok_or(CoreError::MessageNotFound { id: input.id })?;
+        if input
+            .expected_revision
+            .is_some_and(|expected| expected != message.revision)
+        {
+            return Err(CoreError::MessageRevisionMismatch {
+                id: message.id,
+                revision: message.revision,
+            });
+        }
+
+        let now = chrono::Utc::now();
+        if let Some(content) = input.content {
+            if content != message.content {
+                self.revisions.lock().unwrap().push(MessageRevision {
+                    message_id: message.id,
+                    revision: message.revision,
+                    content: std::mem::replace(&mut message.content, content),
+                    written_at: message.updated_at.unwrap_or(message.created_at),
+                    replaced_at: now,
+                });
+            }
+            message.mentions = Mentions::parse(&message.content);
+        }
+        if let Some(is_pinned) = input.is_pinned {
+            message.is_pinned = is_pinned;
+            let mut pinned_at = self.pinned_at.lock().unwrap();
+            if is_pinned {
+                pinned_at.insert(message.id, chrono::Utc::now());
+            } else {
+                pinned_at.remove(&message.id);
+            }
+        }
+        message.revision += 1;
+        message.updated_at = Some(now);
+
+        Ok(message.clone())
+    }
+
+    async fn set_embeds(
+        &self,
+        id: &MessageId,
+        revision: u64,
+        embeds: &[Embed],
+    ) -> Result<Option<Message>, CoreError> {
+        let mut messages = self.messages.lock().unwrap();
+
+        let Some(message) = messages
+            .iter_mut()
+            .find(|m| &m.id == id && m.revision == revision)
+        else {
+            return Ok(None);
+        };
+        message.embeds = embeds.to_vec();
+        message.revision += 1;
+
+        Ok(Some(message.clone()))
+    }
+
+    async fn list_revisions(
+        &self,
+        message_id: &MessageId,
+    ) -> Result<Vec<MessageRevision>, CoreError> {
+        let revisions = self.revisions.lock().unwrap();
+        Ok(revisions
+            .iter()
+            .filter(|revision| &revision.message_id == message_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn delete(&self, id: &MessageId) -> Result<(), CoreError> {
+        let mut messages = self.messages.lock().unwrap();
+
+        let index = messages
+            .iter()
+            .position(|s| &s.id == id)
+            .ok_or(CoreError::MessageNotFound { id: *id })?;
+
+        let mut removed = messages.remove(index);
+
+        if let Some(parent_id) = &removed.reply_to_message_id
+            && let Some(parent) = messages.iter_mut().find(|m| &m.id == parent_id)
+        {
+            parent.reply_count = parent.reply_count.saturating_sub(1);
+        }
+        removed.deleted_at = Some(chrono::Utc::now());
+        self.deleted.lock().unwrap().push(removed);
+
+        Ok(())
+    }
+
+    async fn delete_many(
+        &self,
+        channel_id: &ChannelId,
+        ids: &[MessageId],
+        deleted_by: &UserId,
+        reason: Option<&ModerationReason>,
+    ) -> Result<Vec<Message>, CoreError> {
+        let mut messages = self.messages.lock().unwrap();
+        let now = chrono::Utc::now();
+
+        let mut removed = Vec::new();
+        messages.retain(|m| {
+            let kept = &m.channel_id != channel_id || !ids.contains(&m.id);
+            if !kept {
+                removed.push(Message {
+                    deleted_at: Some(now),
+                    ..m.clone()
+                });
+            }
+            kept
+        });
+        for message in &removed {
+            if let Some(parent_id) = &message.reply_to_message_id
+                && let Some(parent) = messages.iter_mut().find(|m| &m.id == parent_id)
+            {
+                parent.reply_count = parent.reply_count.saturating_sub(1);
+            }
+        }
+        self.deleted.lock().unwrap().extend(removed.iter().cloned());
+
+        if !removed.is_empty() {
+            self.bulk_deletions
+                .lock()
+                .unwrap()
+                .push(MessagesBulkDeletedEvent {
+                    channel_id: *channel_id,
+                    ids: removed.iter().map(|m| m.id).collect(),
+                    deleted_by: *deleted_by,
+                    deleted_at: now,
+                    reason: reason.cloned(),
+                });
+        }
+
+        Ok(removed)
+    }
+
+    async fn hard_delete(&self, id: &MessageId) -> Result<(), CoreError> {
+        let mut messages = self.messages.lock().unwrap();
+
+        if let Some(index) = messages.iter().position(|m| &m.id == id) {
+            let removed = messages.remove(index);
+            if let Some(parent_id) = &removed.reply_to_message_id
+                && let Some(parent) = messages.iter_mut().find(|m| &m.id == parent_id)
+            {
+                parent.reply_count = parent.reply_count.saturating_sub(1);
+            }
+        } else {
+            let mut deleted = self.deleted.lock().unwrap();
+            let index = deleted
+                .iter()
+                .position(|m| &m.id == id)
+                .ok_or(CoreError::MessageNotFound { id: *id })?;
+            deleted.remove(index);
+        }
+
+        self.reactions
+            .lock()
+            .unwrap()
+            .retain(|reaction| &reaction.message_id != id);
+        self.revisions
+            .lock()
+            .unwrap()
+            .retain(|revision| &revision.message_id != id);
+        Ok(())
+    }
+
+    async fn list_deleted_before(
+        &self,
+        before: DateTime<Utc>,
+        held_channels: &[ChannelId],
+        held_authors: &[AuthorId],
+        limit: usize,
+    ) -> Result<Vec<Message>, CoreError> {
+        let mut expired: Vec<Message> = self
+            .deleted
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|m| m.deleted_at.is_some_and(|deleted_at| deleted_at < before))
+            .filter(|m| !held_channels.contains(&m.channel_id))
+            .filter(|m| !held_authors.contains(&m.author_id))
+            .cloned()
+            .collect();
+        expired.sort_by_key(|m| m.deleted_at);
+        expired.truncate(limit);
+        Ok(expired)
+    }
+
+    async fn list_expired_before(
+        &self,
+        before: DateTime<Utc>,
+        held_channels: &[ChannelId],
+        held_authors: &[AuthorId],
+        limit: usize,
+    ) -> Result<Vec<Message>, CoreError> {
+        let messages = self.messages.lock().unwrap();
+        let deleted = self.deleted.lock().unwrap();
+        let mut expired: Vec<Message> = messages
+            .iter()
+            .chain(deleted.iter())
+            .filter(|m| m.expires_at.is_some_and(|expires_at| expires_at < before))
+            .filter(|m| !held_channels.contains(&m.channel_id))
+            .filter(|m| !held_authors.contains(&m.author_id))
+            .cloned()
+            .collect();
+        expired.sort_by_key(|m| m.expires_at);
+        expired.truncate(limit);
+        Ok(expired)
+    }
+
+    async fn delete_by_channel(
+        &self,
+        channel_id: &ChannelId,
+        held_authors: &[AuthorId],
+    ) -> Result<u64, CoreError> {
+        let mut messages = self.messages.lock().unwrap();
+
+        let before = messages.len();
+        let mut deleted = self.deleted.lock().unwrap();
+        let now = chrono::Utc::now();
+        messages.retain(|m| {
+            let kept = &m.channel_id != channel_id || held_authors.contains(&m.author_id);
+            if !kept {
+                deleted.push(Message {
+                    deleted_at: Some(now),
+                    ..m.clone()
+                });
+            }
+            kept
+        });
+
+        Ok((before - messages.len()) as u64)
+    }
+
+    async fn delete_by_author(
+        &self,
+        author_id: &AuthorId,
+        held_channels: &[ChannelId],
+        erased_by: &UserId,
+    ) -> Result<Vec<Message>, CoreError> {
+        let mut messages = self.messages.lock().unwrap();
+        let mut deleted = self.deleted.lock().unwrap();
+        let now = chrono::Utc::now();
+        let erased_from =
+            |m: &Message| &m.author_id == author_id && !held_channels.contains(&m.channel_id);
+
+        // Messages deleted before are anonymized too
+        let mut erased = Vec::new();
+        for message in deleted.iter_mut().filter(|m| erased_from(m)) {
+            erased.push(message.clone());
+            message.content = String::new();
+            message.mentions = Mentions::default();
+            message.attachments = Vec::new();
+            message.embeds = Vec::new();
+            message.nonce = None;
+        }
+
+        let mut newly_deleted: HashMap<ChannelId, Vec<MessageId>> = HashMap::new();
+        messages.retain(|m| {
+            let kept = !erased_from(m);
+            if !kept {
+                erased.push(m.clone());
+                newly_deleted.entry(m.channel_id).or_default().push(m.id);
+                deleted.push(Message {
+                    content: String::new(),
+                    mentions: Mentions::default(),
+                    attachments: Vec::new(),
+                    embeds: Vec::new(),
+                    nonce: None,
+                    deleted_at: Some(now),
+                    ..m.clone()
+                });
+            }
+            kept
+        });
+        for message in erased.iter().filter(|m| m.deleted_at.is_none()) {
+            if let Some(parent_id) = &message.reply_to_message_id
+                && let Some(parent) = messages.iter_mut().find(|m| &m.id == parent_id)
+            {
+                parent.reply_count = parent.reply_count.saturating_sub(1);
+            }
+        }
+        if erased.is_empty() {
+            return Ok(erased);
+        }
+
+        let ids: Vec<MessageId> = erased.iter().map(|m| m.id).collect();
+        let user_id = UserId(author_id.0);
+        self.reactions
+            .lock()
+            .unwrap()
+            .retain(|r| !ids.contains(&r.message_id) && r.user_id != user_id);
+        self.revisions
+            .lock()
+            .unwrap()
+            .retain(|revision| !ids.contains(&revision.message_id));
+        self.reaction_mutes
+            .lock()
+            .unwrap()
+            .retain(|(muted_by, _)| muted_by != &user_id);
+
+        let mut bulk_deletions = self.bulk_deletions.lock().unwrap();
+        for (channel_id, ids) in newly_deleted {
+            bulk_deletions.push(MessagesBulkDeletedEvent {
+                channel_id,
+                ids,
+                deleted_by: *erased_by,
+                deleted_at: now,
+                reason: None,
+            });
+        }
+        self.user_erasures.lock().unwrap().push(UserErasedEvent {
+            user_id,
+            message_ids: ids,
+            erased_by: *erased_by,
+            erased_at: now,
+        });
+
+        Ok(erased)
+    }
+
+    async fn list_replies(
+        &self,
+        message_id: &MessageId,
+        pagination: &GetCursorPaginated,
+    ) -> Result<CursorPage<Message>, CoreError> {
+        let cursor = pagination.decoded_cursor()?;
+        let limit = pagination.effective_limit();
+        let messages = self.messages.lock().unwrap();
+
+        let mut replies: Vec<Message> = messages
+            .iter()
+            .filter(|m| m.reply_to_message_id.as_ref() == Some(message_id))
+            .cloned()
+            .collect();
+        replies.sort_by_key(|m| (m.created_at, m.id.0));
+        let page: Vec<Message> = replies
+            .into_iter()
+            .filter(|m| cursor.is_none_or(|c| (m.created_at, m.id.0) > (c.created_at, c.id)))
+            .take(limit + 1)
+            .map(|m| self.with_reaction_counts(m))
+            .collect();
+
+        Ok(CursorPage::from_overfetched(page, limit, |m| {
+            Cursor::new(m.created_at, m.id.0)
+        }))
+    }
+
+    async fn count_deleted_by_author(
+        &self,
+        author_id: &AuthorId,
+        since: DateTime<Utc>,
+    ) -> Result<u64, CoreError> {
+        let deleted = self.deleted.lock().unwrap();
+        Ok(deleted
+            .iter()
+            .filter(|m| &m.author_id == author_id && m.deleted_at.is_some_and(|at| at >= since))
+            .count() as u64)
+    }
+
+    async fn list_created_before(
+        &self,
+        channel_id: Option<&ChannelId>,
+        before: DateTime<Utc>,
+        excluded_channels: &[ChannelId],
+        held_authors: &[AuthorId],
+        limit: usize,
+    ) -> Result<Vec<Message>, CoreError> {
+        let messages = self.messages.lock().unwrap();
+
+        let mut expired: Vec<Message> = messages
+            .iter()
+            .filter(|m| m.created_at < before)
+            .filter(|m| match channel_id {
+                Some(channel_id) => &m.channel_id == channel_id,
+                None => !excluded_channels.contains(&m.channel_id),
+            })
+            .filter(|m| !held_authors.contains(&m.author_id))
+            .cloned()
+            .collect();
+        expired.sort_by_key(|m| m.created_at);
+        expired.truncate(limit);
+
+        Ok(expired)
+    }
+
+    async fn channel_digest(
+        &self,
+        channel_id: &ChannelId,
+        since: DateTime<Utc>,
+        limit: u32,
+    ) -> Result<ChannelDigest, CoreError> {
+        let messages: Vec<Message> = self
+            .messages
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|m| &m.channel_id == channel_id)
+            .map(|m| self.with_reaction_counts(m.clone()))
+            .collect();
+        let pinned_at = self.pinned_at.lock().unwrap().clone();
+        let reaction_total = |m: &Message| {
+            m.reactions
+                .iter()
+                .map(|reaction| reaction.count)
+                .sum::<u64>()
         };
 
-        messages.push(new_message.clone());
+        let recent: Vec<&Message> = messages.iter().filter(|m| m.created_at >= since).collect();
 
-        Ok(new_message)
+        let mut top_reacted: Vec<Message> = recent
+            .iter()
+            .filter(|m| reaction_total(m) > 0)
+            .map(|m| (*m).clone())
+            .collect();
+        top_reacted.sort_by(|a, b| {
+            reaction_total(b)
+                .cmp(&reaction_total(a))
+                .then(b.created_at.cmp(&a.created_at))
+        });
+        top_reacted.truncate(limit as usize);
+
+        let mut new_pins: Vec<(DateTime<Utc>, Message)> = messages
+            .iter()
+            .filter(|m| m.is_pinned)
+            .filter_map(|m| pinned_at.get(&m.id).map(|at| (*at, m.clone())))
+            .filter(|(at, _)| *at >= since)
+            .collect();
+        new_pins.sort_by_key(|p| std::cmp::Reverse(p.0));
+        new_pins.truncate(limit as usize);
+
+        let mut active_threads: Vec<Message> = messages
+            .iter()
+            .filter(|m| m.last_reply_at.is_some_and(|at| at >= since))
+            .cloned()
+            .collect();
+        active_threads.sort_by_key(|m| std::cmp::Reverse(m.last_reply_at));
+        active_threads.truncate(limit as usize);
+
+        Ok(ChannelDigest {
+            channel_id: *channel_id,
+            since,
+            message_count: recent.len() as u64,
+            top_reacted,
+            new_pins: new_pins.into_iter().map(|(_, m)| m).collect(),
+            active_threads,
+        })
     }
 
-    async fn update(&self, input: UpdateMessageInput) -> Result<Message, CoreError> {
-        let mut messages = self.messages.lock().unwrap();
+    async fn most_active_channels(
+        &self,
+        since: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<ChannelId>, CoreError> {
+        let mut counts: HashMap<ChannelId, u64> = HashMap::new();
+        for message in self.messages.lock().unwrap().iter() {
+            if message.created_at >= since {
+                *counts.entry(message.channel_id).or_default() += 1;
+            }
+        }
 
-        let message = messages
-            .iter_mut()
-            .find(|s| &s.id == &input.id)
-            .ok_or_else(|| CoreError::MessageNotFound {
-                id: input.id.clone(),
+        let mut channels: Vec<(ChannelId, u64)> = counts.into_iter().collect();
+        channels.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.0.cmp(&b.0.0)));
+        Ok(channels
+            .into_iter()
+            .take(limit)
+            .map(|(channel_id, _)| channel_id)
+            .collect())
+    }
+
+    async fn last_messages(&self, channel_ids: &[ChannelId]) -> Result<Vec<Message>, CoreError> {
+        let mut last: HashMap<ChannelId, Message> = HashMap::new();
+        for message in self.messages.lock().unwrap().iter() {
+            if message.deleted_at.is_some()
+                || message.is_expired(Utc::now())
+                || !channel_ids.contains(&message.channel_id)
+            {
+                continue;
+            }
+            let newer = last
+                .get(&message.channel_id)
+                .is_none_or(|newest| newest.sequence < message.sequence);
+            if newer {
+                last.insert(message.channel_id, message.clone());
+            }
+        }
+        Ok(last
+            .into_values()
+            .map(|message| self.with_reaction_counts(message))
+            .collect())
+    }
+}
+
+#[async_trait::async_trait]
+impl ReactionRepository for MockMessageRepository {
+    async fn add_reaction(&self, input: AddReactionInput) -> Result<Reaction, CoreError> {
+        let mut reactions = self.reactions.lock().unwrap();
+
+        if let Some(existing) = reactions.iter().find(|r| {
+            r.message_id == input.message_id && r.user_id == input.user_id && r.emoji == input.emoji
+        }) {
+            return Ok(existing.clone());
+        }
+
+        let reaction = Reaction {
+            message_id: input.message_id,
+            user_id: input.user_id,
+            emoji: input.emoji,
+            created_at: chrono::Utc::now(),
+        };
+        reactions.push(reaction.clone());
+
+        Ok(reaction)
+    }
+
+    async fn remove_reaction(
+        &self,
+        message_id: &MessageId,
+        user_id: &UserId,
+        emoji: &str,
+    ) -> Result<(), CoreError> {
+        let mut reactions = self.reactions.lock().unwrap();
+
+        let index = reactions
+            .iter()
+            .position(|r| &r.message_id == message_id && &r.user_id == user_id && r.emoji == emoji)
+            .ok_or_else(|| CoreError::ReactionNotFound {
+                message_id: *message_id,
+                emoji: emoji.to_string(),
             })?;
 
-        if let Some(content) = input.content {
-            message.content = content;
+        reactions.remove(index);
+
+        Ok(())
+    }
+
+    async fn list_reactions(&self, message_id: &MessageId) -> Result<Vec<Reaction>, CoreError> {
+        let reactions = self.reactions.lock().unwrap();
+
+        Ok(reactions
+            .iter()
+            .filter(|r| &r.message_id == message_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn list_reaction_users(
+        &self,
+        message_id: &MessageId,
+        emoji: &str,
+        pagination: &GetCursorPaginated,
+    ) -> Result<CursorPage<Reaction>, CoreError> {
+        let cursor = pagination.decoded_cursor()?;
+        let limit = pagination.effective_limit();
+        let reactions = self.reactions.lock().unwrap();
+
+        let mut matching: Vec<Reaction> = reactions
+            .iter()
+            .filter(|r| &r.message_id == message_id && r.emoji == emoji)
+            .cloned()
+            .collect();
+        matching.sort_by_key(|r| (r.created_at, r.user_id.0));
+        let page: Vec<Reaction> = matching
+            .into_iter()
+            .filter(|r| cursor.is_none_or(|c| (r.created_at, r.user_id.0) > (c.created_at, c.id)))
+            .take(limit + 1)
+            .collect();
+
+        Ok(CursorPage::from_overfetched(page, limit, |r| {
+            Cursor::new(r.created_at, r.user_id.0)
+        }))
+    }
+
+    async fn set_reaction_notifications_muted(
+        &self,
+        user_id: &UserId,
+        message_id: Option<&MessageId>,
+        muted: bool,
+    ) -> Result<(), CoreError> {
+        let mut mutes = self.reaction_mutes.lock().unwrap();
+        let entry = (*user_id, message_id.copied());
+
+        mutes.retain(|m| m != &entry);
+        if muted {
+            mutes.push(entry);
         }
-        if let Some(is_pinned) = input.is_pinned {
-            message.is_pinned = is_pinned;
+
+        Ok(())
+    }
+
+    async fn reaction_notifications_muted(
+        &self,
+        user_id: &UserId,
+        message_id: &MessageId,
+    ) -> Result<bool, CoreError> {
+        let mutes = self.reaction_mutes.lock().unwrap();
+
+        Ok(mutes
+            .iter()
+            .any(|(user, message)| user == user_id && message.is_none_or(|m| &m == message_id)))
+    }
+}
+
+#[async_trait::async_trait]
+impl NotificationPublisher for MockMessageRepository {
+    async fn request_notification(&self, event: &NotificationRequestedEvent) -> Result<(), CoreError> {
+        self.notifications.lock().unwrap().push(event.clone());
+        Ok(())
+    }
+
+    async fn request_notification_fanout(
+        &self,
+        event: &NotificationFanoutEvent,
+    ) -> Result<(), CoreError> {
+        self.notification_fanouts.lock().unwrap().push(event.clone());
+        Ok(())
+    }
+
+    async fn request_notification_broadcast(
+        &self,
+        event: &NotificationBroadcastEvent,
+    ) -> Result<(), CoreError> {
+        self.notification_broadcasts.lock().unwrap().push(event.clone());
+        Ok(())
+    }
+
+    async fn publish_mention(&self, event: &MessageMentionedEvent) -> Result<(), CoreError> {
+        self.mentions.lock().unwrap().push(event.clone());
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl TrustSafetyPublisher for MockMessageRepository {
+    async fn report_abuse(&self, event: &AbuseDetectedEvent) -> Result<(), CoreError> {
+        self.abuse_reports.lock().unwrap().push(event.clone());
+        Ok(())
+    }
+
+    async fn report_quarantine(
+        &self,
+        event: &AttachmentQuarantinedEvent,
+    ) -> Result<(), CoreError> {
+        self.quarantines.lock().unwrap().push(event.clone());
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl ThreadFollowRepository for MockMessageRepository {
+    async fn follow_thread(&self, follow: &ThreadFollow) -> Result<ThreadFollow, CoreError> {
+        let mut follows = self.thread_follows.lock().unwrap();
+
+        if let Some(existing) = follows
+            .iter()
+            .find(|f| f.user_id == follow.user_id && f.thread_id == follow.thread_id)
+        {
+            return Ok(existing.clone());
         }
-        message.updated_at = Some(chrono::Utc::now());
+        follows.push(follow.clone());
+        Ok(follow.clone())
+    }
 
-        Ok(message.clone())
+    async fn unfollow_thread(
+        &self,
+        user_id: &UserId,
+        thread_id: &MessageId,
+    ) -> Result<(), CoreError> {
+        self.thread_follows
+            .lock()
+            .unwrap()
+            .retain(|f| &f.user_id != user_id || &f.thread_id != thread_id);
+        Ok(())
     }
 
-    async fn delete(&self, id: &MessageId) -> Result<(), CoreError> {
-        let mut messages = self.messages.lock().unwrap();
+    async fn list_thread_followers(&self, thread_id: &MessageId) -> Result<Vec<UserId>, CoreError> {
+        let follows = self.thread_follows.lock().unwrap();
 
-        let index = messages
+        Ok(follows
             .iter()
-            .position(|s| &s.id == id)
-            .ok_or_else(|| CoreError::MessageNotFound { id: id.clone() })?;
+            .filter(|f| &f.thread_id == thread_id)
+            .map(|f| f.user_id)
+            .collect())
+    }
+
+    async fn list_followed_threads(
+        &self,
+        user_id: &UserId,
+        pagination: &GetPaginated,
+    ) -> Result<(Vec<Message>, TotalPaginatedElements), CoreError> {
+        let mut follows: Vec<ThreadFollow> = self
+            .thread_follows
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|f| &f.user_id == user_id)
+            .cloned()
+            .collect();
+        follows.sort_by_key(|f| std::cmp::Reverse(f.followed_at));
+        let total = follows.len() as u64;
+
+        let offset = ((pagination.page - 1) * pagination.limit) as usize;
+        let messages = self.messages.lock().unwrap();
+        let page = follows
+            .iter()
+            .skip(offset)
+            .take(pagination.limit as usize)
+            .filter_map(|f| {
+                messages
+                    .iter()
+                    .find(|m| {
+                        m.id == f.thread_id && m.deleted_at.is_none() && !m.is_expired(Utc::now())
+                    })
+                    .cloned()
+            })
+            .map(|m| self.with_reaction_counts(m))
+            .collect();
+
+        Ok((page, total))
+    }
+
+    async fn thread_preferences(&self, user_id: &UserId) -> Result<ThreadPreferences, CoreError> {
+        let preferences = self.thread_preferences.lock().unwrap();
+
+        Ok(preferences
+            .get(user_id)
+            .cloned()
+            .unwrap_or_else(|| ThreadPreferences::defaults(*user_id)))
+    }
+
+    async fn set_thread_preferences(&self, preferences: &ThreadPreferences) -> Result<(), CoreError> {
+        self.thread_preferences
+            .lock()
+            .unwrap()
+            .insert(preferences.user_id, preferences.clone());
+        Ok(())
+    }
+
+    async fn delete_thread_follows_by_user(&self, user_id: &UserId) -> Result<(), CoreError> {
+        self.thread_follows
+            .lock()
+            .unwrap()
+            .retain(|f| &f.user_id != user_id);
+        self.thread_preferences.lock().unwrap().remove(user_id);
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl SavedMessageRepository for MockMessageRepository {
+    async fn save_message(&self, saved: &SavedMessage) -> Result<SavedMessage, CoreError> {
+        let mut saved_messages = self.saved_messages.lock().unwrap();
 
-        messages.remove(index);
+        if let Some(existing) = saved_messages
+            .iter()
+            .find(|s| s.user_id == saved.user_id && s.message_id == saved.message_id)
+        {
+            return Ok(existing.clone());
+        }
+        saved_messages.push(saved.clone());
+        Ok(saved.clone())
+    }
+
+    async fn unsave_message(
+        &self,
+        user_id: &UserId,
+        message_id: &MessageId,
+    ) -> Result<(), CoreError> {
+        self.saved_messages
+            .lock()
+            .unwrap()
+            .retain(|s| &s.user_id != user_id || &s.message_id != message_id);
+        Ok(())
+    }
+
+    async fn delete_saved_messages_by_user(&self, user_id: &UserId) -> Result<(), CoreError> {
+        self.saved_messages
+            .lock()
+            .unwrap()
+            .retain(|s| &s.user_id != user_id);
+        Ok(())
+    }
+
+    async fn list_saved_messages(
+        &self,
+        user_id: &UserId,
+        pagination: &GetPaginated,
+    ) -> Result<(Vec<Message>, TotalPaginatedElements), CoreError> {
+        let mut saved: Vec<SavedMessage> = self
+            .saved_messages
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|s| &s.user_id == user_id)
+            .cloned()
+            .collect();
+        saved.sort_by_key(|s| std::cmp::Reverse(s.saved_at));
+        let total = saved.len() as u64;
+
+        let offset = ((pagination.page - 1) * pagination.limit) as usize;
+        let messages = self.messages.lock().unwrap();
+        let page = saved
+            .iter()
+            .skip(offset)
+            .take(pagination.limit as usize)
+            .filter_map(|s| {
+                messages
+                    .iter()
+                    .find(|m| {
+                        m.id == s.message_id && m.deleted_at.is_none() && !m.is_expired(Utc::now())
+                    })
+                    .cloned()
+            })
+            .map(|m| self.with_reaction_counts(m))
+            .collect();
+
+        Ok((page, total))
+    }
+}
+
+#[async_trait::async_trait]
+impl ReadMarkerRepository for MockMessageRepository {
+    async fn read_marker(
+        &self,
+        user_id: &UserId,
+        channel_id: &ChannelId,
+    ) -> Result<Option<ReadMarker>, CoreError> {
+        let markers = self.read_markers.lock().unwrap();
+        Ok(markers.get(&(*user_id, *channel_id)).cloned())
+    }
+
+    async fn set_read_marker(&self, marker: &ReadMarker) -> Result<ReadMarker, CoreError> {
+        let mut markers = self.read_markers.lock().unwrap();
+        let kept = markers
+            .entry((marker.user_id, marker.channel_id))
+            .or_insert_with(|| marker.clone());
+        if kept.last_read_sequence < marker.last_read_sequence {
+            *kept = marker.clone();
+        }
+        Ok(kept.clone())
+    }
+
+    async fn read_markers_of_user(&self, user_id: &UserId) -> Result<Vec<ReadMarker>, CoreError> {
+        let markers = self.read_markers.lock().unwrap();
+        Ok(markers
+            .values()
+            .filter(|marker| marker.user_id == *user_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn delete_read_markers_by_user(&self, user_id: &UserId) -> Result<(), CoreError> {
+        self.read_markers
+            .lock()
+            .unwrap()
+            .retain(|(marker_user_id, _), _| marker_user_id != user_id);
+        Ok(())
+    }
+
+    async fn count_unread(
+        &self,
+        markers: &[ReadMarker],
+    ) -> Result<HashMap<ChannelId, u64>, CoreError> {
+        let messages = self.messages.lock().unwrap();
+        let mut counts = HashMap::new();
+        for marker in markers {
+            let unread = messages
+                .iter()
+                .filter(|message| {
+                    message.channel_id == marker.channel_id
+                        && message.deleted_at.is_none()
+                        && !message.is_expired(Utc::now())
+                        && message.sequence > marker.last_read_sequence
+                })
+                .count() as u64;
+            if unread > 0 {
+                counts.insert(marker.channel_id, unread);
+            }
+        }
+        Ok(counts)
+    }
+}
+
+#[async_trait::async_trait]
+impl ChannelStorageRepository for MockMessageRepository {
+    async fn channel_storage(&self, channel_id: &ChannelId) -> Result<ChannelStorage, CoreError> {
+        let storage = self.channel_storage.lock().unwrap();
+
+        Ok(storage
+            .get(channel_id)
+            .cloned()
+            .unwrap_or_else(|| ChannelStorage::empty(*channel_id)))
+    }
+
+    async fn add_channel_storage_usage(
+        &self,
+        channel_id: &ChannelId,
+        bytes: u64,
+    ) -> Result<(), CoreError> {
+        let mut storage = self.channel_storage.lock().unwrap();
+
+        let channel = storage
+            .entry(*channel_id)
+            .or_insert_with(|| ChannelStorage::empty(*channel_id));
+        channel.used_bytes += bytes;
+        Ok(())
+    }
+
+    async fn release_channel_storage_usage(
+        &self,
+        channel_id: &ChannelId,
+        bytes: u64,
+    ) -> Result<(), CoreError> {
+        let mut storage = self.channel_storage.lock().unwrap();
+
+        if let Some(channel) = storage.get_mut(channel_id) {
+            channel.used_bytes = channel.used_bytes.saturating_sub(bytes);
+        }
+        Ok(())
+    }
+
+    async fn set_channel_storage_quota(
+        &self,
+        channel_id: &ChannelId,
+        quota_bytes: Option<u64>,
+    ) -> Result<ChannelStorage, CoreError> {
+        let mut storage = self.channel_storage.lock().unwrap();
+
+        let channel = storage
+            .entry(*channel_id)
+            .or_insert_with(|| ChannelStorage::empty(*channel_id));
+        channel.quota_bytes = quota_bytes;
+        channel.custom_quota = quota_bytes.is_some();
+        Ok(channel.clone())
+    }
+}
 
+#[async_trait::async_trait]
+impl ChannelSettingsRepository for MockMessageRepository {
+    async fn channel_settings(&self, channel_id: &ChannelId) -> Result<ChannelSettings, CoreError> {
+        let settings = self.channel_settings.lock().unwrap();
+
+        Ok(settings
+            .get(channel_id)
+            .cloned()
+            .unwrap_or_else(|| ChannelSettings::defaults(*channel_id)))
+    }
+
+    async fn set_allowed_reactions(
+        &self,
+        channel_id: &ChannelId,
+        allowed: Option<Vec<String>>,
+    ) -> Result<ChannelSettings, CoreError> {
+        let mut settings = self.channel_settings.lock().unwrap();
+
+        let channel = settings
+            .entry(*channel_id)
+            .or_insert_with(|| ChannelSettings::defaults(*channel_id));
+        channel.allowed_reactions = allowed;
+        Ok(channel.clone())
+    }
+
+    async fn set_channel_retention(
+        &self,
+        channel_id: &ChannelId,
+        retention_secs: Option<u64>,
+    ) -> Result<ChannelSettings, CoreError> {
+        let mut settings = self.channel_settings.lock().unwrap();
+
+        let channel = settings
+            .entry(*channel_id)
+            .or_insert_with(|| ChannelSettings::defaults(*channel_id));
+        channel.retention_secs = retention_secs;
+        Ok(channel.clone())
+    }
+
+    async fn list_channel_retentions(&self) -> Result<Vec<ChannelSettings>, CoreError> {
+        let settings = self.channel_settings.lock().unwrap();
+
+        Ok(settings
+            .values()
+            .filter(|settings| settings.retention_secs.is_some())
+            .cloned()
+            .collect())
+    }
+}
+
+#[async_trait::async_trait]
+impl LegalHoldRepository for MockMessageRepository {
+    async fn place_legal_hold(&self, input: PlaceLegalHoldInput) -> Result<LegalHold, CoreError> {
+        let hold = LegalHold {
+            id: LegalHoldId::from(uuid::Uuid::new_v4()),
+            scope: input.scope,
+            reference: input.reference,
+            placed_by: input.placed_by,
+            placed_at: chrono::Utc::now(),
+        };
+        self.legal_holds.lock().unwrap().push(hold.clone());
+        Ok(hold)
+    }
+
+    async fn find_legal_hold(&self, id: &LegalHoldId) -> Result<Option<LegalHold>, CoreError> {
+        let holds = self.legal_holds.lock().unwrap();
+
+        Ok(holds.iter().find(|h| &h.id == id).cloned())
+    }
+
+    async fn list_legal_holds(
+        &self,
+        scope: Option<&LegalHoldScope>,
+    ) -> Result<Vec<LegalHold>, CoreError> {
+        let holds = self.legal_holds.lock().unwrap();
+
+        Ok(holds
+            .iter()
+            .filter(|h| scope.is_none_or(|scope| &h.scope == scope))
+            .cloned()
+            .collect())
+    }
+
+    async fn release_legal_hold(&self, id: &LegalHoldId) -> Result<(), CoreError> {
+        let mut holds = self.legal_holds.lock().unwrap();
+
+        let index = holds
+            .iter()
+            .position(|h| &h.id == id)
+            .ok_or(CoreError::LegalHoldNotFound { id: *id })?;
+        holds.remove(index);
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl ChannelLockRepository for MockMessageRepository {
+    async fn lock_channel_writes(&self, lock: &ChannelWriteLock) -> Result<(), CoreError> {
+        self.channel_write_locks
+            .lock()
+            .unwrap()
+            .insert(lock.channel_id, lock.clone());
+        Ok(())
+    }
+
+    async fn find_channel_write_lock(
+        &self,
+        channel_id: &ChannelId,
+    ) -> Result<Option<ChannelWriteLock>, CoreError> {
+        Ok(self.channel_write_locks.lock().unwrap().get(channel_id).cloned())
+    }
+
+    async fn unlock_channel_writes(&self, channel_id: &ChannelId) -> Result<(), CoreError> {
+        self.channel_write_locks.lock().unwrap().remove(channel_id);
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl AttachmentRepository for MockMessageRepository {
+    async fn insert_attachment_upload(&self, upload: &AttachmentUpload) -> Result<(), CoreError> {
+        self.attachment_uploads.lock().unwrap().push(upload.clone());
+        Ok(())
+    }
+
+    async fn find_attachment_uploads(
+        &self,
+        ids: &[AttachmentId],
+    ) -> Result<Vec<AttachmentUpload>, CoreError> {
+        Ok(self
+            .attachment_uploads
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|upload| ids.contains(&upload.id))
+            .cloned()
+            .collect())
+    }
+
+    async fn attach_uploads(
+        &self,
+        ids: &[AttachmentId],
+        message_id: &MessageId,
+    ) -> Result<(), CoreError> {
+        let mut uploads = self.attachment_uploads.lock().unwrap();
+        let taken = uploads.iter().find(|upload| {
+            ids.contains(&upload.id)
+                && upload.message_id.is_some_and(|posted_with| posted_with != *message_id)
+        });
+        if let Some(upload) = taken {
+            return Err(CoreError::AttachmentNotFound { id: upload.id });
+        }
+        for upload in uploads.iter_mut() {
+            if ids.contains(&upload.id) {
+                upload.message_id = Some(*message_id);
+            }
+        }
+        Ok(())
+    }
+
+    async fn release_uploads(
+        &self,
+        ids: &[AttachmentId],
+        message_id: &MessageId,
+    ) -> Result<(), CoreError> {
+        for upload in self.attachment_uploads.lock().unwrap().iter_mut() {
+            if ids.contains(&upload.id) && upload.message_id == Some(*message_id) {
+                upload.message_id = None;
+            }
+        }
         Ok(())
     }
 }
@@ -1,32 +1,261 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
 use crate::domain::{
-    common::{CoreError, GetPaginated, TotalPaginatedElements, services::Service},
+    common::{
+        CoreError, Cursor, CursorPage, CursorRange, GetCursorPaginated, GetMessagesByCursor,
+        GetPaginated, MessageBound, TotalPaginatedElements, services::Service,
+    },
     health::port::HealthRepository,
     message::{
-        entities::{InsertMessageInput, Message, MessageId, UpdateMessageInput},
-        ports::{MessageRepository, MessageService},
+        entities::{
+            ATTACHMENT_SCANNER_ID, ATTACHMENT_UPLOAD_TTL_SECS, AbuseDetectedEvent, AbusePattern,
+            AddReactionInput, Attachment, AttachmentId, AttachmentQuarantinedEvent,
+            AttachmentUpload, AuthorId, ChannelDigest, ChannelId, ChannelPurge, ChannelSettings,
+            ChannelStorage, ChannelWriteLock, Embed, ImagePreview, ImportMessageInput,
+            ImportedMessages,
+            InsertMessageInput, LegalHold, LegalHoldId, LegalHoldScope, LockChannelWritesInput,
+            MALWARE_REASON_CODE, MAX_ALLOWED_REACTIONS, MAX_CHANNEL_WRITE_LOCK_SECS,
+            MAX_ATTACHMENT_NAME_CHARS, MAX_MODERATION_REASON_TEXT_CHARS, MAX_NONCE_LEN, Message,
+            MessageId, MessageMentionedEvent, MessageRevision, ModerationReason,
+            ModerationReasonTemplate, NotificationBroadcastEvent, NotificationFanoutEvent,
+            NotificationRequestedEvent, PlaceLegalHoldInput, PresignAttachmentInput,
+            PresignedAttachment, RETENTION_SWEEPER_ID, Reaction, ReadMarker, SavedMessage,
+            ScanVerdict, ThreadFollow, ThreadPreferences, UnreadCount, UpdateMessageInput,
+            UserErasure, UserId,
+        },
+        events::MessageEvent,
+        ports::{MessageService, MessageStore, MessageStream},
     },
 };
 
+/// Upper bound on the length of a reaction, enough for multi-codepoint emoji and `:custom_name:`s
+const MAX_REACTION_EMOJI_CHARS: usize = 64;
+
+/// Messages listed in each section of a channel digest
+const DIGEST_SECTION_LIMIT: u32 = 5;
+
 #[async_trait::async_trait]
 impl<S, H> MessageService for Service<S, H>
 where
-    S: MessageRepository,
+    S: MessageStore,
     H: HealthRepository,
 {
-    async fn create_message(&self, input: InsertMessageInput) -> Result<Message, CoreError> {
+    async fn create_message(&self, mut input: InsertMessageInput) -> Result<Message, CoreError> {
         // Validate message content is not empty
         if input.content.trim().is_empty() {
             return Err(CoreError::InvalidMessageName);
         }
 
+        if let Some(nonce) = &input.nonce
+            && (nonce.is_empty() || nonce.chars().count() > MAX_NONCE_LEN)
+        {
+            return Err(CoreError::InvalidMessageNonce { max: MAX_NONCE_LEN });
+        }
+
+        if input.expires_at.is_some_and(|expires_at| expires_at <= Utc::now()) {
+            return Err(CoreError::InvalidMessageExpiry);
+        }
+
+        // Replies must point to an existing message of the same channel, so
+        // threads can always be listed from their parent
+        let mut archived_thread = None;
+        if let Some(parent_id) = &input.reply_to_message_id {
+            match self.message_repository.find_by_id(parent_id).await? {
+                Some(parent) if parent.channel_id == input.channel_id => {
+                    archived_thread = parent.archived_at.map(|_| parent.id);
+                }
+                Some(_) => return Err(CoreError::InvalidReplyTarget { id: *parent_id }),
+                None => return Err(CoreError::MessageNotFound { id: *parent_id }),
+            }
+        }
+
         // @TODO Authorization: Check if the user has permission to create messages
 
+        self.ensure_channel_writable(&input.channel_id).await?;
+
+        if let Some(max) = self.attachment_limits.max_count
+            && input.attachments.len() > max
+        {
+            return Err(CoreError::TooManyAttachments { max });
+        }
+        let mut claimed = Vec::new();
+        if !input.attachments.is_empty() {
+            (input.attachments, claimed) = self.resolve_attachments(&input).await?;
+        }
+        let attachments_size = input.attachments_size();
+        if attachments_size > 0 && self.channel_storage(&input.channel_id).await?.is_exceeded() {
+            return Err(CoreError::ChannelStorageQuotaExceeded {
+                channel_id: input.channel_id,
+            });
+        }
+
+        // Claimed before the message is stored, so of two messages posting
+        // an upload at once, only one is stored
+        let id = input.id;
+        if !claimed.is_empty() {
+            self.message_repository.attach_uploads(&claimed, &id).await?;
+        }
+
         // Create the message via repository
-        let message = self.message_repository.insert(input).await?;
+        let message = match self.message_repository.insert(input).await {
+            Ok(message) => message,
+            Err(e) => {
+                self.release_uploads(&claimed, &id).await;
+                return Err(e);
+            }
+        };
+        if message.id != id {
+            // A retry of a creation whose response the client did not get:
+            // everything else already happened the first time
+            self.release_uploads(&claimed, &id).await;
+            return Ok(message);
+        }
+        self.events.publish(MessageEvent::Created(message.clone()));
+
+        // The reply revives its thread
+        if let Some(thread_id) = archived_thread {
+            match self.message_repository.unarchive_thread(&thread_id).await {
+                Ok(true) => self.publish_updated(&thread_id).await,
+                Ok(false) => {}
+                Err(e) => tracing::warn!(
+                    error = %e,
+                    thread_id = %thread_id,
+                    "failed to unarchive thread"
+                ),
+            }
+        }
+
+        // The message is stored already: notifications and follows are best effort
+        if let Some(thread_id) = message.reply_to_message_id {
+            if let Err(e) = self.notify_thread_followers(&message, &thread_id).await {
+                tracing::warn!(error = %e, thread_id = %thread_id, "failed to notify thread followers");
+            }
+            if let Err(e) = self.auto_follow_thread(&message, &thread_id).await {
+                tracing::warn!(error = %e, thread_id = %thread_id, "failed to follow replied thread");
+            }
+        }
+        if !message.mentions.is_empty() {
+            for event in MessageMentionedEvent::for_message(&message) {
+                if let Err(e) = self.message_repository.publish_mention(&event).await {
+                    tracing::warn!(error = %e, message_id = %message.id, "failed to publish mention");
+                }
+            }
+            if let Err(e) = self.notify_mentions(&message).await {
+                tracing::warn!(error = %e, message_id = %message.id, "failed to notify mentions");
+            }
+        }
+
+        if attachments_size > 0 {
+            // The message is stored already: failing the request would only lead to a duplicate
+            if let Err(e) = self
+                .message_repository
+                .add_channel_storage_usage(&message.channel_id, attachments_size)
+                .await
+            {
+                tracing::warn!(
+                    error = %e,
+                    channel_id = %message.channel_id,
+                    "failed to account attachment storage"
+                );
+            }
+        }
 
         Ok(message)
     }
 
+    async fn import_messages(
+        &self,
+        channel_id: &ChannelId,
+        inputs: Vec<ImportMessageInput>,
+    ) -> Result<ImportedMessages, CoreError> {
+        let now = Utc::now();
+        let parent_ids: Vec<MessageId> = inputs
+            .iter()
+            .filter_map(|input| input.reply_to_message_id)
+            .collect();
+        let stored_parents: HashMap<MessageId, ChannelId> = self
+            .message_repository
+            .find_by_ids(&parent_ids)
+            .await?
+            .into_iter()
+            .map(|parent| (parent.id, parent.channel_id))
+            .collect();
+
+        let mut outcome = ImportedMessages::default();
+        let mut accepted: Vec<(usize, MessageId, u64)> = Vec::new();
+        let mut accepted_ids = HashSet::new();
+        let mut valid = Vec::new();
+        for (position, input) in inputs.into_iter().enumerate() {
+            let rejection = if input.content.trim().is_empty() {
+                Some(CoreError::InvalidMessageName)
+            } else if input.created_at > now
+                || input.updated_at.is_some_and(|updated_at| updated_at < input.created_at)
+            {
+                Some(CoreError::InvalidMessageTimestamps { id: input.id })
+            } else if accepted_ids.contains(&input.id) {
+                Some(CoreError::MessageAlreadyExists { id: input.id })
+            } else {
+                // Replies may target messages imported earlier in the batch
+                input.reply_to_message_id.and_then(|parent_id| {
+                    match stored_parents.get(&parent_id) {
+                        _ if accepted_ids.contains(&parent_id) => None,
+                        Some(parent_channel) if parent_channel == channel_id => None,
+                        Some(_) => Some(CoreError::InvalidReplyTarget { id: parent_id }),
+                        None => Some(CoreError::MessageNotFound { id: parent_id }),
+                    }
+                })
+            };
+
+            match rejection {
+                Some(error) => outcome.rejected.push((position, error)),
+                None => {
+                    accepted_ids.insert(input.id);
+                    accepted.push((position, input.id, input.attachments_size()));
+                    valid.push(input);
+                }
+            }
+        }
+
+        let stored: HashSet<MessageId> = self
+            .message_repository
+            .insert_many(channel_id, valid)
+            .await?
+            .into_iter()
+            .collect();
+
+        let mut attachments_size = 0;
+        for (position, id, size) in accepted {
+            if stored.contains(&id) {
+                outcome.imported.push(id);
+                attachments_size += size;
+            } else {
+                outcome
+                    .rejected
+                    .push((position, CoreError::MessageAlreadyExists { id }));
+            }
+        }
+        outcome.rejected.sort_by_key(|(position, _)| *position);
+
+        // Imported history is kept even past the quota, which only holds back new uploads
+        if attachments_size > 0
+            && let Err(e) = self
+                .message_repository
+                .add_channel_storage_usage(channel_id, attachments_size)
+                .await
+        {
+            tracing::warn!(
+                error = %e,
+                channel_id = %channel_id,
+                "failed to account attachment storage"
+            );
+        }
+
+        Ok(outcome)
+    }
+
     async fn get_message(&self, message_id: &MessageId) -> Result<Message, CoreError> {
         // @TODO Authorization: Check if the user has permission to access the message
 
@@ -35,7 +264,7 @@ where
         match message {
             Some(message) => Ok(message),
             None => Err(CoreError::MessageNotFound {
-                id: message_id.clone(),
+                id: *message_id,
             }),
         }
     }
@@ -52,39 +281,1511 @@ where
         Ok((messages, total))
     }
 
+    async fn get_messages(&self, message_ids: &[MessageId]) -> Result<Vec<Message>, CoreError> {
+        let mut found: HashMap<MessageId, Message> = self
+            .message_repository
+            .find_by_ids(message_ids)
+            .await?
+            .into_iter()
+            .map(|message| (message.id, message))
+            .collect();
+
+        // removing each message once found also drops duplicate IDs
+        Ok(message_ids.iter().filter_map(|id| found.remove(id)).collect())
+    }
+
+    async fn get_message_including_deleted(
+        &self,
+        message_id: &MessageId,
+    ) -> Result<Message, CoreError> {
+        self.message_repository
+            .find_by_id_including_deleted(message_id)
+            .await?
+            .ok_or(CoreError::MessageNotFound { id: *message_id })
+    }
+
+    async fn list_messages_including_deleted(
+        &self,
+        channel_id: &ChannelId,
+        pagination: &GetPaginated,
+    ) -> Result<(Vec<Message>, TotalPaginatedElements), CoreError> {
+        self.message_repository
+            .list_including_deleted(channel_id, pagination)
+            .await
+    }
+
+    async fn export_channel_messages(
+        &self,
+        channel_id: &ChannelId,
+        include_deleted: bool,
+    ) -> Result<MessageStream, CoreError> {
+        self.message_repository
+            .stream_channel(channel_id, include_deleted)
+            .await
+    }
+
+    async fn export_user_messages(
+        &self,
+        user_id: &UserId,
+        include_deleted: bool,
+    ) -> Result<MessageStream, CoreError> {
+        self.message_repository
+            .stream_by_author(&AuthorId(user_id.0), include_deleted)
+            .await
+    }
+
+    async fn list_author_messages(
+        &self,
+        author_id: &AuthorId,
+        channel_id: &ChannelId,
+        pagination: &GetPaginated,
+    ) -> Result<(Vec<Message>, TotalPaginatedElements), CoreError> {
+        self.message_repository
+            .list_by_author(author_id, channel_id, pagination)
+            .await
+    }
+
+    async fn list_threads(
+        &self,
+        channel_id: &ChannelId,
+        include_archived: bool,
+        pagination: &GetPaginated,
+    ) -> Result<(Vec<Message>, TotalPaginatedElements), CoreError> {
+        self.message_repository
+            .list_threads(channel_id, include_archived, pagination)
+            .await
+    }
+
+    async fn follow_thread(
+        &self,
+        user_id: &UserId,
+        thread_id: &MessageId,
+    ) -> Result<ThreadFollow, CoreError> {
+        let thread = self
+            .message_repository
+            .find_by_id(thread_id)
+            .await?
+            .ok_or(CoreError::MessageNotFound { id: *thread_id })?;
+
+        let follow = ThreadFollow {
+            user_id: *user_id,
+            thread_id: thread.id,
+            channel_id: thread.channel_id,
+            followed_at: Utc::now(),
+        };
+        self.message_repository.follow_thread(&follow).await
+    }
+
+    async fn unfollow_thread(
+        &self,
+        user_id: &UserId,
+        thread_id: &MessageId,
+    ) -> Result<(), CoreError> {
+        self.message_repository
+            .unfollow_thread(user_id, thread_id)
+            .await
+    }
+
+    async fn list_followed_threads(
+        &self,
+        user_id: &UserId,
+        pagination: &GetPaginated,
+    ) -> Result<(Vec<Message>, TotalPaginatedElements), CoreError> {
+        self.message_repository
+            .list_followed_threads(user_id, pagination)
+            .await
+    }
+
+    async fn save_message(
+        &self,
+        user_id: &UserId,
+        message_id: &MessageId,
+    ) -> Result<SavedMessage, CoreError> {
+        let message = self
+            .message_repository
+            .find_by_id(message_id)
+            .await?
+            .ok_or(CoreError::MessageNotFound { id: *message_id })?;
+
+        let saved = SavedMessage {
+            user_id: *user_id,
+            message_id: message.id,
+            channel_id: message.channel_id,
+            saved_at: Utc::now(),
+        };
+        self.message_repository.save_message(&saved).await
+    }
+
+    async fn unsave_message(
+        &self,
+        user_id: &UserId,
+        message_id: &MessageId,
+    ) -> Result<(), CoreError> {
+        self.message_repository
+            .unsave_message(user_id, message_id)
+            .await
+    }
+
+    async fn list_saved_messages(
+        &self,
+        user_id: &UserId,
+        pagination: &GetPaginated,
+    ) -> Result<(Vec<Message>, TotalPaginatedElements), CoreError> {
+        self.message_repository
+            .list_saved_messages(user_id, pagination)
+            .await
+    }
+
+    async fn thread_preferences(&self, user_id: &UserId) -> Result<ThreadPreferences, CoreError> {
+        self.message_repository.thread_preferences(user_id).await
+    }
+
+    async fn set_thread_preferences(
+        &self,
+        preferences: ThreadPreferences,
+    ) -> Result<ThreadPreferences, CoreError> {
+        self.message_repository
+            .set_thread_preferences(&preferences)
+            .await?;
+        Ok(preferences)
+    }
+
+    async fn read_marker(
+        &self,
+        user_id: &UserId,
+        channel_id: &ChannelId,
+    ) -> Result<ReadMarker, CoreError> {
+        self.message_repository
+            .read_marker(user_id, channel_id)
+            .await?
+            .ok_or(CoreError::ReadMarkerNotFound {
+                channel_id: *channel_id,
+            })
+    }
+
+    async fn set_read_marker(
+        &self,
+        user_id: &UserId,
+        channel_id: &ChannelId,
+        message_id: &MessageId,
+    ) -> Result<ReadMarker, CoreError> {
+        let message = self
+            .message_repository
+            .find_by_id(message_id)
+            .await?
+            .filter(|message| message.channel_id == *channel_id)
+            .ok_or(CoreError::MessageNotFound { id: *message_id })?;
+
+        self.message_repository
+            .set_read_marker(&ReadMarker {
+                user_id: *user_id,
+                channel_id: *channel_id,
+                last_read_message_id: message.id,
+                last_read_sequence: message.sequence,
+                read_at: Utc::now(),
+            })
+            .await
+    }
+
+    async fn read_markers(&self, user_id: &UserId) -> Result<Vec<ReadMarker>, CoreError> {
+        self.message_repository.read_markers_of_user(user_id).await
+    }
+
+    async fn unread_counts(&self, markers: &[ReadMarker]) -> Result<Vec<UnreadCount>, CoreError> {
+        if markers.is_empty() {
+            return Ok(Vec::new());
+        }
+        let counts = self.message_repository.count_unread(markers).await?;
+        Ok(markers
+            .iter()
+            .map(|marker| UnreadCount {
+                channel_id: marker.channel_id,
+                last_read_sequence: marker.last_read_sequence,
+                unread_count: counts.get(&marker.channel_id).copied().unwrap_or(0),
+            })
+            .collect())
+    }
+
+    async fn archive_idle_threads(
+        &self,
+        idle_before: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<u64, CoreError> {
+        let archived = self
+            .message_repository
+            .archive_idle_threads(idle_before, limit)
+            .await?;
+        let count = archived.len() as u64;
+        for thread in archived {
+            self.events.publish(MessageEvent::Updated(thread));
+        }
+        Ok(count)
+    }
+
+    async fn list_pinned_messages(
+        &self,
+        channel_id: &ChannelId,
+        pagination: &GetPaginated,
+    ) -> Result<(Vec<Message>, TotalPaginatedElements), CoreError> {
+        self.message_repository
+            .list_pinned(channel_id, pagination)
+            .await
+    }
+
     async fn update_message(&self, input: UpdateMessageInput) -> Result<Message, CoreError> {
         // Check if message exists
         let existing_message = self.message_repository.find_by_id(&input.id).await?;
 
-        if existing_message.is_none() {
+        let Some(existing_message) = existing_message else {
             return Err(CoreError::MessageNotFound {
-                id: input.id.clone(),
+                id: input.id,
             });
-        }
+        };
 
         // @TODO Authorization: Verify user is the message owner or has admin privileges
 
+        self.ensure_channel_writable(&existing_message.channel_id).await?;
+
+        // Only pinning counts against the limit, so edits of pinned messages still work
+        let pinning = input.is_pinned == Some(true) && !existing_message.is_pinned;
+        if let (Some(limit), true) = (self.pin_limit, pinning) {
+            let channel_id = existing_message.channel_id;
+            if self.message_repository.count_pinned(&channel_id).await? >= limit {
+                return Err(CoreError::PinLimitReached { channel_id, limit });
+            }
+        }
+
         // Update the message
         let updated_message = self.message_repository.update(input).await?;
+        self.events.publish(MessageEvent::Updated(updated_message.clone()));
 
         Ok(updated_message)
     }
 
+    async fn get_message_history(
+        &self,
+        message_id: &MessageId,
+    ) -> Result<Vec<MessageRevision>, CoreError> {
+        // purged messages have no history left either
+        self.get_message_including_deleted(message_id).await?;
+        self.message_repository.list_revisions(message_id).await
+    }
+
     async fn delete_message(&self, message_id: &MessageId) -> Result<(), CoreError> {
         // Check if message exists
-        let existing_message = self.message_repository.find_by_id(message_id).await?;
-
-        if existing_message.is_none() {
-            return Err(CoreError::MessageNotFound {
-                id: message_id.clone(),
-            });
-        }
+        let existing_message = self
+            .message_repository
+            .find_by_id(message_id)
+            .await?
+            .ok_or(CoreError::MessageNotFound { id: *message_id })?;
 
         // @TODO Authorization: Verify user is the message owner or has admin privileges
 
+        self.ensure_channel_writable(&existing_message.channel_id).await?;
+
         // Delete the message
         self.message_repository.delete(message_id).await?;
+        self.events.publish(MessageEvent::Deleted {
+            id: *message_id,
+            channel_id: existing_message.channel_id,
+        });
+
+        // The message is deleted already: failing the request would only lead to a retry
+        if let Err(e) = self.detect_mass_deletion(&existing_message).await {
+            tracing::warn!(
+                error = %e,
+                author_id = %existing_message.author_id,
+                "failed to check for mass deletion"
+            );
+        }
+
+        Ok(())
+    }
+
+    async fn bulk_delete_messages(
+        &self,
+        channel_id: &ChannelId,
+        message_ids: &[MessageId],
+        deleted_by: &UserId,
+        reason: Option<ModerationReason>,
+    ) -> Result<Vec<MessageId>, CoreError> {
+        let reason = reason
+            .map(|reason| self.resolve_moderation_reason(reason))
+            .transpose()?;
+        self.ensure_channel_writable(channel_id).await?;
+
+        let deleted = self
+            .message_repository
+            .delete_many(channel_id, message_ids, deleted_by, reason.as_ref())
+            .await?;
+        // Moderators deleting the messages of others is no abuse of their authors
+        let deleted_at = Utc::now();
+        for message in &deleted {
+            self.events.publish(MessageEvent::Deleted {
+                id: message.id,
+                channel_id: message.channel_id,
+            });
+
+            // The messages are deleted already: notifications are best effort
+            let Some(event) = NotificationRequestedEvent::for_moderation(
+                message,
+                *deleted_by,
+                reason.clone(),
+                message.deleted_at.unwrap_or(deleted_at),
+            ) else {
+                continue;
+            };
+            if let Err(e) = self.message_repository.request_notification(&event).await {
+                tracing::warn!(error = %e, message_id = %message.id, "failed to notify moderated author");
+            }
+        }
+
+        Ok(deleted.into_iter().map(|message| message.id).collect())
+    }
+
+    fn moderation_reason_templates(&self) -> Vec<ModerationReasonTemplate> {
+        let mut templates: Vec<ModerationReasonTemplate> = self
+            .moderation_reasons
+            .iter()
+            .map(|(code, text)| ModerationReasonTemplate {
+                code: code.clone(),
+                text: text.clone(),
+            })
+            .collect();
+        templates.sort_by(|a, b| a.code.cmp(&b.code));
+        templates
+    }
+
+    async fn hard_delete_message(&self, message_id: &MessageId) -> Result<(), CoreError> {
+        let message = self
+            .message_repository
+            .find_by_id_including_deleted(message_id)
+            .await?
+            .ok_or(CoreError::MessageNotFound { id: *message_id })?;
+
+        let holds = self.message_repository.list_legal_holds(None).await?;
+        let held = holds.iter().any(|hold| match hold.scope {
+            LegalHoldScope::Channel(channel_id) => channel_id == message.channel_id,
+            LegalHoldScope::User(user_id) => user_id.0 == message.author_id.0,
+        });
+        if held {
+            return Err(CoreError::MessageUnderLegalHold { id: *message_id });
+        }
+
+        self.message_repository.hard_delete(message_id).await?;
+        // Already announced when it was soft deleted
+        if message.deleted_at.is_none() {
+            self.events.publish(MessageEvent::Deleted {
+                id: *message_id,
+                channel_id: message.channel_id,
+            });
+        }
+        self.release_attachment_storage(&message).await;
+
+        Ok(())
+    }
+
+    async fn purge_deleted_messages(
+        &self,
+        deleted_before: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<u64, CoreError> {
+        let (held_channels, held_authors) = self.held_scopes().await?;
+        let expired = self
+            .message_repository
+            .list_deleted_before(deleted_before, &held_channels, &held_authors, limit)
+            .await?;
+
+        self.purge(expired).await
+    }
+
+    async fn purge_expired_messages(
+        &self,
+        expired_before: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<u64, CoreError> {
+        let (held_channels, held_authors) = self.held_scopes().await?;
+        let expired = self
+            .message_repository
+            .list_expired_before(expired_before, &held_channels, &held_authors, limit)
+            .await?;
+
+        self.purge(expired).await
+    }
+
+    async fn delete_channel_messages(
+        &self,
+        channel_id: &ChannelId,
+    ) -> Result<ChannelPurge, CoreError> {
+        let holds = self.message_repository.list_legal_holds(None).await?;
+
+        let channel_holds: Vec<String> = holds
+            .iter()
+            .filter(|hold| hold.scope == LegalHoldScope::Channel(*channel_id))
+            .map(|hold| hold.reference.clone())
+            .collect();
+        if !channel_holds.is_empty() {
+            return Ok(ChannelPurge {
+                deleted: 0,
+                held_by: channel_holds,
+            });
+        }
+
+        // Only the holds of users who wrote in the channel kept anything
+        let mut held_authors = Vec::new();
+        let mut held_by = Vec::new();
+        for hold in &holds {
+            let LegalHoldScope::User(user_id) = hold.scope else {
+                continue;
+            };
+            let author_id = AuthorId::from(user_id.0);
+            let (_, written) = self
+                .message_repository
+                .list_by_author(&author_id, channel_id, &GetPaginated { page: 1, limit: 1 })
+                .await?;
+            if written > 0 {
+                held_authors.push(author_id);
+                held_by.push(hold.reference.clone());
+            }
+        }
+
+        // The channel is gone along with its subscribers, so no per-message
+        // event is published
+        let deleted = self
+            .message_repository
+            .delete_by_channel(channel_id, &held_authors)
+            .await?;
+
+        Ok(ChannelPurge { deleted, held_by })
+    }
+
+    async fn erase_user(
+        &self,
+        user_id: &UserId,
+        erased_by: &UserId,
+    ) -> Result<UserErasure, CoreError> {
+        let holds = self.message_repository.list_legal_holds(None).await?;
+
+        let user_holds: Vec<String> = holds
+            .iter()
+            .filter(|hold| hold.scope == LegalHoldScope::User(*user_id))
+            .map(|hold| hold.reference.clone())
+            .collect();
+        if !user_holds.is_empty() {
+            return Ok(UserErasure {
+                erased: 0,
+                channels: Vec::new(),
+                held_by: user_holds,
+            });
+        }
+
+        // Only the holds of channels the user wrote in are reported as keeping anything
+        let author_id = AuthorId::from(user_id.0);
+        let mut held_channels = Vec::new();
+        let mut held_by = Vec::new();
+        for hold in &holds {
+            let LegalHoldScope::Channel(channel_id) = hold.scope else {
+                continue;
+            };
+            held_channels.push(channel_id);
+            let (_, written) = self
+                .message_repository
+                .list_by_author(&author_id, &channel_id, &GetPaginated { page: 1, limit: 1 })
+                .await?;
+            if written > 0 {
+                held_by.push(hold.reference.clone());
+            }
+        }
+
+        let erased = self
+            .message_repository
+            .delete_by_author(&author_id, &held_channels, erased_by)
+            .await?;
+        self.message_repository
+            .delete_thread_follows_by_user(user_id)
+            .await?;
+        self.message_repository
+            .delete_saved_messages_by_user(user_id)
+            .await?;
+        self.message_repository
+            .delete_read_markers_by_user(user_id)
+            .await?;
+        if let Some(archiver) = &self.archiver {
+            let archived = archiver.erase_author(&author_id, &held_channels).await?;
+            tracing::info!(user_id = %user_id, archived, "erased archived messages");
+        }
+        let mut channels = Vec::new();
+        for message in &erased {
+            if !channels.contains(&message.channel_id) {
+                channels.push(message.channel_id);
+            }
+            self.release_attachment_storage(message).await;
+            // Announced again when deleted before, so the search indexes drop
+            // whatever they still hold of it
+            self.events.publish(MessageEvent::Deleted {
+                id: message.id,
+                channel_id: message.channel_id,
+            });
+        }
+
+        Ok(UserErasure {
+            erased: erased.len() as u64,
+            channels,
+            held_by,
+        })
+    }
+
+    async fn add_reaction(&self, input: AddReactionInput) -> Result<Reaction, CoreError> {
+        let emoji = input.emoji.trim().to_string();
+        if emoji.is_empty() || emoji.chars().count() > MAX_REACTION_EMOJI_CHARS {
+            return Err(CoreError::InvalidReaction { emoji: input.emoji });
+        }
+
+        // Check if message exists
+        let message = self
+            .message_repository
+            .find_by_id(&input.message_id)
+            .await?
+            .ok_or(CoreError::MessageNotFound {
+                id: input.message_id,
+            })?;
+        self.ensure_channel_writable(&message.channel_id).await?;
+
+        let settings = self
+            .message_repository
+            .channel_settings(&message.channel_id)
+            .await?;
+        if !settings.allows_reaction(&emoji) {
+            return Err(CoreError::ReactionNotAllowed {
+                emoji,
+                allowed: settings.allowed_reactions.unwrap_or_default(),
+            });
+        }
+
+        let input = AddReactionInput { emoji, ..input };
+        let reaction = self.message_repository.add_reaction(input).await?;
+
+        // Let the author know, unless they muted reaction notifications
+        let muted = self
+            .message_repository
+            .reaction_notifications_muted(&UserId(message.author_id.0), &message.id)
+            .await?;
+        if let Some(event) = NotificationRequestedEvent::for_reaction(&message, &reaction, muted) {
+            self.message_repository.request_notification(&event).await?;
+        }
+
+        Ok(reaction)
+    }
 
+    async fn remove_reaction(
+        &self,
+        message_id: &MessageId,
+        user_id: &UserId,
+        emoji: &str,
+    ) -> Result<(), CoreError> {
+        self.message_repository
+            .remove_reaction(message_id, user_id, emoji.trim())
+            .await
+    }
+
+    async fn list_reactions(&self, message_id: &MessageId) -> Result<Vec<Reaction>, CoreError> {
+        // Check if message exists
+        if self.message_repository.find_by_id(message_id).await?.is_none() {
+            return Err(CoreError::MessageNotFound { id: *message_id });
+        }
+
+        self.message_repository.list_reactions(message_id).await
+    }
+
+    async fn list_reaction_users(
+        &self,
+        message_id: &MessageId,
+        emoji: &str,
+        pagination: &GetCursorPaginated,
+    ) -> Result<CursorPage<Reaction>, CoreError> {
+        // Check if message exists
+        if self.message_repository.find_by_id(message_id).await?.is_none() {
+            return Err(CoreError::MessageNotFound { id: *message_id });
+        }
+
+        self.message_repository
+            .list_reaction_users(message_id, emoji.trim(), pagination)
+            .await
+    }
+
+    async fn list_messages_by_cursor(
+        &self,
+        channel_id: &ChannelId,
+        query: &GetMessagesByCursor,
+    ) -> Result<CursorPage<Message>, CoreError> {
+        // A cursor comes from a page within `before`, it is the tighter bound
+        let before = match (query.pagination.decoded_cursor()?, query.before) {
+            (Some(cursor), _) => Some(cursor),
+            (None, Some(bound)) => Some(self.resolve_bound(channel_id, bound, true).await?),
+            (None, None) => None,
+        };
+        let after = match query.after {
+            Some(bound) => Some(self.resolve_bound(channel_id, bound, false).await?),
+            None => None,
+        };
+
+        self.message_repository
+            .list_by_cursor(
+                channel_id,
+                &CursorRange { before, after },
+                query.pagination.effective_limit(),
+            )
+            .await
+    }
+
+    async fn list_replies(
+        &self,
+        message_id: &MessageId,
+        pagination: &GetCursorPaginated,
+    ) -> Result<CursorPage<Message>, CoreError> {
+        // Check if message exists
+        if self.message_repository.find_by_id(message_id).await?.is_none() {
+            return Err(CoreError::MessageNotFound { id: *message_id });
+        }
+
+        self.message_repository.list_replies(message_id, pagination).await
+    }
+
+    async fn mute_reaction_notifications(
+        &self,
+        user_id: &UserId,
+        message_id: Option<&MessageId>,
+    ) -> Result<(), CoreError> {
+        if let Some(message_id) = message_id {
+            // Check if message exists
+            if self.message_repository.find_by_id(message_id).await?.is_none() {
+                return Err(CoreError::MessageNotFound { id: *message_id });
+            }
+        }
+
+        self.message_repository
+            .set_reaction_notifications_muted(user_id, message_id, true)
+            .await
+    }
+
+    async fn unmute_reaction_notifications(
+        &self,
+        user_id: &UserId,
+        message_id: Option<&MessageId>,
+    ) -> Result<(), CoreError> {
+        self.message_repository
+            .set_reaction_notifications_muted(user_id, message_id, false)
+            .await
+    }
+
+    async fn channel_storage(&self, channel_id: &ChannelId) -> Result<ChannelStorage, CoreError> {
+        let storage = self.message_repository.channel_storage(channel_id).await?;
+        Ok(storage.with_default_quota(self.default_storage_quota))
+    }
+
+    async fn set_channel_storage_quota(
+        &self,
+        channel_id: &ChannelId,
+        quota_bytes: Option<u64>,
+    ) -> Result<ChannelStorage, CoreError> {
+        let storage = self
+            .message_repository
+            .set_channel_storage_quota(channel_id, quota_bytes)
+            .await?;
+        Ok(storage.with_default_quota(self.default_storage_quota))
+    }
+
+    async fn channel_settings(&self, channel_id: &ChannelId) -> Result<ChannelSettings, CoreError> {
+        self.message_repository.channel_settings(channel_id).await
+    }
+
+    async fn set_allowed_reactions(
+        &self,
+        channel_id: &ChannelId,
+        allowed: Option<Vec<String>>,
+    ) -> Result<ChannelSettings, CoreError> {
+        let allowed = match allowed {
+            Some(emojis) => {
+                let mut allowed: Vec<String> = Vec::new();
+                for emoji in emojis {
+                    let trimmed = emoji.trim();
+                    if trimmed.is_empty() || trimmed.chars().count() > MAX_REACTION_EMOJI_CHARS {
+                        return Err(CoreError::InvalidReaction { emoji });
+                    }
+                    if !allowed.iter().any(|allowed| allowed == trimmed) {
+                        allowed.push(trimmed.to_string());
+                    }
+                }
+                if allowed.len() > MAX_ALLOWED_REACTIONS {
+                    return Err(CoreError::TooManyAllowedReactions {
+                        max: MAX_ALLOWED_REACTIONS,
+                    });
+                }
+                Some(allowed)
+            }
+            None => None,
+        };
+
+        self.message_repository
+            .set_allowed_reactions(channel_id, allowed)
+            .await
+    }
+
+    async fn set_channel_retention(
+        &self,
+        channel_id: &ChannelId,
+        retention_secs: Option<u64>,
+    ) -> Result<ChannelSettings, CoreError> {
+        self.message_repository
+            .set_channel_retention(channel_id, retention_secs)
+            .await
+    }
+
+    async fn delete_expired_messages(
+        &self,
+        default_retention: Option<chrono::Duration>,
+        batch_size: usize,
+    ) -> Result<u64, CoreError> {
+        let mut held_channels = Vec::new();
+        let mut held_authors = Vec::new();
+        for hold in self.message_repository.list_legal_holds(None).await? {
+            match hold.scope {
+                LegalHoldScope::Channel(channel_id) => held_channels.push(channel_id),
+                LegalHoldScope::User(user_id) => held_authors.push(AuthorId::from(user_id.0)),
+            }
+        }
+
+        // a retention beyond the representable dates deletes nothing
+        let now = Utc::now();
+        let expiry = |retention: chrono::Duration| {
+            (retention > chrono::Duration::zero())
+                .then(|| now.checked_sub_signed(retention))
+                .flatten()
+        };
+
+        let custom = self.message_repository.list_channel_retentions().await?;
+        let mut deleted = 0;
+        for settings in &custom {
+            if held_channels.contains(&settings.channel_id) {
+                continue;
+            }
+            let Some(before) = settings
+                .retention_secs
+                .and_then(|secs| chrono::Duration::try_seconds(secs.min(i64::MAX as u64) as i64))
+                .and_then(expiry)
+            else {
+                continue;
+            };
+            deleted += self
+                .delete_created_before(
+                    Some(&settings.channel_id),
+                    before,
+                    &[],
+                    &held_authors,
+                    batch_size,
+                )
+                .await?;
+        }
+
+        if let Some(before) = default_retention.and_then(expiry) {
+            let mut excluded = held_channels;
+            excluded.extend(custom.iter().map(|settings| settings.channel_id));
+            deleted += self
+                .delete_created_before(None, before, &excluded, &held_authors, batch_size)
+                .await?;
+        }
+
+        Ok(deleted)
+    }
+
+    async fn channel_digest(
+        &self,
+        channel_id: &ChannelId,
+        since: DateTime<Utc>,
+    ) -> Result<ChannelDigest, CoreError> {
+        self.message_repository
+            .channel_digest(channel_id, since, DIGEST_SECTION_LIMIT)
+            .await
+    }
+
+    async fn most_active_channels(
+        &self,
+        since: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<ChannelId>, CoreError> {
+        self.message_repository
+            .most_active_channels(since, limit)
+            .await
+    }
+
+    async fn last_messages(&self, channel_ids: &[ChannelId]) -> Result<Vec<Message>, CoreError> {
+        if channel_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut found: HashMap<ChannelId, Message> = self
+            .message_repository
+            .last_messages(channel_ids)
+            .await?
+            .into_iter()
+            .map(|message| (message.channel_id, message))
+            .collect();
+        Ok(channel_ids
+            .iter()
+            .filter_map(|channel_id| found.remove(channel_id))
+            .collect())
+    }
+
+    async fn place_legal_hold(&self, input: PlaceLegalHoldInput) -> Result<LegalHold, CoreError> {
+        let reference = input.reference.trim().to_string();
+        if reference.is_empty() {
+            return Err(CoreError::InvalidLegalHoldReference);
+        }
+
+        self.message_repository
+            .place_legal_hold(PlaceLegalHoldInput { reference, ..input })
+            .await
+    }
+
+    async fn get_legal_hold(&self, id: &LegalHoldId) -> Result<LegalHold, CoreError> {
+        self.message_repository
+            .find_legal_hold(id)
+            .await?
+            .ok_or(CoreError::LegalHoldNotFound { id: *id })
+    }
+
+    async fn list_legal_holds(&self, scope: &LegalHoldScope) -> Result<Vec<LegalHold>, CoreError> {
+        self.message_repository.list_legal_holds(Some(scope)).await
+    }
+
+    async fn release_legal_hold(&self, id: &LegalHoldId) -> Result<(), CoreError> {
+        self.message_repository.release_legal_hold(id).await
+    }
+
+    async fn lock_channel_writes(
+        &self,
+        input: LockChannelWritesInput,
+    ) -> Result<ChannelWriteLock, CoreError> {
+        let seconds = input.duration.num_seconds();
+        if !(1..=MAX_CHANNEL_WRITE_LOCK_SECS).contains(&seconds) {
+            return Err(CoreError::InvalidChannelWriteLock {
+                max_secs: MAX_CHANNEL_WRITE_LOCK_SECS,
+            });
+        }
+
+        let now = Utc::now();
+        let lock = ChannelWriteLock {
+            channel_id: input.channel_id,
+            reason: input.reason.trim().to_string(),
+            locked_by: input.locked_by,
+            locked_at: now,
+            expires_at: now + input.duration,
+        };
+        self.message_repository.lock_channel_writes(&lock).await?;
+        Ok(lock)
+    }
+
+    async fn channel_write_lock(
+        &self,
+        channel_id: &ChannelId,
+    ) -> Result<Option<ChannelWriteLock>, CoreError> {
+        let lock = self.message_repository.find_channel_write_lock(channel_id).await?;
+        Ok(lock.filter(|lock| lock.expires_at > Utc::now()))
+    }
+
+    async fn unlock_channel_writes(&self, channel_id: &ChannelId) -> Result<(), CoreError> {
+        self.message_repository.unlock_channel_writes(channel_id).await
+    }
+
+    async fn presign_attachment(
+        &self,
+        input: PresignAttachmentInput,
+    ) -> Result<PresignedAttachment, CoreError> {
+        let storage = self
+            .attachment_storage
+            .as_ref()
+            .ok_or(CoreError::AttachmentUploadsDisabled)?;
+
+        let name = input.name.trim();
+        if name.is_empty()
+            || name.chars().count() > MAX_ATTACHMENT_NAME_CHARS
+            || input.content_type.trim().is_empty()
+            || input.size == 0
+        {
+            return Err(CoreError::InvalidAttachment {
+                max_name_chars: MAX_ATTACHMENT_NAME_CHARS,
+            });
+        }
+        self.check_attachment_limits(&input.content_type, input.size)?;
+
+        let id = AttachmentId::from(Uuid::new_v4());
+        let created_at = Utc::now();
+        let ttl = chrono::Duration::seconds(ATTACHMENT_UPLOAD_TTL_SECS);
+        let upload = AttachmentUpload {
+            id,
+            channel_id: input.channel_id,
+            uploader_id: input.uploader_id,
+            name: name.to_string(),
+            content_type: input.content_type.trim().to_string(),
+            size: input.size,
+            object_key: format!("attachments/{}/{id}", input.channel_id),
+            created_at,
+            expires_at: created_at + ttl,
+            message_id: None,
+        };
+
+        let upload_url = storage
+            .presign_upload(&upload.object_key, ttl.to_std().unwrap_or_default())
+            .await?;
+        self.message_repository.insert_attachment_upload(&upload).await?;
+
+        Ok(PresignedAttachment { upload, upload_url })
+    }
+
+    async fn scan_attachments(&self, message: &Message) -> Result<ScanVerdict, CoreError> {
+        let (Some(scanner), Some(storage)) = (&self.attachment_scanner, &self.attachment_storage)
+        else {
+            return Ok(ScanVerdict::Clean);
+        };
+        let ids: Vec<AttachmentId> = message.attachments.iter().map(|a| a.id).collect();
+        if ids.is_empty() {
+            return Ok(ScanVerdict::Clean);
+        }
+
+        // attachments posted before uploads were registered are not scanned
+        let uploads = self.message_repository.find_attachment_uploads(&ids).await?;
+        for upload in uploads {
+            let Some(content) = storage.fetch(&upload.object_key).await? else {
+                continue;
+            };
+            let verdict = scanner.scan(&content).await?;
+            let ScanVerdict::Infected { signature } = &verdict else {
+                continue;
+            };
+
+            let reason = ModerationReason {
+                code: MALWARE_REASON_CODE.to_string(),
+                text: Some(format!("Attachment infected by {signature}")),
+            };
+            let removed = self
+                .message_repository
+                .delete_many(
+                    &message.channel_id,
+                    &[message.id],
+                    &ATTACHMENT_SCANNER_ID,
+                    Some(&reason),
+                )
+                .await?;
+            for message in &removed {
+                self.events.publish(MessageEvent::Deleted {
+                    id: message.id,
+                    channel_id: message.channel_id,
+                });
+            }
+            self.message_repository
+                .report_quarantine(&AttachmentQuarantinedEvent {
+                    message_id: message.id,
+                    channel_id: message.channel_id,
+                    author_id: message.author_id,
+                    attachment_id: upload.id,
+                    signature: signature.clone(),
+                    quarantined_at: Utc::now(),
+                })
+                .await?;
+            tracing::warn!(
+                message_id = %message.id,
+                attachment_id = %upload.id,
+                %signature,
+                "quarantined a message with an infected attachment"
+            );
+            return Ok(verdict);
+        }
+
+        Ok(ScanVerdict::Clean)
+    }
+
+    async fn unfurl_links(&self, message: &Message) -> Result<Option<Message>, CoreError> {
+        let Some(unfurler) = &self.link_unfurler else {
+            return Ok(None);
+        };
+
+        let mut embeds: Vec<Embed> = Vec::new();
+        for url in self.link_preview_policy.links_to_preview(&message.content) {
+            match unfurler.unfurl(&url).await {
+                Ok(Some(embed)) => embeds.push(embed),
+                Ok(None) => {}
+                Err(e) => {
+                    tracing::debug!(
+                        error = %e,
+                        message_id = %message.id,
+                        %url,
+                        "failed to unfurl a link"
+                    );
+                }
+            }
+        }
+        if embeds.is_empty() {
+            return Ok(None);
+        }
+
+        let updated = self
+            .message_repository
+            .set_embeds(&message.id, message.revision, &embeds)
+            .await?;
+        if let Some(updated) = &updated {
+            self.events.publish(MessageEvent::Updated(updated.clone()));
+        }
+        Ok(updated)
+    }
+}
+
+impl<S, H> Service<S, H>
+where
+    S: MessageStore,
+    H: HealthRepository,
+{
+    /// Position of a listing bound. An instant is placed before the messages
+    /// posted at that instant for an `upper` bound and after them otherwise,
+    /// so that either way they are excluded like the bound itself.
+    async fn resolve_bound(
+        &self,
+        channel_id: &ChannelId,
+        bound: MessageBound,
+        upper: bool,
+    ) -> Result<Cursor, CoreError> {
+        match bound {
+            MessageBound::Message(id) => match self.message_repository.find_by_id(&id).await? {
+                Some(message) if &message.channel_id == channel_id => {
+                    Ok(Cursor::new(message.created_at, message.id.0))
+                }
+                _ => Err(CoreError::InvalidCursor),
+            },
+            MessageBound::Timestamp(at) => {
+                let id = if upper { Uuid::nil() } else { Uuid::max() };
+                Ok(Cursor::new(at, id))
+            }
+        }
+    }
+
+    /// Refuse writes to a channel while it is locked for a migration or an import
+    async fn ensure_channel_writable(&self, channel_id: &ChannelId) -> Result<(), CoreError> {
+        match self.channel_write_lock(channel_id).await? {
+            Some(lock) => Err(CoreError::ChannelWriteLocked {
+                channel_id: *channel_id,
+                retry_after_secs: lock.retry_after_secs(Utc::now()),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Give back the uploads claimed for a message that was not stored
+    async fn release_uploads(&self, ids: &[AttachmentId], message_id: &MessageId) {
+        if ids.is_empty() {
+            return;
+        }
+        if let Err(e) = self.message_repository.release_uploads(ids, message_id).await {
+            tracing::warn!(error = %e, message_id = %message_id, "failed to release attachments");
+        }
+    }
+
+    /// The attachments of `input`, completed from their uploads, and the
+    /// uploads no message was posted with yet. Each must have been presigned
+    /// in the channel of the message by its author and uploaded, and not be
+    /// posted with another message.
+    async fn resolve_attachments(
+        &self,
+        input: &InsertMessageInput,
+    ) -> Result<(Vec<Attachment>, Vec<AttachmentId>), CoreError> {
+        let storage = self
+            .attachment_storage
+            .as_ref()
+            .ok_or(CoreError::AttachmentUploadsDisabled)?;
+
+        let ids: Vec<AttachmentId> = input.attachments.iter().map(|a| a.id).collect();
+        let mut uploads: HashMap<AttachmentId, AttachmentUpload> = self
+            .message_repository
+            .find_attachment_uploads(&ids)
+            .await?
+            .into_iter()
+            .map(|upload| (upload.id, upload))
+            .collect();
+
+        let mut attachments = Vec::with_capacity(ids.len());
+        let mut unclaimed = Vec::new();
+        for id in ids {
+            // taken out, so an attachment cannot be posted twice in a message
+            let upload = uploads
+                .remove(&id)
+                .filter(|upload| {
+                    upload.channel_id == input.channel_id && upload.uploader_id == input.author_id
+                })
+                .ok_or(CoreError::AttachmentNotFound { id })?;
+            match upload.message_id {
+                Some(message_id) => {
+                    if !self.is_retry_of(input, &message_id).await? {
+                        return Err(CoreError::AttachmentNotFound { id });
+                    }
+                }
+                None => unclaimed.push(id),
+            }
+
+            // what was uploaded counts, whatever size was announced
+            let size = storage
+                .object_size(&upload.object_key)
+                .await?
+                .ok_or(CoreError::AttachmentNotUploaded { id })?;
+            self.check_attachment_limits(&upload.content_type, size)?;
+            let url = storage.download_url(&upload.object_key);
+            let preview = self.image_preview(&url, &upload.content_type).await;
+            attachments.push(Attachment {
+                id,
+                url,
+                name: upload.name,
+                size,
+                preview,
+            });
+        }
+
+        Ok((attachments, unclaimed))
+    }
+
+    /// Preview of an image attachment, `None` for other files and when the
+    /// generator fails, which does not keep the message from being posted
+    async fn image_preview(&self, url: &str, content_type: &str) -> Option<ImagePreview> {
+        let generator = self.thumbnail_generator.as_ref()?;
+        if !content_type.trim().to_ascii_lowercase().starts_with("image/") {
+            return None;
+        }
+
+        match generator.preview(url, content_type).await {
+            Ok(preview) => preview,
+            Err(e) => {
+                tracing::warn!(error = %e, %url, "failed to preview an image attachment");
+                None
+            }
+        }
+    }
+
+    /// Check an attachment of `size` bytes and `content_type` against the limits
+    fn check_attachment_limits(&self, content_type: &str, size: u64) -> Result<(), CoreError> {
+        if let Some(max_bytes) = self.attachment_limits.max_size_bytes
+            && size > max_bytes
+        {
+            return Err(CoreError::AttachmentTooLarge { max_bytes });
+        }
+        if !self.attachment_limits.allows_content_type(content_type) {
+            return Err(CoreError::AttachmentTypeNotAllowed {
+                content_type: content_type.to_string(),
+            });
+        }
         Ok(())
     }
+
+    /// Whether `input` retries the creation of the message `message_id`,
+    /// made by the same author in the same channel with the same nonce
+    async fn is_retry_of(
+        &self,
+        input: &InsertMessageInput,
+        message_id: &MessageId,
+    ) -> Result<bool, CoreError> {
+        let Some(nonce) = &input.nonce else {
+            return Ok(false);
+        };
+        Ok(self
+            .message_repository
+            .find_by_id(message_id)
+            .await?
+            .is_some_and(|message| {
+                message.nonce.as_ref() == Some(nonce)
+                    && message.author_id == input.author_id
+                    && message.channel_id == input.channel_id
+            }))
+    }
+
+    /// Soft delete the messages created before `before`, `batch_size` at a
+    /// time, like a moderator would, after archiving them when an archiver is
+    /// configured; see [`MessageRepository::list_created_before`]
+    async fn delete_created_before(
+        &self,
+        channel_id: Option<&ChannelId>,
+        before: DateTime<Utc>,
+        excluded_channels: &[ChannelId],
+        held_authors: &[AuthorId],
+        batch_size: usize,
+    ) -> Result<u64, CoreError> {
+        let batch_size = batch_size.max(1);
+        let mut deleted = 0;
+        loop {
+            let expired = self
+                .message_repository
+                .list_created_before(
+                    channel_id,
+                    before,
+                    excluded_channels,
+                    held_authors,
+                    batch_size,
+                )
+                .await?;
+
+            let mut by_channel: HashMap<ChannelId, Vec<Message>> = HashMap::new();
+            for message in &expired {
+                by_channel.entry(message.channel_id).or_default().push(message.clone());
+            }
+            let mut batch = 0;
+            for (channel_id, messages) in by_channel {
+                // archival failures stop the sweep, so nothing is lost
+                if let Some(archiver) = &self.archiver {
+                    archiver.archive(&channel_id, &messages).await?;
+                }
+                let ids: Vec<MessageId> = messages.iter().map(|message| message.id).collect();
+                let removed = self
+                    .message_repository
+                    .delete_many(&channel_id, &ids, &RETENTION_SWEEPER_ID, None)
+                    .await?;
+                for message in &removed {
+                    self.events.publish(MessageEvent::Deleted {
+                        id: message.id,
+                        channel_id: message.channel_id,
+                    });
+                }
+                batch += removed.len() as u64;
+            }
+            deleted += batch;
+
+            // nothing deleted means the rest was deleted concurrently
+            if expired.len() < batch_size || batch == 0 {
+                break;
+            }
+        }
+        Ok(deleted)
+    }
+
+    /// Check `reason` against the templates, filling in the text of its
+    /// template when the moderator gave none
+    fn resolve_moderation_reason(
+        &self,
+        reason: ModerationReason,
+    ) -> Result<ModerationReason, CoreError> {
+        let code = reason.code.trim().to_string();
+        let text = reason
+            .text
+            .map(|text| text.trim().to_string())
+            .filter(|text| !text.is_empty());
+        if code.is_empty()
+            || text
+                .as_ref()
+                .is_some_and(|text| text.chars().count() > MAX_MODERATION_REASON_TEXT_CHARS)
+        {
+            return Err(CoreError::InvalidModerationReason {
+                max: MAX_MODERATION_REASON_TEXT_CHARS,
+            });
+        }
+        if self.moderation_reasons.is_empty() {
+            return Ok(ModerationReason { code, text });
+        }
+
+        let template = self
+            .moderation_reasons
+            .get(&code)
+            .ok_or_else(|| CoreError::UnknownModerationReason { code: code.clone() })?;
+        Ok(ModerationReason {
+            text: text.or_else(|| Some(template.clone()).filter(|text| !text.is_empty())),
+            code,
+        })
+    }
+
+    /// Tell the followers of `thread_id` about `reply`, except its author
+    /// Publish the current state of a message changed as a side effect of
+    /// another write, re-read so subscribers get it like REST clients would
+    async fn publish_updated(&self, message_id: &MessageId) {
+        match self.message_repository.find_by_id(message_id).await {
+            Ok(Some(message)) => self.events.publish(MessageEvent::Updated(message)),
+            Ok(None) => {}
+            Err(e) => tracing::warn!(
+                error = %e,
+                message_id = %message_id,
+                "failed to read a changed message back"
+            ),
+        }
+    }
+
+    async fn notify_thread_followers(
+        &self,
+        reply: &Message,
+        thread_id: &MessageId,
+    ) -> Result<(), CoreError> {
+        let followers = self
+            .message_repository
+            .list_thread_followers(thread_id)
+            .await?;
+        for follower in followers {
+            if let Some(event) = NotificationRequestedEvent::for_reply(reply, follower) {
+                self.message_repository.request_notification(&event).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Notify the users `message` mentions, `@everyone` and roles standing
+    /// for the matching members of its channel, in fan-out events listing
+    /// the resolved recipients, or in one broadcast naming the groups when
+    /// they are more than the fan-out limit
+    async fn notify_mentions(&self, message: &Message) -> Result<(), CoreError> {
+        let mentions = &message.mentions;
+        let mut recipients = mentions.users.clone();
+        if mentions.everyone {
+            match &self.channel_membership {
+                Some(membership) => {
+                    recipients.extend(membership.list_members(&message.channel_id, None).await?);
+                }
+                None => tracing::debug!(
+                    message_id = %message.id,
+                    "no channel membership to resolve @everyone"
+                ),
+            }
+        } else if !mentions.roles.is_empty() {
+            let roles = mentions.roles.as_slice();
+            match (&self.member_directory, &self.channel_membership) {
+                (Some(directory), _) => {
+                    recipients.extend(directory.role_members(&message.channel_id, roles).await?);
+                }
+                (None, Some(membership)) => {
+                    recipients
+                        .extend(membership.list_members(&message.channel_id, Some(roles)).await?);
+                }
+                (None, None) => tracing::debug!(
+                    message_id = %message.id,
+                    "no member directory to resolve role mentions"
+                ),
+            }
+        }
+
+        let fanouts = NotificationFanoutEvent::for_mentions(message, recipients);
+        let recipient_count: usize = fanouts.iter().map(|event| event.recipient_ids.len()).sum();
+        if self.mention_fanout_limit.is_some_and(|limit| recipient_count > limit) {
+            tracing::info!(
+                message_id = %message.id,
+                recipients = recipient_count,
+                "mention broadcast to its groups rather than fanned out"
+            );
+            let event = NotificationBroadcastEvent::for_mentions(message);
+            return self.message_repository.request_notification_broadcast(&event).await;
+        }
+
+        for event in fanouts {
+            self.message_repository.request_notification_fanout(&event).await?;
+        }
+        Ok(())
+    }
+
+    /// Make the author of `reply` follow `thread_id`, unless they opted out
+    async fn auto_follow_thread(
+        &self,
+        reply: &Message,
+        thread_id: &MessageId,
+    ) -> Result<(), CoreError> {
+        let user_id = UserId(reply.author_id.0);
+        if !self
+            .message_repository
+            .thread_preferences(&user_id)
+            .await?
+            .auto_follow_on_reply
+        {
+            return Ok(());
+        }
+
+        let follow = ThreadFollow {
+            user_id,
+            thread_id: *thread_id,
+            channel_id: reply.channel_id,
+            followed_at: reply.created_at,
+        };
+        self.message_repository.follow_thread(&follow).await?;
+        Ok(())
+    }
+
+    /// Channels and authors under a legal hold, whose messages are never purged
+    async fn held_scopes(&self) -> Result<(Vec<ChannelId>, Vec<AuthorId>), CoreError> {
+        let mut held_channels = Vec::new();
+        let mut held_authors = Vec::new();
+        for hold in self.message_repository.list_legal_holds(None).await? {
+            match hold.scope {
+                LegalHoldScope::Channel(channel_id) => held_channels.push(channel_id),
+                LegalHoldScope::User(user_id) => held_authors.push(AuthorId::from(user_id.0)),
+            }
+        }
+        Ok((held_channels, held_authors))
+    }
+
+    /// Permanently remove `messages`, announcing those not soft deleted yet,
+    /// and return how many were removed
+    async fn purge(&self, messages: Vec<Message>) -> Result<u64, CoreError> {
+        let mut purged = 0;
+        for message in messages {
+            match self.message_repository.hard_delete(&message.id).await {
+                Ok(()) => {}
+                // purged concurrently, e.g. by another replica
+                Err(CoreError::MessageNotFound { .. }) => continue,
+                Err(e) => return Err(e),
+            }
+            // Already announced when it was soft deleted
+            if message.deleted_at.is_none() {
+                self.events.publish(MessageEvent::Deleted {
+                    id: message.id,
+                    channel_id: message.channel_id,
+                });
+            }
+            self.release_attachment_storage(&message).await;
+            purged += 1;
+        }
+        Ok(purged)
+    }
+
+    /// Give the storage of the attachments of a removed message back to its channel
+    async fn release_attachment_storage(&self, removed: &Message) {
+        let size = removed.attachments_size();
+        if size == 0 {
+            return;
+        }
+        // The message is gone already: failing would only lead to a retry that finds nothing
+        if let Err(e) = self
+            .message_repository
+            .release_channel_storage_usage(&removed.channel_id, size)
+            .await
+        {
+            tracing::warn!(
+                error = %e,
+                channel_id = %removed.channel_id,
+                "failed to release attachment storage"
+            );
+        }
+    }
+
+    /// Report the author of `deleted` when this deletion makes them reach the
+    /// mass deletion threshold. Further deletions within the window are not
+    /// reported again, so tooling gets one event per burst.
+    async fn detect_mass_deletion(&self, deleted: &Message) -> Result<(), CoreError> {
+        let Some(threshold) = self.mass_deletion_threshold else {
+            return Ok(());
+        };
+
+        let now = Utc::now();
+        let count = self
+            .message_repository
+            .count_deleted_by_author(&deleted.author_id, now - threshold.window)
+            .await?;
+        if count != threshold.count {
+            return Ok(());
+        }
+
+        let event = AbuseDetectedEvent {
+            pattern: AbusePattern::MassDeletion,
+            user_id: UserId(deleted.author_id.0),
+            channel_id: deleted.channel_id,
+            count,
+            window_seconds: threshold.window.num_seconds().max(0) as u64,
+            detected_at: now,
+        };
+        tracing::info!(user_id = %event.user_id, count, "mass deletion reported to trust & safety");
+        self.message_repository.report_abuse(&event).await
+    }
 }
@@ -1,3 +1,8 @@
+pub mod archive;
 pub mod common;
 pub mod health;
+pub mod lease;
+pub mod lifecycle;
+pub mod membership;
 pub mod message;
+pub mod search;
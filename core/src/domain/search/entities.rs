@@ -0,0 +1,114 @@
+use std::collections::HashSet;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::domain::message::entities::{AuthorId, ChannelId, Message, MessageId};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchQuery {
+    pub text: String,
+    pub channel_id: Option<ChannelId>,
+    /// Only messages of this author
+    #[serde(default)]
+    pub author_id: Option<AuthorId>,
+    /// Only messages posted strictly before this instant
+    #[serde(default)]
+    pub before: Option<DateTime<Utc>>,
+    /// Only messages posted strictly after this instant
+    #[serde(default)]
+    pub after: Option<DateTime<Utc>>,
+    /// Only messages with (`true`) or without (`false`) attachments
+    #[serde(default)]
+    pub has_attachments: Option<bool>,
+    /// Only pinned (`true`) or unpinned (`false`) messages
+    #[serde(default)]
+    pub pinned: Option<bool>,
+    /// Also return messages that were moved to cold storage
+    #[serde(default)]
+    pub include_archived: bool,
+    pub limit: u32,
+}
+
+impl SearchQuery {
+    /// Query for `text` without any filter
+    pub fn new(text: impl Into<String>, limit: u32) -> Self {
+        Self {
+            text: text.into(),
+            channel_id: None,
+            author_id: None,
+            before: None,
+            after: None,
+            has_attachments: None,
+            pinned: None,
+            include_archived: false,
+            limit,
+        }
+    }
+
+    /// Whether `message` passes the structured filters of the query, for
+    /// backends that cannot apply them all
+    pub fn matches(&self, message: &Message) -> bool {
+        self.channel_id.is_none_or(|id| message.channel_id == id)
+            && self.author_id.is_none_or(|id| message.author_id == id)
+            && self.before.is_none_or(|before| message.created_at < before)
+            && self.after.is_none_or(|after| message.created_at > after)
+            && self
+                .has_attachments
+                .is_none_or(|has| message.attachments.is_empty() != has)
+            && self.pinned.is_none_or(|pinned| message.is_pinned == pinned)
+    }
+}
+
+/// Entry returned by a search backend, before hydration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub message_id: MessageId,
+    pub score: f32,
+    pub archived: bool,
+}
+
+/// Search hit hydrated with its message, from MongoDB or from the archive store
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchResult {
+    pub message: Message,
+    pub score: f32,
+    pub archived: bool,
+}
+
+/// Draft to find similar recent messages for, before it is posted
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SimilarMessagesQuery {
+    pub channel_id: ChannelId,
+    pub content: String,
+    pub limit: u32,
+}
+
+/// Existing message similar to a draft
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimilarMessage {
+    pub message: Message,
+    /// Share of words the draft and the message have in common, from 0 to 1
+    pub similarity: f32,
+    /// Thread the message belongs to: its parent for a reply, itself otherwise
+    pub thread_id: MessageId,
+}
+
+/// Jaccard similarity of the words of two texts, ignoring case, punctuation
+/// and words shorter than 3 characters; 0 when either text has no word left
+pub fn content_similarity(a: &str, b: &str) -> f32 {
+    let (a, b) = (words(a), words(b));
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+
+    let common = a.intersection(&b).count();
+    common as f32 / (a.len() + b.len() - common) as f32
+}
+
+fn words(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() >= 3)
+        .map(str::to_lowercase)
+        .collect()
+}
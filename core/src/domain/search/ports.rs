@@ -0,0 +1,76 @@
+use crate::domain::{
+    common::CoreError,
+    message::entities::{Message, MessageId},
+    search::entities::{SearchHit, SearchQuery},
+};
+
+/// Port to an external full-text search backend.
+///
+/// Implementations must be idempotent: indexing a message twice overwrites
+/// the previous entry, and removing an unknown message is not an error.
+#[async_trait::async_trait]
+pub trait SearchIndex: Send + Sync {
+    async fn index_messages(&self, messages: &[Message]) -> Result<(), CoreError>;
+    async fn remove_message(&self, id: &MessageId) -> Result<(), CoreError>;
+
+    /// Flag entries as archived instead of removing them, so archived
+    /// messages stay searchable with `include_archived`
+    async fn set_archived(&self, ids: &[MessageId], archived: bool) -> Result<(), CoreError>;
+
+    /// Hits ordered by relevance; archived entries are only returned when
+    /// `query.include_archived` is set
+    async fn search(&self, query: &SearchQuery) -> Result<Vec<SearchHit>, CoreError>;
+}
+
+/// Port to a search backend storing its own copy of the messages, such as
+/// Elasticsearch, as opposed to one reading the `messages` collection.
+///
+/// It only sees the messages it was fed, typically by a
+/// [`SearchIndexer`](crate::infrastructure::search::SearchIndexer) following
+/// message events, and is rebuilt with `reindex_channel`.
+#[async_trait::async_trait]
+pub trait MessageSearchRepository: SearchIndex {
+    /// Create the index and its mappings when missing (idempotent)
+    async fn ensure_index(&self) -> Result<(), CoreError>;
+}
+
+/// Port to the cold storage holding archived messages
+#[async_trait::async_trait]
+pub trait ArchiveStore: Send + Sync {
+    /// Fetch archived messages; unknown ids are skipped
+    async fn fetch_archived(&self, ids: &[MessageId]) -> Result<Vec<Message>, CoreError>;
+}
+
+/// Search backend that indexes nothing, used when no backend is configured
+#[derive(Clone, Default)]
+pub struct NoopSearchIndex;
+
+#[async_trait::async_trait]
+impl SearchIndex for NoopSearchIndex {
+    async fn index_messages(&self, _messages: &[Message]) -> Result<(), CoreError> {
+        Ok(())
+    }
+
+    async fn remove_message(&self, _id: &MessageId) -> Result<(), CoreError> {
+        Ok(())
+    }
+
+    async fn set_archived(&self, _ids: &[MessageId], _archived: bool) -> Result<(), CoreError> {
+        Ok(())
+    }
+
+    async fn search(&self, _query: &SearchQuery) -> Result<Vec<SearchHit>, CoreError> {
+        Ok(Vec::new())
+    }
+}
+
+/// Archive store that holds nothing, used when archival is not configured
+#[derive(Clone, Default)]
+pub struct NoopArchiveStore;
+
+#[async_trait::async_trait]
+impl ArchiveStore for NoopArchiveStore {
+    async fn fetch_archived(&self, _ids: &[MessageId]) -> Result<Vec<Message>, CoreError> {
+        Ok(Vec::new())
+    }
+}
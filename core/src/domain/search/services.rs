@@ -0,0 +1,152 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use chrono::{Duration, Utc};
+
+use crate::domain::{
+    common::CoreError,
+    message::{entities::MessageId, ports::MessageRepository},
+    search::{
+        entities::{
+            SearchQuery, SearchResult, SimilarMessage, SimilarMessagesQuery, content_similarity,
+        },
+        ports::{ArchiveStore, SearchIndex},
+    },
+};
+
+/// Only messages posted within this window are suggested as similar to a draft
+const SIMILAR_MESSAGES_WINDOW: Duration = Duration::days(30);
+
+/// Below this similarity, a message is not worth suggesting
+const MIN_SIMILARITY: f32 = 0.2;
+
+/// Search hits fetched per requested suggestion, as many are filtered out
+const SIMILAR_CANDIDATES_PER_SUGGESTION: u32 = 4;
+
+/// Runs queries against the search backend and hydrates the hits
+#[derive(Clone)]
+pub struct MessageSearch<R>
+where
+    R: MessageRepository,
+{
+    repository: R,
+    index: Arc<dyn SearchIndex>,
+    archive: Arc<dyn ArchiveStore>,
+}
+
+impl<R> MessageSearch<R>
+where
+    R: MessageRepository,
+{
+    pub fn new(repository: R, index: Arc<dyn SearchIndex>, archive: Arc<dyn ArchiveStore>) -> Self {
+        Self {
+            repository,
+            index,
+            archive,
+        }
+    }
+
+    /// Fetch archived hits from `archive` instead
+    pub fn with_archive(mut self, archive: Arc<dyn ArchiveStore>) -> Self {
+        self.archive = archive;
+        self
+    }
+
+    /// Search messages, keeping the backend's relevance order.
+    ///
+    /// Live hits are loaded from the repository; archived hits are only
+    /// fetched from the archive store when the query asks for them. Hits
+    /// whose message no longer exists (stale index entries) are skipped, as
+    /// are those failing filters the backend could not apply.
+    pub async fn search(&self, query: &SearchQuery) -> Result<Vec<SearchResult>, CoreError> {
+        let hits: Vec<_> = self
+            .index
+            .search(query)
+            .await?
+            .into_iter()
+            .filter(|hit| query.include_archived || !hit.archived)
+            .take(query.limit as usize)
+            .collect();
+
+        let archived_ids: Vec<MessageId> = hits
+            .iter()
+            .filter(|hit| hit.archived)
+            .map(|hit| hit.message_id)
+            .collect();
+        let mut archived: HashMap<_, _> = if archived_ids.is_empty() {
+            HashMap::new()
+        } else {
+            self.archive
+                .fetch_archived(&archived_ids)
+                .await?
+                .into_iter()
+                .map(|message| (message.id, message))
+                .collect()
+        };
+
+        let mut results = Vec::with_capacity(hits.len());
+        for hit in hits {
+            let message = if hit.archived {
+                archived.remove(&hit.message_id)
+            } else {
+                self.repository.find_by_id(&hit.message_id).await?
+            };
+
+            if let Some(message) = message.filter(|message| query.matches(message)) {
+                results.push(SearchResult {
+                    message,
+                    score: hit.score,
+                    archived: hit.archived,
+                });
+            }
+        }
+
+        Ok(results)
+    }
+
+    /// Recent messages of the channel similar to a draft, most similar first.
+    ///
+    /// The search backend preselects candidates, which are then scored by
+    /// [`content_similarity`] to the draft. Only the best message of each
+    /// thread is kept, so a thread is suggested once.
+    pub async fn similar_messages(
+        &self,
+        query: &SimilarMessagesQuery,
+    ) -> Result<Vec<SimilarMessage>, CoreError> {
+        let candidates = self
+            .search(&SearchQuery {
+                channel_id: Some(query.channel_id),
+                ..SearchQuery::new(
+                    query.content.clone(),
+                    query
+                        .limit
+                        .saturating_mul(SIMILAR_CANDIDATES_PER_SUGGESTION),
+                )
+            })
+            .await?;
+
+        let since = Utc::now() - SIMILAR_MESSAGES_WINDOW;
+        let mut similar: Vec<SimilarMessage> = candidates
+            .into_iter()
+            .filter(|result| result.message.created_at >= since)
+            .map(|result| SimilarMessage {
+                similarity: content_similarity(&query.content, &result.message.content),
+                thread_id: result
+                    .message
+                    .reply_to_message_id
+                    .unwrap_or(result.message.id),
+                message: result.message,
+            })
+            .filter(|similar| similar.similarity >= MIN_SIMILARITY)
+            .collect();
+        similar.sort_by(|a, b| b.similarity.total_cmp(&a.similarity));
+
+        let mut threads = HashSet::new();
+        similar.retain(|similar| threads.insert(similar.thread_id));
+        similar.truncate(query.limit as usize);
+
+        Ok(similar)
+    }
+}
@@ -0,0 +1,213 @@
+use communities_client::{
+    ApiClient, ClientError, ErrorCode,
+    models::{self, MessageUpdate, NewMessage},
+};
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        common::{CoreError, GetPaginated, TotalPaginatedElements},
+        message::entities::{
+            Attachment, AttachmentId, AuthorId, ChannelId, CreateMessageRequest, Embed,
+            ImagePreview, Mentions, Message, MessageId, ReactionCount, UpdateMessageRequest,
+            UserId,
+        },
+    },
+    embedded::MessageClient,
+};
+
+/// [`MessageClient`] calling a deployed message service through its REST API,
+/// with the [`ApiClient`] of the `communities_client` crate.
+///
+/// The service must expose plain UUIDs (no `PUBLIC_ID_KEY`), which is the case
+/// of internal deployments: the public IDs it answers are read back as UUIDs.
+#[derive(Clone, Debug)]
+pub struct HttpMessageClient {
+    api: ApiClient,
+}
+
+impl HttpMessageClient {
+    /// Call the API at `base_url` (e.g. `http://communities:8080`) with the
+    /// `access_token` of the user the client acts for
+    pub fn new(base_url: &str, access_token: impl Into<String>) -> Result<Self, CoreError> {
+        let api = ApiClient::new(base_url, access_token).map_err(unavailable)?;
+        Ok(Self::from(api))
+    }
+}
+
+impl From<ApiClient> for HttpMessageClient {
+    fn from(api: ApiClient) -> Self {
+        Self { api }
+    }
+}
+
+/// `not_found` when the API answered `404`, the API error otherwise
+fn client_error(e: ClientError, not_found: CoreError) -> CoreError {
+    match e.code() {
+        Some(ErrorCode::NotFound) => not_found,
+        Some(ErrorCode::ServiceUnavailable) | None => unavailable(e),
+        Some(_) => CoreError::UnknownError {
+            message: format!("message service answered {e}"),
+        },
+    }
+}
+
+fn unavailable(e: ClientError) -> CoreError {
+    CoreError::ServiceUnavailable(format!("message service: {e}"))
+}
+
+fn parse_id(public: &str) -> Result<Uuid, CoreError> {
+    Uuid::try_parse(public).map_err(|_| CoreError::UnknownError {
+        message: format!("message service answered the obfuscated ID {public}"),
+    })
+}
+
+fn from_api(message: models::Message) -> Result<Message, CoreError> {
+    Ok(Message {
+        id: MessageId(parse_id(&message.id)?),
+        channel_id: ChannelId(parse_id(&message.channel_id)?),
+        author_id: AuthorId(message.author_id),
+        content: message.content,
+        reply_to_message_id: message
+            .reply_to_message_id
+            .as_deref()
+            .map(parse_id)
+            .transpose()?
+            .map(MessageId),
+        attachments: message
+            .attachments
+            .into_iter()
+            .map(attachment_from_api)
+            .collect(),
+        is_pinned: message.is_pinned,
+        reactions: message
+            .reactions
+            .into_iter()
+            .map(|count| ReactionCount {
+                emoji: count.emoji,
+                count: count.count,
+            })
+            .collect(),
+        revision: message.revision,
+        sequence: message.sequence,
+        reply_count: message.reply_count,
+        last_reply_at: message.last_reply_at,
+        archived_at: message.archived_at,
+        created_at: message.created_at,
+        updated_at: message.updated_at,
+        deleted_at: message.deleted_at,
+        nonce: message.nonce,
+        embeds: message
+            .embeds
+            .into_iter()
+            .map(|embed| Embed {
+                url: embed.url,
+                title: embed.title,
+                description: embed.description,
+                image_url: embed.image_url,
+                site_name: embed.site_name,
+            })
+            .collect(),
+        mentions: Mentions {
+            users: message.mentions.users.into_iter().map(UserId).collect(),
+            roles: message.mentions.roles,
+            everyone: message.mentions.everyone,
+        },
+        expires_at: message.expires_at,
+    })
+}
+
+fn attachment_from_api(attachment: models::Attachment) -> Attachment {
+    Attachment {
+        id: AttachmentId(attachment.id),
+        name: attachment.name,
+        url: attachment.url,
+        size: attachment.size,
+        preview: attachment.preview.map(|preview| ImagePreview {
+            width: preview.width,
+            height: preview.height,
+            thumbnail_url: preview.thumbnail_url,
+        }),
+    }
+}
+
+#[async_trait::async_trait]
+impl MessageClient for HttpMessageClient {
+    async fn create_message(&self, request: CreateMessageRequest) -> Result<Message, CoreError> {
+        // the only message a creation looks up is the one replied to
+        let not_found = match request.reply_to_message_id {
+            Some(parent_id) => CoreError::MessageNotFound { id: parent_id },
+            None => CoreError::UnknownError {
+                message: "message service returned 404 on creation".to_string(),
+            },
+        };
+        let new_message = NewMessage {
+            channel_id: request.channel_id.to_string(),
+            content: request.content,
+            reply_to_message_id: request.reply_to_message_id.map(|id| id.to_string()),
+            attachments: request.attachments.into_iter().map(|id| id.0).collect(),
+            nonce: request.nonce,
+            expires_at: request.expires_at,
+        };
+        let created = self
+            .api
+            .create_message(&new_message)
+            .await
+            .map_err(|e| client_error(e, not_found))?;
+        from_api(created)
+    }
+
+    async fn get_message(&self, id: &MessageId) -> Result<Message, CoreError> {
+        let found = self
+            .api
+            .get_message(&id.to_string())
+            .await
+            .map_err(|e| client_error(e, CoreError::MessageNotFound { id: *id }))?;
+        from_api(found)
+    }
+
+    async fn list_messages(
+        &self,
+        channel_id: &ChannelId,
+        pagination: &GetPaginated,
+    ) -> Result<(Vec<Message>, TotalPaginatedElements), CoreError> {
+        let not_found = CoreError::UnknownError {
+            message: format!("channel {channel_id} not found"),
+        };
+        let page = self
+            .api
+            .list_messages(&channel_id.to_string(), pagination.page, pagination.limit)
+            .await
+            .map_err(|e| client_error(e, not_found))?;
+        let messages = page
+            .items
+            .into_iter()
+            .map(from_api)
+            .collect::<Result<_, _>>()?;
+        Ok((messages, page.total))
+    }
+
+    async fn update_message(
+        &self,
+        id: &MessageId,
+        request: UpdateMessageRequest,
+    ) -> Result<Message, CoreError> {
+        let update = MessageUpdate {
+            content: request.content,
+            is_pinned: request.is_pinned,
+        };
+        let updated = self
+            .api
+            .update_message(&id.to_string(), &update)
+            .await
+            .map_err(|e| client_error(e, CoreError::MessageNotFound { id: *id }))?;
+        from_api(updated)
+    }
+
+    async fn delete_message(&self, id: &MessageId) -> Result<(), CoreError> {
+        self.api
+            .delete_message(&id.to_string())
+            .await
+            .map_err(|e| client_error(e, CoreError::MessageNotFound { id: *id }))
+    }
+}
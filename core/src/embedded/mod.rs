@@ -0,0 +1,128 @@
+//! Client of the message service for other Rust services.
+//!
+//! Consumers write their code against [`MessageClient`] and pick the
+//! implementation at wiring time:
+//! - [`InProcessMessageClient`] calls a [`MessageService`] directly, e.g. an
+//!   in-memory one in tests with [`InProcessMessageClient::in_memory`]
+//! - `HttpMessageClient` (feature `http-client`) calls a deployed instance
+//!   through its REST API
+//!
+//! Both act on behalf of a single user: the author given to the in-process
+//! client, the owner of the access token given to the HTTP one.
+
+#[cfg(feature = "http-client")]
+mod http;
+
+#[cfg(feature = "http-client")]
+pub use http::HttpMessageClient;
+
+use crate::domain::{
+    common::{CoreError, GetPaginated, TotalPaginatedElements, services::Service},
+    health::port::MockHealthRepository,
+    message::{
+        entities::{
+            AuthorId, ChannelId, CreateMessageRequest, Message, MessageId, UpdateMessageRequest,
+        },
+        ports::{MessageService, MockMessageRepository},
+    },
+};
+
+/// Operations on messages available to other services
+#[async_trait::async_trait]
+pub trait MessageClient: Send + Sync {
+    /// Post a message as the client's user
+    async fn create_message(&self, request: CreateMessageRequest) -> Result<Message, CoreError>;
+    async fn get_message(&self, id: &MessageId) -> Result<Message, CoreError>;
+    /// Messages of a channel, newest first
+    async fn list_messages(
+        &self,
+        channel_id: &ChannelId,
+        pagination: &GetPaginated,
+    ) -> Result<(Vec<Message>, TotalPaginatedElements), CoreError>;
+    async fn update_message(
+        &self,
+        id: &MessageId,
+        request: UpdateMessageRequest,
+    ) -> Result<Message, CoreError>;
+    async fn delete_message(&self, id: &MessageId) -> Result<(), CoreError>;
+}
+
+/// [`MessageClient`] calling a [`MessageService`] of the same process.
+///
+/// The service is called as is: the permission checks of the API do not apply.
+#[derive(Clone)]
+pub struct InProcessMessageClient<S>
+where
+    S: MessageService,
+{
+    service: S,
+    author_id: AuthorId,
+}
+
+impl<S> InProcessMessageClient<S>
+where
+    S: MessageService,
+{
+    /// Call `service`, posting messages as `author_id`
+    pub fn new(service: S, author_id: AuthorId) -> Self {
+        Self { service, author_id }
+    }
+
+    /// The same client posting as another user, sharing the same service
+    pub fn as_author(&self, author_id: AuthorId) -> Self
+    where
+        S: Clone,
+    {
+        Self::new(self.service.clone(), author_id)
+    }
+
+    pub fn service(&self) -> &S {
+        &self.service
+    }
+}
+
+impl InProcessMessageClient<Service<MockMessageRepository, MockHealthRepository>> {
+    /// Client over a fresh in-memory service, for consumer tests
+    pub fn in_memory(author_id: AuthorId) -> Self {
+        Self::new(
+            Service::new(MockMessageRepository::new(), MockHealthRepository::new()),
+            author_id,
+        )
+    }
+}
+
+#[async_trait::async_trait]
+impl<S> MessageClient for InProcessMessageClient<S>
+where
+    S: MessageService,
+{
+    async fn create_message(&self, request: CreateMessageRequest) -> Result<Message, CoreError> {
+        self.service
+            .create_message(request.into_input(self.author_id))
+            .await
+    }
+
+    async fn get_message(&self, id: &MessageId) -> Result<Message, CoreError> {
+        self.service.get_message(id).await
+    }
+
+    async fn list_messages(
+        &self,
+        channel_id: &ChannelId,
+        pagination: &GetPaginated,
+    ) -> Result<(Vec<Message>, TotalPaginatedElements), CoreError> {
+        self.service.list_messages(channel_id, pagination).await
+    }
+
+    async fn update_message(
+        &self,
+        id: &MessageId,
+        request: UpdateMessageRequest,
+    ) -> Result<Message, CoreError> {
+        self.service.update_message(request.into_input(*id)).await
+    }
+
+    async fn delete_message(&self, id: &MessageId) -> Result<(), CoreError> {
+        self.service.delete_message(id).await
+    }
+}
@@ -0,0 +1,16 @@
+//! Cold storage of the messages deleted by the retention sweep
+//!
+//! - `MongoArchiveManifestRepository` records each archived batch in the
+//!   `message_archives` collection: its object key, channel, time range and
+//!   the IDs of its messages, so a message can be found again without
+//!   listing the bucket
+//! - `S3ObjectStorage` (feature `s3-archive`) writes the batches to an
+//!   S3-compatible bucket
+
+mod mongo;
+#[cfg(feature = "s3-archive")]
+mod s3;
+
+pub use mongo::MongoArchiveManifestRepository;
+#[cfg(feature = "s3-archive")]
+pub use s3::S3ObjectStorage;
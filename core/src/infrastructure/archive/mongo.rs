@@ -0,0 +1,219 @@
+use chrono::{DateTime, Utc};
+use futures::TryStreamExt;
+use mongodb::{
+    Collection, Database, IndexModel,
+    bson::{Bson, Document, doc},
+    options::FindOptions,
+};
+
+use crate::{
+    domain::{
+        archive::{
+            entities::{ArchiveBatch, ArchiveBatchId},
+            ports::ArchiveManifestRepository,
+        },
+        common::{CoreError, GetPaginated, MAX_PAGE_LIMIT, TotalPaginatedElements},
+        message::entities::{AuthorId, ChannelId, MessageId},
+    },
+    infrastructure::message::{
+        dto::{binary_to_uuid, uuid_to_binary},
+        encoding::{uuid_match, uuids_in},
+    },
+};
+
+/// Manifest of the archived batches, one document per batch in the
+/// `message_archives` collection
+#[derive(Clone)]
+pub struct MongoArchiveManifestRepository {
+    collection: Collection<Document>,
+}
+
+impl MongoArchiveManifestRepository {
+    pub fn new(db: &Database) -> Self {
+        Self {
+            collection: db.collection("message_archives"),
+        }
+    }
+
+    /// Index the batches by channel, and by the messages and authors they
+    /// hold (idempotent)
+    pub async fn ensure_indexes(&self) -> Result<(), CoreError> {
+        self.collection
+            .create_indexes([
+                IndexModel::builder()
+                    .keys(doc! { "channel_id": 1, "oldest_created_at": 1 })
+                    .build(),
+                IndexModel::builder().keys(doc! { "message_ids": 1 }).build(),
+                IndexModel::builder().keys(doc! { "author_ids": 1 }).build(),
+            ])
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        Ok(())
+    }
+
+    fn batch_from(document: &Document) -> Result<ArchiveBatch, CoreError> {
+        let invalid = |field: &str| CoreError::DatabaseError {
+            msg: format!("invalid `{field}` in archive batch"),
+        };
+        // reference fields may already be in the standard UUID encoding
+        let uuid = |value: Option<&Bson>, field: &str| match value {
+            Some(Bson::Binary(binary)) => binary_to_uuid(binary),
+            _ => Err(invalid(field)),
+        };
+        let date = |field: &str| {
+            document
+                .get_str(field)
+                .ok()
+                .and_then(|date| DateTime::parse_from_rfc3339(date).ok())
+                .map(|date| date.with_timezone(&Utc))
+                .ok_or_else(|| invalid(field))
+        };
+
+        let message_ids = document
+            .get_array("message_ids")
+            .map_err(|_| invalid("message_ids"))?
+            .iter()
+            .map(|id| uuid(Some(id), "message_ids").map(MessageId))
+            .collect::<Result<_, _>>()?;
+        // absent from the batches archived before authors were recorded
+        let author_ids = match document.get_array("author_ids") {
+            Ok(ids) => ids
+                .iter()
+                .map(|id| uuid(Some(id), "author_ids").map(AuthorId))
+                .collect::<Result<_, _>>()?,
+            Err(_) => Vec::new(),
+        };
+
+        Ok(ArchiveBatch {
+            id: ArchiveBatchId(uuid(document.get("_id"), "_id")?),
+            channel_id: ChannelId(uuid(document.get("channel_id"), "channel_id")?),
+            object_key: document
+                .get_str("object_key")
+                .map_err(|_| invalid("object_key"))?
+                .to_string(),
+            message_ids,
+            author_ids,
+            oldest_created_at: date("oldest_created_at")?,
+            newest_created_at: date("newest_created_at")?,
+            size_bytes: document
+                .get_i64("size_bytes")
+                .map_err(|_| invalid("size_bytes"))? as u64,
+            archived_at: date("archived_at")?,
+        })
+    }
+
+    async fn find_batches(
+        &self,
+        filter: Document,
+        options: Option<FindOptions>,
+    ) -> Result<Vec<ArchiveBatch>, CoreError> {
+        let mut cursor = self
+            .collection
+            .find(filter)
+            .with_options(options)
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        let mut batches = Vec::new();
+        while let Some(document) = cursor
+            .try_next()
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?
+        {
+            batches.push(Self::batch_from(&document)?);
+        }
+        Ok(batches)
+    }
+}
+
+#[async_trait::async_trait]
+impl ArchiveManifestRepository for MongoArchiveManifestRepository {
+    async fn record_batch(&self, batch: &ArchiveBatch) -> Result<(), CoreError> {
+        let message_ids: Vec<Bson> = batch
+            .message_ids
+            .iter()
+            .map(|id| Bson::Binary(uuid_to_binary(id.0)))
+            .collect();
+        let author_ids: Vec<Bson> = batch
+            .author_ids
+            .iter()
+            .map(|id| Bson::Binary(uuid_to_binary(id.0)))
+            .collect();
+        let document = doc! {
+            "_id": uuid_to_binary(batch.id.0),
+            "channel_id": uuid_to_binary(batch.channel_id.0),
+            "object_key": batch.object_key.as_str(),
+            "message_ids": message_ids,
+            "author_ids": author_ids,
+            "oldest_created_at": batch.oldest_created_at.to_rfc3339(),
+            "newest_created_at": batch.newest_created_at.to_rfc3339(),
+            "size_bytes": batch.size_bytes as i64,
+            "archived_at": batch.archived_at.to_rfc3339(),
+        };
+
+        self.collection
+            .replace_one(doc! { "_id": uuid_to_binary(batch.id.0) }, document)
+            .upsert(true)
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        Ok(())
+    }
+
+    async fn find_by_message_ids(&self, ids: &[MessageId]) -> Result<Vec<ArchiveBatch>, CoreError> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let filter = doc! { "message_ids": { "$in": uuids_in(ids.iter().map(|id| id.0)) } };
+        self.find_batches(filter, None).await
+    }
+
+    async fn find_by_author(
+        &self,
+        author_id: &AuthorId,
+        excluded_channels: &[ChannelId],
+    ) -> Result<Vec<ArchiveBatch>, CoreError> {
+        let excluded_channels = uuids_in(excluded_channels.iter().map(|channel_id| channel_id.0));
+        let filter = doc! {
+            "channel_id": { "$nin": excluded_channels },
+            "$or": [
+                { "author_ids": uuid_match(author_id.0) },
+                { "author_ids": { "$exists": false } },
+            ],
+        };
+        self.find_batches(filter, None).await
+    }
+
+    async fn delete_batch(&self, batch: &ArchiveBatch) -> Result<(), CoreError> {
+        self.collection
+            .delete_one(doc! { "_id": uuid_match(batch.id.0) })
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        Ok(())
+    }
+
+    async fn list_batches(
+        &self,
+        channel_id: &ChannelId,
+        pagination: &GetPaginated,
+    ) -> Result<(Vec<ArchiveBatch>, TotalPaginatedElements), CoreError> {
+        let filter = doc! { "channel_id": uuid_match(channel_id.0) };
+        let total = self
+            .collection
+            .count_documents(filter.clone())
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        // timestamps are RFC3339 strings in UTC, so they sort chronologically
+        let options = FindOptions::builder()
+            .sort(doc! { "oldest_created_at": 1, "_id": 1 })
+            .skip((pagination.page.saturating_sub(1) * pagination.limit) as u64)
+            .limit(pagination.limit.min(MAX_PAGE_LIMIT) as i64)
+            .build();
+        let batches = self.find_batches(filter, Some(options)).await?;
+
+        Ok((batches, total))
+    }
+}
@@ -0,0 +1,75 @@
+use object_store::{
+    ObjectStore, PutPayload,
+    aws::{AmazonS3, AmazonS3Builder},
+    path::Path,
+};
+
+use crate::domain::{archive::ports::ObjectStorage, common::CoreError};
+
+/// Objects stored in a bucket of an S3-compatible storage (AWS S3, MinIO,
+/// Ceph, ...)
+#[derive(Clone)]
+pub struct S3ObjectStorage {
+    store: AmazonS3,
+}
+
+impl S3ObjectStorage {
+    /// Storage in `bucket`; `endpoint` is left empty for AWS itself and
+    /// points at the service otherwise, which is then addressed by path
+    pub fn new(
+        bucket: &str,
+        region: &str,
+        endpoint: &str,
+        access_key_id: &str,
+        secret_access_key: &str,
+    ) -> Result<Self, CoreError> {
+        let mut builder = AmazonS3Builder::new()
+            .with_bucket_name(bucket)
+            .with_region(region)
+            .with_access_key_id(access_key_id)
+            .with_secret_access_key(secret_access_key);
+        if !endpoint.is_empty() {
+            builder = builder
+                .with_endpoint(endpoint)
+                .with_virtual_hosted_style_request(false)
+                .with_allow_http(endpoint.starts_with("http://"));
+        }
+
+        let store = builder
+            .build()
+            .map_err(|e| CoreError::ArchiveError { msg: e.to_string() })?;
+        Ok(Self { store })
+    }
+}
+
+#[async_trait::async_trait]
+impl ObjectStorage for S3ObjectStorage {
+    async fn put_object(&self, key: &str, body: Vec<u8>) -> Result<(), CoreError> {
+        self.store
+            .put(&Path::from(key), PutPayload::from(body))
+            .await
+            .map_err(|e| CoreError::ArchiveError { msg: e.to_string() })?;
+        Ok(())
+    }
+
+    async fn get_object(&self, key: &str) -> Result<Option<Vec<u8>>, CoreError> {
+        match self.store.get(&Path::from(key)).await {
+            Ok(result) => {
+                let body = result
+                    .bytes()
+                    .await
+                    .map_err(|e| CoreError::ArchiveError { msg: e.to_string() })?;
+                Ok(Some(body.to_vec()))
+            }
+            Err(object_store::Error::NotFound { .. }) => Ok(None),
+            Err(e) => Err(CoreError::ArchiveError { msg: e.to_string() }),
+        }
+    }
+
+    async fn delete_object(&self, key: &str) -> Result<(), CoreError> {
+        match self.store.delete(&Path::from(key)).await {
+            Ok(()) | Err(object_store::Error::NotFound { .. }) => Ok(()),
+            Err(e) => Err(CoreError::ArchiveError { msg: e.to_string() }),
+        }
+    }
+}
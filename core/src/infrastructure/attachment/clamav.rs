@@ -0,0 +1,82 @@
+use std::time::Duration;
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+
+use crate::domain::{
+    common::CoreError,
+    message::{entities::ScanVerdict, ports::AttachmentScanner},
+};
+
+/// Largest chunk streamed to clamd at once, well below its `StreamMaxLength`
+const CHUNK_BYTES: usize = 64 * 1024;
+
+/// Scanner backed by a clamd daemon, reached over TCP with the `INSTREAM`
+/// command
+#[derive(Debug, Clone)]
+pub struct ClamAvScanner {
+    /// `host:port` of the daemon
+    address: String,
+    /// Longest a scan may take, connection included
+    timeout: Duration,
+}
+
+impl ClamAvScanner {
+    pub fn new(address: impl Into<String>, timeout: Duration) -> Self {
+        Self {
+            address: address.into(),
+            timeout,
+        }
+    }
+
+    async fn instream(&self, content: &[u8]) -> Result<String, std::io::Error> {
+        let mut stream = TcpStream::connect(&self.address).await?;
+        stream.write_all(b"zINSTREAM\0").await?;
+        for chunk in content.chunks(CHUNK_BYTES) {
+            stream
+                .write_all(&(chunk.len() as u32).to_be_bytes())
+                .await?;
+            stream.write_all(chunk).await?;
+        }
+        stream.write_all(&0u32.to_be_bytes()).await?;
+
+        let mut reply = Vec::new();
+        stream.read_to_end(&mut reply).await?;
+        Ok(String::from_utf8_lossy(&reply).to_string())
+    }
+}
+
+/// Verdict of a clamd reply: `stream: OK`, `stream: <signature> FOUND` or an
+/// error such as `INSTREAM size limit exceeded. ERROR`
+fn verdict(reply: &str) -> Result<ScanVerdict, CoreError> {
+    let reply = reply.trim_end_matches(['\0', '\n']).trim();
+    let result = reply.strip_prefix("stream:").unwrap_or(reply).trim();
+
+    if result == "OK" {
+        return Ok(ScanVerdict::Clean);
+    }
+    match result.strip_suffix(" FOUND") {
+        Some(signature) => Ok(ScanVerdict::Infected {
+            signature: signature.trim().to_string(),
+        }),
+        None => Err(CoreError::AttachmentScanError {
+            msg: format!("clamd answered: {reply}"),
+        }),
+    }
+}
+
+#[async_trait::async_trait]
+impl AttachmentScanner for ClamAvScanner {
+    async fn scan(&self, content: &[u8]) -> Result<ScanVerdict, CoreError> {
+        let reply = tokio::time::timeout(self.timeout, self.instream(content))
+            .await
+            .map_err(|_| CoreError::AttachmentScanError {
+                msg: format!("clamd did not answer within {:?}", self.timeout),
+            })?
+            .map_err(|e| CoreError::AttachmentScanError { msg: e.to_string() })?;
+
+        verdict(&reply)
+    }
+}
@@ -0,0 +1,26 @@
+//! Storage of the files attached to messages
+//!
+//! - `S3AttachmentStorage` (feature `s3-attachments`) presigns uploads to an
+//!   S3-compatible bucket and checks they happened before the attachments
+//!   are posted; the upload records themselves live with the messages
+//! - `AttachmentScanWorker` scans the attachments of new messages in the
+//!   background, with `ClamAvScanner` (feature `clamav`) or any other
+//!   `AttachmentScanner`
+//! - `HttpThumbnailGenerator` (feature `http-thumbnails`) requests the
+//!   dimensions and thumbnails of image attachments from an external service
+
+#[cfg(feature = "clamav")]
+mod clamav;
+#[cfg(feature = "s3-attachments")]
+mod s3;
+mod scan;
+#[cfg(feature = "http-thumbnails")]
+mod thumbnail;
+
+#[cfg(feature = "clamav")]
+pub use clamav::ClamAvScanner;
+#[cfg(feature = "s3-attachments")]
+pub use s3::S3AttachmentStorage;
+pub use scan::AttachmentScanWorker;
+#[cfg(feature = "http-thumbnails")]
+pub use thumbnail::HttpThumbnailGenerator;
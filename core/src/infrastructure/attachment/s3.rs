@@ -0,0 +1,91 @@
+use std::time::Duration;
+
+use http::Method;
+use object_store::{
+    ObjectStore,
+    aws::{AmazonS3, AmazonS3Builder},
+    path::Path,
+    signer::Signer,
+};
+
+use crate::domain::{common::CoreError, message::ports::AttachmentStorage};
+
+/// Attachments stored in a bucket of an S3-compatible storage (AWS S3,
+/// MinIO, ...), uploaded by the clients themselves through presigned URLs
+#[derive(Clone)]
+pub struct S3AttachmentStorage {
+    store: AmazonS3,
+    /// Base URL the bucket is publicly served from, without trailing slash
+    public_url: String,
+}
+
+impl S3AttachmentStorage {
+    /// Storage in `bucket`; `endpoint` is left empty for AWS itself and
+    /// points at the service otherwise, which is then addressed by path.
+    /// Attachments are downloaded from `public_url` followed by their key.
+    pub fn new(
+        bucket: &str,
+        region: &str,
+        endpoint: &str,
+        access_key_id: &str,
+        secret_access_key: &str,
+        public_url: &str,
+    ) -> Result<Self, CoreError> {
+        let mut builder = AmazonS3Builder::new()
+            .with_bucket_name(bucket)
+            .with_region(region)
+            .with_access_key_id(access_key_id)
+            .with_secret_access_key(secret_access_key);
+        if !endpoint.is_empty() {
+            builder = builder
+                .with_endpoint(endpoint)
+                .with_virtual_hosted_style_request(false)
+                .with_allow_http(endpoint.starts_with("http://"));
+        }
+
+        let store = builder
+            .build()
+            .map_err(|e| CoreError::AttachmentStorageError { msg: e.to_string() })?;
+        Ok(Self {
+            store,
+            public_url: public_url.trim_end_matches('/').to_string(),
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl AttachmentStorage for S3AttachmentStorage {
+    async fn presign_upload(&self, key: &str, expires_in: Duration) -> Result<String, CoreError> {
+        let url = self
+            .store
+            .signed_url(Method::PUT, &Path::from(key), expires_in)
+            .await
+            .map_err(|e| CoreError::AttachmentStorageError { msg: e.to_string() })?;
+        Ok(url.to_string())
+    }
+
+    async fn object_size(&self, key: &str) -> Result<Option<u64>, CoreError> {
+        match self.store.head(&Path::from(key)).await {
+            Ok(meta) => Ok(Some(meta.size as u64)),
+            Err(object_store::Error::NotFound { .. }) => Ok(None),
+            Err(e) => Err(CoreError::AttachmentStorageError { msg: e.to_string() }),
+        }
+    }
+
+    async fn fetch(&self, key: &str) -> Result<Option<Vec<u8>>, CoreError> {
+        let object = match self.store.get(&Path::from(key)).await {
+            Ok(object) => object,
+            Err(object_store::Error::NotFound { .. }) => return Ok(None),
+            Err(e) => return Err(CoreError::AttachmentStorageError { msg: e.to_string() }),
+        };
+        let content = object
+            .bytes()
+            .await
+            .map_err(|e| CoreError::AttachmentStorageError { msg: e.to_string() })?;
+        Ok(Some(content.to_vec()))
+    }
+
+    fn download_url(&self, key: &str) -> String {
+        format!("{}/{key}", self.public_url)
+    }
+}
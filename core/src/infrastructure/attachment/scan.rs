@@ -0,0 +1,74 @@
+use std::sync::Arc;
+
+use tokio::{
+    sync::{Semaphore, broadcast::error::RecvError},
+    task::JoinHandle,
+};
+
+use crate::domain::message::{
+    events::{MessageEvent, MessageEventBus},
+    ports::MessageService,
+};
+
+/// Scans the attachments of new messages in the background, so posting a
+/// message never waits on the scanner
+pub struct AttachmentScanWorker<S>
+where
+    S: MessageService,
+{
+    service: Arc<S>,
+    /// Scans running at once
+    permits: Arc<Semaphore>,
+}
+
+impl<S> AttachmentScanWorker<S>
+where
+    S: MessageService + 'static,
+{
+    pub fn new(service: S, max_concurrent_scans: usize) -> Self {
+        Self {
+            service: Arc::new(service),
+            permits: Arc::new(Semaphore::new(max_concurrent_scans.max(1))),
+        }
+    }
+
+    /// Scan the messages created with attachments published on `events`.
+    ///
+    /// Follow the bus of the service itself, not a change stream, for each
+    /// message to be scanned once by the instance that created it. A message
+    /// that fails to be scanned, or was skipped because the worker lagged
+    /// behind the bus, stays posted.
+    pub fn follow(self, events: &MessageEventBus) -> JoinHandle<()> {
+        let mut receiver = events.subscribe();
+        tokio::spawn(async move {
+            loop {
+                let message = match receiver.recv().await {
+                    Ok(MessageEvent::Created(message)) if !message.attachments.is_empty() => {
+                        message
+                    }
+                    Ok(_) => continue,
+                    Err(RecvError::Lagged(skipped)) => {
+                        tracing::warn!(skipped, "attachment scanner lagged behind message events");
+                        continue;
+                    }
+                    Err(RecvError::Closed) => return,
+                };
+
+                let Ok(permit) = self.permits.clone().acquire_owned().await else {
+                    return;
+                };
+                let service = self.service.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = service.scan_attachments(&message).await {
+                        tracing::warn!(
+                            error = %e,
+                            message_id = %message.id,
+                            "failed to scan the attachments of a message"
+                        );
+                    }
+                    drop(permit);
+                });
+            }
+        })
+    }
+}
@@ -0,0 +1,85 @@
+use std::{fmt::Display, time::Duration};
+
+use reqwest::{Client, StatusCode};
+use serde::{Deserialize, Serialize};
+
+use crate::domain::{
+    common::CoreError,
+    message::{entities::ImagePreview, ports::ThumbnailGenerator},
+};
+
+/// Previews requested from an external image service.
+///
+/// The service is sent `POST <endpoint>` with the URL and content type of the
+/// image, and answers with its dimensions and the URL of a thumbnail it
+/// generated, or `422 Unprocessable Entity` when it cannot read the image.
+#[derive(Clone)]
+pub struct HttpThumbnailGenerator {
+    client: Client,
+    endpoint: String,
+}
+
+#[derive(Serialize)]
+struct PreviewRequest<'a> {
+    url: &'a str,
+    content_type: &'a str,
+}
+
+#[derive(Deserialize)]
+struct PreviewResponse {
+    width: u32,
+    height: u32,
+    #[serde(default)]
+    thumbnail_url: Option<String>,
+}
+
+impl HttpThumbnailGenerator {
+    /// Request previews from `endpoint`, giving up after `timeout`
+    pub fn new(endpoint: impl Into<String>, timeout: Duration) -> Result<Self, CoreError> {
+        let client = Client::builder()
+            .timeout(timeout)
+            .build()
+            .map_err(service_error)?;
+        Ok(Self {
+            client,
+            endpoint: endpoint.into(),
+        })
+    }
+}
+
+fn service_error(e: impl Display) -> CoreError {
+    CoreError::ServiceUnavailable(format!("thumbnail service: {e}"))
+}
+
+#[async_trait::async_trait]
+impl ThumbnailGenerator for HttpThumbnailGenerator {
+    async fn preview(
+        &self,
+        url: &str,
+        content_type: &str,
+    ) -> Result<Option<ImagePreview>, CoreError> {
+        let response = self
+            .client
+            .post(&self.endpoint)
+            .json(&PreviewRequest { url, content_type })
+            .send()
+            .await
+            .map_err(service_error)?;
+
+        let status = response.status();
+        if status == StatusCode::UNPROCESSABLE_ENTITY {
+            return Ok(None);
+        }
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(service_error(format!("{status}: {body}")));
+        }
+
+        let preview: PreviewResponse = response.json().await.map_err(service_error)?;
+        Ok(Some(ImagePreview {
+            width: preview.width,
+            height: preview.height,
+            thumbnail_url: preview.thumbnail_url,
+        }))
+    }
+}
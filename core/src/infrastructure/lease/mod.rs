@@ -0,0 +1,13 @@
+//! Running singleton background jobs on exactly one replica
+//!
+//! - `MongoLeaseLock` keeps one document per lease in the `leases`
+//!   collection; acquiring is a single upsert that only matches an expired
+//!   lease or one we already hold, so two replicas can never both win
+//! - `SingletonJob` runs a job only while holding its lease, renewing it on
+//!   every run
+
+mod mongo;
+mod singleton;
+
+pub use mongo::MongoLeaseLock;
+pub use singleton::SingletonJob;
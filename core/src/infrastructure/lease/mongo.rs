@@ -0,0 +1,74 @@
+use std::time::Duration;
+
+use mongodb::{
+    Collection, Database,
+    bson::{DateTime as BsonDateTime, Document, doc},
+    error::{ErrorKind, WriteFailure},
+};
+
+use crate::domain::{common::CoreError, lease::ports::LeaseLock};
+
+pub(crate) const LEASES_COLLECTION: &str = "leases";
+
+/// Server error code of duplicate key errors
+const DUPLICATE_KEY: i32 = 11000;
+
+#[derive(Clone)]
+pub struct MongoLeaseLock {
+    collection: Collection<Document>,
+    holder: String,
+}
+
+impl MongoLeaseLock {
+    /// `holder` must be unique per replica, e.g. a random id generated at startup
+    pub fn new(db: &Database, holder: impl Into<String>) -> Self {
+        Self {
+            collection: db.collection(LEASES_COLLECTION),
+            holder: holder.into(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl LeaseLock for MongoLeaseLock {
+    async fn try_acquire(&self, name: &str, ttl: Duration) -> Result<bool, CoreError> {
+        let now = BsonDateTime::now();
+        let expires_at = BsonDateTime::from_millis(
+            now.timestamp_millis()
+                .saturating_add(ttl.as_millis() as i64),
+        );
+
+        // When another holder owns an unexpired lease nothing matches, and
+        // the upsert fails on the `_id` already taken
+        let result = self
+            .collection
+            .update_one(
+                doc! {
+                    "_id": name,
+                    "$or": [{ "holder": self.holder.as_str() }, { "expires_at": { "$lte": now } }],
+                },
+                doc! { "$set": { "holder": self.holder.as_str(), "expires_at": expires_at } },
+            )
+            .upsert(true)
+            .await;
+
+        match result {
+            Ok(_) => Ok(true),
+            Err(e) if is_duplicate_key(&e.kind) => Ok(false),
+            Err(e) => Err(CoreError::DatabaseError { msg: e.to_string() }),
+        }
+    }
+
+    async fn release(&self, name: &str) -> Result<(), CoreError> {
+        self.collection
+            .delete_one(doc! { "_id": name, "holder": self.holder.as_str() })
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        Ok(())
+    }
+}
+
+fn is_duplicate_key(kind: &ErrorKind) -> bool {
+    matches!(kind, ErrorKind::Write(WriteFailure::WriteError(error)) if error.code == DUPLICATE_KEY)
+}
@@ -0,0 +1,63 @@
+use std::{future::Future, sync::Arc, time::Duration};
+
+use tokio::{task::JoinHandle, time::MissedTickBehavior};
+
+use crate::domain::{common::CoreError, lease::ports::LeaseLock};
+
+/// A background job run by a single replica at a time.
+///
+/// The lease is renewed before each run, so a run must not outlast the TTL or
+/// another replica may start the job concurrently.
+#[derive(Clone)]
+pub struct SingletonJob {
+    lock: Arc<dyn LeaseLock>,
+    name: String,
+    ttl: Duration,
+}
+
+impl SingletonJob {
+    pub fn new(lock: Arc<dyn LeaseLock>, name: impl Into<String>, ttl: Duration) -> Self {
+        Self {
+            lock,
+            name: name.into(),
+            ttl,
+        }
+    }
+
+    /// Run `job` if this replica holds the lease (taking it when free);
+    /// `None` when another replica holds it
+    pub async fn run_once<F: Future>(&self, job: F) -> Result<Option<F::Output>, CoreError> {
+        if !self.lock.try_acquire(&self.name, self.ttl).await? {
+            tracing::debug!(job = %self.name, "lease held by another replica, skipping");
+            return Ok(None);
+        }
+
+        Ok(Some(job.await))
+    }
+
+    /// Run `job` every `interval` on whichever replica holds the lease.
+    ///
+    /// `interval` must be shorter than the TTL, so the holder renews its lease
+    /// before it expires.
+    pub fn spawn_every<F, Fut>(self, interval: Duration, mut job: F) -> JoinHandle<()>
+    where
+        F: FnMut() -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send,
+    {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = self.run_once(job()).await {
+                    tracing::warn!(job = %self.name, error = %e, "failed to renew job lease");
+                }
+            }
+        })
+    }
+
+    /// Give the lease up, e.g. on shutdown, so another replica takes over right away
+    pub async fn release(&self) -> Result<(), CoreError> {
+        self.lock.release(&self.name).await
+    }
+}
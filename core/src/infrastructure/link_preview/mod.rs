@@ -0,0 +1,14 @@
+//! Previews of the links posted in messages
+//!
+//! - `LinkPreviewWorker` unfurls the links of new messages in the background
+//!   and stores them as embeds of the messages
+//! - `OpenGraphUnfurler` (feature `link-previews`) reads the OpenGraph
+//!   metadata of the linked pages
+
+#[cfg(feature = "link-previews")]
+mod opengraph;
+mod worker;
+
+#[cfg(feature = "link-previews")]
+pub use opengraph::OpenGraphUnfurler;
+pub use worker::LinkPreviewWorker;
@@ -0,0 +1,279 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt::Display,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
+
+use reqwest::{
+    Client,
+    dns::{Addrs, Name, Resolve, Resolving},
+    header::CONTENT_TYPE,
+    redirect,
+};
+use url::{Host, Url};
+
+use crate::domain::{
+    common::CoreError,
+    message::{entities::Embed, ports::LinkUnfurler},
+};
+
+/// Redirects followed before giving up on a link
+const MAX_REDIRECTS: usize = 3;
+
+/// Longest title or description kept in an embed, in characters
+const MAX_TEXT_CHARS: usize = 300;
+
+/// Previews read from the OpenGraph metadata of the linked pages, falling
+/// back on their `<title>`.
+///
+/// Only the first `max_bytes` of a page are read. Private, loopback and
+/// link-local addresses are never connected to, whether a link or one of its
+/// redirects names them or a domain resolves to them, so messages cannot be
+/// used to probe the internal network.
+#[derive(Clone)]
+pub struct OpenGraphUnfurler {
+    client: Client,
+    max_bytes: usize,
+}
+
+impl OpenGraphUnfurler {
+    /// Fetch pages for at most `timeout` each, reading their first `max_bytes`
+    pub fn new(timeout: Duration, max_bytes: usize) -> Result<Self, CoreError> {
+        let redirects = redirect::Policy::custom(|attempt| {
+            if attempt.previous().len() >= MAX_REDIRECTS {
+                attempt.error("too many redirects")
+            } else if !is_public_url(attempt.url()) {
+                attempt.stop()
+            } else {
+                attempt.follow()
+            }
+        });
+        let client = Client::builder()
+            .timeout(timeout)
+            .redirect(redirects)
+            .dns_resolver(Arc::new(PublicResolver))
+            .user_agent(concat!(
+                "communities-link-preview/",
+                env!("CARGO_PKG_VERSION")
+            ))
+            .build()
+            .map_err(unfurl_error)?;
+        Ok(Self { client, max_bytes })
+    }
+}
+
+/// DNS resolver leaving out the addresses that are not public
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(public_addresses(name))
+    }
+}
+
+async fn public_addresses(name: Name) -> Result<Addrs, Box<dyn Error + Send + Sync>> {
+    let addresses: Vec<SocketAddr> = tokio::net::lookup_host((name.as_str(), 0))
+        .await?
+        .filter(|address| is_public_ip(address.ip()))
+        .collect();
+    if addresses.is_empty() {
+        return Err(format!("{} has no public address", name.as_str()).into());
+    }
+    Ok(Box::new(addresses.into_iter()))
+}
+
+fn unfurl_error(e: impl Display) -> CoreError {
+    CoreError::ServiceUnavailable(format!("link preview: {e}"))
+}
+
+fn is_public_url(url: &Url) -> bool {
+    if !matches!(url.scheme(), "http" | "https") {
+        return false;
+    }
+    match url.host() {
+        Some(Host::Domain(domain)) => {
+            let domain = domain.trim_end_matches('.').to_ascii_lowercase();
+            domain != "localhost" && !domain.ends_with(".localhost")
+        }
+        Some(Host::Ipv4(ip)) => is_public_ip(IpAddr::V4(ip)),
+        Some(Host::Ipv6(ip)) => is_public_ip(IpAddr::V6(ip)),
+        None => false,
+    }
+}
+
+fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_ipv4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public_ipv4(ip),
+            None => is_public_ipv6(ip),
+        },
+    }
+}
+
+fn is_public_ipv4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    !(ip.is_private()
+        || ip.is_loopback()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        // shared address space of carrier-grade NATs
+        || (a == 100 && (64..128).contains(&b)))
+}
+
+fn is_public_ipv6(ip: Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    !(ip.is_loopback()
+        || ip.is_unspecified()
+        // unique local addresses
+        || (first & 0xfe00) == 0xfc00
+        // link-local addresses
+        || (first & 0xffc0) == 0xfe80)
+}
+
+#[async_trait::async_trait]
+impl LinkUnfurler for OpenGraphUnfurler {
+    async fn unfurl(&self, url: &str) -> Result<Option<Embed>, CoreError> {
+        let Ok(parsed) = Url::parse(url) else {
+            return Ok(None);
+        };
+        if !is_public_url(&parsed) {
+            return Ok(None);
+        }
+
+        let mut response = self.client.get(parsed).send().await.map_err(unfurl_error)?;
+        let is_html = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.trim_start().starts_with("text/html"));
+        if !response.status().is_success() || !is_html {
+            return Ok(None);
+        }
+        let page_url = response.url().clone();
+
+        let mut body = Vec::new();
+        while body.len() < self.max_bytes {
+            match response.chunk().await.map_err(unfurl_error)? {
+                Some(chunk) => body.extend_from_slice(&chunk),
+                None => break,
+            }
+        }
+        body.truncate(self.max_bytes);
+
+        Ok(read_embed(url, &page_url, &String::from_utf8_lossy(&body)))
+    }
+}
+
+/// Embed of the page at `page_url` linked as `url`, `None` when its `html`
+/// has neither title, description nor image
+fn read_embed(url: &str, page_url: &Url, html: &str) -> Option<Embed> {
+    let metadata = meta_properties(html);
+    let text = |key: &str| {
+        metadata
+            .get(key)
+            .map(|value| value.trim())
+            .filter(|value| !value.is_empty())
+            .map(|value| value.chars().take(MAX_TEXT_CHARS).collect::<String>())
+    };
+
+    let embed = Embed {
+        url: url.to_string(),
+        title: text("og:title")
+            .or_else(|| title(html).map(|title| title.chars().take(MAX_TEXT_CHARS).collect())),
+        description: text("og:description").or_else(|| text("description")),
+        image_url: text("og:image")
+            .and_then(|image| page_url.join(&image).ok())
+            .filter(|image| matches!(image.scheme(), "http" | "https"))
+            .map(String::from),
+        site_name: text("og:site_name"),
+    };
+    if embed.title.is_none() && embed.description.is_none() && embed.image_url.is_none() {
+        return None;
+    }
+    Some(embed)
+}
+
+/// `content` of the `<meta>` tags by their `property`, or `name` without one;
+/// the first tag of a property wins
+fn meta_properties(html: &str) -> HashMap<String, String> {
+    // ASCII lowercasing keeps the byte offsets of the original
+    let lowercase = html.to_ascii_lowercase();
+    let mut properties = HashMap::new();
+    let mut from = 0;
+    while let Some(start) = lowercase[from..].find("<meta").map(|i| from + i) {
+        let Some(end) = lowercase[start..].find('>').map(|i| start + i) else {
+            break;
+        };
+        let tag = attributes(&html[start + "<meta".len()..end]);
+        let key = tag.get("property").or_else(|| tag.get("name"));
+        if let (Some(key), Some(content)) = (key, tag.get("content")) {
+            properties
+                .entry(key.to_ascii_lowercase())
+                .or_insert_with(|| decode_entities(content));
+        }
+        from = end;
+    }
+    properties
+}
+
+/// Attributes of a tag, by lowercase name
+fn attributes(tag: &str) -> HashMap<String, String> {
+    let mut attributes = HashMap::new();
+    let mut rest = tag;
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == '/');
+        let name_len = rest
+            .find(|c: char| c.is_whitespace() || c == '=' || c == '/')
+            .unwrap_or(rest.len());
+        if name_len == 0 {
+            return attributes;
+        }
+        let name = rest[..name_len].to_ascii_lowercase();
+        rest = rest[name_len..].trim_start();
+
+        let Some(value) = rest.strip_prefix('=') else {
+            attributes.insert(name, String::new());
+            continue;
+        };
+        let value = value.trim_start();
+        let (value, remaining) = match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let value = &value[1..];
+                let end = value.find(quote).unwrap_or(value.len());
+                (&value[..end], value.get(end + 1..).unwrap_or_default())
+            }
+            _ => {
+                let end = value.find(char::is_whitespace).unwrap_or(value.len());
+                (&value[..end], &value[end..])
+            }
+        };
+        attributes.insert(name, value.to_string());
+        rest = remaining;
+    }
+}
+
+/// Text of the `<title>` of the page
+fn title(html: &str) -> Option<String> {
+    let lowercase = html.to_ascii_lowercase();
+    let start = lowercase.find("<title")?;
+    let start = start + lowercase[start..].find('>')? + 1;
+    let end = start + lowercase[start..].find("</title")?;
+    let title = decode_entities(html[start..end].trim());
+    (!title.is_empty()).then_some(title)
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&#x27;", "'")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
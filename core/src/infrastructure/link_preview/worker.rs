@@ -0,0 +1,74 @@
+use std::sync::Arc;
+
+use tokio::{
+    sync::{Semaphore, broadcast::error::RecvError},
+    task::JoinHandle,
+};
+
+use crate::domain::message::{
+    events::{MessageEvent, MessageEventBus},
+    ports::MessageService,
+};
+
+/// Unfurls the links of new messages in the background, so posting a message
+/// never waits on the linked sites
+pub struct LinkPreviewWorker<S>
+where
+    S: MessageService,
+{
+    service: Arc<S>,
+    /// Messages unfurled at once
+    permits: Arc<Semaphore>,
+}
+
+impl<S> LinkPreviewWorker<S>
+where
+    S: MessageService + 'static,
+{
+    pub fn new(service: S, max_concurrent_unfurls: usize) -> Self {
+        Self {
+            service: Arc::new(service),
+            permits: Arc::new(Semaphore::new(max_concurrent_unfurls.max(1))),
+        }
+    }
+
+    /// Unfurl the links of the messages created on `events`.
+    ///
+    /// Like the attachment scanner, follow the bus of the service itself for
+    /// each message to be unfurled once, by the instance that created it.
+    /// Messages skipped because the worker lagged behind the bus stay
+    /// without embeds.
+    pub fn follow(self, events: &MessageEventBus) -> JoinHandle<()> {
+        let mut receiver = events.subscribe();
+        tokio::spawn(async move {
+            loop {
+                let message = match receiver.recv().await {
+                    Ok(MessageEvent::Created(message)) if message.content.contains("://") => {
+                        message
+                    }
+                    Ok(_) => continue,
+                    Err(RecvError::Lagged(skipped)) => {
+                        tracing::warn!(skipped, "link previews lagged behind message events");
+                        continue;
+                    }
+                    Err(RecvError::Closed) => return,
+                };
+
+                let Ok(permit) = self.permits.clone().acquire_owned().await else {
+                    return;
+                };
+                let service = self.service.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = service.unfurl_links(&message).await {
+                        tracing::warn!(
+                            error = %e,
+                            message_id = %message.id,
+                            "failed to store the link previews of a message"
+                        );
+                    }
+                    drop(permit);
+                });
+            }
+        })
+    }
+}
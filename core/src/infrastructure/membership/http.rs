@@ -0,0 +1,83 @@
+use std::{fmt::Display, time::Duration};
+
+use reqwest::{Client, StatusCode};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::domain::{
+    common::CoreError,
+    membership::ports::MemberDirectory,
+    message::entities::{ChannelId, UserId},
+};
+
+/// Holders of roles asked to the members service.
+///
+/// The service is sent `POST <endpoint>` with the channel and the mentioned
+/// roles, and answers the IDs of the members of the channel holding one of
+/// them, or `404 Not Found` when it does not know the channel.
+#[derive(Clone)]
+pub struct HttpMemberDirectory {
+    client: Client,
+    endpoint: String,
+}
+
+#[derive(Serialize)]
+struct RoleMembersRequest<'a> {
+    channel_id: Uuid,
+    role_ids: &'a [Uuid],
+}
+
+#[derive(Deserialize)]
+struct RoleMembersResponse {
+    user_ids: Vec<Uuid>,
+}
+
+impl HttpMemberDirectory {
+    /// Ask `endpoint` for the holders of roles, giving up after `timeout`
+    pub fn new(endpoint: impl Into<String>, timeout: Duration) -> Result<Self, CoreError> {
+        let client = Client::builder()
+            .timeout(timeout)
+            .build()
+            .map_err(service_error)?;
+        Ok(Self {
+            client,
+            endpoint: endpoint.into(),
+        })
+    }
+}
+
+fn service_error(e: impl Display) -> CoreError {
+    CoreError::ServiceUnavailable(format!("members service: {e}"))
+}
+
+#[async_trait::async_trait]
+impl MemberDirectory for HttpMemberDirectory {
+    async fn role_members(
+        &self,
+        channel_id: &ChannelId,
+        role_ids: &[Uuid],
+    ) -> Result<Vec<UserId>, CoreError> {
+        let response = self
+            .client
+            .post(&self.endpoint)
+            .json(&RoleMembersRequest {
+                channel_id: channel_id.0,
+                role_ids,
+            })
+            .send()
+            .await
+            .map_err(service_error)?;
+
+        let status = response.status();
+        if status == StatusCode::NOT_FOUND {
+            return Ok(Vec::new());
+        }
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(service_error(format!("{status}: {body}")));
+        }
+
+        let members: RoleMembersResponse = response.json().await.map_err(service_error)?;
+        Ok(members.user_ids.into_iter().map(UserId).collect())
+    }
+}
@@ -0,0 +1,15 @@
+//! Members of channels, to resolve the groups messages mention
+//!
+//! - `MongoChannelMembership` keeps one document per member and channel in
+//!   the `channel_members` collection, members who left being kept with
+//!   their last change so older events cannot bring them back
+//! - `HttpMemberDirectory` (feature `http-members`) asks the members service
+//!   for the holders of roles
+
+#[cfg(feature = "http-members")]
+mod http;
+mod mongo;
+
+#[cfg(feature = "http-members")]
+pub use http::HttpMemberDirectory;
+pub use mongo::MongoChannelMembership;
@@ -0,0 +1,163 @@
+use chrono::{DateTime, Utc};
+use futures::TryStreamExt;
+use mongodb::{
+    Collection, Database, IndexModel,
+    bson::{Bson, DateTime as BsonDateTime, Document, doc},
+    error::{ErrorKind, WriteFailure},
+};
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        common::CoreError,
+        membership::{entities::ChannelMember, ports::ChannelMembership},
+        message::entities::{ChannelId, UserId},
+    },
+    infrastructure::message::{dto::binary_to_uuid, encoding::standard_uuid},
+};
+
+pub(crate) const CHANNEL_MEMBERS_COLLECTION: &str = "channel_members";
+
+/// Server error code of duplicate key errors
+const DUPLICATE_KEY: i32 = 11000;
+
+#[derive(Clone)]
+pub struct MongoChannelMembership {
+    collection: Collection<Document>,
+}
+
+impl MongoChannelMembership {
+    pub fn new(db: &Database) -> Self {
+        Self {
+            collection: db.collection(CHANNEL_MEMBERS_COLLECTION),
+        }
+    }
+
+    /// Create the index listing the members of a channel (idempotent)
+    pub async fn ensure_indexes(&self) -> Result<(), CoreError> {
+        self.collection
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! { "channel_id": 1, "left": 1 })
+                    .build(),
+            )
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        Ok(())
+    }
+
+    /// Apply the change of `user_id` in `channel_id` made `at`, unless a
+    /// newer one was applied already
+    async fn apply(
+        &self,
+        channel_id: &ChannelId,
+        user_id: &UserId,
+        fields: Document,
+        at: DateTime<Utc>,
+    ) -> Result<bool, CoreError> {
+        let at = BsonDateTime::from_millis(at.timestamp_millis());
+        let mut set = doc! {
+            "channel_id": standard_uuid(channel_id.0),
+            "user_id": standard_uuid(user_id.0),
+            "updated_at": at,
+        };
+        set.extend(fields);
+
+        // When a newer change was applied nothing matches, and the upsert
+        // fails on the `_id` already taken
+        let result = self
+            .collection
+            .update_one(
+                doc! {
+                    "_id": format!("{channel_id}:{user_id}"),
+                    "updated_at": { "$lte": at },
+                },
+                doc! { "$set": set },
+            )
+            .upsert(true)
+            .await;
+
+        match result {
+            Ok(_) => Ok(true),
+            Err(e) if is_duplicate_key(&e.kind) => Ok(false),
+            Err(e) => Err(CoreError::DatabaseError { msg: e.to_string() }),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl ChannelMembership for MongoChannelMembership {
+    async fn upsert_member(&self, member: &ChannelMember) -> Result<bool, CoreError> {
+        let role_ids: Vec<Bson> = member
+            .role_ids
+            .iter()
+            .map(|role_id| Bson::Binary(standard_uuid(*role_id)))
+            .collect();
+        self.apply(
+            &member.channel_id,
+            &member.user_id,
+            doc! { "role_ids": role_ids, "left": false },
+            member.updated_at,
+        )
+        .await
+    }
+
+    async fn remove_member(
+        &self,
+        channel_id: &ChannelId,
+        user_id: &UserId,
+        left_at: DateTime<Utc>,
+    ) -> Result<bool, CoreError> {
+        self.apply(
+            channel_id,
+            user_id,
+            doc! { "role_ids": [], "left": true },
+            left_at,
+        )
+        .await
+    }
+
+    async fn list_members(
+        &self,
+        channel_id: &ChannelId,
+        role_ids: Option<&[Uuid]>,
+    ) -> Result<Vec<UserId>, CoreError> {
+        let mut filter = doc! {
+            "channel_id": standard_uuid(channel_id.0),
+            "left": false,
+        };
+        if let Some(role_ids) = role_ids {
+            let role_ids: Vec<Bson> = role_ids
+                .iter()
+                .map(|role_id| Bson::Binary(standard_uuid(*role_id)))
+                .collect();
+            filter.insert("role_ids", doc! { "$in": role_ids });
+        }
+
+        let documents: Vec<Document> = self
+            .collection
+            .find(filter)
+            .projection(doc! { "user_id": 1 })
+            .sort(doc! { "user_id": 1 })
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?
+            .try_collect()
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        documents
+            .iter()
+            .map(|document| match document.get("user_id") {
+                Some(Bson::Binary(user_id)) => binary_to_uuid(user_id).map(UserId),
+                _ => Err(CoreError::DatabaseError {
+                    msg: "channel member without user_id".to_string(),
+                }),
+            })
+            .collect()
+    }
+}
+
+fn is_duplicate_key(kind: &ErrorKind) -> bool {
+    matches!(kind, ErrorKind::Write(WriteFailure::WriteError(error)) if error.code == DUPLICATE_KEY)
+}
@@ -0,0 +1,325 @@
+//! Realtime message events sourced from a MongoDB change stream.
+//!
+//! Unlike the service-level [`MessageEventBus`] publications, which only see
+//! writes made by the current process, the change stream observes every write
+//! to the `messages` collection, so all API replicas push the same events to
+//! their subscribers. Each replica persists its own resume token every few
+//! events or seconds (see [`TokenCheckpoint`]), letting a restarted watcher
+//! pick up close to where the previous one stopped: the events received since
+//! the last save are published again.
+
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use futures::TryStreamExt;
+use mongodb::{
+    Collection, Database,
+    bson::{Bson, Document, doc},
+    change_stream::event::{OperationType, ResumeToken},
+    options::{FullDocumentBeforeChangeType, FullDocumentType},
+};
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+
+use crate::{
+    domain::{
+        common::CoreError,
+        message::{
+            entities::{Message, MessageId},
+            events::{MessageEvent, MessageEventBus},
+        },
+    },
+    infrastructure::message::{
+        dto::{MessageDocument, binary_to_uuid},
+        repositories::mongo::MongoMessageRepository,
+    },
+};
+
+/// Delay before reopening the change stream after an error
+const RESTART_DELAY: Duration = Duration::from_secs(1);
+
+/// Name under which the resume token is stored by default, suffixed with the
+/// replica by [`MessageChangeStreamWatcher::for_instance`]
+const DEFAULT_WATCHER_NAME: &str = "messages";
+
+/// When the watcher saves its resume token: after `every` events or once
+/// `interval` elapsed since the last save, whichever comes first
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenCheckpoint {
+    pub every: u32,
+    pub interval: Duration,
+}
+
+impl Default for TokenCheckpoint {
+    fn default() -> Self {
+        Self {
+            every: 100,
+            interval: Duration::from_secs(5),
+        }
+    }
+}
+
+impl TokenCheckpoint {
+    /// Whether a token followed by `unsaved` events, the last save being
+    /// `elapsed` ago, is due to be saved
+    pub fn is_due(&self, unsaved: u32, elapsed: Duration) -> bool {
+        unsaved > 0 && (unsaved >= self.every || elapsed >= self.interval)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ResumeTokenDocument {
+    #[serde(rename = "_id")]
+    name: String,
+    token: ResumeToken,
+    updated_at: String,
+}
+
+#[derive(Clone)]
+pub struct MessageChangeStreamWatcher {
+    messages: Collection<MessageDocument>,
+    tokens: Collection<ResumeTokenDocument>,
+    name: String,
+    bus: MessageEventBus,
+    /// Fills in the reaction counts of the published messages, so they match
+    /// the REST responses; `None` publishes them without
+    reactions: Option<MongoMessageRepository>,
+    /// `false` on read-only replicas, which must not write to the database
+    persist_tokens: bool,
+    checkpoint: TokenCheckpoint,
+}
+
+impl MessageChangeStreamWatcher {
+    pub fn new(db: &Database, bus: MessageEventBus) -> Self {
+        Self {
+            messages: db.collection::<MessageDocument>("messages"),
+            tokens: db.collection::<ResumeTokenDocument>("change_stream_resume_tokens"),
+            name: DEFAULT_WATCHER_NAME.to_string(),
+            bus,
+            reactions: None,
+            persist_tokens: true,
+            checkpoint: TokenCheckpoint::default(),
+        }
+    }
+
+    /// Store the resume token under another name
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Store the resume token of the replica `instance_id` apart from the
+    /// others, so replicas do not overwrite each other's position
+    pub fn for_instance(self, instance_id: &str) -> Self {
+        self.with_name(format!("{DEFAULT_WATCHER_NAME}:{instance_id}"))
+    }
+
+    /// Save the resume token following `checkpoint` rather than the default
+    pub fn with_checkpoint(mut self, checkpoint: TokenCheckpoint) -> Self {
+        self.checkpoint = checkpoint;
+        self
+    }
+
+    /// Never write the resume token: the watcher still resumes from the last
+    /// persisted one, but a restart reopens the stream from there
+    pub fn without_token_persistence(mut self) -> Self {
+        self.persist_tokens = false;
+        self
+    }
+
+    /// Publish the messages with their reaction counts read from `repository`
+    pub fn with_reaction_counts(mut self, repository: MongoMessageRepository) -> Self {
+        self.reactions = Some(repository);
+        self
+    }
+
+    /// Watch in the background, reopening the stream after errors
+    pub fn spawn(self) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = self.watch().await {
+                    tracing::warn!(error = %e, watcher = %self.name, "message change stream failed, restarting");
+                }
+                tokio::time::sleep(RESTART_DELAY).await;
+            }
+        })
+    }
+
+    /// Open the change stream from the persisted resume token and publish
+    /// events until the stream ends or fails
+    pub async fn watch(&self) -> Result<(), CoreError> {
+        let mut watch = self
+            .messages
+            .watch()
+            .full_document(FullDocumentType::UpdateLookup)
+            // pre-images carry the channel of deleted messages; they require
+            // `changeStreamPreAndPostImages` to be enabled on the collection
+            .full_document_before_change(FullDocumentBeforeChangeType::WhenAvailable);
+        if let Some(token) = self.load_resume_token().await? {
+            watch = watch.start_after(token);
+        }
+
+        let mut stream = watch
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+        tracing::info!(watcher = %self.name, "message change stream opened");
+
+        let mut unsaved = 0;
+        let mut last_save = Instant::now();
+        loop {
+            let change = match stream.try_next().await {
+                Ok(Some(change)) => change,
+                Ok(None) => break,
+                Err(e) => {
+                    // keep the position reached before the failure
+                    if unsaved > 0 {
+                        self.checkpoint_token(stream.resume_token()).await?;
+                    }
+                    return Err(CoreError::DatabaseError { msg: e.to_string() });
+                }
+            };
+
+            match to_message_event(
+                change.operation_type,
+                change.full_document,
+                change.full_document_before_change,
+                change.document_key,
+            ) {
+                Ok(Some(event)) => self.bus.publish(self.with_reactions(event).await),
+                Ok(None) => {}
+                Err(e) => tracing::warn!(error = %e, "skipping undecodable message change"),
+            }
+
+            unsaved += 1;
+            if !self.checkpoint.is_due(unsaved, last_save.elapsed()) {
+                continue;
+            }
+            self.checkpoint_token(stream.resume_token()).await?;
+            unsaved = 0;
+            last_save = Instant::now();
+        }
+
+        if unsaved > 0 {
+            self.checkpoint_token(stream.resume_token()).await?;
+        }
+
+        Ok(())
+    }
+
+    /// `event` with the reaction counts of its message; a message whose
+    /// counts cannot be read is published without them rather than dropped
+    async fn with_reactions(&self, event: MessageEvent) -> MessageEvent {
+        let Some(repository) = &self.reactions else {
+            return event;
+        };
+        match event {
+            MessageEvent::Created(message) => {
+                MessageEvent::Created(Self::count_reactions(repository, message).await)
+            }
+            MessageEvent::Updated(message) => {
+                MessageEvent::Updated(Self::count_reactions(repository, message).await)
+            }
+            event => event,
+        }
+    }
+
+    async fn count_reactions(repository: &MongoMessageRepository, message: Message) -> Message {
+        let mut messages = [message];
+        if let Err(e) = repository.attach_reaction_counts(&mut messages).await {
+            tracing::warn!(
+                error = %e,
+                message_id = %messages[0].id,
+                "failed to count the reactions of a changed message"
+            );
+        }
+        let [message] = messages;
+        message
+    }
+
+    async fn load_resume_token(&self) -> Result<Option<ResumeToken>, CoreError> {
+        let record = self
+            .tokens
+            .find_one(doc! { "_id": &self.name })
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        Ok(record.map(|record| record.token))
+    }
+
+    /// Save `token`, unless tokens are not persisted
+    async fn checkpoint_token(&self, token: Option<ResumeToken>) -> Result<(), CoreError> {
+        match token {
+            Some(token) if self.persist_tokens => self.save_resume_token(token).await,
+            _ => Ok(()),
+        }
+    }
+
+    async fn save_resume_token(&self, token: ResumeToken) -> Result<(), CoreError> {
+        let record = ResumeTokenDocument {
+            name: self.name.clone(),
+            token,
+            updated_at: Utc::now().to_rfc3339(),
+        };
+        self.tokens
+            .replace_one(doc! { "_id": &self.name }, &record)
+            .upsert(true)
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        Ok(())
+    }
+}
+
+/// Convert a change of the `messages` collection into a domain event.
+///
+/// Returns `None` for changes that carry no message event: operations other
+/// than insert/update/replace/delete, updates of since-deleted messages,
+/// deletions without a pre-image (the channel is unknown) and purges of soft
+/// deleted messages. Messages come without their reaction counts, which live
+/// in another collection: the watcher fills them in before publishing.
+pub fn to_message_event(
+    operation: OperationType,
+    full_document: Option<MessageDocument>,
+    before_change: Option<MessageDocument>,
+    document_key: Option<Document>,
+) -> Result<Option<MessageEvent>, CoreError> {
+    let event = match operation {
+        OperationType::Insert => full_document
+            .map(Message::try_from)
+            .transpose()?
+            .map(MessageEvent::Created),
+        // A soft delete is an update setting `deleted_at`
+        OperationType::Update | OperationType::Replace => match full_document {
+            Some(document) if document.deleted_at.is_some() => Some(MessageEvent::Deleted {
+                id: MessageId(binary_to_uuid(&document.id)?),
+                channel_id: binary_to_uuid(&document.channel_id)?.into(),
+            }),
+            document => document
+                .map(Message::try_from)
+                .transpose()?
+                .map(MessageEvent::Updated),
+        },
+        OperationType::Delete => {
+            let Some(before_change) = before_change else {
+                tracing::debug!("message deleted without pre-image, skipping");
+                return Ok(None);
+            };
+            // purging a soft deleted message: its deletion was published already
+            if before_change.deleted_at.is_some() {
+                return Ok(None);
+            }
+            let id = match document_key.as_ref().and_then(|key| key.get("_id")) {
+                Some(Bson::Binary(id)) => MessageId(binary_to_uuid(id)?),
+                _ => MessageId(binary_to_uuid(&before_change.id)?),
+            };
+
+            Some(MessageEvent::Deleted {
+                id,
+                channel_id: binary_to_uuid(&before_change.channel_id)?.into(),
+            })
+        }
+        _ => None,
+    };
+
+    Ok(event)
+}
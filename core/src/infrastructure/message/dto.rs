@@ -0,0 +1,385 @@
+//! Persistence models for the `messages`, `message_reactions`, `message_revisions`,
+//! `legal_holds` and `channel_write_locks` collections.
+//!
+//! These types pin down the exact BSON encoding used in MongoDB so the domain
+//! entities (and the API responses built from them) can evolve independently
+//! of the storage format:
+//! - identifiers are stored as generic-subtype binaries, except
+//!   `reply_to_message_id` which has always been stored as a string
+//! - timestamps are stored as RFC3339 strings
+//!
+//! Documents in the standard encoding (UUID-subtype binaries and BSON dates)
+//! are read too, see [`encoding`].
+
+use chrono::{DateTime, Utc};
+use mongodb::bson::{self, Binary, spec::BinarySubtype};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        common::CoreError,
+        message::entities::{
+            Attachment, AttachmentId, ChannelWriteLock, Embed, ImagePreview, LegalHold,
+            LegalHoldId, LegalHoldScope, Mentions, Message, MessageId, MessageRevision, Reaction, UserId,
+        },
+    },
+    infrastructure::message::encoding,
+};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AttachmentDocument {
+    #[serde(deserialize_with = "encoding::uuid")]
+    pub id: Binary,
+    pub name: String,
+    pub url: String,
+    /// Missing on attachments stored before sizes were tracked
+    #[serde(default)]
+    pub size: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preview: Option<ImagePreview>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MessageDocument {
+    #[serde(rename = "_id", deserialize_with = "encoding::uuid")]
+    pub id: Binary,
+    #[serde(deserialize_with = "encoding::uuid")]
+    pub channel_id: Binary,
+    #[serde(deserialize_with = "encoding::uuid")]
+    pub author_id: Binary,
+    pub content: String,
+    pub reply_to_message_id: Option<String>,
+    pub attachments: Vec<AttachmentDocument>,
+    pub is_pinned: bool,
+    /// Missing on messages stored before revisions were introduced
+    #[serde(default)]
+    pub revision: i64,
+    /// Missing on messages stored before sequences were introduced
+    #[serde(default)]
+    pub sequence: i64,
+    #[serde(default)]
+    pub reply_count: i64,
+    #[serde(default, deserialize_with = "encoding::optional_timestamp")]
+    pub last_reply_at: Option<String>,
+    /// Set on threads archived after staying idle, null once they get a reply
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "encoding::optional_timestamp"
+    )]
+    pub archived_at: Option<String>,
+    #[serde(deserialize_with = "encoding::timestamp")]
+    pub created_at: String,
+    #[serde(default, deserialize_with = "encoding::optional_timestamp")]
+    pub updated_at: Option<String>,
+    /// Set while the message is pinned, to when it was pinned
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "encoding::optional_timestamp"
+    )]
+    pub pinned_at: Option<String>,
+    /// Set when the message is soft deleted; such messages are never read back
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "encoding::optional_timestamp"
+    )]
+    pub deleted_at: Option<String>,
+    /// Left out without a nonce, so the unique nonce index skips the document
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub embeds: Vec<Embed>,
+    /// Left out on messages that never expire. A BSON date rather than a
+    /// string, as the TTL index removing expired messages requires one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<bson::DateTime>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReactionDocument {
+    #[serde(deserialize_with = "encoding::uuid")]
+    pub message_id: Binary,
+    #[serde(deserialize_with = "encoding::uuid")]
+    pub user_id: Binary,
+    pub emoji: String,
+    #[serde(deserialize_with = "encoding::timestamp")]
+    pub created_at: String,
+}
+
+/// Previous content of an edited message
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MessageRevisionDocument {
+    #[serde(deserialize_with = "encoding::uuid")]
+    pub message_id: Binary,
+    pub revision: i64,
+    pub content: String,
+    #[serde(deserialize_with = "encoding::timestamp")]
+    pub written_at: String,
+    #[serde(deserialize_with = "encoding::timestamp")]
+    pub replaced_at: String,
+}
+
+/// Legal hold, holding either `channel_id` or `user_id` depending on its scope
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LegalHoldDocument {
+    #[serde(rename = "_id", deserialize_with = "encoding::uuid")]
+    pub id: Binary,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "encoding::optional_uuid"
+    )]
+    pub channel_id: Option<Binary>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "encoding::optional_uuid"
+    )]
+    pub user_id: Option<Binary>,
+    pub reference: String,
+    #[serde(deserialize_with = "encoding::uuid")]
+    pub placed_by: Binary,
+    #[serde(deserialize_with = "encoding::timestamp")]
+    pub placed_at: String,
+}
+
+/// Write lock of a channel, keyed by the channel ID
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChannelWriteLockDocument {
+    #[serde(rename = "_id", deserialize_with = "encoding::uuid")]
+    pub channel_id: Binary,
+    pub reason: String,
+    #[serde(deserialize_with = "encoding::uuid")]
+    pub locked_by: Binary,
+    #[serde(deserialize_with = "encoding::timestamp")]
+    pub locked_at: String,
+    #[serde(deserialize_with = "encoding::timestamp")]
+    pub expires_at: String,
+}
+
+/// Encode a UUID the way identifiers are stored in the `messages` collection
+pub fn uuid_to_binary(uuid: Uuid) -> Binary {
+    Binary {
+        subtype: BinarySubtype::Generic,
+        bytes: uuid.as_bytes().to_vec(),
+    }
+}
+
+pub fn binary_to_uuid(binary: &Binary) -> Result<Uuid, CoreError> {
+    Uuid::from_slice(&binary.bytes).map_err(|e| CoreError::DatabaseError { msg: e.to_string() })
+}
+
+fn parse_timestamp(value: &str) -> Result<DateTime<Utc>, CoreError> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|date| date.with_timezone(&Utc))
+        .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })
+}
+
+impl From<&Attachment> for AttachmentDocument {
+    fn from(attachment: &Attachment) -> Self {
+        Self {
+            id: uuid_to_binary(attachment.id.0),
+            name: attachment.name.clone(),
+            url: attachment.url.clone(),
+            size: attachment.size as i64,
+            preview: attachment.preview.clone(),
+        }
+    }
+}
+
+impl TryFrom<AttachmentDocument> for Attachment {
+    type Error = CoreError;
+
+    fn try_from(document: AttachmentDocument) -> Result<Self, Self::Error> {
+        Ok(Attachment {
+            id: AttachmentId(binary_to_uuid(&document.id)?),
+            name: document.name,
+            url: document.url,
+            size: document.size.max(0) as u64,
+            preview: document.preview,
+        })
+    }
+}
+
+impl From<&Message> for MessageDocument {
+    fn from(message: &Message) -> Self {
+        Self {
+            id: uuid_to_binary(message.id.0),
+            channel_id: uuid_to_binary(message.channel_id.0),
+            author_id: uuid_to_binary(message.author_id.0),
+            content: message.content.clone(),
+            reply_to_message_id: message.reply_to_message_id.map(|id| id.to_string()),
+            attachments: message.attachments.iter().map(AttachmentDocument::from).collect(),
+            is_pinned: message.is_pinned,
+            revision: message.revision as i64,
+            sequence: message.sequence as i64,
+            reply_count: message.reply_count as i64,
+            last_reply_at: message.last_reply_at.map(|date| date.to_rfc3339()),
+            archived_at: message.archived_at.map(|date| date.to_rfc3339()),
+            created_at: message.created_at.to_rfc3339(),
+            updated_at: message.updated_at.map(|date| date.to_rfc3339()),
+            pinned_at: None,
+            deleted_at: message.deleted_at.map(|date| date.to_rfc3339()),
+            nonce: message.nonce.clone(),
+            embeds: message.embeds.clone(),
+            expires_at: message
+                .expires_at
+                .map(|date| bson::DateTime::from_millis(date.timestamp_millis())),
+        }
+    }
+}
+
+impl TryFrom<MessageDocument> for Message {
+    type Error = CoreError;
+
+    fn try_from(document: MessageDocument) -> Result<Self, Self::Error> {
+        let reply_to_message_id = document
+            .reply_to_message_id
+            .as_deref()
+            .map(Uuid::parse_str)
+            .transpose()
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?
+            .map(MessageId);
+
+        let mentions = Mentions::parse(&document.content);
+        Ok(Message {
+            id: MessageId(binary_to_uuid(&document.id)?),
+            channel_id: binary_to_uuid(&document.channel_id)?.into(),
+            author_id: binary_to_uuid(&document.author_id)?.into(),
+            content: document.content,
+            reply_to_message_id,
+            attachments: document
+                .attachments
+                .into_iter()
+                .map(Attachment::try_from)
+                .collect::<Result<_, _>>()?,
+            is_pinned: document.is_pinned,
+            // reactions live in their own collection and are attached by the repository
+            reactions: Vec::new(),
+            revision: document.revision.max(0) as u64,
+            sequence: document.sequence as u64,
+            reply_count: document.reply_count.max(0) as u64,
+            last_reply_at: document.last_reply_at.as_deref().map(parse_timestamp).transpose()?,
+            archived_at: document.archived_at.as_deref().map(parse_timestamp).transpose()?,
+            created_at: parse_timestamp(&document.created_at)?,
+            updated_at: document.updated_at.as_deref().map(parse_timestamp).transpose()?,
+            deleted_at: document.deleted_at.as_deref().map(parse_timestamp).transpose()?,
+            nonce: document.nonce,
+            embeds: document.embeds,
+            mentions,
+            expires_at: document
+                .expires_at
+                .and_then(|date| DateTime::from_timestamp_millis(date.timestamp_millis())),
+        })
+    }
+}
+
+impl TryFrom<ReactionDocument> for Reaction {
+    type Error = CoreError;
+
+    fn try_from(document: ReactionDocument) -> Result<Self, Self::Error> {
+        Ok(Reaction {
+            message_id: MessageId(binary_to_uuid(&document.message_id)?),
+            user_id: UserId(binary_to_uuid(&document.user_id)?),
+            emoji: document.emoji,
+            created_at: parse_timestamp(&document.created_at)?,
+        })
+    }
+}
+
+impl From<&MessageRevision> for MessageRevisionDocument {
+    fn from(revision: &MessageRevision) -> Self {
+        Self {
+            message_id: uuid_to_binary(revision.message_id.0),
+            revision: revision.revision as i64,
+            content: revision.content.clone(),
+            written_at: revision.written_at.to_rfc3339(),
+            replaced_at: revision.replaced_at.to_rfc3339(),
+        }
+    }
+}
+
+impl TryFrom<MessageRevisionDocument> for MessageRevision {
+    type Error = CoreError;
+
+    fn try_from(document: MessageRevisionDocument) -> Result<Self, Self::Error> {
+        Ok(MessageRevision {
+            message_id: MessageId(binary_to_uuid(&document.message_id)?),
+            revision: document.revision.max(0) as u64,
+            content: document.content,
+            written_at: parse_timestamp(&document.written_at)?,
+            replaced_at: parse_timestamp(&document.replaced_at)?,
+        })
+    }
+}
+
+impl From<&LegalHold> for LegalHoldDocument {
+    fn from(hold: &LegalHold) -> Self {
+        let (channel_id, user_id) = match hold.scope {
+            LegalHoldScope::Channel(channel_id) => (Some(uuid_to_binary(channel_id.0)), None),
+            LegalHoldScope::User(user_id) => (None, Some(uuid_to_binary(user_id.0))),
+        };
+
+        Self {
+            id: uuid_to_binary(hold.id.0),
+            channel_id,
+            user_id,
+            reference: hold.reference.clone(),
+            placed_by: uuid_to_binary(hold.placed_by.0),
+            placed_at: hold.placed_at.to_rfc3339(),
+        }
+    }
+}
+
+impl TryFrom<LegalHoldDocument> for LegalHold {
+    type Error = CoreError;
+
+    fn try_from(document: LegalHoldDocument) -> Result<Self, Self::Error> {
+        let scope = match (&document.channel_id, &document.user_id) {
+            (Some(channel_id), None) => LegalHoldScope::Channel(binary_to_uuid(channel_id)?.into()),
+            (None, Some(user_id)) => LegalHoldScope::User(UserId(binary_to_uuid(user_id)?)),
+            _ => {
+                return Err(CoreError::DatabaseError {
+                    msg: "legal hold must hold either a channel_id or a user_id".to_string(),
+                });
+            }
+        };
+
+        Ok(LegalHold {
+            id: LegalHoldId(binary_to_uuid(&document.id)?),
+            scope,
+            reference: document.reference,
+            placed_by: UserId(binary_to_uuid(&document.placed_by)?),
+            placed_at: parse_timestamp(&document.placed_at)?,
+        })
+    }
+}
+
+impl From<&ChannelWriteLock> for ChannelWriteLockDocument {
+    fn from(lock: &ChannelWriteLock) -> Self {
+        Self {
+            channel_id: uuid_to_binary(lock.channel_id.0),
+            reason: lock.reason.clone(),
+            locked_by: uuid_to_binary(lock.locked_by.0),
+            locked_at: lock.locked_at.to_rfc3339(),
+            expires_at: lock.expires_at.to_rfc3339(),
+        }
+    }
+}
+
+impl TryFrom<ChannelWriteLockDocument> for ChannelWriteLock {
+    type Error = CoreError;
+
+    fn try_from(document: ChannelWriteLockDocument) -> Result<Self, Self::Error> {
+        Ok(ChannelWriteLock {
+            channel_id: binary_to_uuid(&document.channel_id)?.into(),
+            reason: document.reason,
+            locked_by: UserId(binary_to_uuid(&document.locked_by)?),
+            locked_at: parse_timestamp(&document.locked_at)?,
+            expires_at: parse_timestamp(&document.expires_at)?,
+        })
+    }
+}
@@ -0,0 +1,215 @@
+//! Compatibility between the two encodings of identifiers and timestamps in
+//! MongoDB, so the storage can move from one to the other without downtime:
+//! - legacy: UUIDs as generic-subtype binaries, timestamps as RFC3339 strings
+//! - standard: UUIDs as UUID-subtype binaries, timestamps as BSON dates
+//!
+//! Documents are read in either encoding and normalized to the legacy one,
+//! which is still the one written. Filters on the reference fields converted
+//! by [`backfill_standard_uuids`] match both encodings; `_id`s are immutable
+//! and keep the legacy encoding.
+
+use chrono::{DateTime, Utc};
+use futures::TryStreamExt;
+use mongodb::{
+    Database,
+    bson::{Binary, Bson, Document, doc, spec::BinarySubtype},
+};
+use serde::{Deserialize, Deserializer, de::Error};
+use uuid::Uuid;
+
+use crate::domain::common::CoreError;
+
+/// Reference fields converted by the backfill, per collection. Fields holding
+/// an array of subdocuments are given as `array.field`.
+pub const UUID_FIELDS: &[(&str, &[&str])] = &[
+    ("messages", &["channel_id", "author_id", "attachments.id"]),
+    ("message_reactions", &["message_id", "user_id"]),
+    ("message_revisions", &["message_id"]),
+    ("reaction_notification_mutes", &["user_id", "message_id"]),
+    ("legal_holds", &["channel_id", "user_id", "placed_by"]),
+    ("thread_follows", &["user_id", "message_id", "channel_id"]),
+    ("saved_messages", &["user_id", "message_id", "channel_id"]),
+    ("channel_write_locks", &["locked_by"]),
+    ("message_archives", &["channel_id"]),
+    ("attachment_uploads", &["channel_id", "uploader_id", "message_id"]),
+];
+
+pub fn standard_uuid(uuid: Uuid) -> Binary {
+    Binary {
+        subtype: BinarySubtype::Uuid,
+        bytes: uuid.as_bytes().to_vec(),
+    }
+}
+
+fn legacy_uuid(uuid: Uuid) -> Binary {
+    Binary {
+        subtype: BinarySubtype::Generic,
+        bytes: uuid.as_bytes().to_vec(),
+    }
+}
+
+/// Condition matching a reference field holding `uuid` in either encoding
+pub fn uuid_match(uuid: Uuid) -> Document {
+    doc! { "$in": uuids_in([uuid]) }
+}
+
+/// Both encodings of each UUID, for `$in` and `$nin` conditions
+pub fn uuids_in(uuids: impl IntoIterator<Item = Uuid>) -> Vec<Bson> {
+    uuids
+        .into_iter()
+        .flat_map(|uuid| {
+            [
+                Bson::Binary(legacy_uuid(uuid)),
+                Bson::Binary(standard_uuid(uuid)),
+            ]
+        })
+        .collect()
+}
+
+fn normalize_uuid<E: Error>(value: Bson) -> Result<Binary, E> {
+    match value {
+        Bson::Binary(binary)
+            if matches!(binary.subtype, BinarySubtype::Generic | BinarySubtype::Uuid) =>
+        {
+            Ok(legacy_uuid(Uuid::from_slice(&binary.bytes).map_err(E::custom)?))
+        }
+        other => Err(E::custom(format!("expected a UUID binary, found {other}"))),
+    }
+}
+
+fn normalize_timestamp<E: Error>(value: Bson) -> Result<String, E> {
+    match value {
+        Bson::String(value) => Ok(value),
+        Bson::DateTime(date) => DateTime::<Utc>::from_timestamp_millis(date.timestamp_millis())
+            .map(|date| date.to_rfc3339())
+            .ok_or_else(|| E::custom("timestamp out of range")),
+        other => Err(E::custom(format!("expected a timestamp, found {other}"))),
+    }
+}
+
+/// `deserialize_with` of UUID fields
+pub fn uuid<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Binary, D::Error> {
+    normalize_uuid(Bson::deserialize(deserializer)?)
+}
+
+/// `deserialize_with` of optional UUID fields, to be used with `#[serde(default)]`
+pub fn optional_uuid<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Binary>, D::Error> {
+    match Bson::deserialize(deserializer)? {
+        Bson::Null => Ok(None),
+        value => normalize_uuid(value).map(Some),
+    }
+}
+
+/// `deserialize_with` of timestamp fields
+pub fn timestamp<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    normalize_timestamp(Bson::deserialize(deserializer)?)
+}
+
+/// `deserialize_with` of optional timestamp fields, to be used with `#[serde(default)]`
+pub fn optional_timestamp<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<String>, D::Error> {
+    match Bson::deserialize(deserializer)? {
+        Bson::Null => Ok(None),
+        value => normalize_timestamp(value).map(Some),
+    }
+}
+
+/// Standard encoding of a legacy UUID, `None` when `value` is not one
+fn to_standard(value: &Bson) -> Option<Bson> {
+    match value {
+        Bson::Binary(binary) if binary.subtype == BinarySubtype::Generic => {
+            let uuid = Uuid::from_slice(&binary.bytes).ok()?;
+            Some(Bson::Binary(standard_uuid(uuid)))
+        }
+        _ => None,
+    }
+}
+
+/// `$set` converting the legacy UUIDs of `fields` in `document`, `None` when
+/// there is nothing left to convert
+pub fn standard_uuid_update(document: &Document, fields: &[&str]) -> Option<Document> {
+    let mut set = Document::new();
+    for field in fields {
+        match field.split_once('.') {
+            Some((array, field)) => {
+                let Ok(items) = document.get_array(array) else {
+                    continue;
+                };
+                let mut changed = false;
+                let items: Vec<Bson> = items
+                    .iter()
+                    .map(|item| match item {
+                        Bson::Document(item) => {
+                            let mut item = item.clone();
+                            if let Some(value) = item.get(field).and_then(to_standard) {
+                                item.insert(field, value);
+                                changed = true;
+                            }
+                            Bson::Document(item)
+                        }
+                        other => other.clone(),
+                    })
+                    .collect();
+                if changed {
+                    set.insert(array, items);
+                }
+            }
+            None => {
+                if let Some(value) = document.get(field).and_then(to_standard) {
+                    set.insert(*field, value);
+                }
+            }
+        }
+    }
+
+    (!set.is_empty()).then(|| doc! { "$set": set })
+}
+
+/// Convert the reference fields of every collection to the standard UUID
+/// encoding, `batch_size` documents at a time. Converted documents are left
+/// alone, so the backfill can be stopped and run again; returns how many
+/// documents were converted.
+pub async fn backfill_standard_uuids(db: &Database, batch_size: u32) -> Result<u64, CoreError> {
+    let mut converted = 0;
+    for (name, fields) in UUID_FIELDS {
+        let collection = db.collection::<Document>(name);
+        let mut cursor = collection
+            .find(doc! {})
+            .batch_size(batch_size)
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        let mut collection_converted = 0;
+        while let Some(document) = cursor
+            .try_next()
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?
+        {
+            let (Some(id), Some(update)) =
+                (document.get("_id"), standard_uuid_update(&document, fields))
+            else {
+                continue;
+            };
+            // only while the converted fields are unchanged, not to undo a concurrent write
+            let mut filter = doc! { "_id": id.clone() };
+            if let Ok(set) = update.get_document("$set") {
+                for key in set.keys() {
+                    filter.insert(key, document.get(key).cloned().unwrap_or(Bson::Null));
+                }
+            }
+            let result = collection
+                .update_one(filter, update)
+                .await
+                .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+            collection_converted += result.modified_count;
+        }
+
+        tracing::info!(collection = %name, converted = collection_converted, "uuid backfill done");
+        converted += collection_converted;
+    }
+
+    Ok(converted)
+}
@@ -0,0 +1,366 @@
+//! Encryption of channel exports, and the signed links delivering them.
+//!
+//! An encrypted export is a header followed by the NDJSON export cut into
+//! chunks of 64 KiB, each sealed with AES-256-GCM. Chunk nonces carry the
+//! chunk number and flag the last chunk, and every chunk authenticates the
+//! header, so chunks cannot be reordered, dropped or the export truncated
+//! without decryption failing. The content key is either derived from a
+//! passphrase (PBKDF2-HMAC-SHA256) or random and encrypted for the recipient's
+//! RSA public key (RSA-OAEP-SHA256).
+//!
+//! Header layout, integers big-endian:
+//!
+//! ```text
+//! "BEEPEXP" | version (1) | mode (1) | key material | nonce prefix (7)
+//!   mode 1, passphrase: iterations (u32) | salt (16)
+//!   mode 2, public key: encrypted key length (u16) | encrypted key
+//! ```
+
+use aes_gcm::{
+    Aes256Gcm, Key, Nonce,
+    aead::{Aead, KeyInit, OsRng, Payload, rand_core::RngCore},
+};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{DateTime, Utc};
+use rsa::{
+    Oaep, RsaPrivateKey, RsaPublicKey,
+    pkcs8::{DecodePrivateKey, DecodePublicKey},
+    traits::PublicKeyParts,
+};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::domain::{common::CoreError, message::entities::ChannelId};
+
+const MAGIC: &[u8; 7] = b"BEEPEXP";
+const VERSION: u8 = 1;
+const MODE_PASSPHRASE: u8 = 1;
+const MODE_PUBLIC_KEY: u8 = 2;
+
+/// Plaintext bytes per chunk, each followed by its 16-byte tag
+pub const EXPORT_CHUNK_LEN: usize = 64 * 1024;
+const TAG_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const NONCE_PREFIX_LEN: usize = 7;
+const KEY_LEN: usize = 32;
+const SALT_LEN: usize = 16;
+
+/// PBKDF2 rounds for new passphrase exports, after the OWASP recommendation
+pub const PBKDF2_ITERATIONS: u32 = 600_000;
+/// Rounds below which an export header is refused, as tampered with
+const MIN_PBKDF2_ITERATIONS: u32 = 100_000;
+pub const MIN_PASSPHRASE_CHARS: usize = 12;
+const MIN_RSA_BITS: usize = 2048;
+
+/// Who an export is encrypted for, with the key material needed to encrypt it
+///
+/// Holds the derived key of passphrase recipients: only ever store it encrypted,
+/// as in [`ExportLinkSealer`] tokens.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ExportRecipient {
+    /// Key derived from the recipient's passphrase, with the parameters
+    /// written in the header for them to derive it again
+    Passphrase {
+        iterations: u32,
+        salt: Vec<u8>,
+        key: Vec<u8>,
+    },
+    /// PEM-encoded RSA public key (SubjectPublicKeyInfo) of the recipient
+    PublicKey { pem: String },
+}
+
+impl ExportRecipient {
+    /// Derive the key of `passphrase` with a fresh salt; slow on purpose, so
+    /// keep it off the async runtime
+    pub fn from_passphrase(passphrase: &str) -> Result<Self, CoreError> {
+        if passphrase.chars().count() < MIN_PASSPHRASE_CHARS {
+            return Err(CoreError::InvalidExportRecipient {
+                msg: format!("passphrases are at least {MIN_PASSPHRASE_CHARS} characters long"),
+            });
+        }
+        let mut salt = vec![0; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        let key = derive_key(passphrase, &salt, PBKDF2_ITERATIONS);
+
+        Ok(Self::Passphrase {
+            iterations: PBKDF2_ITERATIONS,
+            salt,
+            key,
+        })
+    }
+
+    pub fn from_public_key(pem: &str) -> Result<Self, CoreError> {
+        parse_public_key(pem)?;
+        Ok(Self::PublicKey {
+            pem: pem.to_string(),
+        })
+    }
+}
+
+fn derive_key(passphrase: &str, salt: &[u8], iterations: u32) -> Vec<u8> {
+    let mut key = vec![0; KEY_LEN];
+    pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, iterations, &mut key);
+    key
+}
+
+fn parse_public_key(pem: &str) -> Result<RsaPublicKey, CoreError> {
+    let key = RsaPublicKey::from_public_key_pem(pem.trim()).map_err(|_| {
+        CoreError::InvalidExportRecipient {
+            msg: "public keys must be PEM-encoded RSA keys (BEGIN PUBLIC KEY)".to_string(),
+        }
+    })?;
+    if key.size() * 8 < MIN_RSA_BITS {
+        return Err(CoreError::InvalidExportRecipient {
+            msg: format!("RSA public keys are at least {MIN_RSA_BITS} bits long"),
+        });
+    }
+    Ok(key)
+}
+
+fn encryption_error(msg: &str) -> CoreError {
+    CoreError::EncryptionError {
+        msg: msg.to_string(),
+    }
+}
+
+fn content_cipher(key: &[u8]) -> Result<Aes256Gcm, CoreError> {
+    Aes256Gcm::new_from_slice(key).map_err(|_| encryption_error("invalid export key"))
+}
+
+fn chunk_nonce(prefix: &[u8], counter: u32, last: bool) -> [u8; NONCE_LEN] {
+    let mut nonce = [0; NONCE_LEN];
+    nonce[..NONCE_PREFIX_LEN].copy_from_slice(prefix);
+    nonce[NONCE_PREFIX_LEN..NONCE_LEN - 1].copy_from_slice(&counter.to_be_bytes());
+    nonce[NONCE_LEN - 1] = last as u8;
+    nonce
+}
+
+/// Encrypts an export as it streams, for [`decrypt_export`] to read back
+pub struct ExportEncryptor {
+    cipher: Aes256Gcm,
+    header: Vec<u8>,
+    header_sent: bool,
+    nonce_prefix: [u8; NONCE_PREFIX_LEN],
+    counter: u32,
+    pending: Vec<u8>,
+}
+
+impl ExportEncryptor {
+    pub fn new(recipient: &ExportRecipient) -> Result<Self, CoreError> {
+        let mut header = MAGIC.to_vec();
+        header.push(VERSION);
+        let key = match recipient {
+            ExportRecipient::Passphrase {
+                iterations,
+                salt,
+                key,
+            } => {
+                header.push(MODE_PASSPHRASE);
+                header.extend(iterations.to_be_bytes());
+                header.extend(salt);
+                key.clone()
+            }
+            ExportRecipient::PublicKey { pem } => {
+                let public_key = parse_public_key(pem)?;
+                let mut key = vec![0; KEY_LEN];
+                OsRng.fill_bytes(&mut key);
+                let encrypted = public_key
+                    .encrypt(&mut OsRng, Oaep::new::<Sha256>(), &key)
+                    .map_err(|_| encryption_error("export key encryption failed"))?;
+                header.push(MODE_PUBLIC_KEY);
+                header.extend((encrypted.len() as u16).to_be_bytes());
+                header.extend(encrypted);
+                key
+            }
+        };
+        let mut nonce_prefix = [0; NONCE_PREFIX_LEN];
+        OsRng.fill_bytes(&mut nonce_prefix);
+        header.extend(nonce_prefix);
+
+        Ok(Self {
+            cipher: content_cipher(&key)?,
+            header,
+            header_sent: false,
+            nonce_prefix,
+            counter: 0,
+            pending: Vec::new(),
+        })
+    }
+
+    /// Encrypt `data`, returning what can be sent so far: the header on the
+    /// first call, then every chunk filled up
+    pub fn update(&mut self, data: &[u8]) -> Result<Vec<u8>, CoreError> {
+        let mut out = self.take_header();
+        self.pending.extend_from_slice(data);
+        // full chunks are only sealed once more data follows, the last one being flagged
+        while self.pending.len() > EXPORT_CHUNK_LEN {
+            let rest = self.pending.split_off(EXPORT_CHUNK_LEN);
+            let chunk = std::mem::replace(&mut self.pending, rest);
+            out.extend(self.seal(&chunk, false)?);
+        }
+        Ok(out)
+    }
+
+    /// Seal the last chunk, empty for an empty export
+    pub fn finish(mut self) -> Result<Vec<u8>, CoreError> {
+        let mut out = self.take_header();
+        let chunk = std::mem::take(&mut self.pending);
+        out.extend(self.seal(&chunk, true)?);
+        Ok(out)
+    }
+
+    fn take_header(&mut self) -> Vec<u8> {
+        if self.header_sent {
+            return Vec::new();
+        }
+        self.header_sent = true;
+        self.header.clone()
+    }
+
+    fn seal(&mut self, chunk: &[u8], last: bool) -> Result<Vec<u8>, CoreError> {
+        let nonce = chunk_nonce(&self.nonce_prefix, self.counter, last);
+        self.counter = self
+            .counter
+            .checked_add(1)
+            .ok_or_else(|| encryption_error("export too large to encrypt"))?;
+        self.cipher
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: chunk,
+                    aad: &self.header,
+                },
+            )
+            .map_err(|_| encryption_error("export encryption failed"))
+    }
+}
+
+/// Secret of the recipient of an encrypted export
+pub enum ExportSecret<'a> {
+    Passphrase(&'a str),
+    /// PEM-encoded RSA private key (PKCS#8)
+    PrivateKey(&'a str),
+}
+
+fn take<'a>(bytes: &mut &'a [u8], len: usize) -> Result<&'a [u8], CoreError> {
+    if bytes.len() < len {
+        return Err(encryption_error("truncated export"));
+    }
+    let (taken, rest) = bytes.split_at(len);
+    *bytes = rest;
+    Ok(taken)
+}
+
+/// Decrypt a whole export written by [`ExportEncryptor`], failing on any
+/// wrong secret, tampering or truncation
+pub fn decrypt_export(artifact: &[u8], secret: ExportSecret<'_>) -> Result<Vec<u8>, CoreError> {
+    let mut rest = artifact;
+    if take(&mut rest, MAGIC.len())? != MAGIC || take(&mut rest, 1)? != [VERSION] {
+        return Err(encryption_error("not an encrypted export"));
+    }
+    let key = match (take(&mut rest, 1)?[0], secret) {
+        (MODE_PASSPHRASE, ExportSecret::Passphrase(passphrase)) => {
+            let iterations = u32::from_be_bytes(take(&mut rest, 4)?.try_into().unwrap());
+            if iterations < MIN_PBKDF2_ITERATIONS {
+                return Err(encryption_error("too few key derivation rounds"));
+            }
+            derive_key(passphrase, take(&mut rest, SALT_LEN)?, iterations)
+        }
+        (MODE_PUBLIC_KEY, ExportSecret::PrivateKey(pem)) => {
+            let private_key = RsaPrivateKey::from_pkcs8_pem(pem.trim())
+                .map_err(|_| encryption_error("invalid RSA private key"))?;
+            let len = u16::from_be_bytes(take(&mut rest, 2)?.try_into().unwrap());
+            private_key
+                .decrypt(Oaep::new::<Sha256>(), take(&mut rest, len as usize)?)
+                .map_err(|_| encryption_error("export key decryption failed"))?
+        }
+        _ => {
+            return Err(encryption_error(
+                "export encrypted for another kind of secret",
+            ));
+        }
+    };
+    let nonce_prefix = take(&mut rest, NONCE_PREFIX_LEN)?;
+    let header = &artifact[..artifact.len() - rest.len()];
+    let cipher = content_cipher(&key)?;
+
+    let mut plaintext = Vec::new();
+    let mut counter = 0u32;
+    loop {
+        let len = rest.len().min(EXPORT_CHUNK_LEN + TAG_LEN);
+        let last = len == rest.len();
+        let chunk = take(&mut rest, len)?;
+        let nonce = chunk_nonce(nonce_prefix, counter, last);
+        let opened = cipher
+            .decrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: chunk,
+                    aad: header,
+                },
+            )
+            .map_err(|_| encryption_error("export decryption failed"))?;
+        plaintext.extend(opened);
+        if last {
+            return Ok(plaintext);
+        }
+        counter += 1;
+    }
+}
+
+/// Export a signed link gives access to, until it expires
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ExportLink {
+    pub channel_id: ChannelId,
+    pub include_deleted: bool,
+    pub recipient: ExportRecipient,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Turns export links into opaque URL-safe tokens, encrypted so the key
+/// material they carry stays secret and authenticated so they can be neither
+/// forged nor altered
+#[derive(Clone)]
+pub struct ExportLinkSealer {
+    cipher: Aes256Gcm,
+}
+
+impl ExportLinkSealer {
+    pub fn new(key: &[u8; 32]) -> Self {
+        Self {
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key)),
+        }
+    }
+
+    pub fn seal(&self, link: &ExportLink) -> Result<String, CoreError> {
+        let plaintext = serde_json::to_vec(link)
+            .map_err(|e| CoreError::SerializationError { msg: e.to_string() })?;
+        let mut nonce = [0; NONCE_LEN];
+        OsRng.fill_bytes(&mut nonce);
+        let ciphertext = self
+            .cipher
+            .encrypt(Nonce::from_slice(&nonce), plaintext.as_slice())
+            .map_err(|_| encryption_error("export link encryption failed"))?;
+
+        let mut token = nonce.to_vec();
+        token.extend(ciphertext);
+        Ok(URL_SAFE_NO_PAD.encode(token))
+    }
+
+    /// The link of `token`, or `None` when this sealer did not issue it, it
+    /// was altered or it expired
+    pub fn open(&self, token: &str, now: DateTime<Utc>) -> Option<ExportLink> {
+        let token = URL_SAFE_NO_PAD.decode(token).ok()?;
+        if token.len() < NONCE_LEN {
+            return None;
+        }
+        let (nonce, ciphertext) = token.split_at(NONCE_LEN);
+        let plaintext = self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .ok()?;
+        let link: ExportLink = serde_json::from_slice(&plaintext).ok()?;
+        (link.expires_at > now).then_some(link)
+    }
+}
@@ -1 +1,8 @@
+pub mod change_stream;
+pub mod dto;
+pub mod encoding;
+pub mod export;
+pub mod purge;
 pub mod repositories;
+pub mod retention;
+pub mod thread_archive;
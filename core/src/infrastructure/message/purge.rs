@@ -0,0 +1,165 @@
+//! Permanent removal of soft deleted messages once their retention elapsed,
+//! and of ephemeral messages once they expired.
+//!
+//! Deleted messages stay readable by moderators for the retention period, then
+//! the purge removes them with their reactions and gives their attachment
+//! storage back to their channel. Expired messages are removed the same way,
+//! announced as deleted unless they already were. Messages under a legal hold
+//! are kept.
+
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use chrono::Utc;
+use tokio::{task::JoinHandle, time::MissedTickBehavior};
+
+use crate::{
+    domain::{common::CoreError, lease::ports::LeaseLock, message::ports::MessageService},
+    infrastructure::lease::SingletonJob,
+};
+
+/// Name of the lease held by the replica purging deleted messages
+pub const DELETED_MESSAGE_PURGE_LEASE: &str = "deleted-message-purge";
+
+/// Number of messages purged by this process since startup
+static PURGED_MESSAGES: AtomicU64 = AtomicU64::new(0);
+
+/// Total number of soft deleted and expired messages purged since startup
+pub fn purged_messages() -> u64 {
+    PURGED_MESSAGES.load(Ordering::Relaxed)
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct DeletedMessagePurgeConfig {
+    /// How long soft deleted messages are kept before being purged, `None`
+    /// keeping them forever; expired messages are purged either way
+    pub retention: Option<Duration>,
+    /// Wait between two purge runs
+    pub interval: Duration,
+    /// Messages removed per repository round trip
+    pub batch_size: usize,
+}
+
+impl Default for DeletedMessagePurgeConfig {
+    fn default() -> Self {
+        Self {
+            retention: Some(Duration::from_secs(30 * 24 * 60 * 60)),
+            interval: Duration::from_secs(60 * 60),
+            batch_size: 100,
+        }
+    }
+}
+
+/// Periodically purges the messages soft deleted more than `retention` ago,
+/// and the expired ones
+pub struct DeletedMessagePurge<S>
+where
+    S: MessageService,
+{
+    service: S,
+    config: DeletedMessagePurgeConfig,
+    leader: Option<SingletonJob>,
+}
+
+impl<S> DeletedMessagePurge<S>
+where
+    S: MessageService + 'static,
+{
+    pub fn new(service: S, config: DeletedMessagePurgeConfig) -> Self {
+        Self {
+            service,
+            config,
+            leader: None,
+        }
+    }
+
+    /// Purge from the replica holding the [`DELETED_MESSAGE_PURGE_LEASE`] only.
+    ///
+    /// The lease outlives the interval between two runs, so the holder keeps
+    /// it across runs and a purge never runs on two replicas at once.
+    pub fn with_leader(mut self, lock: Arc<dyn LeaseLock>) -> Self {
+        self.leader = Some(SingletonJob::new(
+            lock,
+            DELETED_MESSAGE_PURGE_LEASE,
+            self.config.interval.saturating_mul(2),
+        ));
+        self
+    }
+
+    /// Purge every message past its retention or expiry, batch after batch,
+    /// and return how many were removed
+    pub async fn purge_once(&self) -> Result<u64, CoreError> {
+        let now = Utc::now();
+        // a retention beyond the representable dates purges nothing
+        let deleted_before = self.config.retention.and_then(|retention| {
+            chrono::Duration::from_std(retention)
+                .ok()
+                .and_then(|retention| now.checked_sub_signed(retention))
+        });
+        let batch_size = self.config.batch_size.max(1);
+
+        let mut purged = 0;
+        if let Some(deleted_before) = deleted_before {
+            loop {
+                let removed = self
+                    .service
+                    .purge_deleted_messages(deleted_before, batch_size)
+                    .await?;
+                purged += removed;
+                PURGED_MESSAGES.fetch_add(removed, Ordering::Relaxed);
+                if removed < batch_size as u64 {
+                    break;
+                }
+            }
+        }
+        loop {
+            let removed = self.service.purge_expired_messages(now, batch_size).await?;
+            purged += removed;
+            PURGED_MESSAGES.fetch_add(removed, Ordering::Relaxed);
+            if removed < batch_size as u64 {
+                break;
+            }
+        }
+
+        if purged > 0 {
+            tracing::info!(
+                purged,
+                total = purged_messages(),
+                "purged soft deleted and expired messages"
+            );
+        }
+        Ok(purged)
+    }
+
+    /// Purge every `interval` in the background
+    pub fn spawn(mut self) -> JoinHandle<()> {
+        let interval = self.config.interval;
+        let leader = self.leader.take();
+        let purge = Arc::new(self);
+        let run = move || {
+            let purge = purge.clone();
+            async move {
+                if let Err(e) = purge.purge_once().await {
+                    tracing::warn!(error = %e, "failed to purge deleted and expired messages");
+                }
+            }
+        };
+
+        match leader {
+            Some(leader) => leader.spawn_every(interval, run),
+            None => tokio::spawn(async move {
+                let mut ticker = tokio::time::interval(interval);
+                ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+                loop {
+                    ticker.tick().await;
+                    run().await;
+                }
+            }),
+        }
+    }
+}
@@ -0,0 +1,138 @@
+//! Presigned attachment uploads in MongoDB
+
+use futures::TryStreamExt;
+use mongodb::bson::{Bson, doc};
+
+use super::MongoMessageRepository;
+use crate::{
+    domain::{
+        common::CoreError,
+        message::{
+            entities::{AttachmentId, AttachmentUpload, MessageId},
+            ports::AttachmentRepository,
+        },
+    },
+    infrastructure::message::{
+        dto::uuid_to_binary,
+        encoding::{uuid_match, uuids_in},
+    },
+};
+
+#[async_trait::async_trait]
+impl AttachmentRepository for MongoMessageRepository {
+    async fn insert_attachment_upload(&self, upload: &AttachmentUpload) -> Result<(), CoreError> {
+        self.attachment_uploads
+            .insert_one(doc! {
+                "_id": uuid_to_binary(upload.id.0),
+                "channel_id": uuid_to_binary(upload.channel_id.0),
+                "uploader_id": uuid_to_binary(upload.uploader_id.0),
+                "name": upload.name.as_str(),
+                "content_type": upload.content_type.as_str(),
+                "size": upload.size as i64,
+                "object_key": upload.object_key.as_str(),
+                "created_at": upload.created_at.to_rfc3339(),
+                "expires_at": upload.expires_at.to_rfc3339(),
+                "message_id": Bson::Null,
+            })
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+        Ok(())
+    }
+
+    async fn find_attachment_uploads(
+        &self,
+        ids: &[AttachmentId],
+    ) -> Result<Vec<AttachmentUpload>, CoreError> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        // `_id`s keep the legacy encoding
+        let ids: Vec<Bson> = ids
+            .iter()
+            .map(|id| Bson::Binary(uuid_to_binary(id.0)))
+            .collect();
+        let mut cursor = self
+            .attachment_uploads
+            .find(doc! { "_id": { "$in": ids } })
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        let mut uploads = Vec::new();
+        while let Some(document) = cursor
+            .try_next()
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?
+        {
+            uploads.push(Self::attachment_upload_from(&document)?);
+        }
+        Ok(uploads)
+    }
+
+    async fn attach_uploads(
+        &self,
+        ids: &[AttachmentId],
+        message_id: &MessageId,
+    ) -> Result<(), CoreError> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        let binary_ids: Vec<Bson> = ids
+            .iter()
+            .map(|id| Bson::Binary(uuid_to_binary(id.0)))
+            .collect();
+        // claiming again for the same message keeps it idempotent
+        let mut claimable = uuids_in([message_id.0]);
+        claimable.push(Bson::Null);
+        let result = self
+            .attachment_uploads
+            .update_many(
+                doc! {
+                    "_id": { "$in": binary_ids.clone() },
+                    "message_id": { "$in": claimable.clone() },
+                },
+                doc! { "$set": { "message_id": uuid_to_binary(message_id.0) } },
+            )
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+        if result.matched_count == ids.len() as u64 {
+            return Ok(());
+        }
+
+        // another message took one of them since they were looked up
+        let taken = self
+            .attachment_uploads
+            .find_one(doc! {
+                "_id": { "$in": binary_ids },
+                "message_id": { "$nin": claimable },
+            })
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+        self.release_uploads(ids, message_id).await?;
+        let id = taken
+            .and_then(|upload| Self::attachment_upload_from(&upload).ok())
+            .map_or(ids[0], |upload| upload.id);
+        Err(CoreError::AttachmentNotFound { id })
+    }
+
+    async fn release_uploads(
+        &self,
+        ids: &[AttachmentId],
+        message_id: &MessageId,
+    ) -> Result<(), CoreError> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        let ids: Vec<Bson> = ids
+            .iter()
+            .map(|id| Bson::Binary(uuid_to_binary(id.0)))
+            .collect();
+        self.attachment_uploads
+            .update_many(
+                doc! { "_id": { "$in": ids }, "message_id": uuid_match(message_id.0) },
+                doc! { "$set": { "message_id": Bson::Null } },
+            )
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+        Ok(())
+    }
+}
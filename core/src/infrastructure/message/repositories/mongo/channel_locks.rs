@@ -0,0 +1,51 @@
+//! Channel write locks in MongoDB
+
+use mongodb::bson::doc;
+
+use super::MongoMessageRepository;
+use crate::{
+    domain::{
+        common::CoreError,
+        message::{
+            entities::{ChannelId, ChannelWriteLock},
+            ports::ChannelLockRepository,
+        },
+    },
+    infrastructure::message::dto::{ChannelWriteLockDocument, uuid_to_binary},
+};
+
+#[async_trait::async_trait]
+impl ChannelLockRepository for MongoMessageRepository {
+    async fn lock_channel_writes(&self, lock: &ChannelWriteLock) -> Result<(), CoreError> {
+        self.channel_write_locks
+            .replace_one(
+                doc! { "_id": uuid_to_binary(lock.channel_id.0) },
+                ChannelWriteLockDocument::from(lock),
+            )
+            .upsert(true)
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+        Ok(())
+    }
+
+    async fn find_channel_write_lock(
+        &self,
+        channel_id: &ChannelId,
+    ) -> Result<Option<ChannelWriteLock>, CoreError> {
+        let document = self
+            .channel_write_locks
+            .find_one(doc! { "_id": uuid_to_binary(channel_id.0) })
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        document.map(ChannelWriteLock::try_from).transpose()
+    }
+
+    async fn unlock_channel_writes(&self, channel_id: &ChannelId) -> Result<(), CoreError> {
+        self.channel_write_locks
+            .delete_one(doc! { "_id": uuid_to_binary(channel_id.0) })
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+        Ok(())
+    }
+}
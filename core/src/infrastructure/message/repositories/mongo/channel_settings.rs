@@ -0,0 +1,95 @@
+//! Channel settings in MongoDB
+
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{Bson, doc},
+    options::ReturnDocument,
+};
+
+use super::MongoMessageRepository;
+use crate::{
+    domain::{
+        common::CoreError,
+        message::{
+            entities::{ChannelId, ChannelSettings},
+            ports::ChannelSettingsRepository,
+        },
+    },
+    infrastructure::message::dto::{binary_to_uuid, uuid_to_binary},
+};
+
+#[async_trait::async_trait]
+impl ChannelSettingsRepository for MongoMessageRepository {
+    async fn channel_settings(&self, channel_id: &ChannelId) -> Result<ChannelSettings, CoreError> {
+        let document = self
+            .channel_settings
+            .find_one(doc! { "_id": uuid_to_binary(channel_id.0) })
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        Ok(Self::channel_settings_from(*channel_id, document.as_ref()))
+    }
+
+    async fn set_allowed_reactions(
+        &self,
+        channel_id: &ChannelId,
+        allowed: Option<Vec<String>>,
+    ) -> Result<ChannelSettings, CoreError> {
+        let update = match allowed {
+            Some(allowed) => doc! { "$set": { "allowed_reactions": allowed } },
+            None => doc! { "$unset": { "allowed_reactions": "" } },
+        };
+        let document = self
+            .channel_settings
+            .find_one_and_update(doc! { "_id": uuid_to_binary(channel_id.0) }, update)
+            .upsert(true)
+            .return_document(ReturnDocument::After)
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        Ok(Self::channel_settings_from(*channel_id, document.as_ref()))
+    }
+
+    async fn set_channel_retention(
+        &self,
+        channel_id: &ChannelId,
+        retention_secs: Option<u64>,
+    ) -> Result<ChannelSettings, CoreError> {
+        let update = match retention_secs {
+            Some(secs) => doc! { "$set": { "retention_secs": secs.min(i64::MAX as u64) as i64 } },
+            None => doc! { "$unset": { "retention_secs": "" } },
+        };
+        let document = self
+            .channel_settings
+            .find_one_and_update(doc! { "_id": uuid_to_binary(channel_id.0) }, update)
+            .upsert(true)
+            .return_document(ReturnDocument::After)
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        Ok(Self::channel_settings_from(*channel_id, document.as_ref()))
+    }
+
+    async fn list_channel_retentions(&self) -> Result<Vec<ChannelSettings>, CoreError> {
+        let mut cursor = self
+            .channel_settings
+            .find(doc! { "retention_secs": { "$exists": true } })
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        let mut settings = Vec::new();
+        while let Some(document) = cursor
+            .try_next()
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?
+        {
+            let channel_id = match document.get("_id") {
+                Some(Bson::Binary(binary)) => ChannelId(binary_to_uuid(binary)?),
+                _ => continue,
+            };
+            settings.push(Self::channel_settings_from(channel_id, Some(&document)));
+        }
+
+        Ok(settings)
+    }
+}
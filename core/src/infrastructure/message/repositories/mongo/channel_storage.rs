@@ -0,0 +1,82 @@
+//! Attachment storage of channels in MongoDB
+
+use mongodb::{bson::doc, options::ReturnDocument};
+
+use super::MongoMessageRepository;
+use crate::{
+    domain::{
+        common::CoreError,
+        message::{
+            entities::{ChannelId, ChannelStorage},
+            ports::ChannelStorageRepository,
+        },
+    },
+    infrastructure::message::dto::uuid_to_binary,
+};
+
+#[async_trait::async_trait]
+impl ChannelStorageRepository for MongoMessageRepository {
+    async fn channel_storage(&self, channel_id: &ChannelId) -> Result<ChannelStorage, CoreError> {
+        let document = self
+            .channel_storage
+            .find_one(doc! { "_id": uuid_to_binary(channel_id.0) })
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        Ok(Self::channel_storage_from(*channel_id, document.as_ref()))
+    }
+
+    async fn add_channel_storage_usage(
+        &self,
+        channel_id: &ChannelId,
+        bytes: u64,
+    ) -> Result<(), CoreError> {
+        self.channel_storage
+            .update_one(
+                doc! { "_id": uuid_to_binary(channel_id.0) },
+                doc! { "$inc": { "used_bytes": bytes as i64 } },
+            )
+            .upsert(true)
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        Ok(())
+    }
+
+    async fn release_channel_storage_usage(
+        &self,
+        channel_id: &ChannelId,
+        bytes: u64,
+    ) -> Result<(), CoreError> {
+        let used_bytes = doc! { "$subtract": ["$used_bytes", bytes as i64] };
+        self.channel_storage
+            .update_one(
+                doc! { "_id": uuid_to_binary(channel_id.0) },
+                vec![doc! { "$set": { "used_bytes": { "$max": [0_i64, used_bytes] } } }],
+            )
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        Ok(())
+    }
+
+    async fn set_channel_storage_quota(
+        &self,
+        channel_id: &ChannelId,
+        quota_bytes: Option<u64>,
+    ) -> Result<ChannelStorage, CoreError> {
+        let update = match quota_bytes {
+            Some(quota) => doc! { "$set": { "quota_bytes": quota as i64 } },
+            None => doc! { "$unset": { "quota_bytes": "" } },
+        };
+        let document = self
+            .channel_storage
+            .find_one_and_update(doc! { "_id": uuid_to_binary(channel_id.0) }, update)
+            .upsert(true)
+            .return_document(ReturnDocument::After)
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        Ok(Self::channel_storage_from(*channel_id, document.as_ref()))
+    }
+}
@@ -0,0 +1,94 @@
+//! Legal holds in MongoDB
+
+use chrono::Utc;
+use futures::TryStreamExt;
+use mongodb::bson::doc;
+
+use super::MongoMessageRepository;
+use crate::{
+    domain::{
+        common::CoreError,
+        message::{
+            entities::{LegalHold, LegalHoldId, LegalHoldScope, PlaceLegalHoldInput},
+            ports::LegalHoldRepository,
+        },
+    },
+    infrastructure::message::{
+        dto::{LegalHoldDocument, uuid_to_binary},
+        encoding::uuid_match,
+    },
+};
+
+#[async_trait::async_trait]
+impl LegalHoldRepository for MongoMessageRepository {
+    async fn place_legal_hold(&self, input: PlaceLegalHoldInput) -> Result<LegalHold, CoreError> {
+        let hold = LegalHold {
+            id: LegalHoldId::from(uuid::Uuid::new_v4()),
+            scope: input.scope,
+            reference: input.reference,
+            placed_by: input.placed_by,
+            placed_at: Utc::now(),
+        };
+
+        self.legal_holds
+            .insert_one(LegalHoldDocument::from(&hold))
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        Ok(hold)
+    }
+
+    async fn find_legal_hold(&self, id: &LegalHoldId) -> Result<Option<LegalHold>, CoreError> {
+        let document = self
+            .legal_holds
+            .find_one(doc! { "_id": uuid_to_binary(id.0) })
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        document.map(LegalHold::try_from).transpose()
+    }
+
+    async fn list_legal_holds(
+        &self,
+        scope: Option<&LegalHoldScope>,
+    ) -> Result<Vec<LegalHold>, CoreError> {
+        let filter = match scope {
+            Some(LegalHoldScope::Channel(channel_id)) => {
+                doc! { "channel_id": uuid_match(channel_id.0) }
+            }
+            Some(LegalHoldScope::User(user_id)) => doc! { "user_id": uuid_match(user_id.0) },
+            None => doc! {},
+        };
+
+        let mut cursor = self
+            .legal_holds
+            .find(filter)
+            .sort(doc! { "placed_at": 1 })
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        let mut holds = Vec::new();
+        while let Some(document) = cursor
+            .try_next()
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?
+        {
+            holds.push(LegalHold::try_from(document)?);
+        }
+
+        Ok(holds)
+    }
+
+    async fn release_legal_hold(&self, id: &LegalHoldId) -> Result<(), CoreError> {
+        let result = self
+            .legal_holds
+            .delete_one(doc! { "_id": uuid_to_binary(id.0) })
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        if result.deleted_count == 0 {
+            return Err(CoreError::LegalHoldNotFound { id: *id });
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,2178 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use chrono::{DateTime, Utc};
+use futures::{
+    TryStreamExt, future,
+    stream::{self, StreamExt},
+};
+use mongodb::{
+    Collection, Database,
+    bson::{Bson, Document, doc, from_document, to_bson},
+    error::{
+        ErrorKind, InsertManyError, TRANSIENT_TRANSACTION_ERROR, UNKNOWN_TRANSACTION_COMMIT_RESULT,
+        WriteFailure,
+    },
+    IndexModel,
+    options::{FindOneAndUpdateOptions, FindOptions, IndexOptions, ReturnDocument},
+};
+
+use crate::{
+    domain::{
+        common::{
+            CoreError, Cursor, CursorPage, CursorRange, GetCursorPaginated, GetPaginated, TotalPaginatedElements,
+        },
+        message::{
+            entities::{
+                AttachmentId, AttachmentUpload, AuthorId, ChannelDigest, ChannelId,
+                CreateMessageEvent, DeleteMessageEvent, ChannelSettings, ChannelStorage, Embed,
+                ImportMessageInput, InsertMessageInput, Mentions, Message, MessageId,
+                MessageRevision, MessagesBulkDeletedEvent, ModerationReason, ReactionCount,
+                ReadMarker, SavedMessage, ThreadArchivedEvent, ThreadFollow, ThreadUnarchivedEvent,
+                UpdateMessageEvent, UpdateMessageInput, UserErasedEvent, UserId,
+            },
+            events::MessageEventBus,
+            ports::{MessageRepository, MessageStream},
+        },
+    },
+    infrastructure::{
+        message::change_stream::MessageChangeStreamWatcher,
+        message::dto::{
+            ChannelWriteLockDocument, LegalHoldDocument, MessageDocument,
+            MessageRevisionDocument, ReactionDocument, binary_to_uuid, uuid_to_binary,
+        },
+        message::encoding::{uuid_match, uuids_in},
+        outbox::{
+            MessageRoutingInfos, OutboxEncryption, OutboxEventRecord,
+            write_outbox_event_with_policy,
+        },
+    },
+};
+
+mod attachments;
+mod channel_locks;
+mod channel_settings;
+mod channel_storage;
+mod legal_holds;
+mod publishers;
+mod reactions;
+mod read_markers;
+mod saved_messages;
+mod thread_follows;
+
+#[derive(Clone)]
+pub struct MongoMessageRepository {
+    collection: Collection<MessageDocument>,
+    reactions: Collection<ReactionDocument>,
+    /// One document per mute; a null `message_id` mutes every message of the user
+    reaction_mutes: Collection<Document>,
+    /// One document per channel: `used_bytes`, and `quota_bytes` when customized
+    channel_storage: Collection<Document>,
+    /// One document per channel changing the defaults: `allowed_reactions` when
+    /// restricted, `retention_secs` when customized
+    channel_settings: Collection<Document>,
+    legal_holds: Collection<LegalHoldDocument>,
+    /// One document per followed thread of a user
+    thread_follows: Collection<Document>,
+    /// One document per message a user saved, with its `channel_id`
+    saved_messages: Collection<Document>,
+    /// One document per user changing the defaults: `auto_follow_on_reply`
+    thread_preferences: Collection<Document>,
+    /// One document per user and channel they read: the last read
+    /// `message_id`, its `sequence` and `read_at`
+    read_markers: Collection<Document>,
+    /// At most one document per channel, removed when the lock is released
+    channel_write_locks: Collection<ChannelWriteLockDocument>,
+    /// Previous contents of edited messages
+    revisions: Collection<MessageRevisionDocument>,
+    /// One document per channel: `last_sequence`, the latest message sequence handed out
+    channel_sequences: Collection<Document>,
+    /// One document per presigned attachment, with the `message_id` it was posted with
+    attachment_uploads: Collection<Document>,
+    db: Database,
+    routing: MessageRoutingInfos,
+    outbox_encryption: Option<OutboxEncryption>,
+    /// Cleared once the deployment turned out to be a standalone server,
+    /// which runs no transaction
+    transactions: Arc<AtomicBool>,
+}
+
+impl MongoMessageRepository {
+    pub fn new(db: &Database) -> Self {
+        Self {
+            collection: db.collection::<MessageDocument>("messages"),
+            reactions: db.collection::<ReactionDocument>("message_reactions"),
+            reaction_mutes: db.collection::<Document>("reaction_notification_mutes"),
+            channel_storage: db.collection::<Document>("channel_storage"),
+            channel_settings: db.collection::<Document>("channel_settings"),
+            legal_holds: db.collection::<LegalHoldDocument>("legal_holds"),
+            thread_follows: db.collection::<Document>("thread_follows"),
+            saved_messages: db.collection::<Document>("saved_messages"),
+            thread_preferences: db.collection::<Document>("thread_preferences"),
+            read_markers: db.collection::<Document>("read_markers"),
+            channel_write_locks: db
+                .collection::<ChannelWriteLockDocument>("channel_write_locks"),
+            revisions: db.collection::<MessageRevisionDocument>("message_revisions"),
+            channel_sequences: db.collection::<Document>("channel_sequences"),
+            attachment_uploads: db.collection::<Document>("attachment_uploads"),
+            db: db.clone(),
+            routing: MessageRoutingInfos::default(),
+            outbox_encryption: None,
+            transactions: Arc::new(AtomicBool::new(true)),
+        }
+    }
+
+    /// Set the routing used for the outbox events written by this repository
+    pub fn with_routing(mut self, routing: MessageRoutingInfos) -> Self {
+        self.routing = routing;
+        self
+    }
+
+    /// Encrypt the payloads of the outbox events written by this repository
+    pub fn with_outbox_encryption(mut self, encryption: OutboxEncryption) -> Self {
+        self.outbox_encryption = Some(encryption);
+        self
+    }
+
+    /// Watcher publishing every change of the `messages` collection on `bus`
+    pub fn change_stream_watcher(&self, bus: MessageEventBus) -> MessageChangeStreamWatcher {
+        MessageChangeStreamWatcher::new(&self.db, bus).with_reaction_counts(self.clone())
+    }
+
+    /// Create the indexes backing channel, thread, author, reaction and revision listings, reaction mutes, thread follows, saved messages, unread counts, nonce deduplication, message expiry, thread archiving and retention (idempotent)
+    pub async fn ensure_indexes(&self) -> Result<(), CoreError> {
+        self.collection
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! { "channel_id": 1, "created_at": -1, "_id": -1 })
+                    .build(),
+            )
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        self.collection
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! { "reply_to_message_id": 1, "created_at": 1, "_id": 1 })
+                    .build(),
+            )
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        self.collection
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! { "author_id": 1, "created_at": -1 })
+                    .build(),
+            )
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        // one message per nonce of an author in a channel
+        self.collection
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! { "channel_id": 1, "author_id": 1, "nonce": 1 })
+                    .options(
+                        IndexOptions::builder()
+                            .unique(true)
+                            .partial_filter_expression(doc! { "nonce": { "$type": "string" } })
+                            .build(),
+                    )
+                    .build(),
+            )
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        // Expired messages used to be removed by a TTL index, which skipped
+        // the legal holds and the cleanup of the purge
+        let mut indexes = self
+            .collection
+            .list_indexes()
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+        while let Some(index) = indexes
+            .try_next()
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?
+        {
+            let Some(options) = index.options else {
+                continue;
+            };
+            if let (Some(name), Some(_)) = (options.name, options.expire_after) {
+                self.collection
+                    .drop_index(name)
+                    .await
+                    .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+            }
+        }
+
+        // purge of expired ephemeral messages
+        self.collection
+            .create_index(IndexModel::builder().keys(doc! { "expires_at": 1 }).build())
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        // purge of soft deleted messages
+        self.collection
+            .create_index(IndexModel::builder().keys(doc! { "deleted_at": 1 }).build())
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        // deletion of the messages past the default retention
+        self.collection
+            .create_index(IndexModel::builder().keys(doc! { "created_at": 1 }).build())
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        // thread listings and archiving of idle threads, on messages with replies only
+        self.collection
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! { "channel_id": 1, "archived_at": 1, "last_reply_at": -1 })
+                    .options(
+                        IndexOptions::builder()
+                            .partial_filter_expression(doc! { "reply_count": { "$gt": 0_i64 } })
+                            .build(),
+                    )
+                    .build(),
+            )
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        self.reactions
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! { "message_id": 1, "user_id": 1, "emoji": 1 })
+                    .options(IndexOptions::builder().unique(true).build())
+                    .build(),
+            )
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        self.reaction_mutes
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! { "user_id": 1, "message_id": 1 })
+                    .options(IndexOptions::builder().unique(true).build())
+                    .build(),
+            )
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        self.thread_follows
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! { "user_id": 1, "message_id": 1 })
+                    .options(IndexOptions::builder().unique(true).build())
+                    .build(),
+            )
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        // followers of a thread, to notify of its replies
+        self.thread_follows
+            .create_index(IndexModel::builder().keys(doc! { "message_id": 1 }).build())
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        self.saved_messages
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! { "user_id": 1, "message_id": 1 })
+                    .options(IndexOptions::builder().unique(true).build())
+                    .build(),
+            )
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        // saved messages of a user, most recently saved first
+        self.saved_messages
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! { "user_id": 1, "created_at": -1 })
+                    .build(),
+            )
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        self.revisions
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! { "message_id": 1, "revision": 1 })
+                    .build(),
+            )
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        // unread counts, from the read markers of a user
+        self.collection
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! { "channel_id": 1, "sequence": 1 })
+                    .build(),
+            )
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+        self.read_markers
+            .create_index(IndexModel::builder().keys(doc! { "user_id": 1 }).build())
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        Ok(())
+    }
+
+    fn channel_storage_from(channel_id: ChannelId, document: Option<&Document>) -> ChannelStorage {
+        let Some(document) = document else {
+            return ChannelStorage::empty(channel_id);
+        };
+        let quota_bytes = document.get_i64("quota_bytes").ok().map(|quota| quota.max(0) as u64);
+
+        ChannelStorage {
+            channel_id,
+            used_bytes: document.get_i64("used_bytes").unwrap_or_default().max(0) as u64,
+            quota_bytes,
+            custom_quota: quota_bytes.is_some(),
+        }
+    }
+
+    fn channel_settings_from(channel_id: ChannelId, document: Option<&Document>) -> ChannelSettings {
+        let allowed_reactions = document
+            .and_then(|document| document.get_array("allowed_reactions").ok())
+            .map(|emojis| {
+                emojis
+                    .iter()
+                    .filter_map(|emoji| emoji.as_str().map(str::to_string))
+                    .collect()
+            });
+
+        let retention_secs = document
+            .and_then(|document| document.get_i64("retention_secs").ok())
+            .map(|secs| secs.max(0) as u64);
+
+        ChannelSettings {
+            allowed_reactions,
+            retention_secs,
+            ..ChannelSettings::defaults(channel_id)
+        }
+    }
+
+    fn thread_follow_from(document: &Document) -> Result<ThreadFollow, CoreError> {
+        // reference fields may already be in the standard UUID encoding
+        let uuid = |field: &str| match document.get(field) {
+            Some(Bson::Binary(binary)) => binary_to_uuid(binary),
+            _ => Err(CoreError::DatabaseError {
+                msg: format!("invalid `{field}` in thread follow"),
+            }),
+        };
+        let followed_at = document
+            .get_str("created_at")
+            .ok()
+            .and_then(|date| DateTime::parse_from_rfc3339(date).ok())
+            .map(|date| date.with_timezone(&Utc))
+            .ok_or_else(|| CoreError::DatabaseError {
+                msg: "invalid `created_at` in thread follow".to_string(),
+            })?;
+
+        Ok(ThreadFollow {
+            user_id: UserId(uuid("user_id")?),
+            thread_id: MessageId(uuid("message_id")?),
+            channel_id: ChannelId(uuid("channel_id")?),
+            followed_at,
+        })
+    }
+
+    fn saved_message_from(document: &Document) -> Result<SavedMessage, CoreError> {
+        // reference fields may already be in the standard UUID encoding
+        let uuid = |field: &str| match document.get(field) {
+            Some(Bson::Binary(binary)) => binary_to_uuid(binary),
+            _ => Err(CoreError::DatabaseError {
+                msg: format!("invalid `{field}` in saved message"),
+            }),
+        };
+        let saved_at = document
+            .get_str("created_at")
+            .ok()
+            .and_then(|date| DateTime::parse_from_rfc3339(date).ok())
+            .map(|date| date.with_timezone(&Utc))
+            .ok_or_else(|| CoreError::DatabaseError {
+                msg: "invalid `created_at` in saved message".to_string(),
+            })?;
+
+        Ok(SavedMessage {
+            user_id: UserId(uuid("user_id")?),
+            message_id: MessageId(uuid("message_id")?),
+            channel_id: ChannelId(uuid("channel_id")?),
+            saved_at,
+        })
+    }
+
+    /// `_id` of the read marker of a user in a channel
+    fn read_marker_id(user_id: &UserId, channel_id: &ChannelId) -> String {
+        format!("{user_id}:{channel_id}")
+    }
+
+    fn read_marker_from(document: &Document) -> Result<ReadMarker, CoreError> {
+        let invalid = |field: &str| CoreError::DatabaseError {
+            msg: format!("invalid `{field}` in read marker"),
+        };
+        let uuid = |field: &str| match document.get(field) {
+            Some(Bson::Binary(binary)) => binary_to_uuid(binary),
+            _ => Err(invalid(field)),
+        };
+        let read_at = document
+            .get_str("read_at")
+            .ok()
+            .and_then(|date| DateTime::parse_from_rfc3339(date).ok())
+            .map(|date| date.with_timezone(&Utc))
+            .ok_or_else(|| invalid("read_at"))?;
+        let sequence = document.get_i64("sequence").map_err(|_| invalid("sequence"))?;
+
+        Ok(ReadMarker {
+            user_id: UserId(uuid("user_id")?),
+            channel_id: ChannelId(uuid("channel_id")?),
+            last_read_message_id: MessageId(uuid("message_id")?),
+            last_read_sequence: sequence as u64,
+            read_at,
+        })
+    }
+
+    fn attachment_upload_from(document: &Document) -> Result<AttachmentUpload, CoreError> {
+        let invalid = |field: &str| CoreError::DatabaseError {
+            msg: format!("invalid `{field}` in attachment upload"),
+        };
+        // reference fields may already be in the standard UUID encoding
+        let uuid = |field: &str| match document.get(field) {
+            Some(Bson::Binary(binary)) => binary_to_uuid(binary),
+            _ => Err(invalid(field)),
+        };
+        let string = |field: &str| {
+            document
+                .get_str(field)
+                .map(str::to_string)
+                .map_err(|_| invalid(field))
+        };
+        let date = |field: &str| {
+            document
+                .get_str(field)
+                .ok()
+                .and_then(|date| DateTime::parse_from_rfc3339(date).ok())
+                .map(|date| date.with_timezone(&Utc))
+                .ok_or_else(|| invalid(field))
+        };
+        let message_id = match document.get("message_id") {
+            None | Some(Bson::Null) => None,
+            Some(_) => Some(MessageId(uuid("message_id")?)),
+        };
+
+        Ok(AttachmentUpload {
+            id: AttachmentId(uuid("_id")?),
+            channel_id: ChannelId(uuid("channel_id")?),
+            uploader_id: AuthorId(uuid("uploader_id")?),
+            name: string("name")?,
+            content_type: string("content_type")?,
+            size: document.get_i64("size").map_err(|_| invalid("size"))? as u64,
+            object_key: string("object_key")?,
+            created_at: date("created_at")?,
+            expires_at: date("expires_at")?,
+            message_id,
+        })
+    }
+
+    /// Messages matching `filter` oldest first, read from the storage as the
+    /// stream is polled
+    async fn stream_oldest_first(&self, filter: Document) -> Result<MessageStream, CoreError> {
+        let cursor = self
+            .collection
+            .find(filter)
+            .with_options(
+                FindOptions::builder()
+                    .sort(doc! { "created_at": 1, "_id": 1 })
+                    .batch_size(EXPORT_BATCH_SIZE as u32)
+                    .build(),
+            )
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        // reaction counts are attached one cursor batch at a time
+        let repository = self.clone();
+        let messages = cursor
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })
+            .and_then(|document| future::ready(Message::try_from(document)))
+            .try_chunks(EXPORT_BATCH_SIZE)
+            .map_err(|e| e.1)
+            .and_then(move |mut batch| {
+                let repository = repository.clone();
+                async move {
+                    repository.attach_reaction_counts(&mut batch).await?;
+                    Ok::<_, CoreError>(stream::iter(batch).map(Ok))
+                }
+            })
+            .try_flatten();
+
+        Ok(Box::pin(messages))
+    }
+
+    /// Restrict a filter to messages that were not soft deleted, nor expired:
+    /// expired messages stay stored until the next purge
+    fn not_deleted(mut filter: Document) -> Document {
+        filter.insert("deleted_at", Bson::Null);
+        filter.insert("expires_at", Self::not_expired());
+        filter
+    }
+
+    /// Condition on `expires_at` matching messages still readable now
+    fn not_expired() -> Document {
+        doc! { "$not": { "$lte": mongodb::bson::DateTime::now() } }
+    }
+
+    /// Hand out the next sequence of `channel_id`; the increment is atomic, so
+    /// concurrent inserts from any instance never share a sequence
+    async fn next_sequence(&self, channel_id: &ChannelId) -> Result<u64, CoreError> {
+        self.reserve_sequences(channel_id, 1).await
+    }
+
+    /// Hand out the next `count` sequences of `channel_id` at once, returning the last one
+    async fn reserve_sequences(
+        &self,
+        channel_id: &ChannelId,
+        count: u64,
+    ) -> Result<u64, CoreError> {
+        let counter = self
+            .channel_sequences
+            .find_one_and_update(
+                doc! { "_id": uuid_to_binary(channel_id.0) },
+                doc! { "$inc": { "last_sequence": count as i64 } },
+            )
+            .with_options(
+                FindOneAndUpdateOptions::builder()
+                    .upsert(true)
+                    .return_document(ReturnDocument::After)
+                    .build(),
+            )
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?
+            .ok_or_else(|| CoreError::DatabaseError {
+                msg: "channel sequence not returned".to_string(),
+            })?;
+
+        counter
+            .get_i64("last_sequence")
+            .map(|sequence| sequence as u64)
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })
+    }
+
+    /// Store `message` and, for a reply, count it in the thread summary of its
+    /// parent, both in one transaction unless the deployment is standalone
+    async fn insert_with_thread_summary(
+        &self,
+        message: &Message,
+        now: DateTime<Utc>,
+    ) -> mongodb::error::Result<()> {
+        // The persistence model takes care of the storage encoding (binary
+        // UUIDs, RFC3339 timestamps)
+        let document = MessageDocument::from(message);
+        let Some(parent_id) = &message.reply_to_message_id else {
+            return self.collection.insert_one(document).await.map(|_| ());
+        };
+        // `$max` keeps the latest reply date even when concurrent replies land
+        // out of order
+        let parent = doc! { "_id": Bson::Binary(uuid_to_binary(parent_id.0)) };
+        let summary = doc! {
+            "$inc": { "reply_count": 1_i64 },
+            "$max": { "last_reply_at": now.to_rfc3339() },
+        };
+
+        if self.transactions.load(Ordering::Relaxed) {
+            match self.insert_reply_in_transaction(&document, &parent, &summary).await {
+                Err(e) if is_transactions_unsupported(&e.kind) => {
+                    self.transactions.store(false, Ordering::Relaxed);
+                    tracing::warn!(
+                        "MongoDB runs standalone: replies and thread summaries are written without transaction"
+                    );
+                }
+                result => return result,
+            }
+        }
+
+        self.collection.insert_one(&document).await?;
+        self.collection.update_one(parent, summary).await?;
+        Ok(())
+    }
+
+    async fn insert_reply_in_transaction(
+        &self,
+        document: &MessageDocument,
+        parent: &Document,
+        summary: &Document,
+    ) -> mongodb::error::Result<()> {
+        let mut session = self.db.client().start_session().await?;
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            session.start_transaction().await?;
+            let written = async {
+                self.collection.insert_one(document).session(&mut session).await?;
+                self.collection
+                    .update_one(parent.clone(), summary.clone())
+                    .session(&mut session)
+                    .await?;
+                Ok::<_, mongodb::error::Error>(())
+            }
+            .await;
+            let committed = match written {
+                Ok(()) => {
+                    let mut commits = 0;
+                    loop {
+                        commits += 1;
+                        match session.commit_transaction().await {
+                            Err(e)
+                                if e.contains_label(UNKNOWN_TRANSACTION_COMMIT_RESULT)
+                                    && commits < MAX_TRANSACTION_ATTEMPTS => {}
+                            result => break result,
+                        }
+                    }
+                }
+                Err(e) => {
+                    let _ = session.abort_transaction().await;
+                    Err(e)
+                }
+            };
+            // concurrent replies to a parent conflict on its summary
+            match committed {
+                Err(e)
+                    if e.contains_label(TRANSIENT_TRANSACTION_ERROR)
+                        && attempts < MAX_TRANSACTION_ATTEMPTS => {}
+                result => return result,
+            }
+        }
+    }
+
+    fn nonce_filter(message: &Message) -> Document {
+        doc! {
+            "channel_id": uuid_match(message.channel_id.0),
+            "author_id": uuid_match(message.author_id.0),
+            "nonce": message.nonce.as_deref(),
+        }
+    }
+
+    /// Readable message already created by the author of `message` in its
+    /// channel with its nonce
+    async fn find_by_nonce(&self, message: &Message) -> Result<Option<Message>, CoreError> {
+        self.find_one_message(Self::not_deleted(Self::nonce_filter(message)))
+            .await
+    }
+
+    /// Take the nonce of `message` off the deleted or expired message holding
+    /// it, so a new message can be created with it
+    async fn release_nonce(&self, message: &Message) -> Result<(), CoreError> {
+        self.collection
+            .update_many(Self::nonce_filter(message), doc! { "$unset": { "nonce": "" } })
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        Ok(())
+    }
+
+    fn reaction_mute_filter(user_id: &UserId, message_id: Option<&MessageId>) -> Document {
+        let message_id = message_id
+            .map(|id| Bson::Document(uuid_match(id.0)))
+            .unwrap_or(Bson::Null);
+
+        doc! { "user_id": uuid_match(user_id.0), "message_id": message_id }
+    }
+
+    /// Fields of a reaction mute its filter cannot fill in on upserts
+    fn reaction_mute_fields(user_id: &UserId, message_id: Option<&MessageId>) -> Document {
+        let mut fields = doc! { "user_id": uuid_to_binary(user_id.0) };
+        if let Some(message_id) = message_id {
+            fields.insert("message_id", uuid_to_binary(message_id.0));
+        }
+        fields
+    }
+
+    fn pagination_options(pagination: &GetPaginated, sort: Document) -> FindOptions {
+        let limit = pagination.limit.min(50) as i64;
+        let skip = ((pagination.page - 1) * pagination.limit) as u64;
+
+        FindOptions::builder()
+            .sort(sort)
+            .skip(skip)
+            .limit(limit)
+            .build()
+    }
+
+    /// First message matching `filter`, with its reaction counts
+    async fn find_one_message(&self, filter: Document) -> Result<Option<Message>, CoreError> {
+        let message = self
+            .collection
+            .find_one(filter)
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?
+            .map(Message::try_from)
+            .transpose()?;
+
+        match message {
+            Some(message) => {
+                let mut messages = [message];
+                self.attach_reaction_counts(&mut messages).await?;
+                let [message] = messages;
+                Ok(Some(message))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Page of the messages matching `filter`, newest first, with their total count
+    async fn list_page(
+        &self,
+        filter: Document,
+        pagination: &GetPaginated,
+    ) -> Result<(Vec<Message>, TotalPaginatedElements), CoreError> {
+        self.list_sorted_page(filter, doc! { "created_at": -1 }, pagination)
+            .await
+    }
+
+    /// Page of the messages matching `filter` in `sort` order, with their total count
+    async fn list_sorted_page(
+        &self,
+        filter: Document,
+        sort: Document,
+        pagination: &GetPaginated,
+    ) -> Result<(Vec<Message>, TotalPaginatedElements), CoreError> {
+        let total = self
+            .collection
+            .count_documents(filter.clone())
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        let mut cursor = self
+            .collection
+            .find(filter)
+            .with_options(Self::pagination_options(pagination, sort))
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        let mut messages = Vec::new();
+        while let Some(document) = cursor
+            .try_next()
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?
+        {
+            messages.push(Message::try_from(document)?);
+        }
+
+        self.attach_reaction_counts(&mut messages).await?;
+
+        Ok((messages, total))
+    }
+
+    /// Fill in the aggregated reaction counts of the given messages with a single query
+    pub(crate) async fn attach_reaction_counts(&self, messages: &mut [Message]) -> Result<(), CoreError> {
+        if messages.is_empty() {
+            return Ok(());
+        }
+
+        let ids = uuids_in(messages.iter().map(|m| m.id.0));
+
+        let pipeline = vec![
+            doc! { "$match": { "message_id": { "$in": ids } } },
+            doc! { "$group": {
+                "_id": { "message_id": "$message_id", "emoji": "$emoji" },
+                "count": { "$sum": 1 },
+            } },
+        ];
+
+        let mut cursor = self
+            .reactions
+            .aggregate(pipeline)
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        let mut counts: HashMap<MessageId, Vec<ReactionCount>> = HashMap::new();
+        while let Some(group) = cursor
+            .try_next()
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?
+        {
+            let (message_id, count) = Self::parse_reaction_group(&group)?;
+            // reactions stored in both encodings are grouped apart
+            let message_counts = counts.entry(message_id).or_default();
+            match message_counts.iter_mut().find(|c| c.emoji == count.emoji) {
+                Some(existing) => existing.count += count.count,
+                None => message_counts.push(count),
+            }
+        }
+
+        for message in messages.iter_mut() {
+            let mut reactions = counts.remove(&message.id).unwrap_or_default();
+            reactions.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.emoji.cmp(&b.emoji)));
+            message.reactions = reactions;
+        }
+
+        Ok(())
+    }
+
+    /// Messages of a `$facet` output field
+    fn facet_messages(facets: &Document, name: &str) -> Result<Vec<Message>, CoreError> {
+        let Ok(documents) = facets.get_array(name) else {
+            return Ok(Vec::new());
+        };
+
+        documents
+            .iter()
+            .filter_map(Bson::as_document)
+            .map(|document| {
+                let document: MessageDocument = from_document(document.clone())
+                    .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+                Message::try_from(document)
+            })
+            .collect()
+    }
+
+    fn parse_reaction_group(group: &Document) -> Result<(MessageId, ReactionCount), CoreError> {
+        let malformed = |e: mongodb::bson::document::ValueAccessError| CoreError::DatabaseError {
+            msg: format!("malformed reaction aggregate: {}", e),
+        };
+
+        let key = group.get_document("_id").map_err(malformed)?;
+        let message_id = match key.get("message_id") {
+            Some(Bson::Binary(binary)) => MessageId(binary_to_uuid(binary)?),
+            _ => {
+                return Err(CoreError::DatabaseError {
+                    msg: "malformed reaction aggregate: missing message_id".to_string(),
+                });
+            }
+        };
+        let emoji = key.get_str("emoji").map_err(malformed)?.to_string();
+        let count = match group.get("count") {
+            Some(Bson::Int32(n)) => *n as u64,
+            Some(Bson::Int64(n)) => *n as u64,
+            _ => 0,
+        };
+
+        Ok((message_id, ReactionCount { emoji, count }))
+    }
+
+    /// Restrict a filter to documents strictly after `cursor` in `(created_at, <id_field>)` order
+    fn after_cursor(mut filter: Document, cursor: Option<Cursor>, id_field: &str) -> Document {
+        if let Some(cursor) = cursor {
+            let created_at = cursor.created_at.to_rfc3339();
+            let id = Bson::Binary(uuid_to_binary(cursor.id));
+            let mut same_instant = doc! { "created_at": created_at.clone() };
+            same_instant.insert(id_field, doc! { "$gt": id });
+            filter.insert(
+                "$or",
+                vec![doc! { "created_at": { "$gt": created_at } }, same_instant],
+            );
+        }
+        filter
+    }
+
+    /// Restrict a filter to documents strictly within `range` in `(created_at, <id_field>)` order
+    fn within_range(mut filter: Document, range: &CursorRange, id_field: &str) -> Document {
+        let bounds = [("$lt", range.before), ("$gt", range.after)];
+        let conditions: Vec<Document> = bounds
+            .into_iter()
+            .filter_map(|(operator, cursor)| {
+                let cursor = cursor?;
+                let created_at = cursor.created_at.to_rfc3339();
+                let id = Bson::Binary(uuid_to_binary(cursor.id));
+                let compare = |value: Bson| {
+                    let mut comparison = Document::new();
+                    comparison.insert(operator, value);
+                    comparison
+                };
+                let mut same_instant = doc! { "created_at": created_at.clone() };
+                same_instant.insert(id_field, compare(id));
+                let other_instant = doc! { "created_at": compare(Bson::String(created_at)) };
+                Some(doc! { "$or": [other_instant, same_instant] })
+            })
+            .collect();
+
+        if !conditions.is_empty() {
+            filter.insert("$and", conditions);
+        }
+        filter
+    }
+
+    fn reaction_filter(message_id: &MessageId, user_id: &UserId, emoji: &str) -> Document {
+        doc! {
+            "message_id": uuid_match(message_id.0),
+            "user_id": uuid_match(user_id.0),
+            "emoji": emoji,
+        }
+    }
+
+    /// `created_at` of now added to the fields set on an upsert
+    fn with_created_at(mut fields: Document) -> Document {
+        fields.insert("created_at", Utc::now().to_rfc3339());
+        fields
+    }
+}
+
+#[async_trait::async_trait]
+impl MessageRepository for MongoMessageRepository {
+    async fn insert(&self, input: InsertMessageInput) -> Result<Message, CoreError> {
+        let sequence = self.next_sequence(&input.channel_id).await?;
+        let now = Utc::now();
+        let mentions = Mentions::parse(&input.content);
+
+        let message = Message {
+            id: input.id,
+            channel_id: input.channel_id,
+            author_id: input.author_id,
+            content: input.content,
+            reply_to_message_id: input.reply_to_message_id,
+            attachments: input.attachments,
+            is_pinned: false,
+            reactions: Vec::new(),
+            revision: 0,
+            sequence,
+            reply_count: 0,
+            last_reply_at: None,
+            archived_at: None,
+            created_at: now,
+            updated_at: None,
+            deleted_at: None,
+            nonce: input.nonce,
+            embeds: Vec::new(),
+            mentions,
+            expires_at: input.expires_at,
+        };
+
+        match self.insert_with_thread_summary(&message, now).await {
+            Ok(()) => {}
+            // The nonce is taken: this is a retry of a creation that succeeded,
+            // unless that message was deleted or expired since
+            Err(e) if message.nonce.is_some() && is_duplicate_key(&e.kind) => {
+                if let Some(first) = self.find_by_nonce(&message).await? {
+                    return Ok(first);
+                }
+                self.release_nonce(&message).await?;
+                self.insert_with_thread_summary(&message, now)
+                    .await
+                    .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+            }
+            Err(e) => return Err(CoreError::DatabaseError { msg: e.to_string() }),
+        }
+
+        let event = OutboxEventRecord::new(
+            self.routing.create_message.clone(),
+            CreateMessageEvent::from(&message),
+        );
+        write_outbox_event_with_policy(&self.db, &event, self.outbox_encryption.as_ref()).await?;
+
+        Ok(message)
+    }
+
+    async fn insert_many(
+        &self,
+        channel_id: &ChannelId,
+        inputs: Vec<ImportMessageInput>,
+    ) -> Result<Vec<MessageId>, CoreError> {
+        if inputs.is_empty() {
+            return Ok(Vec::new());
+        }
+        let count = inputs.len() as u64;
+        let first = self.reserve_sequences(channel_id, count).await? + 1 - count;
+        let messages: Vec<Message> = inputs
+            .into_iter()
+            .zip(first..)
+            .map(|(input, sequence)| input.into_message(*channel_id, sequence))
+            .collect();
+
+        // Unordered, so the messages stored by an earlier attempt of the same
+        // import are skipped without stopping the others
+        let skipped: HashSet<usize> = match self
+            .collection
+            .insert_many(messages.iter().map(MessageDocument::from))
+            .ordered(false)
+            .await
+        {
+            Ok(_) => HashSet::new(),
+            Err(e) => match e.kind.as_ref() {
+                ErrorKind::InsertMany(InsertManyError {
+                    write_errors: Some(errors),
+                    write_concern_error: None,
+                    ..
+                }) if errors.iter().all(|error| error.code == DUPLICATE_KEY) => {
+                    errors.iter().map(|error| error.index).collect()
+                }
+                _ => return Err(CoreError::DatabaseError { msg: e.to_string() }),
+            },
+        };
+        let imported: Vec<Message> = messages
+            .into_iter()
+            .enumerate()
+            .filter(|(index, _)| !skipped.contains(index))
+            .map(|(_, message)| message)
+            .collect();
+
+        // Thread summaries of the parents, which may be part of the import
+        let mut threads: HashMap<MessageId, (i64, DateTime<Utc>)> = HashMap::new();
+        for message in &imported {
+            if let Some(parent_id) = message.reply_to_message_id {
+                let (replies, last_reply_at) =
+                    threads.entry(parent_id).or_insert((0, message.created_at));
+                *replies += 1;
+                *last_reply_at = (*last_reply_at).max(message.created_at);
+            }
+        }
+        for (parent_id, (replies, last_reply_at)) in threads {
+            self.collection
+                .update_one(
+                    doc! { "_id": Bson::Binary(uuid_to_binary(parent_id.0)) },
+                    doc! {
+                        "$inc": { "reply_count": replies },
+                        "$max": { "last_reply_at": last_reply_at.to_rfc3339() },
+                    },
+                )
+                .await
+                .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+        }
+
+        Ok(imported.into_iter().map(|message| message.id).collect())
+    }
+
+    async fn find_by_id(&self, id: &MessageId) -> Result<Option<Message>, CoreError> {
+        let id_bson = Bson::Binary(uuid_to_binary(id.0));
+        self.find_one_message(Self::not_deleted(doc! { "_id": id_bson }))
+            .await
+    }
+
+    async fn find_by_ids(&self, ids: &[MessageId]) -> Result<Vec<Message>, CoreError> {
+        let ids: Vec<Bson> = ids
+            .iter()
+            .map(|id| Bson::Binary(uuid_to_binary(id.0)))
+            .collect();
+        let mut cursor = self
+            .collection
+            .find(Self::not_deleted(doc! { "_id": { "$in": ids } }))
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        let mut messages = Vec::new();
+        while let Some(document) = cursor
+            .try_next()
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?
+        {
+            messages.push(Message::try_from(document)?);
+        }
+
+        self.attach_reaction_counts(&mut messages).await?;
+        Ok(messages)
+    }
+
+    async fn find_by_id_including_deleted(
+        &self,
+        id: &MessageId,
+    ) -> Result<Option<Message>, CoreError> {
+        let id_bson = Bson::Binary(uuid_to_binary(id.0));
+        self.find_one_message(doc! { "_id": id_bson }).await
+    }
+
+    async fn list(
+        &self,
+        channel_id: &crate::domain::message::entities::ChannelId,
+        pagination: &GetPaginated,
+    ) -> Result<(Vec<Message>, TotalPaginatedElements), CoreError>
+    {
+        // build filter by channel_id
+        let filter = Self::not_deleted(doc! { "channel_id": uuid_match(channel_id.0) });
+
+        self.list_page(filter, pagination).await
+    }
+
+    async fn list_including_deleted(
+        &self,
+        channel_id: &ChannelId,
+        pagination: &GetPaginated,
+    ) -> Result<(Vec<Message>, TotalPaginatedElements), CoreError> {
+        self.list_page(doc! { "channel_id": uuid_match(channel_id.0) }, pagination)
+            .await
+    }
+
+    async fn stream_channel(
+        &self,
+        channel_id: &ChannelId,
+        include_deleted: bool,
+    ) -> Result<MessageStream, CoreError> {
+        let mut filter = doc! { "channel_id": uuid_match(channel_id.0) };
+        if !include_deleted {
+            filter = Self::not_deleted(filter);
+        }
+        self.stream_oldest_first(filter).await
+    }
+
+    async fn stream_by_author(
+        &self,
+        author_id: &AuthorId,
+        include_deleted: bool,
+    ) -> Result<MessageStream, CoreError> {
+        let mut filter = doc! { "author_id": uuid_match(author_id.0) };
+        if !include_deleted {
+            filter = Self::not_deleted(filter);
+        }
+        self.stream_oldest_first(filter).await
+    }
+
+    async fn list_by_author(
+        &self,
+        author_id: &AuthorId,
+        channel_id: &ChannelId,
+        pagination: &GetPaginated,
+    ) -> Result<(Vec<Message>, TotalPaginatedElements), CoreError> {
+        let filter = Self::not_deleted(doc! {
+            "author_id": uuid_match(author_id.0),
+            "channel_id": uuid_match(channel_id.0),
+        });
+
+        self.list_page(filter, pagination).await
+    }
+
+    async fn list_pinned(
+        &self,
+        channel_id: &ChannelId,
+        pagination: &GetPaginated,
+    ) -> Result<(Vec<Message>, TotalPaginatedElements), CoreError> {
+        let filter = Self::not_deleted(doc! {
+            "channel_id": uuid_match(channel_id.0),
+            "is_pinned": true,
+        });
+
+        // messages pinned before `pinned_at` was tracked come last
+        let sort = doc! { "pinned_at": -1, "created_at": -1 };
+        self.list_sorted_page(filter, sort, pagination).await
+    }
+
+    async fn count_pinned(&self, channel_id: &ChannelId) -> Result<u64, CoreError> {
+        self.collection
+            .count_documents(Self::not_deleted(doc! {
+                "channel_id": uuid_match(channel_id.0),
+                "is_pinned": true,
+            }))
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })
+    }
+
+    async fn list_threads(
+        &self,
+        channel_id: &ChannelId,
+        include_archived: bool,
+        pagination: &GetPaginated,
+    ) -> Result<(Vec<Message>, TotalPaginatedElements), CoreError> {
+        let mut filter = Self::not_deleted(doc! {
+            "channel_id": uuid_match(channel_id.0),
+            "reply_count": { "$gt": 0_i64 },
+        });
+        if !include_archived {
+            filter.insert("archived_at", Bson::Null);
+        }
+
+        let sort = doc! { "last_reply_at": -1, "_id": -1 };
+        self.list_sorted_page(filter, sort, pagination).await
+    }
+
+    async fn archive_idle_threads(
+        &self,
+        idle_before: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<Message>, CoreError> {
+        let mut cursor = self
+            .collection
+            .find(Self::not_deleted(doc! {
+                "reply_count": { "$gt": 0_i64 },
+                "archived_at": Bson::Null,
+                "last_reply_at": { "$lt": idle_before.to_rfc3339() },
+            }))
+            .limit(limit as i64)
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        let mut idle = Vec::new();
+        while let Some(document) = cursor
+            .try_next()
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?
+        {
+            idle.push(Message::try_from(document)?);
+        }
+
+        let now = Utc::now();
+        let mut archived = Vec::new();
+        for mut thread in idle {
+            // unless a reply came in meanwhile
+            let result = self
+                .collection
+                .update_one(
+                    doc! {
+                        "_id": Bson::Binary(uuid_to_binary(thread.id.0)),
+                        "archived_at": Bson::Null,
+                        "last_reply_at": thread.last_reply_at.map(|at| at.to_rfc3339()),
+                    },
+                    doc! { "$set": { "archived_at": now.to_rfc3339() } },
+                )
+                .await
+                .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+            if result.modified_count == 0 {
+                continue;
+            }
+
+            let event = OutboxEventRecord::new(
+                self.routing.archive_thread.clone(),
+                ThreadArchivedEvent {
+                    thread_id: thread.id,
+                    channel_id: thread.channel_id,
+                    archived_at: now,
+                },
+            );
+            write_outbox_event_with_policy(&self.db, &event, self.outbox_encryption.as_ref())
+                .await?;
+            thread.archived_at = Some(now);
+            archived.push(thread);
+        }
+
+        self.attach_reaction_counts(&mut archived).await?;
+        Ok(archived)
+    }
+
+    async fn unarchive_thread(&self, thread_id: &MessageId) -> Result<bool, CoreError> {
+        let document = self
+            .collection
+            .find_one_and_update(
+                doc! {
+                    "_id": Bson::Binary(uuid_to_binary(thread_id.0)),
+                    "archived_at": { "$type": "string" },
+                },
+                doc! { "$set": { "archived_at": Bson::Null } },
+            )
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+        let Some(document) = document else {
+            return Ok(false);
+        };
+
+        let event = OutboxEventRecord::new(
+            self.routing.unarchive_thread.clone(),
+            ThreadUnarchivedEvent {
+                thread_id: *thread_id,
+                channel_id: binary_to_uuid(&document.channel_id)?.into(),
+                unarchived_at: Utc::now(),
+            },
+        );
+        write_outbox_event_with_policy(&self.db, &event, self.outbox_encryption.as_ref()).await?;
+
+        Ok(true)
+    }
+
+    async fn list_by_cursor(
+        &self,
+        channel_id: &ChannelId,
+        range: &CursorRange,
+        limit: usize,
+    ) -> Result<CursorPage<Message>, CoreError> {
+        let filter = Self::within_range(
+            Self::not_deleted(doc! { "channel_id": uuid_match(channel_id.0) }),
+            range,
+            "_id",
+        );
+
+        let mut cursor = self
+            .collection
+            .find(filter)
+            .sort(doc! { "created_at": -1, "_id": -1 })
+            .limit(limit as i64 + 1)
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        let mut messages = Vec::new();
+        while let Some(document) = cursor
+            .try_next()
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?
+        {
+            messages.push(Message::try_from(document)?);
+        }
+
+        let mut page = CursorPage::from_overfetched(messages, limit, |m| {
+            Cursor::new(m.created_at, m.id.0)
+        });
+        self.attach_reaction_counts(&mut page.items).await?;
+
+        Ok(page)
+    }
+
+    async fn update(&self, input: UpdateMessageInput) -> Result<Message, CoreError> {
+        let collection = self.collection.clone();
+
+        let now = Utc::now();
+        let mut set = doc! {
+            // store updated_at as RFC3339 string to match how `created_at` is serialized
+            "updated_at": now.to_rfc3339()
+        };
+        let mut update = Document::new();
+
+        if let Some(content) = &input.content {
+            set.insert("content", content);
+        }
+
+        if let Some(is_pinned) = input.is_pinned {
+            set.insert("is_pinned", is_pinned);
+            if is_pinned {
+                set.insert("pinned_at", now.to_rfc3339());
+            } else {
+                update.insert("$unset", doc! { "pinned_at": "" });
+            }
+        }
+        update.insert("$set", set);
+        update.insert("$inc", doc! { "revision": 1_i64 });
+
+        // The document as it was before the update is the one whose content
+        // goes to the history, even under concurrent edits
+        let options = FindOneAndUpdateOptions::builder()
+            .return_document(ReturnDocument::Before)
+            .build();
+
+        let id_bson = Bson::Binary(uuid_to_binary(input.id.0));
+        let mut filter = doc! { "_id": id_bson };
+        match input.expected_revision {
+            // messages stored before revisions were introduced have none
+            Some(0) => {
+                filter.insert("revision", doc! { "$in": [0_i64, Bson::Null] });
+            }
+            Some(revision) => {
+                filter.insert("revision", revision as i64);
+            }
+            None => {}
+        }
+
+        let previous = collection
+            .find_one_and_update(Self::not_deleted(filter), update)
+            .with_options(options)
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        let previous = match previous {
+            Some(previous) => Message::try_from(previous)?,
+            // the message is gone, or another update won the race
+            None => {
+                return Err(match self.find_by_id(&input.id).await? {
+                    Some(current) => CoreError::MessageRevisionMismatch {
+                        id: input.id,
+                        revision: current.revision,
+                    },
+                    None => CoreError::MessageNotFound { id: input.id },
+                });
+            }
+        };
+
+        let mut updated = previous.clone();
+        if let Some(content) = input.content {
+            updated.mentions = Mentions::parse(&content);
+            updated.content = content;
+        }
+        if let Some(is_pinned) = input.is_pinned {
+            updated.is_pinned = is_pinned;
+        }
+        updated.revision += 1;
+        updated.updated_at = Some(now);
+
+        if updated.content != previous.content {
+            let revision = MessageRevision {
+                message_id: previous.id,
+                revision: previous.revision,
+                written_at: previous.updated_at.unwrap_or(previous.created_at),
+                content: previous.content,
+                replaced_at: now,
+            };
+            self.revisions
+                .insert_one(MessageRevisionDocument::from(&revision))
+                .await
+                .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+        }
+
+        let event = OutboxEventRecord::new(
+            self.routing.update_message.clone(),
+            UpdateMessageEvent::from(&updated),
+        );
+        write_outbox_event_with_policy(&self.db, &event, self.outbox_encryption.as_ref()).await?;
+
+        let mut messages = [updated];
+        self.attach_reaction_counts(&mut messages).await?;
+        let [updated] = messages;
+        Ok(updated)
+    }
+
+    async fn set_embeds(
+        &self,
+        id: &MessageId,
+        revision: u64,
+        embeds: &[Embed],
+    ) -> Result<Option<Message>, CoreError> {
+        let embeds = to_bson(embeds).map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+        let mut filter = doc! { "_id": Bson::Binary(uuid_to_binary(id.0)) };
+        if revision == 0 {
+            // messages stored before revisions were introduced have none
+            filter.insert("revision", doc! { "$in": [0_i64, Bson::Null] });
+        } else {
+            filter.insert("revision", revision as i64);
+        }
+
+        // Embeds are not an edit: `updated_at` is left alone and no
+        // revision goes to the history, yet the revision moves so the ETag
+        // of the message changes
+        let options = FindOneAndUpdateOptions::builder()
+            .return_document(ReturnDocument::After)
+            .build();
+        let updated = self
+            .collection
+            .find_one_and_update(
+                Self::not_deleted(filter),
+                doc! { "$set": { "embeds": embeds }, "$inc": { "revision": 1_i64 } },
+            )
+            .with_options(options)
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+        let Some(updated) = updated else {
+            return Ok(None);
+        };
+        let updated = Message::try_from(updated)?;
+
+        let event = OutboxEventRecord::new(
+            self.routing.update_message.clone(),
+            UpdateMessageEvent::from(&updated),
+        );
+        write_outbox_event_with_policy(&self.db, &event, self.outbox_encryption.as_ref()).await?;
+
+        let mut messages = [updated];
+        self.attach_reaction_counts(&mut messages).await?;
+        let [updated] = messages;
+        Ok(Some(updated))
+    }
+
+    async fn list_revisions(
+        &self,
+        message_id: &MessageId,
+    ) -> Result<Vec<MessageRevision>, CoreError> {
+        let mut cursor = self
+            .revisions
+            .find(doc! { "message_id": uuid_match(message_id.0) })
+            .sort(doc! { "revision": 1 })
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        let mut revisions = Vec::new();
+        while let Some(document) = cursor
+            .try_next()
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?
+        {
+            revisions.push(MessageRevision::try_from(document)?);
+        }
+
+        Ok(revisions)
+    }
+
+    async fn delete(&self, id: &MessageId) -> Result<(), CoreError> {
+        let collection = self.collection.clone();
+        let id = *id;
+
+        let id_bson = Bson::Binary(uuid_to_binary(id.0));
+
+        // Soft delete, so moderators can still review the message
+        let deleted = collection
+            .find_one_and_update(
+                Self::not_deleted(doc! { "_id": id_bson }),
+                doc! { "$set": { "deleted_at": Utc::now().to_rfc3339() } },
+            )
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?
+            .ok_or(CoreError::MessageNotFound { id })?;
+        let deleted = Message::try_from(deleted)?;
+
+        if let Some(parent_id) = &deleted.reply_to_message_id {
+            collection
+                .update_one(
+                    doc! {
+                        "_id": Bson::Binary(uuid_to_binary(parent_id.0)),
+                        "reply_count": { "$gt": 0_i64 },
+                    },
+                    doc! { "$inc": { "reply_count": -1_i64 } },
+                )
+                .await
+                .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+        }
+
+        self.reactions
+            .delete_many(doc! { "message_id": uuid_match(id.0) })
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        let event = OutboxEventRecord::new(
+            self.routing.delete_message.clone(),
+            DeleteMessageEvent { id, channel_id: deleted.channel_id },
+        );
+        write_outbox_event_with_policy(&self.db, &event, self.outbox_encryption.as_ref()).await?;
+
+        Ok(())
+    }
+
+    async fn delete_many(
+        &self,
+        channel_id: &ChannelId,
+        ids: &[MessageId],
+        deleted_by: &UserId,
+        reason: Option<&ModerationReason>,
+    ) -> Result<Vec<Message>, CoreError> {
+        let ids: Vec<Bson> = ids
+            .iter()
+            .map(|id| Bson::Binary(uuid_to_binary(id.0)))
+            .collect();
+        let mut cursor = self
+            .collection
+            .find(Self::not_deleted(doc! {
+                "_id": { "$in": ids },
+                "channel_id": uuid_match(channel_id.0),
+            }))
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        let mut messages = Vec::new();
+        while let Some(document) = cursor
+            .try_next()
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?
+        {
+            messages.push(Message::try_from(document)?);
+        }
+        if messages.is_empty() {
+            return Ok(messages);
+        }
+
+        let now = Utc::now();
+        let found: Vec<Bson> = messages
+            .iter()
+            .map(|message| Bson::Binary(uuid_to_binary(message.id.0)))
+            .collect();
+        self.collection
+            .update_many(
+                Self::not_deleted(doc! { "_id": { "$in": found } }),
+                doc! { "$set": { "deleted_at": now.to_rfc3339() } },
+            )
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        let mut replies: HashMap<MessageId, i64> = HashMap::new();
+        for parent_id in messages.iter().filter_map(|m| m.reply_to_message_id) {
+            *replies.entry(parent_id).or_default() += 1;
+        }
+        for (parent_id, count) in replies {
+            self.collection
+                .update_one(
+                    doc! {
+                        "_id": Bson::Binary(uuid_to_binary(parent_id.0)),
+                        "reply_count": { "$gte": count },
+                    },
+                    doc! { "$inc": { "reply_count": -count } },
+                )
+                .await
+                .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+        }
+
+        self.reactions
+            .delete_many(doc! { "message_id": { "$in": uuids_in(messages.iter().map(|m| m.id.0)) } })
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        let event = OutboxEventRecord::new(
+            self.routing.bulk_delete_messages.clone(),
+            MessagesBulkDeletedEvent {
+                channel_id: *channel_id,
+                ids: messages.iter().map(|message| message.id).collect(),
+                deleted_by: *deleted_by,
+                deleted_at: now,
+                reason: reason.cloned(),
+            },
+        );
+        write_outbox_event_with_policy(&self.db, &event, self.outbox_encryption.as_ref()).await?;
+
+        for message in &mut messages {
+            message.deleted_at = Some(now);
+        }
+        Ok(messages)
+    }
+
+    async fn hard_delete(&self, id: &MessageId) -> Result<(), CoreError> {
+        let id = *id;
+
+        let removed = self
+            .collection
+            .find_one_and_delete(doc! { "_id": Bson::Binary(uuid_to_binary(id.0)) })
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?
+            .ok_or(CoreError::MessageNotFound { id })?;
+        let removed = Message::try_from(removed)?;
+
+        // Soft deleting already released its parent's count and announced it
+        if removed.deleted_at.is_none() {
+            if let Some(parent_id) = &removed.reply_to_message_id {
+                self.collection
+                    .update_one(
+                        doc! {
+                            "_id": Bson::Binary(uuid_to_binary(parent_id.0)),
+                            "reply_count": { "$gt": 0_i64 },
+                        },
+                        doc! { "$inc": { "reply_count": -1_i64 } },
+                    )
+                    .await
+                    .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+            }
+
+            let event = OutboxEventRecord::new(
+                self.routing.delete_message.clone(),
+                DeleteMessageEvent { id, channel_id: removed.channel_id },
+            );
+            write_outbox_event_with_policy(&self.db, &event, self.outbox_encryption.as_ref())
+                .await?;
+        }
+
+        self.reactions
+            .delete_many(doc! { "message_id": uuid_match(id.0) })
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        self.revisions
+            .delete_many(doc! { "message_id": uuid_match(id.0) })
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        Ok(())
+    }
+
+    async fn list_deleted_before(
+        &self,
+        before: DateTime<Utc>,
+        held_channels: &[ChannelId],
+        held_authors: &[AuthorId],
+        limit: usize,
+    ) -> Result<Vec<Message>, CoreError> {
+        let held_channels = uuids_in(held_channels.iter().map(|channel_id| channel_id.0));
+        let held_authors = uuids_in(held_authors.iter().map(|author_id| author_id.0));
+        // `$lt` on a string leaves out the `null` of messages never deleted
+        let filter = doc! {
+            "deleted_at": { "$lt": before.to_rfc3339() },
+            "channel_id": { "$nin": held_channels },
+            "author_id": { "$nin": held_authors },
+        };
+
+        let mut cursor = self
+            .collection
+            .find(filter)
+            .sort(doc! { "deleted_at": 1 })
+            .limit(limit as i64)
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        let mut messages = Vec::new();
+        while let Some(document) = cursor
+            .try_next()
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?
+        {
+            messages.push(Message::try_from(document)?);
+        }
+
+        Ok(messages)
+    }
+
+    async fn list_expired_before(
+        &self,
+        before: DateTime<Utc>,
+        held_channels: &[ChannelId],
+        held_authors: &[AuthorId],
+        limit: usize,
+    ) -> Result<Vec<Message>, CoreError> {
+        let held_channels = uuids_in(held_channels.iter().map(|channel_id| channel_id.0));
+        let held_authors = uuids_in(held_authors.iter().map(|author_id| author_id.0));
+        // `$lt` on a date leaves out the `null` of messages that never expire
+        let filter = doc! {
+            "expires_at": { "$lt": mongodb::bson::DateTime::from_millis(before.timestamp_millis()) },
+            "channel_id": { "$nin": held_channels },
+            "author_id": { "$nin": held_authors },
+        };
+
+        let mut cursor = self
+            .collection
+            .find(filter)
+            .sort(doc! { "expires_at": 1 })
+            .limit(limit as i64)
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        let mut messages = Vec::new();
+        while let Some(document) = cursor
+            .try_next()
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?
+        {
+            messages.push(Message::try_from(document)?);
+        }
+
+        Ok(messages)
+    }
+
+    async fn delete_by_channel(
+        &self,
+        channel_id: &ChannelId,
+        held_authors: &[AuthorId],
+    ) -> Result<u64, CoreError> {
+        let held_authors = uuids_in(held_authors.iter().map(|author_id| author_id.0));
+        let filter = Self::not_deleted(doc! {
+            "channel_id": uuid_match(channel_id.0),
+            "author_id": { "$nin": held_authors },
+        });
+
+        let result = self
+            .collection
+            .update_many(
+                filter,
+                doc! { "$set": { "deleted_at": Utc::now().to_rfc3339() } },
+            )
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        Ok(result.modified_count)
+    }
+
+    async fn delete_by_author(
+        &self,
+        author_id: &AuthorId,
+        held_channels: &[ChannelId],
+        erased_by: &UserId,
+    ) -> Result<Vec<Message>, CoreError> {
+        let held_channels = uuids_in(held_channels.iter().map(|channel_id| channel_id.0));
+        let mut cursor = self
+            .collection
+            .find(doc! {
+                "author_id": uuid_match(author_id.0),
+                "channel_id": { "$nin": held_channels },
+            })
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        let mut messages = Vec::new();
+        while let Some(document) = cursor
+            .try_next()
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?
+        {
+            messages.push(Message::try_from(document)?);
+        }
+        if messages.is_empty() {
+            return Ok(messages);
+        }
+
+        let now = Utc::now();
+        let found: Vec<Bson> = messages
+            .iter()
+            .map(|message| Bson::Binary(uuid_to_binary(message.id.0)))
+            .collect();
+        // Keeps when messages deleted before were deleted
+        self.collection
+            .update_many(
+                doc! { "_id": { "$in": found } },
+                vec![doc! { "$set": {
+                    "content": "",
+                    "attachments": { "$literal": [] },
+                    "nonce": Bson::Null,
+                    "embeds": { "$literal": [] },
+                    "deleted_at": { "$ifNull": ["$deleted_at", now.to_rfc3339()] },
+                } }],
+            )
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        let newly_deleted: Vec<&Message> = messages
+            .iter()
+            .filter(|message| message.deleted_at.is_none())
+            .collect();
+        let mut replies: HashMap<MessageId, i64> = HashMap::new();
+        for parent_id in newly_deleted.iter().filter_map(|m| m.reply_to_message_id) {
+            *replies.entry(parent_id).or_default() += 1;
+        }
+        for (parent_id, count) in replies {
+            self.collection
+                .update_one(
+                    doc! {
+                        "_id": Bson::Binary(uuid_to_binary(parent_id.0)),
+                        "reply_count": { "$gte": count },
+                    },
+                    doc! { "$inc": { "reply_count": -count } },
+                )
+                .await
+                .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+        }
+
+        let ids = uuids_in(messages.iter().map(|m| m.id.0));
+        self.revisions
+            .delete_many(doc! { "message_id": { "$in": ids.clone() } })
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+        self.reactions
+            .delete_many(doc! { "$or": [
+                { "message_id": { "$in": ids } },
+                { "user_id": uuid_match(author_id.0) },
+            ] })
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+        self.reaction_mutes
+            .delete_many(doc! { "user_id": uuid_match(author_id.0) })
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        let mut by_channel: HashMap<ChannelId, Vec<MessageId>> = HashMap::new();
+        for message in &newly_deleted {
+            by_channel.entry(message.channel_id).or_default().push(message.id);
+        }
+        for (channel_id, ids) in by_channel {
+            let event = OutboxEventRecord::new(
+                self.routing.bulk_delete_messages.clone(),
+                MessagesBulkDeletedEvent {
+                    channel_id,
+                    ids,
+                    deleted_by: *erased_by,
+                    deleted_at: now,
+                    reason: None,
+                },
+            );
+            write_outbox_event_with_policy(&self.db, &event, self.outbox_encryption.as_ref())
+                .await?;
+        }
+        let event = OutboxEventRecord::new(
+            self.routing.erase_user.clone(),
+            UserErasedEvent {
+                user_id: UserId(author_id.0),
+                message_ids: messages.iter().map(|message| message.id).collect(),
+                erased_by: *erased_by,
+                erased_at: now,
+            },
+        );
+        write_outbox_event_with_policy(&self.db, &event, self.outbox_encryption.as_ref()).await?;
+
+        Ok(messages)
+    }
+
+    async fn list_replies(
+        &self,
+        message_id: &MessageId,
+        pagination: &GetCursorPaginated,
+    ) -> Result<CursorPage<Message>, CoreError> {
+        let cursor = pagination.decoded_cursor()?;
+        let limit = pagination.effective_limit();
+
+        // reply_to_message_id is stored as a string, see the dto module
+        let filter = Self::after_cursor(
+            Self::not_deleted(doc! { "reply_to_message_id": message_id.to_string() }),
+            cursor,
+            "_id",
+        );
+
+        let mut cursor = self
+            .collection
+            .find(filter)
+            .sort(doc! { "created_at": 1, "_id": 1 })
+            .limit(limit as i64 + 1)
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        let mut replies = Vec::new();
+        while let Some(document) = cursor
+            .try_next()
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?
+        {
+            replies.push(Message::try_from(document)?);
+        }
+
+        let mut page = CursorPage::from_overfetched(replies, limit, |m| {
+            Cursor::new(m.created_at, m.id.0)
+        });
+        self.attach_reaction_counts(&mut page.items).await?;
+
+        Ok(page)
+    }
+
+    async fn count_deleted_by_author(
+        &self,
+        author_id: &AuthorId,
+        since: DateTime<Utc>,
+    ) -> Result<u64, CoreError> {
+        // `deleted_at` is an RFC3339 string in UTC, so it sorts chronologically
+        self.collection
+            .count_documents(doc! {
+                "author_id": uuid_match(author_id.0),
+                "deleted_at": { "$gte": since.to_rfc3339() },
+            })
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })
+    }
+
+    async fn list_created_before(
+        &self,
+        channel_id: Option<&ChannelId>,
+        before: DateTime<Utc>,
+        excluded_channels: &[ChannelId],
+        held_authors: &[AuthorId],
+        limit: usize,
+    ) -> Result<Vec<Message>, CoreError> {
+        let held_authors = uuids_in(held_authors.iter().map(|author_id| author_id.0));
+        let channel = match channel_id {
+            Some(channel_id) => uuid_match(channel_id.0),
+            None => doc! { "$nin": uuids_in(excluded_channels.iter().map(|channel_id| channel_id.0)) },
+        };
+        // `created_at` is an RFC3339 string in UTC, so it sorts chronologically
+        let filter = Self::not_deleted(doc! {
+            "created_at": { "$lt": before.to_rfc3339() },
+            "channel_id": channel,
+            "author_id": { "$nin": held_authors },
+        });
+
+        let mut cursor = self
+            .collection
+            .find(filter)
+            .sort(doc! { "created_at": 1 })
+            .limit(limit as i64)
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        let mut messages = Vec::new();
+        while let Some(document) = cursor
+            .try_next()
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?
+        {
+            messages.push(Message::try_from(document)?);
+        }
+
+        Ok(messages)
+    }
+
+    async fn channel_digest(
+        &self,
+        channel_id: &ChannelId,
+        since: DateTime<Utc>,
+        limit: u32,
+    ) -> Result<ChannelDigest, CoreError> {
+        // timestamps are RFC3339 strings, which compare in chronological order
+        let since_str = since.to_rfc3339();
+        let since_filter = doc! { "$gte": since_str.as_str() };
+        let limit = i64::from(limit);
+
+        let pipeline = vec![
+            doc! { "$match": Self::not_deleted(doc! {
+                "channel_id": uuid_match(channel_id.0),
+                "$or": [
+                    { "created_at": since_filter.clone() },
+                    { "pinned_at": since_filter.clone() },
+                    { "last_reply_at": since_filter.clone() },
+                ],
+            }) },
+            doc! { "$facet": {
+                "message_count": [
+                    { "$match": { "created_at": since_filter.clone() } },
+                    { "$count": "count" },
+                ],
+                "top_reacted": [
+                    { "$match": { "created_at": since_filter.clone() } },
+                    { "$lookup": {
+                        "from": "message_reactions",
+                        "localField": "_id",
+                        "foreignField": "message_id",
+                        "pipeline": [{ "$count": "count" }],
+                        "as": "reaction_total",
+                    } },
+                    { "$set": {
+                        "reaction_total": { "$ifNull": [{ "$first": "$reaction_total.count" }, 0] },
+                    } },
+                    { "$match": { "reaction_total": { "$gt": 0 } } },
+                    { "$sort": { "reaction_total": -1, "created_at": -1 } },
+                    { "$limit": limit },
+                ],
+                "new_pins": [
+                    { "$match": { "is_pinned": true, "pinned_at": since_filter.clone() } },
+                    { "$sort": { "pinned_at": -1 } },
+                    { "$limit": limit },
+                ],
+                "active_threads": [
+                    { "$match": { "last_reply_at": since_filter } },
+                    { "$sort": { "last_reply_at": -1 } },
+                    { "$limit": limit },
+                ],
+            } },
+        ];
+
+        let facets = self
+            .collection
+            .aggregate(pipeline)
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?
+            .try_next()
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?
+            .unwrap_or_default();
+
+        let message_count = facets
+            .get_array("message_count")
+            .ok()
+            .and_then(|counts| counts.first())
+            .and_then(Bson::as_document)
+            .and_then(|count| match count.get("count") {
+                Some(Bson::Int32(count)) => Some(*count as u64),
+                Some(Bson::Int64(count)) => Some(*count as u64),
+                _ => None,
+            })
+            .unwrap_or(0);
+
+        let mut top_reacted = Self::facet_messages(&facets, "top_reacted")?;
+        let mut new_pins = Self::facet_messages(&facets, "new_pins")?;
+        let mut active_threads = Self::facet_messages(&facets, "active_threads")?;
+        self.attach_reaction_counts(&mut top_reacted).await?;
+        self.attach_reaction_counts(&mut new_pins).await?;
+        self.attach_reaction_counts(&mut active_threads).await?;
+
+        Ok(ChannelDigest {
+            channel_id: *channel_id,
+            since,
+            message_count,
+            top_reacted,
+            new_pins,
+            active_threads,
+        })
+    }
+
+    async fn last_messages(&self, channel_ids: &[ChannelId]) -> Result<Vec<Message>, CoreError> {
+        if channel_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let pipeline = vec![
+            doc! { "$match": Self::not_deleted(doc! {
+                "channel_id": { "$in": uuids_in(channel_ids.iter().map(|channel_id| channel_id.0)) },
+            }) },
+            doc! { "$sort": { "channel_id": 1, "sequence": -1 } },
+            doc! { "$group": { "_id": "$channel_id", "message": { "$first": "$$ROOT" } } },
+            doc! { "$replaceRoot": { "newRoot": "$message" } },
+        ];
+
+        let mut cursor = self
+            .collection
+            .aggregate(pipeline)
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        // a channel stored in both UUID encodings is grouped twice: keep the newest
+        let mut last: HashMap<ChannelId, Message> = HashMap::new();
+        while let Some(document) = cursor
+            .try_next()
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?
+        {
+            let document: MessageDocument = from_document(document)
+                .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+            let message = Message::try_from(document)?;
+            let newer = last
+                .get(&message.channel_id)
+                .is_none_or(|newest| newest.sequence < message.sequence);
+            if newer {
+                last.insert(message.channel_id, message);
+            }
+        }
+
+        let mut messages: Vec<Message> = last.into_values().collect();
+        self.attach_reaction_counts(&mut messages).await?;
+        Ok(messages)
+    }
+
+    async fn most_active_channels(
+        &self,
+        since: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<ChannelId>, CoreError> {
+        let pipeline = vec![
+            doc! { "$match": Self::not_deleted(doc! {
+                "created_at": { "$gte": since.to_rfc3339() },
+            }) },
+            doc! { "$group": { "_id": "$channel_id", "count": { "$sum": 1 } } },
+            doc! { "$sort": { "count": -1, "_id": 1 } },
+            doc! { "$limit": limit as i64 },
+        ];
+
+        let mut cursor = self
+            .collection
+            .aggregate(pipeline)
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        let mut channels = Vec::new();
+        while let Some(group) = cursor
+            .try_next()
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?
+        {
+            match group.get("_id") {
+                Some(Bson::Binary(channel_id)) => {
+                    // a channel stored in both UUID encodings is grouped twice
+                    let channel_id = ChannelId::from(binary_to_uuid(channel_id)?);
+                    if !channels.contains(&channel_id) {
+                        channels.push(channel_id);
+                    }
+                }
+                _ => {
+                    return Err(CoreError::DatabaseError {
+                        msg: "channel activity group without channel id".to_string(),
+                    });
+                }
+            }
+        }
+
+        Ok(channels)
+    }
+}
+
+/// Messages fetched per round trip when streaming a channel
+const EXPORT_BATCH_SIZE: usize = 500;
+
+/// Server error code of duplicate key errors
+const DUPLICATE_KEY: i32 = 11000;
+
+fn is_duplicate_key(kind: &ErrorKind) -> bool {
+    matches!(kind, ErrorKind::Write(WriteFailure::WriteError(error)) if error.code == DUPLICATE_KEY)
+}
+
+/// Server error code of operations a standalone server refuses, among which
+/// transactions
+const ILLEGAL_OPERATION: i32 = 20;
+
+/// Message of the [`ILLEGAL_OPERATION`] a standalone server answers to transactions
+const TRANSACTIONS_UNSUPPORTED: &str =
+    "Transaction numbers are only allowed on a replica set member or mongos";
+
+/// Times a transaction, or its commit, is run when MongoDB asks to retry it
+const MAX_TRANSACTION_ATTEMPTS: u32 = 5;
+
+/// Whether the server refused a transaction for running standalone; other
+/// transaction errors, such as aborts and conflicts, leave transactions enabled
+fn is_transactions_unsupported(kind: &ErrorKind) -> bool {
+    match kind {
+        ErrorKind::Command(error) => {
+            error.code == ILLEGAL_OPERATION && error.message.contains(TRANSACTIONS_UNSUPPORTED)
+        }
+        _ => false,
+    }
+}
@@ -0,0 +1,76 @@
+//! Events written to the outbox for other services in MongoDB
+
+use super::MongoMessageRepository;
+use crate::{
+    domain::{
+        common::CoreError,
+        message::{
+            entities::{
+                AbuseDetectedEvent, AttachmentQuarantinedEvent, MessageMentionedEvent,
+                NotificationBroadcastEvent, NotificationFanoutEvent, NotificationRequestedEvent,
+            },
+            ports::{NotificationPublisher, TrustSafetyPublisher},
+        },
+    },
+    infrastructure::outbox::{OutboxEventRecord, write_outbox_event_with_policy},
+};
+
+#[async_trait::async_trait]
+impl NotificationPublisher for MongoMessageRepository {
+    async fn request_notification(
+        &self,
+        event: &NotificationRequestedEvent,
+    ) -> Result<(), CoreError> {
+        let event =
+            OutboxEventRecord::new(self.routing.notification_requested.clone(), event.clone());
+        write_outbox_event_with_policy(&self.db, &event, self.outbox_encryption.as_ref()).await?;
+
+        Ok(())
+    }
+
+    async fn request_notification_fanout(
+        &self,
+        event: &NotificationFanoutEvent,
+    ) -> Result<(), CoreError> {
+        let event = OutboxEventRecord::new(self.routing.notification_fanout.clone(), event.clone());
+        write_outbox_event_with_policy(&self.db, &event, self.outbox_encryption.as_ref()).await?;
+
+        Ok(())
+    }
+
+    async fn request_notification_broadcast(
+        &self,
+        event: &NotificationBroadcastEvent,
+    ) -> Result<(), CoreError> {
+        let event =
+            OutboxEventRecord::new(self.routing.notification_broadcast.clone(), event.clone());
+        write_outbox_event_with_policy(&self.db, &event, self.outbox_encryption.as_ref()).await?;
+
+        Ok(())
+    }
+
+    async fn publish_mention(&self, event: &MessageMentionedEvent) -> Result<(), CoreError> {
+        let event = OutboxEventRecord::new(self.routing.message_mentioned.clone(), event.clone());
+        write_outbox_event_with_policy(&self.db, &event, self.outbox_encryption.as_ref()).await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl TrustSafetyPublisher for MongoMessageRepository {
+    async fn report_abuse(&self, event: &AbuseDetectedEvent) -> Result<(), CoreError> {
+        let event = OutboxEventRecord::new(self.routing.abuse_detected.clone(), event.clone());
+        write_outbox_event_with_policy(&self.db, &event, self.outbox_encryption.as_ref()).await?;
+
+        Ok(())
+    }
+
+    async fn report_quarantine(&self, event: &AttachmentQuarantinedEvent) -> Result<(), CoreError> {
+        let event =
+            OutboxEventRecord::new(self.routing.attachment_quarantined.clone(), event.clone());
+        write_outbox_event_with_policy(&self.db, &event, self.outbox_encryption.as_ref()).await?;
+
+        Ok(())
+    }
+}
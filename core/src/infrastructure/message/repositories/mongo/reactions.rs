@@ -0,0 +1,184 @@
+//! Reactions and reaction notification mutes in MongoDB
+
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{Bson, doc},
+    options::{FindOneAndUpdateOptions, ReturnDocument},
+};
+
+use super::MongoMessageRepository;
+use crate::{
+    domain::{
+        common::{CoreError, Cursor, CursorPage, GetCursorPaginated},
+        message::{
+            entities::{AddReactionInput, MessageId, Reaction, UserId},
+            ports::ReactionRepository,
+        },
+    },
+    infrastructure::message::{
+        dto::uuid_to_binary,
+        encoding::{uuid_match, uuids_in},
+    },
+};
+
+#[async_trait::async_trait]
+impl ReactionRepository for MongoMessageRepository {
+    async fn add_reaction(&self, input: AddReactionInput) -> Result<Reaction, CoreError> {
+        let filter = Self::reaction_filter(&input.message_id, &input.user_id, &input.emoji);
+
+        // Upsert so that reacting twice with the same emoji is idempotent
+        let options = FindOneAndUpdateOptions::builder()
+            .upsert(true)
+            .return_document(ReturnDocument::After)
+            .build();
+
+        let document = self
+            .reactions
+            .find_one_and_update(
+                filter,
+                doc! { "$setOnInsert": Self::with_created_at(doc! {
+                    "message_id": uuid_to_binary(input.message_id.0),
+                    "user_id": uuid_to_binary(input.user_id.0),
+                }) },
+            )
+            .with_options(options)
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?
+            .ok_or_else(|| CoreError::DatabaseError {
+                msg: "reaction upsert returned no document".to_string(),
+            })?;
+
+        Reaction::try_from(document)
+    }
+
+    async fn remove_reaction(
+        &self,
+        message_id: &MessageId,
+        user_id: &UserId,
+        emoji: &str,
+    ) -> Result<(), CoreError> {
+        let result = self
+            .reactions
+            .delete_one(Self::reaction_filter(message_id, user_id, emoji))
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        if result.deleted_count == 0 {
+            return Err(CoreError::ReactionNotFound {
+                message_id: *message_id,
+                emoji: emoji.to_string(),
+            });
+        }
+
+        Ok(())
+    }
+
+    async fn list_reactions(&self, message_id: &MessageId) -> Result<Vec<Reaction>, CoreError> {
+        let mut cursor = self
+            .reactions
+            .find(doc! { "message_id": uuid_match(message_id.0) })
+            .sort(doc! { "created_at": 1 })
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        let mut reactions = Vec::new();
+        while let Some(document) = cursor
+            .try_next()
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?
+        {
+            reactions.push(Reaction::try_from(document)?);
+        }
+
+        Ok(reactions)
+    }
+
+    async fn list_reaction_users(
+        &self,
+        message_id: &MessageId,
+        emoji: &str,
+        pagination: &GetCursorPaginated,
+    ) -> Result<CursorPage<Reaction>, CoreError> {
+        let cursor = pagination.decoded_cursor()?;
+        let limit = pagination.effective_limit();
+
+        let filter = Self::after_cursor(
+            doc! { "message_id": uuid_match(message_id.0), "emoji": emoji },
+            cursor,
+            "user_id",
+        );
+
+        let mut cursor = self
+            .reactions
+            .find(filter)
+            .sort(doc! { "created_at": 1, "user_id": 1 })
+            .limit(limit as i64 + 1)
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        let mut reactions = Vec::new();
+        while let Some(document) = cursor
+            .try_next()
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?
+        {
+            reactions.push(Reaction::try_from(document)?);
+        }
+
+        Ok(CursorPage::from_overfetched(reactions, limit, |r| {
+            Cursor::new(r.created_at, r.user_id.0)
+        }))
+    }
+
+    async fn set_reaction_notifications_muted(
+        &self,
+        user_id: &UserId,
+        message_id: Option<&MessageId>,
+        muted: bool,
+    ) -> Result<(), CoreError> {
+        let filter = Self::reaction_mute_filter(user_id, message_id);
+
+        if muted {
+            self.reaction_mutes
+                .update_one(
+                    filter,
+                    doc! { "$setOnInsert": Self::with_created_at(
+                        Self::reaction_mute_fields(user_id, message_id),
+                    ) },
+                )
+                .upsert(true)
+                .await
+                .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+        } else {
+            self.reaction_mutes
+                .delete_one(filter)
+                .await
+                .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+        }
+
+        Ok(())
+    }
+
+    async fn reaction_notifications_muted(
+        &self,
+        user_id: &UserId,
+        message_id: &MessageId,
+    ) -> Result<bool, CoreError> {
+        // global mutes have no message
+        let mut message_ids = vec![Bson::Null];
+        message_ids.extend(uuids_in([message_id.0]));
+        let filter = doc! {
+            "user_id": uuid_match(user_id.0),
+            "message_id": { "$in": message_ids },
+        };
+
+        let muted = self
+            .reaction_mutes
+            .count_documents(filter)
+            .limit(1)
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        Ok(muted > 0)
+    }
+}
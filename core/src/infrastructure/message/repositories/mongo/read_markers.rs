@@ -0,0 +1,147 @@
+//! Read markers and unread counts in MongoDB
+
+use std::collections::HashMap;
+
+use futures::TryStreamExt;
+use mongodb::bson::{Bson, Document, doc};
+
+use super::{MongoMessageRepository, is_duplicate_key};
+use crate::{
+    domain::{
+        common::CoreError,
+        message::{
+            entities::{ChannelId, ReadMarker, UserId},
+            ports::ReadMarkerRepository,
+        },
+    },
+    infrastructure::message::{
+        dto::{binary_to_uuid, uuid_to_binary},
+        encoding::uuid_match,
+    },
+};
+
+#[async_trait::async_trait]
+impl ReadMarkerRepository for MongoMessageRepository {
+    async fn read_marker(
+        &self,
+        user_id: &UserId,
+        channel_id: &ChannelId,
+    ) -> Result<Option<ReadMarker>, CoreError> {
+        self.read_markers
+            .find_one(doc! { "_id": Self::read_marker_id(user_id, channel_id) })
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?
+            .map(|document| Self::read_marker_from(&document))
+            .transpose()
+    }
+
+    async fn set_read_marker(&self, marker: &ReadMarker) -> Result<ReadMarker, CoreError> {
+        let id = Self::read_marker_id(&marker.user_id, &marker.channel_id);
+        let sequence = marker.last_read_sequence as i64;
+        let result = self
+            .read_markers
+            .update_one(
+                doc! { "_id": &id, "sequence": { "$lt": sequence } },
+                doc! { "$set": {
+                    "user_id": uuid_to_binary(marker.user_id.0),
+                    "channel_id": uuid_to_binary(marker.channel_id.0),
+                    "message_id": uuid_to_binary(marker.last_read_message_id.0),
+                    "sequence": sequence,
+                    "read_at": marker.read_at.to_rfc3339(),
+                } },
+            )
+            .upsert(true)
+            .await;
+
+        match result {
+            Ok(_) => Ok(marker.clone()),
+            // The user read a later message already: keep its marker
+            Err(e) if is_duplicate_key(&e.kind) => self
+                .read_marker(&marker.user_id, &marker.channel_id)
+                .await?
+                .ok_or_else(|| CoreError::DatabaseError {
+                    msg: "read marker missing after a conflicting upsert".to_string(),
+                }),
+            Err(e) => Err(CoreError::DatabaseError { msg: e.to_string() }),
+        }
+    }
+
+    async fn delete_read_markers_by_user(&self, user_id: &UserId) -> Result<(), CoreError> {
+        self.read_markers
+            .delete_many(doc! { "user_id": uuid_match(user_id.0) })
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        Ok(())
+    }
+
+    async fn read_markers_of_user(&self, user_id: &UserId) -> Result<Vec<ReadMarker>, CoreError> {
+        let mut cursor = self
+            .read_markers
+            .find(doc! { "user_id": uuid_match(user_id.0) })
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        let mut markers = Vec::new();
+        while let Some(document) = cursor
+            .try_next()
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?
+        {
+            markers.push(Self::read_marker_from(&document)?);
+        }
+        Ok(markers)
+    }
+
+    async fn count_unread(
+        &self,
+        markers: &[ReadMarker],
+    ) -> Result<HashMap<ChannelId, u64>, CoreError> {
+        if markers.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let after_markers: Vec<Document> = markers
+            .iter()
+            .map(|marker| {
+                doc! {
+                    "channel_id": uuid_match(marker.channel_id.0),
+                    "sequence": { "$gt": marker.last_read_sequence as i64 },
+                }
+            })
+            .collect();
+        let pipeline = vec![
+            doc! { "$match": Self::not_deleted(doc! { "$or": after_markers }) },
+            doc! { "$group": { "_id": "$channel_id", "count": { "$sum": 1 } } },
+        ];
+
+        let mut cursor = self
+            .collection
+            .aggregate(pipeline)
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        let mut counts = HashMap::new();
+        while let Some(group) = cursor
+            .try_next()
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?
+        {
+            let channel_id = match group.get("_id") {
+                Some(Bson::Binary(channel_id)) => ChannelId::from(binary_to_uuid(channel_id)?),
+                _ => {
+                    return Err(CoreError::DatabaseError {
+                        msg: "unread group without channel id".to_string(),
+                    });
+                }
+            };
+            let count = match group.get("count") {
+                Some(Bson::Int32(count)) => *count as u64,
+                Some(Bson::Int64(count)) => *count as u64,
+                _ => 0,
+            };
+            // a channel stored in both UUID encodings is grouped twice
+            *counts.entry(channel_id).or_insert(0) += count;
+        }
+        Ok(counts)
+    }
+}
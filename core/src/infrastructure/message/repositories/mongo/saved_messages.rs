@@ -0,0 +1,116 @@
+//! Saved messages in MongoDB
+
+use std::collections::HashMap;
+
+use futures::TryStreamExt;
+use mongodb::{bson::doc, options::ReturnDocument};
+
+use super::MongoMessageRepository;
+use crate::{
+    domain::{
+        common::{CoreError, GetPaginated, TotalPaginatedElements},
+        message::{
+            entities::{Message, MessageId, SavedMessage, UserId},
+            ports::{MessageRepository, SavedMessageRepository},
+        },
+    },
+    infrastructure::message::{dto::uuid_to_binary, encoding::uuid_match},
+};
+
+#[async_trait::async_trait]
+impl SavedMessageRepository for MongoMessageRepository {
+    async fn save_message(&self, saved: &SavedMessage) -> Result<SavedMessage, CoreError> {
+        let filter = doc! {
+            "user_id": uuid_match(saved.user_id.0),
+            "message_id": uuid_match(saved.message_id.0),
+        };
+        let fields = doc! {
+            "user_id": uuid_to_binary(saved.user_id.0),
+            "message_id": uuid_to_binary(saved.message_id.0),
+            "channel_id": uuid_to_binary(saved.channel_id.0),
+            "created_at": saved.saved_at.to_rfc3339(),
+        };
+        let document = self
+            .saved_messages
+            .find_one_and_update(filter, doc! { "$setOnInsert": fields })
+            .upsert(true)
+            .return_document(ReturnDocument::After)
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?
+            .ok_or_else(|| CoreError::DatabaseError {
+                msg: "saved message missing after upsert".to_string(),
+            })?;
+
+        Self::saved_message_from(&document)
+    }
+
+    async fn unsave_message(
+        &self,
+        user_id: &UserId,
+        message_id: &MessageId,
+    ) -> Result<(), CoreError> {
+        self.saved_messages
+            .delete_one(doc! {
+                "user_id": uuid_match(user_id.0),
+                "message_id": uuid_match(message_id.0),
+            })
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        Ok(())
+    }
+
+    async fn list_saved_messages(
+        &self,
+        user_id: &UserId,
+        pagination: &GetPaginated,
+    ) -> Result<(Vec<Message>, TotalPaginatedElements), CoreError> {
+        let filter = doc! { "user_id": uuid_match(user_id.0) };
+        let total = self
+            .saved_messages
+            .count_documents(filter.clone())
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        // `created_at` is an RFC3339 string in UTC, so it sorts chronologically
+        let sort = doc! { "created_at": -1, "_id": -1 };
+        let mut cursor = self
+            .saved_messages
+            .find(filter)
+            .with_options(Self::pagination_options(pagination, sort))
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        let mut message_ids = Vec::new();
+        while let Some(document) = cursor
+            .try_next()
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?
+        {
+            message_ids.push(Self::saved_message_from(&document)?.message_id);
+        }
+
+        // keep the order in which they were saved
+        let mut messages: HashMap<MessageId, Message> = self
+            .find_by_ids(&message_ids)
+            .await?
+            .into_iter()
+            .map(|message| (message.id, message))
+            .collect();
+        let messages = message_ids
+            .iter()
+            .filter_map(|id| messages.remove(id))
+            .collect();
+
+        Ok((messages, total))
+    }
+
+    async fn delete_saved_messages_by_user(&self, user_id: &UserId) -> Result<(), CoreError> {
+        self.saved_messages
+            .delete_many(doc! { "user_id": uuid_match(user_id.0) })
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        Ok(())
+    }
+}
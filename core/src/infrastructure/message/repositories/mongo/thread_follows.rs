@@ -0,0 +1,171 @@
+//! Thread follows and thread preferences in MongoDB
+
+use std::collections::HashMap;
+
+use futures::TryStreamExt;
+use mongodb::{bson::doc, options::ReturnDocument};
+
+use super::MongoMessageRepository;
+use crate::{
+    domain::{
+        common::{CoreError, GetPaginated, TotalPaginatedElements},
+        message::{
+            entities::{Message, MessageId, ThreadFollow, ThreadPreferences, UserId},
+            ports::{MessageRepository, ThreadFollowRepository},
+        },
+    },
+    infrastructure::message::{dto::uuid_to_binary, encoding::uuid_match},
+};
+
+#[async_trait::async_trait]
+impl ThreadFollowRepository for MongoMessageRepository {
+    async fn follow_thread(&self, follow: &ThreadFollow) -> Result<ThreadFollow, CoreError> {
+        let filter = doc! {
+            "user_id": uuid_match(follow.user_id.0),
+            "message_id": uuid_match(follow.thread_id.0),
+        };
+        let fields = doc! {
+            "user_id": uuid_to_binary(follow.user_id.0),
+            "message_id": uuid_to_binary(follow.thread_id.0),
+            "channel_id": uuid_to_binary(follow.channel_id.0),
+            "created_at": follow.followed_at.to_rfc3339(),
+        };
+        let document = self
+            .thread_follows
+            .find_one_and_update(filter, doc! { "$setOnInsert": fields })
+            .upsert(true)
+            .return_document(ReturnDocument::After)
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?
+            .ok_or_else(|| CoreError::DatabaseError {
+                msg: "thread follow missing after upsert".to_string(),
+            })?;
+
+        Self::thread_follow_from(&document)
+    }
+
+    async fn unfollow_thread(
+        &self,
+        user_id: &UserId,
+        thread_id: &MessageId,
+    ) -> Result<(), CoreError> {
+        self.thread_follows
+            .delete_one(doc! {
+                "user_id": uuid_match(user_id.0),
+                "message_id": uuid_match(thread_id.0),
+            })
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        Ok(())
+    }
+
+    async fn list_thread_followers(&self, thread_id: &MessageId) -> Result<Vec<UserId>, CoreError> {
+        let mut cursor = self
+            .thread_follows
+            .find(doc! { "message_id": uuid_match(thread_id.0) })
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        let mut followers = Vec::new();
+        while let Some(document) = cursor
+            .try_next()
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?
+        {
+            followers.push(Self::thread_follow_from(&document)?.user_id);
+        }
+
+        Ok(followers)
+    }
+
+    async fn list_followed_threads(
+        &self,
+        user_id: &UserId,
+        pagination: &GetPaginated,
+    ) -> Result<(Vec<Message>, TotalPaginatedElements), CoreError> {
+        let filter = doc! { "user_id": uuid_match(user_id.0) };
+        let total = self
+            .thread_follows
+            .count_documents(filter.clone())
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        // `created_at` is an RFC3339 string in UTC, so it sorts chronologically
+        let sort = doc! { "created_at": -1, "_id": -1 };
+        let mut cursor = self
+            .thread_follows
+            .find(filter)
+            .with_options(Self::pagination_options(pagination, sort))
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        let mut thread_ids = Vec::new();
+        while let Some(document) = cursor
+            .try_next()
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?
+        {
+            thread_ids.push(Self::thread_follow_from(&document)?.thread_id);
+        }
+
+        // keep the order of the follows
+        let mut threads: HashMap<MessageId, Message> = self
+            .find_by_ids(&thread_ids)
+            .await?
+            .into_iter()
+            .map(|message| (message.id, message))
+            .collect();
+        let threads = thread_ids
+            .iter()
+            .filter_map(|id| threads.remove(id))
+            .collect();
+
+        Ok((threads, total))
+    }
+
+    async fn thread_preferences(&self, user_id: &UserId) -> Result<ThreadPreferences, CoreError> {
+        let document = self
+            .thread_preferences
+            .find_one(doc! { "_id": uuid_to_binary(user_id.0) })
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        let defaults = ThreadPreferences::defaults(*user_id);
+        Ok(ThreadPreferences {
+            auto_follow_on_reply: document
+                .and_then(|document| document.get_bool("auto_follow_on_reply").ok())
+                .unwrap_or(defaults.auto_follow_on_reply),
+            ..defaults
+        })
+    }
+
+    async fn set_thread_preferences(
+        &self,
+        preferences: &ThreadPreferences,
+    ) -> Result<(), CoreError> {
+        self.thread_preferences
+            .update_one(
+                doc! { "_id": uuid_to_binary(preferences.user_id.0) },
+                doc! { "$set": { "auto_follow_on_reply": preferences.auto_follow_on_reply } },
+            )
+            .upsert(true)
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        Ok(())
+    }
+
+    async fn delete_thread_follows_by_user(&self, user_id: &UserId) -> Result<(), CoreError> {
+        self.thread_follows
+            .delete_many(doc! { "user_id": uuid_match(user_id.0) })
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+        self.thread_preferences
+            .delete_one(doc! { "_id": uuid_to_binary(user_id.0) })
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        Ok(())
+    }
+}
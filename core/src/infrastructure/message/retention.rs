@@ -0,0 +1,116 @@
+//! Deletion of the messages kept longer than the retention of their channel.
+//!
+//! Channels keep their messages for the default retention unless they have a
+//! custom one. Expired messages are soft deleted, so the purge removes them
+//! for good later on like any deleted message.
+
+use std::{sync::Arc, time::Duration};
+
+use tokio::{task::JoinHandle, time::MissedTickBehavior};
+
+use crate::{
+    domain::{common::CoreError, lease::ports::LeaseLock, message::ports::MessageService},
+    infrastructure::lease::SingletonJob,
+};
+
+/// Name of the lease held by the replica deleting expired messages
+pub const MESSAGE_RETENTION_LEASE: &str = "message-retention";
+
+#[derive(Debug, Clone, Copy)]
+pub struct MessageRetentionConfig {
+    /// How long the messages of channels without a custom retention are
+    /// kept, forever when `None`
+    pub default_retention: Option<Duration>,
+    /// Wait between two runs
+    pub interval: Duration,
+    /// Messages deleted per repository round trip
+    pub batch_size: usize,
+}
+
+impl Default for MessageRetentionConfig {
+    fn default() -> Self {
+        Self {
+            default_retention: None,
+            interval: Duration::from_secs(60 * 60),
+            batch_size: 500,
+        }
+    }
+}
+
+/// Periodically deletes the messages past the retention of their channel
+pub struct MessageRetention<S>
+where
+    S: MessageService,
+{
+    service: S,
+    config: MessageRetentionConfig,
+    leader: Option<SingletonJob>,
+}
+
+impl<S> MessageRetention<S>
+where
+    S: MessageService + 'static,
+{
+    pub fn new(service: S, config: MessageRetentionConfig) -> Self {
+        Self {
+            service,
+            config,
+            leader: None,
+        }
+    }
+
+    /// Delete from the replica holding the [`MESSAGE_RETENTION_LEASE`] only
+    pub fn with_leader(mut self, lock: Arc<dyn LeaseLock>) -> Self {
+        self.leader = Some(SingletonJob::new(
+            lock,
+            MESSAGE_RETENTION_LEASE,
+            self.config.interval.saturating_mul(2),
+        ));
+        self
+    }
+
+    /// Delete every expired message and return how many were deleted
+    pub async fn delete_once(&self) -> Result<u64, CoreError> {
+        // a retention beyond the representable durations keeps everything
+        let default_retention = self
+            .config
+            .default_retention
+            .and_then(|retention| chrono::Duration::from_std(retention).ok());
+
+        let deleted = self
+            .service
+            .delete_expired_messages(default_retention, self.config.batch_size)
+            .await?;
+        if deleted > 0 {
+            tracing::info!(deleted, "deleted messages past their retention");
+        }
+        Ok(deleted)
+    }
+
+    /// Delete every `interval` in the background
+    pub fn spawn(mut self) -> JoinHandle<()> {
+        let interval = self.config.interval;
+        let leader = self.leader.take();
+        let retention = Arc::new(self);
+        let run = move || {
+            let retention = retention.clone();
+            async move {
+                if let Err(e) = retention.delete_once().await {
+                    tracing::warn!(error = %e, "failed to delete expired messages");
+                }
+            }
+        };
+
+        match leader {
+            Some(leader) => leader.spawn_every(interval, run),
+            None => tokio::spawn(async move {
+                let mut ticker = tokio::time::interval(interval);
+                ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+                loop {
+                    ticker.tick().await;
+                    run().await;
+                }
+            }),
+        }
+    }
+}
@@ -0,0 +1,126 @@
+//! Archiving of the threads nobody replied to for a while.
+//!
+//! Archived threads are left out of the default thread listings, and the next
+//! reply unarchives them. Both are announced through the outbox.
+
+use std::{sync::Arc, time::Duration};
+
+use chrono::Utc;
+use tokio::{task::JoinHandle, time::MissedTickBehavior};
+
+use crate::{
+    domain::{common::CoreError, lease::ports::LeaseLock, message::ports::MessageService},
+    infrastructure::lease::SingletonJob,
+};
+
+/// Name of the lease held by the replica archiving idle threads
+pub const THREAD_ARCHIVE_LEASE: &str = "thread-archive";
+
+#[derive(Debug, Clone, Copy)]
+pub struct ThreadArchiveConfig {
+    /// How long a thread stays without a reply before being archived
+    pub idle: Duration,
+    /// Wait between two archiving runs
+    pub interval: Duration,
+    /// Threads archived per repository round trip
+    pub batch_size: usize,
+}
+
+impl Default for ThreadArchiveConfig {
+    fn default() -> Self {
+        Self {
+            idle: Duration::from_secs(7 * 24 * 60 * 60),
+            interval: Duration::from_secs(60 * 60),
+            batch_size: 100,
+        }
+    }
+}
+
+/// Periodically archives the threads without a reply for `idle`
+pub struct ThreadArchive<S>
+where
+    S: MessageService,
+{
+    service: S,
+    config: ThreadArchiveConfig,
+    leader: Option<SingletonJob>,
+}
+
+impl<S> ThreadArchive<S>
+where
+    S: MessageService + 'static,
+{
+    pub fn new(service: S, config: ThreadArchiveConfig) -> Self {
+        Self {
+            service,
+            config,
+            leader: None,
+        }
+    }
+
+    /// Archive from the replica holding the [`THREAD_ARCHIVE_LEASE`] only
+    pub fn with_leader(mut self, lock: Arc<dyn LeaseLock>) -> Self {
+        self.leader = Some(SingletonJob::new(
+            lock,
+            THREAD_ARCHIVE_LEASE,
+            self.config.interval.saturating_mul(2),
+        ));
+        self
+    }
+
+    /// Archive every idle thread, batch after batch, and return how many were archived
+    pub async fn archive_once(&self) -> Result<u64, CoreError> {
+        // an idle period beyond the representable dates archives nothing
+        let Some(idle_before) = chrono::Duration::from_std(self.config.idle)
+            .ok()
+            .and_then(|idle| Utc::now().checked_sub_signed(idle))
+        else {
+            return Ok(0);
+        };
+        let batch_size = self.config.batch_size.max(1);
+
+        let mut archived = 0;
+        loop {
+            let batch = self
+                .service
+                .archive_idle_threads(idle_before, batch_size)
+                .await?;
+            archived += batch;
+            if batch < batch_size as u64 {
+                break;
+            }
+        }
+
+        if archived > 0 {
+            tracing::info!(archived, "archived idle threads");
+        }
+        Ok(archived)
+    }
+
+    /// Archive every `interval` in the background
+    pub fn spawn(mut self) -> JoinHandle<()> {
+        let interval = self.config.interval;
+        let leader = self.leader.take();
+        let archive = Arc::new(self);
+        let run = move || {
+            let archive = archive.clone();
+            async move {
+                if let Err(e) = archive.archive_once().await {
+                    tracing::warn!(error = %e, "failed to archive idle threads");
+                }
+            }
+        };
+
+        match leader {
+            Some(leader) => leader.spawn_every(interval, run),
+            None => tokio::spawn(async move {
+                let mut ticker = tokio::time::interval(interval);
+                ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+                loop {
+                    ticker.tick().await;
+                    run().await;
+                }
+            }),
+        }
+    }
+}
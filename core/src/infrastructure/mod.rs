@@ -1,6 +1,12 @@
+pub mod archive;
+pub mod attachment;
 pub mod health;
+pub mod lease;
+pub mod link_preview;
+pub mod membership;
 pub mod message;
 pub mod outbox;
+pub mod search;
 
 pub use outbox::MessageRoutingInfo;
 pub use outbox::write_outbox_event;
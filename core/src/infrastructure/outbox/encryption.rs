@@ -0,0 +1,243 @@
+//! Envelope encryption of outbox payloads.
+//!
+//! Each record is encrypted with its own data key (AES-256-GCM). The data key
+//! itself is only stored encrypted by a key management service, next to the
+//! ciphertext, so rotating or revoking the master key never requires touching
+//! the outbox records. The payload ciphertext is bound to the `_id` of its
+//! record as associated data, so it cannot be moved to another record.
+
+use std::sync::Arc;
+
+use aes_gcm::{
+    Aes256Gcm, Key, Nonce,
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+};
+use mongodb::bson::{Binary, Bson, Document, doc, spec::BinarySubtype};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::domain::common::CoreError;
+
+const ALGORITHM: &str = "AES-256-GCM";
+
+/// Field wrapping the payload in the encrypted BSON document
+const PAYLOAD_FIELD: &str = "payload";
+
+/// Data key generated by a [`KeyManagementService`]
+pub struct DataKey {
+    pub key_id: String,
+    /// 32 bytes, never persisted
+    pub plaintext: Vec<u8>,
+    /// `plaintext` encrypted under the master key `key_id`
+    pub encrypted: Vec<u8>,
+}
+
+/// Port to the service holding the master keys (AWS KMS, Vault transit, ...)
+#[async_trait::async_trait]
+pub trait KeyManagementService: Send + Sync {
+    async fn generate_data_key(&self) -> Result<DataKey, CoreError>;
+    async fn decrypt_data_key(&self, key_id: &str, encrypted: &[u8]) -> Result<Vec<u8>, CoreError>;
+}
+
+/// Key management backed by a single master key held in memory, for
+/// deployments without a KMS and for tests
+pub struct LocalKeyManagementService {
+    key_id: String,
+    master_key: Aes256Gcm,
+}
+
+impl LocalKeyManagementService {
+    pub fn new(key_id: impl Into<String>, master_key: &[u8; 32]) -> Self {
+        Self {
+            key_id: key_id.into(),
+            master_key: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(master_key)),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl KeyManagementService for LocalKeyManagementService {
+    async fn generate_data_key(&self) -> Result<DataKey, CoreError> {
+        let plaintext = Aes256Gcm::generate_key(OsRng).to_vec();
+        let nonce = Aes256Gcm::generate_nonce(OsRng);
+        let mut encrypted = nonce.to_vec();
+        encrypted.extend(encrypt(&self.master_key, &nonce, &plaintext, &[])?);
+
+        Ok(DataKey {
+            key_id: self.key_id.clone(),
+            plaintext,
+            encrypted,
+        })
+    }
+
+    async fn decrypt_data_key(&self, key_id: &str, encrypted: &[u8]) -> Result<Vec<u8>, CoreError> {
+        if key_id != self.key_id {
+            return Err(CoreError::EncryptionError {
+                msg: format!("unknown master key {key_id}"),
+            });
+        }
+        if encrypted.len() < NONCE_LEN {
+            return Err(CoreError::EncryptionError {
+                msg: "truncated data key".to_string(),
+            });
+        }
+        let (nonce, ciphertext) = encrypted.split_at(NONCE_LEN);
+
+        decrypt(&self.master_key, Nonce::from_slice(nonce), ciphertext, &[])
+    }
+}
+
+/// Length of AES-GCM nonces
+const NONCE_LEN: usize = 12;
+
+/// Encryption metadata stored alongside an encrypted payload
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EncryptionEnvelope {
+    pub algorithm: String,
+    pub key_id: String,
+    pub encrypted_key: Binary,
+    pub nonce: Binary,
+    /// Whether the `_id` of the record is the associated data of the
+    /// ciphertext; unset on records sealed before payloads were bound to it
+    #[serde(default)]
+    pub bound_to_record: bool,
+}
+
+/// Encrypts outbox payloads before they are written, and decrypts them for the relay
+#[derive(Clone)]
+pub struct OutboxEncryption {
+    kms: Arc<dyn KeyManagementService>,
+}
+
+impl OutboxEncryption {
+    pub fn new(kms: Arc<dyn KeyManagementService>) -> Self {
+        Self { kms }
+    }
+
+    /// Encrypt the payload of the record `record_id`, returning the ciphertext
+    /// (as a binary) and its envelope
+    pub async fn seal(
+        &self,
+        payload: &Bson,
+        record_id: Uuid,
+    ) -> Result<(Bson, EncryptionEnvelope), CoreError> {
+        // Payloads are not always documents, so wrap them to encode them as BSON
+        let plaintext = mongodb::bson::to_vec(&doc! { PAYLOAD_FIELD: payload.clone() })
+            .map_err(|e| CoreError::SerializationError { msg: e.to_string() })?;
+
+        let data_key = self.kms.generate_data_key().await?;
+        let cipher = data_cipher(&data_key.plaintext)?;
+        let nonce = Aes256Gcm::generate_nonce(OsRng);
+        let ciphertext = encrypt(&cipher, &nonce, &plaintext, record_id.as_bytes())?;
+
+        let envelope = EncryptionEnvelope {
+            algorithm: ALGORITHM.to_string(),
+            key_id: data_key.key_id,
+            encrypted_key: binary(data_key.encrypted),
+            nonce: binary(nonce.to_vec()),
+            bound_to_record: true,
+        };
+
+        Ok((Bson::Binary(binary(ciphertext)), envelope))
+    }
+
+    /// Decrypt a payload sealed by [`OutboxEncryption::seal`] for the record `record_id`
+    pub async fn open(
+        &self,
+        payload: &Bson,
+        envelope: &EncryptionEnvelope,
+        record_id: Uuid,
+    ) -> Result<Bson, CoreError> {
+        if envelope.algorithm != ALGORITHM {
+            return Err(CoreError::EncryptionError {
+                msg: format!("unsupported algorithm {}", envelope.algorithm),
+            });
+        }
+        let Bson::Binary(ciphertext) = payload else {
+            return Err(CoreError::EncryptionError {
+                msg: "encrypted payload is not a binary".to_string(),
+            });
+        };
+        if envelope.nonce.bytes.len() != NONCE_LEN {
+            return Err(CoreError::EncryptionError {
+                msg: "invalid nonce".to_string(),
+            });
+        }
+
+        let data_key = self
+            .kms
+            .decrypt_data_key(&envelope.key_id, &envelope.encrypted_key.bytes)
+            .await?;
+        let cipher = data_cipher(&data_key)?;
+        let aad: &[u8] = if envelope.bound_to_record {
+            record_id.as_bytes()
+        } else {
+            &[]
+        };
+        let plaintext = decrypt(
+            &cipher,
+            Nonce::from_slice(&envelope.nonce.bytes),
+            &ciphertext.bytes,
+            aad,
+        )?;
+
+        let mut wrapper: Document = mongodb::bson::from_slice(&plaintext)
+            .map_err(|e| CoreError::SerializationError { msg: e.to_string() })?;
+        wrapper
+            .remove(PAYLOAD_FIELD)
+            .ok_or_else(|| CoreError::SerializationError {
+                msg: "decrypted payload is empty".to_string(),
+            })
+    }
+}
+
+fn binary(bytes: Vec<u8>) -> Binary {
+    Binary {
+        subtype: BinarySubtype::Generic,
+        bytes,
+    }
+}
+
+fn data_cipher(key: &[u8]) -> Result<Aes256Gcm, CoreError> {
+    Aes256Gcm::new_from_slice(key).map_err(|_| CoreError::EncryptionError {
+        msg: "invalid data key length".to_string(),
+    })
+}
+
+fn encrypt(
+    cipher: &Aes256Gcm,
+    nonce: &Nonce<<Aes256Gcm as AeadCore>::NonceSize>,
+    plaintext: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>, CoreError> {
+    cipher
+        .encrypt(
+            nonce,
+            Payload {
+                msg: plaintext,
+                aad,
+            },
+        )
+        .map_err(|_| CoreError::EncryptionError {
+            msg: "encryption failed".to_string(),
+        })
+}
+
+fn decrypt(
+    cipher: &Aes256Gcm,
+    nonce: &Nonce<<Aes256Gcm as AeadCore>::NonceSize>,
+    ciphertext: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>, CoreError> {
+    cipher
+        .decrypt(
+            nonce,
+            Payload {
+                msg: ciphertext,
+                aad,
+            },
+        )
+        .map_err(|_| CoreError::EncryptionError {
+            msg: "decryption failed, wrong key, tampered payload or another record".to_string(),
+        })
+}
@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::infrastructure::outbox::policy::OutboxFailurePolicy;
+
 /// Outbox event record (domain-level abstraction)
 #[derive(Debug, Clone)]
 pub struct OutboxEventRecord<TPayload, TRouter>
@@ -32,6 +34,9 @@ where
 pub struct MessageRoutingInfo {
     pub exchange: String,
     pub routing_key: String,
+    /// Behavior when the event cannot be written to the outbox
+    #[serde(default)]
+    pub failure_policy: OutboxFailurePolicy,
 }
 
 impl MessageRoutingInfo {
@@ -39,14 +44,77 @@ impl MessageRoutingInfo {
         Self {
             exchange: exchange.into(),
             routing_key: routing_key.into(),
+            failure_policy: OutboxFailurePolicy::default(),
         }
     }
+
+    pub fn with_failure_policy(mut self, failure_policy: OutboxFailurePolicy) -> Self {
+        self.failure_policy = failure_policy;
+        self
+    }
+}
+
+/// Configuration for message routing information across different event types.
+///
+/// This struct holds the routing configuration for various outbox events
+/// that need to be published to a message broker. Each field represents
+/// the routing information (exchange name and routing key) for a specific
+/// type of domain event.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct MessageRoutingInfos {
+    /// Routing information for message creation events
+    pub create_message: MessageRoutingInfo,
+    /// Routing information for message deletion events
+    pub delete_message: MessageRoutingInfo,
+    /// Routing information for message update events
+    #[serde(default)]
+    pub update_message: MessageRoutingInfo,
+    /// Routing information for the single event announcing a bulk deletion
+    #[serde(default)]
+    pub bulk_delete_messages: MessageRoutingInfo,
+    /// Routing information for the users, roles and `@everyone` mentioned by new messages
+    #[serde(default)]
+    pub message_mentioned: MessageRoutingInfo,
+    /// Routing information for notification requests (e.g. reactions to a user's message)
+    #[serde(default)]
+    pub notification_requested: MessageRoutingInfo,
+    /// Routing information for notifications requested for a list of
+    /// recipients at once (e.g. mentions of a role)
+    #[serde(default)]
+    pub notification_fanout: MessageRoutingInfo,
+    /// Routing information for notifications of groups too large to list
+    /// their recipients, expanded by the notification service
+    #[serde(default)]
+    pub notification_broadcast: MessageRoutingInfo,
+    /// Routing information for abuse patterns, on a routing key dedicated to trust & safety tooling
+    #[serde(default)]
+    pub abuse_detected: MessageRoutingInfo,
+    /// Routing information for messages quarantined for an infected attachment,
+    /// on a routing key dedicated to trust & safety tooling
+    #[serde(default)]
+    pub attachment_quarantined: MessageRoutingInfo,
+    /// Routing information for user erasures, for services holding copies of their messages
+    #[serde(default)]
+    pub erase_user: MessageRoutingInfo,
+    /// Routing information for threads archived after staying idle
+    #[serde(default)]
+    pub archive_thread: MessageRoutingInfo,
+    /// Routing information for archived threads revived by a reply
+    #[serde(default)]
+    pub unarchive_thread: MessageRoutingInfo,
+    /// Routing information for the start and shutdown of replicas
+    #[serde(default)]
+    pub service_lifecycle: MessageRoutingInfo,
 }
 
 /// Router abstraction
 pub trait MessageRouter {
     fn exchange_name(&self) -> &str;
     fn routing_key(&self) -> &str;
+
+    fn failure_policy(&self) -> OutboxFailurePolicy {
+        OutboxFailurePolicy::default()
+    }
 }
 
 impl MessageRouter for MessageRoutingInfo {
@@ -57,4 +125,8 @@ impl MessageRouter for MessageRoutingInfo {
     fn routing_key(&self) -> &str {
         &self.routing_key
     }
+
+    fn failure_policy(&self) -> OutboxFailurePolicy {
+        self.failure_policy
+    }
 }
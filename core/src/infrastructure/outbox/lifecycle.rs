@@ -0,0 +1,41 @@
+use mongodb::Database;
+
+use crate::{
+    domain::{
+        common::CoreError,
+        lifecycle::{entities::ServiceLifecycleEvent, ports::LifecyclePublisher},
+    },
+    infrastructure::outbox::{
+        event::{MessageRoutingInfo, OutboxEventRecord},
+        policy::write_outbox_event_with_policy,
+    },
+};
+
+/// Lifecycle events written to the outbox, so the relay publishes them even
+/// once the replica announcing its shutdown is gone.
+///
+/// Payloads are never encrypted: they carry no user data.
+#[derive(Clone)]
+pub struct OutboxLifecyclePublisher {
+    db: Database,
+    routing: MessageRoutingInfo,
+}
+
+impl OutboxLifecyclePublisher {
+    pub fn new(db: &Database, routing: MessageRoutingInfo) -> Self {
+        Self {
+            db: db.clone(),
+            routing,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl LifecyclePublisher for OutboxLifecyclePublisher {
+    async fn publish(&self, event: &ServiceLifecycleEvent) -> Result<(), CoreError> {
+        let event = OutboxEventRecord::new(self.routing.clone(), event.clone());
+        write_outbox_event_with_policy(&self.db, &event, None).await?;
+
+        Ok(())
+    }
+}
@@ -4,9 +4,37 @@
 //! - `OutboxEvent` trait for defining domain events
 //! - `write_event` helper for writing events within database transactions
 //! - `OutboxError` for error handling
+//! - `OutboxFailurePolicy` to decide per event type how write failures are surfaced
+//! - `OutboxRelay` to publish records, retrying failures with exponential backoff
+//!   until they are moved to the `DEAD` status, from every replica or a single leader
+//! - `OutboxEncryption` for envelope encryption of payloads at rest, with data
+//!   keys from a `LocalKeyManagementService` or, with the `vault-kms` feature,
+//!   the `VaultTransitKms` of a HashiCorp Vault
+//! - `ensure_outbox_indexes` to expire `SENT` records after a retention period
+//! - `OutboxLifecyclePublisher` to announce the start and shutdown of replicas
 
+mod encryption;
 mod event;
+mod lifecycle;
+mod policy;
+mod relay;
+mod retention;
+#[cfg(feature = "vault-kms")]
+mod vault;
 mod writer;
 
-pub use event::{MessageRouter, MessageRoutingInfo, OutboxEventRecord};
-pub use writer::write_outbox_event;
+pub use encryption::{
+    DataKey, EncryptionEnvelope, KeyManagementService, LocalKeyManagementService, OutboxEncryption,
+};
+pub use event::{MessageRouter, MessageRoutingInfo, MessageRoutingInfos, OutboxEventRecord};
+pub use lifecycle::OutboxLifecyclePublisher;
+pub use policy::{
+    OutboxFailurePolicy, OutboxWriteOutcome, dropped_outbox_events, write_outbox_event_with_policy,
+};
+pub use relay::{
+    OUTBOX_RELAY_LEASE, OutboxMessage, OutboxPublisher, OutboxRelay, OutboxRelayConfig,
+};
+pub use retention::ensure_outbox_indexes;
+#[cfg(feature = "vault-kms")]
+pub use vault::VaultTransitKms;
+pub use writer::{OutboxStatus, write_encrypted_outbox_event, write_outbox_event};
@@ -0,0 +1,103 @@
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use mongodb::Database;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    domain::common::CoreError,
+    infrastructure::outbox::{
+        encryption::OutboxEncryption,
+        event::{MessageRouter, OutboxEventRecord},
+        writer::{write_encrypted_outbox_event, write_outbox_attempt},
+    },
+};
+
+/// Number of outbox events dropped by the `log_and_continue` policy since startup
+static DROPPED_OUTBOX_EVENTS: AtomicU64 = AtomicU64::new(0);
+
+/// Delay between two attempts of the `retry` policy
+const RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// What to do when an outbox event cannot be written
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum OutboxFailurePolicy {
+    /// Propagate the error so the calling request fails. The change the event
+    /// announces was already written and is not rolled back
+    #[default]
+    Fail,
+    /// Log the error, count the event as dropped, and let the request succeed
+    LogAndContinue,
+    /// Retry the write up to `max_attempts` times before failing the request.
+    /// An attempt finding the event written by an earlier one succeeds
+    Retry { max_attempts: u32 },
+}
+
+/// Result of writing an outbox event under a failure policy
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutboxWriteOutcome {
+    Written(Uuid),
+    Dropped,
+}
+
+/// Total number of outbox events dropped since startup
+pub fn dropped_outbox_events() -> u64 {
+    DROPPED_OUTBOX_EVENTS.load(Ordering::Relaxed)
+}
+
+/// Write an outbox event, applying the failure policy configured on its router
+/// and encrypting the payload when `encryption` is set
+pub async fn write_outbox_event_with_policy<TPayload, TRouter>(
+    db: &Database,
+    event: &OutboxEventRecord<TPayload, TRouter>,
+    encryption: Option<&OutboxEncryption>,
+) -> Result<OutboxWriteOutcome, CoreError>
+where
+    TPayload: Serialize + Send + Sync,
+    TRouter: MessageRouter + Send + Sync,
+{
+    match event.router.failure_policy() {
+        OutboxFailurePolicy::Fail => write_encrypted_outbox_event(db, event, encryption)
+            .await
+            .map(OutboxWriteOutcome::Written),
+        OutboxFailurePolicy::LogAndContinue => {
+            match write_encrypted_outbox_event(db, event, encryption).await {
+                Ok(id) => Ok(OutboxWriteOutcome::Written(id)),
+                Err(e) => {
+                    DROPPED_OUTBOX_EVENTS.fetch_add(1, Ordering::Relaxed);
+                    tracing::error!(
+                        error = %e,
+                        event_id = %event.id,
+                        routing_key = event.router.routing_key(),
+                        "dropping outbox event"
+                    );
+                    Ok(OutboxWriteOutcome::Dropped)
+                }
+            }
+        }
+        OutboxFailurePolicy::Retry { max_attempts } => {
+            let max_attempts = max_attempts.max(1);
+            let mut attempt = 1;
+            loop {
+                match write_outbox_attempt(db, event, encryption, attempt > 1).await {
+                    Ok(id) => return Ok(OutboxWriteOutcome::Written(id)),
+                    Err(e) if attempt >= max_attempts => return Err(e),
+                    Err(e) => {
+                        tracing::warn!(
+                            error = %e,
+                            event_id = %event.id,
+                            attempt,
+                            "retrying outbox event write"
+                        );
+                        attempt += 1;
+                        tokio::time::sleep(RETRY_DELAY).await;
+                    }
+                }
+            }
+        }
+    }
+}
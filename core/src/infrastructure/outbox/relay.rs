@@ -0,0 +1,270 @@
+use std::{sync::Arc, time::Duration};
+
+use mongodb::{
+    Collection, Database,
+    bson::{Bson, DateTime as BsonDateTime, doc},
+    options::ReturnDocument,
+};
+use uuid::Uuid;
+
+use crate::{
+    domain::{common::CoreError, lease::ports::LeaseLock},
+    infrastructure::lease::SingletonJob,
+    infrastructure::message::dto::uuid_to_binary,
+    infrastructure::outbox::{
+        encryption::OutboxEncryption,
+        writer::{OUTBOX_COLLECTION, OutboxDocument, OutboxStatus},
+    },
+};
+
+/// Name of the lease held by the relay leader in single-leader mode
+pub const OUTBOX_RELAY_LEASE: &str = "outbox-relay";
+
+/// An outbox record handed to the broker
+#[derive(Debug, Clone)]
+pub struct OutboxMessage {
+    pub id: Uuid,
+    pub exchange_name: String,
+    pub routing_key: String,
+    pub payload: Bson,
+}
+
+/// Port to the message broker the relay publishes to
+#[async_trait::async_trait]
+pub trait OutboxPublisher: Send + Sync {
+    async fn publish(&self, message: &OutboxMessage) -> Result<(), CoreError>;
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct OutboxRelayConfig {
+    /// Failed attempts after which a record is moved to `DEAD`
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled after each failure
+    pub base_retry_delay: Duration,
+    pub max_retry_delay: Duration,
+    /// How long a claimed record is hidden from other relays while being published
+    pub lease: Duration,
+    /// Wait between two polls when no record is ready
+    pub poll_interval: Duration,
+    /// Records claimed at once by a relay before publishing them
+    pub batch_size: usize,
+}
+
+impl Default for OutboxRelayConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 10,
+            base_retry_delay: Duration::from_secs(1),
+            max_retry_delay: Duration::from_secs(300),
+            lease: Duration::from_secs(30),
+            poll_interval: Duration::from_millis(500),
+            batch_size: 10,
+        }
+    }
+}
+
+impl OutboxRelayConfig {
+    /// Delay before retrying a record that failed `attempts` times
+    pub fn retry_delay(&self, attempts: u32) -> Duration {
+        let exponent = attempts.saturating_sub(1).min(31);
+        self.base_retry_delay
+            .saturating_mul(1 << exponent)
+            .min(self.max_retry_delay)
+    }
+}
+
+/// Publishes `READY` outbox records, retrying failures with exponential backoff.
+///
+/// By default every replica relays, each claiming its own batches (partitioned
+/// mode). With [`OutboxRelay::with_leader`], only the replica holding the
+/// [`OUTBOX_RELAY_LEASE`] relays, which keeps records in order across replicas.
+pub struct OutboxRelay<P>
+where
+    P: OutboxPublisher,
+{
+    collection: Collection<OutboxDocument>,
+    publisher: P,
+    config: OutboxRelayConfig,
+    encryption: Option<OutboxEncryption>,
+    leader: Option<SingletonJob>,
+}
+
+impl<P> OutboxRelay<P>
+where
+    P: OutboxPublisher,
+{
+    pub fn new(db: &Database, publisher: P, config: OutboxRelayConfig) -> Self {
+        Self {
+            collection: db.collection(OUTBOX_COLLECTION),
+            publisher,
+            config,
+            encryption: None,
+            leader: None,
+        }
+    }
+
+    /// Decrypt encrypted payloads before publishing them
+    pub fn with_encryption(mut self, encryption: OutboxEncryption) -> Self {
+        self.encryption = Some(encryption);
+        self
+    }
+
+    /// Relay from the replica holding the relay lease only (single-leader mode).
+    ///
+    /// The lease lasts [`OutboxRelayConfig::lease`] and is renewed before each
+    /// batch, so a batch must be published within it.
+    pub fn with_leader(mut self, lock: Arc<dyn LeaseLock>) -> Self {
+        self.leader = Some(SingletonJob::new(
+            lock,
+            OUTBOX_RELAY_LEASE,
+            self.config.lease,
+        ));
+        self
+    }
+
+    /// Relay records until the task is aborted
+    pub async fn run(&self) {
+        loop {
+            match self.relay_round().await {
+                Ok(Some(relayed)) if relayed > 0 => {}
+                Ok(_) => tokio::time::sleep(self.config.poll_interval).await,
+                Err(e) => {
+                    tracing::warn!(error = %e, "outbox relay failed");
+                    tokio::time::sleep(self.config.poll_interval).await;
+                }
+            }
+        }
+    }
+
+    /// Relay one batch and return the number of records handled; `None` in
+    /// single-leader mode when another replica is the leader
+    pub async fn relay_round(&self) -> Result<Option<usize>, CoreError> {
+        match &self.leader {
+            Some(leader) => leader.run_once(self.relay_batch()).await?.transpose(),
+            None => self.relay_batch().await.map(Some),
+        }
+    }
+
+    /// Claim up to `batch_size` due records, then publish them in order
+    pub async fn relay_batch(&self) -> Result<usize, CoreError> {
+        let mut batch = Vec::with_capacity(self.config.batch_size);
+        while batch.len() < self.config.batch_size {
+            match self.claim_next().await? {
+                Some(record) => batch.push(record),
+                None => break,
+            }
+        }
+
+        let relayed = batch.len();
+        for record in batch {
+            self.publish(record).await?;
+        }
+        Ok(relayed)
+    }
+
+    /// Publish the oldest due record, if any, and return its resulting status
+    pub async fn relay_next(&self) -> Result<Option<OutboxStatus>, CoreError> {
+        match self.claim_next().await? {
+            Some(record) => self.publish(record).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Publish a claimed record and store its resulting status
+    async fn publish(&self, record: OutboxDocument) -> Result<OutboxStatus, CoreError> {
+        let id = record.id;
+        let published = match self.decrypt_payload(&record).await {
+            Ok(payload) => {
+                let message = OutboxMessage {
+                    id,
+                    exchange_name: record.exchange_name,
+                    routing_key: record.routing_key,
+                    payload,
+                };
+                self.publisher.publish(&message).await
+            }
+            // a missing or wrong key is retried too, in case it gets fixed
+            Err(e) => Err(e),
+        };
+
+        let (status, update) = match published {
+            Ok(()) => (
+                OutboxStatus::Sent,
+                doc! { "$set": { "status": OutboxStatus::Sent.as_str(), "sent_at": BsonDateTime::now() } },
+            ),
+            Err(e) => {
+                let attempts = record.attempts + 1;
+                let status = if attempts >= self.config.max_attempts {
+                    tracing::error!(error = %e, event_id = %id, attempts, "outbox event is dead");
+                    OutboxStatus::Dead
+                } else {
+                    tracing::warn!(error = %e, event_id = %id, attempts, "outbox publish failed");
+                    OutboxStatus::Ready
+                };
+                let next_retry_at = after(self.config.retry_delay(attempts));
+
+                (
+                    status,
+                    doc! { "$set": {
+                        "status": status.as_str(),
+                        "attempts": attempts as i64,
+                        "next_retry_at": next_retry_at,
+                        "last_error": e.to_string(),
+                    } },
+                )
+            }
+        };
+
+        self.collection
+            .update_one(doc! { "_id": uuid_to_binary(id) }, update)
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        Ok(status)
+    }
+
+    async fn decrypt_payload(&self, record: &OutboxDocument) -> Result<Bson, CoreError> {
+        match (&record.encryption, &self.encryption) {
+            (None, _) => Ok(record.payload.clone()),
+            (Some(envelope), Some(encryption)) => {
+                encryption.open(&record.payload, envelope, record.id).await
+            }
+            (Some(_), None) => Err(CoreError::EncryptionError {
+                msg: "payload is encrypted but the relay has no encryption configured".to_string(),
+            }),
+        }
+    }
+
+    /// Take the oldest due `READY` record, pushing its `next_retry_at` by the
+    /// lease so concurrent relays skip it; a relay crashing mid-publish only
+    /// delays the record by the lease.
+    ///
+    /// `findAndModify` is atomic, so replicas claiming concurrently each get
+    /// distinct records. Records written before retries were scheduled have
+    /// no `next_retry_at` and are due right away.
+    async fn claim_next(&self) -> Result<Option<OutboxDocument>, CoreError> {
+        self.collection
+            .find_one_and_update(
+                doc! {
+                    "status": OutboxStatus::Ready.as_str(),
+                    "$or": [
+                        { "next_retry_at": { "$lte": BsonDateTime::now() } },
+                        { "next_retry_at": { "$exists": false } },
+                    ],
+                },
+                doc! { "$set": { "next_retry_at": after(self.config.lease) } },
+            )
+            .sort(doc! { "created_at": 1 })
+            .return_document(ReturnDocument::Before)
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })
+    }
+}
+
+fn after(delay: Duration) -> BsonDateTime {
+    BsonDateTime::from_millis(
+        BsonDateTime::now()
+            .timestamp_millis()
+            .saturating_add(delay.as_millis() as i64),
+    )
+}
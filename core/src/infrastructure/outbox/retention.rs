@@ -0,0 +1,70 @@
+use std::time::Duration;
+
+use mongodb::{
+    Collection, Database, IndexModel,
+    bson::{Document, doc},
+    error::ErrorKind,
+    options::IndexOptions,
+};
+
+use crate::{domain::common::CoreError, infrastructure::outbox::writer::OUTBOX_COLLECTION};
+
+const SENT_AT_TTL_INDEX: &str = "sent_at_ttl";
+
+/// Server error code returned when an index exists with other options
+const INDEX_OPTIONS_CONFLICT: i32 = 85;
+
+/// Create the outbox indexes (idempotent):
+/// - the one the relay uses to find due `READY` records
+/// - a TTL index on `sent_at`, so MongoDB deletes `SENT` records once
+///   `sent_retention` elapsed; only `SENT` records have a `sent_at`, so
+///   `READY` and `DEAD` ones are kept
+///
+/// Changing the retention updates the existing TTL index in place.
+pub async fn ensure_outbox_indexes(
+    db: &Database,
+    sent_retention: Duration,
+) -> Result<(), CoreError> {
+    let collection: Collection<Document> = db.collection(OUTBOX_COLLECTION);
+
+    collection
+        .create_index(
+            IndexModel::builder()
+                .keys(doc! { "status": 1, "next_retry_at": 1, "created_at": 1 })
+                .build(),
+        )
+        .await
+        .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+    let ttl_index = IndexModel::builder()
+        .keys(doc! { "sent_at": 1 })
+        .options(
+            IndexOptions::builder()
+                .name(SENT_AT_TTL_INDEX.to_string())
+                .expire_after(sent_retention)
+                .build(),
+        )
+        .build();
+
+    match collection.create_index(ttl_index).await {
+        Ok(_) => Ok(()),
+        Err(e) if matches!(*e.kind, ErrorKind::Command(ref c) if c.code == INDEX_OPTIONS_CONFLICT) =>
+        {
+            tracing::info!(
+                retention_secs = sent_retention.as_secs(),
+                "updating outbox retention"
+            );
+            db.run_command(doc! {
+                "collMod": OUTBOX_COLLECTION,
+                "index": {
+                    "name": SENT_AT_TTL_INDEX,
+                    "expireAfterSeconds": sent_retention.as_secs() as i64,
+                },
+            })
+            .await
+            .map(|_| ())
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })
+        }
+        Err(e) => Err(CoreError::DatabaseError { msg: e.to_string() }),
+    }
+}
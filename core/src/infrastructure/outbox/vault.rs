@@ -0,0 +1,138 @@
+use std::{fmt::Display, time::Duration};
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use reqwest::Client;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+use crate::{
+    domain::common::CoreError,
+    infrastructure::outbox::encryption::{DataKey, KeyManagementService},
+};
+
+/// Master keys held by the transit secrets engine of HashiCorp Vault.
+///
+/// Data keys are generated with `POST /v1/<mount>/datakey/plaintext/<key>`
+/// and decrypted with `POST /v1/<mount>/decrypt/<key>`; the key name is the
+/// `key_id` of the records, and the Vault ciphertext (`vault:v<n>:...`) their
+/// encrypted data key, so rotating the key in Vault keeps older records readable.
+#[derive(Clone)]
+pub struct VaultTransitKms {
+    client: Client,
+    address: String,
+    mount: String,
+    key_name: String,
+    token: String,
+}
+
+#[derive(Serialize)]
+struct DataKeyRequest {
+    bits: u32,
+}
+
+#[derive(Serialize)]
+struct DecryptRequest<'a> {
+    ciphertext: &'a str,
+}
+
+#[derive(Deserialize)]
+struct VaultResponse<T> {
+    data: T,
+}
+
+#[derive(Deserialize)]
+struct DataKeyResponse {
+    plaintext: String,
+    ciphertext: String,
+}
+
+#[derive(Deserialize)]
+struct DecryptResponse {
+    plaintext: String,
+}
+
+impl VaultTransitKms {
+    /// Generate data keys under `key_name` of the transit engine mounted at
+    /// `mount` of the Vault at `address`, giving up on Vault after `timeout`
+    pub fn new(
+        address: impl Into<String>,
+        token: impl Into<String>,
+        mount: impl Into<String>,
+        key_name: impl Into<String>,
+        timeout: Duration,
+    ) -> Result<Self, CoreError> {
+        let client = Client::builder()
+            .timeout(timeout)
+            .build()
+            .map_err(vault_error)?;
+        Ok(Self {
+            client,
+            address: address.into().trim_end_matches('/').to_string(),
+            mount: mount.into().trim_matches('/').to_string(),
+            key_name: key_name.into(),
+            token: token.into(),
+        })
+    }
+
+    async fn call<T: DeserializeOwned>(
+        &self,
+        operation: &str,
+        key_name: &str,
+        body: &impl Serialize,
+    ) -> Result<T, CoreError> {
+        let url = format!("{}/v1/{}/{operation}/{key_name}", self.address, self.mount);
+        let response = self
+            .client
+            .post(url)
+            .header("X-Vault-Token", &self.token)
+            .json(body)
+            .send()
+            .await
+            .map_err(vault_error)?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(vault_error(format!("{status}: {body}")));
+        }
+        let response: VaultResponse<T> = response.json().await.map_err(vault_error)?;
+        Ok(response.data)
+    }
+}
+
+fn vault_error(e: impl Display) -> CoreError {
+    CoreError::EncryptionError {
+        msg: format!("vault transit: {e}"),
+    }
+}
+
+fn decode_key(plaintext: &str) -> Result<Vec<u8>, CoreError> {
+    STANDARD.decode(plaintext).map_err(vault_error)
+}
+
+#[async_trait::async_trait]
+impl KeyManagementService for VaultTransitKms {
+    async fn generate_data_key(&self) -> Result<DataKey, CoreError> {
+        let key: DataKeyResponse = self
+            .call(
+                "datakey/plaintext",
+                &self.key_name,
+                &DataKeyRequest { bits: 256 },
+            )
+            .await?;
+
+        Ok(DataKey {
+            key_id: self.key_name.clone(),
+            plaintext: decode_key(&key.plaintext)?,
+            encrypted: key.ciphertext.into_bytes(),
+        })
+    }
+
+    async fn decrypt_data_key(&self, key_id: &str, encrypted: &[u8]) -> Result<Vec<u8>, CoreError> {
+        let ciphertext = std::str::from_utf8(encrypted).map_err(vault_error)?;
+        let key: DecryptResponse = self
+            .call("decrypt", key_id, &DecryptRequest { ciphertext })
+            .await?;
+
+        decode_key(&key.plaintext)
+    }
+}
@@ -1,54 +1,141 @@
 use mongodb::{
     Collection, Database,
-    bson::{DateTime as BsonDateTime, doc, to_bson},
+    bson::{DateTime as BsonDateTime, to_bson},
+    error::{ErrorKind, WriteFailure},
 };
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::{
     domain::common::CoreError,
-    infrastructure::outbox::event::{MessageRouter, OutboxEventRecord},
+    infrastructure::outbox::{
+        encryption::{EncryptionEnvelope, OutboxEncryption},
+        event::{MessageRouter, OutboxEventRecord},
+    },
 };
 
-const OUTBOX_COLLECTION: &str = "outbox_messages";
+pub(crate) const OUTBOX_COLLECTION: &str = "outbox_messages";
 
-#[derive(Debug, Serialize)]
-struct OutboxDocument {
+/// Server error code of duplicate key errors
+const DUPLICATE_KEY: i32 = 11000;
+
+/// Lifecycle of an outbox record
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum OutboxStatus {
+    /// Waiting to be published, possibly after a failed attempt
+    Ready,
+    Sent,
+    /// Gave up after too many failed attempts
+    Dead,
+}
+
+impl OutboxStatus {
+    /// Value stored in the `status` field
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OutboxStatus::Ready => "READY",
+            OutboxStatus::Sent => "SENT",
+            OutboxStatus::Dead => "DEAD",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct OutboxDocument {
     #[serde(rename = "_id")]
-    id: Uuid,
-    exchange_name: String,
-    routing_key: String,
-    payload: mongodb::bson::Bson,
-    status: String,
-    created_at: BsonDateTime,
+    pub id: Uuid,
+    pub exchange_name: String,
+    pub routing_key: String,
+    pub payload: mongodb::bson::Bson,
+    pub status: OutboxStatus,
+    pub created_at: BsonDateTime,
+    /// Number of failed publish attempts
+    #[serde(default)]
+    pub attempts: u32,
+    /// The relay ignores the record until then
+    #[serde(default = "BsonDateTime::now")]
+    pub next_retry_at: BsonDateTime,
+    #[serde(default)]
+    pub last_error: Option<String>,
+    #[serde(default)]
+    pub sent_at: Option<BsonDateTime>,
+    /// Set when `payload` is encrypted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encryption: Option<EncryptionEnvelope>,
 }
 
 pub async fn write_outbox_event<TPayload, TRouter>(
     db: &Database,
     event: &OutboxEventRecord<TPayload, TRouter>,
 ) -> Result<Uuid, CoreError>
+where
+    TPayload: Serialize + Send + Sync,
+    TRouter: MessageRouter + Send + Sync,
+{
+    write_encrypted_outbox_event(db, event, None).await
+}
+
+/// Write an outbox event, encrypting its payload when `encryption` is set
+pub async fn write_encrypted_outbox_event<TPayload, TRouter>(
+    db: &Database,
+    event: &OutboxEventRecord<TPayload, TRouter>,
+    encryption: Option<&OutboxEncryption>,
+) -> Result<Uuid, CoreError>
+where
+    TPayload: Serialize + Send + Sync,
+    TRouter: MessageRouter + Send + Sync,
+{
+    write_outbox_attempt(db, event, encryption, false).await
+}
+
+/// Write an outbox event once. On a `retry`, the record being there already
+/// means an earlier attempt was written although its answer was lost, so the
+/// duplicate key on its ID counts as written
+pub(crate) async fn write_outbox_attempt<TPayload, TRouter>(
+    db: &Database,
+    event: &OutboxEventRecord<TPayload, TRouter>,
+    encryption: Option<&OutboxEncryption>,
+    retry: bool,
+) -> Result<Uuid, CoreError>
 where
     TPayload: Serialize + Send + Sync,
     TRouter: MessageRouter + Send + Sync,
 {
     let payload = to_bson(&event.payload)
         .map_err(|e| CoreError::SerializationError { msg: e.to_string() })?;
+    let (payload, encryption) = match encryption {
+        Some(encryption) => {
+            let (payload, envelope) = encryption.seal(&payload, event.id).await?;
+            (payload, Some(envelope))
+        }
+        None => (payload, None),
+    };
 
+    let now = BsonDateTime::now();
     let doc = OutboxDocument {
         id: event.id,
         exchange_name: event.router.exchange_name().to_string(),
         routing_key: event.router.routing_key().to_string(),
         payload,
-        status: "READY".to_string(),
-        created_at: BsonDateTime::now(),
+        status: OutboxStatus::Ready,
+        created_at: now,
+        attempts: 0,
+        next_retry_at: now,
+        last_error: None,
+        sent_at: None,
+        encryption,
     };
 
     let collection: Collection<OutboxDocument> = db.collection(OUTBOX_COLLECTION);
 
-    collection
-        .insert_one(doc)
-        .await
-        .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+    match collection.insert_one(doc).await {
+        Ok(_) => Ok(event.id),
+        Err(e) if retry && is_duplicate_key(&e.kind) => Ok(event.id),
+        Err(e) => Err(CoreError::DatabaseError { msg: e.to_string() }),
+    }
+}
 
-    Ok(event.id)
+fn is_duplicate_key(kind: &ErrorKind) -> bool {
+    matches!(kind, ErrorKind::Write(WriteFailure::WriteError(error)) if error.code == DUPLICATE_KEY)
 }
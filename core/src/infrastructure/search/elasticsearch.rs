@@ -0,0 +1,300 @@
+use std::fmt::Display;
+
+use chrono::{DateTime, Utc};
+use reqwest::{Client, Method, RequestBuilder, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+use crate::domain::{
+    common::CoreError,
+    message::entities::{Message, MessageId},
+    search::{
+        entities::{SearchHit, SearchQuery},
+        ports::{MessageSearchRepository, SearchIndex},
+    },
+};
+
+/// Search backend storing messages in an Elasticsearch index.
+///
+/// Contents are matched with `fuzziness: AUTO`, so typos still find the
+/// message. Documents are keyed by message id: indexing a message again
+/// updates its entry and keeps its archived flag.
+#[derive(Clone)]
+pub struct ElasticsearchMessageSearchRepository {
+    client: Client,
+    base_url: String,
+    index: String,
+    credentials: Option<(String, String)>,
+}
+
+/// Indexed copy of a message
+#[derive(Serialize)]
+struct MessageDocument<'a> {
+    channel_id: Uuid,
+    author_id: Uuid,
+    content: &'a str,
+    reply_to_message_id: Option<Uuid>,
+    created_at: DateTime<Utc>,
+}
+
+impl<'a> From<&'a Message> for MessageDocument<'a> {
+    fn from(message: &'a Message) -> Self {
+        Self {
+            channel_id: message.channel_id.0,
+            author_id: message.author_id.0,
+            content: &message.content,
+            reply_to_message_id: message.reply_to_message_id.map(|id| id.0),
+            created_at: message.created_at,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct BulkResponse {
+    errors: bool,
+}
+
+#[derive(Deserialize)]
+struct SearchResponse {
+    hits: SearchResponseHits,
+}
+
+#[derive(Deserialize)]
+struct SearchResponseHits {
+    hits: Vec<SearchResponseHit>,
+}
+
+#[derive(Deserialize)]
+struct SearchResponseHit {
+    #[serde(rename = "_id")]
+    id: Uuid,
+    #[serde(rename = "_score", default)]
+    score: Option<f32>,
+    #[serde(rename = "_source", default)]
+    source: HitSource,
+}
+
+#[derive(Deserialize, Default)]
+struct HitSource {
+    #[serde(default)]
+    archived: bool,
+}
+
+impl ElasticsearchMessageSearchRepository {
+    /// Use the `index` index of the cluster at `base_url` (e.g. `http://localhost:9200`)
+    pub fn new(base_url: impl Into<String>, index: impl Into<String>) -> Self {
+        Self {
+            client: Client::new(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            index: index.into(),
+            credentials: None,
+        }
+    }
+
+    /// Authenticate every request with HTTP basic auth
+    pub fn with_basic_auth(
+        mut self,
+        username: impl Into<String>,
+        password: impl Into<String>,
+    ) -> Self {
+        self.credentials = Some((username.into(), password.into()));
+        self
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let request = self
+            .client
+            .request(method, format!("{}/{}{}", self.base_url, self.index, path));
+        match &self.credentials {
+            Some((username, password)) => request.basic_auth(username, Some(password)),
+            None => request,
+        }
+    }
+
+    async fn send(&self, request: RequestBuilder) -> Result<Response, CoreError> {
+        request.send().await.map_err(backend_error)
+    }
+}
+
+fn backend_error(e: impl Display) -> CoreError {
+    CoreError::ServiceUnavailable(format!("elasticsearch: {e}"))
+}
+
+/// Fail on any non-success status, keeping the response body for the logs
+async fn success(response: Response) -> Result<Response, CoreError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body = response.text().await.unwrap_or_default();
+    Err(backend_error(format!("{status}: {body}")))
+}
+
+#[async_trait::async_trait]
+impl SearchIndex for ElasticsearchMessageSearchRepository {
+    async fn index_messages(&self, messages: &[Message]) -> Result<(), CoreError> {
+        if messages.is_empty() {
+            return Ok(());
+        }
+
+        // partial updates with upsert, so the archived flag of known messages is kept
+        let mut body = String::new();
+        for message in messages {
+            let action = json!({ "update": { "_id": message.id.0 } });
+            let update = json!({ "doc": MessageDocument::from(message), "doc_as_upsert": true });
+            body.push_str(&format!("{action}\n{update}\n"));
+        }
+
+        let response = self
+            .send(
+                self.request(Method::POST, "/_bulk")
+                    .header("Content-Type", "application/x-ndjson")
+                    .body(body),
+            )
+            .await?;
+        let bulk: BulkResponse = success(response)
+            .await?
+            .json()
+            .await
+            .map_err(backend_error)?;
+        if bulk.errors {
+            return Err(backend_error("bulk indexing failed for some messages"));
+        }
+
+        Ok(())
+    }
+
+    async fn remove_message(&self, id: &MessageId) -> Result<(), CoreError> {
+        let response = self
+            .send(self.request(Method::DELETE, &format!("/_doc/{}", id.0)))
+            .await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(());
+        }
+        success(response).await?;
+
+        Ok(())
+    }
+
+    async fn set_archived(&self, ids: &[MessageId], archived: bool) -> Result<(), CoreError> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        let ids: Vec<Uuid> = ids.iter().map(|id| id.0).collect();
+        let body = json!({
+            "query": { "ids": { "values": ids } },
+            "script": {
+                "source": "ctx._source.archived = params.archived",
+                "lang": "painless",
+                "params": { "archived": archived },
+            },
+        });
+        let response = self
+            .send(
+                self.request(Method::POST, "/_update_by_query?conflicts=proceed")
+                    .json(&body),
+            )
+            .await?;
+        success(response).await?;
+
+        Ok(())
+    }
+
+    async fn search(&self, query: &SearchQuery) -> Result<Vec<SearchHit>, CoreError> {
+        let mut filter = Vec::new();
+        if let Some(channel_id) = query.channel_id {
+            filter.push(json!({ "term": { "channel_id": channel_id.0 } }));
+        }
+        if let Some(author_id) = query.author_id {
+            filter.push(json!({ "term": { "author_id": author_id.0 } }));
+        }
+        if query.before.is_some() || query.after.is_some() {
+            let mut range = serde_json::Map::new();
+            if let Some(before) = query.before {
+                range.insert("lt".to_string(), json!(before));
+            }
+            if let Some(after) = query.after {
+                range.insert("gt".to_string(), json!(after));
+            }
+            filter.push(json!({ "range": { "created_at": range } }));
+        }
+        let mut must_not = Vec::new();
+        if !query.include_archived {
+            must_not.push(json!({ "term": { "archived": true } }));
+        }
+
+        let body = json!({
+            "size": query.limit,
+            "_source": ["archived"],
+            "query": {
+                "bool": {
+                    "must": {
+                        "match": {
+                            "content": { "query": query.text, "fuzziness": "AUTO" }
+                        }
+                    },
+                    "filter": filter,
+                    "must_not": must_not,
+                }
+            },
+        });
+        let response = self
+            .send(self.request(Method::POST, "/_search").json(&body))
+            .await?;
+        let result: SearchResponse = success(response)
+            .await?
+            .json()
+            .await
+            .map_err(backend_error)?;
+
+        Ok(result
+            .hits
+            .hits
+            .into_iter()
+            .map(|hit| SearchHit {
+                message_id: MessageId::from(hit.id),
+                score: hit.score.unwrap_or_default(),
+                archived: hit.source.archived,
+            })
+            .collect())
+    }
+}
+
+#[async_trait::async_trait]
+impl MessageSearchRepository for ElasticsearchMessageSearchRepository {
+    async fn ensure_index(&self) -> Result<(), CoreError> {
+        let response = self.send(self.request(Method::HEAD, "")).await?;
+        if response.status().is_success() {
+            return Ok(());
+        }
+
+        let mappings = json!({
+            "mappings": {
+                "properties": {
+                    "channel_id": { "type": "keyword" },
+                    "author_id": { "type": "keyword" },
+                    "content": { "type": "text" },
+                    "reply_to_message_id": { "type": "keyword" },
+                    "created_at": { "type": "date" },
+                    "archived": { "type": "boolean" },
+                }
+            }
+        });
+        let response = self
+            .send(self.request(Method::PUT, "").json(&mappings))
+            .await?;
+        if response.status() == StatusCode::BAD_REQUEST {
+            // another instance created it in the meantime
+            let body: Value = response.json().await.map_err(backend_error)?;
+            if body["error"]["type"] == "resource_already_exists_exception" {
+                return Ok(());
+            }
+            return Err(backend_error(body));
+        }
+        success(response).await?;
+
+        Ok(())
+    }
+}
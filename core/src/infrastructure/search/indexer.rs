@@ -0,0 +1,166 @@
+use std::{sync::Arc, time::Duration};
+
+use tokio::{
+    sync::{broadcast::error::RecvError, mpsc},
+    task::JoinHandle,
+    time::{Instant, sleep, sleep_until},
+};
+
+use crate::domain::{
+    common::CoreError,
+    message::{
+        entities::{Message, MessageId},
+        events::{MessageEvent, MessageEventBus},
+    },
+    search::ports::SearchIndex,
+};
+
+/// Maximum delay between two attempts at a failing batch
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone)]
+pub enum IndexCommand {
+    Index(Box<Message>),
+    Remove(MessageId),
+    /// Flag entries as archived (or restored) without removing them
+    SetArchived {
+        ids: Vec<MessageId>,
+        archived: bool,
+    },
+}
+
+impl From<MessageEvent> for IndexCommand {
+    fn from(event: MessageEvent) -> Self {
+        match event {
+            MessageEvent::Created(message) | MessageEvent::Updated(message) => {
+                IndexCommand::Index(Box::new(message))
+            }
+            MessageEvent::Deleted { id, .. } => IndexCommand::Remove(id),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SearchIndexerConfig {
+    /// Number of pending commands before producers start waiting
+    pub queue_capacity: usize,
+    /// Maximum number of messages sent to the backend in one call
+    pub batch_size: usize,
+    /// Upper bound on the number of backend calls per second
+    pub max_batches_per_second: u32,
+}
+
+impl Default for SearchIndexerConfig {
+    fn default() -> Self {
+        Self {
+            queue_capacity: 1_000,
+            batch_size: 100,
+            max_batches_per_second: 10,
+        }
+    }
+}
+
+/// Handle to the background task feeding a [`SearchIndex`]
+#[derive(Clone)]
+pub struct SearchIndexer {
+    sender: mpsc::Sender<IndexCommand>,
+}
+
+impl SearchIndexer {
+    /// Spawn the consumer task; it stops once every handle has been dropped
+    /// and the queue is drained.
+    pub fn spawn(
+        index: Arc<dyn SearchIndex>,
+        config: SearchIndexerConfig,
+    ) -> (Self, JoinHandle<()>) {
+        let (sender, receiver) = mpsc::channel(config.queue_capacity.max(1));
+        let consumer = tokio::spawn(consume(index, receiver, config));
+        (Self { sender }, consumer)
+    }
+
+    /// Queue a command, waiting for room when the backend is falling behind
+    pub async fn submit(&self, command: IndexCommand) -> Result<(), CoreError> {
+        self.sender
+            .send(command)
+            .await
+            .map_err(|_| CoreError::ServiceUnavailable("search indexer stopped".to_string()))
+    }
+
+    /// Keep the index up to date with the messages published on `events`.
+    ///
+    /// Fed by a change stream, the bus sees the writes of every instance;
+    /// otherwise only those of this one. Events skipped because the indexer
+    /// lagged behind the bus are lost until the channel is reindexed.
+    pub fn follow(&self, events: &MessageEventBus) -> JoinHandle<()> {
+        let indexer = self.clone();
+        let mut receiver = events.subscribe();
+        tokio::spawn(async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(event) => {
+                        if indexer.submit(event.into()).await.is_err() {
+                            return;
+                        }
+                    }
+                    Err(RecvError::Lagged(skipped)) => {
+                        tracing::warn!(skipped, "search indexer lagged behind message events");
+                    }
+                    Err(RecvError::Closed) => return,
+                }
+            }
+        })
+    }
+}
+
+async fn consume(
+    index: Arc<dyn SearchIndex>,
+    mut receiver: mpsc::Receiver<IndexCommand>,
+    config: SearchIndexerConfig,
+) {
+    let batch_size = config.batch_size.max(1);
+    let min_interval = Duration::from_secs(1) / config.max_batches_per_second.max(1);
+    let mut next_call = Instant::now();
+    let mut commands = Vec::with_capacity(batch_size);
+
+    while receiver.recv_many(&mut commands, batch_size).await > 0 {
+        let mut to_index = Vec::new();
+        for command in commands.drain(..) {
+            match command {
+                IndexCommand::Index(message) => to_index.push(*message),
+                IndexCommand::Remove(id) => {
+                    sleep_until(next_call).await;
+                    retry(|| index.remove_message(&id)).await;
+                    next_call = Instant::now() + min_interval;
+                }
+                IndexCommand::SetArchived { ids, archived } => {
+                    sleep_until(next_call).await;
+                    retry(|| index.set_archived(&ids, archived)).await;
+                    next_call = Instant::now() + min_interval;
+                }
+            }
+        }
+
+        if !to_index.is_empty() {
+            sleep_until(next_call).await;
+            retry(|| index.index_messages(&to_index)).await;
+            next_call = Instant::now() + min_interval;
+        }
+    }
+}
+
+/// Retry a backend call with exponential backoff until it succeeds.
+///
+/// Holding the batch meanwhile keeps the queue full, which in turn slows
+/// producers down instead of piling work on an unhealthy cluster.
+async fn retry<F, Fut>(mut call: F)
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<(), CoreError>>,
+{
+    let mut delay = Duration::from_millis(100);
+    while let Err(e) = call().await {
+        tracing::warn!(error = %e, retry_in_ms = delay.as_millis() as u64, "search backend call failed");
+        sleep(delay).await;
+        delay = (delay * 2).min(MAX_RETRY_DELAY);
+    }
+}
@@ -0,0 +1,215 @@
+use std::fmt::Display;
+
+use chrono::{DateTime, Utc};
+use reqwest::{Client, Method, RequestBuilder, Response};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::domain::{
+    common::CoreError,
+    message::entities::{Message, MessageId},
+    search::{
+        entities::{SearchHit, SearchQuery},
+        ports::{MessageSearchRepository, SearchIndex},
+    },
+};
+
+/// Search backend storing messages in a Meilisearch index.
+///
+/// Meilisearch tolerates typos out of the box; `channel_id` and `author_id`
+/// are declared filterable so searches can be narrowed to either. Writes are
+/// processed asynchronously by Meilisearch and become searchable shortly
+/// after being acknowledged.
+#[derive(Clone)]
+pub struct MeilisearchMessageSearchRepository {
+    client: Client,
+    base_url: String,
+    index: String,
+    api_key: Option<String>,
+}
+
+/// Indexed copy of a message
+#[derive(Serialize)]
+struct MessageDocument<'a> {
+    id: Uuid,
+    channel_id: Uuid,
+    author_id: Uuid,
+    content: &'a str,
+    reply_to_message_id: Option<Uuid>,
+    created_at: DateTime<Utc>,
+}
+
+impl<'a> From<&'a Message> for MessageDocument<'a> {
+    fn from(message: &'a Message) -> Self {
+        Self {
+            id: message.id.0,
+            channel_id: message.channel_id.0,
+            author_id: message.author_id.0,
+            content: &message.content,
+            reply_to_message_id: message.reply_to_message_id.map(|id| id.0),
+            created_at: message.created_at,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct SearchResponse {
+    hits: Vec<SearchResponseHit>,
+}
+
+#[derive(Deserialize)]
+struct SearchResponseHit {
+    id: Uuid,
+    #[serde(default)]
+    archived: bool,
+    #[serde(rename = "_rankingScore", default)]
+    ranking_score: Option<f32>,
+}
+
+impl MeilisearchMessageSearchRepository {
+    /// Use the `index` index of the instance at `base_url` (e.g. `http://localhost:7700`)
+    pub fn new(base_url: impl Into<String>, index: impl Into<String>) -> Self {
+        Self {
+            client: Client::new(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            index: index.into(),
+            api_key: None,
+        }
+    }
+
+    /// Authenticate every request with this API key
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let request = self.client.request(
+            method,
+            format!("{}/indexes/{}{}", self.base_url, self.index, path),
+        );
+        match &self.api_key {
+            Some(api_key) => request.bearer_auth(api_key),
+            None => request,
+        }
+    }
+
+    async fn send(&self, request: RequestBuilder) -> Result<Response, CoreError> {
+        let response = request.send().await.map_err(backend_error)?;
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        let body = response.text().await.unwrap_or_default();
+        Err(backend_error(format!("{status}: {body}")))
+    }
+}
+
+fn backend_error(e: impl Display) -> CoreError {
+    CoreError::ServiceUnavailable(format!("meilisearch: {e}"))
+}
+
+/// Quote a value in a Meilisearch filter expression
+fn filter_value(value: impl Display) -> String {
+    format!("\"{value}\"")
+}
+
+#[async_trait::async_trait]
+impl SearchIndex for MeilisearchMessageSearchRepository {
+    async fn index_messages(&self, messages: &[Message]) -> Result<(), CoreError> {
+        if messages.is_empty() {
+            return Ok(());
+        }
+
+        // PUT adds or updates, so the archived flag of known messages is kept
+        let documents: Vec<MessageDocument> = messages.iter().map(MessageDocument::from).collect();
+        self.send(
+            self.request(Method::PUT, "/documents?primaryKey=id")
+                .json(&documents),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn remove_message(&self, id: &MessageId) -> Result<(), CoreError> {
+        // unknown documents are ignored by Meilisearch
+        self.send(self.request(Method::DELETE, &format!("/documents/{}", id.0)))
+            .await?;
+
+        Ok(())
+    }
+
+    async fn set_archived(&self, ids: &[MessageId], archived: bool) -> Result<(), CoreError> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        let updates: Vec<_> = ids
+            .iter()
+            .map(|id| json!({ "id": id.0, "archived": archived }))
+            .collect();
+        self.send(
+            self.request(Method::PUT, "/documents?primaryKey=id")
+                .json(&updates),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn search(&self, query: &SearchQuery) -> Result<Vec<SearchHit>, CoreError> {
+        let mut filter = Vec::new();
+        if let Some(channel_id) = query.channel_id {
+            filter.push(format!("channel_id = {}", filter_value(channel_id.0)));
+        }
+        if let Some(author_id) = query.author_id {
+            filter.push(format!("author_id = {}", filter_value(author_id.0)));
+        }
+        if !query.include_archived {
+            // also matches messages indexed without the flag
+            filter.push("archived != true".to_string());
+        }
+
+        let body = json!({
+            "q": query.text,
+            "limit": query.limit,
+            "filter": filter,
+            "attributesToRetrieve": ["id", "archived"],
+            "showRankingScore": true,
+        });
+        let result: SearchResponse = self
+            .send(self.request(Method::POST, "/search").json(&body))
+            .await?
+            .json()
+            .await
+            .map_err(backend_error)?;
+
+        Ok(result
+            .hits
+            .into_iter()
+            .map(|hit| SearchHit {
+                message_id: MessageId::from(hit.id),
+                score: hit.ranking_score.unwrap_or_default(),
+                archived: hit.archived,
+            })
+            .collect())
+    }
+}
+
+#[async_trait::async_trait]
+impl MessageSearchRepository for MeilisearchMessageSearchRepository {
+    async fn ensure_index(&self) -> Result<(), CoreError> {
+        // updating the settings of a missing index creates it
+        let settings = json!({
+            "searchableAttributes": ["content"],
+            "filterableAttributes": ["channel_id", "author_id", "archived"],
+        });
+        self.send(self.request(Method::PATCH, "/settings").json(&settings))
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,36 @@
+//! Feeding external search backends without overloading them
+//!
+//! - `SearchIndexer` owns a bounded queue drained by a single consumer task:
+//!   producers wait when the queue is full (backpressure) and the consumer
+//!   never sends more than `max_batches_per_second` batches to the backend
+//! - `reindex_channel` rebuilds the index of a channel from MongoDB through
+//!   the same queue, so a rebuild is throttled like any bulk import
+//! - `MongoTextSearchIndex` searches the `messages` collection itself through
+//!   a `$text` index, scoring hits by `textScore`
+//! - `ElasticsearchMessageSearchRepository` (feature `elasticsearch`) keeps
+//!   its own copy of the messages, fed by a `SearchIndexer` following
+//!   message events, for fuzzy full-text search
+//! - `MeilisearchMessageSearchRepository` (feature `meilisearch`) does the
+//!   same with Meilisearch, lighter to run for small deployments
+//! - `TantivyMessageSearchRepository` (feature `tantivy`) embeds the index in
+//!   the process, for single-node deployments without a search service
+
+#[cfg(feature = "elasticsearch")]
+mod elasticsearch;
+mod indexer;
+#[cfg(feature = "meilisearch")]
+mod meilisearch;
+mod mongo_text;
+mod reindex;
+#[cfg(feature = "tantivy")]
+mod tantivy_index;
+
+#[cfg(feature = "elasticsearch")]
+pub use elasticsearch::ElasticsearchMessageSearchRepository;
+pub use indexer::{IndexCommand, SearchIndexer, SearchIndexerConfig};
+#[cfg(feature = "meilisearch")]
+pub use meilisearch::MeilisearchMessageSearchRepository;
+pub use mongo_text::{CONTENT_TEXT_INDEX, MongoTextSearchIndex};
+pub use reindex::reindex_channel;
+#[cfg(feature = "tantivy")]
+pub use tantivy_index::TantivyMessageSearchRepository;
@@ -0,0 +1,132 @@
+use futures::TryStreamExt;
+use mongodb::{
+    Collection, Database, IndexModel,
+    bson::{Bson, Document, doc},
+    options::IndexOptions,
+};
+
+use crate::{
+    domain::{
+        common::CoreError,
+        message::entities::{Message, MessageId},
+        search::{
+            entities::{SearchHit, SearchQuery},
+            ports::SearchIndex,
+        },
+    },
+    infrastructure::message::{dto::binary_to_uuid, encoding::uuid_match},
+};
+
+/// Name of the `$text` index on message contents
+pub const CONTENT_TEXT_INDEX: &str = "content_text";
+
+/// Search backend querying the `messages` collection through a MongoDB `$text` index.
+///
+/// MongoDB maintains the index itself, so indexing calls are no-ops. Archived
+/// messages have left the collection and are never returned. Every structured
+/// filter of the query is part of the MongoDB filter.
+#[derive(Clone)]
+pub struct MongoTextSearchIndex {
+    collection: Collection<Document>,
+}
+
+impl MongoTextSearchIndex {
+    pub fn new(db: &Database) -> Self {
+        Self {
+            collection: db.collection("messages"),
+        }
+    }
+
+    /// Create the `$text` index on `content` (idempotent)
+    pub async fn ensure_indexes(&self) -> Result<(), CoreError> {
+        self.collection
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! { "content": "text" })
+                    .options(
+                        IndexOptions::builder()
+                            .name(CONTENT_TEXT_INDEX.to_string())
+                            .build(),
+                    )
+                    .build(),
+            )
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl SearchIndex for MongoTextSearchIndex {
+    async fn index_messages(&self, _messages: &[Message]) -> Result<(), CoreError> {
+        Ok(())
+    }
+
+    async fn remove_message(&self, _id: &MessageId) -> Result<(), CoreError> {
+        Ok(())
+    }
+
+    async fn set_archived(&self, _ids: &[MessageId], _archived: bool) -> Result<(), CoreError> {
+        Ok(())
+    }
+
+    async fn search(&self, query: &SearchQuery) -> Result<Vec<SearchHit>, CoreError> {
+        let mut filter = doc! {
+            "$text": { "$search": query.text.as_str() },
+            "deleted_at": Bson::Null,
+            "expires_at": { "$not": { "$lte": mongodb::bson::DateTime::now() } },
+        };
+        if let Some(channel_id) = query.channel_id {
+            filter.insert("channel_id", uuid_match(channel_id.0));
+        }
+        if let Some(author_id) = query.author_id {
+            filter.insert("author_id", uuid_match(author_id.0));
+        }
+        let mut created_at = Document::new();
+        if let Some(before) = query.before {
+            created_at.insert("$lt", before.to_rfc3339());
+        }
+        if let Some(after) = query.after {
+            created_at.insert("$gt", after.to_rfc3339());
+        }
+        if !created_at.is_empty() {
+            filter.insert("created_at", created_at);
+        }
+        if let Some(has_attachments) = query.has_attachments {
+            filter.insert("attachments.0", doc! { "$exists": has_attachments });
+        }
+        if let Some(pinned) = query.pinned {
+            filter.insert("is_pinned", pinned);
+        }
+
+        let score = doc! { "score": { "$meta": "textScore" } };
+        let documents: Vec<Document> = self
+            .collection
+            .find(filter)
+            .projection(doc! { "_id": 1, "score": { "$meta": "textScore" } })
+            .sort(score)
+            .limit(i64::from(query.limit))
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?
+            .try_collect()
+            .await
+            .map_err(|e| CoreError::DatabaseError { msg: e.to_string() })?;
+
+        documents
+            .iter()
+            .map(|document| {
+                let Some(Bson::Binary(id)) = document.get("_id") else {
+                    return Err(CoreError::DatabaseError {
+                        msg: "search hit without a binary _id".to_string(),
+                    });
+                };
+                Ok(SearchHit {
+                    message_id: MessageId::from(binary_to_uuid(id)?),
+                    score: document.get_f64("score").unwrap_or_default() as f32,
+                    archived: false,
+                })
+            })
+            .collect()
+    }
+}
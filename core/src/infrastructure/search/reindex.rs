@@ -0,0 +1,48 @@
+use crate::{
+    domain::{
+        common::{CoreError, GetPaginated, MAX_PAGE_LIMIT},
+        message::{entities::ChannelId, ports::MessageRepository},
+    },
+    infrastructure::search::indexer::{IndexCommand, SearchIndexer},
+};
+
+/// Re-submit every message of a channel to the search indexer.
+///
+/// Returns the number of messages queued. Throughput is bounded by the
+/// indexer, which makes this call wait whenever its queue is full.
+#[tracing::instrument(skip(repository, indexer))]
+pub async fn reindex_channel<R>(
+    repository: &R,
+    indexer: &SearchIndexer,
+    channel_id: &ChannelId,
+) -> Result<u64, CoreError>
+where
+    R: MessageRepository,
+{
+    let mut page = 1;
+    let mut queued = 0;
+
+    loop {
+        let pagination = GetPaginated {
+            page,
+            limit: MAX_PAGE_LIMIT,
+        };
+        let (messages, total) = repository.list(channel_id, &pagination).await?;
+        if messages.is_empty() {
+            break;
+        }
+
+        for message in messages {
+            indexer.submit(IndexCommand::Index(Box::new(message))).await?;
+            queued += 1;
+        }
+        tracing::info!(queued, total, "reindexing channel");
+
+        if queued >= total {
+            break;
+        }
+        page += 1;
+    }
+
+    Ok(queued)
+}
@@ -0,0 +1,313 @@
+use std::{
+    fmt::Display,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use tantivy::{
+    Index, IndexReader, IndexWriter, ReloadPolicy, TantivyDocument, Term,
+    collector::TopDocs,
+    directory::MmapDirectory,
+    doc,
+    query::{BooleanQuery, Occur, Query, QueryParser, TermQuery},
+    schema::{Field, INDEXED, IndexRecordOption, STORED, STRING, Schema, TEXT, Value},
+};
+use tokio::task::spawn_blocking;
+use uuid::Uuid;
+
+use crate::domain::{
+    common::CoreError,
+    message::entities::{Message, MessageId},
+    search::{
+        entities::{SearchHit, SearchQuery},
+        ports::{MessageSearchRepository, SearchIndex},
+    },
+};
+
+/// Memory the index writer may use before flushing to disk
+const WRITER_MEMORY_BYTES: usize = 50_000_000;
+
+#[derive(Clone, Copy)]
+struct Fields {
+    id: Field,
+    channel_id: Field,
+    author_id: Field,
+    content: Field,
+    archived: Field,
+}
+
+/// Search backend embedding a Tantivy index, for single-node deployments
+/// without any search service to run.
+///
+/// The index lives in a local directory locked by this process, so only one
+/// instance can use it at a time. Contents match with one typo per word.
+/// Every write is committed right away and visible to the next search.
+#[derive(Clone)]
+pub struct TantivyMessageSearchRepository {
+    index: Index,
+    reader: IndexReader,
+    writer: Arc<Mutex<IndexWriter>>,
+    fields: Fields,
+}
+
+fn schema() -> (Schema, Fields) {
+    let mut builder = Schema::builder();
+    let fields = Fields {
+        id: builder.add_text_field("id", STRING | STORED),
+        channel_id: builder.add_text_field("channel_id", STRING | STORED),
+        author_id: builder.add_text_field("author_id", STRING | STORED),
+        content: builder.add_text_field("content", TEXT | STORED),
+        archived: builder.add_bool_field("archived", INDEXED | STORED),
+    };
+    (builder.build(), fields)
+}
+
+fn backend_error(e: impl Display) -> CoreError {
+    CoreError::ServiceUnavailable(format!("tantivy: {e}"))
+}
+
+impl TantivyMessageSearchRepository {
+    /// Open the index stored in `path`, creating it when missing
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, CoreError> {
+        std::fs::create_dir_all(path.as_ref()).map_err(backend_error)?;
+        let directory = MmapDirectory::open(path).map_err(backend_error)?;
+        let (schema, fields) = schema();
+        let index = Index::open_or_create(directory, schema).map_err(backend_error)?;
+        Self::from_index(index, fields)
+    }
+
+    /// Index held in memory and lost when dropped
+    pub fn in_memory() -> Result<Self, CoreError> {
+        let (schema, fields) = schema();
+        Self::from_index(Index::create_in_ram(schema), fields)
+    }
+
+    fn from_index(index: Index, fields: Fields) -> Result<Self, CoreError> {
+        let writer = index.writer(WRITER_MEMORY_BYTES).map_err(backend_error)?;
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()
+            .map_err(backend_error)?;
+
+        Ok(Self {
+            index,
+            reader,
+            writer: Arc::new(Mutex::new(writer)),
+            fields,
+        })
+    }
+
+    /// Run `write` on a blocking thread, then commit and reload the reader
+    async fn write<F>(&self, write: F) -> Result<(), CoreError>
+    where
+        F: FnOnce(&Self, &IndexWriter) -> Result<(), CoreError> + Send + 'static,
+    {
+        let this = self.clone();
+        spawn_blocking(move || {
+            let mut writer = this
+                .writer
+                .lock()
+                .map_err(|_| backend_error("index writer poisoned"))?;
+            write(&this, &writer)?;
+            writer.commit().map_err(backend_error)?;
+            this.reader.reload().map_err(backend_error)
+        })
+        .await
+        .map_err(backend_error)?
+    }
+
+    fn text_term(field: Field, id: Uuid) -> Term {
+        Term::from_field_text(field, &id.to_string())
+    }
+
+    /// Committed copy of a message
+    fn stored(&self, id: Uuid) -> Result<Option<TantivyDocument>, CoreError> {
+        let searcher = self.reader.searcher();
+        let query = TermQuery::new(
+            Self::text_term(self.fields.id, id),
+            IndexRecordOption::Basic,
+        );
+        let hits = searcher
+            .search(&query, &TopDocs::with_limit(1))
+            .map_err(backend_error)?;
+
+        hits.first()
+            .map(|(_, address)| searcher.doc(*address))
+            .transpose()
+            .map_err(backend_error)
+    }
+
+    fn is_archived(&self, document: &TantivyDocument) -> bool {
+        document
+            .get_first(self.fields.archived)
+            .and_then(|value| value.as_bool())
+            .unwrap_or(false)
+    }
+
+    fn document(&self, message: &Message, archived: bool) -> TantivyDocument {
+        let fields = self.fields;
+        doc!(
+            fields.id => message.id.0.to_string(),
+            fields.channel_id => message.channel_id.0.to_string(),
+            fields.author_id => message.author_id.0.to_string(),
+            fields.content => message.content.clone(),
+            fields.archived => archived,
+        )
+    }
+
+    /// Copy of a stored document with another archived flag
+    fn with_archived(&self, stored: &TantivyDocument, archived: bool) -> TantivyDocument {
+        let fields = self.fields;
+        let text = |field: Field| {
+            stored
+                .get_first(field)
+                .and_then(|value| value.as_str())
+                .unwrap_or_default()
+                .to_string()
+        };
+        doc!(
+            fields.id => text(fields.id),
+            fields.channel_id => text(fields.channel_id),
+            fields.author_id => text(fields.author_id),
+            fields.content => text(fields.content),
+            fields.archived => archived,
+        )
+    }
+
+    fn search_blocking(&self, query: &SearchQuery) -> Result<Vec<SearchHit>, CoreError> {
+        if query.limit == 0 {
+            return Ok(Vec::new());
+        }
+
+        let mut parser = QueryParser::for_index(&self.index, vec![self.fields.content]);
+        parser.set_field_fuzzy(self.fields.content, false, 1, true);
+        // user input is searched as is, syntax errors only drop the faulty part
+        let (text, _errors) = parser.parse_query_lenient(&query.text);
+
+        let mut clauses: Vec<(Occur, Box<dyn Query>)> = vec![(Occur::Must, text)];
+        if let Some(channel_id) = query.channel_id {
+            clauses.push((
+                Occur::Must,
+                Box::new(TermQuery::new(
+                    Self::text_term(self.fields.channel_id, channel_id.0),
+                    IndexRecordOption::Basic,
+                )),
+            ));
+        }
+        if let Some(author_id) = query.author_id {
+            clauses.push((
+                Occur::Must,
+                Box::new(TermQuery::new(
+                    Self::text_term(self.fields.author_id, author_id.0),
+                    IndexRecordOption::Basic,
+                )),
+            ));
+        }
+        if !query.include_archived {
+            clauses.push((
+                Occur::MustNot,
+                Box::new(TermQuery::new(
+                    Term::from_field_bool(self.fields.archived, true),
+                    IndexRecordOption::Basic,
+                )),
+            ));
+        }
+
+        let searcher = self.reader.searcher();
+        let hits = searcher
+            .search(
+                &BooleanQuery::new(clauses),
+                &TopDocs::with_limit(query.limit as usize),
+            )
+            .map_err(backend_error)?;
+
+        hits.into_iter()
+            .map(|(score, address)| {
+                let document: TantivyDocument = searcher.doc(address).map_err(backend_error)?;
+                let id = document
+                    .get_first(self.fields.id)
+                    .and_then(|value| value.as_str())
+                    .and_then(|id| Uuid::try_parse(id).ok())
+                    .ok_or_else(|| backend_error("indexed message without a valid id"))?;
+
+                Ok(SearchHit {
+                    message_id: MessageId::from(id),
+                    score,
+                    archived: self.is_archived(&document),
+                })
+            })
+            .collect()
+    }
+}
+
+#[async_trait::async_trait]
+impl SearchIndex for TantivyMessageSearchRepository {
+    async fn index_messages(&self, messages: &[Message]) -> Result<(), CoreError> {
+        if messages.is_empty() {
+            return Ok(());
+        }
+
+        let messages = messages.to_vec();
+        self.write(move |this, writer| {
+            for message in &messages {
+                // documents are replaced as a whole, so the archived flag is carried over
+                let archived = this
+                    .stored(message.id.0)?
+                    .is_some_and(|stored| this.is_archived(&stored));
+                writer.delete_term(Self::text_term(this.fields.id, message.id.0));
+                writer
+                    .add_document(this.document(message, archived))
+                    .map_err(backend_error)?;
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    async fn remove_message(&self, id: &MessageId) -> Result<(), CoreError> {
+        let id = id.0;
+        self.write(move |this, writer| {
+            writer.delete_term(Self::text_term(this.fields.id, id));
+            Ok(())
+        })
+        .await
+    }
+
+    async fn set_archived(&self, ids: &[MessageId], archived: bool) -> Result<(), CoreError> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        let ids: Vec<Uuid> = ids.iter().map(|id| id.0).collect();
+        self.write(move |this, writer| {
+            for id in ids {
+                let Some(stored) = this.stored(id)? else {
+                    continue;
+                };
+                writer.delete_term(Self::text_term(this.fields.id, id));
+                writer
+                    .add_document(this.with_archived(&stored, archived))
+                    .map_err(backend_error)?;
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    async fn search(&self, query: &SearchQuery) -> Result<Vec<SearchHit>, CoreError> {
+        let this = self.clone();
+        let query = query.clone();
+        spawn_blocking(move || this.search_blocking(&query))
+            .await
+            .map_err(backend_error)?
+    }
+}
+
+#[async_trait::async_trait]
+impl MessageSearchRepository for TantivyMessageSearchRepository {
+    async fn ensure_index(&self) -> Result<(), CoreError> {
+        // created when opened
+        Ok(())
+    }
+}
@@ -1,5 +1,6 @@
 pub mod application;
 pub mod domain;
+pub mod embedded;
 pub mod infrastructure;
 
 // Re-export commonly used types for convenience
@@ -9,4 +10,4 @@ pub use infrastructure::health::repositories::mongo::MongoHealthRepository;
 pub use infrastructure::message::repositories::mongo::MongoMessageRepository;
 
 // Re-export outbox pattern primitives
-pub use infrastructure::outbox::write_outbox_event;
+pub use infrastructure::outbox::{write_outbox_event, write_outbox_event_with_policy};
@@ -0,0 +1,123 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use communities_core::domain::common::CoreError;
+use communities_core::domain::common::services::Service;
+use communities_core::domain::health::port::MockHealthRepository;
+use communities_core::domain::message::entities::{
+    ATTACHMENT_SCANNER_ID, Attachment, AuthorId, ChannelId, InsertMessageInput, MALWARE_REASON_CODE, Message, MessageId,
+    PresignAttachmentInput, ScanVerdict,
+};
+use communities_core::domain::message::ports::{AttachmentScanner, InMemoryAttachmentStorage, MessageService, MockMessageRepository};
+use communities_core::infrastructure::attachment::AttachmentScanWorker;
+use uuid::Uuid;
+
+const EICAR: &[u8] = b"X5O!P%@AP[4\\PZX54(P^)7CC)7}$EICAR-STANDARD-ANTIVIRUS-TEST-FILE!$H+H*";
+
+/// Recognizes the EICAR test file anywhere in the content
+struct EicarScanner;
+
+#[async_trait::async_trait]
+impl AttachmentScanner for EicarScanner {
+    async fn scan(&self, content: &[u8]) -> Result<ScanVerdict, CoreError> {
+        if content.windows(EICAR.len()).any(|window| window == EICAR) {
+            return Ok(ScanVerdict::Infected { signature: "Eicar-Test-Signature".into() });
+        }
+        Ok(ScanVerdict::Clean)
+    }
+}
+
+fn service(repo: &MockMessageRepository, files: &InMemoryAttachmentStorage) -> Service<MockMessageRepository, MockHealthRepository> {
+    Service::new(repo.clone(), MockHealthRepository::new())
+        .with_attachment_storage(Arc::new(files.clone()))
+        .with_attachment_scanner(Arc::new(EicarScanner))
+}
+
+async fn message_with_file(service: &impl MessageService, files: &InMemoryAttachmentStorage, content: &[u8]) -> Message {
+    let channel_id = ChannelId::from(Uuid::new_v4());
+    let author_id = AuthorId::from(Uuid::new_v4());
+    let presigned = service
+        .presign_attachment(PresignAttachmentInput {
+            channel_id,
+            uploader_id: author_id,
+            name: "file.bin".into(),
+            content_type: "application/octet-stream".into(),
+            size: content.len() as u64,
+        })
+        .await
+        .expect("presign");
+    files.upload_content(&presigned.upload.object_key, content);
+
+    service
+        .create_message(InsertMessageInput {
+            id: MessageId::from(Uuid::new_v4()),
+            channel_id,
+            author_id,
+            content: "see attached".into(),
+            reply_to_message_id: None,
+            attachments: vec![Attachment::reference(presigned.upload.id)],
+            nonce: None,
+            expires_at: None,
+        })
+        .await
+        .expect("create")
+}
+
+#[tokio::test]
+async fn clean_attachments_leave_their_message_posted() {
+    let repo = MockMessageRepository::new();
+    let files = InMemoryAttachmentStorage::new();
+    let service = service(&repo, &files);
+
+    let message = message_with_file(&service, &files, b"holiday pictures").await;
+
+    assert_eq!(service.scan_attachments(&message).await.expect("scan"), ScanVerdict::Clean);
+    assert!(service.get_message(&message.id).await.is_ok());
+    assert!(repo.reported_quarantines().is_empty());
+}
+
+#[tokio::test]
+async fn infected_attachments_quarantine_their_message() {
+    let repo = MockMessageRepository::new();
+    let files = InMemoryAttachmentStorage::new();
+    let service = service(&repo, &files);
+
+    let message = message_with_file(&service, &files, EICAR).await;
+
+    let verdict = service.scan_attachments(&message).await.expect("scan");
+    assert_eq!(verdict, ScanVerdict::Infected { signature: "Eicar-Test-Signature".into() });
+    assert!(service.get_message(&message.id).await.is_err());
+
+    // soft deleted by the scanner, so moderators can still review it
+    let deletions = repo.bulk_deletions();
+    assert_eq!(deletions.len(), 1);
+    assert_eq!(deletions[0].ids, vec![message.id]);
+    assert_eq!(deletions[0].deleted_by, ATTACHMENT_SCANNER_ID);
+    assert_eq!(deletions[0].reason.as_ref().map(|reason| reason.code.as_str()), Some(MALWARE_REASON_CODE));
+
+    let quarantines = repo.reported_quarantines();
+    assert_eq!(quarantines.len(), 1);
+    assert_eq!(quarantines[0].message_id, message.id);
+    assert_eq!(quarantines[0].author_id, message.author_id);
+    assert_eq!(quarantines[0].attachment_id, message.attachments[0].id);
+    assert_eq!(quarantines[0].signature, "Eicar-Test-Signature");
+}
+
+#[tokio::test]
+async fn the_worker_scans_new_messages_in_the_background() {
+    let repo = MockMessageRepository::new();
+    let files = InMemoryAttachmentStorage::new();
+    let service = service(&repo, &files);
+    AttachmentScanWorker::new(service.clone(), 2).follow(service.events());
+
+    let message = message_with_file(&service, &files, EICAR).await;
+
+    tokio::time::timeout(Duration::from_secs(2), async {
+        while repo.reported_quarantines().is_empty() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("the message should be quarantined");
+    assert_eq!(repo.reported_quarantines()[0].message_id, message.id);
+}
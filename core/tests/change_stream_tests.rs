@@ -0,0 +1,87 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use communities_core::domain::message::entities::{AuthorId, ChannelId, Mentions, Message, MessageId};
+use communities_core::domain::message::events::MessageEvent;
+use communities_core::infrastructure::message::change_stream::{TokenCheckpoint, to_message_event};
+use communities_core::infrastructure::message::dto::{MessageDocument, uuid_to_binary};
+use mongodb::bson::doc;
+use mongodb::change_stream::event::OperationType;
+use uuid::Uuid;
+
+fn sample_document() -> MessageDocument {
+    MessageDocument::from(&Message {
+        id: MessageId::from(Uuid::new_v4()),
+        channel_id: ChannelId::from(Uuid::new_v4()),
+        author_id: AuthorId::from(Uuid::new_v4()),
+        content: "from the change stream".into(),
+        reply_to_message_id: None,
+        attachments: vec![],
+        is_pinned: false,
+        reactions: vec![],
+        revision: 1,
+        sequence: 0,
+        reply_count: 0,
+        last_reply_at: None,
+        archived_at: None,
+        created_at: Utc::now(),
+        updated_at: None,
+        deleted_at: None,
+        nonce: None,
+        embeds: Vec::new(),
+        mentions: Mentions::default(),
+        expires_at: None,
+    })
+}
+
+#[test]
+fn insert_and_update_changes_carry_the_full_document() {
+    let document = sample_document();
+
+    let created = to_message_event(OperationType::Insert, Some(document.clone()), None, None).unwrap();
+    assert!(matches!(created, Some(MessageEvent::Created(m)) if m.content == "from the change stream"));
+
+    let updated = to_message_event(OperationType::Update, Some(document), None, None).unwrap();
+    assert!(matches!(updated, Some(MessageEvent::Updated(m)) if m.revision == 1));
+
+    // the message was deleted before the update could be looked up
+    let gone = to_message_event(OperationType::Update, None, None, None).unwrap();
+    assert!(gone.is_none());
+}
+
+#[test]
+fn delete_change_needs_a_pre_image_for_the_channel() {
+    let document = sample_document();
+    let id = MessageId(Uuid::from_slice(&document.id.bytes).unwrap());
+    let channel = ChannelId(Uuid::from_slice(&document.channel_id.bytes).unwrap());
+    let key = doc! { "_id": uuid_to_binary(id.0) };
+
+    let deleted = to_message_event(OperationType::Delete, None, Some(document), Some(key.clone())).unwrap();
+    match deleted {
+        Some(MessageEvent::Deleted { id: deleted_id, channel_id }) => {
+            assert_eq!(deleted_id, id);
+            assert_eq!(channel_id, channel);
+        }
+        other => panic!("expected a deleted event, got {other:?}"),
+    }
+
+    let without_pre_image = to_message_event(OperationType::Delete, None, None, Some(key.clone())).unwrap();
+    assert!(without_pre_image.is_none());
+
+    // purging a soft deleted message announces nothing new
+    let soft_deleted = MessageDocument { deleted_at: Some(Utc::now().to_rfc3339()), ..sample_document() };
+    let purged = to_message_event(OperationType::Delete, None, Some(soft_deleted), Some(key)).unwrap();
+    assert!(purged.is_none());
+
+    assert!(to_message_event(OperationType::Drop, None, None, None).unwrap().is_none());
+}
+
+#[test]
+fn resume_tokens_are_saved_every_few_events_or_seconds() {
+    let checkpoint = TokenCheckpoint { every: 3, interval: Duration::from_secs(5) };
+
+    assert!(!checkpoint.is_due(0, Duration::from_secs(60)));
+    assert!(!checkpoint.is_due(2, Duration::from_secs(1)));
+    assert!(checkpoint.is_due(3, Duration::from_secs(1)));
+    assert!(checkpoint.is_due(1, Duration::from_secs(5)));
+}
@@ -0,0 +1,190 @@
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+use communities_core::domain::common::CoreError;
+use communities_core::domain::common::services::Service;
+use communities_core::domain::health::port::MockHealthRepository;
+use communities_core::domain::membership::entities::ChannelMember;
+use communities_core::domain::membership::ports::{
+    ChannelMembership, InMemoryChannelMembership, MemberDirectory,
+};
+use communities_core::domain::message::entities::{
+    AuthorId, ChannelId, InsertMessageInput, MessageId, NotificationFanoutEvent, NotificationKind,
+    UserId,
+};
+use communities_core::domain::message::ports::{MessageService, MockMessageRepository};
+use uuid::Uuid;
+
+fn member(channel_id: ChannelId, user_id: UserId, role_ids: Vec<Uuid>, minutes_ago: i64) -> ChannelMember {
+    ChannelMember { channel_id, user_id, role_ids, updated_at: Utc::now() - Duration::minutes(minutes_ago) }
+}
+
+#[tokio::test]
+async fn outdated_membership_changes_are_skipped() {
+    let membership = InMemoryChannelMembership::new();
+    let channel_id = ChannelId::from(Uuid::new_v4());
+    let user_id = UserId(Uuid::new_v4());
+
+    assert!(membership.upsert_member(&member(channel_id, user_id, vec![], 10)).await.unwrap());
+    assert!(membership.remove_member(&channel_id, &user_id, Utc::now() - Duration::minutes(5)).await.unwrap());
+    // the join delivered late does not bring them back
+    assert!(!membership.upsert_member(&member(channel_id, user_id, vec![], 8)).await.unwrap());
+    assert!(membership.list_members(&channel_id, None).await.unwrap().is_empty());
+
+    assert!(membership.upsert_member(&member(channel_id, user_id, vec![], 1)).await.unwrap());
+    assert_eq!(membership.list_members(&channel_id, None).await.unwrap(), vec![user_id]);
+}
+
+#[tokio::test]
+async fn members_are_listed_by_role() {
+    let membership = InMemoryChannelMembership::new();
+    let channel_id = ChannelId::from(Uuid::new_v4());
+    let (moderators, readers) = (Uuid::new_v4(), Uuid::new_v4());
+    let (alice, bob) = (UserId(Uuid::new_v4()), UserId(Uuid::new_v4()));
+    membership.upsert_member(&member(channel_id, alice, vec![moderators, readers], 1)).await.unwrap();
+    membership.upsert_member(&member(channel_id, bob, vec![readers], 1)).await.unwrap();
+    membership.upsert_member(&member(ChannelId::from(Uuid::new_v4()), UserId(Uuid::new_v4()), vec![moderators], 1)).await.unwrap();
+
+    assert_eq!(membership.list_members(&channel_id, Some(&[moderators])).await.unwrap(), vec![alice]);
+    assert_eq!(membership.list_members(&channel_id, Some(&[readers])).await.unwrap().len(), 2);
+    assert_eq!(membership.list_members(&channel_id, None).await.unwrap().len(), 2);
+}
+
+fn post(channel_id: ChannelId, author: UserId, content: String) -> InsertMessageInput {
+    InsertMessageInput {
+        id: MessageId::from(Uuid::new_v4()),
+        channel_id,
+        author_id: AuthorId::from(author.0),
+        content,
+        reply_to_message_id: None,
+        attachments: vec![],
+        nonce: None,
+        expires_at: None,
+    }
+}
+
+/// Directory answering the same members for any role
+struct FixedDirectory(Vec<UserId>);
+
+#[async_trait::async_trait]
+impl MemberDirectory for FixedDirectory {
+    async fn role_members(&self, _channel_id: &ChannelId, _role_ids: &[Uuid]) -> Result<Vec<UserId>, CoreError> {
+        Ok(self.0.clone())
+    }
+}
+
+#[tokio::test]
+async fn group_mentions_notify_the_channel_members_once_each() {
+    let repo = MockMessageRepository::new();
+    let membership = InMemoryChannelMembership::new();
+    let service = Service::new(repo.clone(), MockHealthRepository::new())
+        .with_channel_membership(Arc::new(membership.clone()));
+    let channel_id = ChannelId::from(Uuid::new_v4());
+    let author = UserId(Uuid::new_v4());
+    let moderators = Uuid::new_v4();
+    let (alice, bob) = (UserId(Uuid::new_v4()), UserId(Uuid::new_v4()));
+    membership.upsert_member(&member(channel_id, author, vec![moderators], 1)).await.unwrap();
+    membership.upsert_member(&member(channel_id, alice, vec![moderators], 1)).await.unwrap();
+    membership.upsert_member(&member(channel_id, bob, vec![], 1)).await.unwrap();
+
+    // the author is never notified, and alice only once
+    let content = format!("<@&{moderators}> and <@{}>, look", alice.0);
+    let message = service.create_message(post(channel_id, author, content)).await.expect("create");
+    let fanouts = repo.requested_notification_fanouts();
+    assert_eq!(fanouts.len(), 1);
+    assert_eq!(fanouts[0].recipient_ids, vec![alice]);
+    assert_eq!(fanouts[0].kind, NotificationKind::Mention);
+    assert_eq!(fanouts[0].actor_id, author);
+    assert_eq!(fanouts[0].message_id, message.id);
+
+    service.create_message(post(channel_id, author, "hello @everyone!".to_string())).await.expect("create");
+    let mut recipients = repo.requested_notification_fanouts()[1].recipient_ids.clone();
+    recipients.sort_by_key(|user| user.0);
+    let mut expected = vec![alice, bob];
+    expected.sort_by_key(|user| user.0);
+    assert_eq!(recipients, expected);
+    assert!(repo.requested_notifications().is_empty());
+}
+
+#[tokio::test]
+async fn roles_are_expanded_by_the_member_directory() {
+    let repo = MockMessageRepository::new();
+    let membership = InMemoryChannelMembership::new();
+    let channel_id = ChannelId::from(Uuid::new_v4());
+    let author = UserId(Uuid::new_v4());
+    let (alice, bob) = (UserId(Uuid::new_v4()), UserId(Uuid::new_v4()));
+    // the snapshot lags behind: bob got the role, alice lost it
+    let moderators = Uuid::new_v4();
+    membership.upsert_member(&member(channel_id, alice, vec![moderators], 1)).await.unwrap();
+    let service = Service::new(repo.clone(), MockHealthRepository::new())
+        .with_channel_membership(Arc::new(membership))
+        .with_member_directory(Arc::new(FixedDirectory(vec![author, bob])));
+
+    let content = format!("<@&{moderators}>, look");
+    service.create_message(post(channel_id, author, content)).await.expect("create");
+
+    let fanouts = repo.requested_notification_fanouts();
+    assert_eq!(fanouts.len(), 1);
+    assert_eq!(fanouts[0].recipient_ids, vec![bob]);
+}
+
+#[tokio::test]
+async fn large_recipient_lists_are_split_across_events() {
+    let repo = MockMessageRepository::new();
+    let members: Vec<UserId> = (0..NotificationFanoutEvent::MAX_RECIPIENTS + 1).map(|_| UserId(Uuid::new_v4())).collect();
+    let service = Service::new(repo.clone(), MockHealthRepository::new())
+        .with_member_directory(Arc::new(FixedDirectory(members.clone())));
+    let content = format!("<@&{}>", Uuid::new_v4());
+
+    service.create_message(post(ChannelId::from(Uuid::new_v4()), UserId(Uuid::new_v4()), content)).await.expect("create");
+
+    let fanouts = repo.requested_notification_fanouts();
+    assert_eq!(fanouts.len(), 2);
+    assert_eq!(fanouts[0].recipient_ids.len(), NotificationFanoutEvent::MAX_RECIPIENTS);
+    let recipients: Vec<UserId> = fanouts.iter().flat_map(|fanout| fanout.recipient_ids.clone()).collect();
+    assert_eq!(recipients, members);
+}
+
+#[tokio::test]
+async fn mentions_past_the_fanout_limit_are_broadcast_to_their_groups() {
+    let repo = MockMessageRepository::new();
+    let author = UserId(Uuid::new_v4());
+    let members: Vec<UserId> = (0..3).map(|_| UserId(Uuid::new_v4())).collect();
+    let service = Service::new(repo.clone(), MockHealthRepository::new())
+        .with_member_directory(Arc::new(FixedDirectory(members)))
+        .with_mention_fanout_limit(Some(2));
+    let (channel_id, moderators, alice) = (ChannelId::from(Uuid::new_v4()), Uuid::new_v4(), UserId(Uuid::new_v4()));
+
+    // at the limit, recipients are still listed
+    service.create_message(post(channel_id, author, format!("<@{}> <@{}>", alice.0, author.0))).await.expect("create");
+    assert_eq!(repo.requested_notification_fanouts().len(), 1);
+
+    let content = format!("<@&{moderators}> <@{}> <@{}>", alice.0, author.0);
+    let message = service.create_message(post(channel_id, author, content)).await.expect("create");
+
+    assert_eq!(repo.requested_notification_fanouts().len(), 1);
+    let broadcasts = repo.requested_notification_broadcasts();
+    assert_eq!(broadcasts.len(), 1);
+    assert_eq!(broadcasts[0].channel_id, channel_id);
+    assert_eq!(broadcasts[0].role_ids, vec![moderators]);
+    assert!(!broadcasts[0].everyone);
+    assert_eq!(broadcasts[0].user_ids, vec![alice]);
+    assert_eq!(broadcasts[0].message_id, message.id);
+}
+
+#[tokio::test]
+async fn without_membership_only_named_users_are_notified() {
+    let repo = MockMessageRepository::new();
+    let service = Service::new(repo.clone(), MockHealthRepository::new());
+    let alice = UserId(Uuid::new_v4());
+
+    let content = format!("@everyone <@&{}> <@{}>", Uuid::new_v4(), alice.0);
+    service
+        .create_message(post(ChannelId::from(Uuid::new_v4()), UserId(Uuid::new_v4()), content))
+        .await
+        .expect("create");
+
+    let fanouts = repo.requested_notification_fanouts();
+    assert_eq!(fanouts.len(), 1);
+    assert_eq!(fanouts[0].recipient_ids, vec![alice]);
+}
@@ -0,0 +1,150 @@
+use std::sync::Arc;
+
+use communities_core::domain::common::CoreError;
+use communities_core::domain::common::services::Service;
+use communities_core::domain::health::port::MockHealthRepository;
+use communities_core::domain::message::entities::{
+    Attachment, AuthorId, ChannelId, InsertMessageInput, MessageId, PresignAttachmentInput,
+};
+use communities_core::domain::message::ports::{
+    InMemoryAttachmentStorage, MessageService, MockMessageRepository,
+};
+use uuid::Uuid;
+
+type TestService = Service<MockMessageRepository, MockHealthRepository>;
+
+fn service(files: &InMemoryAttachmentStorage) -> TestService {
+    Service::new(MockMessageRepository::new(), MockHealthRepository::new())
+        .with_attachment_storage(Arc::new(files.clone()))
+}
+
+/// A message with an attachment of `size` bytes presigned and uploaded by its
+/// author, without attachment when `size` is 0
+async fn message_with_attachment(
+    service: &TestService,
+    files: &InMemoryAttachmentStorage,
+    channel_id: ChannelId,
+    size: u64,
+) -> InsertMessageInput {
+    let author_id = AuthorId::from(Uuid::new_v4());
+    let mut attachments = Vec::new();
+    if size > 0 {
+        let presigned = service
+            .presign_attachment(PresignAttachmentInput {
+                channel_id,
+                uploader_id: author_id,
+                name: "file.bin".into(),
+                content_type: "application/octet-stream".into(),
+                size,
+            })
+            .await
+            .unwrap();
+        files.upload(&presigned.upload.object_key, size);
+        attachments.push(Attachment::reference(presigned.upload.id));
+    }
+
+    InsertMessageInput {
+        id: MessageId::from(Uuid::new_v4()),
+        channel_id,
+        author_id,
+        content: "see attached".into(),
+        reply_to_message_id: None,
+        attachments,
+        nonce: None,
+        expires_at: None,
+    }
+}
+
+#[tokio::test]
+async fn attachments_are_rejected_once_the_default_quota_is_exceeded() {
+    let files = InMemoryAttachmentStorage::new();
+    let service = service(&files).with_default_storage_quota(Some(100));
+    let channel = ChannelId::from(Uuid::new_v4());
+
+    // the quota is soft: the upload crossing it goes through
+    service
+        .create_message(message_with_attachment(&service, &files, channel, 60).await)
+        .await
+        .unwrap();
+    service
+        .create_message(message_with_attachment(&service, &files, channel, 60).await)
+        .await
+        .unwrap();
+
+    let storage = service.channel_storage(&channel).await.unwrap();
+    assert_eq!(storage.used_bytes, 120);
+    assert_eq!(storage.quota_bytes, Some(100));
+    assert!(!storage.custom_quota);
+
+    let result = service
+        .create_message(message_with_attachment(&service, &files, channel, 1).await)
+        .await;
+    assert!(matches!(
+        result,
+        Err(CoreError::ChannelStorageQuotaExceeded { channel_id }) if channel_id == channel
+    ));
+
+    // messages without attachments are not affected
+    service
+        .create_message(message_with_attachment(&service, &files, channel, 0).await)
+        .await
+        .unwrap();
+
+    // other channels have their own usage
+    let other = ChannelId::from(Uuid::new_v4());
+    service
+        .create_message(message_with_attachment(&service, &files, other, 60).await)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn custom_quotas_override_the_default_one() {
+    let files = InMemoryAttachmentStorage::new();
+    let service = service(&files).with_default_storage_quota(Some(10));
+    let channel = ChannelId::from(Uuid::new_v4());
+
+    let storage = service
+        .set_channel_storage_quota(&channel, Some(1000))
+        .await
+        .unwrap();
+    assert_eq!(storage.quota_bytes, Some(1000));
+    assert!(storage.custom_quota);
+
+    service
+        .create_message(message_with_attachment(&service, &files, channel, 500).await)
+        .await
+        .unwrap();
+    service
+        .create_message(message_with_attachment(&service, &files, channel, 1).await)
+        .await
+        .unwrap();
+
+    // reverting to the default quota applies it again
+    let storage = service
+        .set_channel_storage_quota(&channel, None)
+        .await
+        .unwrap();
+    assert_eq!(storage.quota_bytes, Some(10));
+    assert!(!storage.custom_quota);
+    assert!(storage.is_exceeded());
+}
+
+#[tokio::test]
+async fn channels_are_unlimited_without_a_default_quota() {
+    let files = InMemoryAttachmentStorage::new();
+    let service = service(&files);
+    let channel = ChannelId::from(Uuid::new_v4());
+
+    service
+        .create_message(message_with_attachment(&service, &files, channel, u64::MAX / 2).await)
+        .await
+        .unwrap();
+    service
+        .create_message(message_with_attachment(&service, &files, channel, 1).await)
+        .await
+        .unwrap();
+
+    let storage = service.channel_storage(&channel).await.unwrap();
+    assert_eq!(storage.quota_bytes, None);
+}
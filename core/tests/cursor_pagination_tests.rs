@@ -0,0 +1,189 @@
+use chrono::Utc;
+use communities_core::domain::common::{
+    CoreError, Cursor, GetCursorPaginated, GetMessagesByCursor, MessageBound,
+};
+use communities_core::domain::health::port::MockHealthRepository;
+use communities_core::domain::common::services::Service;
+use communities_core::domain::message::entities::{AuthorId, ChannelId, InsertMessageInput, MessageId};
+use communities_core::domain::message::ports::{MessageService, MockMessageRepository};
+use uuid::Uuid;
+
+#[test]
+fn cursor_round_trips_and_rejects_garbage() {
+    let cursor = Cursor::new(Utc::now(), Uuid::new_v4());
+    let encoded = cursor.encode();
+
+    assert!(encoded.chars().all(|c| c.is_ascii_hexdigit()));
+    assert_eq!(Cursor::decode(&encoded).expect("decode"), cursor);
+    assert!(matches!(Cursor::decode("not-a-cursor"), Err(CoreError::InvalidCursor)));
+    assert!(matches!(Cursor::decode("é1"), Err(CoreError::InvalidCursor)));
+}
+
+#[tokio::test]
+async fn replies_are_paginated_with_cursors() {
+    let service = Service::new(MockMessageRepository::new(), MockHealthRepository::new());
+    let channel = ChannelId::from(Uuid::new_v4());
+    let author = AuthorId::from(Uuid::new_v4());
+    let parent = MessageId::from(Uuid::new_v4());
+
+    let message = |id: MessageId, reply_to: Option<MessageId>| InsertMessageInput {
+        id,
+        channel_id: channel,
+        author_id: author,
+        content: "thread".into(),
+        reply_to_message_id: reply_to,
+        attachments: vec![],
+        nonce: None,
+        expires_at: None,
+    };
+
+    service.create_message(message(parent, None)).await.expect("create parent");
+    for _ in 0..5 {
+        service
+            .create_message(message(MessageId::from(Uuid::new_v4()), Some(parent)))
+            .await
+            .expect("create reply");
+    }
+
+    let mut pagination = GetCursorPaginated { cursor: None, limit: 2 };
+    let mut seen = Vec::new();
+    loop {
+        let page = service.list_replies(&parent, &pagination).await.expect("list replies");
+        assert!(page.items.len() <= 2);
+        seen.extend(page.items.into_iter().map(|m| m.id));
+        match page.next_cursor {
+            Some(next) => pagination.cursor = Some(next),
+            None => break,
+        }
+    }
+
+    assert_eq!(seen.len(), 5);
+    assert!(!seen.contains(&parent));
+}
+
+#[tokio::test]
+async fn channel_messages_are_paginated_newest_first() {
+    let service = Service::new(MockMessageRepository::new(), MockHealthRepository::new());
+    let channel = ChannelId::from(Uuid::new_v4());
+
+    for i in 0..5 {
+        service
+            .create_message(InsertMessageInput {
+                id: MessageId::from(Uuid::new_v4()),
+                channel_id: channel,
+                author_id: AuthorId::from(Uuid::new_v4()),
+                content: format!("message {i}"),
+                reply_to_message_id: None,
+                attachments: vec![],
+                nonce: None,
+                expires_at: None,
+            })
+            .await
+            .expect("create message");
+    }
+
+    let mut pagination = GetCursorPaginated { cursor: None, limit: 2 };
+    let mut seen = Vec::new();
+    loop {
+        let page = service
+            .list_messages_by_cursor(
+                &channel,
+                &GetMessagesByCursor {
+                    pagination: pagination.clone(),
+                    ..Default::default()
+                },
+            )
+            .await
+            .expect("list messages");
+        assert!(page.items.len() <= 2);
+        seen.extend(page.items);
+        match page.next_cursor {
+            Some(next) => pagination.cursor = Some(next),
+            None => break,
+        }
+    }
+
+    assert_eq!(seen.len(), 5);
+    assert!(seen.windows(2).all(|w| (w[0].created_at, w[0].id.0) > (w[1].created_at, w[1].id.0)));
+}
+
+#[tokio::test]
+async fn channel_listing_can_be_bounded() {
+    let service = Service::new(MockMessageRepository::new(), MockHealthRepository::new());
+    let channel = ChannelId::from(Uuid::new_v4());
+
+    let mut ids = Vec::new();
+    for i in 0..5 {
+        let message = service
+            .create_message(InsertMessageInput {
+                id: MessageId::from(Uuid::new_v4()),
+                channel_id: channel,
+                author_id: AuthorId::from(Uuid::new_v4()),
+                content: format!("message {i}"),
+                reply_to_message_id: None,
+                attachments: vec![],
+                nonce: None,
+                expires_at: None,
+            })
+            .await
+            .expect("create message");
+        ids.push(message.id);
+        tokio::time::sleep(std::time::Duration::from_millis(2)).await;
+    }
+
+    // strictly between the first and the last message
+    let query = GetMessagesByCursor {
+        pagination: GetCursorPaginated { cursor: None, limit: 10 },
+        before: Some(MessageBound::Message(ids[4])),
+        after: Some(MessageBound::Message(ids[0])),
+    };
+    let page = service.list_messages_by_cursor(&channel, &query).await.expect("list");
+    let listed: Vec<MessageId> = page.items.iter().map(|m| m.id).collect();
+    assert_eq!(listed, vec![ids[3], ids[2], ids[1]]);
+
+    // the cursor of the next page stays within the bounds
+    let query = GetMessagesByCursor {
+        pagination: GetCursorPaginated { cursor: None, limit: 1 },
+        after: Some(MessageBound::Message(ids[2])),
+        before: None,
+    };
+    let first = service.list_messages_by_cursor(&channel, &query).await.expect("list");
+    assert_eq!(first.items[0].id, ids[4]);
+    let query = GetMessagesByCursor {
+        pagination: GetCursorPaginated { cursor: first.next_cursor, limit: 1 },
+        ..query
+    };
+    let second = service.list_messages_by_cursor(&channel, &query).await.expect("list");
+    assert_eq!(second.items[0].id, ids[3]);
+    assert!(second.next_cursor.is_none());
+
+    // timestamps work as bounds too
+    let query = GetMessagesByCursor {
+        after: Some(MessageBound::Timestamp(Utc::now())),
+        ..Default::default()
+    };
+    let page = service.list_messages_by_cursor(&channel, &query).await.expect("list");
+    assert!(page.items.is_empty());
+
+    // a message of another channel is not a valid bound
+    let query = GetMessagesByCursor {
+        before: Some(MessageBound::Message(MessageId::from(Uuid::new_v4()))),
+        ..Default::default()
+    };
+    let res = service.list_messages_by_cursor(&channel, &query).await;
+    assert!(matches!(res, Err(CoreError::InvalidCursor)));
+}
+
+#[test]
+fn message_bounds_are_ids_or_timestamps() {
+    let id = Uuid::new_v4();
+    assert_eq!(
+        id.to_string().parse::<MessageBound>().unwrap(),
+        MessageBound::Message(MessageId::from(id))
+    );
+    assert!(matches!(
+        "2024-05-01T12:00:00Z".parse::<MessageBound>(),
+        Ok(MessageBound::Timestamp(_))
+    ));
+    assert!(matches!("yesterday".parse::<MessageBound>(), Err(CoreError::InvalidCursor)));
+}
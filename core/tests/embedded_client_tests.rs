@@ -0,0 +1,58 @@
+use communities_core::domain::common::{CoreError, GetPaginated};
+use communities_core::domain::message::entities::{
+    AuthorId, ChannelId, CreateMessageRequest, UpdateMessageRequest,
+};
+use communities_core::embedded::{InProcessMessageClient, MessageClient};
+use uuid::Uuid;
+
+/// Consumer code only knows about the trait
+async fn post_and_edit(client: &dyn MessageClient, channel_id: ChannelId) -> Result<(), CoreError> {
+    let message = client
+        .create_message(CreateMessageRequest {
+            channel_id,
+            content: "hello".into(),
+            reply_to_message_id: None,
+            attachments: vec![],
+            nonce: None,
+            expires_at: None,
+        })
+        .await?;
+    client
+        .update_message(
+            &message.id,
+            UpdateMessageRequest {
+                content: Some("hello, edited".into()),
+                is_pinned: None,
+            },
+        )
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn in_memory_client_behaves_like_the_service() {
+    let author = AuthorId::from(Uuid::new_v4());
+    let client = InProcessMessageClient::in_memory(author);
+    let channel = ChannelId::from(Uuid::new_v4());
+
+    post_and_edit(&client, channel)
+        .await
+        .expect("post and edit");
+
+    let (messages, total) = client
+        .list_messages(&channel, &GetPaginated::default())
+        .await
+        .expect("list");
+    assert_eq!(total, 1);
+    assert_eq!(messages[0].content, "hello, edited");
+    assert_eq!(messages[0].author_id, author);
+
+    // another user of the same service sees the same messages
+    let other = client.as_author(AuthorId::from(Uuid::new_v4()));
+    let fetched = other.get_message(&messages[0].id).await.expect("get");
+    assert_eq!(fetched.author_id, author);
+
+    other.delete_message(&fetched.id).await.expect("delete");
+    let res = client.get_message(&fetched.id).await;
+    assert!(matches!(res, Err(CoreError::MessageNotFound { .. })));
+}
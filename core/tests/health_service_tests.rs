@@ -0,0 +1,70 @@
+use std::sync::Arc;
+
+use communities_core::domain::common::CoreError;
+use communities_core::domain::common::services::Service;
+use communities_core::domain::health::entities::IsHealthy;
+use communities_core::domain::health::port::{
+    HealthProbe, HealthRepository, HealthService, MockHealthRepository,
+};
+use communities_core::domain::message::ports::MockMessageRepository;
+
+struct StaticProbe(&'static str, IsHealthy);
+
+#[async_trait::async_trait]
+impl HealthProbe for StaticProbe {
+    fn name(&self) -> &str {
+        self.0
+    }
+
+    async fn probe(&self) -> IsHealthy {
+        self.1
+    }
+}
+
+struct DownDatabase;
+
+impl HealthRepository for DownDatabase {
+    async fn ping(&self) -> IsHealthy {
+        IsHealthy::new(false)
+    }
+}
+
+#[tokio::test]
+async fn healthy_when_every_dependency_is_up() {
+    let service = Service::new(MockMessageRepository::new(), MockHealthRepository::new())
+        .with_health_probe(Arc::new(StaticProbe("search", IsHealthy::Healthy)));
+
+    let report = service.check_health().await.expect("healthy");
+    assert_eq!(report.status, IsHealthy::Healthy);
+    assert_eq!(report.components.len(), 1);
+}
+
+#[tokio::test]
+async fn optional_dependency_down_degrades_the_service() {
+    let service = Service::new(MockMessageRepository::new(), MockHealthRepository::new())
+        .with_health_probe(Arc::new(StaticProbe("search", IsHealthy::Unhealthy)))
+        .with_health_probe(Arc::new(StaticProbe("rabbitmq", IsHealthy::Healthy)));
+
+    let report = service
+        .check_health()
+        .await
+        .expect("degraded is still serving");
+    assert_eq!(report.status, IsHealthy::Degraded);
+    assert!(report.status.value());
+
+    let search = report
+        .components
+        .iter()
+        .find(|c| c.name == "search")
+        .unwrap();
+    assert_eq!(search.status, IsHealthy::Unhealthy);
+}
+
+#[tokio::test]
+async fn database_down_is_unhealthy() {
+    let service = Service::new(MockMessageRepository::new(), DownDatabase)
+        .with_health_probe(Arc::new(StaticProbe("search", IsHealthy::Healthy)));
+
+    let res = service.check_health().await;
+    assert!(matches!(res, Err(CoreError::Unhealthy)));
+}
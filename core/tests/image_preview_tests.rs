@@ -0,0 +1,88 @@
+use std::sync::Arc;
+
+use communities_core::domain::common::CoreError;
+use communities_core::domain::common::services::Service;
+use communities_core::domain::health::port::MockHealthRepository;
+use communities_core::domain::message::entities::{
+    Attachment, AuthorId, ChannelId, ImagePreview, InsertMessageInput, Message, MessageId, PresignAttachmentInput,
+};
+use communities_core::domain::message::ports::{InMemoryAttachmentStorage, MessageService, MockMessageRepository, ThumbnailGenerator};
+use uuid::Uuid;
+
+/// Previews every image as 640x480
+struct FixedPreviews;
+
+#[async_trait::async_trait]
+impl ThumbnailGenerator for FixedPreviews {
+    async fn preview(&self, url: &str, _content_type: &str) -> Result<Option<ImagePreview>, CoreError> {
+        Ok(Some(ImagePreview { width: 640, height: 480, thumbnail_url: Some(format!("{url}?thumbnail")) }))
+    }
+}
+
+struct FailingPreviews;
+
+#[async_trait::async_trait]
+impl ThumbnailGenerator for FailingPreviews {
+    async fn preview(&self, _url: &str, _content_type: &str) -> Result<Option<ImagePreview>, CoreError> {
+        Err(CoreError::ServiceUnavailable("thumbnail service down".into()))
+    }
+}
+
+async fn post(service: &impl MessageService, files: &InMemoryAttachmentStorage, name: &str, content_type: &str) -> Message {
+    let channel_id = ChannelId::from(Uuid::new_v4());
+    let author_id = AuthorId::from(Uuid::new_v4());
+    let presigned = service
+        .presign_attachment(PresignAttachmentInput { channel_id, uploader_id: author_id, name: name.into(), content_type: content_type.into(), size: 10 })
+        .await
+        .expect("presign");
+    files.upload(&presigned.upload.object_key, 10);
+
+    service
+        .create_message(InsertMessageInput {
+            id: MessageId::from(Uuid::new_v4()),
+            channel_id,
+            author_id,
+            content: "look".into(),
+            reply_to_message_id: None,
+            attachments: vec![Attachment::reference(presigned.upload.id)],
+            nonce: None,
+            expires_at: None,
+        })
+        .await
+        .expect("create")
+}
+
+#[tokio::test]
+async fn image_attachments_are_posted_with_a_preview() {
+    let repo = MockMessageRepository::new();
+    let files = InMemoryAttachmentStorage::new();
+    let service = Service::new(repo, MockHealthRepository::new())
+        .with_attachment_storage(Arc::new(files.clone()))
+        .with_thumbnail_generator(Arc::new(FixedPreviews));
+
+    let message = post(&service, &files, "cat.png", "image/png").await;
+    let attachment = &message.attachments[0];
+    let preview = attachment.preview.as_ref().expect("images get a preview");
+    assert_eq!((preview.width, preview.height), (640, 480));
+    assert_eq!(preview.thumbnail_url, Some(format!("{}?thumbnail", attachment.url)));
+
+    // stored with the message
+    let stored = service.get_message(&message.id).await.expect("get");
+    assert_eq!(stored.attachments[0].preview, attachment.preview);
+
+    // only images are previewed
+    let message = post(&service, &files, "notes.txt", "text/plain").await;
+    assert_eq!(message.attachments[0].preview, None);
+}
+
+#[tokio::test]
+async fn images_are_posted_without_preview_when_the_generator_fails() {
+    let files = InMemoryAttachmentStorage::new();
+    let service = Service::new(MockMessageRepository::new(), MockHealthRepository::new())
+        .with_attachment_storage(Arc::new(files.clone()))
+        .with_thumbnail_generator(Arc::new(FailingPreviews));
+
+    let message = post(&service, &files, "cat.png", "image/png").await;
+    assert_eq!(message.attachments.len(), 1);
+    assert_eq!(message.attachments[0].preview, None);
+}
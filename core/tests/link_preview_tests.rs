@@ -0,0 +1,137 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use communities_core::domain::common::CoreError;
+use communities_core::domain::common::services::Service;
+use communities_core::domain::health::port::MockHealthRepository;
+use communities_core::domain::message::entities::{
+    AuthorId, ChannelId, Embed, InsertMessageInput, LinkPreviewPolicy, MAX_EMBEDS_PER_MESSAGE, Message, MessageId,
+    UpdateMessageInput,
+};
+use communities_core::domain::message::events::MessageEvent;
+use communities_core::domain::message::ports::{LinkUnfurler, MessageService, MockMessageRepository};
+use communities_core::infrastructure::link_preview::LinkPreviewWorker;
+use uuid::Uuid;
+
+/// Titles every page after its URL, except those of `broken.example` which fail
+struct TitledPages;
+
+#[async_trait::async_trait]
+impl LinkUnfurler for TitledPages {
+    async fn unfurl(&self, url: &str) -> Result<Option<Embed>, CoreError> {
+        if url.contains("broken.example") {
+            return Err(CoreError::ServiceUnavailable("unreachable".into()));
+        }
+        Ok(Some(Embed {
+            url: url.to_string(),
+            title: Some(format!("Title of {url}")),
+            description: None,
+            image_url: None,
+            site_name: None,
+        }))
+    }
+}
+
+fn service(policy: LinkPreviewPolicy) -> Service<MockMessageRepository, MockHealthRepository> {
+    Service::new(MockMessageRepository::new(), MockHealthRepository::new()).with_link_unfurler(Arc::new(TitledPages), policy)
+}
+
+async fn post(service: &impl MessageService, content: &str) -> Message {
+    service
+        .create_message(InsertMessageInput {
+            id: MessageId::from(Uuid::new_v4()),
+            channel_id: ChannelId::from(Uuid::new_v4()),
+            author_id: AuthorId::from(Uuid::new_v4()),
+            content: content.into(),
+            reply_to_message_id: None,
+            attachments: vec![],
+            nonce: None,
+            expires_at: None,
+        })
+        .await
+        .expect("create")
+}
+
+fn policy(allowed: &[&str], denied: &[&str]) -> LinkPreviewPolicy {
+    LinkPreviewPolicy {
+        allowed_domains: allowed.iter().map(|domain| domain.to_string()).collect(),
+        denied_domains: denied.iter().map(|domain| domain.to_string()).collect(),
+    }
+}
+
+#[test]
+fn links_are_found_in_the_content_once_each() {
+    let links = LinkPreviewPolicy::default()
+        .links_to_preview("see https://a.example/x, (http://b.example/y) and https://a.example/x! ftp://c.example");
+    assert_eq!(links, vec!["https://a.example/x", "http://b.example/y"]);
+
+    let content: Vec<String> = (0..10).map(|i| format!("https://example.com/{i}")).collect();
+    let links = LinkPreviewPolicy::default().links_to_preview(&content.join(" "));
+    assert_eq!(links.len(), MAX_EMBEDS_PER_MESSAGE);
+    assert_eq!(links[0], "https://example.com/0");
+}
+
+#[test]
+fn domains_cover_their_subdomains() {
+    let policy = policy(&["example.com"], &["ads.example.com"]);
+
+    let links = policy.links_to_preview(
+        "https://example.com https://www.example.com/a https://ads.example.com/b https://notexample.com https://other.org",
+    );
+    assert_eq!(links, vec!["https://example.com", "https://www.example.com/a"]);
+}
+
+#[tokio::test]
+async fn links_are_stored_as_embeds_with_a_new_revision() {
+    let service = service(policy(&[], &["denied.example"]));
+    let message = post(&service, "https://site.example/post https://broken.example https://denied.example/x").await;
+    let mut events = service.events().subscribe();
+
+    let updated = service.unfurl_links(&message).await.expect("unfurl").expect("an embed");
+    assert_eq!(updated.embeds.len(), 1);
+    assert_eq!(updated.embeds[0].url, "https://site.example/post");
+    assert_eq!(updated.revision, message.revision + 1);
+    // embeds are not an edit
+    assert_eq!(updated.updated_at, None);
+
+    let stored = service.get_message(&message.id).await.expect("get");
+    assert_eq!(stored.embeds, updated.embeds);
+    match events.try_recv() {
+        Ok(MessageEvent::Updated(event)) => assert_eq!(event.embeds, updated.embeds),
+        other => panic!("expected an update, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn embeds_of_a_message_edited_meanwhile_are_dropped() {
+    let service = service(LinkPreviewPolicy::default());
+    let message = post(&service, "https://site.example/post").await;
+    service
+        .update_message(UpdateMessageInput {
+            id: message.id,
+            content: Some("no more links".into()),
+            is_pinned: None,
+            expected_revision: None,
+        })
+        .await
+        .expect("edit");
+
+    assert!(service.unfurl_links(&message).await.expect("unfurl").is_none());
+    assert!(service.get_message(&message.id).await.expect("get").embeds.is_empty());
+}
+
+#[tokio::test]
+async fn the_worker_unfurls_new_messages_in_the_background() {
+    let service = service(LinkPreviewPolicy::default());
+    LinkPreviewWorker::new(service.clone(), 2).follow(service.events());
+
+    let message = post(&service, "read https://site.example/post").await;
+
+    tokio::time::timeout(Duration::from_secs(2), async {
+        while service.get_message(&message.id).await.expect("get").embeds.is_empty() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("the message should get its embed");
+}
@@ -0,0 +1,115 @@
+use communities_core::domain::common::services::Service;
+use communities_core::domain::health::port::MockHealthRepository;
+use communities_core::domain::message::entities::{
+    AuthorId, ChannelId, InsertMessageInput, MentionTarget, Mentions, MessageId,
+    UpdateMessageInput, UserId,
+};
+use communities_core::domain::message::ports::{MessageService, MockMessageRepository};
+use uuid::Uuid;
+
+#[test]
+fn users_and_roles_are_mentioned_once_each() {
+    let (alice, bob, moderators) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+    let content = format!("<@{alice}> and <@{bob}>, ask <@&{moderators}> or <@{alice}>");
+
+    let mentions = Mentions::parse(&content);
+    assert_eq!(mentions.users, vec![UserId(alice), UserId(bob)]);
+    assert_eq!(mentions.roles, vec![moderators]);
+    assert!(!mentions.everyone);
+
+    assert!(Mentions::parse("hello @everyone!").everyone);
+    assert!(!Mentions::parse("hello @everyones, or me@everyone").everyone);
+}
+
+#[test]
+fn tokens_without_a_uuid_are_plain_text() {
+    let simple = Uuid::new_v4().simple();
+    let user = Uuid::new_v4();
+    let content = format!("mail me@example.com, <@bob> <@{simple}> <@&admins> <@{user}");
+
+    assert!(Mentions::parse(&content).is_empty());
+
+    // a broken token does not hide the next one
+    let mentions = Mentions::parse(&format!("<@<@{user}>"));
+    assert_eq!(mentions.users, vec![UserId(user)]);
+}
+
+#[tokio::test]
+async fn mentions_follow_the_content_of_messages() {
+    let service = Service::new(MockMessageRepository::new(), MockHealthRepository::new());
+    let (alice, bob) = (Uuid::new_v4(), Uuid::new_v4());
+
+    let message = service
+        .create_message(InsertMessageInput {
+            id: MessageId::from(Uuid::new_v4()),
+            channel_id: ChannelId::from(Uuid::new_v4()),
+            author_id: AuthorId::from(Uuid::new_v4()),
+            content: format!("hi <@{alice}>"),
+            reply_to_message_id: None,
+            attachments: vec![],
+            nonce: None,
+            expires_at: None,
+        })
+        .await
+        .expect("create");
+    assert_eq!(message.mentions.users, vec![UserId(alice)]);
+
+    let edited = service
+        .update_message(UpdateMessageInput {
+            id: message.id,
+            content: Some(format!("hi <@{bob}>")),
+            is_pinned: None,
+            expected_revision: None,
+        })
+        .await
+        .expect("edit");
+    assert_eq!(edited.mentions.users, vec![UserId(bob)]);
+
+    let stored = service.get_message(&message.id).await.expect("get");
+    assert_eq!(stored.mentions, edited.mentions);
+}
+
+#[tokio::test]
+async fn each_mention_of_a_new_message_is_published() {
+    let repo = MockMessageRepository::new();
+    let service = Service::new(repo.clone(), MockHealthRepository::new());
+    let (author, alice, moderators) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+
+    let message = service
+        .create_message(InsertMessageInput {
+            id: MessageId::from(Uuid::new_v4()),
+            channel_id: ChannelId::from(Uuid::new_v4()),
+            author_id: AuthorId::from(author),
+            content: format!("@everyone <@&{moderators}> <@{author}> <@{alice}>"),
+            reply_to_message_id: None,
+            attachments: vec![],
+            nonce: None,
+            expires_at: None,
+        })
+        .await
+        .expect("create");
+
+    let published = repo.published_mentions();
+    let targets: Vec<MentionTarget> = published.iter().map(|event| event.target).collect();
+    assert_eq!(
+        targets,
+        vec![
+            MentionTarget::User { user_id: UserId(alice) },
+            MentionTarget::Role { role_id: moderators },
+            MentionTarget::Everyone,
+        ]
+    );
+    assert!(published.iter().all(|event| event.message_id == message.id));
+
+    // edits mention again without publishing
+    service
+        .update_message(UpdateMessageInput {
+            id: message.id,
+            content: Some(format!("<@{alice}>!")),
+            is_pinned: None,
+            expected_revision: None,
+        })
+        .await
+        .expect("edit");
+    assert_eq!(repo.published_mentions().len(), 3);
+}
@@ -0,0 +1,160 @@
+use std::sync::Arc;
+
+use communities_core::domain::archive::ports::{
+    ArchiveManifestRepository, InMemoryArchiveManifest, InMemoryObjectStorage, ObjectStorage,
+};
+use communities_core::domain::archive::services::MessageArchive;
+use communities_core::domain::common::services::Service;
+use communities_core::domain::common::{CoreError, GetPaginated};
+use communities_core::domain::health::port::MockHealthRepository;
+use communities_core::domain::message::entities::{AuthorId, ChannelId, ImportMessageInput, MessageId, UserId};
+use communities_core::domain::message::ports::{MessageRepository, MessageService, MockMessageRepository};
+use communities_core::domain::search::ports::ArchiveStore;
+use uuid::Uuid;
+
+/// Storage refusing every write, as when the bucket is unreachable
+struct UnreachableStorage;
+
+#[async_trait::async_trait]
+impl ObjectStorage for UnreachableStorage {
+    async fn put_object(&self, _key: &str, _body: Vec<u8>) -> Result<(), CoreError> {
+        Err(CoreError::ArchiveError { msg: "connection refused".into() })
+    }
+
+    async fn get_object(&self, _key: &str) -> Result<Option<Vec<u8>>, CoreError> {
+        Err(CoreError::ArchiveError { msg: "connection refused".into() })
+    }
+
+    async fn delete_object(&self, _key: &str) -> Result<(), CoreError> {
+        Err(CoreError::ArchiveError { msg: "connection refused".into() })
+    }
+}
+
+fn old_message(days_ago: i64) -> ImportMessageInput {
+    ImportMessageInput {
+        id: MessageId::from(Uuid::new_v4()),
+        author_id: AuthorId::from(Uuid::new_v4()),
+        content: format!("{days_ago} days old"),
+        reply_to_message_id: None,
+        attachments: vec![],
+        is_pinned: false,
+        created_at: chrono::Utc::now() - chrono::Duration::days(days_ago),
+        updated_at: None,
+    }
+}
+
+#[tokio::test]
+async fn expired_messages_are_archived_before_being_deleted() {
+    let repo = MockMessageRepository::new();
+    let storage = InMemoryObjectStorage::new();
+    let manifest = InMemoryArchiveManifest::new();
+    let archive = Arc::new(
+        MessageArchive::new(manifest.clone(), Arc::new(storage.clone())).with_prefix("cold/"),
+    );
+    let service = Service::new(repo.clone(), MockHealthRepository::new())
+        .with_message_archiver(archive.clone());
+    let channel = ChannelId::from(Uuid::new_v4());
+
+    let oldest = old_message(100);
+    let older = old_message(60);
+    let recent = old_message(1);
+    repo.insert_many(&channel, vec![oldest.clone(), older.clone(), recent.clone()])
+        .await
+        .expect("import");
+
+    let deleted = service
+        .delete_expired_messages(Some(chrono::Duration::days(30)), 10)
+        .await
+        .expect("delete expired");
+    assert_eq!(deleted, 2);
+
+    let keys = storage.keys();
+    assert_eq!(keys.len(), 1);
+    assert!(keys[0].starts_with(&format!("cold/{channel}/")));
+    assert!(keys[0].ends_with(".ndjson.gz"));
+
+    let (batches, total) = manifest
+        .list_batches(&channel, &GetPaginated::default())
+        .await
+        .expect("list batches");
+    assert_eq!(total, 1);
+    assert_eq!(batches[0].object_key, keys[0]);
+    assert_eq!(batches[0].message_ids.len(), 2);
+    assert_eq!(batches[0].oldest_created_at, oldest.created_at);
+    assert_eq!(batches[0].newest_created_at, older.created_at);
+
+    // archived messages are read back from the compressed batch
+    let mut restored = archive
+        .fetch_archived(&[oldest.id, older.id, recent.id])
+        .await
+        .expect("fetch archived");
+    restored.sort_by_key(|message| message.created_at);
+    let contents: Vec<&str> = restored.iter().map(|message| message.content.as_str()).collect();
+    assert_eq!(contents, vec!["100 days old", "60 days old"]);
+}
+
+#[tokio::test]
+async fn messages_are_kept_when_their_archival_fails() {
+    let repo = MockMessageRepository::new();
+    let archive = MessageArchive::new(InMemoryArchiveManifest::new(), Arc::new(UnreachableStorage));
+    let service = Service::new(repo.clone(), MockHealthRepository::new())
+        .with_message_archiver(Arc::new(archive));
+    let channel = ChannelId::from(Uuid::new_v4());
+
+    let old = old_message(100);
+    repo.insert_many(&channel, vec![old.clone()]).await.expect("import");
+
+    let result = service
+        .delete_expired_messages(Some(chrono::Duration::days(30)), 10)
+        .await;
+    assert!(matches!(result, Err(CoreError::ArchiveError { .. })));
+    service.get_message(&old.id).await.expect("kept until archived");
+    assert!(repo.bulk_deletions().is_empty());
+}
+
+#[tokio::test]
+async fn erasing_a_user_removes_their_archived_messages() {
+    let repo = MockMessageRepository::new();
+    let storage = InMemoryObjectStorage::new();
+    let manifest = InMemoryArchiveManifest::new();
+    let archive = Arc::new(MessageArchive::new(manifest.clone(), Arc::new(storage.clone())));
+    let service = Service::new(repo.clone(), MockHealthRepository::new())
+        .with_message_archiver(archive.clone());
+    let (shared, own) = (ChannelId::from(Uuid::new_v4()), ChannelId::from(Uuid::new_v4()));
+    let user = UserId::from(Uuid::new_v4());
+
+    let written_by_user = |days_ago| ImportMessageInput { author_id: AuthorId::from(user.0), ..old_message(days_ago) };
+    let (erased, other) = (written_by_user(100), old_message(90));
+    let alone = written_by_user(80);
+    repo.insert_many(&shared, vec![erased.clone(), other.clone()]).await.expect("import");
+    repo.insert_many(&own, vec![alone.clone()]).await.expect("import");
+    service
+        .delete_expired_messages(Some(chrono::Duration::days(30)), 10)
+        .await
+        .expect("delete expired");
+    assert_eq!(storage.keys().len(), 2);
+
+    // as archived before the authors of batches were recorded
+    let (batches, _) = manifest.list_batches(&shared, &GetPaginated::default()).await.expect("list batches");
+    let mut legacy = batches[0].clone();
+    legacy.author_ids.clear();
+    manifest.record_batch(&legacy).await.expect("record batch");
+
+    service.erase_user(&user, &UserId::from(Uuid::new_v4())).await.expect("erase");
+
+    let restored = archive
+        .fetch_archived(&[erased.id, other.id, alone.id])
+        .await
+        .expect("fetch archived");
+    let ids: Vec<MessageId> = restored.iter().map(|message| message.id).collect();
+    assert_eq!(ids, vec![other.id]);
+
+    // the batch left empty is gone, the other one rewritten with its authors
+    assert_eq!(storage.keys().len(), 1);
+    let (batches, total) = manifest.list_batches(&own, &GetPaginated::default()).await.expect("list batches");
+    assert!(batches.is_empty() && total == 0);
+    let (batches, _) = manifest.list_batches(&shared, &GetPaginated::default()).await.expect("list batches");
+    assert_eq!(batches[0].message_ids, vec![other.id]);
+    assert_eq!(batches[0].author_ids, vec![other.author_id]);
+    assert_eq!(batches[0].oldest_created_at, other.created_at);
+}
@@ -0,0 +1,121 @@
+use chrono::Utc;
+use communities_core::domain::message::entities::{
+    Attachment, AttachmentId, AuthorId, ChannelId, Mentions, Message, MessageId,
+};
+use communities_core::infrastructure::message::dto::MessageDocument;
+use communities_core::infrastructure::message::encoding::{UUID_FIELDS, standard_uuid_update};
+use mongodb::bson::{self, Bson, spec::BinarySubtype};
+use uuid::Uuid;
+
+fn sample_message() -> Message {
+    Message {
+        id: MessageId::from(Uuid::new_v4()),
+        channel_id: ChannelId::from(Uuid::new_v4()),
+        author_id: AuthorId::from(Uuid::new_v4()),
+        content: "dto round trip".into(),
+        reply_to_message_id: Some(MessageId::from(Uuid::new_v4())),
+        attachments: vec![Attachment { id: AttachmentId::from(Uuid::new_v4()), name: "f".into(), url: "u".into(), size: 0, preview: None }],
+        is_pinned: true,
+        reactions: vec![],
+        revision: 3,
+        sequence: 0,
+        reply_count: 2,
+        last_reply_at: Some(Utc::now()),
+        archived_at: None,
+        created_at: Utc::now(),
+        updated_at: Some(Utc::now()),
+        deleted_at: None,
+        nonce: None,
+        embeds: Vec::new(),
+        mentions: Mentions::default(),
+        expires_at: None,
+    }
+}
+
+#[test]
+fn message_document_round_trip_preserves_message() {
+    let message = sample_message();
+
+    let document = bson::to_document(&MessageDocument::from(&message)).expect("serialize document");
+    let decoded: MessageDocument = bson::from_document(document).expect("deserialize document");
+    let restored = Message::try_from(decoded).expect("convert document to message");
+
+    assert_eq!(
+        serde_json::to_value(&restored).unwrap(),
+        serde_json::to_value(&message).unwrap()
+    );
+}
+
+#[test]
+fn message_document_storage_encoding_is_stable() {
+    let message = sample_message();
+    let document = bson::to_document(&MessageDocument::from(&message)).expect("serialize document");
+
+    match document.get("_id") {
+        Some(Bson::Binary(binary)) => {
+            assert_eq!(binary.subtype, BinarySubtype::Generic);
+            assert_eq!(binary.bytes, message.id.0.as_bytes().to_vec());
+        }
+        other => panic!("unexpected _id encoding: {:?}", other),
+    }
+    assert!(matches!(document.get("channel_id"), Some(Bson::Binary(_))));
+    assert!(matches!(document.get("reply_to_message_id"), Some(Bson::String(_))));
+    assert_eq!(
+        document.get("created_at"),
+        Some(&Bson::String(message.created_at.to_rfc3339()))
+    );
+}
+
+fn message_uuid_fields() -> &'static [&'static str] {
+    UUID_FIELDS
+        .iter()
+        .find(|(collection, _)| *collection == "messages")
+        .map(|(_, fields)| *fields)
+        .expect("messages fields")
+}
+
+#[test]
+fn message_document_reads_the_standard_encoding() {
+    let message = sample_message();
+    let mut document =
+        bson::to_document(&MessageDocument::from(&message)).expect("serialize document");
+
+    let update = standard_uuid_update(&document, message_uuid_fields()).expect("legacy fields");
+    for (key, value) in update.get_document("$set").unwrap() {
+        document.insert(key, value.clone());
+    }
+    document.insert("created_at", bson::DateTime::from_millis(message.created_at.timestamp_millis()));
+    assert!(matches!(
+        document.get("channel_id"),
+        Some(Bson::Binary(binary)) if binary.subtype == BinarySubtype::Uuid
+    ));
+
+    let decoded: MessageDocument = bson::from_document(document).expect("deserialize document");
+    let restored = Message::try_from(decoded).expect("convert document to message");
+
+    assert_eq!(restored.channel_id, message.channel_id);
+    assert_eq!(restored.author_id, message.author_id);
+    assert_eq!(restored.attachments[0].id, message.attachments[0].id);
+    assert_eq!(
+        restored.created_at.timestamp_millis(),
+        message.created_at.timestamp_millis()
+    );
+}
+
+#[test]
+fn standard_uuid_update_skips_converted_documents() {
+    let message = sample_message();
+    let mut document =
+        bson::to_document(&MessageDocument::from(&message)).expect("serialize document");
+
+    let update = standard_uuid_update(&document, message_uuid_fields()).expect("legacy fields");
+    let set = update.get_document("$set").unwrap();
+    assert!(set.contains_key("channel_id"));
+    assert!(set.contains_key("author_id"));
+    assert!(set.contains_key("attachments"));
+    for (key, value) in set {
+        document.insert(key, value.clone());
+    }
+
+    assert_eq!(standard_uuid_update(&document, message_uuid_fields()), None);
+}
@@ -17,7 +17,9 @@ async fn mock_repo_crud_flow() {
         author_id: author,
         content: "hello world".to_string(),
         reply_to_message_id: None,
-        attachments: vec![Attachment { id: AttachmentId::from(Uuid::new_v4()), name: "file.txt".into(), url: "http://example.com/file.txt".into() }],
+        attachments: vec![Attachment { id: AttachmentId::from(Uuid::new_v4()), name: "file.txt".into(), url: "http://example.com/file.txt".into(), size: 0, preview: None }],
+        nonce: None,
+        expires_at: None,
     };
 
     // Insert
@@ -37,7 +39,7 @@ async fn mock_repo_crud_flow() {
     assert!(list.iter().any(|m| m.id == id));
 
     // Update
-    let update_input = UpdateMessageInput { id, content: Some("updated".into()), is_pinned: Some(true) };
+    let update_input = UpdateMessageInput { id, content: Some("updated".into()), is_pinned: Some(true), expected_revision: None };
     let updated = repo.update(update_input).await.expect("update should succeed");
     assert_eq!(updated.content, "updated");
     assert!(updated.is_pinned);
@@ -1,16 +1,31 @@
-use communities_core::domain::message::entities::{InsertMessageInput, MessageId, ChannelId, AuthorId, Attachment, AttachmentId, UpdateMessageInput};
-use communities_core::domain::message::ports::{MockMessageRepository, MessageService};
+use std::sync::Arc;
+
+use communities_core::domain::message::entities::{Embed, InsertMessageInput, MessageId, ChannelId, AuthorId, Attachment, AttachmentId, PresignAttachmentInput, AttachmentLimits, UpdateMessageInput, AddReactionInput, ReactionCount, UserId, LegalHoldScope, PlaceLegalHoldInput, AbusePattern, AbuseThreshold, LockChannelWritesInput, ImportMessageInput, NotificationKind, ThreadPreferences, ModerationReason, ModerationReasonTemplate};
+use communities_core::domain::message::events::MessageEvent;
+use communities_core::domain::message::ports::{AttachmentRepository, InMemoryAttachmentStorage, MockMessageRepository, MessageRepository, MessageService, ReactionRepository};
 use communities_core::domain::health::port::MockHealthRepository;
-use communities_core::domain::common::CoreError;
+use communities_core::domain::common::{CoreError, GetPaginated};
 use communities_core::domain::common::services::Service;
 use uuid::Uuid;
 
+/// Presign an attachment of `size` bytes for `author_id` in `channel_id` and
+/// upload it, as a client would
+async fn uploaded_attachment(service: &impl MessageService, files: &InMemoryAttachmentStorage, channel_id: ChannelId, author_id: AuthorId, size: u64) -> Attachment {
+    let presigned = service
+        .presign_attachment(PresignAttachmentInput { channel_id, uploader_id: author_id, name: "a.txt".into(), content_type: "text/plain".into(), size })
+        .await
+        .expect("presign");
+    files.upload(&presigned.upload.object_key, size);
+    Attachment::reference(presigned.upload.id)
+}
+
 #[tokio::test]
 async fn service_create_get_update_delete_flow() {
     let repo = MockMessageRepository::new();
     let health = MockHealthRepository::new();
+    let files = InMemoryAttachmentStorage::new();
 
-    let service = Service::new(repo.clone(), health);
+    let service = Service::new(repo.clone(), health).with_attachment_storage(Arc::new(files.clone()));
 
     let id = MessageId::from(Uuid::new_v4());
     let channel = ChannelId::from(Uuid::new_v4());
@@ -22,21 +37,26 @@ async fn service_create_get_update_delete_flow() {
         author_id: author,
         content: "service message".into(),
         reply_to_message_id: None,
-        attachments: vec![Attachment { id: AttachmentId::from(Uuid::new_v4()), name: "a".into(), url: "u".into() }],
+        attachments: vec![uploaded_attachment(&service, &files, channel, author, 3).await],
+        nonce: None,
+        expires_at: None,
     };
 
     // create
     let created = service.create_message(input.clone()).await.expect("create should work");
     assert_eq!(created.id, id);
+    assert_eq!(created.attachments[0].name, "a.txt");
+    assert_eq!(created.attachments[0].size, 3);
 
     // get
     let got = service.get_message(&id).await.expect("get should work");
     assert_eq!(got.content, "service message");
 
     // update
-    let update = UpdateMessageInput { id, content: Some("changed".into()), is_pinned: Some(false) };
+    let update = UpdateMessageInput { id, content: Some("changed".into()), is_pinned: Some(false), expected_revision: None };
     let updated = service.update_message(update).await.expect("update should work");
     assert_eq!(updated.content, "changed");
+    assert_eq!(updated.revision, created.revision + 1);
 
     // delete
     service.delete_message(&id).await.expect("delete should work");
@@ -59,8 +79,1869 @@ async fn create_invalid_message_name_rejected() {
         content: "  ".into(),
         reply_to_message_id: None,
         attachments: vec![],
+        nonce: None,
+        expires_at: None,
     };
 
     let res = service.create_message(input).await;
     assert!(matches!(res, Err(CoreError::InvalidMessageName)));
 }
+
+#[tokio::test]
+async fn reactions_are_aggregated_on_messages() {
+    let repo = MockMessageRepository::new();
+    let health = MockHealthRepository::new();
+    let service = Service::new(repo, health);
+
+    let id = MessageId::from(Uuid::new_v4());
+    let input = InsertMessageInput {
+        id,
+        channel_id: ChannelId::from(Uuid::new_v4()),
+        author_id: AuthorId::from(Uuid::new_v4()),
+        content: "react to me".into(),
+        reply_to_message_id: None,
+        attachments: vec![],
+        nonce: None,
+        expires_at: None,
+    };
+    service.create_message(input).await.expect("create should work");
+
+    let alice = UserId::from(Uuid::new_v4());
+    let bob = UserId::from(Uuid::new_v4());
+    for (user, emoji) in [(alice, "👍"), (bob, "👍"), (alice, "🎉"), (alice, "👍")] {
+        service
+            .add_reaction(AddReactionInput { message_id: id, user_id: user, emoji: emoji.into() })
+            .await
+            .expect("add reaction should work");
+    }
+
+    let message = service.get_message(&id).await.expect("get should work");
+    assert_eq!(
+        message.reactions,
+        vec![
+            ReactionCount { emoji: "👍".into(), count: 2 },
+            ReactionCount { emoji: "🎉".into(), count: 1 },
+        ]
+    );
+
+    service.remove_reaction(&id, &bob, "👍").await.expect("remove should work");
+    let res = service.remove_reaction(&id, &bob, "👍").await;
+    assert!(matches!(res, Err(CoreError::ReactionNotFound { .. })));
+
+    let res = service
+        .add_reaction(AddReactionInput { message_id: id, user_id: bob, emoji: "  ".into() })
+        .await;
+    assert!(matches!(res, Err(CoreError::InvalidReaction { .. })));
+}
+
+#[tokio::test]
+async fn replies_must_target_a_message_of_the_same_channel() {
+    let service = Service::new(MockMessageRepository::new(), MockHealthRepository::new());
+    let channel = ChannelId::from(Uuid::new_v4());
+    let parent = MessageId::from(Uuid::new_v4());
+
+    let input = |id: MessageId, channel_id: ChannelId, reply_to: Option<MessageId>| InsertMessageInput {
+        id,
+        channel_id,
+        author_id: AuthorId::from(Uuid::new_v4()),
+        content: "thread".into(),
+        reply_to_message_id: reply_to,
+        attachments: vec![],
+        nonce: None,
+        expires_at: None,
+    };
+
+    service.create_message(input(parent, channel, None)).await.expect("create parent");
+
+    let res = service
+        .create_message(input(MessageId::from(Uuid::new_v4()), ChannelId::from(Uuid::new_v4()), Some(parent)))
+        .await;
+    assert!(matches!(res, Err(CoreError::InvalidReplyTarget { .. })));
+
+    let res = service
+        .create_message(input(MessageId::from(Uuid::new_v4()), channel, Some(MessageId::from(Uuid::new_v4()))))
+        .await;
+    assert!(matches!(res, Err(CoreError::MessageNotFound { .. })));
+
+    service
+        .create_message(input(MessageId::from(Uuid::new_v4()), channel, Some(parent)))
+        .await
+        .expect("reply in the same channel should work");
+}
+
+#[tokio::test]
+async fn parent_tracks_thread_metadata() {
+    let service = Service::new(MockMessageRepository::new(), MockHealthRepository::new());
+    let channel = ChannelId::from(Uuid::new_v4());
+    let parent = MessageId::from(Uuid::new_v4());
+
+    let input = |id: MessageId, reply_to: Option<MessageId>| InsertMessageInput {
+        id,
+        channel_id: channel,
+        author_id: AuthorId::from(Uuid::new_v4()),
+        content: "thread".into(),
+        reply_to_message_id: reply_to,
+        attachments: vec![],
+        nonce: None,
+        expires_at: None,
+    };
+
+    service.create_message(input(parent, None)).await.expect("create parent");
+    let first = MessageId::from(Uuid::new_v4());
+    service.create_message(input(first, Some(parent))).await.expect("create reply");
+    let second = service
+        .create_message(input(MessageId::from(Uuid::new_v4()), Some(parent)))
+        .await
+        .expect("create reply");
+
+    let summary = service.get_message(&parent).await.expect("get parent");
+    assert_eq!(summary.reply_count, 2);
+    assert_eq!(summary.last_reply_at, Some(second.created_at));
+
+    service.delete_message(&first).await.expect("delete reply");
+    let summary = service.get_message(&parent).await.expect("get parent");
+    assert_eq!(summary.reply_count, 1);
+}
+
+#[tokio::test]
+async fn writes_are_published_on_the_event_bus() {
+    let service = Service::new(MockMessageRepository::new(), MockHealthRepository::new());
+    let mut events = service.events().subscribe();
+
+    let id = MessageId::from(Uuid::new_v4());
+    let channel = ChannelId::from(Uuid::new_v4());
+    let input = InsertMessageInput {
+        id,
+        channel_id: channel,
+        author_id: AuthorId::from(Uuid::new_v4()),
+        content: "live".into(),
+        reply_to_message_id: None,
+        attachments: vec![],
+        nonce: None,
+        expires_at: None,
+    };
+
+    service.create_message(input).await.expect("create should work");
+    service
+        .update_message(UpdateMessageInput { id, content: Some("edited".into()), is_pinned: None, expected_revision: None })
+        .await
+        .expect("update should work");
+    service
+        .update_message(UpdateMessageInput { id, content: None, is_pinned: Some(true), expected_revision: None })
+        .await
+        .expect("pin should work");
+    service.delete_message(&id).await.expect("delete should work");
+
+    assert!(matches!(events.recv().await, Ok(MessageEvent::Created(m)) if m.id == id));
+    assert!(matches!(events.recv().await, Ok(MessageEvent::Updated(m)) if m.content == "edited"));
+    assert!(matches!(events.recv().await, Ok(MessageEvent::Updated(m)) if m.is_pinned && m.revision == 2));
+    assert!(matches!(
+        events.recv().await,
+        Ok(MessageEvent::Deleted { id: deleted, channel_id }) if deleted == id && channel_id == channel
+    ));
+}
+
+#[tokio::test]
+async fn retried_creations_with_a_nonce_return_the_first_message() {
+    let service = Service::new(MockMessageRepository::new(), MockHealthRepository::new());
+    let mut events = service.events().subscribe();
+    let channel = ChannelId::from(Uuid::new_v4());
+    let author = AuthorId::from(Uuid::new_v4());
+    let input = |nonce: &str| InsertMessageInput {
+        id: MessageId::from(Uuid::new_v4()),
+        channel_id: channel,
+        author_id: author,
+        content: "sent twice".into(),
+        reply_to_message_id: None,
+        attachments: vec![],
+        nonce: Some(nonce.into()),
+        expires_at: None,
+    };
+
+    let first = service.create_message(input("n-1")).await.expect("create should work");
+    let retried = service.create_message(input("n-1")).await.expect("retry should work");
+    assert_eq!(retried.id, first.id);
+
+    let other = service.create_message(input("n-2")).await.expect("create should work");
+    assert_ne!(other.id, first.id);
+
+    // only the actual creations are published
+    assert!(matches!(events.recv().await, Ok(MessageEvent::Created(m)) if m.id == first.id));
+    assert!(matches!(events.recv().await, Ok(MessageEvent::Created(m)) if m.id == other.id));
+
+    let too_long = service.create_message(input(&"n".repeat(65))).await;
+    assert!(matches!(too_long, Err(CoreError::InvalidMessageNonce { .. })));
+
+    // the nonce of a deleted message can be used again
+    service.delete_message(&first.id).await.expect("delete should work");
+    let recreated = service.create_message(input("n-1")).await.expect("create should work");
+    assert_ne!(recreated.id, first.id);
+}
+
+#[tokio::test]
+async fn ephemeral_messages_disappear_once_expired() {
+    let service = Service::new(MockMessageRepository::new(), MockHealthRepository::new());
+    let channel = ChannelId::from(Uuid::new_v4());
+    let input = |expires_at| InsertMessageInput {
+        id: MessageId::from(Uuid::new_v4()),
+        channel_id: channel,
+        author_id: AuthorId::from(Uuid::new_v4()),
+        content: "gone soon".into(),
+        reply_to_message_id: None,
+        attachments: vec![],
+        nonce: None,
+        expires_at,
+    };
+
+    let past = service.create_message(input(Some(chrono::Utc::now() - chrono::Duration::seconds(1)))).await;
+    assert!(matches!(past, Err(CoreError::InvalidMessageExpiry)));
+
+    let kept = service.create_message(input(None)).await.expect("create");
+    let ephemeral = service
+        .create_message(input(Some(chrono::Utc::now() + chrono::Duration::milliseconds(50))))
+        .await
+        .expect("create");
+    assert!(service.get_message(&ephemeral.id).await.is_ok());
+
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    let res = service.get_message(&ephemeral.id).await;
+    assert!(matches!(res, Err(CoreError::MessageNotFound { .. })));
+    let (messages, total) = service.list_messages(&channel, &GetPaginated::default()).await.expect("list");
+    assert_eq!(messages.iter().map(|m| m.id).collect::<Vec<_>>(), vec![kept.id]);
+    assert_eq!(total, 1);
+}
+
+#[tokio::test]
+async fn expired_messages_are_purged_unless_held() {
+    let service = Service::new(MockMessageRepository::new(), MockHealthRepository::new());
+    let channel = ChannelId::from(Uuid::new_v4());
+    let held_author = AuthorId::from(Uuid::new_v4());
+    let input = |author_id| InsertMessageInput {
+        id: MessageId::from(Uuid::new_v4()),
+        channel_id: channel,
+        author_id,
+        content: "gone soon".into(),
+        reply_to_message_id: None,
+        attachments: vec![],
+        nonce: None,
+        expires_at: Some(chrono::Utc::now() + chrono::Duration::milliseconds(50)),
+    };
+    let expired = service.create_message(input(AuthorId::from(Uuid::new_v4()))).await.expect("create");
+    let held = service.create_message(input(held_author)).await.expect("create");
+    let hold = service
+        .place_legal_hold(PlaceLegalHoldInput {
+            scope: LegalHoldScope::User(UserId::from(held_author.0)),
+            reference: "CASE-8".into(),
+            placed_by: UserId::from(Uuid::new_v4()),
+        })
+        .await
+        .expect("place hold");
+    let mut events = service.events().subscribe();
+
+    // not expired yet
+    let purged = service.purge_expired_messages(chrono::Utc::now(), 10).await.expect("purge");
+    assert_eq!(purged, 0);
+
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    let purged = service.purge_expired_messages(chrono::Utc::now(), 10).await.expect("purge");
+    assert_eq!(purged, 1);
+    let res = service.get_message_including_deleted(&expired.id).await;
+    assert!(matches!(res, Err(CoreError::MessageNotFound { .. })));
+    assert!(matches!(
+        events.recv().await,
+        Ok(MessageEvent::Deleted { id, channel_id }) if id == expired.id && channel_id == channel
+    ));
+
+    // kept until its hold is released
+    service.release_legal_hold(&hold.id).await.expect("release hold");
+    let purged = service.purge_expired_messages(chrono::Utc::now(), 10).await.expect("purge");
+    assert_eq!(purged, 1);
+    assert!(matches!(
+        events.recv().await,
+        Ok(MessageEvent::Deleted { id, .. }) if id == held.id
+    ));
+}
+
+#[tokio::test]
+async fn messages_are_numbered_in_order_per_channel() {
+    let service = Service::new(MockMessageRepository::new(), MockHealthRepository::new());
+    let mut events = service.events().subscribe();
+    let (first_channel, second_channel) = (ChannelId::from(Uuid::new_v4()), ChannelId::from(Uuid::new_v4()));
+    let input = |channel_id: ChannelId| InsertMessageInput {
+        id: MessageId::from(Uuid::new_v4()),
+        channel_id,
+        author_id: AuthorId::from(Uuid::new_v4()),
+        content: "numbered".into(),
+        reply_to_message_id: None,
+        attachments: vec![],
+        nonce: None,
+        expires_at: None,
+    };
+
+    let mut sequences = Vec::new();
+    for channel_id in [first_channel, first_channel, second_channel, first_channel] {
+        sequences.push(service.create_message(input(channel_id)).await.expect("create should work").sequence);
+    }
+    // each channel counts on its own
+    assert_eq!(sequences, vec![1, 2, 1, 3]);
+
+    // consumers of the events get the sequence too
+    assert!(matches!(events.recv().await, Ok(MessageEvent::Created(m)) if m.sequence == 1));
+}
+
+#[tokio::test]
+async fn messages_are_fetched_in_bulk_in_request_order() {
+    let service = Service::new(MockMessageRepository::new(), MockHealthRepository::new());
+    let channel = ChannelId::from(Uuid::new_v4());
+    let input = |content: &str| InsertMessageInput {
+        id: MessageId::from(Uuid::new_v4()),
+        channel_id: channel,
+        author_id: AuthorId::from(Uuid::new_v4()),
+        content: content.into(),
+        reply_to_message_id: None,
+        attachments: vec![],
+        nonce: None,
+        expires_at: None,
+    };
+
+    let first = service.create_message(input("first")).await.expect("create should work");
+    let second = service.create_message(input("second")).await.expect("create should work");
+    let deleted = service.create_message(input("deleted")).await.expect("create should work");
+    service.delete_message(&deleted.id).await.expect("delete should work");
+    let missing = MessageId::from(Uuid::new_v4());
+
+    let fetched = service
+        .get_messages(&[second.id, missing, first.id, deleted.id, second.id])
+        .await
+        .expect("bulk get should work");
+
+    // missing and deleted messages are left out, duplicates returned once
+    let ids: Vec<MessageId> = fetched.iter().map(|m| m.id).collect();
+    assert_eq!(ids, vec![second.id, first.id]);
+}
+
+#[tokio::test]
+async fn reaction_notifications_respect_author_mutes() {
+    let repo = MockMessageRepository::new();
+    let service = Service::new(repo.clone(), MockHealthRepository::new());
+    let author = AuthorId::from(Uuid::new_v4());
+    let author_user = UserId::from(author.0);
+    let reactor = UserId::from(Uuid::new_v4());
+
+    let mut ids = Vec::new();
+    for _ in 0..2 {
+        let id = MessageId::from(Uuid::new_v4());
+        service
+            .create_message(InsertMessageInput {
+                id,
+                channel_id: ChannelId::from(Uuid::new_v4()),
+                author_id: author,
+                content: "notify me".into(),
+                reply_to_message_id: None,
+                attachments: vec![],
+                nonce: None,
+                expires_at: None,
+            })
+            .await
+            .expect("create should work");
+        ids.push(id);
+    }
+    let react = |message_id: MessageId, user_id: UserId, emoji: &str| {
+        service.add_reaction(AddReactionInput { message_id, user_id, emoji: emoji.into() })
+    };
+
+    react(ids[0], reactor, "👍").await.expect("react should work");
+    // reacting to one's own message never notifies
+    react(ids[0], author_user, "👍").await.expect("react should work");
+    assert_eq!(repo.requested_notifications().len(), 1);
+    assert_eq!(repo.requested_notifications()[0].recipient_id, author_user);
+    assert_eq!(repo.requested_notifications()[0].emoji.as_deref(), Some("👍"));
+
+    service.mute_reaction_notifications(&author_user, Some(&ids[0])).await.expect("mute should work");
+    react(ids[0], reactor, "🎉").await.expect("react should work");
+    react(ids[1], reactor, "🎉").await.expect("react should work");
+    assert_eq!(repo.requested_notifications().len(), 2);
+    assert_eq!(repo.requested_notifications()[1].message_id, ids[1]);
+
+    service.mute_reaction_notifications(&author_user, None).await.expect("mute should work");
+    react(ids[1], reactor, "🔥").await.expect("react should work");
+    assert_eq!(repo.requested_notifications().len(), 2);
+
+    // lifting the global mute keeps the per-message one
+    service.unmute_reaction_notifications(&author_user, None).await.expect("unmute should work");
+    react(ids[0], reactor, "🔥").await.expect("react should work");
+    react(ids[1], reactor, "👀").await.expect("react should work");
+    assert_eq!(repo.requested_notifications().len(), 3);
+    assert_eq!(repo.requested_notifications()[2].message_id, ids[1]);
+
+    let res = service.mute_reaction_notifications(&author_user, Some(&MessageId::from(Uuid::new_v4()))).await;
+    assert!(matches!(res, Err(CoreError::MessageNotFound { .. })));
+}
+
+#[tokio::test]
+async fn deleting_channel_messages_only_touches_that_channel() {
+    let service = Service::new(MockMessageRepository::new(), MockHealthRepository::new());
+    let deleted_channel = ChannelId::from(Uuid::new_v4());
+    let other_channel = ChannelId::from(Uuid::new_v4());
+
+    let input = |channel_id: ChannelId| InsertMessageInput {
+        id: MessageId::from(Uuid::new_v4()),
+        channel_id,
+        author_id: AuthorId::from(Uuid::new_v4()),
+        content: "hello".into(),
+        reply_to_message_id: None,
+        attachments: vec![],
+        nonce: None,
+        expires_at: None,
+    };
+
+    for channel in [deleted_channel, deleted_channel, other_channel] {
+        service.create_message(input(channel)).await.expect("create");
+    }
+
+    let purge = service.delete_channel_messages(&deleted_channel).await.expect("delete channel");
+    assert_eq!(purge.deleted, 2);
+    assert!(purge.held_by.is_empty());
+
+    let pagination = GetPaginated { page: 1, limit: 10 };
+    let (remaining, total) = service.list_messages(&deleted_channel, &pagination).await.expect("list");
+    assert!(remaining.is_empty());
+    assert_eq!(total, 0);
+    let (_, total) = service.list_messages(&other_channel, &pagination).await.expect("list");
+    assert_eq!(total, 1);
+
+    // a redelivered event is a no-op
+    let purge = service.delete_channel_messages(&deleted_channel).await.expect("delete channel again");
+    assert_eq!(purge.deleted, 0);
+}
+
+#[tokio::test]
+async fn deleted_messages_stay_readable_for_moderation() {
+    let service = Service::new(MockMessageRepository::new(), MockHealthRepository::new());
+    let channel = ChannelId::from(Uuid::new_v4());
+
+    let input = || InsertMessageInput {
+        id: MessageId::from(Uuid::new_v4()),
+        channel_id: channel,
+        author_id: AuthorId::from(Uuid::new_v4()),
+        content: "rude words".into(),
+        reply_to_message_id: None,
+        attachments: vec![],
+        nonce: None,
+        expires_at: None,
+    };
+    let kept = service.create_message(input()).await.expect("create");
+    let deleted = service.create_message(input()).await.expect("create");
+    service.delete_message(&deleted.id).await.expect("delete");
+
+    assert!(matches!(service.get_message(&deleted.id).await, Err(CoreError::MessageNotFound { .. })));
+    let reviewed = service.get_message_including_deleted(&deleted.id).await.expect("get deleted");
+    assert_eq!(reviewed.content, "rude words");
+    assert!(reviewed.deleted_at.is_some());
+
+    let pagination = GetPaginated { page: 1, limit: 10 };
+    let (visible, total) = service.list_messages(&channel, &pagination).await.expect("list");
+    assert_eq!(total, 1);
+    assert_eq!(visible[0].id, kept.id);
+    let (all, total) = service.list_messages_including_deleted(&channel, &pagination).await.expect("list deleted");
+    assert_eq!(total, 2);
+    assert!(all.iter().any(|m| m.id == deleted.id && m.deleted_at.is_some()));
+    assert!(all.iter().any(|m| m.id == kept.id && m.deleted_at.is_none()));
+
+    let missing = service.get_message_including_deleted(&MessageId::from(Uuid::new_v4())).await;
+    assert!(matches!(missing, Err(CoreError::MessageNotFound { .. })));
+}
+
+#[tokio::test]
+async fn hard_deletes_remove_messages_unless_held() {
+    let repo = MockMessageRepository::new();
+    let service = Service::new(repo.clone(), MockHealthRepository::new());
+    let channel = ChannelId::from(Uuid::new_v4());
+    let held_author = AuthorId::from(Uuid::new_v4());
+
+    let input = |author_id: AuthorId| InsertMessageInput {
+        id: MessageId::from(Uuid::new_v4()),
+        channel_id: channel,
+        author_id,
+        content: "hello".into(),
+        reply_to_message_id: None,
+        attachments: vec![],
+        nonce: None,
+        expires_at: None,
+    };
+    let live = service.create_message(input(AuthorId::from(Uuid::new_v4()))).await.expect("create");
+    let soft_deleted = service.create_message(input(AuthorId::from(Uuid::new_v4()))).await.expect("create");
+    let held = service.create_message(input(held_author)).await.expect("create");
+    service
+        .add_reaction(AddReactionInput { message_id: live.id, user_id: UserId::from(Uuid::new_v4()), emoji: "👍".into() })
+        .await
+        .expect("react");
+    service.delete_message(&soft_deleted.id).await.expect("soft delete");
+
+    // live and soft deleted messages alike are gone for good
+    service.hard_delete_message(&live.id).await.expect("hard delete live");
+    service.hard_delete_message(&soft_deleted.id).await.expect("hard delete soft deleted");
+    for id in [live.id, soft_deleted.id] {
+        let res = service.get_message_including_deleted(&id).await;
+        assert!(matches!(res, Err(CoreError::MessageNotFound { .. })));
+    }
+    assert!(repo.list_reactions(&live.id).await.expect("reactions").is_empty());
+    let res = service.hard_delete_message(&live.id).await;
+    assert!(matches!(res, Err(CoreError::MessageNotFound { .. })));
+
+    service
+        .place_legal_hold(PlaceLegalHoldInput {
+            scope: LegalHoldScope::User(UserId::from(held_author.0)),
+            reference: "CASE-3".into(),
+            placed_by: UserId::from(Uuid::new_v4()),
+        })
+        .await
+        .expect("place hold");
+    let res = service.hard_delete_message(&held.id).await;
+    assert!(matches!(res, Err(CoreError::MessageUnderLegalHold { .. })));
+    service.get_message(&held.id).await.expect("held message is kept");
+}
+
+#[tokio::test]
+async fn purge_removes_expired_deletions_and_releases_storage() {
+    let repo = MockMessageRepository::new();
+    let files = InMemoryAttachmentStorage::new();
+    let service = Service::new(repo.clone(), MockHealthRepository::new()).with_attachment_storage(Arc::new(files.clone()));
+    let channel = ChannelId::from(Uuid::new_v4());
+    let held_author = AuthorId::from(Uuid::new_v4());
+
+    let input = |author_id: AuthorId, attachment: Attachment| InsertMessageInput {
+        id: MessageId::from(Uuid::new_v4()),
+        channel_id: channel,
+        author_id,
+        content: "hello".into(),
+        reply_to_message_id: None,
+        attachments: vec![attachment],
+        nonce: None,
+        expires_at: None,
+    };
+    let author = AuthorId::from(Uuid::new_v4());
+    let kept = service.create_message(input(author, uploaded_attachment(&service, &files, channel, author, 10).await)).await.expect("create");
+    let author = AuthorId::from(Uuid::new_v4());
+    let expired = service.create_message(input(author, uploaded_attachment(&service, &files, channel, author, 100).await)).await.expect("create");
+    let held = service.create_message(input(held_author, uploaded_attachment(&service, &files, channel, held_author, 1000).await)).await.expect("create");
+    service.delete_message(&expired.id).await.expect("delete");
+    service.delete_message(&held.id).await.expect("delete");
+    service
+        .place_legal_hold(PlaceLegalHoldInput {
+            scope: LegalHoldScope::User(UserId::from(held_author.0)),
+            reference: "CASE-4".into(),
+            placed_by: UserId::from(Uuid::new_v4()),
+        })
+        .await
+        .expect("place hold");
+
+    // still within the retention
+    let purged = service.purge_deleted_messages(chrono::Utc::now() - chrono::Duration::days(1), 10).await.expect("purge");
+    assert_eq!(purged, 0);
+
+    let purged = service.purge_deleted_messages(chrono::Utc::now() + chrono::Duration::seconds(1), 10).await.expect("purge");
+    assert_eq!(purged, 1);
+    let res = service.get_message_including_deleted(&expired.id).await;
+    assert!(matches!(res, Err(CoreError::MessageNotFound { .. })));
+    service.get_message_including_deleted(&held.id).await.expect("held message is kept");
+    service.get_message(&kept.id).await.expect("live message is kept");
+    assert_eq!(service.channel_storage(&channel).await.expect("storage").used_bytes, 1010);
+}
+
+#[tokio::test]
+async fn legal_holds_keep_messages_out_of_channel_purges() {
+    let service = Service::new(MockMessageRepository::new(), MockHealthRepository::new());
+    let held_channel = ChannelId::from(Uuid::new_v4());
+    let other_channel = ChannelId::from(Uuid::new_v4());
+    let held_author = AuthorId::from(Uuid::new_v4());
+    let admin = UserId::from(Uuid::new_v4());
+
+    let input = |channel_id: ChannelId, author_id: AuthorId| InsertMessageInput {
+        id: MessageId::from(Uuid::new_v4()),
+        channel_id,
+        author_id,
+        content: "hello".into(),
+        reply_to_message_id: None,
+        attachments: vec![],
+        nonce: None,
+        expires_at: None,
+    };
+    service.create_message(input(held_channel, AuthorId::from(Uuid::new_v4()))).await.expect("create");
+    service.create_message(input(other_channel, held_author)).await.expect("create");
+    service.create_message(input(other_channel, AuthorId::from(Uuid::new_v4()))).await.expect("create");
+
+    let blank = service
+        .place_legal_hold(PlaceLegalHoldInput { scope: LegalHoldScope::Channel(held_channel), reference: "  ".into(), placed_by: admin })
+        .await;
+    assert!(matches!(blank, Err(CoreError::InvalidLegalHoldReference)));
+
+    let channel_hold = service
+        .place_legal_hold(PlaceLegalHoldInput { scope: LegalHoldScope::Channel(held_channel), reference: " CASE-1 ".into(), placed_by: admin })
+        .await
+        .expect("place channel hold");
+    assert_eq!(channel_hold.reference, "CASE-1");
+    service
+        .place_legal_hold(PlaceLegalHoldInput { scope: LegalHoldScope::User(UserId::from(held_author.0)), reference: "CASE-2".into(), placed_by: admin })
+        .await
+        .expect("place user hold");
+
+    // a held channel is kept whole
+    let purge = service.delete_channel_messages(&held_channel).await.expect("purge held channel");
+    assert_eq!(purge.deleted, 0);
+    assert_eq!(purge.held_by, vec!["CASE-1".to_string()]);
+
+    // only the messages of held users are kept elsewhere
+    let purge = service.delete_channel_messages(&other_channel).await.expect("purge other channel");
+    assert_eq!(purge.deleted, 1);
+    assert_eq!(purge.held_by, vec!["CASE-2".to_string()]);
+    let pagination = GetPaginated { page: 1, limit: 10 };
+    let (remaining, _) = service.list_messages(&other_channel, &pagination).await.expect("list");
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining[0].author_id, held_author);
+
+    // released holds no longer protect the channel
+    let holds = service.list_legal_holds(&LegalHoldScope::Channel(held_channel)).await.expect("list holds");
+    assert_eq!(holds, vec![channel_hold.clone()]);
+    service.release_legal_hold(&channel_hold.id).await.expect("release");
+    let res = service.get_legal_hold(&channel_hold.id).await;
+    assert!(matches!(res, Err(CoreError::LegalHoldNotFound { .. })));
+    let res = service.release_legal_hold(&channel_hold.id).await;
+    assert!(matches!(res, Err(CoreError::LegalHoldNotFound { .. })));
+    let purge = service.delete_channel_messages(&held_channel).await.expect("purge released channel");
+    assert_eq!(purge.deleted, 1);
+    assert!(purge.held_by.is_empty());
+}
+
+#[tokio::test]
+async fn channel_digest_summarizes_recent_activity() {
+    let repo = MockMessageRepository::new();
+    let service = Service::new(repo, MockHealthRepository::new());
+    let channel = ChannelId::from(Uuid::new_v4());
+    let since = chrono::Utc::now() - chrono::Duration::minutes(1);
+
+    let post = |channel_id: ChannelId, reply_to_message_id: Option<MessageId>| InsertMessageInput {
+        id: MessageId::from(Uuid::new_v4()),
+        channel_id,
+        author_id: AuthorId::from(Uuid::new_v4()),
+        content: "hello".into(),
+        reply_to_message_id,
+        attachments: vec![],
+        nonce: None,
+        expires_at: None,
+    };
+    let question = service.create_message(post(channel, None)).await.expect("create should work");
+    let popular = service.create_message(post(channel, None)).await.expect("create should work");
+    let pinned = service.create_message(post(channel, None)).await.expect("create should work");
+    service.create_message(post(channel, Some(question.id))).await.expect("reply should work");
+    service
+        .create_message(post(ChannelId::from(Uuid::new_v4()), None))
+        .await
+        .expect("create should work");
+
+    for (message_id, emoji) in [(popular.id, "👍"), (popular.id, "🎉"), (pinned.id, "👍")] {
+        service
+            .add_reaction(AddReactionInput {
+                message_id,
+                user_id: UserId::from(Uuid::new_v4()),
+                emoji: emoji.into(),
+            })
+            .await
+            .expect("reaction should work");
+    }
+    service
+        .update_message(UpdateMessageInput { id: pinned.id, content: None, is_pinned: Some(true), expected_revision: None })
+        .await
+        .expect("pin should work");
+
+    let digest = service.channel_digest(&channel, since).await.expect("digest should work");
+    assert_eq!(digest.message_count, 4);
+    let ids = |messages: &[communities_core::domain::message::entities::Message]| {
+        messages.iter().map(|m| m.id).collect::<Vec<_>>()
+    };
+    assert_eq!(ids(&digest.top_reacted), vec![popular.id, pinned.id]);
+    assert_eq!(ids(&digest.new_pins), vec![pinned.id]);
+    assert_eq!(ids(&digest.active_threads), vec![question.id]);
+
+    let later = chrono::Utc::now() + chrono::Duration::seconds(1);
+    let digest = service.channel_digest(&channel, later).await.expect("digest should work");
+    assert_eq!(digest.message_count, 0);
+    assert!(digest.top_reacted.is_empty() && digest.new_pins.is_empty() && digest.active_threads.is_empty());
+}
+
+#[tokio::test]
+async fn author_messages_are_listed_per_channel_newest_first() {
+    let repo = MockMessageRepository::new();
+    let service = Service::new(repo, MockHealthRepository::new());
+    let channel = ChannelId::from(Uuid::new_v4());
+    let author = AuthorId::from(Uuid::new_v4());
+
+    let post = |channel_id: ChannelId, author_id: AuthorId| InsertMessageInput {
+        id: MessageId::from(Uuid::new_v4()),
+        channel_id,
+        author_id,
+        content: "hello".into(),
+        reply_to_message_id: None,
+        attachments: vec![],
+        nonce: None,
+        expires_at: None,
+    };
+    for _ in 0..3 {
+        service.create_message(post(channel, author)).await.expect("create should work");
+    }
+    service.create_message(post(channel, AuthorId::from(Uuid::new_v4()))).await.expect("create should work");
+    service.create_message(post(ChannelId::from(Uuid::new_v4()), author)).await.expect("create should work");
+
+    let (messages, total) = service
+        .list_author_messages(&author, &channel, &GetPaginated { page: 1, limit: 2 })
+        .await
+        .expect("listing should work");
+    assert_eq!(total, 3);
+    assert_eq!(messages.len(), 2);
+    assert!(messages.iter().all(|m| m.author_id == author && m.channel_id == channel));
+    assert!(messages[0].created_at >= messages[1].created_at);
+}
+
+#[tokio::test]
+async fn mass_deletions_are_reported_once_per_burst() {
+    let repo = MockMessageRepository::new();
+    let service = Service::new(repo.clone(), MockHealthRepository::new())
+        .with_mass_deletion_threshold(Some(AbuseThreshold { count: 3, window: chrono::Duration::minutes(10) }));
+    let channel = ChannelId::from(Uuid::new_v4());
+    let author = AuthorId::from(Uuid::new_v4());
+
+    let mut ids = Vec::new();
+    for _ in 0..4 {
+        let message = service
+            .create_message(InsertMessageInput {
+                id: MessageId::from(Uuid::new_v4()),
+                channel_id: channel,
+                author_id: author,
+                content: "soon gone".into(),
+                reply_to_message_id: None,
+                attachments: vec![],
+                nonce: None,
+                expires_at: None,
+            })
+            .await
+            .expect("create should work");
+        ids.push(message.id);
+    }
+
+    for id in &ids[..2] {
+        service.delete_message(id).await.expect("delete should work");
+    }
+    assert!(repo.reported_abuse().is_empty());
+
+    for id in &ids[2..] {
+        service.delete_message(id).await.expect("delete should work");
+    }
+    let reports = repo.reported_abuse();
+    assert_eq!(reports.len(), 1);
+    assert_eq!(reports[0].pattern, AbusePattern::MassDeletion);
+    assert_eq!(reports[0].user_id, UserId::from(author.0));
+    assert_eq!(reports[0].channel_id, channel);
+    assert_eq!(reports[0].count, 3);
+    assert_eq!(reports[0].window_seconds, 600);
+}
+
+#[tokio::test]
+async fn pinned_messages_are_listed_most_recently_pinned_first() {
+    let service = Service::new(MockMessageRepository::new(), MockHealthRepository::new());
+    let channel = ChannelId::from(Uuid::new_v4());
+
+    let post = |channel_id: ChannelId| InsertMessageInput {
+        id: MessageId::from(Uuid::new_v4()),
+        channel_id,
+        author_id: AuthorId::from(Uuid::new_v4()),
+        content: "pin me".into(),
+        reply_to_message_id: None,
+        attachments: vec![],
+        nonce: None,
+        expires_at: None,
+    };
+    let pin = |id: MessageId| UpdateMessageInput { id, content: None, is_pinned: Some(true), expected_revision: None };
+
+    let first = service.create_message(post(channel)).await.expect("create should work");
+    let second = service.create_message(post(channel)).await.expect("create should work");
+    service.create_message(post(channel)).await.expect("create should work");
+    let elsewhere = service.create_message(post(ChannelId::from(Uuid::new_v4()))).await.expect("create should work");
+
+    service.update_message(pin(second.id)).await.expect("pin should work");
+    service.update_message(pin(elsewhere.id)).await.expect("pin should work");
+    service.update_message(pin(first.id)).await.expect("pin should work");
+
+    let (pinned, total) = service
+        .list_pinned_messages(&channel, &GetPaginated { page: 1, limit: 10 })
+        .await
+        .expect("listing should work");
+    assert_eq!(total, 2);
+    assert!(pinned.iter().all(|m| m.is_pinned && m.channel_id == channel));
+    let ids: Vec<MessageId> = pinned.iter().map(|m| m.id).collect();
+    assert!(ids.contains(&first.id) && ids.contains(&second.id));
+}
+
+#[tokio::test]
+async fn pinning_stops_at_the_channel_pin_limit() {
+    let service = Service::new(MockMessageRepository::new(), MockHealthRepository::new()).with_pin_limit(Some(2));
+    let channel = ChannelId::from(Uuid::new_v4());
+
+    let mut ids = Vec::new();
+    for _ in 0..3 {
+        let message = service
+            .create_message(InsertMessageInput {
+                id: MessageId::from(Uuid::new_v4()),
+                channel_id: channel,
+                author_id: AuthorId::from(Uuid::new_v4()),
+                content: "pin me".into(),
+                reply_to_message_id: None,
+                attachments: vec![],
+                nonce: None,
+                expires_at: None,
+            })
+            .await
+            .expect("create should work");
+        ids.push(message.id);
+    }
+    let pin = |id: MessageId, is_pinned: bool| UpdateMessageInput { id, content: None, is_pinned: Some(is_pinned), expected_revision: None };
+
+    service.update_message(pin(ids[0], true)).await.expect("pin should work");
+    service.update_message(pin(ids[1], true)).await.expect("pin should work");
+    let res = service.update_message(pin(ids[2], true)).await;
+    assert!(matches!(res, Err(CoreError::PinLimitReached { limit: 2, .. })));
+
+    // pinned messages can still be edited, and unpinning frees a slot
+    service.update_message(pin(ids[0], true)).await.expect("re-pinning should work");
+    service.update_message(pin(ids[1], false)).await.expect("unpin should work");
+    service.update_message(pin(ids[2], true)).await.expect("pin should work");
+}
+
+#[tokio::test]
+async fn edits_keep_the_replaced_content_in_the_history() {
+    let repo = MockMessageRepository::new();
+    let service = Service::new(repo.clone(), MockHealthRepository::new());
+
+    let message = service
+        .create_message(InsertMessageInput {
+            id: MessageId::from(Uuid::new_v4()),
+            channel_id: ChannelId::from(Uuid::new_v4()),
+            author_id: AuthorId::from(Uuid::new_v4()),
+            content: "first".into(),
+            reply_to_message_id: None,
+            attachments: vec![],
+            nonce: None,
+            expires_at: None,
+        })
+        .await
+        .expect("create");
+    let edit = |content: Option<&str>, is_pinned: Option<bool>| UpdateMessageInput {
+        id: message.id,
+        content: content.map(String::from),
+        is_pinned,
+        expected_revision: None,
+    };
+    service.update_message(edit(Some("second"), None)).await.expect("edit");
+    // pins and unchanged contents are not edits
+    service.update_message(edit(None, Some(true))).await.expect("pin");
+    service.update_message(edit(Some("second"), None)).await.expect("no-op edit");
+    let latest = service.update_message(edit(Some("third"), None)).await.expect("edit");
+    assert_eq!(latest.revision, 4);
+
+    let history = service.get_message_history(&message.id).await.expect("history");
+    let contents: Vec<(u64, &str)> = history.iter().map(|r| (r.revision, r.content.as_str())).collect();
+    assert_eq!(contents, vec![(0, "first"), (3, "second")]);
+    assert_eq!(history[0].written_at, message.created_at);
+    assert!(history[0].replaced_at <= history[1].replaced_at);
+
+    // moderators can still audit deleted messages, until they are purged
+    service.delete_message(&message.id).await.expect("soft delete");
+    assert_eq!(service.get_message_history(&message.id).await.expect("history").len(), 2);
+    service.hard_delete_message(&message.id).await.expect("hard delete");
+    assert!(repo.list_revisions(&message.id).await.expect("revisions").is_empty());
+    let res = service.get_message_history(&message.id).await;
+    assert!(matches!(res, Err(CoreError::MessageNotFound { .. })));
+}
+
+#[tokio::test]
+async fn stale_revisions_do_not_overwrite_concurrent_edits() {
+    let service = Service::new(MockMessageRepository::new(), MockHealthRepository::new());
+    let message = service
+        .create_message(InsertMessageInput {
+            id: MessageId::from(Uuid::new_v4()),
+            channel_id: ChannelId::from(Uuid::new_v4()),
+            author_id: AuthorId::from(Uuid::new_v4()),
+            content: "draft".into(),
+            reply_to_message_id: None,
+            attachments: vec![],
+            nonce: None,
+            expires_at: None,
+        })
+        .await
+        .expect("create");
+    let edit = |content: &str| UpdateMessageInput {
+        id: message.id,
+        content: Some(content.into()),
+        is_pinned: None,
+        expected_revision: Some(message.revision),
+    };
+
+    // both editors read revision 0, the first to save wins
+    let saved = service.update_message(edit("first editor")).await.expect("first edit");
+    let res = service.update_message(edit("second editor")).await;
+    assert!(matches!(
+        res,
+        Err(CoreError::MessageRevisionMismatch { revision, .. }) if revision == saved.revision
+    ));
+    assert_eq!(service.get_message(&message.id).await.expect("get").content, "first editor");
+}
+
+#[tokio::test]
+async fn locked_channels_refuse_writes_until_unlocked() {
+    let service = Service::new(MockMessageRepository::new(), MockHealthRepository::new());
+    let channel = ChannelId::from(Uuid::new_v4());
+    let input = || InsertMessageInput {
+        id: MessageId::from(Uuid::new_v4()),
+        channel_id: channel,
+        author_id: AuthorId::from(Uuid::new_v4()),
+        content: "during migration".into(),
+        reply_to_message_id: None,
+        attachments: vec![],
+        nonce: None,
+        expires_at: None,
+    };
+    let lock = |seconds| LockChannelWritesInput {
+        channel_id: channel,
+        reason: "import".into(),
+        locked_by: UserId::from(Uuid::new_v4()),
+        duration: chrono::Duration::seconds(seconds),
+    };
+
+    let res = service.lock_channel_writes(lock(0)).await;
+    assert!(matches!(res, Err(CoreError::InvalidChannelWriteLock { .. })));
+
+    let message = service.create_message(input()).await.expect("create before lock");
+    service.lock_channel_writes(lock(60)).await.expect("lock");
+
+    let res = service.create_message(input()).await;
+    assert!(matches!(
+        res,
+        Err(CoreError::ChannelWriteLocked { retry_after_secs, .. }) if (1..=60).contains(&retry_after_secs)
+    ));
+    assert!(matches!(
+        service.delete_message(&message.id).await,
+        Err(CoreError::ChannelWriteLocked { .. })
+    ));
+    // reads are unaffected
+    service.get_message(&message.id).await.expect("get while locked");
+
+    // other channels are unaffected
+    let other = InsertMessageInput { channel_id: ChannelId::from(Uuid::new_v4()), ..input() };
+    service.create_message(other).await.expect("create in another channel");
+
+    service.unlock_channel_writes(&channel).await.expect("unlock");
+    assert!(service.channel_write_lock(&channel).await.expect("lock").is_none());
+    service.create_message(input()).await.expect("create after unlock");
+}
+
+#[tokio::test]
+async fn bulk_deletes_only_touch_the_channel_and_announce_once() {
+    let repo = MockMessageRepository::new();
+    let service = Service::new(repo.clone(), MockHealthRepository::new());
+    let channel = ChannelId::from(Uuid::new_v4());
+    let moderator = UserId::from(Uuid::new_v4());
+    let post = |channel_id| InsertMessageInput {
+        id: MessageId::from(Uuid::new_v4()),
+        channel_id,
+        author_id: AuthorId::from(Uuid::new_v4()),
+        content: "spam".into(),
+        reply_to_message_id: None,
+        attachments: vec![],
+        nonce: None,
+        expires_at: None,
+    };
+
+    let first = service.create_message(post(channel)).await.expect("create");
+    let second = service.create_message(post(channel)).await.expect("create");
+    let kept = service.create_message(post(channel)).await.expect("create");
+    let elsewhere = service
+        .create_message(post(ChannelId::from(Uuid::new_v4())))
+        .await
+        .expect("create");
+    let missing = MessageId::from(Uuid::new_v4());
+
+    let deleted = service
+        .bulk_delete_messages(&channel, &[first.id, second.id, elsewhere.id, missing], &moderator, None)
+        .await
+        .expect("bulk delete");
+    assert_eq!(deleted.len(), 2);
+    assert!(deleted.contains(&first.id) && deleted.contains(&second.id));
+
+    assert!(matches!(service.get_message(&first.id).await, Err(CoreError::MessageNotFound { .. })));
+    service.get_message(&kept.id).await.expect("untouched message");
+    service.get_message(&elsewhere.id).await.expect("message of another channel");
+
+    let events = repo.bulk_deletions();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].channel_id, channel);
+    assert_eq!(events[0].deleted_by, moderator);
+    assert_eq!(events[0].ids.len(), 2);
+}
+
+#[tokio::test]
+async fn channel_exports_stream_messages_oldest_first() {
+    use futures::TryStreamExt;
+
+    let repo = MockMessageRepository::new();
+    let service = Service::new(repo.clone(), MockHealthRepository::new());
+    let channel = ChannelId::from(Uuid::new_v4());
+    let post = |channel_id| InsertMessageInput {
+        id: MessageId::from(Uuid::new_v4()),
+        channel_id,
+        author_id: AuthorId::from(Uuid::new_v4()),
+        content: "archived".into(),
+        reply_to_message_id: None,
+        attachments: vec![],
+        nonce: None,
+        expires_at: None,
+    };
+
+    let first = service.create_message(post(channel)).await.expect("create");
+    let deleted = service.create_message(post(channel)).await.expect("create");
+    let last = service.create_message(post(channel)).await.expect("create");
+    service
+        .create_message(post(ChannelId::from(Uuid::new_v4())))
+        .await
+        .expect("create");
+    service.delete_message(&deleted.id).await.expect("delete");
+
+    let exported: Vec<MessageId> = service
+        .export_channel_messages(&channel, false)
+        .await
+        .expect("export")
+        .map_ok(|m| m.id)
+        .try_collect()
+        .await
+        .expect("stream");
+    assert_eq!(exported, vec![first.id, last.id]);
+
+    let exported: Vec<MessageId> = service
+        .export_channel_messages(&channel, true)
+        .await
+        .expect("export")
+        .map_ok(|m| m.id)
+        .try_collect()
+        .await
+        .expect("stream");
+    assert_eq!(exported, vec![first.id, deleted.id, last.id]);
+}
+
+#[tokio::test]
+async fn imports_keep_ids_and_timestamps_and_reject_invalid_messages() {
+    let repo = MockMessageRepository::new();
+    let service = Service::new(repo.clone(), MockHealthRepository::new());
+    let channel = ChannelId::from(Uuid::new_v4());
+    let written_at = chrono::Utc::now() - chrono::Duration::days(365);
+    let message = |content: &str, reply_to_message_id| ImportMessageInput {
+        id: MessageId::from(Uuid::new_v4()),
+        author_id: AuthorId::from(Uuid::new_v4()),
+        content: content.into(),
+        reply_to_message_id,
+        attachments: vec![],
+        is_pinned: false,
+        created_at: written_at,
+        updated_at: None,
+    };
+
+    // imports run while the channel is locked for them
+    service
+        .lock_channel_writes(LockChannelWritesInput {
+            channel_id: channel,
+            reason: "import".into(),
+            locked_by: UserId::from(Uuid::new_v4()),
+            duration: chrono::Duration::seconds(600),
+        })
+        .await
+        .expect("lock");
+
+    let parent = message("parent", None);
+    let reply = message("reply", Some(parent.id));
+    let orphan = message("orphan", Some(MessageId::from(Uuid::new_v4())));
+    let empty = message(" ", None);
+    let mut from_the_future = message("later", None);
+    from_the_future.created_at = chrono::Utc::now() + chrono::Duration::days(1);
+
+    let outcome = service
+        .import_messages(
+            &channel,
+            vec![parent.clone(), reply.clone(), orphan, empty, from_the_future],
+        )
+        .await
+        .expect("import");
+    assert_eq!(outcome.imported, vec![parent.id, reply.id]);
+    let rejected: Vec<usize> = outcome.rejected.iter().map(|(position, _)| *position).collect();
+    assert_eq!(rejected, vec![2, 3, 4]);
+    assert!(matches!(outcome.rejected[0].1, CoreError::MessageNotFound { .. }));
+    assert!(matches!(outcome.rejected[1].1, CoreError::InvalidMessageName));
+    assert!(matches!(outcome.rejected[2].1, CoreError::InvalidMessageTimestamps { .. }));
+
+    let stored = service.get_message(&parent.id).await.expect("imported parent");
+    assert_eq!(stored.created_at, written_at);
+    assert_eq!(stored.reply_count, 1);
+    assert_eq!(stored.channel_id, channel);
+
+    // sending the import again stores nothing twice
+    let outcome = service
+        .import_messages(&channel, vec![parent.clone(), reply.clone()])
+        .await
+        .expect("import");
+    assert!(outcome.imported.is_empty());
+    assert!(outcome
+        .rejected
+        .iter()
+        .all(|(_, error)| matches!(error, CoreError::MessageAlreadyExists { .. })));
+}
+
+#[tokio::test]
+async fn reactions_are_restricted_to_the_channel_allowlist() {
+    let repo = MockMessageRepository::new();
+    let health = MockHealthRepository::new();
+    let service = Service::new(repo, health);
+
+    let channel = ChannelId::from(Uuid::new_v4());
+    let id = MessageId::from(Uuid::new_v4());
+    let input = InsertMessageInput {
+        id,
+        channel_id: channel,
+        author_id: AuthorId::from(Uuid::new_v4()),
+        content: "react to me".into(),
+        reply_to_message_id: None,
+        attachments: vec![],
+        nonce: None,
+        expires_at: None,
+    };
+    service.create_message(input).await.expect("create should work");
+
+    let settings = service
+        .set_allowed_reactions(&channel, Some(vec![" 👍".into(), "✅".into(), "👍".into()]))
+        .await
+        .expect("restricting reactions should work");
+    assert_eq!(settings.allowed_reactions, Some(vec!["👍".to_string(), "✅".to_string()]));
+
+    let user = UserId::from(Uuid::new_v4());
+    service
+        .add_reaction(AddReactionInput { message_id: id, user_id: user, emoji: "✅".into() })
+        .await
+        .expect("allowed reaction should work");
+    let res = service
+        .add_reaction(AddReactionInput { message_id: id, user_id: user, emoji: "🎉".into() })
+        .await;
+    match res {
+        Err(CoreError::ReactionNotAllowed { emoji, allowed }) => {
+            assert_eq!(emoji, "🎉");
+            assert_eq!(allowed, vec!["👍".to_string(), "✅".to_string()]);
+        }
+        other => panic!("expected the reaction to be refused, got {other:?}"),
+    }
+
+    let res = service.set_allowed_reactions(&channel, Some(vec!["  ".into()])).await;
+    assert!(matches!(res, Err(CoreError::InvalidReaction { .. })));
+
+    service.set_allowed_reactions(&channel, None).await.expect("lifting should work");
+    service
+        .add_reaction(AddReactionInput { message_id: id, user_id: user, emoji: "🎉".into() })
+        .await
+        .expect("any reaction is allowed again");
+}
+
+#[tokio::test]
+async fn erasing_a_user_anonymizes_their_messages_outside_legal_holds() {
+    let repo = MockMessageRepository::new();
+    let files = InMemoryAttachmentStorage::new();
+    let service = Service::new(repo.clone(), MockHealthRepository::new()).with_attachment_storage(Arc::new(files.clone()));
+    let channel = ChannelId::from(Uuid::new_v4());
+    let held_channel = ChannelId::from(Uuid::new_v4());
+    let user = UserId::from(Uuid::new_v4());
+    let author = AuthorId::from(user.0);
+    let admin = UserId::from(Uuid::new_v4());
+
+    let content = format!("personal, cc <@{}>", Uuid::new_v4());
+    let input = |channel_id: ChannelId, author_id: AuthorId, attachment: Attachment| InsertMessageInput {
+        id: MessageId::from(Uuid::new_v4()),
+        channel_id,
+        author_id,
+        content: content.clone(),
+        reply_to_message_id: None,
+        attachments: vec![attachment],
+        nonce: Some(Uuid::new_v4().to_string()),
+        expires_at: None,
+    };
+    let other_author = AuthorId::from(Uuid::new_v4());
+    let other = service.create_message(input(channel, other_author, uploaded_attachment(&service, &files, channel, other_author, 10).await)).await.expect("create");
+    let live = service.create_message(input(channel, author, uploaded_attachment(&service, &files, channel, author, 10).await)).await.expect("create");
+    let deleted = service.create_message(input(channel, author, uploaded_attachment(&service, &files, channel, author, 10).await)).await.expect("create");
+    let preview = Embed { url: "https://example.com".into(), title: Some("Example".into()), description: None, image_url: None, site_name: None };
+    for message in [&live, &deleted] {
+        repo.set_embeds(&message.id, message.revision, std::slice::from_ref(&preview)).await.expect("set embeds");
+    }
+    service.delete_message(&deleted.id).await.expect("delete");
+    let held = service.create_message(input(held_channel, author, uploaded_attachment(&service, &files, held_channel, author, 10).await)).await.expect("create");
+    service
+        .add_reaction(AddReactionInput { message_id: other.id, user_id: user, emoji: "👍".into() })
+        .await
+        .expect("react");
+    service.follow_thread(&user, &other.id).await.expect("follow");
+    service.save_message(&user, &other.id).await.expect("save");
+    service.set_read_marker(&user, &channel, &other.id).await.expect("mark read");
+    service
+        .set_thread_preferences(ThreadPreferences { user_id: user, auto_follow_on_reply: false })
+        .await
+        .expect("set preferences");
+    service
+        .place_legal_hold(PlaceLegalHoldInput { scope: LegalHoldScope::Channel(held_channel), reference: "CASE-7".into(), placed_by: admin })
+        .await
+        .expect("place hold");
+
+    let mut events = service.events().subscribe();
+
+    let erasure = service.erase_user(&user, &admin).await.expect("erase");
+    assert_eq!(erasure.erased, 2);
+    assert_eq!(erasure.channels, vec![channel]);
+    assert_eq!(erasure.held_by, vec!["CASE-7".to_string()]);
+
+    for id in [live.id, deleted.id] {
+        let erased = service.get_message_including_deleted(&id).await.expect("erased message is kept anonymized");
+        assert!(erased.content.is_empty());
+        assert!(erased.mentions.is_empty());
+        assert!(erased.attachments.is_empty());
+        assert!(erased.embeds.is_empty());
+        assert!(erased.nonce.is_none());
+        assert!(erased.deleted_at.is_some());
+    }
+    // deleted before or not, so the search indexes drop both
+    let mut announced = Vec::new();
+    while let Ok(MessageEvent::Deleted { id, .. }) = events.try_recv() {
+        announced.push(id);
+    }
+    assert_eq!(announced.len(), 2);
+    assert!(announced.contains(&live.id) && announced.contains(&deleted.id));
+    service.get_message(&held.id).await.expect("held message is kept");
+    assert!(service.get_message(&other.id).await.expect("get").reactions.is_empty());
+    let (_, followed) = service.list_followed_threads(&user, &GetPaginated::default()).await.expect("followed threads");
+    assert_eq!(followed, 0);
+    let (_, saved) = service.list_saved_messages(&user, &GetPaginated::default()).await.expect("saved messages");
+    assert_eq!(saved, 0);
+    assert!(service.read_markers(&user).await.expect("read markers").is_empty());
+    assert!(service.thread_preferences(&user).await.expect("preferences").auto_follow_on_reply);
+    assert_eq!(service.channel_storage(&channel).await.expect("storage").used_bytes, 10);
+
+    // only the message deleted by the erasure is announced as deleted
+    let bulk_deletions = repo.bulk_deletions();
+    assert_eq!(bulk_deletions.len(), 1);
+    assert_eq!(bulk_deletions[0].ids, vec![live.id]);
+    assert_eq!(repo.user_erasures().len(), 1);
+
+    // erasing twice only rewrites what is already anonymized
+    service.erase_user(&user, &admin).await.expect("erase again");
+    assert_eq!(repo.bulk_deletions().len(), 1);
+}
+
+#[tokio::test]
+async fn idle_threads_are_archived_until_their_next_reply() {
+    let repo = MockMessageRepository::new();
+    let service = Service::new(repo.clone(), MockHealthRepository::new());
+    let channel = ChannelId::from(Uuid::new_v4());
+    let pagination = GetPaginated { page: 1, limit: 10 };
+
+    let input = |reply_to: Option<MessageId>| InsertMessageInput {
+        id: MessageId::from(Uuid::new_v4()),
+        channel_id: channel,
+        author_id: AuthorId::from(Uuid::new_v4()),
+        content: "thread".into(),
+        reply_to_message_id: reply_to,
+        attachments: vec![],
+        nonce: None,
+        expires_at: None,
+    };
+    let thread = service.create_message(input(None)).await.expect("create thread");
+    service.create_message(input(Some(thread.id))).await.expect("reply");
+    service.create_message(input(None)).await.expect("create message without replies");
+
+    let (threads, total) = service.list_threads(&channel, false, &pagination).await.expect("list threads");
+    assert_eq!(total, 1);
+    assert_eq!(threads[0].id, thread.id);
+
+    // replied to within the idle period
+    let archived = service.archive_idle_threads(chrono::Utc::now() - chrono::Duration::days(7), 10).await.expect("archive");
+    assert_eq!(archived, 0);
+
+    let archived = service.archive_idle_threads(chrono::Utc::now() + chrono::Duration::seconds(1), 10).await.expect("archive");
+    assert_eq!(archived, 1);
+    assert_eq!(repo.thread_archivals()[0].thread_id, thread.id);
+    let (_, total) = service.list_threads(&channel, false, &pagination).await.expect("list threads");
+    assert_eq!(total, 0);
+    let (threads, _) = service.list_threads(&channel, true, &pagination).await.expect("list archived threads");
+    assert!(threads[0].archived_at.is_some());
+
+    service.create_message(input(Some(thread.id))).await.expect("reply to the archived thread");
+    let revived = service.get_message(&thread.id).await.expect("get thread");
+    assert!(revived.archived_at.is_none());
+    assert_eq!(revived.reply_count, 2);
+    assert_eq!(repo.thread_unarchivals().len(), 1);
+    let (_, total) = service.list_threads(&channel, false, &pagination).await.expect("list threads");
+    assert_eq!(total, 1);
+}
+
+#[tokio::test]
+async fn thread_followers_are_notified_of_replies() {
+    let repo = MockMessageRepository::new();
+    let service = Service::new(repo.clone(), MockHealthRepository::new());
+    let channel = ChannelId::from(Uuid::new_v4());
+    let pagination = GetPaginated { page: 1, limit: 10 };
+    let follower = UserId::from(Uuid::new_v4());
+    let replier = UserId::from(Uuid::new_v4());
+
+    let input = |author: UserId, reply_to: Option<MessageId>| InsertMessageInput {
+        id: MessageId::from(Uuid::new_v4()),
+        channel_id: channel,
+        author_id: AuthorId::from(author.0),
+        content: "thread".into(),
+        reply_to_message_id: reply_to,
+        attachments: vec![],
+        nonce: None,
+        expires_at: None,
+    };
+    let thread = service.create_message(input(follower, None)).await.expect("create thread");
+
+    let missing = service.follow_thread(&follower, &MessageId::from(Uuid::new_v4())).await;
+    assert!(matches!(missing, Err(CoreError::MessageNotFound { .. })));
+    let follow = service.follow_thread(&follower, &thread.id).await.expect("follow");
+    assert_eq!(follow.channel_id, channel);
+    service.follow_thread(&follower, &thread.id).await.expect("follow again");
+
+    // the replier is not notified of their own reply, and follows the thread
+    let reply = service.create_message(input(replier, Some(thread.id))).await.expect("reply");
+    let notifications = repo.requested_notifications();
+    assert_eq!(notifications.len(), 1);
+    assert_eq!(notifications[0].kind, NotificationKind::Reply);
+    assert_eq!(notifications[0].recipient_id, follower);
+    assert_eq!(notifications[0].message_id, reply.id);
+    let (threads, total) = service.list_followed_threads(&replier, &pagination).await.expect("list followed threads");
+    assert_eq!(total, 1);
+    assert_eq!(threads[0].id, thread.id);
+
+    service.create_message(input(follower, Some(thread.id))).await.expect("reply of the follower");
+    assert_eq!(repo.requested_notifications().len(), 2);
+    assert_eq!(repo.requested_notifications()[1].recipient_id, replier);
+
+    // without auto follow, replying to another thread does not follow it
+    service
+        .set_thread_preferences(ThreadPreferences { user_id: replier, auto_follow_on_reply: false })
+        .await
+        .expect("set preferences");
+    let other = service.create_message(input(follower, None)).await.expect("create other thread");
+    service.create_message(input(replier, Some(other.id))).await.expect("reply to the other thread");
+    let (_, total) = service.list_followed_threads(&replier, &pagination).await.expect("list followed threads");
+    assert_eq!(total, 1);
+
+    service.unfollow_thread(&follower, &thread.id).await.expect("unfollow");
+    service.create_message(input(replier, Some(thread.id))).await.expect("reply after unfollow");
+    assert_eq!(repo.requested_notifications().len(), 2);
+}
+
+#[tokio::test]
+async fn user_export_covers_every_channel_and_deleted_messages_on_request() {
+    use futures::TryStreamExt;
+
+    let repo = MockMessageRepository::new();
+    let service = Service::new(repo.clone(), MockHealthRepository::new());
+    let user = UserId::from(Uuid::new_v4());
+
+    let input = |author: Uuid| InsertMessageInput {
+        id: MessageId::from(Uuid::new_v4()),
+        channel_id: ChannelId::from(Uuid::new_v4()),
+        author_id: AuthorId::from(author),
+        content: "exported".into(),
+        reply_to_message_id: None,
+        attachments: vec![],
+        nonce: None,
+        expires_at: None,
+    };
+    let first = service.create_message(input(user.0)).await.expect("create first");
+    let second = service.create_message(input(user.0)).await.expect("create second");
+    service.create_message(input(Uuid::new_v4())).await.expect("create message of someone else");
+    service.delete_message(&second.id).await.expect("delete second");
+
+    let exported: Vec<_> = service
+        .export_user_messages(&user, false)
+        .await
+        .expect("export")
+        .try_collect()
+        .await
+        .expect("read export");
+    assert_eq!(exported.len(), 1);
+    assert_eq!(exported[0].id, first.id);
+
+    let exported: Vec<_> = service
+        .export_user_messages(&user, true)
+        .await
+        .expect("export with deleted")
+        .try_collect()
+        .await
+        .expect("read export");
+    let ids: Vec<MessageId> = exported.iter().map(|m| m.id).collect();
+    assert_eq!(ids, vec![first.id, second.id]);
+}
+
+#[tokio::test]
+async fn moderation_reasons_follow_the_templates_and_reach_the_authors() {
+    let repo = MockMessageRepository::new();
+    let service = Service::new(repo.clone(), MockHealthRepository::new())
+        .with_moderation_reason_templates(vec![
+            "spam=Spam or advertising".parse::<ModerationReasonTemplate>().expect("template"),
+        ]);
+    let channel = ChannelId::from(Uuid::new_v4());
+    let moderator = UserId::from(Uuid::new_v4());
+    let author = UserId::from(Uuid::new_v4());
+
+    let message = service
+        .create_message(InsertMessageInput {
+            id: MessageId::from(Uuid::new_v4()),
+            channel_id: channel,
+            author_id: AuthorId::from(author.0),
+            content: "buy now".into(),
+            reply_to_message_id: None,
+            attachments: vec![],
+            nonce: None,
+            expires_at: None,
+        })
+        .await
+        .expect("create");
+
+    let unknown = ModerationReason { code: "rude".into(), text: None };
+    let result = service.bulk_delete_messages(&channel, &[message.id], &moderator, Some(unknown)).await;
+    assert!(matches!(result, Err(CoreError::UnknownModerationReason { .. })));
+    service.get_message(&message.id).await.expect("kept after a rejected reason");
+
+    let spam = ModerationReason { code: "spam".into(), text: None };
+    service
+        .bulk_delete_messages(&channel, &[message.id], &moderator, Some(spam))
+        .await
+        .expect("bulk delete");
+
+    let reason = repo.bulk_deletions()[0].reason.clone().expect("reason in the event");
+    assert_eq!(reason.text.as_deref(), Some("Spam or advertising"));
+    let notifications = repo.requested_notifications();
+    assert_eq!(notifications.len(), 1);
+    assert_eq!(notifications[0].kind, NotificationKind::Moderation);
+    assert_eq!(notifications[0].recipient_id, author);
+    assert_eq!(notifications[0].reason, Some(reason));
+}
+
+#[tokio::test]
+async fn expired_messages_are_deleted_per_channel_retention() {
+    let repo = MockMessageRepository::new();
+    let service = Service::new(repo.clone(), MockHealthRepository::new());
+    let default_channel = ChannelId::from(Uuid::new_v4());
+    let short_channel = ChannelId::from(Uuid::new_v4());
+    let kept_channel = ChannelId::from(Uuid::new_v4());
+    let held_author = AuthorId::from(Uuid::new_v4());
+
+    let message = |author_id: AuthorId, days_ago: i64| ImportMessageInput {
+        id: MessageId::from(Uuid::new_v4()),
+        author_id,
+        content: "old".into(),
+        reply_to_message_id: None,
+        attachments: vec![],
+        is_pinned: false,
+        created_at: chrono::Utc::now() - chrono::Duration::days(days_ago),
+        updated_at: None,
+    };
+    let old = message(AuthorId::from(Uuid::new_v4()), 100);
+    let recent = message(AuthorId::from(Uuid::new_v4()), 10);
+    let held = message(held_author, 100);
+    repo.insert_many(&default_channel, vec![old.clone(), recent.clone(), held.clone()]).await.expect("import");
+    let short = message(AuthorId::from(Uuid::new_v4()), 10);
+    repo.insert_many(&short_channel, vec![short.clone()]).await.expect("import");
+    let forever = message(AuthorId::from(Uuid::new_v4()), 1000);
+    repo.insert_many(&kept_channel, vec![forever.clone()]).await.expect("import");
+
+    service.set_channel_retention(&short_channel, Some(7 * 24 * 60 * 60)).await.expect("short retention");
+    let settings = service.set_channel_retention(&kept_channel, Some(0)).await.expect("keep forever");
+    assert_eq!(settings.retention_secs, Some(0));
+    service
+        .place_legal_hold(PlaceLegalHoldInput {
+            scope: LegalHoldScope::User(UserId::from(held_author.0)),
+            reference: "CASE-1".into(),
+            placed_by: UserId::from(Uuid::new_v4()),
+        })
+        .await
+        .expect("hold");
+
+    let deleted = service
+        .delete_expired_messages(Some(chrono::Duration::days(30)), 1)
+        .await
+        .expect("delete expired");
+    assert_eq!(deleted, 2);
+
+    assert!(matches!(service.get_message(&old.id).await, Err(CoreError::MessageNotFound { .. })));
+    assert!(matches!(service.get_message(&short.id).await, Err(CoreError::MessageNotFound { .. })));
+    service.get_message(&recent.id).await.expect("within the default retention");
+    service.get_message(&held.id).await.expect("under a legal hold");
+    service.get_message(&forever.id).await.expect("kept forever");
+    assert_eq!(repo.bulk_deletions().len(), 2);
+
+    // without a default retention, only custom ones apply
+    let deleted = service.delete_expired_messages(None, 10).await.expect("delete expired");
+    assert_eq!(deleted, 0);
+}
+
+#[tokio::test]
+async fn attachments_must_be_presigned_and_uploaded_before_being_posted() {
+    let files = InMemoryAttachmentStorage::new();
+    let service = Service::new(MockMessageRepository::new(), MockHealthRepository::new()).with_attachment_storage(Arc::new(files.clone()));
+    let channel = ChannelId::from(Uuid::new_v4());
+    let author = AuthorId::from(Uuid::new_v4());
+    let input = |author_id: AuthorId, attachment: Attachment, nonce: Option<&str>| InsertMessageInput {
+        id: MessageId::from(Uuid::new_v4()),
+        channel_id: channel,
+        author_id,
+        content: "see attached".into(),
+        reply_to_message_id: None,
+        attachments: vec![attachment],
+        nonce: nonce.map(str::to_string),
+        expires_at: None,
+    };
+
+    let res = service
+        .presign_attachment(PresignAttachmentInput { channel_id: channel, uploader_id: author, name: " ".into(), content_type: "text/plain".into(), size: 1 })
+        .await;
+    assert!(matches!(res, Err(CoreError::InvalidAttachment { .. })));
+
+    // free-form attachments are refused
+    let unknown = Attachment { id: AttachmentId::from(Uuid::new_v4()), name: "a".into(), url: "https://elsewhere.example.com/a".into(), size: 1, preview: None };
+    let res = service.create_message(input(author, unknown.clone(), None)).await;
+    assert!(matches!(res, Err(CoreError::AttachmentNotFound { id }) if id == unknown.id));
+
+    let presigned = service
+        .presign_attachment(PresignAttachmentInput { channel_id: channel, uploader_id: author, name: "report.pdf".into(), content_type: "application/pdf".into(), size: 100 })
+        .await
+        .expect("presign");
+    assert!(presigned.upload.expires_at > presigned.upload.created_at);
+    let attachment = Attachment::reference(presigned.upload.id);
+
+    let res = service.create_message(input(author, attachment.clone(), None)).await;
+    assert!(matches!(res, Err(CoreError::AttachmentNotUploaded { .. })));
+
+    // only the uploader can post it
+    files.upload(&presigned.upload.object_key, 42);
+    let res = service.create_message(input(AuthorId::from(Uuid::new_v4()), attachment.clone(), None)).await;
+    assert!(matches!(res, Err(CoreError::AttachmentNotFound { .. })));
+
+    // the uploaded size counts, not the announced one
+    let message = service.create_message(input(author, attachment.clone(), Some("n-1"))).await.expect("create");
+    assert_eq!(message.attachments[0].name, "report.pdf");
+    assert_eq!(message.attachments[0].size, 42);
+    assert_eq!(message.attachments[0].url, format!("memory://{}", presigned.upload.object_key));
+
+    // retrying the creation is fine, posting it with another message is not
+    let retried = service.create_message(input(author, attachment.clone(), Some("n-1"))).await.expect("retry");
+    assert_eq!(retried.id, message.id);
+    let res = service.create_message(input(author, attachment, None)).await;
+    assert!(matches!(res, Err(CoreError::AttachmentNotFound { .. })));
+}
+
+#[tokio::test]
+async fn uploads_are_claimed_all_or_none() {
+    let repo = MockMessageRepository::new();
+    let files = InMemoryAttachmentStorage::new();
+    let service = Service::new(repo.clone(), MockHealthRepository::new()).with_attachment_storage(Arc::new(files.clone()));
+    let channel = ChannelId::from(Uuid::new_v4());
+    let author = AuthorId::from(Uuid::new_v4());
+    let free = uploaded_attachment(&service, &files, channel, author, 1).await;
+    let taken = uploaded_attachment(&service, &files, channel, author, 1).await;
+
+    // another message claimed one of the uploads first
+    let other = MessageId::from(Uuid::new_v4());
+    repo.attach_uploads(&[taken.id], &other).await.expect("claim");
+    let res = repo.attach_uploads(&[free.id, taken.id], &MessageId::from(Uuid::new_v4())).await;
+    assert!(matches!(res, Err(CoreError::AttachmentNotFound { id }) if id == taken.id));
+
+    // the other upload was not claimed on the way
+    let uploads = repo.find_attachment_uploads(&[free.id]).await.expect("find");
+    assert_eq!(uploads[0].message_id, None);
+    let message = service
+        .create_message(InsertMessageInput {
+            id: MessageId::from(Uuid::new_v4()),
+            channel_id: channel,
+            author_id: author,
+            content: "see attached".into(),
+            reply_to_message_id: None,
+            attachments: vec![free.clone()],
+            nonce: None,
+            expires_at: None,
+        })
+        .await
+        .expect("create");
+    let uploads = repo.find_attachment_uploads(&[free.id]).await.expect("find");
+    assert_eq!(uploads[0].message_id, Some(message.id));
+}
+
+#[tokio::test]
+async fn attachments_are_refused_without_an_attachment_storage() {
+    let service = Service::new(MockMessageRepository::new(), MockHealthRepository::new());
+    let channel = ChannelId::from(Uuid::new_v4());
+    let author = AuthorId::from(Uuid::new_v4());
+
+    let res = service
+        .presign_attachment(PresignAttachmentInput { channel_id: channel, uploader_id: author, name: "a.txt".into(), content_type: "text/plain".into(), size: 1 })
+        .await;
+    assert!(matches!(res, Err(CoreError::AttachmentUploadsDisabled)));
+}
+
+#[tokio::test]
+async fn attachments_beyond_the_limits_are_refused() {
+    let files = InMemoryAttachmentStorage::new();
+    let service = Service::new(MockMessageRepository::new(), MockHealthRepository::new())
+        .with_attachment_storage(Arc::new(files.clone()))
+        .with_attachment_limits(AttachmentLimits {
+            max_count: Some(2),
+            max_size_bytes: Some(100),
+            allowed_content_types: vec!["image/*".into(), "application/pdf".into()],
+        });
+    let channel = ChannelId::from(Uuid::new_v4());
+    let author = AuthorId::from(Uuid::new_v4());
+    let presign = |content_type: &str, size: u64| PresignAttachmentInput { channel_id: channel, uploader_id: author, name: "file".into(), content_type: content_type.into(), size };
+    let input = |attachments: Vec<Attachment>| InsertMessageInput {
+        id: MessageId::from(Uuid::new_v4()),
+        channel_id: channel,
+        author_id: author,
+        content: "see attached".into(),
+        reply_to_message_id: None,
+        attachments,
+        nonce: None,
+        expires_at: None,
+    };
+
+    let res = service.presign_attachment(presign("application/x-msdownload", 10)).await;
+    assert!(matches!(res, Err(CoreError::AttachmentTypeNotAllowed { content_type }) if content_type == "application/x-msdownload"));
+    let res = service.presign_attachment(presign("image/png", 101)).await;
+    assert!(matches!(res, Err(CoreError::AttachmentTooLarge { max_bytes: 100 })));
+
+    let mut attachments = Vec::new();
+    for content_type in ["image/png", "IMAGE/JPEG", "application/pdf; version=1.7"] {
+        let presigned = service.presign_attachment(presign(content_type, 10)).await.expect("presign");
+        files.upload(&presigned.upload.object_key, 10);
+        attachments.push(Attachment::reference(presigned.upload.id));
+    }
+    let res = service.create_message(input(attachments.clone())).await;
+    assert!(matches!(res, Err(CoreError::TooManyAttachments { max: 2 })));
+
+    // uploads larger than announced are caught when posted
+    let presigned = service.presign_attachment(presign("image/gif", 10)).await.expect("presign");
+    files.upload(&presigned.upload.object_key, 1000);
+    let res = service.create_message(input(vec![Attachment::reference(presigned.upload.id)])).await;
+    assert!(matches!(res, Err(CoreError::AttachmentTooLarge { max_bytes: 100 })));
+
+    let message = service.create_message(input(attachments[..2].to_vec())).await.expect("create");
+    assert_eq!(message.attachments.len(), 2);
+}
+
+#[tokio::test]
+async fn thread_archiving_is_published_as_updates_of_the_thread() {
+    let service = Service::new(MockMessageRepository::new(), MockHealthRepository::new());
+    let channel = ChannelId::from(Uuid::new_v4());
+    let input = |reply_to: Option<MessageId>| InsertMessageInput {
+        id: MessageId::from(Uuid::new_v4()),
+        channel_id: channel,
+        author_id: AuthorId::from(Uuid::new_v4()),
+        content: "thread".into(),
+        reply_to_message_id: reply_to,
+        attachments: vec![],
+        nonce: None,
+        expires_at: None,
+    };
+    let thread = service.create_message(input(None)).await.expect("create thread");
+    service.create_message(input(Some(thread.id))).await.expect("reply");
+
+    let mut events = service.events().subscribe();
+    service.archive_idle_threads(chrono::Utc::now() + chrono::Duration::seconds(1), 10).await.expect("archive");
+    assert!(matches!(
+        events.recv().await,
+        Ok(MessageEvent::Updated(m)) if m.id == thread.id && m.archived_at.is_some()
+    ));
+
+    let reply = service.create_message(input(Some(thread.id))).await.expect("reply to the archived thread");
+    assert!(matches!(events.recv().await, Ok(MessageEvent::Created(m)) if m.id == reply.id));
+    assert!(matches!(
+        events.recv().await,
+        Ok(MessageEvent::Updated(m)) if m.id == thread.id && m.archived_at.is_none() && m.reply_count == 2
+    ));
+}
+
+#[tokio::test]
+async fn read_markers_only_move_forward() {
+    let service = Service::new(MockMessageRepository::new(), MockHealthRepository::new());
+    let channel = ChannelId::from(Uuid::new_v4());
+    let reader = UserId::from(Uuid::new_v4());
+    let input = |channel_id: ChannelId| InsertMessageInput {
+        id: MessageId::from(Uuid::new_v4()),
+        channel_id,
+        author_id: AuthorId::from(Uuid::new_v4()),
+        content: "unread".into(),
+        reply_to_message_id: None,
+        attachments: vec![],
+        nonce: None,
+        expires_at: None,
+    };
+    let first = service.create_message(input(channel)).await.expect("create");
+    let second = service.create_message(input(channel)).await.expect("create");
+    let elsewhere = service.create_message(input(ChannelId::from(Uuid::new_v4()))).await.expect("create");
+
+    let res = service.read_marker(&reader, &channel).await;
+    assert!(matches!(res, Err(CoreError::ReadMarkerNotFound { .. })));
+    let res = service.set_read_marker(&reader, &channel, &elsewhere.id).await;
+    assert!(matches!(res, Err(CoreError::MessageNotFound { .. })));
+
+    let marker = service.set_read_marker(&reader, &channel, &second.id).await.expect("mark");
+    assert_eq!(marker.last_read_message_id, second.id);
+    assert_eq!(marker.last_read_sequence, second.sequence);
+
+    // a late device marking the first message keeps the second one
+    let kept = service.set_read_marker(&reader, &channel, &first.id).await.expect("mark earlier");
+    assert_eq!(kept, marker);
+    assert_eq!(service.read_marker(&reader, &channel).await.expect("get"), marker);
+}
+
+#[tokio::test]
+async fn unread_counts_follow_the_read_markers() {
+    let service = Service::new(MockMessageRepository::new(), MockHealthRepository::new());
+    let (busy, quiet) = (ChannelId::from(Uuid::new_v4()), ChannelId::from(Uuid::new_v4()));
+    let reader = UserId::from(Uuid::new_v4());
+    let input = |channel_id: ChannelId| InsertMessageInput {
+        id: MessageId::from(Uuid::new_v4()),
+        channel_id,
+        author_id: AuthorId::from(Uuid::new_v4()),
+        content: "unread".into(),
+        reply_to_message_id: None,
+        attachments: vec![],
+        nonce: None,
+        expires_at: None,
+    };
+    let read = service.create_message(input(busy)).await.expect("create");
+    let latest = service.create_message(input(quiet)).await.expect("create");
+    service.create_message(input(busy)).await.expect("create");
+    let deleted = service.create_message(input(busy)).await.expect("create");
+    service.create_message(input(busy)).await.expect("create");
+    service.delete_message(&deleted.id).await.expect("delete");
+
+    service.set_read_marker(&reader, &busy, &read.id).await.expect("mark");
+    service.set_read_marker(&reader, &quiet, &latest.id).await.expect("mark");
+
+    let markers = service.read_markers(&reader).await.expect("markers");
+    assert_eq!(markers.len(), 2);
+    let mut counts = service.unread_counts(&markers).await.expect("counts");
+    counts.sort_by_key(|count| count.unread_count);
+    let counts: Vec<(ChannelId, u64)> = counts.iter().map(|count| (count.channel_id, count.unread_count)).collect();
+    assert_eq!(counts, vec![(quiet, 0), (busy, 2)]);
+
+    assert!(service.read_markers(&UserId::from(Uuid::new_v4())).await.expect("markers").is_empty());
+}
+
+#[tokio::test]
+async fn last_messages_are_the_newest_of_each_channel() {
+    let service = Service::new(MockMessageRepository::new(), MockHealthRepository::new());
+    let (busy, emptied, empty) = (
+        ChannelId::from(Uuid::new_v4()),
+        ChannelId::from(Uuid::new_v4()),
+        ChannelId::from(Uuid::new_v4()),
+    );
+    let input = |channel_id: ChannelId| InsertMessageInput {
+        id: MessageId::from(Uuid::new_v4()),
+        channel_id,
+        author_id: AuthorId::from(Uuid::new_v4()),
+        content: "preview".into(),
+        reply_to_message_id: None,
+        attachments: vec![],
+        nonce: None,
+        expires_at: None,
+    };
+    service.create_message(input(busy)).await.expect("create");
+    let newest = service.create_message(input(busy)).await.expect("create");
+    let deleted = service.create_message(input(emptied)).await.expect("create");
+    service.delete_message(&deleted.id).await.expect("delete");
+
+    let last = service.last_messages(&[empty, emptied, busy]).await.expect("last messages");
+    let ids: Vec<MessageId> = last.iter().map(|message| message.id).collect();
+    assert_eq!(ids, vec![newest.id]);
+}
+
+#[tokio::test]
+async fn last_messages_skip_expired_messages() {
+    let service = Service::new(MockMessageRepository::new(), MockHealthRepository::new());
+    let channel = ChannelId::from(Uuid::new_v4());
+    let input = |expires_at| InsertMessageInput {
+        id: MessageId::from(Uuid::new_v4()),
+        channel_id: channel,
+        author_id: AuthorId::from(Uuid::new_v4()),
+        content: "preview".into(),
+        reply_to_message_id: None,
+        attachments: vec![],
+        nonce: None,
+        expires_at,
+    };
+    let kept = service.create_message(input(None)).await.expect("create");
+    service
+        .create_message(input(Some(chrono::Utc::now() + chrono::Duration::milliseconds(50))))
+        .await
+        .expect("create");
+
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    let last = service.last_messages(&[channel]).await.expect("last messages");
+    let ids: Vec<MessageId> = last.iter().map(|message| message.id).collect();
+    assert_eq!(ids, vec![kept.id]);
+}
+
+#[tokio::test]
+async fn saved_messages_are_listed_most_recent_first() {
+    let service = Service::new(MockMessageRepository::new(), MockHealthRepository::new());
+    let user = UserId::from(Uuid::new_v4());
+    let input = || InsertMessageInput {
+        id: MessageId::from(Uuid::new_v4()),
+        channel_id: ChannelId::from(Uuid::new_v4()),
+        author_id: AuthorId::from(Uuid::new_v4()),
+        content: "bookmark".into(),
+        reply_to_message_id: None,
+        attachments: vec![],
+        nonce: None,
+        expires_at: None,
+    };
+    let first = service.create_message(input()).await.expect("create");
+    let second = service.create_message(input()).await.expect("create");
+    let deleted = service.create_message(input()).await.expect("create");
+
+    let res = service.save_message(&user, &MessageId::from(Uuid::new_v4())).await;
+    assert!(matches!(res, Err(CoreError::MessageNotFound { .. })));
+
+    let saved = service.save_message(&user, &first.id).await.expect("save");
+    assert_eq!(saved.channel_id, first.channel_id);
+    // saving twice keeps the first save
+    assert_eq!(service.save_message(&user, &first.id).await.expect("save again"), saved);
+    service.save_message(&user, &second.id).await.expect("save");
+    service.save_message(&user, &deleted.id).await.expect("save");
+    service.delete_message(&deleted.id).await.expect("delete");
+
+    let (messages, total) = service
+        .list_saved_messages(&user, &GetPaginated::default())
+        .await
+        .expect("list");
+    let ids: Vec<MessageId> = messages.iter().map(|message| message.id).collect();
+    assert_eq!(ids, vec![second.id, first.id]);
+    assert_eq!(total, 3);
+
+    service.unsave_message(&user, &second.id).await.expect("unsave");
+    let (messages, _) = service
+        .list_saved_messages(&user, &GetPaginated::default())
+        .await
+        .expect("list");
+    assert_eq!(messages.len(), 1);
+}
@@ -16,10 +16,10 @@ async fn mongo_repository_crud_flow() {
     // connection URI we'll use
     let mut uri_to_use: Option<String> = None;
 
-    if let Some(u) = env_uri {
-        if !u.is_empty() {
-            uri_to_use = Some(u);
-        }
+    if let Some(u) = env_uri
+        && !u.is_empty()
+    {
+        uri_to_use = Some(u);
     }
 
     // If no env var, try to start a docker mongo using testcontainers
@@ -86,13 +86,26 @@ async fn mongo_repository_crud_flow() {
         author_id: author,
         content: "mongo hello".to_string(),
         reply_to_message_id: None,
-        attachments: vec![Attachment { id: AttachmentId::from(Uuid::new_v4()), name: "f".into(), url: "u".into() }],
+        attachments: vec![Attachment { id: AttachmentId::from(Uuid::new_v4()), name: "f".into(), url: "u".into(), size: 0, preview: None }],
+        nonce: None,
+        expires_at: None,
     };
 
     // Insert
     let inserted = repo.insert(input.clone()).await.expect("insert should succeed");
     assert_eq!(inserted.id, id);
 
+    // The creation is announced through the outbox
+    {
+        use mongodb::bson::{doc, Document};
+        let announced = db
+            .collection::<Document>("outbox_messages")
+            .count_documents(doc! { "payload.content": "mongo hello" })
+            .await
+            .expect("outbox count should succeed");
+        assert_eq!(announced, 1);
+    }
+
     // Find
     // Diagnostic: inspect raw documents in the collection to debug serialization issues
     {
@@ -119,8 +132,24 @@ async fn mongo_repository_crud_flow() {
     assert!(total >= 1);
     assert!(list.iter().any(|m| m.id == id));
 
+    // Text search, scored by relevance
+    {
+        use communities_core::domain::search::{entities::SearchQuery, ports::SearchIndex};
+        use communities_core::infrastructure::search::MongoTextSearchIndex;
+
+        let search = MongoTextSearchIndex::new(&db);
+        search.ensure_indexes().await.expect("text index should be created");
+        let hits = search
+            .search(&SearchQuery { channel_id: Some(channel), ..SearchQuery::new("hello", 10) })
+            .await
+            .expect("search should succeed");
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].message_id, id);
+        assert!(hits[0].score > 0.0);
+    }
+
     // Update
-    let update_input = UpdateMessageInput { id, content: Some("updated mongo".into()), is_pinned: Some(true) };
+    let update_input = UpdateMessageInput { id, content: Some("updated mongo".into()), is_pinned: Some(true), expected_revision: None };
     let updated = repo.update(update_input).await.expect("update should succeed");
     assert_eq!(updated.content, "updated mongo");
 
@@ -129,6 +158,17 @@ async fn mongo_repository_crud_flow() {
     let after = repo.find_by_id(&id).await.expect("find after delete should succeed");
     assert!(after.is_none());
 
+    // Nonces are deduplicated until their message is deleted
+    repo.ensure_indexes().await.expect("indexes should be created");
+    let with_nonce = || InsertMessageInput { id: MessageId::from(Uuid::new_v4()), nonce: Some("n-1".into()), ..input.clone() };
+    let first = repo.insert(with_nonce()).await.expect("insert should succeed");
+    let retried = repo.insert(with_nonce()).await.expect("retry should succeed");
+    assert_eq!(retried.id, first.id);
+    repo.delete(&first.id).await.expect("delete should succeed");
+    let recreated = repo.insert(with_nonce()).await.expect("insert after delete should succeed");
+    assert_ne!(recreated.id, first.id);
+    assert!(repo.find_by_id(&recreated.id).await.expect("find should succeed").is_some());
+
     // cleanup DB
     let _ = db.drop().await;
 
@@ -162,7 +202,7 @@ async fn try_start_docker_mongo() -> Result<(String, String), String> {
     }
 
     // Start container with random host port mapping (-P) and capture container id
-    let name = format!("test-mongo-{}", Uuid::new_v4().to_string());
+    let name = format!("test-mongo-{}", Uuid::new_v4());
     let run = Command::new("docker")
         .args(["run", "-d", "-P", "--rm", "--name", &name, "mongo:6.0"])
         .output()
@@ -0,0 +1,66 @@
+use std::sync::Arc;
+
+use communities_core::domain::common::CoreError;
+use communities_core::infrastructure::outbox::{
+    EncryptionEnvelope, LocalKeyManagementService, OutboxEncryption,
+};
+use mongodb::bson::{Bson, doc};
+use uuid::Uuid;
+
+fn encryption(key_id: &str, master_key: [u8; 32]) -> OutboxEncryption {
+    OutboxEncryption::new(Arc::new(LocalKeyManagementService::new(key_id, &master_key)))
+}
+
+#[tokio::test]
+async fn sealed_payload_round_trips_and_hides_content() {
+    let encryption = encryption("k1", [7; 32]);
+    let payload = Bson::Document(doc! { "id": "m1", "content": "top secret", "revision": 3_i64 });
+    let record_id = Uuid::new_v4();
+
+    let (sealed, envelope) = encryption.seal(&payload, record_id).await.expect("seal should work");
+    let Bson::Binary(ciphertext) = &sealed else { panic!("expected a binary payload") };
+    assert!(!ciphertext.bytes.windows(10).any(|w| w == b"top secret"));
+    assert_eq!(envelope.key_id, "k1");
+    assert_eq!(envelope.algorithm, "AES-256-GCM");
+
+    let opened = encryption.open(&sealed, &envelope, record_id).await.expect("open should work");
+    assert_eq!(opened, payload);
+
+    // every record gets its own data key and nonce
+    let (_, other) = encryption.seal(&payload, record_id).await.expect("seal should work");
+    assert_ne!(other.encrypted_key, envelope.encrypted_key);
+}
+
+#[tokio::test]
+async fn wrong_master_key_or_tampering_is_rejected() {
+    let payload = Bson::String("hello".into());
+    let record_id = Uuid::new_v4();
+    let (sealed, envelope) = encryption("k1", [7; 32]).seal(&payload, record_id).await.unwrap();
+
+    let res = encryption("k1", [8; 32]).open(&sealed, &envelope, record_id).await;
+    assert!(matches!(res, Err(CoreError::EncryptionError { .. })));
+    let res = encryption("k2", [7; 32]).open(&sealed, &envelope, record_id).await;
+    assert!(matches!(res, Err(CoreError::EncryptionError { .. })));
+
+    let Bson::Binary(mut tampered) = sealed else { unreachable!() };
+    tampered.bytes[0] ^= 1;
+    let res = encryption("k1", [7; 32]).open(&Bson::Binary(tampered), &envelope, record_id).await;
+    assert!(matches!(res, Err(CoreError::EncryptionError { .. })));
+}
+
+#[tokio::test]
+async fn payloads_cannot_be_moved_to_another_record() {
+    let encryption = encryption("k1", [7; 32]);
+    let payload = Bson::String("hello".into());
+    let record_id = Uuid::new_v4();
+    let (sealed, envelope) = encryption.seal(&payload, record_id).await.unwrap();
+    assert!(envelope.bound_to_record);
+
+    let res = encryption.open(&sealed, &envelope, Uuid::new_v4()).await;
+    assert!(matches!(res, Err(CoreError::EncryptionError { .. })));
+
+    // nor passed off as a record sealed before the binding
+    let unbound = EncryptionEnvelope { bound_to_record: false, ..envelope };
+    let res = encryption.open(&sealed, &unbound, record_id).await;
+    assert!(matches!(res, Err(CoreError::EncryptionError { .. })));
+}
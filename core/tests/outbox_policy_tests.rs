@@ -0,0 +1,32 @@
+use communities_core::infrastructure::outbox::{MessageRouter, MessageRoutingInfo, OutboxFailurePolicy};
+use serde_json::json;
+
+#[test]
+fn routing_info_defaults_to_fail_policy() {
+    let info: MessageRoutingInfo = serde_json::from_value(json!({
+        "exchange": "beep.messages",
+        "routing_key": "message.created"
+    }))
+    .expect("routing info should deserialize");
+
+    assert_eq!(info.failure_policy(), OutboxFailurePolicy::Fail);
+}
+
+#[test]
+fn routing_info_reads_configured_policy() {
+    let info: MessageRoutingInfo = serde_json::from_value(json!({
+        "exchange": "beep.messages",
+        "routing_key": "message.created",
+        "failure_policy": { "mode": "retry", "max_attempts": 3 }
+    }))
+    .expect("routing info should deserialize");
+    assert_eq!(info.failure_policy(), OutboxFailurePolicy::Retry { max_attempts: 3 });
+
+    let info: MessageRoutingInfo = serde_json::from_value(json!({
+        "exchange": "beep.messages",
+        "routing_key": "message.deleted",
+        "failure_policy": { "mode": "log_and_continue" }
+    }))
+    .expect("routing info should deserialize");
+    assert_eq!(info.failure_policy(), OutboxFailurePolicy::LogAndContinue);
+}
@@ -0,0 +1,71 @@
+use std::{sync::Arc, time::Duration};
+
+use communities_core::{
+    domain::{
+        common::CoreError,
+        lease::ports::{InMemoryLeaseLock, LeaseLock},
+    },
+    infrastructure::outbox::{
+        OUTBOX_RELAY_LEASE, OutboxMessage, OutboxPublisher, OutboxRelay, OutboxRelayConfig,
+        OutboxStatus,
+    },
+};
+use mongodb::Client;
+
+struct NoopPublisher;
+
+#[async_trait::async_trait]
+impl OutboxPublisher for NoopPublisher {
+    async fn publish(&self, _message: &OutboxMessage) -> Result<(), CoreError> {
+        Ok(())
+    }
+}
+
+#[test]
+fn retry_delay_doubles_up_to_the_cap() {
+    let config = OutboxRelayConfig {
+        base_retry_delay: Duration::from_secs(1),
+        max_retry_delay: Duration::from_secs(60),
+        ..Default::default()
+    };
+
+    let delays: Vec<u64> = (1..=8).map(|attempts| config.retry_delay(attempts).as_secs()).collect();
+    assert_eq!(delays, vec![1, 2, 4, 8, 16, 32, 60, 60]);
+
+    // huge attempt counts must not overflow
+    assert_eq!(config.retry_delay(u32::MAX), Duration::from_secs(60));
+}
+
+#[test]
+fn outbox_status_is_stored_in_upper_case() {
+    for (status, stored) in [
+        (OutboxStatus::Ready, "READY"),
+        (OutboxStatus::Sent, "SENT"),
+        (OutboxStatus::Dead, "DEAD"),
+    ] {
+        assert_eq!(status.as_str(), stored);
+        assert_eq!(serde_json::to_value(status).unwrap(), stored);
+    }
+}
+
+#[tokio::test]
+async fn follower_relay_skips_rounds_while_another_replica_leads() {
+    // the client connects lazily: a follower must not reach the database
+    let db = Client::with_uri_str("mongodb://127.0.0.1:1")
+        .await
+        .unwrap()
+        .database("outbox_relay_tests");
+    let leader = InMemoryLeaseLock::new("replica-a");
+    let follower = leader.for_holder("replica-b");
+    assert!(
+        leader
+            .try_acquire(OUTBOX_RELAY_LEASE, Duration::from_secs(60))
+            .await
+            .unwrap()
+    );
+
+    let relay = OutboxRelay::new(&db, NoopPublisher, OutboxRelayConfig::default())
+        .with_leader(Arc::new(follower));
+
+    assert_eq!(relay.relay_round().await.unwrap(), None);
+}
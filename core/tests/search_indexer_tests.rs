@@ -0,0 +1,120 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use communities_core::domain::common::CoreError;
+use communities_core::domain::message::entities::{AuthorId, ChannelId, InsertMessageInput, Message, MessageId};
+use communities_core::domain::message::events::{MessageEvent, MessageEventBus};
+use communities_core::domain::message::ports::{MessageRepository, MockMessageRepository};
+use communities_core::domain::search::entities::{SearchHit, SearchQuery};
+use communities_core::domain::search::ports::SearchIndex;
+use communities_core::infrastructure::search::{SearchIndexer, SearchIndexerConfig, reindex_channel};
+use uuid::Uuid;
+
+#[derive(Default)]
+struct RecordingIndex {
+    batches: Mutex<Vec<(Instant, usize)>>,
+    removed: Mutex<Vec<MessageId>>,
+}
+
+#[async_trait::async_trait]
+impl SearchIndex for RecordingIndex {
+    async fn index_messages(&self, messages: &[Message]) -> Result<(), CoreError> {
+        self.batches.lock().unwrap().push((Instant::now(), messages.len()));
+        Ok(())
+    }
+
+    async fn remove_message(&self, id: &MessageId) -> Result<(), CoreError> {
+        self.removed.lock().unwrap().push(*id);
+        Ok(())
+    }
+
+    async fn set_archived(&self, _ids: &[MessageId], _archived: bool) -> Result<(), CoreError> {
+        Ok(())
+    }
+
+    async fn search(&self, _query: &SearchQuery) -> Result<Vec<SearchHit>, CoreError> {
+        Ok(Vec::new())
+    }
+}
+
+async fn seed(repo: &MockMessageRepository, channel: ChannelId, count: usize) -> Vec<Message> {
+    let mut messages = Vec::with_capacity(count);
+    for i in 0..count {
+        let message = repo
+            .insert(InsertMessageInput {
+                id: MessageId::from(Uuid::new_v4()),
+                channel_id: channel,
+                author_id: AuthorId::from(Uuid::new_v4()),
+                content: format!("message {i}"),
+                reply_to_message_id: None,
+                attachments: vec![],
+                nonce: None,
+                expires_at: None,
+            })
+            .await
+            .expect("insert should work");
+        messages.push(message);
+    }
+    messages
+}
+
+#[tokio::test]
+async fn reindex_channel_batches_and_throttles_backend_calls() {
+    let repo = MockMessageRepository::new();
+    let channel = ChannelId::from(Uuid::new_v4());
+    seed(&repo, channel, 120).await;
+    seed(&repo, ChannelId::from(Uuid::new_v4()), 5).await;
+
+    let index = Arc::new(RecordingIndex::default());
+    let (indexer, consumer) = SearchIndexer::spawn(
+        index.clone(),
+        SearchIndexerConfig {
+            queue_capacity: 10,
+            batch_size: 50,
+            max_batches_per_second: 20,
+        },
+    );
+
+    let queued = reindex_channel(&repo, &indexer, &channel).await.expect("reindex should work");
+    assert_eq!(queued, 120);
+
+    drop(indexer);
+    consumer.await.expect("consumer should stop once drained");
+
+    let batches = index.batches.lock().unwrap();
+    assert_eq!(batches.iter().map(|(_, len)| len).sum::<usize>(), 120);
+    assert!(batches.iter().all(|(_, len)| *len <= 50));
+    // consecutive backend calls are at least 1/20s apart
+    for pair in batches.windows(2) {
+        assert!(pair[1].0 - pair[0].0 >= Duration::from_millis(45));
+    }
+}
+
+#[tokio::test]
+async fn follow_indexes_created_messages_and_removes_deleted_ones() {
+    let repo = MockMessageRepository::new();
+    let channel = ChannelId::from(Uuid::new_v4());
+    let messages = seed(&repo, channel, 2).await;
+
+    let index = Arc::new(RecordingIndex::default());
+    let (indexer, _consumer) = SearchIndexer::spawn(index.clone(), SearchIndexerConfig::default());
+    let events = MessageEventBus::new();
+    indexer.follow(&events);
+
+    events.publish(MessageEvent::Created(messages[0].clone()));
+    events.publish(MessageEvent::Updated(messages[1].clone()));
+    events.publish(MessageEvent::Deleted { id: messages[0].id, channel_id: channel });
+
+    tokio::time::timeout(Duration::from_secs(2), async {
+        loop {
+            let indexed: usize = index.batches.lock().unwrap().iter().map(|(_, len)| len).sum();
+            if indexed == 2 && index.removed.lock().unwrap().len() == 1 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("events should reach the index");
+    assert_eq!(*index.removed.lock().unwrap(), vec![messages[0].id]);
+}
@@ -0,0 +1,245 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use communities_core::domain::common::CoreError;
+use communities_core::domain::message::entities::{
+    Attachment, AttachmentId, AuthorId, ChannelId, InsertMessageInput, Mentions, Message, MessageId, UpdateMessageInput,
+};
+use communities_core::domain::message::ports::{MessageRepository, MockMessageRepository};
+use communities_core::domain::search::entities::{
+    SearchHit, SearchQuery, SearchResult, SimilarMessagesQuery, content_similarity,
+};
+use communities_core::domain::search::ports::{ArchiveStore, SearchIndex};
+use communities_core::domain::search::services::MessageSearch;
+use uuid::Uuid;
+
+/// Backend returning a fixed list of hits, ignoring the archive filter so
+/// the service's own filtering is exercised
+struct FixedIndex(Vec<SearchHit>);
+
+#[async_trait::async_trait]
+impl SearchIndex for FixedIndex {
+    async fn index_messages(&self, _messages: &[Message]) -> Result<(), CoreError> {
+        Ok(())
+    }
+
+    async fn remove_message(&self, _id: &MessageId) -> Result<(), CoreError> {
+        Ok(())
+    }
+
+    async fn set_archived(&self, _ids: &[MessageId], _archived: bool) -> Result<(), CoreError> {
+        Ok(())
+    }
+
+    async fn search(&self, _query: &SearchQuery) -> Result<Vec<SearchHit>, CoreError> {
+        Ok(self.0.clone())
+    }
+}
+
+struct FixedArchive(Vec<Message>);
+
+#[async_trait::async_trait]
+impl ArchiveStore for FixedArchive {
+    async fn fetch_archived(&self, ids: &[MessageId]) -> Result<Vec<Message>, CoreError> {
+        Ok(self.0.iter().filter(|m| ids.contains(&m.id)).cloned().collect())
+    }
+}
+
+fn archived_message(channel: ChannelId) -> Message {
+    Message {
+        id: MessageId::from(Uuid::new_v4()),
+        channel_id: channel,
+        author_id: AuthorId::from(Uuid::new_v4()),
+        content: "archived".into(),
+        reply_to_message_id: None,
+        attachments: vec![],
+        is_pinned: false,
+        reactions: vec![],
+        revision: 0,
+        sequence: 0,
+        reply_count: 0,
+        last_reply_at: None,
+        archived_at: None,
+        created_at: Utc::now(),
+        updated_at: None,
+        deleted_at: None,
+        nonce: None,
+        embeds: Vec::new(),
+        mentions: Mentions::default(),
+        expires_at: None,
+    }
+}
+
+fn hit(message_id: MessageId, score: f32, archived: bool) -> SearchHit {
+    SearchHit { message_id, score, archived }
+}
+
+fn query(include_archived: bool) -> SearchQuery {
+    SearchQuery { include_archived, ..SearchQuery::new("hello", 10) }
+}
+
+#[tokio::test]
+async fn search_hydrates_archived_hits_only_on_demand() {
+    let repo = MockMessageRepository::new();
+    let channel = ChannelId::from(Uuid::new_v4());
+    let live = repo
+        .insert(InsertMessageInput {
+            id: MessageId::from(Uuid::new_v4()),
+            channel_id: channel,
+            author_id: AuthorId::from(Uuid::new_v4()),
+            content: "live".into(),
+            reply_to_message_id: None,
+            attachments: vec![],
+            nonce: None,
+            expires_at: None,
+        })
+        .await
+        .expect("insert should work");
+    let archived = archived_message(channel);
+    let stale = MessageId::from(Uuid::new_v4());
+
+    let index = FixedIndex(vec![hit(archived.id, 3.0, true), hit(stale, 2.0, false), hit(live.id, 1.0, false)]);
+    let search = MessageSearch::new(repo, Arc::new(index), Arc::new(FixedArchive(vec![archived.clone()])));
+
+    let results = search.search(&query(false)).await.expect("search should work");
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].message.id, live.id);
+    assert!(!results[0].archived);
+
+    // relevance order is kept and stale entries are skipped
+    let results = search.search(&query(true)).await.expect("search should work");
+    let ids: Vec<_> = results.iter().map(|r| r.message.id).collect();
+    assert_eq!(ids, vec![archived.id, live.id]);
+    assert!(results[0].archived);
+}
+
+async fn insert(
+    repo: &MockMessageRepository,
+    channel: ChannelId,
+    content: &str,
+    reply_to_message_id: Option<MessageId>,
+) -> Message {
+    repo.insert(InsertMessageInput {
+        id: MessageId::from(Uuid::new_v4()),
+        channel_id: channel,
+        author_id: AuthorId::from(Uuid::new_v4()),
+        content: content.into(),
+        reply_to_message_id,
+        attachments: vec![],
+        nonce: None,
+        expires_at: None,
+    })
+    .await
+    .expect("insert should work")
+}
+
+#[test]
+fn content_similarity_compares_words_ignoring_case_and_short_words() {
+    assert_eq!(
+        content_similarity("How to reset my password?", "how TO reset MY password"),
+        1.0
+    );
+    assert_eq!(content_similarity("reset password", "deploy server"), 0.0);
+    assert_eq!(content_similarity("", "reset password"), 0.0);
+    // {reset, password} shared out of {how, reset, password, server}
+    assert_eq!(
+        content_similarity("how to reset password", "reset server password"),
+        0.5
+    );
+}
+
+#[tokio::test]
+async fn similar_messages_keeps_the_most_similar_message_of_each_thread() {
+    let repo = MockMessageRepository::new();
+    let channel = ChannelId::from(Uuid::new_v4());
+    let question = insert(&repo, channel, "how can I reset my password", None).await;
+    let answer = insert(&repo, channel, "reset your password from the settings", Some(question.id)).await;
+    let other = insert(&repo, channel, "password reset link expired", None).await;
+    let unrelated = insert(&repo, channel, "deploying the server tonight", None).await;
+
+    let index = FixedIndex(
+        [&unrelated, &answer, &other, &question]
+            .iter()
+            .map(|m| hit(m.id, 1.0, false))
+            .collect(),
+    );
+    let search = MessageSearch::new(repo, Arc::new(index), Arc::new(FixedArchive(vec![])));
+
+    let similar = search
+        .similar_messages(&SimilarMessagesQuery {
+            channel_id: channel,
+            content: "How do I reset my password?".into(),
+            limit: 5,
+        })
+        .await
+        .expect("suggestions should work");
+
+    let ids: Vec<_> = similar.iter().map(|s| s.message.id).collect();
+    assert_eq!(ids, vec![question.id, other.id]);
+    assert_eq!(similar[0].thread_id, question.id);
+    assert!(similar[0].similarity > similar[1].similarity);
+}
+
+#[tokio::test]
+async fn similar_messages_honours_the_limit() {
+    let repo = MockMessageRepository::new();
+    let channel = ChannelId::from(Uuid::new_v4());
+    let mut hits = Vec::new();
+    for _ in 0..3 {
+        let message = insert(&repo, channel, "build fails on main", None).await;
+        hits.push(hit(message.id, 1.0, false));
+    }
+    let search = MessageSearch::new(repo, Arc::new(FixedIndex(hits)), Arc::new(FixedArchive(vec![])));
+
+    let similar = search
+        .similar_messages(&SimilarMessagesQuery {
+            channel_id: channel,
+            content: "build fails".into(),
+            limit: 2,
+        })
+        .await
+        .expect("suggestions should work");
+    assert_eq!(similar.len(), 2);
+}
+
+#[tokio::test]
+async fn search_applies_structured_filters_the_backend_ignored() {
+    let repo = MockMessageRepository::new();
+    let channel = ChannelId::from(Uuid::new_v4());
+    let plain = insert(&repo, channel, "plain", None).await;
+    let pinned = insert(&repo, channel, "pinned", None).await;
+    repo.update(UpdateMessageInput { id: pinned.id, content: None, is_pinned: Some(true), expected_revision: None })
+        .await
+        .expect("pin should work");
+    let with_file = repo
+        .insert(InsertMessageInput {
+            id: MessageId::from(Uuid::new_v4()),
+            channel_id: channel,
+            author_id: plain.author_id,
+            content: "with file".into(),
+            reply_to_message_id: None,
+            attachments: vec![Attachment { id: AttachmentId::from(Uuid::new_v4()), name: "a".into(), url: "u".into(), size: 1, preview: None }],
+            nonce: None,
+            expires_at: None,
+        })
+        .await
+        .expect("insert should work");
+
+    let index = FixedIndex(vec![hit(plain.id, 3.0, false), hit(pinned.id, 2.0, false), hit(with_file.id, 1.0, false)]);
+    let search = MessageSearch::new(repo, Arc::new(index), Arc::new(FixedArchive(vec![])));
+    let ids = |results: Vec<SearchResult>| results.into_iter().map(|r| r.message.id).collect::<Vec<_>>();
+
+    let results = search.search(&SearchQuery { pinned: Some(true), ..query(false) }).await.unwrap();
+    assert_eq!(ids(results), vec![pinned.id]);
+
+    let results = search.search(&SearchQuery { has_attachments: Some(false), ..query(false) }).await.unwrap();
+    assert_eq!(ids(results), vec![plain.id, pinned.id]);
+
+    let results = search.search(&SearchQuery { author_id: Some(plain.author_id), ..query(false) }).await.unwrap();
+    assert_eq!(ids(results), vec![plain.id, with_file.id]);
+
+    let results = search.search(&SearchQuery { after: Some(Utc::now()), ..query(false) }).await.unwrap();
+    assert!(results.is_empty());
+    let results = search.search(&SearchQuery { before: Some(Utc::now()), ..query(false) }).await.unwrap();
+    assert_eq!(results.len(), 3);
+}
@@ -0,0 +1,41 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use communities_core::domain::lease::ports::{InMemoryLeaseLock, LeaseLock};
+use communities_core::infrastructure::lease::SingletonJob;
+
+const TTL: Duration = Duration::from_millis(100);
+
+#[tokio::test]
+async fn only_the_lease_holder_runs_the_job() {
+    let first = InMemoryLeaseLock::new("replica-1");
+    let second = first.for_holder("replica-2");
+
+    let first_job = SingletonJob::new(Arc::new(first), "sweep", TTL);
+    let second_job = SingletonJob::new(Arc::new(second), "sweep", TTL);
+
+    assert_eq!(first_job.run_once(async { 1 }).await.unwrap(), Some(1));
+    assert_eq!(second_job.run_once(async { 2 }).await.unwrap(), None);
+    // the holder renews its own lease
+    assert_eq!(first_job.run_once(async { 3 }).await.unwrap(), Some(3));
+
+    first_job.release().await.unwrap();
+    assert_eq!(second_job.run_once(async { 4 }).await.unwrap(), Some(4));
+}
+
+#[tokio::test]
+async fn expired_leases_are_taken_over() {
+    let first = InMemoryLeaseLock::new("replica-1");
+    let second = first.for_holder("replica-2");
+
+    assert!(first.try_acquire("sweep", TTL).await.unwrap());
+    assert!(!second.try_acquire("sweep", TTL).await.unwrap());
+
+    tokio::time::sleep(TTL * 2).await;
+    assert!(second.try_acquire("sweep", TTL).await.unwrap());
+    assert!(!first.try_acquire("sweep", TTL).await.unwrap());
+
+    // releasing a lease held by someone else is a no-op
+    first.release("sweep").await.unwrap();
+    assert!(!first.try_acquire("sweep", TTL).await.unwrap());
+}
@@ -0,0 +1,83 @@
+#![cfg(feature = "tantivy")]
+
+use communities_core::domain::message::entities::{
+    AuthorId, ChannelId, InsertMessageInput, Message, MessageId,
+};
+use communities_core::domain::message::ports::{MessageRepository, MockMessageRepository};
+use communities_core::domain::search::entities::SearchQuery;
+use communities_core::domain::search::ports::SearchIndex;
+use communities_core::infrastructure::search::TantivyMessageSearchRepository;
+use uuid::Uuid;
+
+async fn message(repo: &MockMessageRepository, channel: ChannelId, content: &str) -> Message {
+    repo.insert(InsertMessageInput {
+        id: MessageId::from(Uuid::new_v4()),
+        channel_id: channel,
+        author_id: AuthorId::from(Uuid::new_v4()),
+        content: content.to_string(),
+        reply_to_message_id: None,
+        attachments: vec![],
+        nonce: None,
+        expires_at: None,
+    })
+    .await
+    .expect("insert should work")
+}
+
+fn query(channel: ChannelId, text: &str) -> SearchQuery {
+    SearchQuery {
+        channel_id: Some(channel),
+        ..SearchQuery::new(text, 10)
+    }
+}
+
+#[tokio::test]
+async fn tantivy_index_follows_writes_and_archiving() {
+    let repo = MockMessageRepository::new();
+    let index = TantivyMessageSearchRepository::in_memory().expect("in-memory index should open");
+    let channel = ChannelId::from(Uuid::new_v4());
+    let other_channel = ChannelId::from(Uuid::new_v4());
+
+    let deploy = message(&repo, channel, "deploy the release tonight").await;
+    let lunch = message(&repo, channel, "lunch at noon").await;
+    let elsewhere = message(&repo, other_channel, "deploy elsewhere").await;
+    index
+        .index_messages(&[deploy.clone(), lunch.clone(), elsewhere])
+        .await
+        .unwrap();
+
+    // one typo away, and scoped to the channel
+    let hits = index.search(&query(channel, "deplyo")).await.unwrap();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].message_id, deploy.id);
+
+    index.set_archived(&[deploy.id], true).await.unwrap();
+    assert!(
+        index
+            .search(&query(channel, "deploy"))
+            .await
+            .unwrap()
+            .is_empty()
+    );
+
+    // indexing an edit again keeps the archived flag
+    index.index_messages(std::slice::from_ref(&deploy)).await.unwrap();
+    let archived = index
+        .search(&SearchQuery {
+            include_archived: true,
+            ..query(channel, "deploy")
+        })
+        .await
+        .unwrap();
+    assert_eq!(archived.len(), 1);
+    assert!(archived[0].archived);
+
+    index.remove_message(&lunch.id).await.unwrap();
+    assert!(
+        index
+            .search(&query(channel, "lunch"))
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}